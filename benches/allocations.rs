@@ -0,0 +1,63 @@
+//! The number of allocations of the grootboek and hour log parsers.
+//!
+//! Run with `cargo bench --bench allocations`.
+//! The time of the same parsers is measured by `cargo bench --bench parse`.
+//! The allocations are counted with a global allocator, which would skew the timings,
+//! so they are reported by a separate benchmark.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use zzp::grootboek::Transaction;
+
+mod common;
+
+/// An allocator that counts the number of allocations.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+	let grootboek = common::generate_grootboek(2, 50);
+	count("grootboek: Transaction::parse_from_str", || {
+		Transaction::parse_from_str(&grootboek).unwrap().len()
+	});
+
+	let uurlog = common::generate_uurlog(2, 50);
+	count("uurlog: parse_bytes", || {
+		zzp::uurlog::parse_bytes(uurlog.as_bytes()).unwrap().len()
+	});
+}
+
+/// Run a parser once and print the number of allocations per record.
+///
+/// The function must return the number of parsed records.
+fn count(name: &str, function: impl FnOnce() -> usize) {
+	let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+	let records = function();
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+
+	println!("{}", name);
+	println!("  records:                {:>10}", records);
+	println!("  allocations:            {:>10}", allocations);
+	println!("  allocations per record: {:>10.2}", allocations as f64 / records as f64);
+}
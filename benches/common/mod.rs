@@ -0,0 +1,47 @@
+//! Generated test data for the benchmarks.
+
+/// Generate a ledger with `per_day` transactions on every day of `years` years.
+pub fn generate_grootboek(years: i16, per_day: usize) -> String {
+	let mut data = String::new();
+	for year in 2000..2000 + years {
+		for month in 1..=12 {
+			for day in 1..=28 {
+				for i in 0..per_day {
+					let cents = (i * 1234 + day * 56) % 100_000 + 1;
+					data.push_str(&format!("{}-{:02}-{:02}: Invoice {}-{:03}\n", year, month, day, year, i));
+					if i % 10 == 0 {
+						data.push_str("\t; Paid in two parts.\n");
+					}
+					data.push_str(&format!("\tinvoice: invoices/{}-{:03}.pdf\n", year, i));
+					data.push_str(&format!("\t-{}.{:02} debitors/customer-{}\n", cents / 100, cents % 100, i % 20));
+					data.push_str(&format!("\t+{}.{:02} revenue/customer-{}\n", cents / 100, cents % 100, i % 20));
+					if i % 5 == 0 {
+						data.push_str("\t-0.21 vat/payable\n\t+0.21 vat/receivable\n\t-1.00 expenses/fees\n\t+1.00 bank\n");
+					}
+					data.push('\n');
+				}
+			}
+		}
+	}
+	data
+}
+
+/// Generate an hour log with `per_day` entries on every day of `years` years.
+#[allow(dead_code)] // Not used by every benchmark.
+pub fn generate_uurlog(years: i16, per_day: usize) -> String {
+	let mut data = String::new();
+	for year in 2000..2000 + years {
+		for month in 1..=12 {
+			for day in 1..=28 {
+				for i in 0..per_day {
+					match i % 3 {
+						0 => data.push_str(&format!("{}-{:02}-{:02}, 1h30m, [project-{}] Development\n", year, month, day, i % 7)),
+						1 => data.push_str(&format!("{}-{:02}-{:02}, 45m, [meeting] [project-{}] Weekly meeting\n", year, month, day, i % 7)),
+						_ => data.push_str(&format!("{}-{:02}-{:02}, 2h, Support\n", year, month, day)),
+					}
+				}
+			}
+		}
+	}
+	data
+}
@@ -0,0 +1,44 @@
+//! Benchmarks of parsing a large grootboek file after reading it into a `String` and from a memory mapped file.
+//!
+//! Run with `cargo bench --bench mapped`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::path::Path;
+
+use zzp::grootboek::{MappedFile, Transaction};
+
+mod common;
+
+fn parse_file(c: &mut Criterion) {
+	// Ten years of transactions, which is about 20 MiB.
+	let path = std::env::temp_dir().join(format!("zzp-bench-{}.grootboek", std::process::id()));
+	let data = common::generate_grootboek(10, 50);
+	std::fs::write(&path, &data).unwrap();
+
+	let mut group = c.benchmark_group("grootboek file");
+	group.sample_size(20);
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_with_input(BenchmarkId::new("read_to_string + parse", data.len()), path.as_path(), |b, path| {
+		b.iter(|| read_and_parse(path))
+	});
+	group.bench_with_input(BenchmarkId::new("mmap + parse", data.len()), path.as_path(), |b, path| {
+		b.iter(|| map_and_parse(path))
+	});
+	group.finish();
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+fn read_and_parse(path: &Path) -> usize {
+	let data = std::fs::read_to_string(path).unwrap();
+	Transaction::parse_from_str(&data).unwrap().len()
+}
+
+fn map_and_parse(path: &Path) -> usize {
+	// SAFETY: The benchmark file is not modified while it is mapped.
+	let data = unsafe { MappedFile::open(path) }.unwrap();
+	Transaction::parse_from_str(&data).unwrap().len()
+}
+
+criterion_group!(benches, parse_file);
+criterion_main!(benches);
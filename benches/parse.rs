@@ -0,0 +1,35 @@
+//! Benchmarks of the grootboek and hour log parsers.
+//!
+//! Run with `cargo bench --bench parse`.
+//! The number of allocations of the same parsers is reported by `cargo bench --bench allocations`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use zzp::grootboek::Transaction;
+
+mod common;
+
+fn parse_grootboek(c: &mut Criterion) {
+	let data = common::generate_grootboek(2, 50);
+	let mut group = c.benchmark_group("grootboek");
+	group.sample_size(20);
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("Transaction::parse_from_str", |b| {
+		b.iter(|| Transaction::parse_from_str(&data).unwrap().len())
+	});
+	group.finish();
+}
+
+fn parse_uurlog(c: &mut Criterion) {
+	let data = common::generate_uurlog(2, 50);
+	let mut group = c.benchmark_group("uurlog");
+	group.sample_size(20);
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("parse_bytes", |b| {
+		b.iter(|| zzp::uurlog::parse_bytes(data.as_bytes()).unwrap().len())
+	});
+	group.finish();
+}
+
+criterion_group!(benches, parse_grootboek, parse_uurlog);
+criterion_main!(benches);
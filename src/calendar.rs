@@ -0,0 +1,281 @@
+//! Calendar calculations for ISO 8601 weeks, fiscal years, Dutch public holidays and business days.
+
+use gregorian::{Date, Month, Year, YearMonth};
+use std::ops::Range;
+
+/// Get the ISO 8601 week-numbering year and week number of a date.
+pub fn iso_week(date: Date) -> (i32, u32) {
+	let year = i32::from(date.year().to_number());
+	let day_of_year = days_since_epoch(year, date.month().to_number().into(), date.day().into()) - days_since_epoch(year, 1, 1) + 1;
+	let week = (day_of_year - weekday(date) + 10) / 7;
+	if week < 1 {
+		(year - 1, weeks_in_year(year - 1))
+	} else if week as u32 > weeks_in_year(year) {
+		(year + 1, 1)
+	} else {
+		(year, week as u32)
+	}
+}
+
+/// Get the day of the week of a date, from 1 for Monday to 7 for Sunday.
+pub fn weekday(date: Date) -> i64 {
+	let days = days_since_epoch(date.year().to_number().into(), date.month().to_number().into(), date.day().into());
+	// 1970-01-01 was a Thursday.
+	(days + 3).rem_euclid(7) + 1
+}
+
+/// Get the number of ISO 8601 weeks in a year.
+pub(crate) fn weeks_in_year(year: i32) -> u32 {
+	let p = |year: i32| (year + year.div_euclid(4) - year.div_euclid(100) + year.div_euclid(400)).rem_euclid(7);
+	if p(year) == 4 || p(year - 1) == 3 {
+		53
+	} else {
+		52
+	}
+}
+
+/// Get the number of days between 1970-01-01 and a date.
+fn days_since_epoch(year: i32, month: u32, day: u32) -> i64 {
+	let year = i64::from(if month <= 2 { year - 1 } else { year });
+	let month = i64::from(month);
+	let era = year.div_euclid(400);
+	let year_of_era = year - era * 400;
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146_097 + day_of_era - 719_468
+}
+
+/// Get the Monday of the ISO 8601 week of a date.
+pub fn week_start(date: Date) -> Date {
+	(1..weekday(date)).fold(date, |date, _| date.prev())
+}
+
+/// Get the dates of a fiscal year that starts in `start_month`, as half-open range.
+///
+/// A fiscal year is named after the calendar year it starts in,
+/// so with a start in July the fiscal year 2024 runs from 2024-07-01 until 2025-06-30.
+pub fn fiscal_year(year: Year, start_month: Month) -> Range<Date> {
+	YearMonth::new(year, start_month).first_day()..YearMonth::new(year.next(), start_month).first_day()
+}
+
+/// Get the fiscal year of a date, for fiscal years that start in `start_month`.
+pub fn fiscal_year_of(date: Date, start_month: Month) -> Year {
+	if date.month().to_number() >= start_month.to_number() {
+		date.year()
+	} else {
+		date.year().prev()
+	}
+}
+
+/// Get the fiscal quarter of a date, from 0 to 3, for fiscal years that start in `start_month`.
+pub fn fiscal_quarter_index(date: Date, start_month: Month) -> usize {
+	let months = (12 + date.month().to_number() - start_month.to_number()) % 12;
+	usize::from(months / 3)
+}
+
+/// Get the date of Easter Sunday in a year.
+///
+/// This uses the anonymous algorithm for the Gregorian calendar.
+pub fn easter(year: Year) -> Date {
+	let y = i32::from(year.to_number());
+	let a = y % 19;
+	let b = y / 100;
+	let c = y % 100;
+	let d = b / 4;
+	let e = b % 4;
+	let f = (b + 8) / 25;
+	let g = (b - f + 1) / 3;
+	let h = (19 * a + b - d - g + 15) % 30;
+	let i = c / 4;
+	let k = c % 4;
+	let l = (32 + 2 * e + 2 * i - h - k) % 7;
+	let m = (a + 11 * h + 22 * l) / 451;
+	let month = (h + l - 7 * m + 114) / 31;
+	let day = (h + l - 7 * m + 114) % 31 + 1;
+	// Easter is always in March or April.
+	let month = if month == 3 { Month::March } else { Month::April };
+	Date::new(year, month, day as u8).unwrap()
+}
+
+/// A Dutch public holiday.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Holiday {
+	/// The date of the holiday.
+	pub date: Date,
+
+	/// The Dutch name of the holiday, like `Tweede paasdag`.
+	pub name: &'static str,
+}
+
+/// Get the Dutch public holidays of a year, sorted by date.
+///
+/// These are the holidays of the Algemene termijnenwet:
+/// New Year's Day, Easter, Ascension Day, Pentecost, Christmas, King's Day and Liberation Day.
+/// Good Friday is not included, since it is not a general holiday.
+pub fn holidays(year: Year) -> Vec<Holiday> {
+	let date = |month: Month, day: u8| Date::new(year, month, day).unwrap();
+	let easter = easter(year);
+
+	// King's Day moves to Saturday when it falls on a Sunday, and was Queen's Day on 30 April before 2014.
+	let (kings_day, kings_day_name) = if year.to_number() >= 2014 {
+		(date(Month::April, 27), "Koningsdag")
+	} else {
+		(date(Month::April, 30), "Koninginnedag")
+	};
+	let kings_day = if weekday(kings_day) == 7 { kings_day.prev() } else { kings_day };
+
+	let mut holidays = vec![
+		Holiday { date: date(Month::January, 1), name: "Nieuwjaarsdag" },
+		Holiday { date: easter, name: "Eerste paasdag" },
+		Holiday { date: add_days(easter, 1), name: "Tweede paasdag" },
+		Holiday { date: kings_day, name: kings_day_name },
+		Holiday { date: date(Month::May, 5), name: "Bevrijdingsdag" },
+		Holiday { date: add_days(easter, 39), name: "Hemelvaartsdag" },
+		Holiday { date: add_days(easter, 49), name: "Eerste pinksterdag" },
+		Holiday { date: add_days(easter, 50), name: "Tweede pinksterdag" },
+		Holiday { date: date(Month::December, 25), name: "Eerste kerstdag" },
+		Holiday { date: date(Month::December, 26), name: "Tweede kerstdag" },
+	];
+	holidays.sort_by_key(|x| x.date);
+	holidays
+}
+
+/// Get the Dutch public holiday on a date, if any.
+pub fn holiday(date: Date) -> Option<Holiday> {
+	holidays(date.year()).into_iter().find(|x| x.date == date)
+}
+
+/// Check if a date is a Dutch public holiday.
+///
+/// See [`holidays`] for the holidays that are included.
+pub fn is_holiday(date: Date) -> bool {
+	holiday(date).is_some()
+}
+
+/// Check if a date is a business day: a weekday that is not a public holiday.
+pub fn is_business_day(date: Date) -> bool {
+	weekday(date) <= 5 && !is_holiday(date)
+}
+
+/// Count the business days from `start` until but not including `end`.
+pub fn business_days_between(start: Date, end: Date) -> u32 {
+	let mut count = 0;
+	let mut date = start;
+	while date < end {
+		if is_business_day(date) {
+			count += 1;
+		}
+		date = date.next();
+	}
+	count
+}
+
+/// Get the date a number of business days after another date.
+///
+/// With zero days, this gives the first business day on or after the date.
+pub fn add_business_days(date: Date, days: u32) -> Date {
+	let mut date = date;
+	while !is_business_day(date) {
+		date = date.next();
+	}
+	for _ in 0..days {
+		date = date.next();
+		while !is_business_day(date) {
+			date = date.next();
+		}
+	}
+	date
+}
+
+/// Get the date a number of days after another date.
+pub(crate) fn add_days(date: Date, days: u32) -> Date {
+	(0..days).fold(date, |date, _| date.next())
+}
+
+#[cfg(test)]
+#[test]
+fn test_weekday() {
+	use assert2::assert;
+	assert!(weekday(Date::new(2024, Month::January, 1).unwrap()) == 1);
+	assert!(weekday(Date::new(2024, Month::March, 31).unwrap()) == 7);
+	assert!(weekday(Date::new(2026, Month::October, 16).unwrap()) == 5);
+}
+
+#[cfg(test)]
+#[test]
+fn test_easter() {
+	use assert2::assert;
+	assert!(easter(Year::new(2024)) == Date::new(2024, Month::March, 31).unwrap());
+	assert!(easter(Year::new(2025)) == Date::new(2025, Month::April, 20).unwrap());
+	assert!(easter(Year::new(2026)) == Date::new(2026, Month::April, 5).unwrap());
+	assert!(easter(Year::new(2038)) == Date::new(2038, Month::April, 25).unwrap());
+}
+
+#[cfg(test)]
+#[test]
+fn test_holidays() {
+	use assert2::assert;
+	let date = |year: i16, month: Month, day: u8| Date::new(year, month, day).unwrap();
+	assert!(is_holiday(date(2024, Month::January, 1)));
+	assert!(is_holiday(date(2024, Month::April, 1)));
+	assert!(is_holiday(date(2024, Month::May, 9)));
+	assert!(is_holiday(date(2024, Month::May, 20)));
+	assert!(is_holiday(date(2024, Month::December, 26)));
+	assert!(!is_holiday(date(2024, Month::March, 29)));
+	assert!(!is_holiday(date(2024, Month::May, 21)));
+
+	// King's Day on a Sunday moves to the Saturday before.
+	assert!(is_holiday(date(2025, Month::April, 26)));
+	assert!(!is_holiday(date(2025, Month::April, 27)));
+	assert!(is_holiday(date(2013, Month::April, 30)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_holiday_names() {
+	use assert2::assert;
+	let date = |year: i16, month: Month, day: u8| Date::new(year, month, day).unwrap();
+	assert!(holidays(Year::new(2024)).len() == 10);
+	assert!(let Some(Holiday { name: "Hemelvaartsdag", .. }) = holiday(date(2024, Month::May, 9)));
+	assert!(let Some(Holiday { name: "Koninginnedag", .. }) = holiday(date(2013, Month::April, 30)));
+	assert!(holiday(date(2024, Month::May, 10)).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_business_days() {
+	use assert2::assert;
+	let date = |year: i16, month: Month, day: u8| Date::new(year, month, day).unwrap();
+
+	// Good Friday is a business day, Easter Monday is not.
+	assert!(is_business_day(date(2024, Month::March, 29)));
+	assert!(!is_business_day(date(2024, Month::March, 30)));
+	assert!(!is_business_day(date(2024, Month::April, 1)));
+
+	assert!(business_days_between(date(2024, Month::March, 25), date(2024, Month::April, 8)) == 9);
+	assert!(business_days_between(date(2024, Month::January, 1), date(2025, Month::January, 1)) == 256);
+	assert!(add_business_days(date(2024, Month::March, 28), 1) == date(2024, Month::March, 29));
+	assert!(add_business_days(date(2024, Month::March, 29), 1) == date(2024, Month::April, 2));
+	assert!(add_business_days(date(2024, Month::March, 30), 0) == date(2024, Month::April, 2));
+}
+
+#[cfg(test)]
+#[test]
+fn test_fiscal_year() {
+	use assert2::assert;
+	let date = |year: i16, month: Month, day: u8| Date::new(year, month, day).unwrap();
+
+	let range = fiscal_year(Year::new(2024), Month::January);
+	assert!(range == (date(2024, Month::January, 1)..date(2025, Month::January, 1)));
+	let range = fiscal_year(Year::new(2024), Month::July);
+	assert!(range == (date(2024, Month::July, 1)..date(2025, Month::July, 1)));
+
+	assert!(fiscal_year_of(date(2025, Month::June, 30), Month::July) == Year::new(2024));
+	assert!(fiscal_year_of(date(2025, Month::July, 1), Month::July) == Year::new(2025));
+	assert!(fiscal_year_of(date(2025, Month::June, 30), Month::January) == Year::new(2025));
+
+	assert!(fiscal_quarter_index(date(2024, Month::July, 1), Month::July) == 0);
+	assert!(fiscal_quarter_index(date(2024, Month::December, 31), Month::July) == 1);
+	assert!(fiscal_quarter_index(date(2025, Month::June, 30), Month::July) == 3);
+	assert!(fiscal_quarter_index(date(2025, Month::March, 1), Month::January) == 0);
+}
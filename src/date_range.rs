@@ -0,0 +1,133 @@
+use gregorian::Date;
+
+use crate::partial_date::{PartialDate, ParsePartialDateError};
+
+/// A range of dates with optional bounds, like `2024-01-15..2024-02-03` or `..2024-06-30`.
+///
+/// Both bounds are inclusive partial dates:
+/// `2024-01..2024-03` is the range from the first of January until the end of March.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DateRange {
+	/// The first date in the range, or `None` for no lower bound.
+	pub start: Option<PartialDate>,
+
+	/// The last date in the range, or `None` for no upper bound.
+	pub end: Option<PartialDate>,
+}
+
+impl DateRange {
+	/// Get the first date in the range.
+	pub fn as_start_date(self) -> Option<Date> {
+		self.start.map(|x| x.as_start_date())
+	}
+
+	/// Get the last date in the range.
+	pub fn as_end_date(self) -> Option<Date> {
+		self.end.map(|x| x.as_end_date())
+	}
+
+	/// Get the first date in the range and the date after the last date, like a half-open range.
+	pub fn as_half_open(self) -> (Option<Date>, Option<Date>) {
+		(self.as_start_date(), self.as_end_date().map(|x| x.next()))
+	}
+}
+
+impl std::str::FromStr for DateRange {
+	type Err = ParseDateRangeError;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		let (start, end) = data.split_once("..")
+			.ok_or(ParseDateRangeError::MissingSeparator)?;
+		if start.is_empty() && end.is_empty() {
+			return Err(ParseDateRangeError::MissingBounds);
+		}
+
+		let start = match start {
+			"" => None,
+			x => Some(x.parse().map_err(ParseDateRangeError::InvalidStart)?),
+		};
+		let end = match end {
+			"" => None,
+			x => Some(x.parse().map_err(ParseDateRangeError::InvalidEnd)?),
+		};
+		let range = Self { start, end };
+		if let (Some(start), Some(end)) = (range.as_start_date(), range.as_end_date()) {
+			if end < start {
+				return Err(ParseDateRangeError::EndBeforeStart);
+			}
+		}
+		Ok(range)
+	}
+}
+
+impl std::fmt::Display for DateRange {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if let Some(start) = &self.start {
+			write!(f, "{}", start)?;
+		}
+		write!(f, "..")?;
+		if let Some(end) = &self.end {
+			write!(f, "{}", end)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseDateRangeError {
+	MissingSeparator,
+	MissingBounds,
+	InvalidStart(ParsePartialDateError),
+	InvalidEnd(ParsePartialDateError),
+	EndBeforeStart,
+}
+
+impl std::error::Error for ParseDateRangeError {}
+
+impl std::fmt::Display for ParseDateRangeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::MissingSeparator => write!(f, "invalid syntax, expected START..END"),
+			Self::MissingBounds => write!(f, "the range needs a start date, an end date or both"),
+			Self::InvalidStart(e) => write!(f, "invalid start date: {}", e),
+			Self::InvalidEnd(e) => write!(f, "invalid end date: {}", e),
+			Self::EndBeforeStart => write!(f, "the end date is before the start date"),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+	use assert2::assert;
+	use gregorian::Month;
+
+	let range: DateRange = "2024-01-15..2024-02-03".parse().unwrap();
+	assert!(range.as_start_date() == Some(Date::new(2024, Month::January, 15).unwrap()));
+	assert!(range.as_end_date() == Some(Date::new(2024, Month::February, 3).unwrap()));
+	assert!(range.as_half_open().1 == Some(Date::new(2024, Month::February, 4).unwrap()));
+
+	let range: DateRange = "..2024-06".parse().unwrap();
+	assert!(range.as_start_date().is_none());
+	assert!(range.as_end_date() == Some(Date::new(2024, Month::June, 30).unwrap()));
+
+	let range: DateRange = "2024-Q2..".parse().unwrap();
+	assert!(range.as_start_date() == Some(Date::new(2024, Month::April, 1).unwrap()));
+	assert!(range.as_end_date().is_none());
+
+	assert!(let Err(ParseDateRangeError::MissingSeparator) = "2024-01-15".parse::<DateRange>());
+	assert!(let Err(ParseDateRangeError::MissingBounds) = "..".parse::<DateRange>());
+	assert!(let Err(ParseDateRangeError::InvalidEnd(_)) = "2024..2024-13".parse::<DateRange>());
+	assert!(let Err(ParseDateRangeError::EndBeforeStart) = "2024-02..2024-01".parse::<DateRange>());
+}
+
+#[cfg(test)]
+#[test]
+fn test_display() {
+	use assert2::assert;
+
+	for input in ["2024-01-15..2024-02-03", "..2024-06-30", "2024-Q2.."] {
+		let range: DateRange = input.parse().unwrap();
+		assert!(range.to_string() == input);
+	}
+}
@@ -0,0 +1,75 @@
+use gregorian::Date;
+
+use super::parse::ParseErrorDetails;
+use super::types::Transaction;
+
+/// Append a transaction to the contents of a grootboek file.
+///
+/// The existing data must parse without errors.
+/// Trailing whitespace at the end of the data is replaced by exactly one blank line before the new transaction,
+/// and the data will end with a newline.
+///
+/// Unless `force` is true, the transaction is not appended if the data contains a transaction with a later date.
+pub fn append_transaction_to_string(data: &mut String, transaction: &Transaction, force: bool) -> Result<(), AppendStringError> {
+	let existing = Transaction::parse_from_str(data)
+		.map_err(|e| AppendStringError::Parse(e.details, e.token.to_string()))?;
+	if !force {
+		if let Some(last) = existing.iter().map(|x| x.date).max() {
+			if last > transaction.date {
+				return Err(AppendStringError::OutOfOrder { last, date: transaction.date });
+			}
+		}
+	}
+
+	data.truncate(data.trim_end().len());
+	if !data.is_empty() {
+		data.push_str("\n\n");
+	}
+	data.push_str(&format!("{}\n", transaction));
+	Ok(())
+}
+
+#[derive(Debug)]
+pub enum AppendStringError {
+	Parse(ParseErrorDetails, String),
+	OutOfOrder { last: Date, date: Date },
+}
+
+impl std::error::Error for AppendStringError {}
+
+impl std::fmt::Display for AppendStringError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Parse(details, token) => write!(f, "parse error at token: {:?}: {}", token, details),
+			Self::OutOfOrder { last, date } => write!(f, "refusing to append transaction dated {}: it contains a transaction dated {}", date, last),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_append_transaction_to_string() {
+	use assert2::assert;
+	use super::types::{Account, Cents, Mutation};
+
+	let mut transaction = Transaction::new(
+		Date::new(2024, gregorian::Month::February, 1).unwrap(),
+		"Second",
+		Vec::new(),
+		vec![
+			Mutation { amount: Cents(200), account: Account::from_raw("a") },
+			Mutation { amount: Cents(-200), account: Account::from_raw("b") },
+		],
+	);
+
+	let mut data = String::new();
+	assert!(let Ok(()) = append_transaction_to_string(&mut data, &transaction, false));
+	assert!(data == "2024-02-01: Second\n+2.00 a\n-2.00 b\n");
+
+	transaction.date = Date::new(2024, gregorian::Month::January, 15).unwrap();
+	assert!(let Err(AppendStringError::OutOfOrder { .. }) = append_transaction_to_string(&mut data, &transaction, false));
+	assert!(data == "2024-02-01: Second\n+2.00 a\n-2.00 b\n");
+
+	let mut data = String::from("2024-01-01 First\n");
+	assert!(let Err(AppendStringError::Parse(..)) = append_transaction_to_string(&mut data, &transaction, false));
+}
@@ -0,0 +1,95 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use gregorian::Date;
+
+use super::append::{append_transaction_to_string, AppendStringError};
+use super::parse::ParseErrorDetails;
+use super::types::Transaction;
+
+/// Append a transaction to a grootboek file.
+///
+/// The file is created if it does not exist yet.
+/// If it does exist, it must parse without errors.
+/// Trailing whitespace at the end of the file is replaced by exactly one blank line before the new transaction,
+/// and the file will end with a newline.
+///
+/// Unless `force` is true, the transaction is not appended if the file contains a transaction with a later date.
+pub fn append_transaction(path: impl AsRef<Path>, transaction: &Transaction, force: bool) -> Result<(), AppendError> {
+	let path = path.as_ref();
+	let mut file = std::fs::OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create(true)
+		.truncate(false)
+		.open(path)
+		.map_err(|e| AppendError::Open(path.into(), e))?;
+
+	let mut data = String::new();
+	file.read_to_string(&mut data)
+		.map_err(|e| AppendError::Read(path.into(), e))?;
+
+	// Only write the new data, so a failed write does not lose existing transactions.
+	let keep = data.trim_end().len();
+	append_transaction_to_string(&mut data, transaction, force)
+		.map_err(|e| match e {
+			AppendStringError::Parse(details, token) => AppendError::Parse(path.into(), details, token),
+			AppendStringError::OutOfOrder { last, date } => AppendError::OutOfOrder { path: path.into(), last, date },
+		})?;
+	file.set_len(keep as u64)
+		.and_then(|()| file.seek(SeekFrom::End(0)))
+		.and_then(|_| file.write_all(&data.as_bytes()[keep..]))
+		.map_err(|e| AppendError::Write(path.into(), e))
+}
+
+#[derive(Debug)]
+pub enum AppendError {
+	Open(PathBuf, std::io::Error),
+	Read(PathBuf, std::io::Error),
+	Parse(PathBuf, ParseErrorDetails, String),
+	OutOfOrder { path: PathBuf, last: Date, date: Date },
+	Write(PathBuf, std::io::Error),
+}
+
+impl std::error::Error for AppendError {}
+
+impl std::fmt::Display for AppendError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Open(path, e) => write!(f, "failed to open {}: {}", path.display(), e),
+			Self::Read(path, e) => write!(f, "failed to read from {}: {}", path.display(), e),
+			Self::Parse(path, details, token) => write!(f, "failed to parse {}: parse error at token: {:?}: {}", path.display(), token, details),
+			Self::OutOfOrder { path, last, date } => write!(f, "refusing to append transaction dated {} to {}: it contains a transaction dated {}", date, path.display(), last),
+			Self::Write(path, e) => write!(f, "failed to write to {}: {}", path.display(), e),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_append_transaction() {
+	use assert2::assert;
+	use super::types::{Account, Cents, Mutation};
+
+	let path = std::env::temp_dir().join(format!("zzp-test-append-{}.grootboek", std::process::id()));
+	std::fs::write(&path, "2024-01-01: First\n+1.00 a\n-1.00 b\n\n\n  \n").unwrap();
+
+	let mut transaction = Transaction::new(
+		Date::new(2024, gregorian::Month::February, 1).unwrap(),
+		"Second",
+		Vec::new(),
+		vec![
+			Mutation { amount: Cents(200), account: Account::from_raw("a") },
+			Mutation { amount: Cents(-200), account: Account::from_raw("b") },
+		],
+	);
+	assert!(let Ok(()) = append_transaction(&path, &transaction, false));
+	assert!(std::fs::read_to_string(&path).unwrap() == "2024-01-01: First\n+1.00 a\n-1.00 b\n\n2024-02-01: Second\n+2.00 a\n-2.00 b\n");
+
+	transaction.date = Date::new(2024, gregorian::Month::January, 15).unwrap();
+	assert!(let Err(AppendError::OutOfOrder { .. }) = append_transaction(&path, &transaction, false));
+	assert!(let Ok(()) = append_transaction(&path, &transaction, true));
+	assert!(Transaction::parse_from_str(&std::fs::read_to_string(&path).unwrap()).unwrap().len() == 3);
+
+	std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,259 @@
+use gregorian::Date;
+
+use super::parse::{parse_header, BodyLine, InvalidTagDetails, ParseError};
+use super::types::NoteLine;
+
+/// A transaction with the comments directly preceding it.
+struct Block<'a> {
+	/// The comments before the transaction header.
+	///
+	/// Empty strings represent a blank line between comments.
+	comments: Vec<&'a str>,
+	date: Date,
+	description: &'a str,
+	lines: Vec<BodyLine<'a>>,
+}
+
+/// Normalize the formatting of a grootboek file.
+///
+/// Transactions are sorted by date, keeping the original order for transactions on the same date.
+/// All lines are trimmed, transactions are separated by a single blank line,
+/// tags are sorted by label, and mutation amounts are aligned and written with an explicit sign and two decimals.
+/// Notes are indented with a tab and have a single space after the `;`.
+///
+/// Comments are preserved and move together with the transaction that follows them.
+/// Comments at the start of the file that are separated from the first transaction by a blank line stay at the start,
+/// and comments after the last transaction stay at the end.
+pub fn normalize(data: &str) -> Result<String, ParseError<'_>> {
+	let mut preamble = Vec::new();
+	let mut pending = Vec::new();
+	let mut blocks: Vec<Block> = Vec::new();
+	let mut in_transaction = false;
+
+	for line in data.lines() {
+		let line = line.trim();
+		if in_transaction {
+			let block = blocks.last_mut().unwrap();
+			if line.is_empty() {
+				in_transaction = false;
+				continue;
+			}
+			let body_line = BodyLine::parse(line)?;
+			if let BodyLine::Tag(_) = body_line {
+				if block.lines.iter().any(|x| matches!(x, BodyLine::Mutation(_))) {
+					return Err(InvalidTagDetails::TagAfterMutation.for_token(line));
+				}
+			}
+			block.lines.push(body_line);
+		} else if line.is_empty() {
+			if blocks.is_empty() && preamble.is_empty() && !pending.is_empty() {
+				preamble = std::mem::take(&mut pending);
+			} else if !pending.is_empty() && pending.last() != Some(&"") {
+				pending.push("");
+			}
+		} else if line.starts_with('#') {
+			pending.push(line);
+		} else {
+			let (date, description) = parse_header(line)?;
+			blocks.push(Block {
+				comments: std::mem::take(&mut pending),
+				date,
+				description,
+				lines: Vec::new(),
+			});
+			in_transaction = true;
+		}
+	}
+
+	// Remove the trailing blank line from the comments at the end of the file.
+	if pending.last() == Some(&"") {
+		pending.pop();
+	}
+
+	// Sort by date, relying on the sort being stable.
+	blocks.sort_by_key(|x| x.date);
+	for block in &mut blocks {
+		sort_tags(&mut block.lines);
+	}
+
+	let mut output = String::with_capacity(data.len());
+	let mut first = true;
+	let mut separate = |output: &mut String| {
+		if !std::mem::replace(&mut first, false) {
+			output.push('\n');
+		}
+	};
+
+	if !preamble.is_empty() {
+		separate(&mut output);
+		write_comments(&mut output, &preamble);
+	}
+
+	for block in &blocks {
+		separate(&mut output);
+		write_comments(&mut output, &block.comments);
+		write_block(&mut output, block);
+	}
+
+	if !pending.is_empty() {
+		separate(&mut output);
+		write_comments(&mut output, &pending);
+	}
+
+	Ok(output)
+}
+
+/// Sort the tags of a transaction by label, keeping the original order for tags with the same label.
+///
+/// The other lines stay where they are.
+fn sort_tags(lines: &mut [BodyLine]) {
+	let slots: Vec<usize> = lines.iter()
+		.enumerate()
+		.filter(|(_, line)| matches!(line, BodyLine::Tag(_)))
+		.map(|(i, _)| i)
+		.collect();
+	let mut tags: Vec<_> = slots.iter()
+		.map(|&i| match &lines[i] {
+			BodyLine::Tag(tag) => *tag,
+			_ => unreachable!(),
+		})
+		.collect();
+	tags.sort_by_key(|x| x.label);
+	for (slot, tag) in slots.into_iter().zip(tags) {
+		lines[slot] = BodyLine::Tag(tag);
+	}
+}
+
+fn write_comments(output: &mut String, comments: &[&str]) {
+	for comment in comments {
+		output.push_str(comment);
+		output.push('\n');
+	}
+}
+
+fn write_block(output: &mut String, block: &Block) {
+	use std::fmt::Write;
+
+	let amount_width = block.lines.iter()
+		.filter_map(|line| match line {
+			BodyLine::Mutation(mutation) => Some(mutation.amount.to_string().len()),
+			_ => None,
+		})
+		.max()
+		.unwrap_or(0);
+
+	// Writing to a String can not fail.
+	writeln!(output, "{}: {}", block.date, block.description).unwrap();
+	for line in &block.lines {
+		match line {
+			BodyLine::Comment(comment) => writeln!(output, "{}", comment).unwrap(),
+			BodyLine::Note(note) => writeln!(output, "{}", NoteLine(note)).unwrap(),
+			BodyLine::Tag(tag) => writeln!(output, "{}: {}", tag.label, tag.value).unwrap(),
+			BodyLine::Mutation(mutation) => {
+				let amount = mutation.amount.to_string();
+				writeln!(output, "{:>width$} {}", amount, mutation.account, width = amount_width).unwrap()
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_normalize() {
+	use assert2::assert;
+
+	let input = concat!(
+		"# Grootboek 2024\n",
+		"\n",
+		"# The second transaction.\n",
+		"  2024-02-01 :  Hosting  \n",
+		"   ; Paid by credit card.\n",
+		"+10 costs/hosting\n",
+		"  -10.00  bank\n",
+		"\n",
+		"\n",
+		"# The first transaction.\n",
+		"\n",
+		"2024-01-15: Invoice 1\n",
+		"invoice:invoices/1.pdf\n",
+		"# Including VAT.\n",
+		"+1210.00 debitors/acme\n",
+		"-1000.00 revenue/acme\n",
+		"-210.00 vat/21\n",
+		"# The end.\n",
+	);
+
+	let expected = concat!(
+		"# Grootboek 2024\n",
+		"\n",
+		"# The first transaction.\n",
+		"\n",
+		"2024-01-15: Invoice 1\n",
+		"invoice: invoices/1.pdf\n",
+		"# Including VAT.\n",
+		"+1210.00 debitors/acme\n",
+		"-1000.00 revenue/acme\n",
+		" -210.00 vat/21\n",
+		"# The end.\n",
+		"\n",
+		"# The second transaction.\n",
+		"2024-02-01: Hosting\n",
+		"\t; Paid by credit card.\n",
+		"+10.00 costs/hosting\n",
+		"-10.00 bank\n",
+	);
+
+	let normalized = normalize(input);
+	assert!(let Ok(_) = &normalized);
+	let normalized = normalized.unwrap();
+	assert!(normalized == expected);
+	assert!(normalize(&normalized).unwrap() == normalized);
+}
+
+#[cfg(test)]
+#[test]
+fn test_normalize_tag_order() {
+	use assert2::assert;
+
+	let input = concat!(
+		"2024-01-15: Invoice 1\n",
+		"reference: PO-2\n",
+		"invoice: invoices/1.pdf\n",
+		"\t; A note.\n",
+		"reference: PO-1\n",
+		"+1.00 debitors/acme\n",
+		"-1.00 revenue/acme\n",
+	);
+
+	let expected = concat!(
+		"2024-01-15: Invoice 1\n",
+		"invoice: invoices/1.pdf\n",
+		"reference: PO-2\n",
+		"\t; A note.\n",
+		"reference: PO-1\n",
+		"+1.00 debitors/acme\n",
+		"-1.00 revenue/acme\n",
+	);
+
+	assert!(normalize(input).unwrap() == expected);
+}
+
+#[cfg(test)]
+#[test]
+fn test_normalize_notes_match_display() {
+	use assert2::assert;
+	use super::types::Transaction;
+
+	let input = concat!(
+		"2024-05-03: Correction\n",
+		";Invoice 41 was booked twice.  \n",
+		"\t;   Reverting the second booking.\n",
+		"invoice: invoices/41.pdf\n",
+		"-121.00 debitors/acme\n",
+		"+121.00 revenue/acme\n",
+	);
+
+	let transactions = Transaction::parse_from_str(input).unwrap();
+	assert!(transactions.len() == 1);
+	assert!(normalize(input).unwrap() == format!("{}\n", transactions[0]));
+}
@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+
+/// A grootboek file mapped into memory.
+///
+/// The transactions of large ledgers can be parsed directly from the mapped file with [`Transaction::parse_from_str`][super::Transaction::parse_from_str],
+/// without copying the whole file into a `String` first.
+/// The parsed transactions borrow from the mapped file.
+///
+/// Files that can not be mapped, like pipes, are read into memory instead.
+pub struct MappedFile {
+	contents: Contents,
+}
+
+/// The contents of a [`MappedFile`].
+enum Contents {
+	/// A read-only mapping of the file, checked to be valid UTF-8.
+	Mapped(memmap2::Mmap),
+
+	/// The contents of a file that could not be mapped.
+	Read(String),
+}
+
+impl MappedFile {
+	/// Map a file into memory.
+	///
+	/// The contents of the file must be valid UTF-8.
+	///
+	/// # Safety
+	/// The file must not be modified or truncated while the `MappedFile` exists, by this or any other process.
+	/// Changes to the file show up in the mapped contents, which may then no longer be valid UTF-8,
+	/// and reading beyond the end of a truncated file crashes the process.
+	///
+	/// Use [`std::fs::read_to_string`] instead if the file may be written to while the transactions are in use.
+	pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, MapError> {
+		let path = path.as_ref();
+		let file = std::fs::File::open(path)
+			.map_err(|e| MapError::Open(path.into(), e))?;
+
+		// SAFETY: The caller guarantees that the file is not modified while it is mapped.
+		let mapping = match unsafe { memmap2::Mmap::map(&file) } {
+			Ok(x) => x,
+			Err(_) => {
+				let contents = std::io::read_to_string(file)
+					.map_err(|e| MapError::Read(path.into(), e))?;
+				return Ok(Self { contents: Contents::Read(contents) });
+			},
+		};
+		std::str::from_utf8(&mapping)
+			.map_err(|e| MapError::InvalidUtf8(path.into(), e))?;
+		Ok(Self { contents: Contents::Mapped(mapping) })
+	}
+
+	/// Map a file into memory without blocking the async executor.
+	///
+	/// Checking that the contents are valid UTF-8 reads the whole file,
+	/// so this is done on the blocking thread pool of tokio.
+	///
+	/// # Safety
+	/// The file must not be modified or truncated while the `MappedFile` exists, see [`Self::open`].
+	#[cfg(feature = "tokio")]
+	pub async unsafe fn open_async(path: impl Into<PathBuf>) -> Result<Self, MapError> {
+		let path = path.into();
+		// SAFETY: The caller upholds the contract of `open`.
+		tokio::task::spawn_blocking(move || unsafe { Self::open(path) }).await
+			.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+	}
+
+	/// Get the contents of the file.
+	pub fn as_str(&self) -> &str {
+		match &self.contents {
+			// SAFETY: The contents were checked to be valid UTF-8 when the file was mapped,
+			// and the caller of `open` guarantees that the file is not modified.
+			Contents::Mapped(mapping) => unsafe { std::str::from_utf8_unchecked(mapping) },
+			Contents::Read(contents) => contents,
+		}
+	}
+}
+
+impl std::ops::Deref for MappedFile {
+	type Target = str;
+
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl std::fmt::Debug for MappedFile {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("MappedFile")
+			.field("len", &self.as_str().len())
+			.field("mapped", &matches!(self.contents, Contents::Mapped(_)))
+			.finish()
+	}
+}
+
+#[derive(Debug)]
+pub enum MapError {
+	Open(PathBuf, std::io::Error),
+	Read(PathBuf, std::io::Error),
+	InvalidUtf8(PathBuf, std::str::Utf8Error),
+}
+
+impl std::error::Error for MapError {}
+
+impl std::fmt::Display for MapError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Open(path, e) => write!(f, "failed to open {}: {}", path.display(), e),
+			Self::Read(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+			Self::InvalidUtf8(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_mapped_file() {
+	use assert2::assert;
+	use super::types::Transaction;
+
+	let path = std::env::temp_dir().join(format!("zzp-test-mapped-{}.grootboek", std::process::id()));
+	let data = "2024-01-01: First\n+1.00 a\n-1.00 b\n\n2024-02-01: Second\n+2.00 a\n-2.00 b\n";
+	std::fs::write(&path, data).unwrap();
+	// SAFETY: The file is not modified while it is mapped.
+	let file = unsafe { MappedFile::open(&path) }.unwrap();
+	assert!(file.as_str() == data);
+
+	// The transactions borrow from the mapped file.
+	let transactions = Transaction::parse_from_str(&file).unwrap();
+	assert!(transactions.len() == 2);
+	assert!(file.as_bytes().as_ptr_range().contains(&transactions[1].description.as_ptr()));
+	drop(transactions);
+	drop(file);
+
+	std::fs::write(&path, "").unwrap();
+	assert!(unsafe { MappedFile::open(&path) }.unwrap().as_str() == "");
+
+	std::fs::write(&path, b"2024-01-01: \xFF\n").unwrap();
+	assert!(let Err(MapError::InvalidUtf8(..)) = unsafe { MappedFile::open(&path) });
+
+	std::fs::remove_file(&path).unwrap();
+}
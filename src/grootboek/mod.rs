@@ -1,8 +1,53 @@
 mod types;
+mod append;
+#[cfg(feature = "system")]
+mod file;
+#[cfg(feature = "system")]
+mod mapped;
 mod parse;
+mod query;
+mod format;
+mod vat;
+
+/// The date type of transactions, the same type as used by [`crate::uurlog`] and [`crate::partial_date`].
+pub use gregorian::Date;
 
 pub use types::Account;
 pub use types::Cents;
+pub use types::InvalidRoundingMode;
 pub use types::Mutation;
+pub use types::NoteLine;
+pub use types::RoundingMode;
 pub use types::Tag;
 pub use types::Transaction;
+
+pub use append::AppendStringError;
+pub use append::append_transaction_to_string;
+#[cfg(feature = "system")]
+pub use file::AppendError;
+#[cfg(feature = "system")]
+pub use file::append_transaction;
+
+#[cfg(feature = "system")]
+pub use mapped::MapError;
+#[cfg(feature = "system")]
+pub use mapped::MappedFile;
+
+pub use parse::InvalidMutationDetails;
+pub use parse::InvalidTagDetails;
+pub use parse::InvalidTransactionHeaderDetails;
+pub use parse::ParseError;
+pub use parse::ParseErrorDetails;
+
+pub use query::ParseQueryError;
+pub use query::Query;
+
+pub use format::normalize;
+
+pub use vat::VatSplit;
+pub use vat::VatTotal;
+pub use vat::add_vat;
+pub use vat::invoice_mutations;
+pub use vat::payment_mutations;
+pub use vat::split_gross;
+pub use vat::vat_totals;
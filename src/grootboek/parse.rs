@@ -1,4 +1,5 @@
 use gregorian::Date;
+use smallvec::SmallVec;
 
 use super::types::Account;
 use super::types::Cents;
@@ -10,7 +11,6 @@ impl<'a> Transaction<'a> {
 	pub fn parse_from_str(data: &'a str) -> Result<Vec<Self>, ParseError<'a>> {
 		let mut lines = data.lines();
 		let mut output = Vec::new();
-		output.reserve((lines.size_hint().0 + 3) / 4);
 
 		while let Some(transaction) = Self::parse_from_lines(&mut lines)? {
 			output.push(transaction);
@@ -19,6 +19,11 @@ impl<'a> Transaction<'a> {
 		Ok(output)
 	}
 
+	/// Parse the next transaction.
+	///
+	/// The body is collected in buffers on the stack and copied into vectors with the exact size,
+	/// so each non-empty list of notes, tags or mutations is allocated once.
+	/// Only transactions with more lines than fit in the buffers allocate while they are parsed.
 	pub fn parse_from_lines(lines: &mut std::str::Lines<'a>) -> Result<Option<Self>, ParseError<'a>> {
 		let header = loop {
 			let line = match lines.next() {
@@ -32,46 +37,85 @@ impl<'a> Transaction<'a> {
 			}
 		};
 
-		// Split header in date and description.
-		let (date, description) = partition(header, ':')
-			.ok_or_else(|| MissingDescription.for_token(header))?;
-		let date = date.trim();
-		let description = description.trim();
+		let (date, description) = parse_header(header)?;
 
-		// Reject empty descriptions.
-		if description.is_empty() {
-			return Err(MissingDescription.for_token(header));
-		}
-
-		// Parse the date.
-		let date: Date = date.parse().map_err(|_| InvalidTransactionHeaderDetails::InvalidDate.for_token(date))?;
-
-		// Parse tags and mutations until there are none left.
-		let mut tags = Vec::new();
-		let mut mutations = Vec::new();
+		// Parse notes, tags and mutations until there are none left.
+		let mut notes = SmallVec::<[&str; 4]>::new();
+		let mut tags = SmallVec::<[Tag; 4]>::new();
+		let mut mutations = SmallVec::<[Mutation; 8]>::new();
 
-		while let Some(line) = lines.next() {
+		for line in lines {
 			let line = line.trim();
 			// Stop on empty line.
 			if line.is_empty() {
 				break;
-			// Ignore comments.
-			} else if line.starts_with('#') {
-				continue;
-			// See if the line looks like a tag.
-			} else if let Some(tag) = Tag::parse_from_str(line) {
-				if mutations.is_empty() {
-					tags.push(tag?);
-				} else {
-					return Err(InvalidTagDetails::TagAfterMutation.for_token(line));
-				}
-			// Parse mutations.
-			} else {
-				mutations.push(Mutation::parse_from_str(line)?);
+			}
+			match BodyLine::parse(line)? {
+				BodyLine::Comment(_) => continue,
+				BodyLine::Note(note) => notes.push(note),
+				BodyLine::Tag(tag) => {
+					if mutations.is_empty() {
+						tags.push(tag);
+					} else {
+						return Err(InvalidTagDetails::TagAfterMutation.for_token(line));
+					}
+				},
+				BodyLine::Mutation(mutation) => mutations.push(mutation),
 			}
 		}
 
-		Ok(Some(Self { date, description, tags, mutations }))
+		Ok(Some(Self {
+			date,
+			description,
+			notes: notes.to_vec(),
+			tags: tags.to_vec(),
+			mutations: mutations.to_vec(),
+		}))
+	}
+}
+
+/// Parse a transaction header into the date and description.
+pub(crate) fn parse_header(header: &str) -> Result<(Date, &str), ParseError<'_>> {
+	// Split header in date and description.
+	let (date, description) = partition(header, ':')
+		.ok_or_else(|| MissingDescription.for_token(header))?;
+	let date = date.trim();
+	let description = description.trim();
+
+	// Reject empty descriptions.
+	if description.is_empty() {
+		return Err(MissingDescription.for_token(header));
+	}
+
+	// Parse the date.
+	let date: Date = date.parse().map_err(|_| InvalidTransactionHeaderDetails::InvalidDate.for_token(date))?;
+	Ok((date, description))
+}
+
+/// A line from the body of a transaction.
+pub(crate) enum BodyLine<'a> {
+	Comment(&'a str),
+	Note(&'a str),
+	Tag(Tag<'a>),
+	Mutation(Mutation<'a>),
+}
+
+impl<'a> BodyLine<'a> {
+	/// Parse a trimmed, non-empty line from the body of a transaction.
+	pub(crate) fn parse(line: &'a str) -> Result<Self, ParseError<'a>> {
+		// Comments.
+		if line.starts_with('#') {
+			Ok(Self::Comment(line))
+		// Notes, trimmed after the `;`.
+		} else if let Some(note) = line.strip_prefix(';') {
+			Ok(Self::Note(note.trim()))
+		// See if the line looks like a tag.
+		} else if let Some(tag) = Tag::parse_from_str(line) {
+			Ok(Self::Tag(tag?))
+		// Parse mutations.
+		} else {
+			Ok(Self::Mutation(Mutation::parse_from_str(line)?))
+		}
 	}
 }
 
@@ -113,7 +157,7 @@ impl<'a> Mutation<'a> {
 }
 
 impl Cents {
-	fn parse_from_str(data: &str) -> Result<Self, ()> {
+	pub(crate) fn parse_from_str(data: &str) -> Result<Self, ()> {
 		if let Some((whole, decimals)) = partition(data, '.') {
 			if decimals.len() != 2 {
 				Err(())
@@ -194,7 +238,7 @@ impl InvalidTransactionHeaderDetails {
 }
 
 impl InvalidTagDetails {
-	fn for_token(self, token: &str) -> ParseError {
+	pub(crate) fn for_token(self, token: &str) -> ParseError {
 		ParseError { details: self.into(), token }
 	}
 }
@@ -260,3 +304,21 @@ impl std::error::Error for ParseErrorDetails {}
 impl std::error::Error for InvalidTransactionHeaderDetails {}
 impl std::error::Error for InvalidTagDetails {}
 impl std::error::Error for InvalidMutationDetails {}
+
+#[cfg(test)]
+#[test]
+fn test_parse_notes() {
+	use assert2::assert;
+
+	let data = "2024-05-03: Correction\n\t; Invoice 41 was booked twice.\n\t;   Reverting the second booking.\ninvoice: invoices/41.pdf\n; Note between mutations.\n-121.00 debitors/acme\n+121.00 revenue/acme\n";
+	let transactions = Transaction::parse_from_str(data).unwrap();
+	assert!(transactions.len() == 1);
+	assert!(transactions[0].notes == ["Invoice 41 was booked twice.", "Reverting the second booking.", "Note between mutations."]);
+	assert!(transactions[0].tags.len() == 1);
+	assert!(transactions[0].mutations.len() == 2);
+
+	// Notes are written with a single space after the `;`.
+	let written = transactions[0].to_string();
+	assert!(written.contains("\n\t; Reverting the second booking.\n"));
+	assert!(Transaction::parse_from_str(&written).unwrap() == transactions);
+}
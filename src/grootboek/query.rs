@@ -0,0 +1,288 @@
+use crate::partial_date::{PartialDate, ParsePartialDateError};
+
+use super::types::Cents;
+use super::types::Transaction;
+
+/// A query to filter transactions.
+///
+/// A query consists of whitespace separated terms, and a transaction matches the query if it matches all terms.
+/// Values containing whitespace can be quoted with double quotes.
+/// A term can be negated by prefixing it with `!`.
+///
+/// The supported terms are:
+///  * `account:PREFIX`: the transaction mutates the account or one of its sub-accounts (a trailing `/*` is allowed).
+///  * `tag:LABEL` or `tag:LABEL=VALUE`: the transaction has a tag with the given label (and value).
+///  * `date:PERIOD`: the transaction date lies within a period, like `2024`, `2024-Q2`, `2024-05` or `2024-05-03`.
+///  * `date<PERIOD`, `date<=PERIOD`, `date>PERIOD`, `date>=PERIOD`: the transaction date lies before or after a period.
+///  * `amount=X`, `amount<X`, `amount<=X`, `amount>X`, `amount>=X`: the absolute amount of any mutation compares to `X`.
+///    The amount is written without sign and with up to two decimals, like `100`, `1.5` or `1.50`.
+///  * `desc:TEXT` or just `TEXT`: the transaction description contains the text (case insensitive).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Query {
+	terms: Vec<Term>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Term {
+	Not(Box<Term>),
+	Account(String),
+	Tag(String, Option<String>),
+	Date(Comparison, PartialDate),
+	Amount(Comparison, Cents),
+	Description(String),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Comparison {
+	Equal,
+	Less,
+	LessOrEqual,
+	Greater,
+	GreaterOrEqual,
+}
+
+impl Query {
+	/// Create a query that matches all transactions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Check if the query has no terms and matches all transactions.
+	pub fn is_empty(&self) -> bool {
+		self.terms.is_empty()
+	}
+
+	/// Check if a transaction matches the query.
+	pub fn matches(&self, transaction: &Transaction) -> bool {
+		self.terms.iter().all(|term| term.matches(transaction))
+	}
+}
+
+impl Term {
+	fn matches(&self, transaction: &Transaction) -> bool {
+		match self {
+			Self::Not(term) => !term.matches(transaction),
+			Self::Account(prefix) => transaction.mutates_account(prefix),
+			Self::Tag(label, value) => transaction.tags.iter().any(|tag| {
+				tag.label == label && value.as_ref().map(|value| tag.value == value).unwrap_or(true)
+			}),
+			Self::Date(comparison, period) => match comparison {
+				Comparison::Equal => transaction.date >= period.as_start_date() && transaction.date <= period.as_end_date(),
+				Comparison::Less => transaction.date < period.as_start_date(),
+				Comparison::LessOrEqual => transaction.date <= period.as_end_date(),
+				Comparison::Greater => transaction.date > period.as_end_date(),
+				Comparison::GreaterOrEqual => transaction.date >= period.as_start_date(),
+			},
+			Self::Amount(comparison, amount) => transaction.mutations.iter().any(|mutation| {
+				let value = Cents(mutation.amount.total_cents().abs());
+				match comparison {
+					Comparison::Equal => value == *amount,
+					Comparison::Less => value < *amount,
+					Comparison::LessOrEqual => value <= *amount,
+					Comparison::Greater => value > *amount,
+					Comparison::GreaterOrEqual => value >= *amount,
+				}
+			}),
+			Self::Description(text) => transaction.description.to_lowercase().contains(text.as_str()),
+		}
+	}
+
+	fn parse(token: &str) -> Result<Self, ParseQueryError> {
+		if let Some(token) = token.strip_prefix('!') {
+			return Ok(Self::Not(Box::new(Self::parse(token)?)));
+		}
+
+		let key_len = token.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(token.len());
+		let (key, rest) = token.split_at(key_len);
+		let (comparison, value) = match parse_comparison(rest) {
+			Some(x) => x,
+			None => return Ok(Self::Description(unquote(token).to_lowercase())),
+		};
+		let value = unquote(value);
+
+		match (key, comparison) {
+			("account", None) => {
+				let prefix = value.strip_suffix('*').unwrap_or(value);
+				Ok(Self::Account(prefix.to_string()))
+			},
+			("tag", None) => match value.split_once('=') {
+				Some((label, value)) => Ok(Self::Tag(label.to_string(), Some(value.to_string()))),
+				None => Ok(Self::Tag(value.to_string(), None)),
+			},
+			("desc", None) => Ok(Self::Description(value.to_lowercase())),
+			("date", comparison) => {
+				let period = value.parse().map_err(|e| ParseQueryError::InvalidDate(token.to_string(), e))?;
+				Ok(Self::Date(comparison.unwrap_or(Comparison::Equal), period))
+			},
+			("amount", Some(comparison)) => {
+				if value.starts_with(['+', '-']) {
+					return Err(ParseQueryError::SignedAmount(token.to_string()));
+				}
+				let amount = parse_amount(value).ok_or_else(|| ParseQueryError::InvalidAmount(token.to_string()))?;
+				Ok(Self::Amount(comparison, amount))
+			},
+			_ => Err(ParseQueryError::InvalidTerm(token.to_string())),
+		}
+	}
+}
+
+/// Parse the comparison operator at the start of a term value.
+///
+/// Returns `None` as comparison for the `:` separator.
+fn parse_comparison(data: &str) -> Option<(Option<Comparison>, &str)> {
+	let operators = [
+		(":", None),
+		("<=", Some(Comparison::LessOrEqual)),
+		(">=", Some(Comparison::GreaterOrEqual)),
+		("<", Some(Comparison::Less)),
+		(">", Some(Comparison::Greater)),
+		("=", Some(Comparison::Equal)),
+	];
+	for (operator, comparison) in operators {
+		if let Some(rest) = data.strip_prefix(operator) {
+			return Some((comparison, rest));
+		}
+	}
+	None
+}
+
+/// Parse an amount without sign and with up to two decimals.
+fn parse_amount(data: &str) -> Option<Cents> {
+	let (whole, decimals) = match data.split_once('.') {
+		Some((whole, decimals)) if !decimals.is_empty() && decimals.len() <= 2 => (whole, decimals),
+		Some(_) => return None,
+		None => (data, ""),
+	};
+	if whole.is_empty() || !whole.bytes().chain(decimals.bytes()).all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+	let whole: i32 = whole.parse().ok()?;
+	// Pad the decimals to cents, so `1.5` is 150 cents.
+	let cents: i32 = format!("{:0<2}", decimals).parse().ok()?;
+	whole.checked_mul(100)?.checked_add(cents).map(Cents)
+}
+
+/// Remove the double quotes from a (partially) quoted value.
+fn unquote(data: &str) -> &str {
+	let data = data.strip_prefix('"').unwrap_or(data);
+	data.strip_suffix('"').unwrap_or(data)
+}
+
+/// Split a query in tokens on whitespace, keeping quoted strings together.
+fn tokenize(data: &str) -> Vec<&str> {
+	let mut tokens = Vec::new();
+	let mut start = None;
+	let mut quoted = false;
+	for (i, c) in data.char_indices() {
+		if c == '"' {
+			quoted = !quoted;
+		}
+		if c.is_whitespace() && !quoted {
+			if let Some(start) = start.take() {
+				tokens.push(&data[start..i]);
+			}
+		} else if start.is_none() {
+			start = Some(i);
+		}
+	}
+	if let Some(start) = start {
+		tokens.push(&data[start..]);
+	}
+	tokens
+}
+
+impl std::str::FromStr for Query {
+	type Err = ParseQueryError;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		let terms = tokenize(data)
+			.into_iter()
+			.map(Term::parse)
+			.collect::<Result<_, _>>()?;
+		Ok(Self { terms })
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseQueryError {
+	InvalidTerm(String),
+	InvalidDate(String, ParsePartialDateError),
+	InvalidAmount(String),
+	SignedAmount(String),
+}
+
+impl std::error::Error for ParseQueryError {}
+
+impl std::fmt::Display for ParseQueryError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::InvalidTerm(token) => write!(f, "invalid query term: {:?}", token),
+			Self::InvalidDate(token, e) => write!(f, "invalid date in query term {:?}: {}", token, e),
+			Self::InvalidAmount(token) => write!(f, "invalid amount in query term {:?}", token),
+			Self::SignedAmount(token) => write!(f, "amounts are compared without sign, remove the sign from query term {:?}", token),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_query() {
+	use assert2::assert;
+
+	let data = "2024-05-03: Invoice 42\ninvoice: invoices/42.pdf\n+121.00 debitors/acme\n-100.00 revenue/acme\n-21.00 vat/21\n";
+	let transactions = Transaction::parse_from_str(data).unwrap();
+	let transaction = &transactions[0];
+
+	let matches = |query: &str| query.parse::<Query>().unwrap().matches(transaction);
+	assert!(matches(""));
+	assert!(matches("account:revenue/*"));
+	assert!(matches("account:revenue account:vat"));
+	assert!(!matches("account:costs"));
+	assert!(matches("!account:costs"));
+	assert!(matches("tag:invoice"));
+	assert!(matches("tag:invoice=invoices/42.pdf"));
+	assert!(!matches("tag:invoice=invoices/43.pdf"));
+	assert!(matches("date:2024-Q2 date:2024-05 date>=2024-05-03 date<2024-06"));
+	assert!(!matches("date:2024-Q1"));
+	assert!(matches("amount>100 amount=21"));
+	assert!(!matches("amount>200"));
+	assert!(matches("invoice desc:\"invoice 42\""));
+	assert!(let Err(ParseQueryError::InvalidTerm(_)) = "foo:bar".parse::<Query>());
+	assert!(let Err(ParseQueryError::InvalidDate(..)) = "date:tomorrow".parse::<Query>());
+	assert!(let Err(ParseQueryError::InvalidAmount(_)) = "amount>lots".parse::<Query>());
+}
+
+#[cfg(test)]
+#[test]
+fn test_query_amount() {
+	use assert2::assert;
+
+	let data = "2024-05-03: Bank costs
++1.50 costs/bank
+-1.50 bank
+";
+	let transactions = Transaction::parse_from_str(data).unwrap();
+	let matches = |query: &str| query.parse::<Query>().unwrap().matches(&transactions[0]);
+	assert!(matches("amount=1.5"));
+	assert!(matches("amount=1.50"));
+	assert!(matches("amount>1.4 amount<1.6"));
+	assert!(!matches("amount>1.5"));
+	assert!(matches("amount>=1.5"));
+	assert!(matches("amount>1"));
+	assert!(!matches("amount>2"));
+	assert!(matches("amount<=\"1.50\""));
+
+	assert!(parse_amount("0.05") == Some(Cents(5)));
+	assert!(parse_amount("12.3") == Some(Cents(1230)));
+	assert!(parse_amount("12") == Some(Cents(1200)));
+	assert!(parse_amount("1.555") == None);
+	assert!(parse_amount("1.") == None);
+	assert!(parse_amount(".5") == None);
+	assert!(parse_amount("1.-5") == None);
+	assert!(parse_amount("1,50") == None);
+	assert!(parse_amount("99999999999") == None);
+
+	assert!(let Err(ParseQueryError::SignedAmount(_)) = "amount>-1.50".parse::<Query>());
+	assert!(let Err(ParseQueryError::SignedAmount(_)) = "amount=+1.50".parse::<Query>());
+	assert!(let Err(ParseQueryError::InvalidAmount(_)) = "amount>1.555".parse::<Query>());
+}
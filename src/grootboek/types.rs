@@ -4,10 +4,23 @@ use gregorian::Date;
 pub struct Transaction<'a> {
 	pub date: Date,
 	pub description: &'a str,
+	/// Free-form notes, written as `; note` lines in the body of the transaction.
+	///
+	/// Notes are trimmed, so they have no leading or trailing whitespace.
+	/// The description is always a single line:
+	/// notes are the only way to continue a transaction over multiple lines.
+	pub notes: Vec<&'a str>,
 	pub tags: Vec<Tag<'a>>,
 	pub mutations: Vec<Mutation<'a>>,
 }
 
+impl<'a> Transaction<'a> {
+	/// Create a transaction without notes.
+	pub fn new(date: Date, description: &'a str, tags: Vec<Tag<'a>>, mutations: Vec<Mutation<'a>>) -> Self {
+		Self { date, description, notes: Vec::new(), tags, mutations }
+	}
+}
+
 impl Transaction<'_> {
 	pub fn mutates_account(&self, prefix: &str) -> bool {
 		for mutation in &self.mutations {
@@ -35,6 +48,12 @@ pub struct Mutation<'a> {
 pub struct Cents(pub i32);
 
 impl Cents {
+	/// Convert an amount in whole currency units to cents, rounding to whole cents.
+	pub fn from_f64(amount: f64, rounding: RoundingMode) -> Self {
+		let amount = (amount * 1_000_000.0).round() as i64;
+		Self(rounding.div(amount, 10_000) as i32)
+	}
+
 	pub fn total_cents(self) -> i32 {
 		self.0
 	}
@@ -42,6 +61,147 @@ impl Cents {
 	pub fn is_negative(self) -> bool {
 		self.0 < 0
 	}
+
+	/// Multiply the amount by a factor, rounding the result to whole cents.
+	///
+	/// The factor is converted to a fixed point number with 6 decimals before multiplying,
+	/// so that the rounding is done on an exact intermediate result.
+	pub fn mul_f64(self, factor: f64, rounding: RoundingMode) -> Self {
+		let factor = (factor * 1_000_000.0).round() as i64;
+		Self(rounding.div(i64::from(self.0) * factor, 1_000_000) as i32)
+	}
+
+	/// Divide the amount by a divisor, rounding the result to whole cents.
+	///
+	/// The divisor is converted to a fixed point number with 6 decimals before dividing,
+	/// so that the rounding is done on an exact intermediate result.
+	///
+	/// # Panics
+	/// This function panics if the divisor rounds to zero.
+	pub fn div_f64(self, divisor: f64, rounding: RoundingMode) -> Self {
+		let divisor = (divisor * 1_000_000.0).round() as i64;
+		Self(rounding.div(i64::from(self.0) * 1_000_000, divisor) as i32)
+	}
+
+	/// Compute a percentage of the amount, rounded to whole cents.
+	pub fn percentage(self, percentage: f64, rounding: RoundingMode) -> Self {
+		let percentage = (percentage * 10_000.0).round() as i64;
+		Self(rounding.div(i64::from(self.0) * percentage, 1_000_000) as i32)
+	}
+
+	/// Add a percentage to the amount, rounding only the added part to whole cents.
+	pub fn add_percentage(self, percentage: f64, rounding: RoundingMode) -> Self {
+		self + self.percentage(percentage, rounding)
+	}
+}
+
+/// The rounding mode to use when an amount does not fit in whole cents.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RoundingMode {
+	/// Round to the nearest cent, rounding halfway cases away from zero.
+	#[default]
+	HalfAwayFromZero,
+
+	/// Round to the nearest cent, rounding halfway cases to the nearest even number of cents.
+	HalfEven,
+
+	/// Round towards zero.
+	TowardZero,
+
+	/// Round away from zero.
+	AwayFromZero,
+
+	/// Round towards negative infinity.
+	Down,
+
+	/// Round towards positive infinity.
+	Up,
+}
+
+impl RoundingMode {
+	/// Divide two integers, rounding the result according to the rounding mode.
+	///
+	/// # Panics
+	/// This function panics if `denominator` is zero.
+	pub fn div(self, numerator: i64, denominator: i64) -> i64 {
+		let (numerator, denominator) = if denominator < 0 {
+			(-numerator, -denominator)
+		} else {
+			(numerator, denominator)
+		};
+
+		// Rust integer division truncates towards zero.
+		let quotient = numerator / denominator;
+		let remainder = numerator % denominator;
+		if remainder == 0 {
+			return quotient;
+		}
+
+		let away_from_zero = if numerator < 0 { quotient - 1 } else { quotient + 1 };
+		let twice_remainder = remainder.abs() * 2;
+		match self {
+			Self::TowardZero => quotient,
+			Self::AwayFromZero => away_from_zero,
+			Self::Down => quotient.min(away_from_zero),
+			Self::Up => quotient.max(away_from_zero),
+			Self::HalfAwayFromZero => {
+				if twice_remainder >= denominator {
+					away_from_zero
+				} else {
+					quotient
+				}
+			},
+			Self::HalfEven => {
+				if twice_remainder > denominator || (twice_remainder == denominator && quotient % 2 != 0) {
+					away_from_zero
+				} else {
+					quotient
+				}
+			},
+		}
+	}
+}
+
+impl std::str::FromStr for RoundingMode {
+	type Err = InvalidRoundingMode;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		match data {
+			"half_away_from_zero" => Ok(Self::HalfAwayFromZero),
+			"half_even" => Ok(Self::HalfEven),
+			"toward_zero" => Ok(Self::TowardZero),
+			"away_from_zero" => Ok(Self::AwayFromZero),
+			"down" => Ok(Self::Down),
+			"up" => Ok(Self::Up),
+			_ => Err(InvalidRoundingMode { _private: () }),
+		}
+	}
+}
+
+impl std::fmt::Display for RoundingMode {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::HalfAwayFromZero => f.write_str("half_away_from_zero"),
+			Self::HalfEven => f.write_str("half_even"),
+			Self::TowardZero => f.write_str("toward_zero"),
+			Self::AwayFromZero => f.write_str("away_from_zero"),
+			Self::Down => f.write_str("down"),
+			Self::Up => f.write_str("up"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidRoundingMode {
+	_private: (),
+}
+
+impl std::error::Error for InvalidRoundingMode {}
+
+impl std::fmt::Display for InvalidRoundingMode {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "invalid rounding mode, expected one of: half_away_from_zero, half_even, toward_zero, away_from_zero, down, up")
+	}
 }
 
 impl std::ops::Add<Cents> for Cents {
@@ -72,6 +232,42 @@ impl std::ops::AddAssign<&Cents> for Cents {
 	}
 }
 
+impl std::ops::Sub<Cents> for Cents {
+	type Output = Cents;
+
+	fn sub(self, other: Cents) -> Self::Output {
+		Cents(self.0 - other.0)
+	}
+}
+
+impl std::ops::Sub<&Cents> for &Cents {
+	type Output = Cents;
+
+	fn sub(self, other: &Cents) -> Self::Output {
+		*self - *other
+	}
+}
+
+impl std::ops::SubAssign<Cents> for Cents {
+	fn sub_assign(&mut self, other: Cents) {
+		self.0 -= other.0;
+	}
+}
+
+impl std::ops::SubAssign<&Cents> for Cents {
+	fn sub_assign(&mut self, other: &Cents) {
+		*self -= *other
+	}
+}
+
+impl std::ops::Mul<i32> for Cents {
+	type Output = Cents;
+
+	fn mul(self, other: i32) -> Self::Output {
+		Cents(self.0 * other)
+	}
+}
+
 impl std::ops::Neg for Cents {
 	type Output = Self;
 
@@ -173,9 +369,37 @@ impl<'a> Iterator for AccountParents<'a> {
 impl std::fmt::Display for Cents {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		let Cents(amount) = self;
-		let whole = amount / 100;
-		let cents = (amount % 100).abs();
-		write!(f, "{:+}.{:02}", whole, cents)
+		let sign = if *amount < 0 { '-' } else { '+' };
+		let amount = amount.unsigned_abs();
+		write!(f, "{}{}.{:02}", sign, amount / 100, amount % 100)
+	}
+}
+
+impl std::fmt::Display for Transaction<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}: {}", self.date, self.description)?;
+		for note in &self.notes {
+			write!(f, "\n{}", NoteLine(note))?;
+		}
+		for tag in &self.tags {
+			write!(f, "\n{}: {}", tag.label, tag.value)?;
+		}
+		for mutation in &self.mutations {
+			write!(f, "\n{} {}", mutation.amount, mutation.account)?;
+		}
+		Ok(())
+	}
+}
+
+/// A note of a transaction, displayed as a note line without a trailing newline.
+///
+/// This is the only place where note lines are written,
+/// so [`Transaction`] and [`normalize`](super::normalize) write notes the same way.
+pub struct NoteLine<'a>(pub &'a str);
+
+impl std::fmt::Display for NoteLine<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "\t; {}", self.0)
 	}
 }
 
@@ -184,3 +408,69 @@ impl std::fmt::Display for Account<'_> {
 		self.raw.fmt(f)
 	}
 }
+
+#[cfg(test)]
+#[test]
+fn test_display_cents() {
+	use assert2::assert;
+
+	assert!(Cents(12345).to_string() == "+123.45");
+	assert!(Cents(-12345).to_string() == "-123.45");
+	assert!(Cents(-50).to_string() == "-0.50");
+	assert!(Cents(5).to_string() == "+0.05");
+	assert!(Cents(0).to_string() == "+0.00");
+}
+
+#[cfg(test)]
+#[test]
+fn test_rounding_mode() {
+	use assert2::assert;
+
+	assert!(RoundingMode::HalfAwayFromZero.div(5, 2) == 3);
+	assert!(RoundingMode::HalfAwayFromZero.div(-5, 2) == -3);
+	assert!(RoundingMode::HalfAwayFromZero.div(4, 3) == 1);
+	assert!(RoundingMode::HalfEven.div(5, 2) == 2);
+	assert!(RoundingMode::HalfEven.div(7, 2) == 4);
+	assert!(RoundingMode::HalfEven.div(-5, 2) == -2);
+	assert!(RoundingMode::TowardZero.div(-7, 2) == -3);
+	assert!(RoundingMode::AwayFromZero.div(7, 3) == 3);
+	assert!(RoundingMode::Down.div(-7, 2) == -4);
+	assert!(RoundingMode::Up.div(7, 3) == 3);
+	assert!(RoundingMode::Up.div(7, -3) == -2);
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_rounding_mode() {
+	use assert2::assert;
+
+	for mode in [
+		RoundingMode::HalfAwayFromZero,
+		RoundingMode::HalfEven,
+		RoundingMode::TowardZero,
+		RoundingMode::AwayFromZero,
+		RoundingMode::Down,
+		RoundingMode::Up,
+	] {
+		assert!(mode.to_string().parse() == Ok(mode));
+	}
+	assert!("half_even".parse() == Ok(RoundingMode::HalfEven));
+	assert!(let Err(_) = "nearest".parse::<RoundingMode>());
+}
+
+#[cfg(test)]
+#[test]
+fn test_cents_mul_div() {
+	use assert2::assert;
+
+	assert!(Cents(250).percentage(21.0, RoundingMode::HalfAwayFromZero) == Cents(53));
+	assert!(Cents(250).percentage(21.0, RoundingMode::HalfEven) == Cents(52));
+	assert!(Cents(-250).percentage(21.0, RoundingMode::HalfAwayFromZero) == Cents(-53));
+	assert!(Cents(10000).add_percentage(9.0, RoundingMode::HalfAwayFromZero) == Cents(10900));
+	assert!(Cents(1000).mul_f64(0.333, RoundingMode::HalfAwayFromZero) == Cents(333));
+	assert!(Cents(1000).div_f64(3.0, RoundingMode::Up) == Cents(334));
+	assert!(Cents(1000).div_f64(3.0, RoundingMode::Down) == Cents(333));
+	assert!(Cents(100) * 3 - Cents(50) == Cents(250));
+	assert!(Cents::from_f64(12.345, RoundingMode::HalfAwayFromZero) == Cents(1235));
+	assert!(Cents::from_f64(-0.1 - 0.2, RoundingMode::HalfAwayFromZero) == Cents(-30));
+}
@@ -0,0 +1,220 @@
+use super::types::Account;
+use super::types::Cents;
+use super::types::Mutation;
+use super::types::RoundingMode;
+
+/// An amount split in the part without VAT and the VAT itself.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct VatSplit {
+	/// The amount without VAT.
+	pub net: Cents,
+
+	/// The VAT.
+	pub vat: Cents,
+}
+
+impl VatSplit {
+	/// Get the amount including VAT.
+	pub fn gross(self) -> Cents {
+		self.net + self.vat
+	}
+}
+
+/// Split an amount including VAT in the net amount and the VAT.
+///
+/// The VAT is rounded to whole cents according to the rounding mode,
+/// and the remainder is assigned to the net amount so that the parts always add up to the gross amount.
+pub fn split_gross(gross: Cents, percentage: f64, rounding: RoundingMode) -> VatSplit {
+	let percentage = (percentage * 10_000.0).round() as i64;
+	let vat = rounding.div(i64::from(gross.total_cents()) * percentage, 100 * 10_000 + percentage);
+	let vat = Cents(vat as i32);
+	VatSplit {
+		net: gross - vat,
+		vat,
+	}
+}
+
+/// Compute the VAT over a net amount.
+///
+/// The VAT is rounded to whole cents according to the rounding mode.
+pub fn add_vat(net: Cents, percentage: f64, rounding: RoundingMode) -> VatSplit {
+	VatSplit {
+		net,
+		vat: net.percentage(percentage, rounding),
+	}
+}
+
+/// The net amount and VAT of the lines of an invoice with the same VAT percentage.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VatTotal {
+	/// The VAT percentage.
+	pub percentage: f64,
+
+	/// The total amount without VAT.
+	pub net: Cents,
+
+	/// The VAT over the net amount.
+	pub vat: Cents,
+}
+
+/// Compute the net amount and VAT per VAT percentage of the lines of an invoice.
+///
+/// Each line is a net amount in whole cents with its VAT percentage.
+/// If `per_line` is true, the VAT is computed for each line and added up,
+/// otherwise it is computed over the total per VAT percentage.
+/// The totals are sorted by VAT percentage.
+pub fn vat_totals<I>(lines: I, per_line: bool, rounding: RoundingMode) -> Vec<VatTotal>
+where
+	I: IntoIterator<Item = (Cents, f64)>,
+{
+	let mut totals: Vec<VatTotal> = Vec::new();
+	for (net, percentage) in lines {
+		let index = match totals.iter().position(|x| x.percentage == percentage) {
+			Some(index) => index,
+			None => {
+				totals.push(VatTotal { percentage, net: Cents(0), vat: Cents(0) });
+				totals.len() - 1
+			},
+		};
+		totals[index].net += net;
+		if per_line {
+			totals[index].vat += add_vat(net, percentage, rounding).vat;
+		}
+	}
+
+	if !per_line {
+		for total in &mut totals {
+			total.vat = add_vat(total.net, total.percentage, rounding).vat;
+		}
+	}
+	totals.sort_by(|a, b| a.percentage.total_cmp(&b.percentage));
+	totals
+}
+
+/// Create the mutations for an invoice.
+///
+/// The debitor account receives the total amount including VAT,
+/// the revenue account is credited with the net amount,
+/// and each VAT account is credited with the given amount of VAT.
+pub fn invoice_mutations<'a, I>(debitor: Account<'a>, revenue: Account<'a>, net: Cents, vat: I) -> Vec<Mutation<'a>>
+where
+	I: IntoIterator<Item = (Account<'a>, Cents)>,
+{
+	let vat = vat.into_iter();
+	let mut mutations = Vec::with_capacity(2 + vat.size_hint().0);
+	mutations.push(Mutation { amount: net, account: debitor });
+	mutations.push(Mutation { amount: -net, account: revenue });
+
+	for (account, amount) in vat {
+		mutations[0].amount += amount;
+		mutations.push(Mutation { amount: -amount, account });
+	}
+
+	mutations
+}
+
+/// Create the mutations for the payment of an invoice.
+///
+/// The bank account receives the paid amount and the debitor account is credited with the same amount.
+pub fn payment_mutations<'a>(bank: Account<'a>, debitor: Account<'a>, amount: Cents) -> Vec<Mutation<'a>> {
+	vec![
+		Mutation { amount, account: bank },
+		Mutation { amount: -amount, account: debitor },
+	]
+}
+
+#[cfg(test)]
+#[test]
+fn test_split_gross() {
+	use assert2::assert;
+
+	assert!(split_gross(Cents(12100), 21.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(10000), vat: Cents(2100) });
+	assert!(split_gross(Cents(1000), 21.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(826), vat: Cents(174) });
+	assert!(split_gross(Cents(1000), 9.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(917), vat: Cents(83) });
+	assert!(split_gross(Cents(-1000), 21.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(-826), vat: Cents(-174) });
+	assert!(split_gross(Cents(999), 0.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(999), vat: Cents(0) });
+	assert!(split_gross(Cents(1), 21.0, RoundingMode::HalfAwayFromZero).gross() == Cents(1));
+}
+
+#[cfg(test)]
+#[test]
+fn test_invoice_mutations() {
+	use assert2::assert;
+
+	let mutations = invoice_mutations(
+		Account::from_raw("debitors/acme"),
+		Account::from_raw("revenue"),
+		Cents(10000),
+		vec![
+			(Account::from_raw("vat/21"), Cents(1050)),
+			(Account::from_raw("vat/9"), Cents(450)),
+		],
+	);
+	assert!(mutations.len() == 4);
+	assert!(mutations[0].amount == Cents(11500));
+	assert!(mutations[1].amount == Cents(-10000));
+	assert!(mutations[2].amount == Cents(-1050));
+	assert!(mutations[3].amount == Cents(-450));
+	assert!(mutations.iter().map(|x| x.amount).sum::<Cents>() == Cents(0));
+}
+
+#[cfg(test)]
+#[test]
+fn test_invoice_mutations_negative() {
+	use assert2::assert;
+
+	// A credit invoice for hours that were invoiced too much.
+	let mutations = invoice_mutations(
+		Account::from_raw("debitors/acme"),
+		Account::from_raw("revenue"),
+		Cents(-10000),
+		vec![(Account::from_raw("vat/21"), Cents(-2100))],
+	);
+	assert!(mutations.len() == 3);
+	assert!(mutations[0].amount == Cents(-12100));
+	assert!(mutations[1].amount == Cents(10000));
+	assert!(mutations[2].amount == Cents(2100));
+	assert!(mutations.iter().map(|x| x.amount).sum::<Cents>() == Cents(0));
+}
+
+#[cfg(test)]
+#[test]
+fn test_add_vat_negative() {
+	use assert2::assert;
+
+	assert!(add_vat(Cents(-1000), 21.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(-1000), vat: Cents(-210) });
+	assert!(add_vat(Cents(-250), 9.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(-250), vat: Cents(-23) });
+	assert!(add_vat(Cents(250), 9.0, RoundingMode::HalfAwayFromZero) == VatSplit { net: Cents(250), vat: Cents(23) });
+}
+
+#[cfg(test)]
+#[test]
+fn test_payment_mutations() {
+	use assert2::assert;
+
+	let mutations = payment_mutations(Account::from_raw("bank"), Account::from_raw("debitors/acme"), Cents(12100));
+	assert!(mutations.len() == 2);
+	assert!(mutations[0].account == Account::from_raw("bank"));
+	assert!(mutations[0].amount == Cents(12100));
+	assert!(mutations[1].account == Account::from_raw("debitors/acme"));
+	assert!(mutations[1].amount == Cents(-12100));
+}
+
+#[cfg(test)]
+#[test]
+fn test_vat_totals() {
+	use assert2::assert;
+	let lines = [(Cents(1002), 21.0), (Cents(500), 9.0), (Cents(1002), 21.0)];
+
+	let totals = vat_totals(lines, false, RoundingMode::HalfAwayFromZero);
+	assert!(totals == [
+		VatTotal { percentage: 9.0, net: Cents(500), vat: Cents(45) },
+		VatTotal { percentage: 21.0, net: Cents(2004), vat: Cents(421) },
+	]);
+
+	let totals = vat_totals(lines, true, RoundingMode::HalfAwayFromZero);
+	assert!(totals == [
+		VatTotal { percentage: 9.0, net: Cents(500), vat: Cents(45) },
+		VatTotal { percentage: 21.0, net: Cents(2004), vat: Cents(420) },
+	]);
+}
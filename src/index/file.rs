@@ -0,0 +1,184 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use gregorian::Date;
+
+use super::{hash, FileKind, Index};
+
+/// The contents of an indexed file, or the part of it with the records of a period.
+#[derive(Debug, Clone)]
+pub struct IndexedData {
+	/// The data that was read from the file.
+	pub data: String,
+
+	/// The line number of the start of the data in the file, starting at 1.
+	pub line: usize,
+}
+
+/// Get the path of the index of a file.
+///
+/// This is the path of the file with `.index` appended, like `grootboek.txt.index`.
+pub fn index_path(path: impl AsRef<Path>) -> PathBuf {
+	let mut index_path = path.as_ref().as_os_str().to_owned();
+	index_path.push(".index");
+	index_path.into()
+}
+
+/// Build the index of a file and write it next to the file.
+pub fn write_index(path: impl AsRef<Path>, kind: FileKind) -> Result<Index, IndexError> {
+	let path = path.as_ref();
+	let metadata = std::fs::metadata(path)
+		.map_err(|e| IndexError::Read(path.into(), e))?;
+	let data = std::fs::read_to_string(path)
+		.map_err(|e| IndexError::Read(path.into(), e))?;
+	let mut index = Index::build(kind, &data);
+	index.modified = modified_nanos(&metadata);
+
+	let index_path = index_path(path);
+	std::fs::write(&index_path, index.to_string())
+		.map_err(|e| IndexError::Write(index_path, e))?;
+	Ok(index)
+}
+
+/// Read the records from `start` until but not including `end` from a file with an index.
+///
+/// Returns `None` if the file has no index, or if its records are not sorted by date.
+/// Then the whole file must be read instead.
+/// If the records are not sorted but the file was already read to update the index, the whole file is returned.
+/// The returned records may still be outside of the period in that case.
+///
+/// If the size or the modification time of the file changed, the file is read completely to check the hash of the contents.
+/// If the contents changed, the index is rebuilt.
+/// The index is only a cache, so failing to write the updated index is not an error.
+pub fn read_period(path: impl AsRef<Path>, kind: FileKind, start: Option<Date>, end: Option<Date>) -> Result<Option<IndexedData>, IndexError> {
+	let path = path.as_ref();
+	let index_path = index_path(path);
+	let index = match std::fs::read_to_string(&index_path) {
+		Ok(data) => data,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+		Err(e) => return Err(IndexError::Read(index_path, e)),
+	};
+	// An invalid index is rebuilt, just like a stale one.
+	let index: Option<Index> = index.parse().ok().filter(|x: &Index| x.kind == kind);
+
+	// Get the metadata before reading the file, so that a concurrent change makes the index stale.
+	let metadata = std::fs::metadata(path)
+		.map_err(|e| IndexError::Read(path.into(), e))?;
+	let modified = modified_nanos(&metadata);
+
+	if let Some(index) = &index {
+		if index.size == metadata.len() && modified.is_some() && index.modified == modified {
+			let span = match index.span(start, end) {
+				Some(span) => span,
+				None => return Ok(None),
+			};
+			let mut file = std::fs::File::open(path)
+				.map_err(|e| IndexError::Read(path.into(), e))?;
+			let mut data = String::new();
+			file.seek(SeekFrom::Start(span.bytes.start))
+				.and_then(|_| file.take(span.bytes.end - span.bytes.start).read_to_string(&mut data))
+				.map_err(|e| IndexError::Read(path.into(), e))?;
+			return Ok(Some(IndexedData { data, line: span.line }));
+		}
+	}
+
+	let mut data = std::fs::read_to_string(path)
+		.map_err(|e| IndexError::Read(path.into(), e))?;
+	let mut index = match index {
+		Some(index) if index.size == data.len() as u64 && index.hash == hash(data.as_bytes()) => index,
+		_ => Index::build(kind, &data),
+	};
+	index.modified = modified;
+	std::fs::write(&index_path, index.to_string()).ok();
+
+	match index.span(start, end) {
+		Some(span) => {
+			data.truncate(span.bytes.end as usize);
+			data.drain(..span.bytes.start as usize);
+			Ok(Some(IndexedData { data, line: span.line }))
+		},
+		None => Ok(Some(IndexedData { data, line: 1 })),
+	}
+}
+
+/// Read the records of a period from a file with an index without blocking the async executor.
+///
+/// The file is read on the blocking thread pool of tokio, see [`read_period`].
+#[cfg(feature = "tokio")]
+pub async fn read_period_async(path: impl Into<PathBuf>, kind: FileKind, start: Option<Date>, end: Option<Date>) -> Result<Option<IndexedData>, IndexError> {
+	let path = path.into();
+	tokio::task::spawn_blocking(move || read_period(path, kind, start, end)).await
+		.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Get the modification time of a file in nanoseconds since the unix epoch.
+fn modified_nanos(metadata: &std::fs::Metadata) -> Option<u128> {
+	let modified = metadata.modified().ok()?;
+	let modified = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+	Some(modified.as_nanos())
+}
+
+#[derive(Debug)]
+pub enum IndexError {
+	Read(PathBuf, std::io::Error),
+	Write(PathBuf, std::io::Error),
+}
+
+impl std::error::Error for IndexError {}
+
+impl std::fmt::Display for IndexError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Read(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+			Self::Write(path, e) => write!(f, "failed to write {}: {}", path.display(), e),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_read_period() {
+	use assert2::assert;
+
+	let path = std::env::temp_dir().join(format!("zzp-test-index-{}.uurlog", std::process::id()));
+	let date = |day| Date::new(2024, gregorian::Month::January, day).unwrap();
+	std::fs::write(&path, "2024-01-01, 1h, First\n2024-01-02, 1h, Second\n").unwrap();
+
+	// Without an index, the whole file must be read.
+	assert!(let Ok(None) = read_period(&path, FileKind::Uurlog, Some(date(2)), None));
+
+	write_index(&path, FileKind::Uurlog).unwrap();
+	let indexed = read_period(&path, FileKind::Uurlog, Some(date(2)), None).unwrap().unwrap();
+	assert!(indexed.data == "2024-01-02, 1h, Second\n");
+	assert!(indexed.line == 2);
+
+	// A changed file makes the index stale, so it is rebuilt.
+	std::fs::write(&path, "2024-01-01, 1h, First\n2024-01-01, 2h, Extra\n2024-01-02, 1h, Second\n2024-01-03, 1h, Third\n").unwrap();
+	let indexed = read_period(&path, FileKind::Uurlog, Some(date(2)), Some(date(3))).unwrap().unwrap();
+	assert!(indexed.data == "2024-01-02, 1h, Second\n");
+	assert!(indexed.line == 3);
+	let index: Index = std::fs::read_to_string(index_path(&path)).unwrap().parse().unwrap();
+	assert!(index.points.len() == 3);
+
+	std::fs::remove_file(index_path(&path)).unwrap();
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(test, feature = "tokio"))]
+#[test]
+fn test_read_period_async() {
+	use assert2::assert;
+
+	let path = std::env::temp_dir().join(format!("zzp-test-index-async-{}.uurlog", std::process::id()));
+	let date = |day| Date::new(2024, gregorian::Month::January, day).unwrap();
+	std::fs::write(&path, "2024-01-01, 1h, First\n2024-01-02, 1h, Second\n").unwrap();
+	write_index(&path, FileKind::Uurlog).unwrap();
+
+	let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+	let indexed = runtime.block_on(read_period_async(&path, FileKind::Uurlog, Some(date(2)), None)).unwrap().unwrap();
+	assert!(indexed.data == "2024-01-02, 1h, Second\n");
+	assert!(indexed.line == 2);
+
+	std::fs::remove_file(index_path(&path)).unwrap();
+	std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,330 @@
+//! Sidecar index files for fast period queries on large grootboek files and hour logs.
+//!
+//! An index records the byte offset of the first transaction or entry of each date in a file.
+//! If the records in the file are sorted by date, the records of a period can be read without parsing the whole file.
+//!
+//! The index also records the size, the modification time and a hash of the indexed file.
+//! A file with a different size or modification time is hashed again, and the index is rebuilt if the contents changed.
+
+use gregorian::Date;
+use std::ops::Range;
+
+#[cfg(feature = "system")]
+mod file;
+#[cfg(feature = "system")]
+pub use file::*;
+
+/// The first line of an index file, with the version of the format.
+const HEADER: &str = "# zzp index v1";
+
+/// The kind of file that is indexed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FileKind {
+	/// A grootboek file with transactions.
+	Grootboek,
+
+	/// An hour log with one entry per line.
+	Uurlog,
+}
+
+/// An index of the dates in a grootboek file or hour log.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Index {
+	/// The kind of the indexed file.
+	pub kind: FileKind,
+
+	/// The size of the indexed file in bytes.
+	pub size: u64,
+
+	/// The modification time of the indexed file in nanoseconds since the unix epoch, if known.
+	pub modified: Option<u128>,
+
+	/// The hash of the contents of the indexed file, see [`hash`].
+	pub hash: u64,
+
+	/// The records in the file are sorted by date and all have a valid date.
+	///
+	/// If not, the index can not be used to select a period and [`Index::points`] is empty.
+	pub sorted: bool,
+
+	/// The position of the first record of each date, in order of date.
+	pub points: Vec<IndexPoint>,
+}
+
+/// The position of the first record with a date.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IndexPoint {
+	/// The date of the record.
+	pub date: Date,
+
+	/// The byte offset of the start of the record.
+	pub offset: u64,
+
+	/// The line number of the start of the record, starting at 1.
+	pub line: usize,
+}
+
+/// A part of an indexed file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Span {
+	/// The byte range of the part in the file.
+	pub bytes: Range<u64>,
+
+	/// The line number of the start of the part, starting at 1.
+	pub line: usize,
+}
+
+impl Index {
+	/// Build the index of the contents of a file.
+	///
+	/// The modification time can not be known from the contents, so it is left empty.
+	pub fn build(kind: FileKind, data: &str) -> Self {
+		let mut points: Vec<IndexPoint> = Vec::new();
+		let mut sorted = true;
+		let mut in_transaction = false;
+		let mut offset = 0;
+
+		for (i, line) in data.split_inclusive('\n').enumerate() {
+			let start = offset;
+			offset += line.len();
+			let line = line.trim();
+
+			// Grootboek transactions start after an empty line and end at the next one.
+			// Only comments can appear between transactions.
+			let date = match kind {
+				FileKind::Grootboek => {
+					if line.is_empty() {
+						in_transaction = false;
+						continue;
+					}
+					if in_transaction || line.starts_with('#') {
+						continue;
+					}
+					in_transaction = true;
+					line.split(':').next().unwrap().trim()
+				},
+				FileKind::Uurlog => {
+					if line.is_empty() || line.starts_with('#') {
+						continue;
+					}
+					line.split(',').next().unwrap().trim()
+				},
+			};
+
+			let date: Date = match date.parse() {
+				Ok(date) => date,
+				Err(_) => {
+					sorted = false;
+					break;
+				},
+			};
+			match points.last() {
+				Some(last) if last.date == date => (),
+				Some(last) if last.date > date => {
+					sorted = false;
+					break;
+				},
+				_ => points.push(IndexPoint { date, offset: start as u64, line: i + 1 }),
+			}
+		}
+
+		if !sorted {
+			points.clear();
+		}
+
+		Self {
+			kind,
+			size: data.len() as u64,
+			modified: None,
+			hash: hash(data.as_bytes()),
+			sorted,
+			points,
+		}
+	}
+
+	/// Find the part of the file with the records from `start` until but not including `end`.
+	///
+	/// Returns `None` if the records in the file are not sorted by date.
+	pub fn span(&self, start: Option<Date>, end: Option<Date>) -> Option<Span> {
+		if !self.sorted {
+			return None;
+		}
+
+		let find = |date: Option<Date>| date.and_then(|date| self.points.iter().find(|x| x.date >= date));
+		let (start_offset, line) = match (start, find(start)) {
+			(None, _) => (0, 1),
+			(Some(_), Some(point)) => (point.offset, point.line),
+			(Some(_), None) => (self.size, 1),
+		};
+		let end_offset = match (end, find(end)) {
+			(Some(_), Some(point)) => point.offset,
+			_ => self.size,
+		};
+
+		Some(Span {
+			bytes: start_offset..end_offset.max(start_offset),
+			line,
+		})
+	}
+}
+
+/// Compute the 64 bit FNV-1a hash of the contents of a file.
+pub fn hash(data: &[u8]) -> u64 {
+	let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+	for &byte in data {
+		hash ^= u64::from(byte);
+		hash = hash.wrapping_mul(0x0100_0000_01b3);
+	}
+	hash
+}
+
+impl std::fmt::Display for FileKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Grootboek => write!(f, "grootboek"),
+			Self::Uurlog => write!(f, "uurlog"),
+		}
+	}
+}
+
+impl std::str::FromStr for FileKind {
+	type Err = ParseIndexError;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		match data {
+			"grootboek" => Ok(Self::Grootboek),
+			"uurlog" => Ok(Self::Uurlog),
+			_ => Err(ParseIndexError::InvalidField("kind")),
+		}
+	}
+}
+
+impl std::fmt::Display for Index {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		writeln!(f, "{}", HEADER)?;
+		writeln!(f, "kind {}", self.kind)?;
+		writeln!(f, "size {}", self.size)?;
+		if let Some(modified) = self.modified {
+			writeln!(f, "modified {}", modified)?;
+		}
+		writeln!(f, "hash {:016x}", self.hash)?;
+		writeln!(f, "sorted {}", self.sorted)?;
+		for point in &self.points {
+			writeln!(f, "{} {} {}", point.date, point.offset, point.line)?;
+		}
+		Ok(())
+	}
+}
+
+impl std::str::FromStr for Index {
+	type Err = ParseIndexError;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		let mut lines = data.lines();
+		if lines.next() != Some(HEADER) {
+			return Err(ParseIndexError::UnsupportedVersion);
+		}
+
+		let mut kind = None;
+		let mut size = None;
+		let mut modified = None;
+		let mut hash = None;
+		let mut sorted = None;
+		let mut points = Vec::new();
+		for line in lines {
+			let (key, value) = line.split_once(' ').ok_or(ParseIndexError::InvalidLine)?;
+			match key {
+				"kind" => kind = Some(value.parse()?),
+				"size" => size = Some(value.parse().map_err(|_| ParseIndexError::InvalidField("size"))?),
+				"modified" => modified = Some(value.parse().map_err(|_| ParseIndexError::InvalidField("modified"))?),
+				"hash" => hash = Some(u64::from_str_radix(value, 16).map_err(|_| ParseIndexError::InvalidField("hash"))?),
+				"sorted" => sorted = Some(value.parse().map_err(|_| ParseIndexError::InvalidField("sorted"))?),
+				date => {
+					let mut fields = value.split(' ');
+					let offset = fields.next().and_then(|x| x.parse().ok());
+					let line = fields.next().and_then(|x| x.parse().ok());
+					match (date.parse(), offset, line, fields.next()) {
+						(Ok(date), Some(offset), Some(line), None) => points.push(IndexPoint { date, offset, line }),
+						_ => return Err(ParseIndexError::InvalidLine),
+					}
+				},
+			}
+		}
+
+		Ok(Self {
+			kind: kind.ok_or(ParseIndexError::MissingField("kind"))?,
+			size: size.ok_or(ParseIndexError::MissingField("size"))?,
+			modified,
+			hash: hash.ok_or(ParseIndexError::MissingField("hash"))?,
+			sorted: sorted.ok_or(ParseIndexError::MissingField("sorted"))?,
+			points,
+		})
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseIndexError {
+	UnsupportedVersion,
+	InvalidLine,
+	InvalidField(&'static str),
+	MissingField(&'static str),
+}
+
+impl std::error::Error for ParseIndexError {}
+
+impl std::fmt::Display for ParseIndexError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::UnsupportedVersion => write!(f, "unsupported index version"),
+			Self::InvalidLine => write!(f, "invalid line in index"),
+			Self::InvalidField(name) => write!(f, "invalid {} in index", name),
+			Self::MissingField(name) => write!(f, "missing {} in index", name),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_grootboek_index() {
+	use assert2::assert;
+	use crate::grootboek::Transaction;
+
+	let data = "# Comment\n2024-01-01: First\n+1.00 a\n-1.00 b\n\n2024-01-01: Second\n+1.00 a\n-1.00 b\n\n# Comment\n\n2024-02-01: Third\n\t# 2023-01-01: not a transaction\n+1.00 a\n-1.00 b\n";
+	let index = Index::build(FileKind::Grootboek, data);
+	assert!(index.sorted);
+	assert!(index.points.len() == 2);
+	assert!(index.points[1].line == 12);
+	assert!(index.to_string().parse() == Ok(index.clone()));
+
+	let date = |month| Date::new(2024, month, 1).unwrap();
+	let span = index.span(Some(date(gregorian::Month::February)), None).unwrap();
+	assert!(span.line == 12);
+	let transactions = Transaction::parse_from_str(&data[span.bytes.start as usize..span.bytes.end as usize]).unwrap();
+	assert!(transactions.len() == 1);
+	assert!(transactions[0].description == "Third");
+
+	let span = index.span(None, Some(date(gregorian::Month::February))).unwrap();
+	let transactions = Transaction::parse_from_str(&data[span.bytes.start as usize..span.bytes.end as usize]).unwrap();
+	assert!(transactions.len() == 2);
+
+	let span = index.span(Some(date(gregorian::Month::March)), None).unwrap();
+	assert!(span.bytes == (data.len() as u64..data.len() as u64));
+
+	let unsorted = Index::build(FileKind::Grootboek, "2024-02-01: Second\n+1.00 a\n-1.00 b\n\n2024-01-01: First\n+1.00 a\n-1.00 b\n");
+	assert!(!unsorted.sorted);
+	assert!(unsorted.span(None, None).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_uurlog_index() {
+	use assert2::assert;
+
+	let data = "2024-01-01, 1h, First\n# 2025-01-01, 1h, Comment\n2024-01-02, 1h, Second\n2024-01-02, 1h, Third\n";
+	let index = Index::build(FileKind::Uurlog, data);
+	assert!(index.sorted);
+	assert!(index.points.len() == 2);
+	let span = index.span(Some(Date::new(2024, gregorian::Month::January, 2).unwrap()), None).unwrap();
+	assert!(span.line == 3);
+	assert!(&data[span.bytes.start as usize..] == "2024-01-02, 1h, Second\n2024-01-02, 1h, Third\n");
+}
@@ -1,8 +1,8 @@
 pub use gregorian;
 
+pub mod calendar;
+pub mod date_range;
 pub mod grootboek;
+pub mod index;
 pub mod partial_date;
 pub mod uurlog;
-
-#[cfg(feature = "invoice")]
-pub mod invoice;
@@ -1,39 +1,74 @@
 use gregorian::{Date, Year, Month, YearMonth, InvalidDate};
 use std::ops::Range;
 
+use crate::calendar;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PartialDate {
 	Year(Year),
+	YearHalf(Year, u8),
+	YearQuarter(Year, u8),
 	YearMonth(YearMonth),
+	/// An ISO 8601 week, given by the Monday of the week.
+	YearWeek(Date),
 	YearMonthDay(Date),
 }
 
 impl PartialDate {
+	/// Resolve a relative period like `today` or `last-month` against the current date.
+	///
+	/// Supported are `today`, `yesterday` and `this-` or `last-` followed by `week`, `month`, `quarter` or `year`.
+	/// Returns `None` if the input is not a relative period.
+	pub fn relative(input: &str, today: Date) -> Option<Self> {
+		let quarter = (today.month().to_number() - 1) / 3 + 1;
+		let date = match input {
+			"today" => Self::YearMonthDay(today),
+			"yesterday" => Self::YearMonthDay(today.prev()),
+			"this-week" => Self::YearWeek(calendar::week_start(today)),
+			"last-week" => Self::YearWeek(calendar::week_start(calendar::week_start(today).prev())),
+			"this-month" => Self::YearMonth(today.year_month()),
+			"last-month" => Self::YearMonth(today.year_month().prev()),
+			"this-quarter" => Self::YearQuarter(today.year(), quarter),
+			"last-quarter" if quarter == 1 => Self::YearQuarter(today.year().prev(), 4),
+			"last-quarter" => Self::YearQuarter(today.year(), quarter - 1),
+			"this-year" => Self::Year(today.year()),
+			"last-year" => Self::Year(today.year().prev()),
+			_ => return None,
+		};
+		Some(date)
+	}
+
 	/// Interpret the partial date as start date.
 	///
-	/// This gives the first day of a year or month if more specific fields are not given.
+	/// This gives the first day of a year, half year, quarter, month or week if more specific fields are not given.
 	pub fn as_start_date(self) -> Date {
 		match self {
 			Self::Year(x) => x.first_day(),
+			Self::YearHalf(year, half) => half_first_month(year, half).first_day(),
+			Self::YearQuarter(year, quarter) => quarter_first_month(year, quarter).first_day(),
 			Self::YearMonth(x) => x.first_day(),
+			Self::YearWeek(x) => x,
 			Self::YearMonthDay(x) => x,
 		}
 	}
 
 	/// Interpret the partial date as an end date.
 	///
-	/// This gives the last day of a year or month if more specific fields are not given.
+	/// This gives the last day of a year, half year, quarter, month or week if more specific fields are not given.
 	pub fn as_end_date(self) -> Date {
 		match self {
 			Self::Year(x) => x.last_day(),
+			Self::YearHalf(year, half) => half_first_month(year, half).next().next().next().next().next().last_day(),
+			Self::YearQuarter(year, quarter) => quarter_first_month(year, quarter).next().next().last_day(),
 			Self::YearMonth(x) => x.last_day(),
+			Self::YearWeek(x) => calendar::add_days(x, 6),
 			Self::YearMonthDay(x) => x,
 		}
 	}
 
 	/// Interpret the partial date as a half-open date range.
 	///
-	/// This gives a date range comprising of the entire year or month,
+	/// This gives a date range comprising of the entire year, half year, quarter, month or week,
 	/// or just a single day.
 	pub fn as_range(self) -> Range<Date> {
 		match self {
@@ -41,10 +76,22 @@ impl PartialDate {
 				start: x.first_day(),
 				end: x.next().first_day(),
 			},
+			Self::YearHalf(year, half) => Range {
+				start: half_first_month(year, half).first_day(),
+				end: half_first_month(year, half).next().next().next().next().next().next().first_day(),
+			},
+			Self::YearQuarter(year, quarter) => Range {
+				start: quarter_first_month(year, quarter).first_day(),
+				end: quarter_first_month(year, quarter).next().next().next().first_day(),
+			},
 			Self::YearMonth(x) => Range {
 				start: x.first_day(),
 				end: x.next().first_day(),
 			},
+			Self::YearWeek(x) => Range {
+				start: x,
+				end: calendar::add_days(x, 7),
+			},
 			Self::YearMonthDay(x) => Range {
 				start: x,
 				end: x.next(),
@@ -53,10 +100,19 @@ impl PartialDate {
 	}
 }
 
+/// Parse a partial date like `2024`, `2024-Q2`, `2024-W05` or `2024-05-31`.
+///
+/// With the `system` feature, relative periods like `last-month` are resolved against the current date.
+/// Without it, use [`PartialDate::relative`] with a date from elsewhere.
 impl std::str::FromStr for PartialDate {
 	type Err = ParsePartialDateError;
 
 	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		#[cfg(feature = "system")]
+		if let Some(date) = Self::relative(data, Date::today()) {
+			return Ok(date);
+		}
+
 		let mut fields = data.splitn(3, '-');
 		let year = fields.next().unwrap();
 		let month = fields.next();
@@ -64,7 +120,36 @@ impl std::str::FromStr for PartialDate {
 
 		let year: i16 = year.parse().map_err(|_| InvalidPartialDateSyntax::new())?;
 
-		if let Some(month) = month {
+		if let Some(quarter) = month.and_then(|x| x.strip_prefix('Q').or_else(|| x.strip_prefix('q'))) {
+			if day.is_some() {
+				return Err(InvalidPartialDateSyntax::new().into());
+			}
+			let quarter: u8 = quarter.parse().map_err(|_| InvalidPartialDateSyntax::new())?;
+			if !(1..=4).contains(&quarter) {
+				return Err(InvalidPartialDateSyntax::new().into());
+			}
+			Ok(Self::YearQuarter(year.into(), quarter))
+		} else if let Some(half) = month.and_then(|x| x.strip_prefix('H').or_else(|| x.strip_prefix('h'))) {
+			if day.is_some() {
+				return Err(InvalidPartialDateSyntax::new().into());
+			}
+			let half: u8 = half.parse().map_err(|_| InvalidPartialDateSyntax::new())?;
+			if !(1..=2).contains(&half) {
+				return Err(InvalidPartialDateSyntax::new().into());
+			}
+			Ok(Self::YearHalf(year.into(), half))
+		} else if let Some(week) = month.and_then(|x| x.strip_prefix('W').or_else(|| x.strip_prefix('w'))) {
+			if day.is_some() {
+				return Err(InvalidPartialDateSyntax::new().into());
+			}
+			let week: u32 = week.parse().map_err(|_| InvalidPartialDateSyntax::new())?;
+			if week < 1 || week > calendar::weeks_in_year(year.into()) {
+				return Err(InvalidPartialDateSyntax::new().into());
+			}
+			// The first week of the year is the week with January 4th in it.
+			let first_week = calendar::week_start(Date::new(year, Month::January, 4)?);
+			Ok(Self::YearWeek(calendar::add_days(first_week, (week - 1) * 7)))
+		} else if let Some(month) = month {
 			let month: u8 = month.parse().map_err(|_| InvalidPartialDateSyntax::new())?;
 			let month = Month::new(month)?;
 			if let Some(day) = day {
@@ -79,6 +164,68 @@ impl std::str::FromStr for PartialDate {
 	}
 }
 
+impl std::fmt::Display for PartialDate {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Year(x) => write!(f, "{}", x),
+			Self::YearHalf(year, half) => write!(f, "{}-H{}", year, half),
+			Self::YearQuarter(year, quarter) => write!(f, "{}-Q{}", year, quarter),
+			Self::YearMonth(x) => write!(f, "{}", x),
+			Self::YearWeek(x) => {
+				let (year, week) = calendar::iso_week(*x);
+				write!(f, "{:04}-W{:02}", year, week)
+			},
+			Self::YearMonthDay(x) => write!(f, "{}", x),
+		}
+	}
+}
+
+impl std::cmp::Ord for PartialDate {
+	/// Partial dates are ordered by their first day, and then by their last day.
+	///
+	/// So a year comes before the first quarter of that year, which comes before the first month.
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		self.as_start_date().cmp(&other.as_start_date())
+			.then_with(|| other.as_end_date().cmp(&self.as_end_date()))
+	}
+}
+
+impl std::cmp::PartialOrd for PartialDate {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Partial dates are serialized as string, in the same syntax as they are parsed.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PartialDate {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+/// Relative periods like `last-month` are resolved against the current date when deserializing.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PartialDate {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		use serde::de::Error;
+		let data = String::deserialize(deserializer)?;
+		data.parse().map_err(|e| D::Error::custom(format!("invalid partial date {:?}: {}", data, e)))
+	}
+}
+
+/// Get the first month of a half year.
+fn half_first_month(year: Year, half: u8) -> YearMonth {
+	let month = Month::new((half - 1) * 6 + 1).unwrap();
+	YearMonth::new(year, month)
+}
+
+/// Get the first month of a quarter.
+fn quarter_first_month(year: Year, quarter: u8) -> YearMonth {
+	let month = Month::new((quarter - 1) * 3 + 1).unwrap();
+	YearMonth::new(year, month)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParsePartialDateError {
 	InvalidSyntax(InvalidPartialDateSyntax),
@@ -131,3 +278,93 @@ impl std::fmt::Display for InvalidPartialDateSyntax {
 		write!(f, "invalid syntax")
 	}
 }
+
+#[cfg(test)]
+#[test]
+fn test_parse_quarter() {
+	use assert2::assert;
+
+	let quarter: PartialDate = "2024-Q2".parse().unwrap();
+	assert!(quarter.as_start_date() == Date::new(2024, Month::April, 1).unwrap());
+	assert!(quarter.as_end_date() == Date::new(2024, Month::June, 30).unwrap());
+	assert!(quarter.as_range().end == Date::new(2024, Month::July, 1).unwrap());
+	assert!(let Ok(PartialDate::YearQuarter(_, 4)) = "2024-q4".parse::<PartialDate>());
+	assert!(let Err(_) = "2024-Q5".parse::<PartialDate>());
+	assert!(let Err(_) = "2024-Q1-01".parse::<PartialDate>());
+}
+
+#[cfg(test)]
+#[test]
+fn test_display() {
+	use assert2::assert;
+
+	for input in ["2024", "2024-H1", "2024-Q2", "2024-05", "2024-05-03"] {
+		let date: PartialDate = input.parse().unwrap();
+		assert!(date.to_string() == input);
+	}
+	assert!("2024-q3".parse::<PartialDate>().unwrap().to_string() == "2024-Q3");
+}
+
+#[cfg(test)]
+#[test]
+fn test_week() {
+	use assert2::assert;
+
+	let week: PartialDate = "2024-W01".parse().unwrap();
+	assert!(week.as_start_date() == Date::new(2024, Month::January, 1).unwrap());
+	assert!(week.as_end_date() == Date::new(2024, Month::January, 7).unwrap());
+	assert!(week.to_string() == "2024-W01");
+
+	// The first week of 2021 starts in 2021, the last week of 2020 ends in 2021.
+	let week: PartialDate = "2020-W53".parse().unwrap();
+	assert!(week.as_start_date() == Date::new(2020, Month::December, 28).unwrap());
+	assert!(week.as_range().end == Date::new(2021, Month::January, 4).unwrap());
+	assert!(let Err(_) = "2021-W53".parse::<PartialDate>());
+	assert!(let Err(_) = "2021-W00".parse::<PartialDate>());
+}
+
+#[cfg(test)]
+#[test]
+fn test_relative() {
+	use assert2::assert;
+
+	let today = Date::new(2024, Month::January, 17).unwrap();
+	let relative = |input| PartialDate::relative(input, today).unwrap().to_string();
+	assert!(relative("today") == "2024-01-17");
+	assert!(relative("yesterday") == "2024-01-16");
+	assert!(relative("this-week") == "2024-W03");
+	assert!(relative("last-week") == "2024-W02");
+	assert!(relative("this-month") == "2024-01");
+	assert!(relative("last-month") == "2023-12");
+	assert!(relative("this-quarter") == "2024-Q1");
+	assert!(relative("last-quarter") == "2023-Q4");
+	assert!(relative("this-year") == "2024");
+	assert!(relative("last-year") == "2023");
+	assert!(PartialDate::relative("2024-01", today).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_ord() {
+	use assert2::assert;
+
+	let parse = |input: &str| input.parse::<PartialDate>().unwrap();
+	let mut dates = [parse("2024-02"), parse("2024-01-01"), parse("2024-Q1"), parse("2023-12-31"), parse("2024"), parse("2024-01"), parse("2024-H1")];
+	dates.sort();
+	let dates: Vec<String> = dates.iter().map(|x| x.to_string()).collect();
+	assert!(dates == ["2023-12-31", "2024", "2024-H1", "2024-Q1", "2024-01", "2024-01-01", "2024-02"]);
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_half() {
+	use assert2::assert;
+
+	let half: PartialDate = "2024-H2".parse().unwrap();
+	assert!(half.as_start_date() == Date::new(2024, Month::July, 1).unwrap());
+	assert!(half.as_end_date() == Date::new(2024, Month::December, 31).unwrap());
+	assert!(half.as_range().end == Date::new(2025, Month::January, 1).unwrap());
+	assert!("2024-H1".parse::<PartialDate>().unwrap().as_end_date() == Date::new(2024, Month::June, 30).unwrap());
+	assert!(let Err(_) = "2024-H3".parse::<PartialDate>());
+	assert!(let Err(_) = "2024-H1-01".parse::<PartialDate>());
+}
@@ -21,19 +21,14 @@ impl Entry {
 		// Extract and trim fields.
 		let mut fields = data.splitn(3, ',');
 		let date = fields.next().unwrap().trim();
-		let hours = fields.next().ok_or(InvalidEntrySyntax::new(data))?.trim();
-		let mut description = fields.next().ok_or(InvalidEntrySyntax::new(data))?.trim();
+		let hours = fields.next().ok_or_else(|| InvalidEntrySyntax::new(data))?.trim();
+		let description = fields.next().ok_or_else(|| InvalidEntrySyntax::new(data))?.trim();
 
 		// Parse fields.
 		let date : Date =  date.parse()?;
 		let hours = Hours::from_str(hours)?;
 
-		let mut tags = Vec::new();
-		while description.starts_with('[') {
-			let end = description.find(']').ok_or_else(|| UnclosedTag { data: description.to_string() })?;
-			tags.push(description[1..end].to_string());
-			description = &description[end + 1..].trim();
-		}
+		let (tags, description) = split_tags(description)?;
 
 		Ok(Self {
 			date,
@@ -44,6 +39,20 @@ impl Entry {
 	}
 }
 
+/// Split the leading tags like `[one] [two]` from a description.
+///
+/// Returns the tags and the remaining description.
+pub fn split_tags(description: &str) -> Result<(Vec<String>, &str), UnclosedTag> {
+	let mut description = description.trim();
+	let mut tags = Vec::new();
+	while description.starts_with('[') {
+		let end = description.find(']').ok_or_else(|| UnclosedTag { data: description.to_string() })?;
+		tags.push(description[1..end].to_string());
+		description = description[end + 1..].trim();
+	}
+	Ok((tags, description))
+}
+
 impl std::fmt::Display for Entry {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		write!(f, "{}, {}, ", self.date, self.hours)?;
@@ -152,3 +161,14 @@ fn test_parse_not_ok() {
 	assert!(let Err(EntryParseError::DateParseError(_)) = Entry::from_str("when was this again?, 1h30m, swapping production and test environment"));
 	assert!(let Err(EntryParseError::HoursParseError(_)) = Entry::from_str("2020-01-01, 17hhh20mmm, wrokking onnnn new yeaarss *hiccup*"));
 }
+
+#[cfg(test)]
+#[test]
+fn test_split_tags() {
+	use assert2::assert;
+	let (tags, description) = split_tags("[one][two] [three] goofing around").unwrap();
+	assert!(tags == &["one", "two", "three"]);
+	assert!(description == "goofing around");
+	assert!(let Ok((_, "no tags")) = split_tags("no tags"));
+	assert!(let Err(_) = split_tags("[one] [two goofing around"));
+}
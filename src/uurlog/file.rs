@@ -0,0 +1,85 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+#[cfg(feature = "tokio")]
+use std::path::PathBuf;
+
+use super::{append_entries_to_string, parse_bytes, Entry, FileEntryParseError};
+
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Entry>, FileParseError> {
+	let data = std::fs::read(path)?;
+	parse_bytes(&data).map_err(|e| e.into())
+}
+
+/// Read and parse an hour log file without blocking the async executor.
+///
+/// The file is read and parsed on the blocking thread pool of tokio.
+#[cfg(feature = "tokio")]
+pub async fn parse_file_async(path: impl Into<PathBuf>) -> Result<Vec<Entry>, FileParseError> {
+	let path = path.into();
+	tokio::task::spawn_blocking(move || parse_file(path)).await
+		.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Append an entry to an hour log file.
+///
+/// The file is created if it does not exist yet.
+/// Trailing whitespace at the end of the file is removed before the new entry,
+/// and the file will end with a newline.
+pub fn append_entry(path: impl AsRef<Path>, entry: &Entry) -> std::io::Result<()> {
+	append_entries(path, std::slice::from_ref(entry))
+}
+
+/// Append entries to an hour log file.
+///
+/// See [`append_entry`] for details.
+pub fn append_entries(path: impl AsRef<Path>, entries: &[Entry]) -> std::io::Result<()> {
+	if entries.is_empty() {
+		return Ok(());
+	}
+
+	let mut file = std::fs::OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create(true)
+		.truncate(false)
+		.open(path)?;
+
+	let mut data = String::new();
+	file.read_to_string(&mut data)?;
+
+	// Only write the new data, so a failed write does not lose existing entries.
+	let keep = data.trim_end().len();
+	append_entries_to_string(&mut data, entries);
+	file.set_len(keep as u64)?;
+	file.seek(SeekFrom::End(0))?;
+	file.write_all(&data.as_bytes()[keep..])
+}
+
+#[derive(Debug)]
+pub enum FileParseError {
+	Io(std::io::Error),
+	Entry(FileEntryParseError)
+}
+
+impl std::error::Error for FileParseError {}
+
+impl From<std::io::Error> for FileParseError {
+	fn from(other: std::io::Error) -> Self {
+		Self::Io(other)
+	}
+}
+
+impl From<FileEntryParseError> for FileParseError {
+	fn from(other: FileEntryParseError) -> Self {
+		Self::Entry(other)
+	}
+}
+
+impl std::fmt::Display for FileParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Io(e) => write!(f, "{}", e),
+			Self::Entry(e) => write!(f, "{}", e),
+		}
+	}
+}
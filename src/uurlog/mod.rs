@@ -1,21 +1,18 @@
-use std::path::Path;
-
 mod hours;
 mod entry;
+#[cfg(feature = "system")]
+mod file;
 
 pub use hours::*;
 pub use entry::*;
-
-pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Entry>, FileParseError> {
-	let data = std::fs::read(path)?;
-	parse_bytes(&data).map_err(|e| e.into())
-}
+#[cfg(feature = "system")]
+pub use file::*;
 
 pub fn parse_bytes(data: &[u8]) -> Result<Vec<Entry>, FileEntryParseError> {
 	let mut result = Vec::new();
 
 	for (i, line) in data.split(|c| *c == b'\n').enumerate() {
-		let line = std::str::from_utf8(line).map_err(|_| FileEntryParseError::new(i, EntryParseError::InvalidUtf8))?;
+		let line = std::str::from_utf8(line).map_err(|_| FileEntryParseError::new(i + 1, EntryParseError::InvalidUtf8))?;
 		let line = line.trim();
 		if line.is_empty() || line.starts_with('#') {
 			continue;
@@ -28,10 +25,23 @@ pub fn parse_bytes(data: &[u8]) -> Result<Vec<Entry>, FileEntryParseError> {
 	Ok(result)
 }
 
-#[derive(Debug)]
-pub enum FileParseError {
-	Io(std::io::Error),
-	Entry(FileEntryParseError)
+/// Append entries to the contents of an hour log file.
+///
+/// Trailing whitespace at the end of the data is removed before the new entries,
+/// and the data will end with a newline.
+pub fn append_entries_to_string(data: &mut String, entries: &[Entry]) {
+	if entries.is_empty() {
+		return;
+	}
+
+	data.truncate(data.trim_end().len());
+	for entry in entries {
+		if !data.is_empty() {
+			data.push('\n');
+		}
+		data.push_str(&entry.to_string());
+	}
+	data.push('\n');
 }
 
 #[derive(Debug)]
@@ -46,30 +56,8 @@ impl FileEntryParseError {
 	}
 }
 
-impl std::error::Error for FileParseError {}
 impl std::error::Error for FileEntryParseError {}
 
-impl From<std::io::Error> for FileParseError {
-	fn from(other: std::io::Error) -> Self {
-		Self::Io(other)
-	}
-}
-
-impl From<FileEntryParseError> for FileParseError {
-	fn from(other: FileEntryParseError) -> Self {
-		Self::Entry(other)
-	}
-}
-
-impl std::fmt::Display for FileParseError {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		match self {
-			Self::Io(e) => write!(f, "{}", e),
-			Self::Entry(e) => write!(f, "{}", e),
-		}
-	}
-}
-
 impl std::fmt::Display for FileEntryParseError {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		write!(f, "on line {}: {}", self.line, self.error)
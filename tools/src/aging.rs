@@ -0,0 +1,64 @@
+//! Aging report of unpaid invoices.
+//!
+//! The aging report shows the outstanding amount of each customer,
+//! grouped by how many days the invoices are past their due date.
+
+use std::collections::BTreeMap;
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+
+use crate::register::{InvoiceRegister, InvoiceStatus, RegisteredInvoice};
+
+/// The columns of the aging report, by the number of days past the due date.
+pub const AGING_COLUMNS: [&str; 5] = ["current", "1-30", "31-60", "61-90", "90+"];
+
+/// The outstanding amounts of a customer in one currency.
+#[derive(Debug, Clone)]
+pub struct AgingLine {
+	/// The name of the customer.
+	pub customer: String,
+
+	/// The ISO 4217 currency code of the amounts.
+	pub currency: String,
+
+	/// The outstanding amount for each of the [`AGING_COLUMNS`].
+	pub amounts: [Cents; 5],
+}
+
+impl AgingLine {
+	/// Get the total outstanding amount of the customer.
+	pub fn total(&self) -> Cents {
+		self.amounts.iter().sum()
+	}
+}
+
+/// Get the index in [`AGING_COLUMNS`] for an invoice.
+pub fn aging_column(invoice: &RegisteredInvoice, today: Date) -> usize {
+	match invoice.days_overdue(today) {
+		0 => 0,
+		1..=30 => 1,
+		31..=60 => 2,
+		61..=90 => 3,
+		_ => 4,
+	}
+}
+
+/// Compute the aging report of the unpaid invoices in the register.
+///
+/// The lines are sorted by customer name.
+/// A customer with invoices in different currencies gets a line for each currency.
+pub fn aging_report(register: &InvoiceRegister, today: Date) -> Vec<AgingLine> {
+	let mut lines: BTreeMap<(&str, &str), [Cents; 5]> = BTreeMap::new();
+	for invoice in register.invoices().iter().filter(|x| x.status != InvoiceStatus::Paid) {
+		let amounts = lines.entry((&invoice.customer, &invoice.currency)).or_insert([Cents(0); 5]);
+		amounts[aging_column(invoice, today)] += invoice.total();
+	}
+
+	lines.into_iter()
+		.map(|((customer, currency), amounts)| AgingLine {
+			customer: customer.into(),
+			currency: currency.into(),
+			amounts,
+		})
+		.collect()
+}
@@ -0,0 +1,270 @@
+//! Parser for ISO 20022 CAMT.053 bank statements.
+//!
+//! Most Dutch banks (like Rabobank, ING and bunq) can export account statements in this format.
+
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+
+use super::BankTransaction;
+use super::xml::{self, Element};
+
+/// Parse a CAMT.053 bank statement.
+///
+/// Only booked entries are returned, pending entries are skipped.
+/// Batch entries with multiple transaction details are split into a transaction for each detail.
+pub fn parse(data: &str) -> Result<Vec<BankTransaction>, String> {
+	let document = xml::parse(data)?;
+	let statements = document.child("BkToCstmrStmt")
+		.ok_or("not a CAMT.053 statement: missing BkToCstmrStmt element")?;
+
+	let mut transactions = Vec::new();
+	for statement in statements.children("Stmt") {
+		for entry in statement.children("Ntry") {
+			let status = entry.text_at(&["Sts", "Cd"]).or_else(|| entry.text_at(&["Sts"]));
+			if status.map(|x| x != "BOOK").unwrap_or(false) {
+				continue;
+			}
+			parse_entry(entry, &mut transactions)?;
+		}
+	}
+
+	Ok(transactions)
+}
+
+/// Parse an entry and add the transactions to `output`.
+fn parse_entry(entry: &Element, output: &mut Vec<BankTransaction>) -> Result<(), String> {
+	let date = entry.text_at(&["BookgDt", "Dt"])
+		.or_else(|| entry.text_at(&["BookgDt", "DtTm"]))
+		.or_else(|| entry.text_at(&["ValDt", "Dt"]))
+		.ok_or("missing booking date of entry")?;
+	let date: Date = date.get(..10).unwrap_or(date).parse()
+		.map_err(|e| format!("invalid booking date {:?}: {}", date, e))?;
+
+	let entry_amount = parse_amount_element(entry.child("Amt").ok_or("missing amount of entry")?)?;
+	let entry_credit = parse_credit_debit(entry.text_at(&["CdtDbtInd"]))?;
+	let entry_reference = entry.text_at(&["AcctSvcrRef"]);
+	let entry_info = entry.text_at(&["AddtlNtryInf"]);
+
+	let details: Vec<&Element> = entry.children("NtryDtls")
+		.flat_map(|x| x.children("TxDtls"))
+		.collect();
+
+	if details.is_empty() {
+		let (amount, currency) = entry_amount;
+		output.push(BankTransaction {
+			date,
+			amount: if entry_credit { amount } else { -amount },
+			currency,
+			counterparty_name: None,
+			counterparty_iban: None,
+			description: entry_info.unwrap_or("").to_string(),
+			references: Vec::new(),
+			bank_reference: entry_reference.map(String::from),
+		});
+		return Ok(());
+	}
+
+	for (i, details_element) in details.iter().enumerate() {
+		let (amount, currency) = match details_element.child("Amt").or_else(|| details_element.path(&["AmtDtls", "TxAmt", "Amt"])) {
+			Some(x) => parse_amount_element(x)?,
+			None if details.len() == 1 => entry_amount.clone(),
+			None => return Err(format!("missing amount of transaction details in batch entry of {}", date)),
+		};
+		let credit = match details_element.text_at(&["CdtDbtInd"]) {
+			Some(x) => parse_credit_debit(Some(x))?,
+			None => entry_credit,
+		};
+
+		// The counterparty is the debtor of incoming payments and the creditor of outgoing payments.
+		let (party, account) = if credit { ("Dbtr", "DbtrAcct") } else { ("Cdtr", "CdtrAcct") };
+		let parties = details_element.child("RltdPties");
+		let counterparty_name = parties.and_then(|x| x.text_at(&[party, "Nm"]).or_else(|| x.text_at(&[party, "Pty", "Nm"])));
+		let counterparty_iban = parties.and_then(|x| x.text_at(&[account, "Id", "IBAN"]));
+
+		let remittance = details_element.child("RmtInf");
+		let unstructured: Vec<&str> = remittance.iter()
+			.flat_map(|x| x.children("Ustrd"))
+			.map(|x| x.text.trim())
+			.filter(|x| !x.is_empty())
+			.collect();
+		let description = if unstructured.is_empty() {
+			details_element.text_at(&["AddtlTxInf"]).or(entry_info).unwrap_or("").to_string()
+		} else {
+			unstructured.join(" ")
+		};
+
+		let mut references = Vec::new();
+		for structured in remittance.iter().flat_map(|x| x.children("Strd")) {
+			references.extend(structured.text_at(&["CdtrRefInf", "Ref"]).map(String::from));
+			references.extend(structured.children("RfrdDocInf").filter_map(|x| x.text_at(&["Nb"])).map(String::from));
+		}
+
+		// Prefer the unique reference of the bank, the end-to-end ID is not always unique.
+		let bank_reference = details_element.text_at(&["Refs", "AcctSvcrRef"]).map(String::from)
+			.or_else(|| match entry_reference {
+				Some(x) if details.len() > 1 => Some(format!("{}/{}", x, i + 1)),
+				Some(x) => Some(x.to_string()),
+				None => None,
+			})
+			.or_else(|| details_element.text_at(&["Refs", "EndToEndId"]).filter(|x| *x != "NOTPROVIDED").map(String::from));
+
+		output.push(BankTransaction {
+			date,
+			amount: if credit { amount } else { -amount },
+			currency,
+			counterparty_name: counterparty_name.map(String::from),
+			counterparty_iban: counterparty_iban.map(String::from),
+			description,
+			references,
+			bank_reference,
+		});
+	}
+
+	Ok(())
+}
+
+fn parse_credit_debit(indicator: Option<&str>) -> Result<bool, String> {
+	match indicator {
+		Some("CRDT") => Ok(true),
+		Some("DBIT") => Ok(false),
+		Some(x) => Err(format!("invalid credit/debit indicator {:?}", x)),
+		None => Err("missing credit/debit indicator".into()),
+	}
+}
+
+/// Parse an amount element with a currency attribute.
+fn parse_amount_element(element: &Element) -> Result<(Cents, String), String> {
+	let currency = element.attribute("Ccy").unwrap_or("EUR").to_string();
+	let amount = parse_amount(element.text.trim())?;
+	Ok((amount, currency))
+}
+
+/// Parse a decimal amount like `1234.5` into cents.
+pub(super) fn parse_amount(data: &str) -> Result<Cents, String> {
+	let error = || format!("invalid amount {:?}", data);
+	let (whole, fraction) = match data.split_once('.') {
+		Some((whole, fraction)) => (whole, fraction),
+		None => (data, ""),
+	};
+	if whole.is_empty() || !whole.bytes().all(|x| x.is_ascii_digit()) || !fraction.bytes().all(|x| x.is_ascii_digit()) {
+		return Err(error());
+	}
+	// Amounts are given in whole cents, but may have trailing zeroes.
+	if fraction.len() > 2 && fraction[2..].bytes().any(|x| x != b'0') {
+		return Err(error());
+	}
+	let whole: i32 = whole.parse().map_err(|_| error())?;
+	let cents: i32 = format!("{:0<2}", fraction.get(..2).unwrap_or(fraction)).parse().map_err(|_| error())?;
+	whole.checked_mul(100)
+		.and_then(|x| x.checked_add(cents))
+		.map(Cents)
+		.ok_or_else(error)
+}
+
+#[cfg(test)]
+fn test_statement(entries: &str) -> String {
+	format!(concat!(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+		"<Document xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.02\">\n",
+		"<BkToCstmrStmt><Stmt>\n",
+		"<Id>1</Id>\n",
+		"{}",
+		"</Stmt></BkToCstmrStmt>\n",
+		"</Document>\n",
+	), entries)
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+	use assert2::{assert, let_assert};
+
+	let data = test_statement(concat!(
+		"<Ntry>\n",
+		"  <Amt Ccy=\"EUR\">121.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts>BOOK</Sts>\n",
+		"  <BookgDt><Dt>2024-01-31</Dt></BookgDt><AcctSvcrRef>REF-1</AcctSvcrRef>\n",
+		"  <NtryDtls><TxDtls>\n",
+		"    <RltdPties><Dbtr><Nm>Customer &amp; Co</Nm></Dbtr><DbtrAcct><Id><IBAN>NL91ABNA0417164300</IBAN></Id></DbtrAcct></RltdPties>\n",
+		"    <RmtInf><Ustrd>Invoice</Ustrd><Ustrd>2024-001</Ustrd><Strd><CdtrRefInf><Ref>RF18539007547034</Ref></CdtrRefInf></Strd></RmtInf>\n",
+		"  </TxDtls></NtryDtls>\n",
+		"</Ntry>\n",
+		"<Ntry>\n",
+		"  <Amt Ccy=\"EUR\">10.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts>PDNG</Sts>\n",
+		"  <BookgDt><Dt>2024-02-01</Dt></BookgDt>\n",
+		"</Ntry>\n",
+		"<Ntry>\n",
+		"  <Amt Ccy=\"EUR\">30.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>\n",
+		"  <BookgDt><DtTm>2024-02-01T10:00:00</DtTm></BookgDt><AcctSvcrRef>BATCH</AcctSvcrRef>\n",
+		"  <NtryDtls>\n",
+		"    <TxDtls><Amt Ccy=\"EUR\">10.00</Amt><RltdPties><Cdtr><Nm>Supplier A</Nm></Cdtr></RltdPties></TxDtls>\n",
+		"    <TxDtls><Amt Ccy=\"EUR\">20.00</Amt><RltdPties><Cdtr><Pty><Nm>Supplier B</Nm></Pty></Cdtr></RltdPties></TxDtls>\n",
+		"  </NtryDtls>\n",
+		"</Ntry>\n",
+		"<Ntry>\n",
+		"  <Amt Ccy=\"EUR\">2.50</Amt><CdtDbtInd>DBIT</CdtDbtInd>\n",
+		"  <ValDt><Dt>2024-02-02</Dt></ValDt><AddtlNtryInf>Bank fees</AddtlNtryInf>\n",
+		"</Ntry>\n",
+	));
+	let_assert!(Ok(transactions) = parse(&data));
+	assert!(transactions.len() == 4);
+
+	let payment = &transactions[0];
+	assert!(payment.date == "2024-01-31".parse::<Date>().unwrap());
+	assert!(payment.amount == Cents(12100));
+	assert!(payment.currency == "EUR");
+	assert!(payment.counterparty_name.as_deref() == Some("Customer & Co"));
+	assert!(payment.counterparty_iban.as_deref() == Some("NL91ABNA0417164300"));
+	assert!(payment.description == "Invoice 2024-001");
+	assert!(payment.references == ["RF18539007547034"]);
+	assert!(payment.bank_reference.as_deref() == Some("REF-1"));
+
+	// Batch entries are split, with a reference for each transaction.
+	assert!(transactions[1].date == "2024-02-01".parse::<Date>().unwrap());
+	assert!(transactions[1].amount == Cents(-1000));
+	assert!(transactions[1].counterparty_name.as_deref() == Some("Supplier A"));
+	assert!(transactions[1].bank_reference.as_deref() == Some("BATCH/1"));
+	assert!(transactions[2].amount == Cents(-2000));
+	assert!(transactions[2].counterparty_name.as_deref() == Some("Supplier B"));
+	assert!(transactions[2].bank_reference.as_deref() == Some("BATCH/2"));
+
+	let fees = &transactions[3];
+	assert!(fees.date == "2024-02-02".parse::<Date>().unwrap());
+	assert!(fees.amount == Cents(-250));
+	assert!(fees.description == "Bank fees");
+	assert!(fees.counterparty_name.is_none());
+	assert!(fees.bank_reference.is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_malformed() {
+	use assert2::assert;
+
+	let entry = |contents: &str| test_statement(&format!("<Ntry>{}</Ntry>\n", contents));
+	assert!(let Err(_) = parse("<Document><Other/></Document>"));
+	assert!(let Err(_) = parse(&test_statement("<Ntry><Amt>1.00</Amt>\n")));
+	assert!(let Err(_) = parse(&entry("<Amt>1.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>")));
+	assert!(let Err(_) = parse(&entry("<Amt>1.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><BookgDt><Dt>2024-02-30</Dt></BookgDt>")));
+	assert!(let Err(_) = parse(&entry("<CdtDbtInd>CRDT</CdtDbtInd><BookgDt><Dt>2024-02-01</Dt></BookgDt>")));
+	assert!(let Err(_) = parse(&entry("<Amt>1,00</Amt><CdtDbtInd>CRDT</CdtDbtInd><BookgDt><Dt>2024-02-01</Dt></BookgDt>")));
+	assert!(let Err(_) = parse(&entry("<Amt>1.00</Amt><BookgDt><Dt>2024-02-01</Dt></BookgDt>")));
+	assert!(let Err(_) = parse(&entry("<Amt>1.00</Amt><CdtDbtInd>CREDIT</CdtDbtInd><BookgDt><Dt>2024-02-01</Dt></BookgDt>")));
+	assert!(let Err(_) = parse(&entry(concat!(
+		"<Amt>3.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><BookgDt><Dt>2024-02-01</Dt></BookgDt>",
+		"<NtryDtls><TxDtls><Amt>1.00</Amt></TxDtls><TxDtls></TxDtls></NtryDtls>",
+	))));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_amount() {
+	use assert2::assert;
+
+	assert!(parse_amount("0") == Ok(Cents(0)));
+	assert!(parse_amount("1234.5") == Ok(Cents(123450)));
+	assert!(parse_amount("12.340") == Ok(Cents(1234)));
+	assert!(let Err(_) = parse_amount("12.345"));
+	assert!(let Err(_) = parse_amount(".50"));
+	assert!(let Err(_) = parse_amount("-1.00"));
+	assert!(let Err(_) = parse_amount("99999999999"));
+}
@@ -0,0 +1,268 @@
+//! Import of bank statements into the grootboek.
+
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+
+use crate::register::{InvoiceRegister, InvoiceStatus, RegisteredInvoice};
+
+pub mod camt053;
+pub mod ofx;
+pub mod qif;
+pub mod rules;
+mod xml;
+
+/// The tag label with the unique reference of the bank, used to skip transactions that were already imported.
+pub const BANK_REFERENCE_TAG: &str = "bank-ref";
+
+/// The tag label with the IBAN of the counterparty of an imported transaction.
+pub const IBAN_TAG: &str = "iban";
+
+/// The file format of a bank statement.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StatementFormat {
+	/// An ISO 20022 CAMT.053 XML statement.
+	Camt053,
+
+	/// A Quicken Interchange Format file.
+	Qif,
+
+	/// An Open Financial Exchange file, version 1 (SGML) or 2 (XML).
+	Ofx,
+}
+
+impl StatementFormat {
+	/// All supported statement formats.
+	pub const ALL: [Self; 3] = [Self::Camt053, Self::Qif, Self::Ofx];
+
+	/// Detect the format of a bank statement from its contents.
+	pub fn detect(data: &str) -> Option<Self> {
+		let data = data.strip_prefix('\u{FEFF}').unwrap_or(data).trim_start();
+		if data.starts_with('!') {
+			Some(Self::Qif)
+		} else if data.starts_with("OFXHEADER") || data.contains("<OFX>") {
+			Some(Self::Ofx)
+		} else if data.contains("BkToCstmrStmt") {
+			Some(Self::Camt053)
+		} else {
+			None
+		}
+	}
+
+	/// Parse a bank statement in this format.
+	pub fn parse(self, data: &str) -> Result<Vec<BankTransaction>, String> {
+		match self {
+			Self::Camt053 => camt053::parse(data),
+			Self::Qif => qif::parse(data),
+			Self::Ofx => ofx::parse(data),
+		}
+	}
+}
+
+/// A transaction from a bank statement.
+#[derive(Debug, Clone)]
+pub struct BankTransaction {
+	/// The booking date of the transaction.
+	pub date: Date,
+
+	/// The amount of the transaction, positive for incoming and negative for outgoing payments.
+	pub amount: Cents,
+
+	/// The ISO 4217 currency code of the amount.
+	pub currency: String,
+
+	/// The name of the counterparty, if known.
+	pub counterparty_name: Option<String>,
+
+	/// The IBAN of the counterparty, if known.
+	pub counterparty_iban: Option<String>,
+
+	/// The unstructured remittance information, usually the description entered by the payer.
+	pub description: String,
+
+	/// The structured remittance information, like a payment reference or the number of the paid invoice.
+	pub references: Vec<String>,
+
+	/// The unique reference of the transaction from the bank, if any.
+	pub bank_reference: Option<String>,
+}
+
+impl BankTransaction {
+	/// Find the unpaid invoice that is paid by this transaction.
+	///
+	/// An invoice matches if its number appears in the structured references or the description,
+	/// and the amount of the transaction equals the total of the invoice.
+	pub fn find_invoice<'a>(&self, register: &'a InvoiceRegister) -> Option<&'a RegisteredInvoice> {
+		register.invoices().iter()
+			.filter(|x| x.status != InvoiceStatus::Paid && x.total() == self.amount)
+			.find(|invoice| {
+				self.references.iter().any(|x| x.trim() == invoice.number)
+					|| contains_word(&self.description, &invoice.number)
+			})
+	}
+
+	/// Get a single-line description for the grootboek, with the counterparty and the remittance information.
+	pub fn summary(&self) -> String {
+		let description = self.description.split_whitespace().collect::<Vec<_>>().join(" ");
+		match (&self.counterparty_name, description.is_empty()) {
+			(Some(name), false) => format!("{} - {}", name.trim(), description),
+			(Some(name), true) => name.trim().to_string(),
+			(None, false) => description,
+			(None, true) => String::from("Bank transaction"),
+		}
+	}
+}
+
+/// Parse a signed amount like `-1,234.56` or `1.234,56` into cents.
+///
+/// The last `.` or `,` is the decimal separator, other separators group thousands.
+/// A single comma followed by three digits also groups thousands.
+fn parse_signed_amount(data: &str) -> Result<Cents, String> {
+	let (negative, unsigned) = match data.trim().strip_prefix('-') {
+		Some(rest) => (true, rest),
+		None => (false, data.trim().strip_prefix('+').unwrap_or(data.trim())),
+	};
+	let unsigned: String = unsigned.chars().filter(|c| !c.is_whitespace()).collect();
+	let is_separator = |c: char| c == '.' || c == ',';
+	let decimal = unsigned.rfind(is_separator).filter(|&i| {
+		let separator = unsigned[i..].chars().next().unwrap();
+		let repeated = unsigned.matches(separator).count() > 1;
+		let thousands = separator == ',' && unsigned.len() - i == 4 && unsigned.matches(is_separator).count() == 1;
+		!repeated && !thousands
+	});
+	let normalized: String = match decimal {
+		Some(i) => format!("{}.{}", unsigned[..i].replace(['.', ','], ""), &unsigned[i + 1..]),
+		None => unsigned.replace(['.', ','], ""),
+	};
+	let amount = camt053::parse_amount(&normalized)
+		.map_err(|_| format!("invalid amount {:?}", data))?;
+	Ok(if negative { -amount } else { amount })
+}
+
+/// Check if `haystack` contains `needle` not directly surrounded by other letters or digits.
+fn contains_word(haystack: &str, needle: &str) -> bool {
+	if needle.is_empty() {
+		return false;
+	}
+	haystack.match_indices(needle).any(|(i, _)| {
+		let before = haystack[..i].chars().next_back();
+		let after = haystack[i + needle.len()..].chars().next();
+		!before.map(|x| x.is_alphanumeric()).unwrap_or(false) && !after.map(|x| x.is_alphanumeric()).unwrap_or(false)
+	})
+}
+
+impl std::fmt::Display for StatementFormat {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Camt053 => write!(f, "camt053"),
+			Self::Qif => write!(f, "qif"),
+			Self::Ofx => write!(f, "ofx"),
+		}
+	}
+}
+
+impl std::str::FromStr for StatementFormat {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		Self::ALL.into_iter()
+			.find(|x| x.to_string() == input)
+			.ok_or_else(|| format!("unknown statement format {:?}, expected camt053, qif or ofx", input))
+	}
+}
+
+#[cfg(test)]
+fn test_transaction(amount: i32, description: &str, references: &[&str]) -> BankTransaction {
+	BankTransaction {
+		date: "2024-02-10".parse().unwrap(),
+		amount: Cents(amount),
+		currency: "EUR".into(),
+		counterparty_name: Some(" Customer ".into()),
+		counterparty_iban: None,
+		description: description.into(),
+		references: references.iter().map(|x| x.to_string()).collect(),
+		bank_reference: None,
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_find_invoice() {
+	use assert2::assert;
+	use crate::register::test_invoice;
+
+	let mut paid = test_invoice("acme", None, Vec::new());
+	paid.number = "2024-002".into();
+	paid.status = InvoiceStatus::Paid;
+	let register = InvoiceRegister::from_invoices(vec![test_invoice("acme", None, Vec::new()), paid]);
+
+	let found = test_transaction(12100, "Payment of invoice 2024-001.", &[]).find_invoice(&register);
+	assert!(found.map(|x| x.number.as_str()) == Some("2024-001"));
+	let found = test_transaction(12100, "", &[" 2024-001 "]).find_invoice(&register);
+	assert!(found.map(|x| x.number.as_str()) == Some("2024-001"));
+
+	// The amount must match, the number must be a whole word and paid invoices are skipped.
+	assert!(test_transaction(12000, "invoice 2024-001", &[]).find_invoice(&register).is_none());
+	assert!(test_transaction(12100, "invoice 2024-0012", &[]).find_invoice(&register).is_none());
+	assert!(test_transaction(12100, "invoice 2024-002", &[]).find_invoice(&register).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_summary() {
+	use assert2::assert;
+
+	let mut transaction = test_transaction(100, "Invoice\n  2024-001 ", &[]);
+	assert!(transaction.summary() == "Customer - Invoice 2024-001");
+	transaction.description.clear();
+	assert!(transaction.summary() == "Customer");
+	transaction.counterparty_name = None;
+	assert!(transaction.summary() == "Bank transaction");
+	transaction.description = "Fees".into();
+	assert!(transaction.summary() == "Fees");
+}
+
+#[cfg(test)]
+#[test]
+fn test_contains_word() {
+	use assert2::assert;
+
+	assert!(contains_word("invoice 2024-001", "2024-001"));
+	assert!(contains_word("2024-001, thanks", "2024-001"));
+	assert!(!contains_word("invoice 2024-0010", "2024-001"));
+	assert!(!contains_word("invoiceX2024-001", "2024-001"));
+	assert!(!contains_word("invoice", ""));
+}
+
+#[cfg(test)]
+#[test]
+fn test_detect_format() {
+	use assert2::assert;
+
+	assert!(StatementFormat::detect("\u{FEFF}!Type:Bank\n") == Some(StatementFormat::Qif));
+	assert!(StatementFormat::detect("OFXHEADER:100\n") == Some(StatementFormat::Ofx));
+	assert!(StatementFormat::detect("<?xml version=\"1.0\"?>\n<OFX>\n") == Some(StatementFormat::Ofx));
+	assert!(StatementFormat::detect("<?xml version=\"1.0\"?>\n<Document><BkToCstmrStmt>") == Some(StatementFormat::Camt053));
+	assert!(StatementFormat::detect("date,amount\n").is_none());
+
+	for format in StatementFormat::ALL {
+		assert!(format.to_string().parse() == Ok(format));
+	}
+	assert!(let Err(_) = "csv".parse::<StatementFormat>());
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_signed_amount() {
+	use assert2::assert;
+
+	assert!(parse_signed_amount("1,234.56") == Ok(Cents(123456)));
+	assert!(parse_signed_amount("1.234,56") == Ok(Cents(123456)));
+	assert!(parse_signed_amount(" -12,50 ") == Ok(Cents(-1250)));
+	assert!(parse_signed_amount("+5") == Ok(Cents(500)));
+	assert!(parse_signed_amount("1,234") == Ok(Cents(123400)));
+	assert!(parse_signed_amount("1 234,00") == Ok(Cents(123400)));
+	assert!(parse_signed_amount("1.234.567") == Ok(Cents(123456700)));
+	assert!(let Err(_) = parse_signed_amount(""));
+	assert!(let Err(_) = parse_signed_amount("ten"));
+	assert!(let Err(_) = parse_signed_amount("--1"));
+}
@@ -0,0 +1,275 @@
+//! Parser for Open Financial Exchange (OFX) statements.
+//!
+//! Some banks and credit card companies export transactions in this format, also known as Microsoft Money or Quicken Web Connect files.
+//! Both the SGML based version 1 and the XML based version 2 are supported.
+
+use zzp::gregorian::{Date, Month};
+
+use super::{parse_signed_amount, BankTransaction};
+use super::xml::{self, Element};
+
+/// The message sets, responses and statements with transactions, for bank accounts and credit cards.
+const STATEMENTS: [[&str; 3]; 2] = [
+	["BANKMSGSRSV1", "STMTTRNRS", "STMTRS"],
+	["CREDITCARDMSGSRSV1", "CCSTMTTRNRS", "CCSTMTRS"],
+];
+
+/// Parse an OFX file with bank or credit card statements.
+///
+/// Transactions without a currency have the default currency of their statement.
+pub fn parse(data: &str) -> Result<Vec<BankTransaction>, String> {
+	let start = data.find("<OFX>")
+		.ok_or("not an OFX file: missing OFX element")?;
+	let root = parse_sgml(data, start)?;
+	let ofx = root.child("OFX")
+		.ok_or("not an OFX file: missing OFX element")?;
+
+	let mut transactions = Vec::new();
+	for [message_set, response, statement] in STATEMENTS {
+		let statements = ofx.children(message_set)
+			.flat_map(|x| x.children(response))
+			.flat_map(|x| x.children(statement));
+		for statement in statements {
+			let currency = statement.text_at(&["CURDEF"]).unwrap_or("EUR");
+			let entries = statement.children("BANKTRANLIST")
+				.flat_map(|x| x.children("STMTTRN"));
+			for entry in entries {
+				transactions.push(parse_transaction(entry, currency)?);
+			}
+		}
+	}
+
+	Ok(transactions)
+}
+
+/// Parse a `STMTTRN` element.
+fn parse_transaction(entry: &Element, default_currency: &str) -> Result<BankTransaction, String> {
+	let date = entry.text_at(&["DTPOSTED"])
+		.ok_or("missing posting date of transaction")?;
+	let date = parse_date(date)?;
+	let amount = entry.text_at(&["TRNAMT"])
+		.ok_or_else(|| format!("missing amount of transaction of {}", date))?;
+	let amount = parse_signed_amount(amount)?;
+
+	let currency = entry.text_at(&["CURRENCY", "CURSYM"])
+		.or_else(|| entry.text_at(&["ORIGCURRENCY", "CURSYM"]))
+		.unwrap_or(default_currency);
+	let counterparty_name = entry.text_at(&["NAME"])
+		.or_else(|| entry.text_at(&["PAYEE", "NAME"]));
+	// The account of the counterparty is only useful for the bank rules if it is an IBAN.
+	let counterparty_iban = entry.text_at(&["BANKACCTTO", "ACCTID"])
+		.filter(|x| x.len() > 4 && x[..2].bytes().all(|x| x.is_ascii_alphabetic()) && x[2..4].bytes().all(|x| x.is_ascii_digit()));
+
+	Ok(BankTransaction {
+		date,
+		amount,
+		currency: currency.to_string(),
+		counterparty_name: counterparty_name.map(String::from),
+		counterparty_iban: counterparty_iban.map(String::from),
+		description: entry.text_at(&["MEMO"]).unwrap_or("").to_string(),
+		references: entry.text_at(&["REFNUM"]).map(String::from).into_iter().collect(),
+		bank_reference: entry.text_at(&["FITID"]).map(String::from),
+	})
+}
+
+/// Parse a date time like `20240131`, `20240131120000` or `20240131120000.000[-5:EST]`.
+///
+/// Only the date is used: the time zone is ignored.
+fn parse_date(data: &str) -> Result<Date, String> {
+	let invalid = || format!("invalid date {:?}", data);
+	let date = data.get(..8).ok_or_else(invalid)?;
+	if !date.bytes().all(|x| x.is_ascii_digit()) {
+		return Err(invalid());
+	}
+	let year: i16 = date[0..4].parse().map_err(|_| invalid())?;
+	let month = Month::new(date[4..6].parse().map_err(|_| invalid())?).map_err(|_| invalid())?;
+	let day: u8 = date[6..8].parse().map_err(|_| invalid())?;
+	Date::new(year, month, day).map_err(|_| invalid())
+}
+
+/// Parse the elements of an OFX file, starting at byte `start`.
+///
+/// OFX version 1 is SGML, where elements with a value have no closing tag.
+/// An element followed by text is a value, an element followed by another tag contains other elements.
+/// This also parses the XML of OFX version 2, where the closing tags are given.
+///
+/// The returned element has no name and contains the top-level elements.
+fn parse_sgml(data: &str, start: usize) -> Result<Element, String> {
+	let error = |pos: usize, message: String| {
+		let line = data[..pos].matches('\n').count() + 1;
+		format!("line {}: {}", line, message)
+	};
+
+	let mut stack = vec![Element::default()];
+	let mut pos = start;
+	loop {
+		let rest = &data[pos..];
+		let trimmed = rest.trim_start();
+		pos += rest.len() - trimmed.len();
+		if trimmed.is_empty() {
+			break;
+		}
+		if !trimmed.starts_with('<') {
+			return Err(error(pos, format!("unexpected text {:?}", trimmed.lines().next().unwrap_or(""))));
+		}
+		if trimmed.starts_with("<!--") || trimmed.starts_with("<?") {
+			let terminator = if trimmed.starts_with("<?") { "?>" } else { "-->" };
+			let end = trimmed.find(terminator).ok_or_else(|| error(pos, format!("missing {:?}", terminator)))?;
+			pos += end + terminator.len();
+			continue;
+		}
+
+		let end = trimmed.find('>').ok_or_else(|| error(pos, "missing '>'".into()))?;
+		let tag = trimmed[1..end].trim();
+		pos += end + 1;
+
+		if let Some(name) = tag.strip_prefix('/') {
+			let name = name.trim();
+			// Close the element and any unclosed elements it contains.
+			let index = stack.iter().rposition(|x| x.name == name)
+				.filter(|&i| i > 0)
+				.ok_or_else(|| error(pos, format!("unexpected closing tag for {:?}", name)))?;
+			while stack.len() > index {
+				let element = stack.pop().unwrap();
+				stack.last_mut().unwrap().children.push(element);
+			}
+			continue;
+		}
+
+		if let Some(name) = tag.strip_suffix('/') {
+			let element = Element { name: name.trim().to_string(), ..Element::default() };
+			stack.last_mut().unwrap().children.push(element);
+			continue;
+		}
+
+		let name = tag.split_whitespace().next().unwrap_or("");
+		if name.is_empty() {
+			return Err(error(pos, "expected a tag name".into()));
+		}
+		let rest = &data[pos..];
+		let text_end = rest.find('<').unwrap_or(rest.len());
+		let text = rest[..text_end].trim();
+		if text.is_empty() {
+			stack.push(Element { name: name.to_string(), ..Element::default() });
+		} else {
+			pos += text_end;
+			let closing = format!("</{}>", name);
+			if data[pos..].starts_with(&closing) {
+				pos += closing.len();
+			}
+			let element = Element { name: name.to_string(), text: xml::unescape(text), ..Element::default() };
+			stack.last_mut().unwrap().children.push(element);
+		}
+	}
+
+	while stack.len() > 1 {
+		let element = stack.pop().unwrap();
+		stack.last_mut().unwrap().children.push(element);
+	}
+	Ok(stack.pop().unwrap())
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_sgml() {
+	use assert2::{assert, let_assert};
+	use zzp::grootboek::Cents;
+
+	let data = concat!(
+		"OFXHEADER:100\r\n",
+		"DATA:OFXSGML\r\n",
+		"VERSION:102\r\n",
+		"\r\n",
+		"<OFX>\r\n",
+		"<SIGNONMSGSRSV1><SONRS><STATUS><CODE>0<SEVERITY>INFO</STATUS></SONRS></SIGNONMSGSRSV1>\r\n",
+		"<BANKMSGSRSV1><STMTTRNRS><STMTRS><CURDEF>EUR\r\n",
+		"<BANKTRANLIST>\r\n",
+		"<DTSTART>20240101\r\n",
+		"<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240131120000.000[-5:EST]<TRNAMT>121.00<FITID>F1\r\n",
+		"<NAME>Customer &amp; Co<MEMO>Invoice 2024-001<REFNUM>2024-001\r\n",
+		"<BANKACCTTO><ACCTID>NL91ABNA0417164300</BANKACCTTO></STMTTRN>\r\n",
+		"<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240201<TRNAMT>-10.5<FITID>F2\r\n",
+		"<PAYEE><NAME>Shop</PAYEE><CURRENCY><CURSYM>USD</CURRENCY><BANKACCTTO><ACCTID>12345678</BANKACCTTO>\r\n",
+		"</STMTTRN>\r\n",
+		"</BANKTRANLIST>\r\n",
+		"</STMTRS></STMTTRNRS></BANKMSGSRSV1>\r\n",
+		"<CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS><CURDEF>USD\r\n",
+		"<BANKTRANLIST><STMTTRN><DTPOSTED>20240202<TRNAMT>-5.00<FITID>C1</BANKTRANLIST>\r\n",
+		"</CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>\r\n",
+		"</OFX>\r\n",
+	);
+	let_assert!(Ok(transactions) = parse(data));
+	assert!(transactions.len() == 3);
+
+	let payment = &transactions[0];
+	assert!(payment.date == Date::new(2024, zzp::gregorian::January, 31).unwrap());
+	assert!(payment.amount == Cents(12100));
+	assert!(payment.currency == "EUR");
+	assert!(payment.counterparty_name.as_deref() == Some("Customer & Co"));
+	assert!(payment.counterparty_iban.as_deref() == Some("NL91ABNA0417164300"));
+	assert!(payment.description == "Invoice 2024-001");
+	assert!(payment.references == ["2024-001"]);
+	assert!(payment.bank_reference.as_deref() == Some("F1"));
+
+	let purchase = &transactions[1];
+	assert!(purchase.amount == Cents(-1050));
+	assert!(purchase.currency == "USD");
+	assert!(purchase.counterparty_name.as_deref() == Some("Shop"));
+	assert!(purchase.counterparty_iban.is_none());
+
+	let card = &transactions[2];
+	assert!(card.date == Date::new(2024, zzp::gregorian::February, 2).unwrap());
+	assert!(card.amount == Cents(-500));
+	assert!(card.currency == "USD");
+	assert!(card.bank_reference.as_deref() == Some("C1"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_xml() {
+	use assert2::{assert, let_assert};
+	use zzp::grootboek::Cents;
+
+	let data = concat!(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+		"<?OFX OFXHEADER=\"200\" VERSION=\"220\"?>\n",
+		"<OFX>\n",
+		"  <!-- A statement. -->\n",
+		"  <BANKMSGSRSV1><STMTTRNRS><STMTRS><BANKTRANLIST>\n",
+		"    <STMTTRN><DTPOSTED>20240131</DTPOSTED><TRNAMT>1.234,50</TRNAMT><FITID>X1</FITID><MEMO>Salary</MEMO></STMTTRN>\n",
+		"    <STMTTRN><DTPOSTED>20240201</DTPOSTED><TRNAMT>-1</TRNAMT><MEMO/></STMTTRN>\n",
+		"  </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1>\n",
+		"</OFX>\n",
+	);
+	let_assert!(Ok(transactions) = parse(data));
+	assert!(transactions.len() == 2);
+	assert!(transactions[0].amount == Cents(123450));
+	assert!(transactions[0].currency == "EUR");
+	assert!(transactions[0].description == "Salary");
+	assert!(transactions[0].bank_reference.as_deref() == Some("X1"));
+	assert!(transactions[1].amount == Cents(-100));
+	assert!(transactions[1].description.is_empty());
+	assert!(transactions[1].bank_reference.is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_malformed() {
+	use assert2::{assert, let_assert};
+
+	let statement = |transaction: &str| format!("<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><BANKTRANLIST><STMTTRN>{}</STMTTRN></BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>", transaction);
+	assert!(let Err(_) = parse("OFXHEADER:100\n"));
+	assert!(let Ok(_) = parse(&statement("<DTPOSTED>20240131<TRNAMT>1.00")));
+	assert!(let Err(_) = parse(&statement("<TRNAMT>1.00")));
+	assert!(let Err(_) = parse(&statement("<DTPOSTED>2024013<TRNAMT>1.00")));
+	assert!(let Err(_) = parse(&statement("<DTPOSTED>20241301<TRNAMT>1.00")));
+	assert!(let Err(_) = parse(&statement("<DTPOSTED>20240131")));
+	assert!(let Err(_) = parse(&statement("<DTPOSTED>20240131<TRNAMT>ten")));
+
+	let_assert!(Err(error) = parse("<OFX>\n<BANKMSGSRSV1>\n</STMTRS>\n</OFX>"));
+	assert!(error == "line 3: unexpected closing tag for \"STMTRS\"");
+	let_assert!(Err(error) = parse("<OFX>\n<BANKMSGSRSV1\n"));
+	assert!(error == "line 2: missing '>'");
+	let_assert!(Err(error) = parse("<OFX>\n<BANKMSGSRSV1>\n</BANKMSGSRSV1>\ntext\n</OFX>"));
+	assert!(error == "line 4: unexpected text \"text\"");
+}
@@ -0,0 +1,296 @@
+//! Parser for Quicken Interchange Format (QIF) files.
+//!
+//! Some banks and most credit card companies can export transactions in this format.
+//! QIF has no unique transaction references, so a reference is derived from the contents of each transaction.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use zzp::gregorian::{Date, Month};
+
+use super::{parse_signed_amount, BankTransaction};
+
+/// The account types with transactions that can be imported.
+const ACCOUNT_TYPES: [&str; 5] = ["Bank", "CCard", "Cash", "Oth A", "Oth L"];
+
+/// Parse a QIF file.
+///
+/// Only the transactions of bank, credit card, cash and other asset or liability accounts are returned.
+/// Split lines are ignored: the whole amount of a transaction is returned.
+/// QIF files have no currency, so all amounts are in euros.
+///
+/// QIF does not specify the order of the day and the month in dates.
+/// If a date in the file can only be read one way, that order is used for all dates.
+/// Otherwise, dates separated by slashes are read as month/day/year, and other dates as day-month-year.
+pub fn parse(data: &str) -> Result<Vec<BankTransaction>, String> {
+	let data = data.strip_prefix('\u{FEFF}').unwrap_or(data);
+	let records = parse_records(data)?;
+
+	let raw_dates = records.iter()
+		.map(|record| parse_raw_date(record.date).map_err(|e| format!("line {}: {}", record.line, e)))
+		.collect::<Result<Vec<_>, _>>()?;
+	let day_first = day_first(&raw_dates)?;
+
+	let mut transactions = Vec::new();
+	let mut seen = BTreeMap::new();
+	for (record, raw_date) in records.iter().zip(&raw_dates) {
+		let error = |message: String| format!("line {}: {}", record.line, message);
+		let date = raw_date.to_date(day_first).map_err(error)?;
+		let amount = parse_signed_amount(record.amount).map_err(error)?;
+		let key = (date, amount, record.payee, record.memo);
+		let occurrence = seen.entry(key).or_insert(0);
+		*occurrence += 1;
+		transactions.push(BankTransaction {
+			date,
+			amount,
+			currency: String::from("EUR"),
+			counterparty_name: Some(record.payee.to_string()).filter(|x| !x.is_empty()),
+			counterparty_iban: None,
+			description: record.memo.to_string(),
+			references: Vec::new(),
+			bank_reference: Some(transaction_reference(record, date, amount.0, *occurrence)),
+		});
+	}
+
+	Ok(transactions)
+}
+
+/// A transaction record from a QIF file.
+#[derive(Debug, Default)]
+struct Record<'a> {
+	/// The line number of the start of the record.
+	line: usize,
+	date: &'a str,
+	amount: &'a str,
+	payee: &'a str,
+	memo: &'a str,
+}
+
+/// Split a QIF file in transaction records.
+fn parse_records(data: &str) -> Result<Vec<Record<'_>>, String> {
+	let mut records = Vec::new();
+	let mut account_type = None;
+	let mut record = Record::default();
+	let mut has_fields = false;
+	for (i, line) in data.lines().enumerate() {
+		let line_number = i + 1;
+		let line = line.trim_end();
+		if line.is_empty() {
+			continue;
+		}
+		if !has_fields {
+			record.line = line_number;
+		}
+
+		if let Some(header) = line.strip_prefix('!') {
+			// Options like `!Option:AutoSwitch` do not change the type of the records.
+			if let Some(name) = header.strip_prefix("Type:") {
+				account_type = Some(name.trim());
+			} else if header == "Account" {
+				account_type = None;
+			}
+			continue;
+		}
+
+		let (code, value) = line.split_at(line.chars().next().map(char::len_utf8).unwrap_or(0));
+		let value = value.trim();
+		match code {
+			"^" => {
+				let record = std::mem::take(&mut record);
+				let importable = account_type.map(|x| ACCOUNT_TYPES.iter().any(|y| y.eq_ignore_ascii_case(x))).unwrap_or(false);
+				if has_fields && importable {
+					if record.date.is_empty() {
+						return Err(format!("line {}: missing date of transaction", record.line));
+					}
+					if record.amount.is_empty() {
+						return Err(format!("line {}: missing amount of transaction", record.line));
+					}
+					records.push(record);
+				}
+				has_fields = false;
+				continue;
+			},
+			"D" => record.date = value,
+			"T" | "U" => record.amount = value,
+			"P" => record.payee = value,
+			"M" => record.memo = value,
+			_ => (),
+		}
+		has_fields = true;
+	}
+
+	if has_fields && account_type.is_some() {
+		return Err(format!("line {}: missing '^' at the end of the last record", record.line));
+	}
+	Ok(records)
+}
+
+/// A date of which the order of the day and the month is not known yet.
+#[derive(Debug, Copy, Clone)]
+struct RawDate {
+	/// The first and second number of the date, if the year is last.
+	parts: [u8; 2],
+
+	/// The year of the date.
+	year: i16,
+
+	/// The date has the year first, like `2024-01-31`.
+	year_first: bool,
+
+	/// The date is separated with slashes, like in US dates.
+	slashes: bool,
+}
+
+impl RawDate {
+	fn to_date(self, day_first: bool) -> Result<Date, String> {
+		let (month, day) = if self.year_first || !day_first {
+			(self.parts[0], self.parts[1])
+		} else {
+			(self.parts[1], self.parts[0])
+		};
+		let invalid = || format!("invalid date {}-{:02}-{:02}", self.year, month, day);
+		let month = Month::new(month).map_err(|_| invalid())?;
+		Date::new(self.year, month, day).map_err(|_| invalid())
+	}
+}
+
+/// Parse a date like `1/31/2024`, `1/31'24`, `31-01-2024`, `31.01.2024` or `2024-01-31`.
+fn parse_raw_date(data: &str) -> Result<RawDate, String> {
+	let invalid = || format!("invalid date {:?}", data);
+	let slashes = data.contains('/');
+	let parts: Vec<&str> = data.split(['/', '-', '.', '\''])
+		.map(str::trim)
+		.collect();
+	if parts.len() != 3 || parts.iter().any(|x| x.is_empty() || !x.bytes().all(|x| x.is_ascii_digit())) {
+		return Err(invalid());
+	}
+
+	let year_first = parts[0].len() == 4;
+	let (year, first, second) = if year_first {
+		(parts[0], parts[1], parts[2])
+	} else {
+		(parts[2], parts[0], parts[1])
+	};
+	let mut year: i16 = year.parse().map_err(|_| invalid())?;
+	if year < 100 {
+		year += 2000;
+	}
+	Ok(RawDate {
+		parts: [first.parse().map_err(|_| invalid())?, second.parse().map_err(|_| invalid())?],
+		year,
+		year_first,
+		slashes,
+	})
+}
+
+/// Determine if the dates of a file have the day before the month.
+fn day_first(dates: &[RawDate]) -> Result<bool, String> {
+	let dates = || dates.iter().filter(|x| !x.year_first);
+	let day_first = dates().any(|x| x.parts[0] > 12);
+	let month_first = dates().any(|x| x.parts[1] > 12);
+	match (day_first, month_first) {
+		(true, true) => Err("dates have both the day and the month first".into()),
+		(true, false) => Ok(true),
+		(false, true) => Ok(false),
+		(false, false) => Ok(!dates().any(|x| x.slashes)),
+	}
+}
+
+/// Derive a reference for a transaction, to skip it if it is imported again.
+///
+/// The `occurrence` distinguishes identical transactions in the same file.
+fn transaction_reference(record: &Record, date: Date, cents: i32, occurrence: usize) -> String {
+	let data = format!("{}\n{}\n{}\n{}\n{}", date, cents, record.payee, record.memo, occurrence);
+	let digest: String = Sha256::digest(data.as_bytes()).iter().take(12).map(|x| format!("{:02x}", x)).collect();
+	format!("qif-{}", digest)
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+	use assert2::{assert, let_assert};
+	use zzp::grootboek::Cents;
+
+	let data = concat!(
+		"\u{FEFF}!Type:Bank\r\n",
+		"D31/01/2024\r\n",
+		"T-1,234.56\r\n",
+		"PSupplier\r\n",
+		"MInvoice 123\r\n",
+		"^\r\n",
+		"D01/02'24\r\n",
+		"U121.00\r\n",
+		"^\r\n",
+		"D01/02'24\r\n",
+		"U121.00\r\n",
+		"^\r\n",
+		"!Account\r\n",
+		"NSavings\r\n",
+		"^\r\n",
+		"!Type:Invst\r\n",
+		"D02/01/2024\r\n",
+		"T10.00\r\n",
+		"^\r\n",
+	);
+	let_assert!(Ok(transactions) = parse(data));
+	assert!(transactions.len() == 3);
+	assert!(transactions[0].date == Date::new(2024, zzp::gregorian::January, 31).unwrap());
+	assert!(transactions[0].amount == Cents(-123456));
+	assert!(transactions[0].counterparty_name.as_deref() == Some("Supplier"));
+	assert!(transactions[0].description == "Invoice 123");
+	assert!(transactions[1].date == Date::new(2024, zzp::gregorian::February, 1).unwrap());
+	assert!(transactions[1].amount == Cents(12100));
+	assert!(transactions[1].counterparty_name.is_none());
+
+	// Identical transactions get different references, which are the same when the file is imported again.
+	let_assert!(Some(reference) = &transactions[1].bank_reference);
+	assert!(reference.starts_with("qif-"));
+	assert!(transactions[2].bank_reference != transactions[1].bank_reference);
+	let_assert!(Ok(again) = parse(data));
+	assert!(again[2].bank_reference == transactions[2].bank_reference);
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_dates() {
+	use assert2::{assert, let_assert};
+
+	let dates = |data: &str| -> Result<Vec<Date>, String> {
+		Ok(parse(data)?.into_iter().map(|x| x.date).collect())
+	};
+	let date = |month, day| Date::new(2024, Month::new(month).unwrap(), day).unwrap();
+
+	// Ambiguous dates with slashes are month/day/year, other ambiguous dates are day-month-year.
+	let_assert!(Ok(parsed) = dates("!Type:Bank\nD02/01/2024\nT1\n^\n"));
+	assert!(parsed == [date(2, 1)]);
+	let_assert!(Ok(parsed) = dates("!Type:Bank\nD02-01-2024\nT1\n^\n"));
+	assert!(parsed == [date(1, 2)]);
+
+	// A date that can only be read one way decides the order for all dates.
+	let_assert!(Ok(parsed) = dates("!Type:Bank\nD02/01/2024\nT1\n^\nD1/31/2024\nT1\n^\n"));
+	assert!(parsed == [date(2, 1), date(1, 31)]);
+	let_assert!(Ok(parsed) = dates("!Type:Bank\nD02.01.2024\nT1\n^\nD2024-01-31\nT1\n^\nD31.01.24\nT1\n^\n"));
+	assert!(parsed == [date(1, 2), date(1, 31), date(1, 31)]);
+
+	assert!(let Err(_) = dates("!Type:Bank\nD31/01/2024\nT1\n^\nD01/31/2024\nT1\n^\n"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_malformed() {
+	use assert2::{assert, let_assert};
+
+	let_assert!(Err(error) = parse("!Type:Bank\nD01/31/2024\nT1.00\n"));
+	assert!(error == "line 2: missing '^' at the end of the last record");
+	let_assert!(Err(error) = parse("!Type:Bank\nT1.00\n^\n"));
+	assert!(error == "line 2: missing date of transaction");
+	let_assert!(Err(error) = parse("!Type:Bank\n\nD01/31/2024\n^\n"));
+	assert!(error == "line 3: missing amount of transaction");
+	let_assert!(Err(error) = parse("!Type:Bank\nD01/31/2024\nT1.00\n^\nD31 jan\nT1.00\n^\n"));
+	assert!(error == "line 5: invalid date \"31 jan\"");
+	assert!(let Err(_) = parse("!Type:Bank\nD2024/13/01\nT1.00\n^\n"));
+	assert!(let Err(_) = parse("!Type:Bank\nD01/31/2024\nTten\n^\n"));
+
+	// Records of other account types are not checked.
+	let_assert!(Ok(transactions) = parse("!Type:Invst\nNBuy\n^\n"));
+	assert!(transactions.is_empty());
+}
@@ -0,0 +1,242 @@
+//! Rules to categorize imported bank transactions.
+
+use ordered_float::NotNan;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use zzp::grootboek::{Cents, RoundingMode, VatSplit};
+
+use super::BankTransaction;
+use crate::ZzpConfig;
+
+/// The default path of the rules file, relative to the directory of `zzp.toml`.
+pub const DEFAULT_RULES_PATH: &str = "bank-rules.toml";
+
+/// The rules to categorize imported bank transactions.
+///
+/// The rules are stored as a TOML file with a `[[Rule]]` table for each rule.
+/// The first rule that matches a transaction is used.
+#[derive(Debug, Clone)]
+pub struct BankRules {
+	path: PathBuf,
+	rules: Vec<(BankRule, Option<Regex>)>,
+}
+
+/// A rule to categorize bank transactions.
+///
+/// All given conditions must match for the rule to apply.
+/// A rule without conditions matches all transactions.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BankRule {
+	/// The name of the rule, shown when the rule is applied.
+	pub name: Option<String>,
+
+	/// Only match transactions with this IBAN of the counterparty (spaces and case are ignored).
+	pub iban: Option<String>,
+
+	/// Only match transactions with a description that matches this regular expression.
+	///
+	/// The description consists of the name of the counterparty and the remittance information.
+	pub description: Option<String>,
+
+	/// Only match transactions with at least this amount, negative for outgoing payments.
+	pub min_amount: Option<NotNan<f64>>,
+
+	/// Only match transactions with at most this amount, negative for outgoing payments.
+	pub max_amount: Option<NotNan<f64>>,
+
+	/// The grootboek account to book the transaction on.
+	pub account: String,
+
+	/// Tags to add to the grootboek transaction.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub tags: BTreeMap<String, String>,
+
+	/// The VAT percentage included in the amount.
+	///
+	/// The VAT of outgoing payments is booked on the VAT input account,
+	/// the VAT of incoming payments on the VAT account.
+	pub vat: Option<NotNan<f64>>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+	#[serde(rename = "Rule")]
+	#[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+	rules: Vec<BankRule>,
+}
+
+impl BankRules {
+	/// Open the rules file for a ZZP configuration.
+	///
+	/// The `root_dir` is the directory containing `zzp.toml`.
+	/// A missing rules file is treated as a file without rules.
+	pub fn open(root_dir: impl AsRef<Path>, config: &ZzpConfig) -> Result<Self, String> {
+		let path = root_dir.as_ref().join(config.grootboek.bank_rules.as_deref().unwrap_or(DEFAULT_RULES_PATH));
+		let file: RulesFile = if path.exists() {
+			crate::read_toml(&path).map_err(|e| e.to_string())?
+		} else {
+			RulesFile::default()
+		};
+		Self::from_rules(path, file.rules)
+	}
+
+	/// Check the rules and compile their regular expressions.
+	fn from_rules(path: PathBuf, rules: Vec<BankRule>) -> Result<Self, String> {
+		let rules = rules.into_iter()
+			.enumerate()
+			.map(|(i, rule)| {
+				let error = |message: String| format!("{}: rule {}: {}", path.display(), i + 1, message);
+				for label in rule.tags.keys() {
+					if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+						return Err(error(format!("invalid tag label {:?}", label)));
+					}
+				}
+				let regex = rule.description.as_deref()
+					.map(Regex::new)
+					.transpose()
+					.map_err(|e| error(e.to_string()))?;
+				Ok((rule, regex))
+			})
+			.collect::<Result<_, String>>()?;
+
+		Ok(Self { path, rules })
+	}
+
+	/// Get the path of the rules file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Find the first rule that matches a transaction.
+	pub fn find(&self, transaction: &BankTransaction) -> Option<&BankRule> {
+		self.rules.iter()
+			.find(|(rule, regex)| rule.matches(regex.as_ref(), transaction))
+			.map(|(rule, _)| rule)
+	}
+}
+
+impl BankRule {
+	fn matches(&self, regex: Option<&Regex>, transaction: &BankTransaction) -> bool {
+		if let Some(iban) = &self.iban {
+			match &transaction.counterparty_iban {
+				Some(other) if normalize_iban(iban) == normalize_iban(other) => (),
+				_ => return false,
+			}
+		}
+		if let Some(regex) = regex {
+			if !regex.is_match(&transaction.summary()) {
+				return false;
+			}
+		}
+		if let Some(min_amount) = self.min_amount {
+			if transaction.amount < Cents::from_f64(min_amount.into_inner(), RoundingMode::HalfAwayFromZero) {
+				return false;
+			}
+		}
+		if let Some(max_amount) = self.max_amount {
+			if transaction.amount > Cents::from_f64(max_amount.into_inner(), RoundingMode::HalfAwayFromZero) {
+				return false;
+			}
+		}
+		true
+	}
+
+	/// Split the counter amount of a bank transaction in the part for the account and the VAT.
+	///
+	/// Without a VAT percentage, all of the amount is for the account.
+	pub fn split(&self, amount: Cents) -> VatSplit {
+		match self.vat {
+			Some(vat) => zzp::grootboek::split_gross(-amount, vat.into_inner(), RoundingMode::HalfAwayFromZero),
+			None => VatSplit { net: -amount, vat: Cents(0) },
+		}
+	}
+}
+
+fn normalize_iban(iban: &str) -> String {
+	iban.chars()
+		.filter(|c| !c.is_whitespace())
+		.map(|c| c.to_ascii_uppercase())
+		.collect()
+}
+
+#[cfg(test)]
+fn test_rules(data: &str) -> Result<BankRules, String> {
+	let file: RulesFile = toml::from_str(data).map_err(|e| e.to_string())?;
+	BankRules::from_rules(PathBuf::from("bank-rules.toml"), file.rules)
+}
+
+#[cfg(test)]
+fn test_transaction(amount: i32, iban: Option<&str>, description: &str) -> BankTransaction {
+	let mut transaction = super::test_transaction(amount, description, &[]);
+	transaction.counterparty_iban = iban.map(String::from);
+	transaction
+}
+
+#[cfg(test)]
+#[test]
+fn test_find() {
+	use assert2::{assert, let_assert};
+
+	let_assert!(Ok(rules) = test_rules(concat!(
+		"[[Rule]]\n",
+		"name = \"rent\"\n",
+		"iban = \"nl91 abna 0417 1643 00\"\n",
+		"account = \"expenses/rent\"\n",
+		"\n",
+		"[[Rule]]\n",
+		"name = \"small purchases\"\n",
+		"description = \"^Customer - \"\n",
+		"min_amount = -50.0\n",
+		"max_amount = -0.01\n",
+		"account = \"expenses/office\"\n",
+		"vat = 21.0\n",
+		"tags = { category = \"office\" }\n",
+	)));
+	let name = |transaction: &BankTransaction| rules.find(transaction).and_then(|x| x.name.as_deref());
+
+	assert!(name(&test_transaction(-100000, Some("NL91ABNA0417164300"), "")) == Some("rent"));
+	assert!(name(&test_transaction(-100000, Some("NL02RABO0123456789"), "")).is_none());
+	assert!(name(&test_transaction(-100000, None, "")).is_none());
+	assert!(name(&test_transaction(-5000, None, "Paper")) == Some("small purchases"));
+	assert!(name(&test_transaction(-1, None, "Paper")) == Some("small purchases"));
+	assert!(name(&test_transaction(-5001, None, "Paper")).is_none());
+	assert!(name(&test_transaction(0, None, "Paper")).is_none());
+	assert!(name(&test_transaction(-1000, None, "")).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_invalid_rules() {
+	use assert2::assert;
+
+	assert!(let Err(_) = test_rules("[[Rule]]\ndescription = \"(\"\naccount = \"expenses\"\n"));
+	assert!(let Err(_) = test_rules("[[Rule]]\naccount = \"expenses\"\ntags = { \"bad label\" = \"x\" }\n"));
+	assert!(let Err(_) = test_rules("[[Rule]]\naccount = \"expenses\"\nunknown = 1\n"));
+	assert!(let Err(_) = test_rules("[[Rule]]\nname = \"no account\"\n"));
+	assert!(let Ok(_) = test_rules(""));
+}
+
+#[cfg(test)]
+#[test]
+fn test_split() {
+	use assert2::assert;
+
+	let rule = |vat: Option<f64>| BankRule {
+		name: None,
+		iban: None,
+		description: None,
+		min_amount: None,
+		max_amount: None,
+		account: "expenses".into(),
+		tags: BTreeMap::new(),
+		vat: vat.map(|x| NotNan::new(x).unwrap()),
+	};
+	assert!(rule(None).split(Cents(-12100)) == VatSplit { net: Cents(12100), vat: Cents(0) });
+	assert!(rule(Some(21.0)).split(Cents(-12100)) == VatSplit { net: Cents(10000), vat: Cents(2100) });
+	assert!(rule(Some(21.0)).split(Cents(12100)) == VatSplit { net: Cents(-10000), vat: Cents(-2100) });
+	assert!(rule(Some(9.0)).split(Cents(-1000)) == VatSplit { net: Cents(917), vat: Cents(83) });
+}
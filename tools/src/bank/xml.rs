@@ -0,0 +1,281 @@
+//! A minimal XML parser for bank statements.
+//!
+//! Only elements, attributes and text are supported.
+//! Namespaces are ignored: elements are identified by their local name.
+
+/// An XML element.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+	/// The local name of the element, without namespace prefix.
+	pub name: String,
+
+	/// The attributes of the element, with the namespace prefix stripped from the names.
+	pub attributes: Vec<(String, String)>,
+
+	/// The child elements.
+	pub children: Vec<Element>,
+
+	/// The text content of the element, excluding the text of child elements.
+	pub text: String,
+}
+
+impl Element {
+	/// Get the first child element with the given name.
+	pub fn child(&self, name: &str) -> Option<&Element> {
+		self.children.iter().find(|x| x.name == name)
+	}
+
+	/// Get all child elements with the given name.
+	pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+		self.children.iter().filter(move |x| x.name == name)
+	}
+
+	/// Follow a path of child element names.
+	pub fn path(&self, path: &[&str]) -> Option<&Element> {
+		path.iter().try_fold(self, |element, name| element.child(name))
+	}
+
+	/// Get the trimmed text of the element at a path, if it exists and is not empty.
+	pub fn text_at(&self, path: &[&str]) -> Option<&str> {
+		self.path(path)
+			.map(|x| x.text.trim())
+			.filter(|x| !x.is_empty())
+	}
+
+	/// Get the value of an attribute.
+	pub fn attribute(&self, name: &str) -> Option<&str> {
+		self.attributes.iter()
+			.find(|(key, _)| key == name)
+			.map(|(_, value)| value.as_str())
+	}
+}
+
+/// Parse an XML document and return the root element.
+pub fn parse(data: &str) -> Result<Element, String> {
+	let data = data.strip_prefix('\u{FEFF}').unwrap_or(data);
+	let mut parser = Parser { data, pos: 0 };
+	parser.skip_misc()?;
+	let root = parser.parse_element()?;
+	parser.skip_misc()?;
+	if parser.pos != data.len() {
+		return Err(parser.error("unexpected content after the root element"));
+	}
+	Ok(root)
+}
+
+struct Parser<'a> {
+	data: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn rest(&self) -> &'a str {
+		&self.data[self.pos..]
+	}
+
+	fn error(&self, message: &str) -> String {
+		let line = self.data[..self.pos].matches('\n').count() + 1;
+		format!("line {}: {}", line, message)
+	}
+
+	/// Skip until after the given terminator.
+	fn skip_past(&mut self, terminator: &str) -> Result<&'a str, String> {
+		match self.rest().find(terminator) {
+			Some(i) => {
+				let skipped = &self.rest()[..i];
+				self.pos += i + terminator.len();
+				Ok(skipped)
+			},
+			None => Err(self.error(&format!("missing {:?}", terminator))),
+		}
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_start().len();
+	}
+
+	/// Skip whitespace, comments, processing instructions and the document type declaration.
+	fn skip_misc(&mut self) -> Result<(), String> {
+		loop {
+			self.skip_whitespace();
+			if self.rest().starts_with("<?") {
+				self.skip_past("?>")?;
+			} else if self.rest().starts_with("<!--") {
+				self.skip_past("-->")?;
+			} else if self.rest().starts_with("<!") {
+				self.skip_past(">")?;
+			} else {
+				return Ok(());
+			}
+		}
+	}
+
+	fn parse_name(&mut self) -> Result<&'a str, String> {
+		let rest = self.rest();
+		let end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=').unwrap_or(rest.len());
+		if end == 0 {
+			return Err(self.error("expected a name"));
+		}
+		self.pos += end;
+		Ok(&rest[..end])
+	}
+
+	fn parse_element(&mut self) -> Result<Element, String> {
+		if !self.rest().starts_with('<') {
+			return Err(self.error("expected an element"));
+		}
+		self.pos += 1;
+		let qualified_name = self.parse_name()?;
+		let mut element = Element {
+			name: local_name(qualified_name).to_string(),
+			..Element::default()
+		};
+
+		loop {
+			self.skip_whitespace();
+			if self.rest().starts_with("/>") {
+				self.pos += 2;
+				return Ok(element);
+			} else if self.rest().starts_with('>') {
+				self.pos += 1;
+				break;
+			}
+
+			let name = self.parse_name()?;
+			self.skip_whitespace();
+			if !self.rest().starts_with('=') {
+				return Err(self.error("expected '=' after attribute name"));
+			}
+			self.pos += 1;
+			self.skip_whitespace();
+			let quote = match self.rest().chars().next() {
+				Some(c) if c == '"' || c == '\'' => c,
+				_ => return Err(self.error("expected a quoted attribute value")),
+			};
+			self.pos += 1;
+			let value = self.skip_past(&quote.to_string())?;
+			element.attributes.push((local_name(name).to_string(), unescape(value)));
+		}
+
+		loop {
+			let rest = self.rest();
+			if rest.starts_with("</") {
+				self.pos += 2;
+				let name = self.parse_name()?;
+				if name != qualified_name {
+					return Err(self.error(&format!("expected closing tag for {:?}, found {:?}", qualified_name, name)));
+				}
+				self.skip_whitespace();
+				self.skip_past(">")?;
+				return Ok(element);
+			} else if rest.starts_with("<![CDATA[") {
+				self.pos += "<![CDATA[".len();
+				let text = self.skip_past("]]>")?;
+				element.text.push_str(text);
+			} else if rest.starts_with("<!--") {
+				self.skip_past("-->")?;
+			} else if rest.starts_with("<?") {
+				self.skip_past("?>")?;
+			} else if rest.starts_with('<') {
+				element.children.push(self.parse_element()?);
+			} else if rest.is_empty() {
+				return Err(self.error(&format!("missing closing tag for {:?}", qualified_name)));
+			} else {
+				let end = rest.find('<').unwrap_or(rest.len());
+				element.text.push_str(&unescape(&rest[..end]));
+				self.pos += end;
+			}
+		}
+	}
+}
+
+/// Strip the namespace prefix from a name.
+fn local_name(name: &str) -> &str {
+	match name.rfind(':') {
+		Some(i) => &name[i + 1..],
+		None => name,
+	}
+}
+
+/// Replace entity and character references in text.
+///
+/// Unknown or invalid references are kept as they are.
+pub(super) fn unescape(data: &str) -> String {
+	let mut output = String::with_capacity(data.len());
+	let mut rest = data;
+	while let Some(start) = rest.find('&') {
+		output.push_str(&rest[..start]);
+		rest = &rest[start..];
+		let end = match rest.find(';') {
+			Some(x) => x,
+			None => break,
+		};
+		let replacement = match &rest[1..end] {
+			"amp" => Some('&'),
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			reference => reference.strip_prefix("#x").or_else(|| reference.strip_prefix("#X"))
+				.and_then(|x| u32::from_str_radix(x, 16).ok())
+				.or_else(|| reference.strip_prefix('#').and_then(|x| x.parse().ok()))
+				.and_then(char::from_u32),
+		};
+		match replacement {
+			Some(c) => {
+				output.push(c);
+				rest = &rest[end + 1..];
+			},
+			None => {
+				output.push('&');
+				rest = &rest[1..];
+			},
+		}
+	}
+	output.push_str(rest);
+	output
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+	use assert2::{assert, let_assert};
+
+	let_assert!(Ok(root) = parse(concat!(
+		"\u{FEFF}<?xml version=\"1.0\"?>\n",
+		"<!DOCTYPE root>\n",
+		"<!-- comment -->\n",
+		"<ns:root xmlns:ns=\"urn:test\" ns:id='1'>\n",
+		"  <ns:item>a &amp; b &#65;&#x42; &unknown;</ns:item>\n",
+		"  <item><![CDATA[<raw>]]></item>\n",
+		"  <empty/>\n",
+		"</ns:root>\n",
+	)));
+	assert!(root.name == "root");
+	assert!(root.attribute("id") == Some("1"));
+	assert!(root.children("item").count() == 2);
+	assert!(root.text_at(&["item"]) == Some("a & b AB &unknown;"));
+	assert!(root.children[1].text == "<raw>");
+	assert!(root.path(&["empty"]).is_some());
+	assert!(root.text_at(&["empty"]).is_none());
+	assert!(root.path(&["empty", "missing"]).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_malformed() {
+	use assert2::{assert, let_assert};
+
+	let_assert!(Err(error) = parse(""));
+	assert!(error == "line 1: expected an element");
+	let_assert!(Err(error) = parse("<a>\n<b></a>"));
+	assert!(error == "line 2: expected closing tag for \"b\", found \"a\"");
+	let_assert!(Err(error) = parse("<a>\n<b/>\n"));
+	assert!(error == "line 3: missing closing tag for \"a\"");
+	assert!(let Err(_) = parse("<a x=1/>"));
+	assert!(let Err(_) = parse("<a x/>"));
+	assert!(let Err(_) = parse("<a x=\"1/>"));
+	assert!(let Err(_) = parse("<a/><b/>"));
+	assert!(let Err(_) = parse("<a><!-- comment</a>"));
+}
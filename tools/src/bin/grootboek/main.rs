@@ -0,0 +1,1220 @@
+use ordered_float::NotNan;
+use regex::Regex;
+use std::ffi::OsString;
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap::AppSettings;
+use yansi::Paint;
+
+use zzp::gregorian::Date;
+use zzp::date_range::DateRange;
+use zzp::partial_date::PartialDate;
+use zzp_tools::period::Period;
+use zzp::grootboek::Cents;
+use zzp::grootboek::MappedFile;
+use zzp::grootboek::Query;
+use zzp::grootboek::RoundingMode;
+use zzp::grootboek::Transaction;
+use zzp::index::FileKind;
+use zzp_tools::{CustomerConfig, ZzpConfig};
+use zzp_tools::grootboek::{color_cents, compute_totals, find_config, find_unbalanced, read_config, register};
+use zzp_tools::grootboek::{AccountNode, AccountTree, Contents, TagFilter, TransactionFilter};
+use zzp_tools::icp::IcpDeclaration;
+use zzp_tools::income_statement::IncomeStatement;
+use zzp_tools::income_tax::IncomeTax;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::vat_return::{CORRECTION_THRESHOLD, VatDeclaration, VatDeclarations, VatReturn, VAT_RETURN_TAG, whole_euros};
+
+mod stats;
+
+/// An error of a subcommand, reported once by `main`.
+type Error = Box<dyn std::error::Error>;
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = AppSettings::VersionlessSubcommands)]
+struct Options {
+	/// The output format of reports, or xaf to export an XML Auditfile Financieel 3.2.
+	#[structopt(long)]
+	#[structopt(global = true)]
+	#[structopt(value_name = "FORMAT")]
+	#[structopt(possible_values = &["text", "json", "xaf"])]
+	#[structopt(default_value = "text")]
+	format: Format,
+
+	/// Map the grootboek file into memory instead of reading it, which is faster for very large files.
+	///
+	/// The file must not be modified while the command runs, or the command may crash.
+	#[structopt(long)]
+	#[structopt(global = true)]
+	mmap: bool,
+
+	#[structopt(subcommand)]
+	command: Command,
+}
+
+#[derive(StructOpt)]
+enum Command {
+	/// Show the total balance of all accounts.
+	Totals(TotalsOptions),
+
+	/// Check for unbalanced transactions.
+	Check(CheckOptions),
+
+	/// Show the transactions matching a query.
+	Query(QueryOptions),
+
+	/// Sort the transactions in a file by date and normalize the formatting.
+	Fmt(FmtOptions),
+
+	/// Show the mutations of an account with a running balance.
+	Register(RegisterOptions),
+
+	/// Compute the Dutch VAT return (btw-aangifte) for a period.
+	Btw(BtwOptions),
+
+	/// Compare a filed VAT return with the grootboek to see if a correction (suppletie) is needed.
+	Suppletie(SuppletieOptions),
+
+	/// Compute the Dutch declaration of intra-community supplies (opgaaf ICP) for a period.
+	Icp(IcpOptions),
+
+	/// Show the figures for the Dutch income tax return (aangifte inkomstenbelasting) for a year.
+	Ib(IbOptions),
+
+	/// Show the income statement (winst- en verliesrekening) using the chart of accounts from zzp.toml.
+	IncomeStatement(IncomeStatementOptions),
+
+	/// Export the mutations of the transactions as CSV (or JSON with --format json), with one row per mutation.
+	///
+	/// With --format xaf, the transactions are exported as Dutch XML Auditfile Financieel (XAF 3.2)
+	/// for the Belastingdienst or an accountant. Revenue and expense accounts are taken from the chart of accounts in zzp.toml.
+	Export(ExportOptions),
+
+	/// Show the monthly totals of accounts as a bar chart or sparkline.
+	Stats(StatsOptions),
+
+	/// Create or update the index of files, so commands with a period only read the transactions of the period.
+	///
+	/// The index is stored next to the file, like grootboek.txt.index.
+	/// Once a file has an index, it is updated automatically when the file changes.
+	/// The index is only used if the transactions in the file are sorted by date.
+	Index(IndexOptions),
+}
+
+impl Command {
+	/// Get the transaction filter of the command, if it has one.
+	fn filter_mut(&mut self) -> Option<&mut FilterOptions> {
+		match self {
+			Self::Totals(x) => Some(&mut x.filter),
+			Self::Check(x) => Some(&mut x.filter),
+			Self::Query(x) => Some(&mut x.filter),
+			Self::Register(x) => Some(&mut x.filter),
+			Self::IncomeStatement(x) => Some(&mut x.filter),
+			Self::Export(x) => Some(&mut x.filter),
+			Self::Stats(x) => Some(&mut x.filter),
+			Self::Fmt(_) | Self::Btw(_) | Self::Suppletie(_) | Self::Icp(_) | Self::Ib(_) | Self::Index(_) => None,
+		}
+	}
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct FilterOptions {
+	/// The file to parse.
+	file: PathBuf,
+
+	/// Consider only transactions that mutate the given account or a sub-account.
+	#[structopt(long, short)]
+	#[structopt(value_name = "ACCOUNT")]
+	account: Option<String>,
+
+	/// Limit records to this period, like 2024-Q2, last-month or the name of a custom period from zzp.toml.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	period: Option<Period>,
+
+	/// The date range of the period, see [`FilterOptions::resolve`].
+	#[structopt(skip)]
+	period_range: Option<Range<Date>>,
+
+	/// The filter for the transactions, see [`FilterOptions::resolve`].
+	#[structopt(skip)]
+	transaction_filter: TransactionFilter,
+
+	/// Only consider records from this date or later.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	start_date: Option<PartialDate>,
+
+	/// Only consider records from this date or earlier.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	end_date: Option<PartialDate>,
+
+	/// Only consider records in this date range, like 2024-01-15..2024-02-03 or ..2024-06-30.
+	#[structopt(long)]
+	#[structopt(value_name = "[START]..[END]")]
+	#[structopt(conflicts_with_all = &["period", "start-date", "end-date"])]
+	dates: Option<DateRange>,
+
+	/// Consider only transactions with a description that matches this regular expression.
+	#[structopt(long)]
+	#[structopt(value_name = "REGEX")]
+	description: Option<Regex>,
+
+	/// Consider only transactions with this tag, optionally with the given value (can be repeated).
+	#[structopt(long)]
+	#[structopt(value_name = "LABEL[=VALUE]")]
+	#[structopt(number_of_values = 1)]
+	tag: Vec<TagFilter>,
+
+	/// Consider only transactions with a mutation of at least this absolute amount (on the given account, if any).
+	#[structopt(long)]
+	#[structopt(value_name = "AMOUNT")]
+	min_amount: Option<NotNan<f64>>,
+
+	/// Consider only transactions with a mutation of at most this absolute amount (on the given account, if any).
+	#[structopt(long)]
+	#[structopt(value_name = "AMOUNT")]
+	max_amount: Option<NotNan<f64>>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct TotalsOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// Show the balance of all accounts at the end of this date, including all earlier transactions.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	#[structopt(conflicts_with_all = &["period", "start-date", "end-date"])]
+	at: Option<Date>,
+
+	/// Print the matching transactions before the totals.
+	#[structopt(long)]
+	transactions: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct CheckOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// Also check that documents referenced by tags exist, and look for unreferenced documents.
+	#[structopt(long)]
+	attachments: bool,
+
+	/// The tag label that refers to documents (defaults to the invoice tag from zzp.toml).
+	#[structopt(long)]
+	#[structopt(value_name = "LABEL")]
+	#[structopt(requires = "attachments")]
+	#[structopt(number_of_values = 1)]
+	attachment_tag: Vec<String>,
+
+	/// The directory to search for unreferenced documents (defaults to the attachments directory from zzp.toml).
+	///
+	/// Without an attachments directory, only missing documents are reported.
+	#[structopt(long)]
+	#[structopt(value_name = "DIR")]
+	#[structopt(requires = "attachments")]
+	attachment_dir: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct QueryOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// The query to match transactions against, like "account:revenue/* tag:invoice date:2024-Q2 amount>100".
+	#[structopt(value_name = "QUERY")]
+	query: Query,
+
+	/// Show the totals of the matching transactions instead of the transactions themselves.
+	#[structopt(long)]
+	totals: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct FmtOptions {
+	/// The files to format.
+	#[structopt(required = true)]
+	#[structopt(value_name = "FILE")]
+	files: Vec<PathBuf>,
+
+	/// Print the formatted files to standard output instead of overwriting the files.
+	#[structopt(long)]
+	stdout: bool,
+
+	/// Only check if the files are formatted, without changing them.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "stdout")]
+	check: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct IndexOptions {
+	/// The files to index.
+	#[structopt(required = true)]
+	#[structopt(value_name = "FILE")]
+	files: Vec<PathBuf>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct RegisterOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// The account to show the mutations of, including the mutations of sub-accounts.
+	#[structopt(name = "ACCOUNT")]
+	register_account: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct BtwOptions {
+	/// The grootboek file.
+	file: PathBuf,
+
+	/// The period of the VAT return, like 2024-Q2.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-QUARTER|-MONTH]")]
+	period: PartialDate,
+
+	/// Book the VAT to pay or to receive in the grootboek.
+	#[structopt(long)]
+	book: bool,
+
+	/// The date of the booking [default: the last day of the period].
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	#[structopt(requires = "book")]
+	date: Option<Date>,
+
+	/// Book the VAT return even if the grootboek has transactions with a later date.
+	#[structopt(long)]
+	#[structopt(requires = "book")]
+	force: bool,
+
+	/// Record the VAT return as filed in the declarations file, without booking it.
+	///
+	/// Booking a VAT return with --book also records it.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "book")]
+	record: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct SuppletieOptions {
+	/// The grootboek file.
+	file: PathBuf,
+
+	/// The period of the filed VAT return, like 2024-Q2.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-QUARTER|-MONTH]")]
+	period: PartialDate,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct IcpOptions {
+	/// The grootboek file.
+	file: PathBuf,
+
+	/// The period of the declaration, like 2024-Q2.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-QUARTER|-MONTH]")]
+	period: PartialDate,
+
+	/// Declare the supplies as goods (leveringen) instead of services (diensten).
+	#[structopt(long)]
+	goods: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct IbOptions {
+	/// The grootboek file.
+	file: PathBuf,
+
+	/// The year of the income tax return.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY")]
+	year: i16,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct IncomeStatementOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// Also write the income statement as PDF to this file.
+	#[structopt(long)]
+	#[structopt(value_name = "FILE")]
+	pdf: Option<PathBuf>,
+
+	/// Also write the income statement as CSV to this file.
+	#[structopt(long)]
+	#[structopt(value_name = "FILE")]
+	csv: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct ExportOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// Write the export to this file instead of standard output.
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	output: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = AppSettings::DeriveDisplayOrder)]
+struct StatsOptions {
+	#[structopt(flatten)]
+	filter: FilterOptions,
+
+	/// The accounts to show the monthly totals of, including all sub-accounts.
+	#[structopt(value_name = "ACCOUNT")]
+	#[structopt(required = true)]
+	accounts: Vec<String>,
+
+	/// Show the direct sub-accounts of each account separately, like expenses per category.
+	#[structopt(long)]
+	split: bool,
+
+	/// Show a single-line sparkline for each account instead of a bar chart.
+	#[structopt(long)]
+	sparkline: bool,
+}
+
+impl FilterOptions {
+	/// Look up the date range of the selected period and build the transaction filter.
+	///
+	/// Custom periods are read from the zzp.toml of the grootboek file.
+	fn resolve(&mut self) -> Result<(), Error> {
+		if let Some(period) = &self.period {
+			let periods = if period.is_named() { read_config(&self.file)?.period } else { Vec::new() };
+			self.period_range = Some(period.as_range(&periods)?);
+		}
+		let (start_date, end_date) = self.date_range();
+		self.transaction_filter = TransactionFilter {
+			start_date,
+			end_date,
+			account: self.account.clone(),
+			description: self.description.clone(),
+			tags: self.tag.clone(),
+			min_amount: self.min_amount.map(|x| Cents::from_f64(x.into_inner(), RoundingMode::HalfAwayFromZero)),
+			max_amount: self.max_amount.map(|x| Cents::from_f64(x.into_inner(), RoundingMode::HalfAwayFromZero)),
+		};
+		Ok(())
+	}
+
+	/// Get the first date and the date after the last date of the selected period.
+	fn date_range(&self) -> (Option<Date>, Option<Date>) {
+		match (&self.period_range, self.dates) {
+			(Some(range), _) => (Some(range.start), Some(range.end)),
+			(None, Some(dates)) => dates.as_half_open(),
+			(None, None) => (self.start_date.map(|x| x.as_start_date()), self.end_date.map(|x| x.as_end_date().next())),
+		}
+	}
+
+	/// Read the grootboek file.
+	///
+	/// If the file has an index, only the transactions of the selected dates are read.
+	/// Otherwise, the whole file is read, or mapped into memory if `mmap` is true.
+	fn read(&self, mmap: bool) -> Result<Contents, Error> {
+		let (start_date, end_date) = self.date_range();
+		match zzp_tools::grootboek::read_indexed_period(&self.file, start_date, end_date)? {
+			Some(contents) => Ok(contents),
+			None => read_or_map_file(&self.file, mmap),
+		}
+	}
+
+	fn matches(&self, transaction: &Transaction) -> bool {
+		self.transaction_filter.matches(transaction)
+	}
+
+	/// Describe the selected period, like `2024-Q2` or `2024-01-01 - 2024-06-30`.
+	fn period_label(&self) -> String {
+		let (start_date, end_date) = match self.dates {
+			Some(dates) => (dates.start, dates.end),
+			None => (self.start_date, self.end_date),
+		};
+		match (&self.period, start_date, end_date) {
+			(Some(period), _, _) => period.to_string(),
+			(None, Some(start), Some(end)) => format!("{} - {}", start.as_start_date(), end.as_end_date()),
+			(None, Some(start), None) => format!("from {}", start.as_start_date()),
+			(None, None, Some(end)) => format!("until {}", end.as_end_date()),
+			(None, None, None) => String::from("all transactions"),
+		}
+	}
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+	std::fs::read_to_string(path).map_err(|e| format!("failed to read {:?}: {}", path, e).into())
+}
+
+/// Read a whole grootboek file for a command that does not write to it, or map it into memory if `mmap` is true.
+fn read_or_map_file(path: &Path, mmap: bool) -> Result<Contents, Error> {
+	if !mmap {
+		return Ok(zzp_tools::grootboek::read_period(path, None, None)?);
+	}
+	// SAFETY: The file is only mapped with --mmap.
+	// The documentation of that option tells the user that the file must not be modified while the command runs.
+	let file = unsafe { MappedFile::open(path) }?;
+	Ok(Contents::Mapped(file))
+}
+
+/// The output format of reports, or the XML Auditfile Financieel for the export.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Format {
+	Report(OutputFormat),
+	Xaf,
+}
+
+impl std::str::FromStr for Format {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		match input {
+			"xaf" => Ok(Self::Xaf),
+			input => input.parse().map(Self::Report),
+		}
+	}
+}
+
+fn do_main(options: &Options) -> Result<(), Error> {
+	let xaf = options.format == Format::Xaf;
+	let mmap = options.mmap;
+	let format = match (options.format, &options.command) {
+		(Format::Report(format), _) => format,
+		(Format::Xaf, Command::Export(_)) => OutputFormat::Text,
+		(Format::Xaf, _) => return Err("--format xaf can only be used with grootboek export".into()),
+	};
+	match &options.command {
+		Command::Totals(options) => {
+			let data = options.filter.read(mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let transactions: Vec<_> = transactions.into_iter()
+				.filter(|x| options.filter.matches(x))
+				.filter(|x| options.at.map(|at| x.date <= at).unwrap_or(true))
+				.collect();
+			if format == OutputFormat::Json {
+				let mut fields = Vec::new();
+				if options.transactions {
+					fields.push(("transactions", Json::array(&transactions)));
+				}
+				fields.push(("totals", totals_json(&compute_totals(transactions.iter().cloned()))));
+				Json::object(fields).print();
+				return Ok(());
+			}
+			if options.transactions {
+				print_transactions(transactions.iter().cloned())?;
+				println!();
+			}
+			let totals = compute_totals(transactions);
+			print_totals(&totals);
+			Ok(())
+		},
+		Command::Check(options) => {
+			// Looking for unreferenced documents needs all transactions, not only the ones of the period.
+			let data = if options.attachments {
+				read_or_map_file(&options.filter.file, mmap)?
+			} else {
+				options.filter.read(mmap)?
+			};
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let attachments = if options.attachments {
+				check_attachments(&transactions, options)
+			} else {
+				Ok(())
+			};
+			let transactions: Vec<_> = transactions.into_iter().filter(|x| options.filter.matches(x)).collect();
+			let balance = check_balance(transactions);
+			attachments.and(balance)
+		},
+		Command::Query(options) => {
+			let data = options.filter.read(mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let transactions = transactions.into_iter()
+				.filter(|x| options.filter.matches(x))
+				.filter(|x| options.query.matches(x));
+			match (format, options.totals) {
+				(OutputFormat::Text, true) => print_totals(&compute_totals(transactions)),
+				(OutputFormat::Text, false) => print_transactions(transactions)?,
+				(OutputFormat::Json, true) => totals_json(&compute_totals(transactions)).print(),
+				(OutputFormat::Json, false) => Json::array(&transactions.collect::<Vec<_>>()).print(),
+			}
+			Ok(())
+		},
+		Command::Register(options) => {
+			let data = options.filter.read(mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let transactions = transactions.into_iter().filter(|x| options.filter.matches(x));
+			match format {
+				OutputFormat::Text => print_register(transactions, &options.register_account),
+				OutputFormat::Json => register_json(transactions, &options.register_account).print(),
+			}
+			Ok(())
+		},
+		Command::Btw(options) => {
+			let data = read_file(&options.file)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let config = read_config(&options.file)?;
+			let period = options.period.to_string();
+			let range = options.period.as_range();
+
+			let vat_return = VatReturn::compute(&config, transactions.iter().filter(|x| range.contains(&x.date)))?;
+			match format {
+				OutputFormat::Text => print_vat_return(&vat_return, &period),
+				OutputFormat::Json => vat_return.to_json(&period).print(),
+			}
+
+			if options.book {
+				let booked = transactions.iter()
+					.any(|x| x.tags.iter().any(|tag| tag.label == VAT_RETURN_TAG && tag.value == period));
+				if booked {
+					return Err(format!("the VAT return for {} has already been booked", period).into());
+				}
+				// The VatReturn section must exist, or computing the VAT return would have failed.
+				let vat_config = config.vat_return.as_ref().unwrap();
+				let description = format!("VAT return {}", period);
+				let date = options.date.unwrap_or_else(|| options.period.as_end_date());
+				let transaction = vat_return.booking(vat_config, date, &description, &period)?;
+				if format == OutputFormat::Text {
+					println!();
+					zzp_tools::grootboek::print_full_colored(&transaction);
+				}
+				zzp::grootboek::append_transaction(&options.file, &transaction, options.force)?;
+			}
+			if options.book || options.record {
+				let config_path = find_config(&options.file)?;
+				let mut declarations = VatDeclarations::open(config_path.parent().unwrap(), &config)?;
+				declarations.add(VatDeclaration::new(&vat_return, &period, Date::today()));
+				declarations.save()?;
+			}
+			Ok(())
+		},
+		Command::Suppletie(options) => {
+			let data = read_or_map_file(&options.file, mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let config_path = find_config(&options.file)?;
+			let config = ZzpConfig::read_file(&config_path)?;
+			let declarations = VatDeclarations::open(config_path.parent().unwrap(), &config)?;
+			let period = options.period.to_string();
+			let declaration = declarations.find(&period)
+				.ok_or_else(|| format!("no filed VAT return for {} in {}, record it with `grootboek btw --record`", period, declarations.path().display()))?;
+			let range = options.period.as_range();
+
+			let vat_return = VatReturn::compute(&config, transactions.iter().filter(|x| range.contains(&x.date)))?;
+			match format {
+				OutputFormat::Text => print_suppletie(declaration, &vat_return, &period),
+				OutputFormat::Json => suppletie_json(declaration, &vat_return, &period).print(),
+			}
+			Ok(())
+		},
+		Command::Icp(options) => {
+			let data = read_or_map_file(&options.file, mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let config_path = find_config(&options.file)?;
+			let config = ZzpConfig::read_file(&config_path)?;
+			let customers = CustomerConfig::find_all(config_path.parent().unwrap())?
+				.into_iter()
+				.map(|path| CustomerConfig::read_file(path).map_err(|e| e.to_string()))
+				.collect::<Result<Vec<_>, _>>()?;
+			let period = options.period.to_string();
+			let range = options.period.as_range();
+
+			let declaration = IcpDeclaration::compute(&config, &customers, transactions.iter().filter(|x| range.contains(&x.date)));
+			match format {
+				OutputFormat::Text => print_icp_declaration(&declaration, &period, options.goods),
+				OutputFormat::Json => declaration.to_json(&period).print(),
+			}
+			Ok(())
+		},
+		Command::Ib(options) => {
+			let data = read_or_map_file(&options.file, mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let config = read_config(&options.file)?;
+			let period = options.year.to_string();
+			let income_tax = IncomeTax::compute(&config, transactions.iter().filter(|x| x.date.year().to_number() == options.year))?;
+			match format {
+				OutputFormat::Text => print_income_tax(&income_tax, &period),
+				OutputFormat::Json => income_tax.to_json(&period).print(),
+			}
+			Ok(())
+		},
+		Command::IncomeStatement(options) => {
+			let data = options.filter.read(mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let config = read_config(&options.filter.file)?;
+			let period = options.filter.period_label();
+			let statement = IncomeStatement::compute(&config, transactions.iter().filter(|x| options.filter.matches(x)))?;
+			match format {
+				OutputFormat::Text => print_income_statement(&statement, &period),
+				OutputFormat::Json => statement.to_json(&period).print(),
+			}
+
+			if let Some(path) = &options.csv {
+				let file = std::fs::File::create(path)
+					.map_err(|e| format!("failed to create {:?}: {}", path, e))?;
+				statement.write_csv(&mut std::io::BufWriter::new(file))
+					.map_err(|e| format!("failed to write to {:?}: {}", path, e))?;
+			}
+			if let Some(path) = &options.pdf {
+				let file = std::fs::File::create(path)
+					.map_err(|e| format!("failed to create {:?}: {}", path, e))?;
+				statement.make_pdf(std::io::BufWriter::new(file), &config, &period)?;
+			}
+			Ok(())
+		},
+		Command::Export(options) => {
+			let data = options.filter.read(mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let mut transactions: Vec<_> = transactions.into_iter().filter(|x| options.filter.matches(x)).collect();
+
+			let mut output: Box<dyn Write> = match &options.output {
+				Some(path) => {
+					let file = std::fs::File::create(path)
+						.map_err(|e| format!("failed to create {:?}: {}", path, e))?;
+					Box::new(std::io::BufWriter::new(file))
+				},
+				None => Box::new(std::io::stdout()),
+			};
+			if xaf {
+				let config = read_config(&options.filter.file)?;
+				transactions.sort_by_key(|x| x.date);
+				let (start_date, end_date) = options.filter.date_range();
+				let start_date = start_date.or_else(|| transactions.first().map(|x| x.date));
+				let end_date = end_date.map(|x| x.prev()).or_else(|| transactions.last().map(|x| x.date));
+				let (start_date, end_date) = start_date.zip(end_date)
+					.ok_or("no transactions to export, use --period to export an empty audit file")?;
+				zzp_tools::xaf::write_xaf(&mut output, &config, &transactions, start_date, end_date, Date::today())?;
+				return output.flush().map_err(|e| format!("failed to write export: {}", e).into());
+			}
+			let result = match format {
+				OutputFormat::Text => zzp_tools::grootboek::write_mutations_csv(&mut output, &transactions),
+				OutputFormat::Json => zzp_tools::grootboek::write_mutations_json(&mut output, &transactions),
+			};
+			result.and_then(|()| output.flush())
+				.map_err(|e| format!("failed to write export: {}", e).into())
+		},
+		Command::Stats(options) => {
+			let data = options.filter.read(mmap)?;
+			let transactions = Transaction::parse_from_str(&data).map_err(|e| e.to_string())?;
+			let transactions: Vec<_> = transactions.into_iter().filter(|x| options.filter.matches(x)).collect();
+			let (start_date, end_date) = options.filter.date_range();
+			let series = stats::monthly_totals(&transactions, &options.accounts, options.split, start_date, end_date);
+			match (format, options.sparkline) {
+				(OutputFormat::Text, false) => stats::print_bars(&series),
+				(OutputFormat::Text, true) => stats::print_sparklines(&series),
+				(OutputFormat::Json, _) => stats::series_json(&series).print(),
+			}
+			Ok(())
+		},
+		Command::Fmt(options) => {
+			let mut unformatted = 0;
+			for file in &options.files {
+				let data = read_file(file)?;
+				let formatted = zzp::grootboek::normalize(&data).map_err(|e| format!("{:?}: {}", file, e))?;
+				if options.stdout {
+					print!("{}", formatted);
+				} else if formatted == data {
+					continue;
+				} else if options.check {
+					println!("{} {}", Paint::yellow("Not formatted:").bold(), file.display());
+					unformatted += 1;
+				} else {
+					std::fs::write(file, formatted)
+						.map_err(|e| format!("failed to write to {:?}: {}", file, e))?;
+				}
+			}
+			if unformatted != 0 {
+				Err(format!("Found {} files that are not formatted, run grootboek fmt to format them.", unformatted).into())
+			} else {
+				Ok(())
+			}
+		},
+		Command::Index(options) => {
+			for file in &options.files {
+				let index = zzp::index::write_index(file, FileKind::Grootboek)?;
+				if index.sorted {
+					println!("{} {}", Paint::green("Indexed:").bold(), file.display());
+				} else {
+					println!("{} {}: the transactions are not sorted by date, so the index is not used until the file is sorted with grootboek fmt", Paint::yellow("Unsorted:").bold(), file.display());
+				}
+			}
+			Ok(())
+		},
+	}
+}
+
+fn check_balance<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>) -> Result<(), Error> {
+	let mut unbalanced_transactions = 0;
+	for (transaction, balance) in find_unbalanced(transactions) {
+		zzp_tools::grootboek::print_full_colored(&transaction);
+		println!("{prefix} {balance}",
+			prefix = Paint::red("Unbalanced amount:").bold(),
+			balance = color_cents(balance),
+		);
+		unbalanced_transactions += 1;
+		println!()
+	}
+
+	if unbalanced_transactions != 0 {
+		Err(format!("Found {} unbalanced transactions.", unbalanced_transactions).into())
+	} else {
+		Ok(())
+	}
+}
+
+fn check_attachments(transactions: &[Transaction], options: &CheckOptions) -> Result<(), Error> {
+	let file = options.filter.file.canonicalize()
+		.map_err(|e| format!("failed to resolve path {:?}: {}", options.filter.file, e))?;
+	let grootboek_dir = file.parent()
+		.ok_or_else(|| format!("failed to determine parent directory of {:?}", file))?;
+
+	// The configuration is only needed for the defaults.
+	let config = if options.attachment_tag.is_empty() || options.attachment_dir.is_none() {
+		match ZzpConfig::find("/", grootboek_dir) {
+			Some(config_path) => Some(ZzpConfig::read_file(&config_path)?),
+			None => None,
+		}
+	} else {
+		None
+	};
+
+	let labels = if options.attachment_tag.is_empty() {
+		let config = config.as_ref()
+			.ok_or("could not find zzp.toml to determine the attachment tag, use --attachment-tag to specify it")?;
+		vec![config.invoice.grootboek_tag.clone()]
+	} else {
+		options.attachment_tag.clone()
+	};
+	let labels: Vec<_> = labels.iter().map(|x| x.as_str()).collect();
+
+	let attachments_dir = match &options.attachment_dir {
+		Some(dir) => Some(dir.clone()),
+		None => config.as_ref()
+			.and_then(|x| x.grootboek.attachments_directory.as_ref())
+			.map(|x| grootboek_dir.join(x)),
+	};
+
+	let selected = |x: &Transaction| options.filter.matches(x);
+	let mut check = zzp_tools::grootboek::check_attachments(transactions, selected, &labels, grootboek_dir, attachments_dir.as_deref())
+		.map_err(|e| format!("failed to check attachments: {}", e))?;
+	let index_file = zzp::index::index_path(&file);
+	check.orphaned.retain(|x| *x != file && *x != index_file);
+
+	for dangling in &check.dangling {
+		println!("{prefix} {date}: {desc}: {label}: {value}",
+			prefix = Paint::red("Missing document:").bold(),
+			date = Paint::cyan(dangling.date),
+			desc = Paint::magenta(dangling.description),
+			label = Paint::cyan(dangling.tag.label),
+			value = Paint::cyan(dangling.tag.value),
+		);
+	}
+	for orphan in &check.orphaned {
+		let path = orphan.strip_prefix(grootboek_dir).unwrap_or(orphan);
+		println!("{prefix} {path}",
+			prefix = Paint::yellow("Unreferenced document:").bold(),
+			path = path.display(),
+		);
+	}
+
+	if check.is_ok() {
+		Ok(())
+	} else {
+		Err(format!("Found {} missing and {} unreferenced documents.", check.dangling.len(), check.orphaned.len()).into())
+	}
+}
+
+fn print_transactions<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>) -> Result<(), Error> {
+	let stdout = std::io::stdout();
+	let mut stdout = stdout.lock();
+	for (i, transaction) in transactions.into_iter().enumerate() {
+		if i > 0 {
+			writeln!(stdout).map_err(|e| format!("failed to write to stdout: {}", e))?;
+		}
+		zzp_tools::grootboek::write_full_colored(&mut stdout, &transaction)
+			.map_err(|e| format!("failed to write to stdout: {}", e))?;
+	}
+	Ok(())
+}
+
+fn print_register<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>, account: &str) {
+	for row in register(transactions, account) {
+		println!("{date}  {amount}  {balance}  {account}  {desc}",
+			date = Paint::cyan(row.date),
+			amount = color_cents(row.amount).style().paint(format!("{:>12}", row.amount.to_string())),
+			balance = color_cents(row.balance).style().paint(format!("{:>12}", row.balance.to_string())).bold(),
+			account = row.account,
+			desc = Paint::magenta(row.description),
+		);
+	}
+}
+
+fn register_json<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>, account: &str) -> Json {
+	let rows = register(transactions, account).into_iter().map(|row| {
+		Json::object([
+			("date", row.date.into()),
+			("description", row.description.into()),
+			("account", row.account.as_str().into()),
+			("amount", row.amount.into()),
+			("balance", row.balance.into()),
+		])
+	});
+	Json::Array(rows.collect())
+}
+
+fn print_vat_return(vat_return: &VatReturn, period: &str) {
+	let euros = |amount: Cents| format!("{:>10}", amount.total_cents() / 100);
+	let description_width = vat_return.boxes.iter()
+		.map(|x| x.description.chars().count())
+		.chain(["5a", "5b", "5c", "5g"].iter().filter_map(|x| zzp_tools::vat_return::default_box_description(x)).map(|x| x.chars().count()))
+		.max()
+		.unwrap_or(0);
+
+	println!("{}", Paint::default(format!("VAT return {}", period)).bold());
+	println!();
+	println!("{:<4}  {:<width$}  {:>10}  {:>10}",
+		"Box", "", "Turnover", "VAT",
+		width = description_width,
+	);
+	for line in &vat_return.boxes {
+		println!("{name}  {description:<width$}  {turnover}  {vat}",
+			name = Paint::cyan(format!("{:<4}", line.name)),
+			description = line.description,
+			width = description_width,
+			turnover = line.turnover.map(|x| euros(whole_euros(x, RoundingMode::Down))).unwrap_or_else(|| " ".repeat(10)),
+			vat = line.vat.map(|x| euros(whole_euros(x, RoundingMode::Down))).unwrap_or_else(|| " ".repeat(10)),
+		);
+	}
+	println!();
+
+	let totals = [
+		("5a", vat_return.total_vat()),
+		("5b", vat_return.total_input_vat()),
+		("5c", vat_return.vat_due()),
+		("5g", vat_return.vat_due()),
+	];
+	for (name, amount) in totals {
+		println!("{name}  {description:<width$}  {padding}  {amount}",
+			name = Paint::cyan(format!("{:<4}", name)),
+			description = zzp_tools::vat_return::default_box_description(name).unwrap_or(""),
+			width = description_width,
+			padding = " ".repeat(10),
+			amount = color_cents(-amount).style().paint(euros(amount)).bold(),
+		);
+	}
+}
+
+fn print_suppletie(declaration: &VatDeclaration, vat_return: &VatReturn, period: &str) {
+	let euros = |amount: Option<Cents>| amount.map(|x| format!("{:>10}", x.total_cents() / 100)).unwrap_or_else(|| " ".repeat(10));
+	let rounded = |amount: Option<Cents>| amount.map(|x| whole_euros(x, RoundingMode::Down));
+	let description_width = vat_return.boxes.iter()
+		.map(|x| x.description.chars().count())
+		.chain(["5a", "5b", "5c"].iter().filter_map(|x| zzp_tools::vat_return::default_box_description(x)).map(|x| x.chars().count()))
+		.max()
+		.unwrap_or(0);
+	let paint_current = |declared: Option<Cents>, current: Option<Cents>| {
+		if declared == current {
+			Paint::default(euros(current))
+		} else {
+			Paint::yellow(euros(current)).bold()
+		}
+	};
+
+	println!("{}", Paint::default(format!("VAT correction {} (filed {})", period, declaration.date)).bold());
+	println!();
+	println!("{:<4}  {:<width$}  {:>21}  {:>21}",
+		"Box", "", "Turnover", "VAT",
+		width = description_width,
+	);
+	println!("{:<4}  {:<width$}  {:>10} {:>10}  {:>10} {:>10}",
+		"", "", "declared", "current", "declared", "current",
+		width = description_width,
+	);
+	for line in &vat_return.boxes {
+		let declared = declaration.boxes.iter().find(|x| x.name == line.name);
+		let declared_turnover = declared.and_then(|x| x.turnover_cents).map(Cents);
+		let declared_vat = declared.and_then(|x| x.vat_cents).map(Cents);
+		println!("{name}  {description:<width$}  {declared_turnover} {turnover}  {declared_vat} {vat}",
+			name = Paint::cyan(format!("{:<4}", line.name)),
+			description = line.description,
+			width = description_width,
+			declared_turnover = euros(declared_turnover),
+			turnover = paint_current(declared_turnover, rounded(line.turnover)),
+			declared_vat = euros(declared_vat),
+			vat = paint_current(declared_vat, rounded(line.vat)),
+		);
+	}
+	println!();
+
+	let totals = [
+		("5a", Cents(declaration.total_vat_cents), vat_return.total_vat()),
+		("5b", Cents(declaration.total_input_vat_cents), vat_return.total_input_vat()),
+		("5c", declaration.vat_due(), vat_return.vat_due()),
+	];
+	for (name, declared, current) in totals {
+		println!("{name}  {description:<width$}  {padding}  {declared} {current}",
+			name = Paint::cyan(format!("{:<4}", name)),
+			description = zzp_tools::vat_return::default_box_description(name).unwrap_or(""),
+			width = description_width,
+			padding = " ".repeat(21),
+			declared = euros(Some(declared)),
+			current = paint_current(Some(declared), Some(current)),
+		);
+	}
+	println!();
+
+	let difference = vat_return.vat_due() - declaration.vat_due();
+	println!("{} {}", Paint::default("Difference in VAT to pay:").bold(), color_cents(-difference).style().paint(difference.total_cents() / 100).bold());
+	if difference == Cents(0) {
+		println!("{}", Paint::green("No correction needed."));
+	} else if difference.total_cents().abs() <= CORRECTION_THRESHOLD.total_cents() {
+		println!("The difference is at most {} euros: you may correct it in the next VAT return instead of filing a suppletie.", CORRECTION_THRESHOLD.total_cents() / 100);
+	} else {
+		println!("{}", Paint::red(format!("The difference is more than {} euros: file a suppletie for {}.", CORRECTION_THRESHOLD.total_cents() / 100, period)).bold());
+	}
+}
+
+fn suppletie_json(declaration: &VatDeclaration, vat_return: &VatReturn, period: &str) -> Json {
+	let difference = vat_return.vat_due() - declaration.vat_due();
+	Json::object([
+		("period", period.into()),
+		("filed", declaration.date.into()),
+		("boxes", Json::array(vat_return.boxes.iter().map(|line| {
+			let declared = declaration.boxes.iter().find(|x| x.name == line.name);
+			Json::object([
+				("name", line.name.into()),
+				("declared_turnover", declared.and_then(|x| x.turnover_cents).map(Cents).into()),
+				("turnover", line.turnover.map(|x| whole_euros(x, RoundingMode::Down)).into()),
+				("declared_vat", declared.and_then(|x| x.vat_cents).map(Cents).into()),
+				("vat", line.vat.map(|x| whole_euros(x, RoundingMode::Down)).into()),
+			])
+		}))),
+		("declared_vat_due", declaration.vat_due().into()),
+		("vat_due", vat_return.vat_due().into()),
+		("difference", difference.into()),
+		("suppletie_required", (difference.total_cents().abs() > CORRECTION_THRESHOLD.total_cents()).into()),
+	])
+}
+
+fn print_icp_declaration(declaration: &IcpDeclaration, period: &str, goods: bool) {
+	let euros = |amount: Cents| format!("{:>10}", amount.total_cents() / 100);
+	let number_width = declaration.lines.iter()
+		.map(|x| x.vat_number.len())
+		.chain(["Btw-identificatienummer".len()])
+		.max()
+		.unwrap_or(0);
+
+	println!("{}", Paint::default(format!("Opgaaf intracommunautaire prestaties {}", period)).bold());
+	println!();
+	println!("{}", Paint::cyan(if goods { "Intracommunautaire leveringen" } else { "Intracommunautaire diensten" }).bold());
+	println!("{:<8}  {:<width$}  {:>10}", "Landcode", "Btw-identificatienummer", "Bedrag", width = number_width);
+	for line in &declaration.lines {
+		println!("{:<8}  {:<width$}  {}  {}",
+			line.country_code,
+			line.vat_number,
+			euros(whole_euros(line.amount, RoundingMode::Down)),
+			Paint::fixed(241, line.customer.as_deref().unwrap_or("")),
+			width = number_width,
+		);
+	}
+	let total = declaration.total();
+	println!("{}  {}",
+		Paint::default(format!("{:<width$}", "Totaal", width = number_width + 10)).bold(),
+		Paint::default(euros(whole_euros(total, RoundingMode::Down))).bold(),
+	);
+}
+
+fn print_income_tax(income_tax: &IncomeTax, period: &str) {
+	let euros = |amount: Cents| format!("{:>10}", amount.total_cents() / 100);
+	let name_width = income_tax.fields.iter().map(|x| x.name.chars().count()).max().unwrap_or(0);
+	let description_width = income_tax.fields.iter()
+		.map(|x| x.description.chars().count())
+		.chain(["Winst"].iter().map(|x| x.len()))
+		.max()
+		.unwrap_or(0);
+
+	println!("{}", Paint::default(format!("Income tax return {}", period)).bold());
+	println!();
+	for line in &income_tax.fields {
+		println!("{name}  {description:<width$}  {amount}",
+			name = Paint::cyan(format!("{:<name_width$}", line.name, name_width = name_width)),
+			description = line.description,
+			width = description_width,
+			amount = euros(whole_euros(line.amount, RoundingMode::Down)),
+		);
+	}
+	println!();
+
+	let profit = income_tax.profit();
+	println!("{padding}  {description}  {amount}",
+		padding = " ".repeat(name_width),
+		description = Paint::default(format!("{:<width$}", "Winst", width = description_width)).bold(),
+		amount = color_cents(profit).style().paint(euros(whole_euros(profit, RoundingMode::Down))).bold(),
+	);
+}
+
+fn print_income_statement(statement: &IncomeStatement, period: &str) {
+	let name_width = statement.revenue.iter()
+		.chain(&statement.expenses)
+		.map(|x| x.name.chars().count())
+		.chain(["Total revenue", "Total expenses", "Net result"].iter().map(|x| x.len()))
+		.max()
+		.unwrap_or(0);
+	let amount = |x: Cents| format!("{:>12}", x.to_string());
+
+	println!("{}", Paint::default(format!("Income statement {}", period)).bold());
+	let sections = [
+		("Revenue", &statement.revenue, statement.total_revenue()),
+		("Expenses", &statement.expenses, statement.total_expenses()),
+	];
+	for (title, lines, total) in sections {
+		println!();
+		println!("{}", Paint::cyan(title).bold());
+		for line in lines {
+			println!("  {:<width$}  {}", line.name, amount(line.amount), width = name_width);
+		}
+		println!("  {}  {}",
+			Paint::default(format!("{:<width$}", format!("Total {}", title.to_lowercase()), width = name_width)).bold(),
+			Paint::default(amount(total)).bold(),
+		);
+	}
+
+	let net_result = statement.net_result();
+	println!();
+	println!("  {}  {}",
+		Paint::default(format!("{:<width$}", "Net result", width = name_width)).bold(),
+		color_cents(net_result).style().paint(amount(net_result)).bold(),
+	);
+}
+
+fn main() {
+	let args: Vec<_> = std::env::args_os().collect();
+	let mut options = match Options::from_iter_safe(&args) {
+		Ok(options) => options,
+		Err(error) => match legacy_args(&args, &error) {
+			Some(options) => options,
+			None => error.exit(),
+		},
+	};
+	let result = match options.command.filter_mut() {
+		Some(filter) => filter.resolve(),
+		None => Ok(()),
+	};
+	if let Err(error) = result.and_then(|()| do_main(&options)) {
+		eprintln!("Error: {}", error);
+		std::process::exit(1);
+	}
+}
+
+/// Parse the command line from before the subcommands were added.
+///
+/// `grootboek FILE [--check] [OPTIONS]` is the same as `grootboek totals FILE [OPTIONS]`,
+/// or `grootboek check FILE [OPTIONS]` if `--check` is given.
+/// Returns `None` if the arguments are not valid in the old form either.
+fn legacy_args(args: &[OsString], error: &structopt::clap::Error) -> Option<Options> {
+	use structopt::clap::ErrorKind;
+
+	// The old form gives the file where a subcommand is expected.
+	if !matches!(error.kind, ErrorKind::UnknownArgument | ErrorKind::UnrecognizedSubcommand | ErrorKind::InvalidSubcommand) {
+		return None;
+	}
+
+	let check = args.iter().any(|x| x == "--check" || x == "-c");
+	let mut args: Vec<_> = args.iter()
+		.filter(|x| *x != "--check" && *x != "-c")
+		.cloned()
+		.collect();
+	let subcommand = if check { "check" } else { "totals" };
+	args.insert(1.min(args.len()), subcommand.into());
+	Options::from_iter_safe(args).ok()
+}
+
+fn print_totals(totals: &AccountTree<Cents>) {
+	println!("Total: {}", color_cents(totals.root.data));
+	print_totals_subtree(&totals.root, "");
+}
+
+/// Get the totals as JSON, with an object for every account including the parent accounts.
+fn totals_json(totals: &AccountTree<Cents>) -> Json {
+	fn add_accounts(node: &AccountNode<Cents>, output: &mut Vec<Json>) {
+		for child in &node.children {
+			output.push(Json::object([
+				("account", child.account.as_str().into()),
+				("total", child.data.into()),
+			]));
+			add_accounts(child, output);
+		}
+	}
+
+	let mut accounts = Vec::new();
+	add_accounts(&totals.root, &mut accounts);
+	Json::object([
+		("total", totals.root.data.into()),
+		("accounts", Json::Array(accounts)),
+	])
+}
+
+fn print_totals_subtree(node: &AccountNode<Cents>, indent: &str) {
+	for (i, child) in node.children.iter().enumerate() {
+		let (tree_char, subindent) = if i == node.children.len() - 1 {
+			("└─", "   ")
+		} else {
+			("├─", "│  ")
+		};
+
+		println!("{}{} {}: {}", indent, tree_char, child.account.name(), color_cents(child.data));
+		print_totals_subtree(child, &format!("{}{}", indent, subindent));
+	}
+}
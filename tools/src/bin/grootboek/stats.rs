@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use yansi::Paint;
+
+use zzp::gregorian::{Date, YearMonth};
+use zzp::grootboek::{Cents, Transaction};
+use zzp_tools::grootboek::color_cents;
+use zzp_tools::json::Json;
+
+/// The width of the longest bar in a bar chart.
+const BAR_WIDTH: usize = 40;
+
+/// The monthly totals of an account.
+pub(crate) struct Series {
+	pub account: String,
+	pub months: Vec<(YearMonth, Cents)>,
+}
+
+/// Compute the monthly totals of the mutations of accounts and their sub-accounts.
+///
+/// If `split` is true, each direct sub-account of the given accounts gets its own series.
+/// The series cover all months from `start` until `end` (exclusive),
+/// or from the first to the last month with mutations if those are not given.
+pub(crate) fn monthly_totals(transactions: &[Transaction], accounts: &[String], split: bool, start: Option<Date>, end: Option<Date>) -> Vec<Series> {
+	let mut totals: BTreeMap<String, BTreeMap<YearMonth, Cents>> = BTreeMap::new();
+	for account in accounts {
+		let account = account.trim_end_matches('/');
+		if !split {
+			totals.entry(account.to_string()).or_default();
+		}
+		let depth = account.matches('/').count() + 1;
+		for transaction in transactions {
+			for mutation in transaction.mutations.iter().filter(|x| x.account.matches_prefix(account)) {
+				let name = if split {
+					mutation.account.walk_nodes().nth(depth).unwrap_or(mutation.account).as_str()
+				} else {
+					account
+				};
+				*totals.entry(name.to_string())
+					.or_default()
+					.entry(transaction.date.year_month())
+					.or_insert(Cents(0)) += mutation.amount;
+			}
+		}
+	}
+
+	let first = start.map(|x| x.year_month())
+		.or_else(|| totals.values().filter_map(|x| x.keys().next()).min().copied());
+	let last = end.map(|x| x.prev().year_month())
+		.or_else(|| totals.values().filter_map(|x| x.keys().next_back()).max().copied());
+	let months = match (first, last) {
+		(Some(first), Some(last)) => std::iter::successors(Some(first), |x| Some(x.next()))
+			.take_while(|x| *x <= last)
+			.collect(),
+		_ => Vec::new(),
+	};
+
+	totals.into_iter()
+		.map(|(account, totals)| Series {
+			months: months.iter().map(|month| (*month, totals.get(month).copied().unwrap_or(Cents(0)))).collect(),
+			account,
+		})
+		.collect()
+}
+
+/// Print a bar chart of the monthly totals of each series.
+///
+/// The bars of all series use the same scale, and show the absolute amount colored by sign.
+pub(crate) fn print_bars(series: &[Series]) {
+	let max = max_abs(series);
+	for (i, series) in series.iter().enumerate() {
+		if i > 0 {
+			println!();
+		}
+		println!("{}", Paint::default(&series.account).bold());
+		for (month, amount) in &series.months {
+			let eighths = scale(*amount, max, BAR_WIDTH * 8);
+			let bar = format!("{:<width$}", bar(eighths), width = BAR_WIDTH);
+			println!("{month}  {bar}  {amount}",
+				month = Paint::cyan(month),
+				bar = color_cents(*amount).style().paint(bar),
+				amount = color_cents(*amount).style().paint(format!("{:>12}", amount.to_string())),
+			);
+		}
+		let total: Cents = series.months.iter().map(|(_, amount)| *amount).sum();
+		println!("{:7}  {:width$}  {}", "Total", "", color_cents(total).style().paint(format!("{:>12}", total.to_string())).bold(), width = BAR_WIDTH);
+	}
+}
+
+/// Print a sparkline of the monthly totals of each series.
+///
+/// Each sparkline is scaled to its own largest absolute amount.
+pub(crate) fn print_sparklines(series: &[Series]) {
+	const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+	if let (Some((first, _)), Some((last, _))) = (series.first().and_then(|x| x.months.first()), series.first().and_then(|x| x.months.last())) {
+		println!("{} {} - {}", Paint::default("Months:").bold(), Paint::cyan(first), Paint::cyan(last));
+	}
+	let name_width = series.iter().map(|x| x.account.chars().count()).max().unwrap_or(0);
+	for series in series {
+		let max = max_abs(std::slice::from_ref(series));
+		let line: String = series.months.iter()
+			.map(|(_, amount)| match scale(*amount, max, LEVELS.len()) {
+				0 if amount.total_cents() == 0 => ' ',
+				x => LEVELS[x.clamp(1, LEVELS.len()) - 1],
+			})
+			.collect();
+		let total: Cents = series.months.iter().map(|(_, amount)| *amount).sum();
+		println!("{:<width$}  {}  {}", series.account, line, color_cents(total).style().paint(format!("{:>12}", total.to_string())), width = name_width);
+	}
+}
+
+/// Get the monthly totals of each series as JSON.
+pub(crate) fn series_json(series: &[Series]) -> Json {
+	Json::array(series.iter().map(|series| Json::object([
+		("account", series.account.as_str().into()),
+		("months", Json::array(series.months.iter().map(|(month, amount)| Json::object([
+			("month", month.to_string().into()),
+			("total", (*amount).into()),
+		])))),
+		("total", series.months.iter().map(|(_, amount)| *amount).sum::<Cents>().into()),
+	])))
+}
+
+/// Get the largest absolute monthly total of all series.
+fn max_abs(series: &[Series]) -> u32 {
+	series.iter()
+		.flat_map(|x| &x.months)
+		.map(|(_, amount)| amount.total_cents().unsigned_abs())
+		.max()
+		.unwrap_or(0)
+}
+
+/// Scale the absolute value of an amount to the range `0..=steps`, rounding up so non-zero amounts are visible.
+fn scale(amount: Cents, max: u32, steps: usize) -> usize {
+	if max == 0 {
+		return 0;
+	}
+	let amount = u64::from(amount.total_cents().unsigned_abs());
+	(amount * steps as u64).div_ceil(u64::from(max)) as usize
+}
+
+/// Draw a horizontal bar with a length in eighths of a character.
+fn bar(eighths: usize) -> String {
+	const PARTIAL: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+	let mut bar = "█".repeat(eighths / 8);
+	let partial = eighths % 8;
+	if partial > 0 {
+		bar.push(PARTIAL[partial - 1]);
+	}
+	bar
+}
@@ -0,0 +1,115 @@
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp::gregorian::Date;
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct EditOptions {
+	/// The file with hour log entries [default: the hour log of the customer for the date].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// The date to add or edit entries for instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+}
+
+pub(crate) fn edit(options: EditOptions) -> Result<(), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let date = options.date.unwrap_or_else(Date::today);
+	let file = match options.file {
+		Some(file) => current_dir.join(file),
+		None => super::default_uurlog_file(&current_dir, date)?,
+	};
+
+	// Insert an empty entry for the date after the last entry on or before that date.
+	let data = match std::fs::read_to_string(&file) {
+		Ok(data) => data,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+		Err(e) => return Err(format!("failed to read {}: {}", file.display(), e).into()),
+	};
+	let template = format!("{},", date);
+	let mut lines: Vec<&str> = data.lines().collect();
+	let position = lines.iter()
+		.rposition(|line| line_date(line).map(|x| x <= date).unwrap_or(false))
+		.map(|i| i + 1)
+		.unwrap_or_else(|| if lines.iter().any(|line| line_date(line).is_some()) { 0 } else { lines.len() });
+	lines.insert(position, &template);
+	write_lines(&file, &lines)?;
+
+	loop {
+		run_editor(&file, position + 1)?;
+
+		// Remove the empty entry again if it was not filled in.
+		let data = std::fs::read_to_string(&file)
+			.map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+		let lines: Vec<&str> = data.lines().collect();
+		if lines.iter().any(|line| line.trim() == template) {
+			let lines: Vec<&str> = lines.into_iter().filter(|line| line.trim() != template).collect();
+			write_lines(&file, &lines)?;
+		}
+
+		match zzp::uurlog::parse_file(&file) {
+			Ok(_) => return Ok(()),
+			Err(e) => {
+				log::error!("invalid entry in {}: {}", file.display(), e);
+				if !ask_edit_again()? {
+					return Err(format!("{} still has an invalid entry", file.display()).into());
+				}
+			},
+		}
+	}
+}
+
+/// Get the date of an hour log line, if it starts with a valid date.
+fn line_date(line: &str) -> Option<Date> {
+	line.split(',').next()?.trim().parse().ok()
+}
+
+fn write_lines(path: &Path, lines: &[&str]) -> Result<(), Error> {
+	let mut data = lines.join("\n");
+	data.push('\n');
+	std::fs::write(path, data)
+		.map_err(|e| format!("failed to write to {}: {}", path.display(), e).into())
+}
+
+/// Open a file in the editor of the user, with the cursor on the given line.
+///
+/// The editor is taken from `$VISUAL` or `$EDITOR`, and defaults to `vi`.
+fn run_editor(path: &Path, line: usize) -> Result<(), Error> {
+	let editor = std::env::var("VISUAL")
+		.or_else(|_| std::env::var("EDITOR"))
+		.unwrap_or_else(|_| String::from("vi"));
+	let mut args = editor.split_whitespace();
+	let program = args.next()
+		.ok_or("the configured editor is empty")?;
+
+	let status = std::process::Command::new(program)
+		.args(args)
+		.arg(format!("+{}", line))
+		.arg(path)
+		.status()
+		.map_err(|e| format!("failed to run {}: {}", program, e))?;
+	if !status.success() {
+		return Err(format!("{} exited with {}", program, status).into());
+	}
+	Ok(())
+}
+
+fn ask_edit_again() -> Result<bool, Error> {
+	eprint!("Edit the file again? [Y/n] ");
+	let mut answer = String::new();
+	std::io::stdin().lock().read_line(&mut answer)
+		.map_err(|e| format!("failed to read from standard input: {}", e))?;
+	let answer = answer.trim();
+	Ok(answer.is_empty() || answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp::date_range::DateRange;
+use zzp::partial_date::PartialDate;
+use zzp_tools::ical::Calendar;
+use zzp_tools::period::Period;
+
+use super::{Error, read_uurlog};
+
+#[derive(StructOpt)]
+pub enum ExportCommand {
+	/// Export hour entries as iCalendar file, with an all-day event for each entry.
+	Ical(IcalOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct IcalOptions {
+	/// The file with hour log entries [default: the hour logs of the customer].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// The period to export, like 2024-05, last-month or the name of a custom period.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY[-MM[-DD]]")]
+	period: Option<Period>,
+
+	/// Only export hour entries from this date or later.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	start_date: Option<PartialDate>,
+
+	/// Only export hour entries from this date or earlier.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	end_date: Option<PartialDate>,
+
+	/// Only export hour entries in this date range, like 2024-01-15..2024-02-03 or ..2024-06-30.
+	#[structopt(long)]
+	#[structopt(value_name = "[START]..[END]")]
+	#[structopt(conflicts_with_all = &["period", "start-date", "end-date"])]
+	dates: Option<DateRange>,
+
+	/// Write the calendar to this file instead of standard output.
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE.ics")]
+	output: Option<PathBuf>,
+}
+
+pub(crate) fn run(command: ExportCommand) -> Result<(), Error> {
+	match command {
+		ExportCommand::Ical(x) => export_ical(x),
+	}
+}
+
+fn export_ical(options: IcalOptions) -> Result<(), Error> {
+	let (start_date, end_date) = super::period_bounds(options.period.as_ref(), options.start_date, options.end_date, options.dates)?;
+
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let (name, entries) = match &options.file {
+		Some(file) => (None, read_uurlog(&current_dir.join(file), start_date, end_date)?),
+		None => {
+			let (customer_config_path, customer_config) = super::find_customer_config(&current_dir)?;
+			let entries = super::read_customer_uurlog(&customer_config, customer_config_path.parent().unwrap(), start_date, end_date)?;
+			(Some(customer_config.customer.name), entries)
+		},
+	};
+
+	let calendar = Calendar::from_entries(name, &entries);
+	match &options.output {
+		Some(path) => {
+			std::fs::write(path, calendar.to_string())
+				.map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+			log::info!("exported {} entries to {}", entries.len(), path.display());
+		},
+		None => print!("{}", calendar),
+	}
+	Ok(())
+}
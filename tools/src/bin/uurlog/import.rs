@@ -0,0 +1,307 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::date_range::DateRange;
+use zzp::gregorian::Date;
+use zzp::partial_date::PartialDate;
+use zzp::uurlog::Entry;
+use zzp_tools::CalendarRule;
+use zzp_tools::ical::{ParsedCalendar, ParsedEvent};
+use zzp_tools::period::Period;
+use zzp_tools::toggl::ConvertOptions;
+
+use super::{Error, format_iterator};
+
+#[derive(StructOpt)]
+pub enum ImportCommand {
+	/// Import the detailed CSV export of Toggl Track.
+	Toggl(TogglOptions),
+
+	/// Import calendar events that match the [[CalendarRule]] sections of customer.toml.
+	Ical(IcalOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct TogglOptions {
+	/// The CSV file exported from Toggl Track.
+	#[structopt(value_name = "CSV_FILE")]
+	input: PathBuf,
+
+	/// The file with hour log entries [default: the hour log of the customer for today].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// Add the Toggl project of each entry as tag.
+	#[structopt(long)]
+	project_tag: bool,
+
+	/// Merge entries with the same date, tags and description into a single entry.
+	#[structopt(long)]
+	merge_per_day: bool,
+
+	/// Only print the converted entries, without adding them to the hour log.
+	#[structopt(long)]
+	dry_run: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct IcalOptions {
+	/// The iCalendar file, or - to read standard input, like from `curl https://calendar.example.com/work.ics`.
+	#[structopt(value_name = "ICS_FILE")]
+	input: PathBuf,
+
+	/// The file with hour log entries [default: the hour logs of the customer].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// The period to import, like 2024-05, last-week or the name of a custom period.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY[-MM[-DD]]")]
+	period: Option<Period>,
+
+	/// Only import events from this date or later.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	start_date: Option<PartialDate>,
+
+	/// Only import events from this date or earlier.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	end_date: Option<PartialDate>,
+
+	/// Only import events in this date range, like 2024-01-15..2024-02-03 or ..2024-06-30.
+	#[structopt(long)]
+	#[structopt(value_name = "[START]..[END]")]
+	#[structopt(conflicts_with_all = &["period", "start-date", "end-date"])]
+	dates: Option<DateRange>,
+
+	/// Add the entries without asking for confirmation.
+	#[structopt(long, short)]
+	yes: bool,
+
+	/// Only print the converted entries, without adding them to the hour log.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "yes")]
+	dry_run: bool,
+}
+
+pub(crate) fn run(command: ImportCommand) -> Result<(), Error> {
+	match command {
+		ImportCommand::Toggl(x) => import_toggl(x),
+		ImportCommand::Ical(x) => import_ical(x),
+	}
+}
+
+fn import_toggl(options: TogglOptions) -> Result<(), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let file = match &options.file {
+		Some(file) => current_dir.join(file),
+		None => super::default_uurlog_file(&current_dir, Date::today())?,
+	};
+
+	let data = std::fs::read_to_string(&options.input)
+		.map_err(|e| format!("failed to read {}: {}", options.input.display(), e))?;
+	let toggl_entries = zzp_tools::toggl::parse_csv(&data)
+		.map_err(|e| format!("failed to parse {}: {}", options.input.display(), e))?;
+	let entries = zzp_tools::toggl::convert(&toggl_entries, ConvertOptions {
+		project_tag: options.project_tag,
+		merge_per_day: options.merge_per_day,
+	});
+
+	for entry in &entries {
+		println!("{date}, {hours}, {tags}{description}",
+			date = Paint::cyan(entry.date),
+			hours = Paint::red(entry.hours),
+			tags = Paint::yellow(format_iterator(&entry.tags, "[", "] [", "] ")),
+			description = entry.description,
+		);
+	}
+
+	if options.dry_run {
+		return Ok(());
+	}
+
+	zzp::uurlog::append_entries(&file, &entries)
+		.map_err(|e| format!("failed to add entries to {}: {}", file.display(), e))?;
+	log::info!("added {} entries to {}", entries.len(), file.display());
+	Ok(())
+}
+
+fn import_ical(options: IcalOptions) -> Result<(), Error> {
+	let (start_date, end_date) = super::period_bounds(options.period.as_ref(), options.start_date, options.end_date, options.dates)?;
+
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let (customer_config_path, customer_config) = super::find_customer_config(&current_dir)?;
+	let customer_dir = customer_config_path.parent().unwrap();
+	if customer_config.calendar_rule.is_empty() {
+		return Err(format!("{} has no [[CalendarRule]] sections to select the events to import", customer_config_path.display()).into());
+	}
+	let rules = customer_config.calendar_rule.iter()
+		.map(|rule| {
+			let title = rule.title.as_deref()
+				.map(Regex::new)
+				.transpose()
+				.map_err(|e| format!("invalid title regex in {}: {}", customer_config_path.display(), e))?;
+			Ok((rule, title))
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+
+	// Standard input can not be used for both the calendar and the confirmation.
+	if options.input == Path::new("-") && !options.yes && !options.dry_run {
+		return Err("use --yes or --dry-run when reading the calendar from standard input".into());
+	}
+	let data = read_input(&options.input)?;
+	let calendar = ParsedCalendar::parse(&data)
+		.map_err(|e| format!("failed to parse {}: {}", options.input.display(), e))?;
+
+	// Existing entries are not imported again.
+	let mut existing = match &options.file {
+		Some(file) if !current_dir.join(file).exists() => Vec::new(),
+		Some(file) => super::read_uurlog(&current_dir.join(file), None, None)?,
+		None => super::read_customer_uurlog(&customer_config, customer_dir, None, None)?,
+	};
+
+	let mut entries = Vec::new();
+	let mut duplicates = 0;
+	let mut recurring = 0;
+	for event in &calendar.events {
+		// Occurrences of a recurring event can fall in the period even if the first one does not, so check them before the period.
+		if event.recurring {
+			if rules.iter().any(|(rule, title)| matches_rule(rule, title.as_ref(), calendar.name.as_deref(), event)) {
+				log::warn!("skipping recurring event {:?} starting on {}", event.summary, event.date);
+				recurring += 1;
+			}
+			continue;
+		}
+		if start_date.map(|x| event.date < x).unwrap_or(false) || end_date.map(|x| event.date >= x).unwrap_or(false) {
+			continue;
+		}
+		let rule = match rules.iter().find(|(rule, title)| matches_rule(rule, title.as_ref(), calendar.name.as_deref(), event)) {
+			Some((rule, _)) => rule,
+			None => continue,
+		};
+		let entry = match convert_event(rule, event) {
+			Ok(entry) => entry,
+			Err(e) => {
+				log::warn!("skipping event {:?} on {}: {}", event.summary, event.date, e);
+				continue;
+			},
+		};
+		if is_duplicate(&existing, &entry) {
+			duplicates += 1;
+			continue;
+		}
+		existing.push(entry.clone());
+		entries.push(entry);
+	}
+	entries.sort_by_key(|x| x.date);
+
+	for entry in &entries {
+		println!("{date}, {hours}, {tags}{description}",
+			date = Paint::cyan(entry.date),
+			hours = Paint::red(entry.hours),
+			tags = Paint::yellow(format_iterator(&entry.tags, "[", "] [", "] ")),
+			description = entry.description,
+		);
+	}
+	if duplicates > 0 {
+		log::info!("skipped {} events that are already in the hour log", duplicates);
+	}
+	if recurring > 0 {
+		log::warn!("skipped {} recurring events: recurring events are not supported, add them to the hour log by hand", recurring);
+	}
+	if entries.is_empty() {
+		log::info!("no new entries to import");
+		return Ok(());
+	}
+	if options.dry_run || (!options.yes && !ask_confirmation(entries.len())?) {
+		return Ok(());
+	}
+
+	// The hour log of the customer can be split over multiple files by date.
+	let mut files: BTreeMap<PathBuf, Vec<Entry>> = BTreeMap::new();
+	for entry in entries {
+		let file = match &options.file {
+			Some(file) => current_dir.join(file),
+			None => customer_config.hours_file(customer_dir, entry.date)?,
+		};
+		files.entry(file).or_default().push(entry);
+	}
+	for (file, entries) in &files {
+		zzp::uurlog::append_entries(file, entries)
+			.map_err(|e| format!("failed to add entries to {}: {}", file.display(), e))?;
+		log::info!("added {} entries to {}", entries.len(), file.display());
+	}
+	Ok(())
+}
+
+/// Read the input file, or standard input for `-`.
+fn read_input(path: &Path) -> Result<String, Error> {
+	let mut data = String::new();
+	if path == Path::new("-") {
+		std::io::stdin().lock().read_to_string(&mut data)
+			.map_err(|e| format!("failed to read from standard input: {}", e))?;
+	} else {
+		data = std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+	}
+	Ok(data)
+}
+
+fn matches_rule(rule: &CalendarRule, title: Option<&Regex>, calendar: Option<&str>, event: &ParsedEvent) -> bool {
+	if rule.calendar.is_some() && rule.calendar.as_deref() != calendar {
+		return false;
+	}
+	title.map(|x| x.is_match(&event.summary)).unwrap_or(true)
+}
+
+/// Convert a calendar event to an hour log entry with the tags of the matching rule.
+fn convert_event(rule: &CalendarRule, event: &ParsedEvent) -> Result<Entry, String> {
+	let hours = match event.duration {
+		None => return Err("all-day events have no duration".into()),
+		Some(hours) if hours.total_minutes() == 0 => return Err("the event has no duration".into()),
+		Some(hours) => hours,
+	};
+	// Hour log entries are a single line.
+	let description = event.summary.split_whitespace().collect::<Vec<_>>().join(" ");
+	if description.is_empty() {
+		return Err("the event has no title".into());
+	}
+	Ok(Entry {
+		date: event.date,
+		hours,
+		tags: rule.tags.clone(),
+		description,
+	})
+}
+
+/// Check if an entry for the same work is already in the hour log.
+///
+/// The tags are not compared, so changing the tags of imported entries does not import them again.
+fn is_duplicate(existing: &[Entry], entry: &Entry) -> bool {
+	existing.iter().any(|x| x.date == entry.date && x.hours == entry.hours && x.description == entry.description)
+}
+
+fn ask_confirmation(count: usize) -> Result<bool, Error> {
+	eprint!("Add {} entries to the hour log? [y/N] ", count);
+	let mut answer = String::new();
+	std::io::stdin().lock().read_line(&mut answer)
+		.map_err(|e| format!("failed to read from standard input: {}", e))?;
+	let answer = answer.trim();
+	Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+}
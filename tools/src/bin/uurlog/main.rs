@@ -4,10 +4,25 @@ use structopt::clap;
 use yansi::Paint;
 use std::fmt::Display;
 
+use zzp::date_range::DateRange;
+use zzp::index::FileKind;
 use zzp::partial_date::PartialDate;
+use zzp_tools::period::Period;
 use zzp::uurlog::{Date, Entry, Hours};
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::{CustomerConfig, PeriodConfig, ZzpConfig};
 
+mod edit;
+mod export;
+mod import;
 mod invoice;
+mod report;
+mod timer;
+mod uninvoiced;
+mod views;
+
+/// An error of a subcommand, reported once by `main`.
+type Error = Box<dyn std::error::Error>;
 
 #[derive(StructOpt)]
 #[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
@@ -20,14 +35,66 @@ struct Options {
 	#[structopt(global = true)]
 	verbose: i8,
 
+	/// The administration to use from zzp-workspace.toml [env: ZZP_ADMINISTRATION].
+	#[structopt(long)]
+	#[structopt(global = true)]
+	#[structopt(value_name = "NAME")]
+	administration: Option<String>,
+
+	/// The output format of reports.
+	#[structopt(long)]
+	#[structopt(global = true)]
+	#[structopt(value_name = "FORMAT")]
+	#[structopt(possible_values = &["text", "json"])]
+	#[structopt(default_value = "text")]
+	format: OutputFormat,
+
 	#[structopt(subcommand)]
 	command: Command,
 }
 
 #[derive(StructOpt)]
+#[allow(clippy::large_enum_variant)]
 enum Command {
 	Show(ShowOptions),
 	Invoice(invoice::InvoiceOptions),
+
+	/// Start a timer for a new hour log entry.
+	Start(timer::StartOptions),
+
+	/// Stop the running timer and add the entry to the hour log.
+	Stop(timer::StopOptions),
+
+	/// Show the running timer and the elapsed time.
+	Status,
+
+	/// Open the hour log in your editor at the entries of a date.
+	Edit(edit::EditOptions),
+
+	/// Show the total hours per day, week, month, tag or description.
+	Report(report::ReportOptions),
+
+	/// Import hour entries from other time tracking tools.
+	Import(import::ImportCommand),
+
+	/// Export hour entries for use in other applications.
+	Export(export::ExportCommand),
+
+	/// Show the entries of today with a running total.
+	Today(views::ViewOptions),
+
+	/// Show the entries of the current week with a running total.
+	Week(views::ViewOptions),
+
+	/// Show the hours of all customers that have not been invoiced yet.
+	Uninvoiced(uninvoiced::UninvoicedOptions),
+
+	/// Create or update the index of hour logs, so commands with a period only read the entries of the period.
+	///
+	/// The index is stored next to the hour log, like hours.uurlog.index.
+	/// Once an hour log has an index, it is updated automatically when the hour log changes.
+	/// The index is only used if the entries in the hour log are sorted by date.
+	Index(IndexOptions),
 }
 
 #[derive(StructOpt)]
@@ -40,10 +107,10 @@ struct ShowOptions {
 	#[structopt(value_name = "FILE")]
 	file: PathBuf,
 
-	/// The period to synchronize.
+	/// The period to synchronize, like 2024-05, this-week or the name of a custom period.
 	#[structopt(long)]
 	#[structopt(value_name = "YYYY[-MM[-DD]]")]
-	period: Option<PartialDate>,
+	period: Option<Period>,
 
 	/// Only consider hour entries from this date or later.
 	#[structopt(long)]
@@ -56,13 +123,32 @@ struct ShowOptions {
 	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
 	#[structopt(conflicts_with = "period")]
 	end_date: Option<PartialDate>,
+
+	/// Only consider hour entries in this date range, like 2024-01-15..2024-02-03 or ..2024-06-30.
+	#[structopt(long)]
+	#[structopt(value_name = "[START]..[END]")]
+	#[structopt(conflicts_with_all = &["period", "start-date", "end-date"])]
+	dates: Option<DateRange>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+struct IndexOptions {
+	/// The hour logs to index.
+	#[structopt(required = true)]
+	#[structopt(value_name = "FILE")]
+	files: Vec<PathBuf>,
 }
 
 fn main() {
 	let options = Options::from_args();
 	init_logging(options.verbose);
+	zzp_tools::workspace::select_administration(options.administration.clone());
 
-	if do_main(options).is_err() {
+	if let Err(e) = do_main(options) {
+		log::error!("{}", e);
 		std::process::exit(1);
 	}
 }
@@ -83,23 +169,37 @@ fn init_logging(verbosity: i8) {
 	env_logger::from_env("RUST_LOG").filter_module(module_path!(), level).init();
 }
 
-fn do_main(options: Options) -> Result<(), ()> {
+fn do_main(options: Options) -> Result<(), Error> {
 	match options.command {
-		Command::Show(x) => show_entries(x),
-		Command::Invoice(x) => invoice::make_invoice(x),
+		Command::Show(x) => show_entries(x, options.format),
+		Command::Invoice(x) => Ok(invoice::make_invoice(x)?),
+		Command::Start(x) => timer::start(x),
+		Command::Stop(x) => timer::stop(x),
+		Command::Status => timer::status(options.format),
+		Command::Edit(x) => edit::edit(x),
+		Command::Report(x) => report::report(x, options.format),
+		Command::Import(x) => import::run(x),
+		Command::Export(x) => export::run(x),
+		Command::Today(x) => views::today(x, options.format),
+		Command::Week(x) => views::week(x, options.format),
+		Command::Uninvoiced(x) => uninvoiced::uninvoiced(x, options.format),
+		Command::Index(x) => write_index(x),
 	}
 }
 
-fn show_entries(options: ShowOptions) -> Result<(), ()> {
-	let mut start_date = options.start_date.map(|x| x.as_start_date());
-	let mut end_date = options.end_date.map(|x| x.as_end_date().next());
-	if let Some(period) = options.period {
-		let range = period.as_range();
-		start_date = Some(range.start);
-		end_date = Some(range.end);
-	};
+fn show_entries(options: ShowOptions, format: OutputFormat) -> Result<(), Error> {
+	let (start_date, end_date) = period_bounds(options.period.as_ref(), options.start_date, options.end_date, options.dates)?;
 
 	let entries = read_uurlog(&options.file, start_date, end_date)?;
+	if format == OutputFormat::Json {
+		let total = entries.iter().fold(Hours::from_minutes(0), |total, entry| total + entry.hours);
+		Json::object([
+			("entries", Json::array(entries.iter().map(entry_json))),
+			("total", total.into()),
+		]).print();
+		return Ok(());
+	}
+
 	let mut total = Hours::from_minutes(0);
 	for entry in entries {
 		total += entry.hours;
@@ -116,20 +216,89 @@ fn show_entries(options: ShowOptions) -> Result<(), ()> {
 	Ok(())
 }
 
-fn read_uurlog(path: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, ()> {
-	// Read all entries from the hour log.
-	let mut entries = zzp::uurlog::parse_file(path)
-		.map_err(|e| log::error!("failed to read hour entries from {}: {}", path.display(), e))?;
+fn read_uurlog(path: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, Error> {
+	Ok(zzp_tools::hours::read_hours_file(path, start_date, end_date)?)
+}
 
-	// Filter on date.
-	if let Some(start_date) = start_date {
-		entries.retain(|x| x.date >= start_date);
+/// Create or update the index of hour logs.
+fn write_index(options: IndexOptions) -> Result<(), Error> {
+	for file in &options.files {
+		let index = zzp::index::write_index(file, FileKind::Uurlog)?;
+		if index.sorted {
+			log::info!("indexed {}", file.display());
+		} else {
+			log::warn!("the entries in {} are not sorted by date, so the index is not used until they are sorted", file.display());
+		}
 	}
-	if let Some(end_date) = end_date {
-		entries.retain(|x| x.date < end_date);
+	Ok(())
+}
+
+/// Read the entries from all hour logs of a customer.
+///
+/// The `customer_dir` is the directory containing `customer.toml`.
+fn read_customer_uurlog(customer_config: &CustomerConfig, customer_dir: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, Error> {
+	Ok(zzp_tools::hours::read_customer_hours(customer_config, customer_dir, start_date, end_date)?)
+}
+
+/// Get an hour log entry as JSON.
+fn entry_json(entry: &Entry) -> Json {
+	Json::object([
+		("date", entry.date.into()),
+		("hours", entry.hours.into()),
+		("tags", Json::array(&entry.tags)),
+		("description", entry.description.as_str().into()),
+	])
+}
+
+/// Get the hour log of the customer in the working directory for entries on a date.
+fn default_uurlog_file(current_dir: &Path, date: Date) -> Result<PathBuf, Error> {
+	let (customer_config_path, customer_config) = find_customer_config(current_dir)?;
+	Ok(customer_config.hours_file(customer_config_path.parent().unwrap(), date)?)
+}
+
+/// Get the first date and the date after the last date to consider from the period options.
+fn period_bounds(period: Option<&Period>, start_date: Option<PartialDate>, end_date: Option<PartialDate>, dates: Option<DateRange>) -> Result<(Option<Date>, Option<Date>), String> {
+	if let Some(period) = period {
+		let periods = if period.is_named() { custom_periods()? } else { Vec::new() };
+		let range = period.as_range(&periods)?;
+		return Ok((Some(range.start), Some(range.end)));
+	}
+	if let Some(dates) = dates {
+		return Ok(dates.as_half_open());
+	}
+	Ok((start_date.map(|x| x.as_start_date()), end_date.map(|x| x.as_end_date().next())))
+}
+
+/// Read the custom periods for the working directory, including the periods of the customer if there is one.
+fn custom_periods() -> Result<Vec<PeriodConfig>, String> {
+	let current_dir = zzp_tools::workspace::current_dir()
+		.map_err(|e| e.to_string())?;
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)
+		.map_err(|e| e.to_string())?;
+	let mut zzp_config = ZzpConfig::read_file(&zzp_config_path)
+		.map_err(|e| e.to_string())?;
+	if let Some(customer_config_path) = CustomerConfig::find(zzp_config_path.parent().unwrap(), &current_dir) {
+		let customer_config = CustomerConfig::read_file(&customer_config_path)
+			.map_err(|e| e.to_string())?;
+		zzp_config.apply_customer_overrides(&customer_config);
 	}
+	Ok(zzp_config.period)
+}
+
+/// Read the entries from all hour logs of the customer in the working directory.
+fn read_default_uurlog(current_dir: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, Error> {
+	let (customer_config_path, customer_config) = find_customer_config(current_dir)?;
+	read_customer_uurlog(&customer_config, customer_config_path.parent().unwrap(), start_date, end_date)
+}
 
-	Ok(entries)
+/// Find and read the configuration of the customer in the working directory.
+fn find_customer_config(current_dir: &Path) -> Result<(PathBuf, CustomerConfig), Error> {
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(current_dir)?;
+	let root_dir = zzp_config_path.parent().unwrap();
+	let customer_config_path = CustomerConfig::find(root_dir, current_dir)
+		.ok_or("could not find customer.toml, use --file to select the hour log")?;
+	let customer_config = CustomerConfig::read_file(&customer_config_path)?;
+	Ok((customer_config_path, customer_config))
 }
 
 fn format_iterator<I, Pre, Sep, Post>(iter: I, pre: Pre, sep: Sep, post: Post) -> FormatIterator<I::IntoIter, Pre, Sep, Post>
@@ -166,6 +335,11 @@ where
 	Post: Display,
 {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		// Print nothing at all for an empty iterator.
+		if self.iter.clone().next().is_none() {
+			return Ok(());
+		}
+
 		write!(f, "{}", self.pre)?;
 
 		for (i, item) in self.iter.clone().enumerate() {
@@ -0,0 +1,208 @@
+use ordered_float::NotNan;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::grootboek::{Cents, RoundingMode};
+use zzp::date_range::DateRange;
+use zzp::partial_date::PartialDate;
+use zzp_tools::period::Period;
+use zzp::uurlog::{Entry, Hours};
+use zzp_tools::hours::HourRates;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::{CustomerConfig, ZzpConfig};
+
+use super::{Error, read_uurlog};
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ReportOptions {
+	/// The file with hour log entries [default: the hour logs of the customer].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// The period to report on, like 2024-05, 2024-W19, last-month or the name of a custom period.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY[-MM[-DD]]")]
+	period: Option<Period>,
+
+	/// Only consider hour entries from this date or later.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	start_date: Option<PartialDate>,
+
+	/// Only consider hour entries from this date or earlier.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR[-MONTH[-DAY]]")]
+	#[structopt(conflicts_with = "period")]
+	end_date: Option<PartialDate>,
+
+	/// Only consider hour entries in this date range, like 2024-01-15..2024-02-03 or ..2024-06-30.
+	#[structopt(long)]
+	#[structopt(value_name = "[START]..[END]")]
+	#[structopt(conflicts_with_all = &["period", "start-date", "end-date"])]
+	dates: Option<DateRange>,
+
+	/// Show the subtotal of the hours per day, week, month, tag or description.
+	#[structopt(long)]
+	#[structopt(value_name = "GROUP")]
+	#[structopt(possible_values = &["day", "week", "month", "tag", "description"])]
+	#[structopt(default_value = "day")]
+	group_by: GroupBy,
+
+	/// Show the revenue of the hours, using the price per hour of the customer and the tags.
+	#[structopt(long)]
+	revenue: bool,
+}
+
+/// How to group hour entries in a report.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum GroupBy {
+	Day,
+	Week,
+	Month,
+	Tag,
+	Description,
+}
+
+/// The subtotal of a group of hour entries.
+#[derive(Default)]
+struct Subtotal {
+	hours: Hours,
+	revenue: NotNan<f64>,
+}
+
+pub(crate) fn report(options: ReportOptions, format: OutputFormat) -> Result<(), Error> {
+	let (start_date, end_date) = super::period_bounds(options.period.as_ref(), options.start_date, options.end_date, options.dates)?;
+
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let entries = match &options.file {
+		Some(file) => read_uurlog(&current_dir.join(file), start_date, end_date)?,
+		None => super::read_default_uurlog(&current_dir, start_date, end_date)?,
+	};
+
+	// The revenue needs the price per hour from the configuration of the customer.
+	let configs = if options.revenue {
+		let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)?;
+		let root_dir = zzp_config_path.parent().unwrap();
+		let customer_config_path = CustomerConfig::find(root_dir, &current_dir)
+			.ok_or("could not find customer.toml")?;
+		let mut zzp_config = ZzpConfig::read_file(&zzp_config_path)?;
+		let customer_config = CustomerConfig::read_file(&customer_config_path)?;
+		zzp_config.apply_customer_overrides(&customer_config);
+		Some((zzp_config, customer_config))
+	} else {
+		None
+	};
+	let rates = configs.as_ref().map(|(zzp_config, customer_config)| HourRates::from_config(zzp_config, customer_config));
+
+	let mut groups: BTreeMap<String, Subtotal> = BTreeMap::new();
+	let mut total = Subtotal::default();
+	for entry in &entries {
+		let revenue = match &rates {
+			Some(rates) => {
+				let price = zzp_tools::hours::price_per_hour(rates, entry)?;
+				price * f64::from(entry.hours.total_minutes()) / 60.0
+			},
+			None => NotNan::default(),
+		};
+		total.hours += entry.hours;
+		total.revenue += revenue;
+
+		// Entries with multiple tags are counted for each tag.
+		for key in group_keys(options.group_by, entry) {
+			let subtotal = groups.entry(key).or_default();
+			subtotal.hours += entry.hours;
+			subtotal.revenue += revenue;
+		}
+	}
+
+	let format_revenue = |amount: NotNan<f64>| match &configs {
+		Some((zzp_config, _)) => zzp_config.currency().format(Cents::from_f64(amount.into_inner(), RoundingMode::HalfAwayFromZero)),
+		None => String::new(),
+	};
+
+	if format == OutputFormat::Json {
+		let revenue = |amount: NotNan<f64>| match options.revenue {
+			true => Json::from(Cents::from_f64(amount.into_inner(), RoundingMode::HalfAwayFromZero)),
+			false => Json::Null,
+		};
+		Json::object([
+			("groups", Json::array(groups.iter().map(|(key, subtotal)| Json::object([
+				("key", key.as_str().into()),
+				("hours", subtotal.hours.into()),
+				("revenue", revenue(subtotal.revenue)),
+			])))),
+			("total_hours", total.hours.into()),
+			("total_revenue", revenue(total.revenue)),
+		]).print();
+		return Ok(());
+	}
+
+	let total_label = "Total";
+	let key_width = groups.keys()
+		.map(|x| x.chars().count())
+		.chain(std::iter::once(total_label.len()))
+		.max()
+		.unwrap_or(0);
+	let hours_width = total.hours.to_string().len();
+	let revenue_width = format_revenue(total.revenue).chars().count();
+
+	for (key, subtotal) in &groups {
+		print!("{key}  {hours}",
+			key = Paint::cyan(format!("{:<width$}", key, width = key_width)),
+			hours = Paint::red(format!("{:>width$}", subtotal.hours.to_string(), width = hours_width)),
+		);
+		if options.revenue {
+			print!("  {:>width$}", format_revenue(subtotal.revenue), width = revenue_width);
+		}
+		println!();
+	}
+
+	println!();
+	print!("{key}  {hours}",
+		key = Paint::default(format!("{:<width$}", total_label, width = key_width)).bold(),
+		hours = Paint::yellow(format!("{:>width$}", total.hours.to_string(), width = hours_width)),
+	);
+	if options.revenue {
+		print!("  {}", Paint::yellow(format_revenue(total.revenue)));
+	}
+	println!();
+	Ok(())
+}
+
+/// Get the groups of an entry.
+fn group_keys(group_by: GroupBy, entry: &Entry) -> Vec<String> {
+	match group_by {
+		GroupBy::Day => vec![entry.date.to_string()],
+		GroupBy::Week => {
+			let (year, week) = zzp::calendar::iso_week(entry.date);
+			vec![format!("{:04}-W{:02}", year, week)]
+		},
+		GroupBy::Month => vec![entry.date.year_month().to_string()],
+		GroupBy::Tag if entry.tags.is_empty() => vec![String::from("(no tag)")],
+		GroupBy::Tag => entry.tags.clone(),
+		GroupBy::Description => vec![entry.description.clone()],
+	}
+}
+
+impl std::str::FromStr for GroupBy {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		match input {
+			"day" => Ok(Self::Day),
+			"week" => Ok(Self::Week),
+			"month" => Ok(Self::Month),
+			"tag" => Ok(Self::Tag),
+			"description" => Ok(Self::Description),
+			_ => Err(format!("invalid group {:?}, expected day, week, month, tag or description", input)),
+		}
+	}
+}
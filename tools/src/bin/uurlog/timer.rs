@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::gregorian::Date;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::timer::Timer;
+
+use super::{Error, format_iterator};
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct StartOptions {
+	/// The file with hour log entries [default: the hour log of the customer for today].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// The description of the entry, optionally starting with tags like `[tag]`.
+	#[structopt(value_name = "[TAGS] DESCRIPTION")]
+	#[structopt(required = true)]
+	description: Vec<String>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct StopOptions {
+	/// Stop the timer without adding an entry to the hour log.
+	#[structopt(long)]
+	discard: bool,
+}
+
+pub(crate) fn start(options: StartOptions) -> Result<(), Error> {
+	let (current_dir, root_dir) = find_root_dir()?;
+	let file = match options.file {
+		Some(file) => current_dir.join(file),
+		None => super::default_uurlog_file(&current_dir, Date::today())?,
+	};
+
+	let description = options.description.join(" ");
+	let (tags, description) = zzp::uurlog::split_tags(&description)
+		.map_err(|e| e.to_string())?;
+	if description.is_empty() {
+		return Err("the description of the entry can not be empty".into());
+	}
+
+	let timer = Timer::new(file, tags, description.to_string());
+	timer.start(&root_dir)?;
+	log::info!("started timer for {}", timer.file.display());
+	Ok(())
+}
+
+pub(crate) fn stop(options: StopOptions) -> Result<(), Error> {
+	let (_current_dir, root_dir) = find_root_dir()?;
+	let timer = read_timer(&root_dir)?;
+
+	if options.discard {
+		Timer::discard(&root_dir)?;
+		log::info!("discarded timer after {}", timer.elapsed());
+		return Ok(());
+	}
+
+	// Add the entry before removing the timer, so no time is lost if writing fails.
+	let entry = timer.entry();
+	zzp::uurlog::append_entry(&timer.file, &entry)
+		.map_err(|e| format!("failed to add entry to {}: {}", timer.file.display(), e))?;
+	Timer::discard(&root_dir)?;
+	print_entry(&timer, &entry);
+	Ok(())
+}
+
+pub(crate) fn status(format: OutputFormat) -> Result<(), Error> {
+	let (_current_dir, root_dir) = find_root_dir()?;
+	let timer = read_timer(&root_dir)?;
+	if format == OutputFormat::Json {
+		Json::object([
+			("entry", super::entry_json(&timer.entry())),
+			("file", timer.file.display().to_string().into()),
+		]).print();
+		return Ok(());
+	}
+	print_entry(&timer, &timer.entry());
+	Ok(())
+}
+
+fn print_entry(timer: &Timer, entry: &zzp::uurlog::Entry) {
+	println!("{date}, {hours}, {tags}{description}",
+		date = Paint::cyan(entry.date),
+		hours = Paint::red(entry.hours),
+		tags = Paint::yellow(format_iterator(&entry.tags, "[", "] [", "] ")),
+		description = entry.description,
+	);
+	println!("{} {}", Paint::default("Hour log:").bold(), timer.file.display());
+}
+
+fn read_timer(root_dir: &Path) -> Result<Timer, Error> {
+	Timer::read(root_dir)?
+		.ok_or_else(|| "no timer is running".into())
+}
+
+/// Find the working directory and the directory containing `zzp.toml`.
+///
+/// The running timer is stored in the directory of `zzp.toml`.
+fn find_root_dir() -> Result<(PathBuf, PathBuf), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)?;
+	let root_dir = zzp_config_path.parent().unwrap().to_path_buf();
+	Ok((current_dir, root_dir))
+}
@@ -0,0 +1,118 @@
+use ordered_float::NotNan;
+use std::collections::BTreeMap;
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::gregorian::{Date, YearMonth};
+use zzp::grootboek::{Cents, RoundingMode};
+use zzp::uurlog::Hours;
+use zzp_tools::hours::HourRates;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::register::InvoiceRegister;
+use zzp_tools::{CustomerConfig, ZzpConfig};
+
+use super::{Error, read_customer_uurlog};
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct UninvoicedOptions {
+	/// Only show hours of the customer with this name.
+	#[structopt(long)]
+	#[structopt(value_name = "NAME")]
+	customer: Option<String>,
+
+	/// Only consider hour entries before this date.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	before: Option<Date>,
+}
+
+/// The uninvoiced hours of a customer in a month.
+#[derive(Default)]
+struct Subtotal {
+	hours: Hours,
+	amount: NotNan<f64>,
+}
+
+/// Show the hours of all customers that are not covered by the period of an invoice in the invoice register.
+///
+/// Invoices without a period (like invoices created with `zzp-invoice`) are not taken into account.
+pub(crate) fn uninvoiced(options: UninvoicedOptions, format: OutputFormat) -> Result<(), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)?;
+	let root_dir = zzp_config_path.parent().unwrap();
+	let config = ZzpConfig::read_file(&zzp_config_path)?;
+	let register = InvoiceRegister::open(root_dir, &config)?;
+
+	let customer_config_paths = CustomerConfig::find_all(root_dir)?;
+
+	let mut total = Hours::from_minutes(0);
+	let mut customers = Vec::new();
+	for path in customer_config_paths {
+		let customer_config = CustomerConfig::read_file(&path)?;
+		let name = &customer_config.customer.name;
+		if options.customer.as_ref().map(|x| x != name).unwrap_or(false) {
+			continue;
+		}
+
+		let entries = read_customer_uurlog(&customer_config, path.parent().unwrap(), None, options.before)?;
+
+		let mut zzp_config = config.clone();
+		zzp_config.apply_customer_overrides(&customer_config);
+		let rates = HourRates::from_config(&zzp_config, &customer_config);
+
+		let mut months: BTreeMap<YearMonth, Subtotal> = BTreeMap::new();
+		for entry in entries {
+			if register.find_period(name, &(entry.date..entry.date.next())).is_some() {
+				continue;
+			}
+			let price = zzp_tools::hours::price_per_hour(&rates, &entry)
+				.map_err(|e| format!("{}: {}", name, e))?;
+			let subtotal = months.entry(entry.date.year_month()).or_default();
+			subtotal.hours += entry.hours;
+			subtotal.amount += price * f64::from(entry.hours.total_minutes()) / 60.0;
+		}
+
+		if months.is_empty() {
+			continue;
+		}
+
+		let amount = |subtotal: &Subtotal| Cents::from_f64(subtotal.amount.into_inner(), RoundingMode::HalfAwayFromZero);
+		if format == OutputFormat::Json {
+			total += months.values().fold(Hours::from_minutes(0), |total, x| total + x.hours);
+			customers.push(Json::object([
+				("customer", name.as_str().into()),
+				("months", Json::array(months.iter().map(|(month, subtotal)| Json::object([
+					("month", month.to_string().into()),
+					("hours", subtotal.hours.into()),
+					("amount", amount(subtotal).into()),
+				])))),
+			]));
+			continue;
+		}
+
+		println!("{}", Paint::default(name).bold());
+		for (month, subtotal) in &months {
+			total += subtotal.hours;
+			println!("  {month}  {hours:>8}  {amount:>14}",
+				month = Paint::cyan(month),
+				hours = Paint::red(subtotal.hours.to_string()),
+				amount = zzp_config.currency().format(amount(subtotal)),
+			);
+		}
+		println!();
+	}
+
+	if format == OutputFormat::Json {
+		Json::object([
+			("customers", Json::Array(customers)),
+			("total", total.into()),
+		]).print();
+		return Ok(());
+	}
+	println!("{} {}", Paint::default("Total uninvoiced time:").bold(), Paint::yellow(total));
+	Ok(())
+}
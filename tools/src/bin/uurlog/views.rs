@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::gregorian::Date;
+use zzp::uurlog::Hours;
+use zzp_tools::ZzpConfig;
+use zzp_tools::json::{Json, OutputFormat};
+
+use super::{Error, format_iterator, read_uurlog};
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ViewOptions {
+	/// The file with hour log entries [default: the hour logs of the customer].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	file: Option<PathBuf>,
+
+	/// Show the entries for the day or week of this date instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+}
+
+pub(crate) fn today(options: ViewOptions, format: OutputFormat) -> Result<(), Error> {
+	let date = options.date.unwrap_or_else(Date::today);
+	let target = read_config()?.and_then(|x| x.uurlog).and_then(|x| x.daily_target);
+	show_period(options.file, date, date.next(), target, format)
+}
+
+pub(crate) fn week(options: ViewOptions, format: OutputFormat) -> Result<(), Error> {
+	let date = options.date.unwrap_or_else(Date::today);
+	let start = zzp::calendar::week_start(date);
+	let end = (0..7).fold(start, |date, _| date.next());
+	let target = read_config()?.and_then(|x| x.uurlog).and_then(|x| x.weekly_target);
+
+	if format == OutputFormat::Text {
+		let (year, week) = zzp::calendar::iso_week(date);
+		println!("{}", Paint::default(format!("Week {} of {}", week, year)).bold());
+	}
+	show_period(options.file, start, end, target, format)
+}
+
+/// Show the entries in a half-open range of dates with a running total.
+fn show_period(file: Option<PathBuf>, start: Date, end: Date, target: Option<Hours>, format: OutputFormat) -> Result<(), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let entries = match file {
+		Some(file) => read_uurlog(&current_dir.join(file), Some(start), Some(end))?,
+		None => super::read_default_uurlog(&current_dir, Some(start), Some(end))?,
+	};
+	if format == OutputFormat::Json {
+		let total = entries.iter().fold(Hours::from_minutes(0), |total, entry| total + entry.hours);
+		Json::object([
+			("start", start.into()),
+			("end", end.prev().into()),
+			("entries", Json::array(entries.iter().map(super::entry_json))),
+			("total", total.into()),
+			("target", target.into()),
+		]).print();
+		return Ok(());
+	}
+
+	let mut total = Hours::from_minutes(0);
+	for entry in &entries {
+		total += entry.hours;
+		println!("{date}, {hours}, {total}, {tags}{description}",
+			date = Paint::cyan(entry.date),
+			hours = Paint::red(entry.hours),
+			total = Paint::yellow(total),
+			tags = Paint::yellow(format_iterator(&entry.tags, "[", "] [", "] ")),
+			description = entry.description,
+		);
+	}
+
+	println!();
+	println!("{} {}", Paint::default("Total time:").bold(), Paint::yellow(total));
+	if let Some(target) = target {
+		let total = total.total_minutes();
+		let target_minutes = target.total_minutes();
+		if total >= target_minutes {
+			let over = Hours::from_minutes(total - target_minutes);
+			println!("{} {} ({} over)", Paint::default("Target:").bold(), target, Paint::green(over));
+		} else {
+			let remaining = Hours::from_minutes(target_minutes - total);
+			println!("{} {} ({} to go)", Paint::default("Target:").bold(), target, Paint::red(remaining));
+		}
+	}
+	Ok(())
+}
+
+/// Read the ZZP configuration, if there is one.
+fn read_config() -> Result<Option<ZzpConfig>, Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	match zzp_tools::workspace::find_zzp_config(&current_dir) {
+		Ok(path) => Ok(Some(ZzpConfig::read_file(&path)?)),
+		Err(_) => Ok(None),
+	}
+}
@@ -1,13 +1,10 @@
-use dynfmt::{Format, SimpleCurlyFormat};
-use std::collections::BTreeMap;
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use structopt::StructOpt;
 use structopt::clap;
-use zzp_tools::invoice::InvoiceFile;
+use zzp_tools::generate::{EntrySource, GenerateOptions, InvoiceConfig, InvoiceError, InvoiceOptions};
+use zzp_tools::invoice::DiscountValue;
 
 use zzp::gregorian::Date;
-use zzp_tools::{CustomerConfig, ZzpConfig};
 
 #[derive(StructOpt)]
 #[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
@@ -19,14 +16,32 @@ pub struct Options {
 	#[structopt(global = true)]
 	verbose: i8,
 
-	/// The invoice number to use.
+	/// The administration to use from zzp-workspace.toml [env: ZZP_ADMINISTRATION].
 	#[structopt(long)]
-	number: String,
+	#[structopt(global = true)]
+	#[structopt(value_name = "NAME")]
+	administration: Option<String>,
+
+	/// The invoice number to use instead of the next number in the sequence.
+	#[structopt(long)]
+	number: Option<String>,
+
+	/// Only print the next invoice number in the sequence, without consuming it.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "number")]
+	peek: bool,
 
-	/// The file with hour log entries.
+	/// The file with invoice entries.
 	#[structopt(long, short)]
 	#[structopt(value_name = "FILE")]
-	input: PathBuf,
+	#[structopt(required_unless_one = &["peek", "quote"])]
+	input: Option<PathBuf>,
+
+	/// Create the invoice from the entries of an accepted quote.
+	#[structopt(long)]
+	#[structopt(value_name = "FILE")]
+	#[structopt(conflicts_with = "input")]
+	quote: Option<PathBuf>,
 
 	/// Write the generated invoice to this path instead of the default.
 	#[structopt(long, short)]
@@ -42,16 +57,51 @@ pub struct Options {
 	#[structopt(value_name = "YYYY-MM-DD")]
 	date: Option<Date>,
 
+	/// Give a discount on the invoice, as percentage (ending with `%`) or as fixed amount.
+	#[structopt(long)]
+	#[structopt(value_name = "PERCENTAGE%|AMOUNT")]
+	discount: Option<DiscountValue>,
+
+	/// The description of the discount on the invoice [default: Discount].
+	#[structopt(long)]
+	#[structopt(requires = "discount")]
+	discount_description: Option<String>,
+
+	/// The purchase order number or other customer reference to show on the invoice.
+	#[structopt(long)]
+	#[structopt(value_name = "REFERENCE")]
+	reference: Option<String>,
+
+	/// Create a draft invoice with a watermark, without adding it to the grootboek or using an invoice number.
+	#[structopt(long)]
+	draft: bool,
+
+	/// Only show the invoice lines, totals, file name and grootboek transaction, without writing any files or using an invoice number.
+	#[structopt(long)]
+	dry_run: bool,
+
+	/// Send the invoice to the customer by email after creating it, even without an email delivery method.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "dry-run")]
+	email: bool,
+
 	/// Do not automatically add the invoice to the grootboek.
 	#[structopt(long)]
 	skip_grootboek: bool,
+
+	/// Add the invoice to the grootboek even if it contains transactions with a later date.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "skip-grootboek")]
+	force_grootboek: bool,
 }
 
 fn main() {
 	let options = Options::from_args();
 	init_logging(options.verbose);
+	zzp_tools::workspace::select_administration(options.administration.clone());
 
-	if do_main(options).is_err() {
+	if let Err(e) = do_main(options) {
+		log::error!("{}", e);
 		std::process::exit(1);
 	}
 }
@@ -72,181 +122,51 @@ fn init_logging(verbosity: i8) {
 	env_logger::from_env("RUST_LOG").filter_module(module_path!(), level).init();
 }
 
-fn do_main(options: Options) -> Result<(), ()> {
-	// Find configuration files.
-	let current_dir = std::env::current_dir()
-		.map_err(|e| log::error!("failed to determine working directory: {}", e))?;
-	let zzp_config_path = ZzpConfig::find("/", &current_dir)
-		.ok_or_else(|| log::error!("could not find zzp.toml"))?;
-	let root_dir = zzp_config_path.parent().unwrap();
-	let customer_config_path = CustomerConfig::find(root_dir, &current_dir)
-		.ok_or_else(|| log::error!("could not find customer.toml"))?;
-
-	// Read configuration files.
-	let zzp_config = ZzpConfig::read_file(&zzp_config_path)
-		.map_err(|e| log::error!("{}", e))?;
-	let customer_config = CustomerConfig::read_file(&customer_config_path)
-		.map_err(|e| log::error!("{}", e))?;
-
-	// Consolidate command line options with config files.
+fn do_main(options: Options) -> Result<(), InvoiceError> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let config = InvoiceConfig::find(&current_dir)?;
 	let date = options.date.unwrap_or_else(Date::today);
 
-	let args: std::collections::BTreeMap<_, _> = [
-		("year", date.year().to_string()),
-		("month", format!("{:02}", date.month().to_number())),
-		("day", format!("{:02}", date.day())),
-	].into_iter().collect();
-
-	let grootboek_path = SimpleCurlyFormat.format(&zzp_config.grootboek.path, &args)
-		.map_err(|e| log::error!("failed to expand grootboek path: {}", e))?;
-	let grootboek_path = root_dir.join(&*grootboek_path);
-	let grootboek_dir = grootboek_path.parent()
-		.ok_or_else(|| log::error!("failed to determine parent directory of {}", grootboek_path.display()))?;
-
-	let invoice_directory = SimpleCurlyFormat.format(&zzp_config.invoice.directory, &args)
-		.map_err(|e| log::error!("failed to expand invoice directory: {}", e))?;
-	let output = options.output
-		.map(|path| current_dir.join(path))
-		.unwrap_or_else(|| {
-		generate_invoice_file_name(root_dir.join(&*invoice_directory), &options.number, &zzp_config)
-	});
-
-	// Read invoice entries.
-	let mut invoice: InvoiceFile = zzp_tools::read_toml(&options.input)
-		.map_err(|e| log::error!("{e}"))?;
-	invoice.entries.sort_by(|a, b| a.date.cmp(&b.date));
-
-	let invoice_tag_value = output.strip_prefix(grootboek_dir)
-		.map_err(|_| {
-			log::error!("invoice path ({}) is not below the grootboek directory ({})", output.display(), grootboek_dir.display());
-		})?
-		.display()
-		.to_string();
-
-	let quarter;
-	if date.month() >= zzp::gregorian::October {
-		quarter = 4;
-	} else if date.month() >= zzp::gregorian::July {
-		quarter = 3;
-	} else if date.month() >= zzp::gregorian::April {
-		quarter = 2;
-	} else {
-		quarter = 1;
+	if options.peek {
+		let number = config.peek_number(date)?;
+		println!("{}", number);
+		return Ok(());
 	}
 
-	let format_args: BTreeMap<_, _> = [
-		("year", date.year().to_string()),
-		("month", format!("{:02}", date.month().to_number())),
-		("day", format!("{:02}", date.day())),
-		("quarter", quarter.to_string()),
-		("debitor", customer_config.customer.grootboek_name.clone()),
-		("invoice_number", options.number.clone()),
-	].into_iter().collect();
-
-	let mut total_ex_vat = 0.0;
-	let mut total_vat = BTreeMap::new();
-	for entry in &invoice.entries {
-		total_ex_vat += entry.total_ex_vat().into_inner();
-		let vat = total_vat.entry(entry.vat_percentage).or_insert(0.0);
-		*vat += entry.total_vat_only().into_inner();
-	}
-
-	let total_vat: BTreeMap<_, _> = total_vat.into_iter().map(|(key, value)| {
-		let mut format_args = format_args.clone();
-		format_args.insert("percentage", key.to_string());
-
-		let key = SimpleCurlyFormat.format(&zzp_config.grootboek.vat_account, format_args)
-			.map_err(|e| log::error!("failed to expand VAT account: {}", e))?;
-		let value = zzp::grootboek::Cents((value * 100.0).round() as i32);
-		Ok((key, value))
-	}).collect::<Result<_, _>>()?;
-
-	let total_vat_all = total_vat.values().sum();
-	let total_ex_vat = zzp::grootboek::Cents((total_ex_vat * 100.0).round() as i32);
-
-	let description = SimpleCurlyFormat.format(&zzp_config.invoice.grootboek_description, &format_args)
-		.map_err(|e| log::error!("failed to expand grootboek description: {}", e))?;
-	let debitor_account = SimpleCurlyFormat.format(&zzp_config.grootboek.debitor_account, &format_args)
-		.map_err(|e| log::error!("failed to expand debitor account: {}", e))?;
-	let revenue_account = SimpleCurlyFormat.format(&zzp_config.grootboek.revenue_account, &format_args)
-		.map_err(|e| log::error!("failed to expand revenue account: {}", e))?;
-
-	let mut grootboek_entry = zzp::grootboek::Transaction {
-		date,
-		description: &description,
-		tags: vec![
-			zzp::grootboek::Tag {
-				label: &zzp_config.invoice.grootboek_tag,
-				value: &invoice_tag_value,
-			},
-		],
-		mutations: vec![
-			zzp::grootboek::Mutation {
-				amount: total_ex_vat + total_vat_all,
-				account: zzp::grootboek::Account::from_raw(&debitor_account),
-			},
-			zzp::grootboek::Mutation {
-				amount: -total_ex_vat,
-				account: zzp::grootboek::Account::from_raw(&revenue_account),
-			},
-		],
+	// Clap ensures the input or a quote is given unless `--peek` is used.
+	let entries = match &options.quote {
+		Some(quote) => EntrySource::Quote(quote),
+		None => EntrySource::File(options.input.as_ref().unwrap()),
 	};
 
-	for (account, &amount) in &total_vat {
-		grootboek_entry.mutations.push(zzp::grootboek::Mutation {
-			account: zzp::grootboek::Account::from_raw(account),
-			amount: -amount,
-		})
-	}
+	let invoice_options = InvoiceOptions {
+		entries,
+		discount: options.discount,
+		discount_description: options.discount_description.as_deref(),
+		generate: GenerateOptions {
+			number: options.number.as_deref(),
+			date,
+			output: options.output.map(|path| current_dir.join(path)),
+			overwrite: options.overwrite,
+			draft: options.draft,
+			dry_run: options.dry_run,
+			email: options.email,
+			skip_grootboek: options.skip_grootboek,
+			force_grootboek: options.force_grootboek,
+			reference: options.reference.as_deref(),
+			recurring: None,
+			period: None,
+			renderer: None,
+		},
+	};
+	let generated = zzp_tools::generate::generate(&invoice_options, &config)?;
 
-	if let Some(parent) = output.parent() {
-		std::fs::create_dir_all(parent)
-			.map_err(|e| log::error!("failed to create directory {}: {}", parent.display(), e))?;
+	if generated.emailed {
+		log::info!("sent invoice {} to {}", generated.number, config.customer_config.customer.email.as_deref().unwrap_or_default());
 	}
-
-	let file = std::fs::OpenOptions::new()
-		.create(true)
-		.truncate(true)
-		.create_new(!options.overwrite)
-		.write(true)
-		.open(&output)
-		.map_err(|e| log::error!("failed to create {}: {}", output.display(), e))?;
-	let file = std::io::BufWriter::new(file);
-
-	zzp_tools::invoice::make_invoice(
-		file,
-		&zzp_config,
-		&customer_config.customer,
-		&options.number,
-		date,
-		&invoice.entries,
-	)
-		.map_err(|e| log::error!("{}", e))?;
-
-	zzp_tools::grootboek::print_full_colored(&grootboek_entry);
-	if !options.skip_grootboek {
-		let mut grootboek_file = std::fs::OpenOptions::new()
-			.append(true)
-			.create(true)
-			.open(&grootboek_path)
-			.map_err(|e| log::error!("failed to open {} for writing: {}", grootboek_path.display(), e))?;
-		writeln!(grootboek_file)
-			.map_err(|e| log::error!("failed to write to {}: {}", grootboek_path.display(), e))?;
-		zzp_tools::grootboek::write_full(&mut grootboek_file, &grootboek_entry)
-			.map_err(|e| log::error!("failed to write to {}: {}", grootboek_path.display(), e))?;
+	if generated.submitted {
+		log::info!("submitted invoice {} to the Peppol access point", generated.number);
 	}
 
 	Ok(())
 }
-
-fn generate_invoice_file_name(invoice_dir: impl AsRef<Path>, number: &str, config: &ZzpConfig) -> PathBuf {
-	let mut invoice = config.invoice_localization.invoice.clone();
-	unsafe {
-		invoice.as_bytes_mut()[0].make_ascii_uppercase();
-	}
-	invoice_dir.as_ref().join(format!("{company} - {invoice} {number}.pdf",
-		company = config.company.name,
-		number = number,
-		invoice = invoice,
-	))
-}
@@ -0,0 +1,160 @@
+use dynfmt::{Format, SimpleCurlyFormat};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+use zzp_tools::quote::QuoteFile;
+
+use zzp::gregorian::Date;
+use zzp_tools::{CustomerConfig, ZzpConfig};
+
+/// An error of a subcommand, reported once by `main`.
+type Error = Box<dyn std::error::Error>;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct Options {
+	#[structopt(long, short)]
+	#[structopt(parse(from_occurrences))]
+	#[structopt(global = true)]
+	verbose: i8,
+
+	/// The administration to use from zzp-workspace.toml [env: ZZP_ADMINISTRATION].
+	#[structopt(long)]
+	#[structopt(global = true)]
+	#[structopt(value_name = "NAME")]
+	administration: Option<String>,
+
+	/// The quote number to use.
+	#[structopt(long)]
+	number: String,
+
+	/// The file with the quote entries.
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	input: PathBuf,
+
+	/// Write the generated quote to this path instead of the default.
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	output: Option<PathBuf>,
+
+	/// Overwrite the output file if it exists.
+	#[structopt(long)]
+	overwrite: bool,
+
+	/// The date to use for the quote instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+}
+
+fn main() {
+	let options = Options::from_args();
+	init_logging(options.verbose);
+	zzp_tools::workspace::select_administration(options.administration.clone());
+
+	if let Err(e) = do_main(options) {
+		log::error!("{}", e);
+		std::process::exit(1);
+	}
+}
+
+fn init_logging(verbosity: i8) {
+	let level = if verbosity <= -2 {
+		log::LevelFilter::Error
+	} else if verbosity == -1 {
+		log::LevelFilter::Warn
+	} else if verbosity == 0 {
+		log::LevelFilter::Info
+	} else if verbosity == 1 {
+		log::LevelFilter::Debug
+	} else {
+		log::LevelFilter::Trace
+	};
+
+	env_logger::from_env("RUST_LOG").filter_module(module_path!(), level).init();
+}
+
+fn do_main(options: Options) -> Result<(), Error> {
+	// Find configuration files.
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)?;
+	let root_dir = zzp_config_path.parent().unwrap();
+	let customer_config_path = CustomerConfig::find(root_dir, &current_dir)
+		.ok_or("could not find customer.toml")?;
+
+	// Read configuration files.
+	let mut zzp_config = ZzpConfig::read_file(&zzp_config_path)?;
+	let customer_config = CustomerConfig::read_file(&customer_config_path)?;
+	zzp_config.apply_customer_overrides(&customer_config);
+
+	let quote_config = zzp_config.quote.as_ref()
+		.ok_or("missing [Quote] section in zzp.toml")?;
+	let lang = zzp_config.quote_localization.as_ref()
+		.ok_or("missing [QuoteLocalization] section in zzp.toml")?;
+
+	// Consolidate command line options with config files.
+	let date = options.date.unwrap_or_else(Date::today);
+
+	let args: std::collections::BTreeMap<_, _> = [
+		("year", date.year().to_string()),
+		("month", format!("{:02}", date.month().to_number())),
+		("day", format!("{:02}", date.day())),
+	].into_iter().collect();
+
+	let quote_directory = SimpleCurlyFormat.format(&quote_config.directory, &args)
+		.map_err(|e| format!("failed to expand quote directory: {}", e))?;
+	let output = options.output
+		.map(|path| current_dir.join(path))
+		.unwrap_or_else(|| {
+		generate_quote_file_name(root_dir.join(&*quote_directory), &options.number, &lang.quote, &zzp_config)
+	});
+
+	// Read quote entries.
+	let mut quote: QuoteFile = zzp_tools::read_toml(&options.input)?;
+	quote.entries.sort_by_key(|x| x.date);
+	let valid_until = quote.valid_until(&zzp_config, date);
+
+	if let Some(parent) = output.parent() {
+		std::fs::create_dir_all(parent)
+			.map_err(|e| format!("failed to create directory {}: {}", parent.display(), e))?;
+	}
+
+	let file = std::fs::OpenOptions::new()
+		.create(true)
+		.truncate(true)
+		.create_new(!options.overwrite)
+		.write(true)
+		.open(&output)
+		.map_err(|e| format!("failed to create {}: {}", output.display(), e))?;
+	let file = std::io::BufWriter::new(file);
+
+	zzp_tools::quote::make_quote(
+		file,
+		&zzp_config,
+		&customer_config.customer,
+		&options.number,
+		date,
+		valid_until,
+		&quote.entries,
+	)?;
+
+	log::info!("quote written to {}, valid until {}", output.display(), valid_until);
+	log::info!("use `zzp-invoice --quote {}` to create an invoice when the quote is accepted", options.input.display());
+
+	Ok(())
+}
+
+fn generate_quote_file_name(quote_dir: impl AsRef<Path>, number: &str, quote: &str, config: &ZzpConfig) -> PathBuf {
+	let mut quote = quote.to_string();
+	unsafe {
+		quote.as_bytes_mut()[0].make_ascii_uppercase();
+	}
+	quote_dir.as_ref().join(format!("{company} - {quote} {number}.pdf",
+		company = config.company.name,
+		number = number,
+		quote = quote,
+	))
+}
@@ -0,0 +1,220 @@
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp::gregorian::{Date, Year};
+use zzp::grootboek::Transaction;
+use zzp::partial_date::PartialDate;
+use zzp_tools::income_statement::IncomeStatement;
+use zzp_tools::income_tax::IncomeTax;
+use zzp_tools::register::InvoiceRegister;
+use zzp_tools::revenue::RevenueReport;
+use zzp_tools::vat_return::VatReturn;
+use zzp_tools::ZzpConfig;
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ArchiveOptions {
+	/// The fiscal year to archive.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY")]
+	year: i16,
+
+	/// The ZIP file to write [default: archive-YEAR-TIMESTAMP.zip next to zzp.toml].
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	output: Option<PathBuf>,
+}
+
+/// A file to put in the archive.
+struct ArchiveFile {
+	/// The path inside the archive.
+	name: String,
+
+	/// The contents of the file.
+	data: Vec<u8>,
+}
+
+pub fn run(options: ArchiveOptions) -> Result<(), Error> {
+	let (root_dir, config) = super::read_zzp_config()?;
+	let year = Year::new(options.year);
+	let range = config.fiscal_year(year);
+	let last_day = range.end.prev();
+	let timestamp = timestamp();
+	let output = options.output.clone()
+		.unwrap_or_else(|| root_dir.join(format!("archive-{}-{}.zip", options.year, timestamp)));
+
+	let mut files = Vec::new();
+	let mut add_file = |path: &Path| -> Result<(), Error> {
+		let data = std::fs::read(path)
+			.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+		files.push(ArchiveFile { name: archive_name(&root_dir, path), data });
+		Ok(())
+	};
+
+	add_file(&root_dir.join("zzp.toml"))?;
+
+	// The grootboek files with transactions from the year.
+	let grootboek_paths = super::grootboek_paths(&root_dir, &config, range.start, last_day)?;
+	for path in &grootboek_paths {
+		add_file(path)?;
+	}
+
+	// The invoice register and the invoices of the year.
+	let register = InvoiceRegister::open(&root_dir, &config)?;
+	if register.path().exists() {
+		add_file(register.path())?;
+	}
+	for invoice in register.invoices().iter().filter(|x| range.contains(&x.date)) {
+		let path = root_dir.join(&invoice.file);
+		if path.exists() {
+			add_file(&path)?;
+		} else {
+			log::warn!("invoice file {} of invoice {} does not exist", path.display(), invoice.number);
+		}
+	}
+
+	// The configuration and hour logs of each customer.
+	let customers = super::customer::read_customers(&root_dir)?;
+	for (path, customer_config) in &customers {
+		add_file(path)?;
+		let hours_paths = customer_config.hours_files(path.parent().unwrap())?;
+		for hours_path in &hours_paths {
+			add_file(hours_path)?;
+		}
+	}
+
+	// Reports computed from the grootboek, including the VAT quarters that overlap with the start or end of the fiscal year.
+	let first_quarter = calendar_quarter(range.start);
+	let last_quarter = calendar_quarter(last_day);
+	let grootboek_files = super::read_grootboek_files(&root_dir, &config, first_quarter.as_start_date(), last_quarter.as_end_date())?;
+	let transactions = super::parse_grootboek_files(&grootboek_files, first_quarter.as_start_date(), last_quarter.as_end_date())?;
+	let customer_configs: Vec<_> = customers.into_iter().map(|(_, x)| x).collect();
+	files.extend(reports(&config, &customer_configs, options.year, &transactions)?);
+
+	let manifest = manifest(&config, options.year, &timestamp, &files);
+	let checksums: String = files.iter()
+		.map(|file| format!("{}  {}\n", sha256(&file.data), file.name))
+		.collect();
+	files.push(ArchiveFile { name: "manifest.txt".into(), data: manifest.into_bytes() });
+	files.push(ArchiveFile { name: "SHA256SUMS".into(), data: checksums.into_bytes() });
+
+	write_zip(&output, &files)
+		.map_err(|e| format!("failed to write {}: {}", output.display(), e))?;
+	log::info!("archived {} files of {} to {}", files.len() - 2, options.year, output.display());
+	Ok(())
+}
+
+/// Compute the reports of the year that can be made from the configuration.
+fn reports(config: &ZzpConfig, customers: &[zzp_tools::CustomerConfig], year: i16, transactions: &[Transaction]) -> Result<Vec<ArchiveFile>, Error> {
+	let mut reports = Vec::new();
+	let mut add_report = |name: String, data: String| reports.push(ArchiveFile { name: format!("reports/{}", name), data: data.into_bytes() });
+
+	let revenue = RevenueReport::compute(config, customers, year, transactions)?;
+	add_report("revenue.json".into(), format!("{}\n", revenue.to_json()));
+
+	let fiscal_year = config.fiscal_year(Year::new(year));
+	let fiscal_transactions = || transactions.iter().filter(|x| fiscal_year.contains(&x.date));
+
+	if !config.account.is_empty() {
+		let statement = IncomeStatement::compute(config, fiscal_transactions())?;
+		let mut csv = Vec::new();
+		statement.write_csv(&mut csv)
+			.map_err(|e| format!("failed to write income statement: {}", e))?;
+		add_report("income-statement.csv".into(), String::from_utf8(csv).unwrap());
+	}
+
+	if config.income_tax.is_some() {
+		let income_tax = IncomeTax::compute(config, fiscal_transactions())?;
+		add_report("income-tax.json".into(), format!("{}\n", income_tax.to_json(&config.fiscal_year_name(Year::new(year)))));
+	}
+
+	// VAT returns are per calendar quarter, so include every quarter that overlaps with the fiscal year.
+	if config.vat_return.is_some() {
+		let quarters = [year, year + 1].into_iter()
+			.flat_map(|year| (1..=4).map(move |quarter| PartialDate::YearQuarter(Year::new(year), quarter)))
+			.filter(|period| period.as_start_date() < fiscal_year.end && period.as_end_date() >= fiscal_year.start);
+		for period in quarters {
+			let range = period.as_range();
+			let vat_return = VatReturn::compute(config, transactions.iter().filter(|x| range.contains(&x.date)))?;
+			add_report(format!("vat-return-{}.json", period), format!("{}\n", vat_return.to_json(&period.to_string())));
+		}
+	}
+
+	Ok(reports)
+}
+
+/// Get the calendar quarter of a date.
+fn calendar_quarter(date: Date) -> PartialDate {
+	PartialDate::YearQuarter(date.year(), (date.month().to_number() - 1) / 3 + 1)
+}
+
+/// Get the path of a file inside the archive.
+///
+/// Files below the directory of `zzp.toml` keep their relative path, other files are put in `external/`.
+fn archive_name(root_dir: &Path, path: &Path) -> String {
+	let name = match path.strip_prefix(root_dir) {
+		Ok(relative) => relative.to_path_buf(),
+		Err(_) => Path::new("external").join(path.file_name().unwrap_or_default()),
+	};
+	name.components()
+		.map(|x| x.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+/// Create the manifest with the details of the archive and a line per file.
+fn manifest(config: &ZzpConfig, year: i16, timestamp: &str, files: &[ArchiveFile]) -> String {
+	let mut manifest = String::new();
+	manifest.push_str(&format!("Administration archive of {}\n", config.company.name));
+	manifest.push_str(&format!("Year: {}\n", config.fiscal_year_name(Year::new(year))));
+	manifest.push_str(&format!("Created: {}\n", timestamp));
+	manifest.push_str(&format!("Files: {}\n", files.len()));
+	manifest.push('\n');
+	for file in files {
+		manifest.push_str(&format!("{:>10}  {}\n", file.data.len(), file.name));
+	}
+	manifest.push_str("\nThe SHA-256 checksums of the files are in SHA256SUMS, which can be checked with `sha256sum -c SHA256SUMS`.\n");
+	manifest
+}
+
+fn write_zip(path: &Path, files: &[ArchiveFile]) -> zip::result::ZipResult<()> {
+	let file = std::fs::File::create(path)?;
+	let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+	let options = zip::write::SimpleFileOptions::default()
+		.compression_method(zip::CompressionMethod::Deflated);
+	for file in files {
+		zip.start_file(file.name.as_str(), options)?;
+		zip.write_all(&file.data)?;
+	}
+	zip.finish()?.flush()?;
+	Ok(())
+}
+
+fn sha256(data: &[u8]) -> String {
+	Sha256::digest(data).iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// Get the current UTC time as timestamp for file names, like `20240131T235959Z`.
+fn timestamp() -> String {
+	let seconds = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map(|x| x.as_secs())
+		.unwrap_or(0);
+	let today = Date::today();
+	let time = seconds % (24 * 60 * 60);
+	format!("{}{:02}{:02}T{:02}{:02}{:02}Z",
+		today.year().to_number(),
+		today.month().to_number(),
+		today.day(),
+		time / 3600,
+		time / 60 % 60,
+		time % 60,
+	)
+}
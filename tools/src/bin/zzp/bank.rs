@@ -0,0 +1,236 @@
+use dynfmt::{Format, SimpleCurlyFormat};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp::grootboek::{Account, Cents, Mutation, Tag, Transaction};
+use zzp_tools::bank::{BankTransaction, StatementFormat, BANK_REFERENCE_TAG, IBAN_TAG};
+use zzp_tools::bank::rules::BankRules;
+use zzp_tools::hooks::Hook;
+use zzp_tools::register::InvoiceRegister;
+use zzp_tools::ZzpConfig;
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub enum BankCommand {
+	/// Import a CAMT.053, QIF or OFX bank statement into the grootboek.
+	Import(ImportOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ImportOptions {
+	/// The CAMT.053, QIF or OFX file exported from the bank or credit card company.
+	#[structopt(value_name = "FILE")]
+	statement: PathBuf,
+
+	/// The format of the statement, detected from the contents by default.
+	#[structopt(long)]
+	#[structopt(value_name = "FORMAT")]
+	#[structopt(possible_values = &["camt053", "qif", "ofx"])]
+	statement_format: Option<StatementFormat>,
+
+	/// The grootboek account of the bank account.
+	#[structopt(long)]
+	#[structopt(value_name = "ACCOUNT")]
+	bank_account: Option<String>,
+
+	/// The grootboek account for transactions that do not pay an invoice and match no rule.
+	#[structopt(long)]
+	#[structopt(value_name = "ACCOUNT")]
+	#[structopt(default_value = "uncategorized")]
+	unmatched_account: String,
+
+	/// Only show the transactions that would be imported, without changing the grootboek or the invoice register.
+	#[structopt(long)]
+	dry_run: bool,
+
+	/// Add the transactions to the grootboek even if it contains transactions with a later date.
+	#[structopt(long)]
+	force_grootboek: bool,
+}
+
+pub fn run(command: BankCommand) -> Result<(), Error> {
+	let (root_dir, zzp_config) = super::read_zzp_config()?;
+	match command {
+		BankCommand::Import(options) => import(&root_dir, &zzp_config, options),
+	}
+}
+
+/// Import a bank statement.
+///
+/// Transactions that pay an unpaid invoice from the invoice register are booked on the debitor account of the invoice,
+/// and the invoice is marked as paid.
+/// Other transactions are booked according to the first matching rule from the rules file,
+/// or on the unmatched account if no rule matches.
+/// Transactions with a bank reference that is already in the grootboek are skipped.
+fn import(root_dir: &Path, config: &ZzpConfig, options: ImportOptions) -> Result<(), Error> {
+	let data = std::fs::read_to_string(&options.statement)
+		.map_err(|e| format!("failed to read {}: {}", options.statement.display(), e))?;
+	let format = options.statement_format
+		.or_else(|| StatementFormat::detect(&data))
+		.ok_or_else(|| format!("unknown format of {}: use --statement-format to specify it", options.statement.display()))?;
+	let mut transactions = format.parse(&data)
+		.map_err(|e| format!("failed to parse {}: {}", options.statement.display(), e))?;
+	transactions.sort_by_key(|x| x.date);
+
+	let bank_account = options.bank_account.as_ref()
+		.or(config.grootboek.bank_account.as_ref())
+		.ok_or("no bank account given: use --bank-account or set bank_account in the [Grootboek] section of zzp.toml")?;
+	let mut register = InvoiceRegister::open(root_dir, config)?;
+	let rules = BankRules::open(root_dir, config)?;
+
+	// The bank references already in the grootboek, per grootboek file.
+	let mut imported: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+	let mut paid = Vec::new();
+	let mut count = 0;
+	for bank_transaction in &transactions {
+		let mut format_args = zzp_tools::grootboek::date_format_args(bank_transaction.date);
+		let grootboek_path = expand(&config.grootboek.path, &format_args, "grootboek path")?;
+		let grootboek_path = root_dir.join(grootboek_path);
+		if !imported.contains_key(&grootboek_path) {
+			imported.insert(grootboek_path.clone(), read_bank_references(&grootboek_path)?);
+		}
+		let references = imported.get_mut(&grootboek_path).unwrap();
+		if let Some(reference) = &bank_transaction.bank_reference {
+			if references.contains(reference) {
+				log::debug!("skipping transaction {} from {}: already imported", reference, bank_transaction.date);
+				continue;
+			}
+			references.insert(reference.clone());
+		}
+
+		if bank_transaction.currency != config.currency().code {
+			log::warn!("transaction of {} has currency {}, booking the amount as {}", bank_transaction.date, bank_transaction.currency, config.currency().code);
+		}
+
+		let bank = expand(bank_account, &format_args, "bank account")?;
+		let invoice = bank_transaction.find_invoice(&register).cloned();
+		let rule = rules.find(bank_transaction);
+		let mut contra = Vec::new();
+		let mut tags = Vec::new();
+		let description = match (&invoice, rule) {
+			(Some(invoice), _) => {
+				format_args.insert("customer", invoice.customer.clone());
+				format_args.insert("invoice_number", invoice.number.clone());
+				contra.push((invoice.debitor_account.clone(), -bank_transaction.amount));
+				let description = config.invoice.payment_description.as_deref()
+					.unwrap_or(zzp_tools::register::DEFAULT_PAYMENT_DESCRIPTION);
+				expand(description, &format_args, "payment description")?
+			},
+			(None, Some(rule)) => {
+				if let Some(name) = &rule.name {
+					log::info!("applying rule {:?} to transaction of {}", name, bank_transaction.date);
+				}
+				let split = rule.split(bank_transaction.amount);
+				contra.push((expand(&rule.account, &format_args, "rule account")?, split.net));
+				if let Some(percentage) = rule.vat.filter(|_| split.vat != Cents(0)) {
+					// Outgoing payments have VAT input tax, incoming payments have VAT to pay.
+					let vat_account = if bank_transaction.amount.is_negative() {
+						expand(&config.grootboek.vat_input_account, &format_args, "VAT input account")?
+					} else {
+						let mut format_args = format_args.clone();
+						format_args.insert("percentage", percentage.to_string());
+						expand(&config.grootboek.vat_account, &format_args, "VAT account")?
+					};
+					contra.push((vat_account, split.vat));
+				}
+				tags.extend(rule.tags.iter().map(|(label, value)| (label.as_str(), value.as_str())));
+				bank_transaction.summary()
+			},
+			(None, None) => {
+				contra.push((options.unmatched_account.clone(), -bank_transaction.amount));
+				bank_transaction.summary()
+			},
+		};
+
+		let transaction = make_transaction(bank_transaction, &description, &bank, &contra, &tags);
+		zzp_tools::grootboek::print_full_colored(&transaction);
+		eprintln!();
+		count += 1;
+
+		if options.dry_run {
+			continue;
+		}
+		zzp::grootboek::append_transaction(&grootboek_path, &transaction, options.force_grootboek)?;
+		if let Some(invoice) = &invoice {
+			register.mark_paid(&invoice.number, bank_transaction.date)?;
+			log::info!("marked invoice {} as paid on {}", invoice.number, bank_transaction.date);
+			paid.push((invoice.number.clone(), bank_transaction.date));
+		}
+	}
+
+	if options.dry_run {
+		log::info!("imported {} of {} transactions from {}", count, transactions.len(), options.statement.display());
+		return Ok(());
+	}
+	register.save()?;
+	log::info!("imported {} of {} transactions from {}", count, transactions.len(), options.statement.display());
+
+	for (number, date) in &paid {
+		let invoice = register.find(number).unwrap();
+		zzp_tools::hooks::run_paid_hook(root_dir, config, invoice, *date)?;
+	}
+	let args = [
+		("statement", options.statement.display().to_string()),
+		("count", count.to_string()),
+	].into_iter().collect();
+	zzp_tools::hooks::run_hook(root_dir, config, Hook::PostBankImport, &args)?;
+	Ok(())
+}
+
+/// Make the grootboek transaction for a bank transaction.
+///
+/// The `contra` mutations balance the mutation of the bank account.
+fn make_transaction<'a>(
+	bank_transaction: &'a BankTransaction,
+	description: &'a str,
+	bank_account: &'a str,
+	contra: &'a [(String, Cents)],
+	extra_tags: &[(&'a str, &'a str)],
+) -> Transaction<'a> {
+	let mut tags = Vec::new();
+	if let Some(reference) = &bank_transaction.bank_reference {
+		tags.push(Tag { label: BANK_REFERENCE_TAG, value: reference });
+	}
+	if let Some(iban) = &bank_transaction.counterparty_iban {
+		tags.push(Tag { label: IBAN_TAG, value: iban });
+	}
+	tags.extend(extra_tags.iter().map(|&(label, value)| Tag { label, value }));
+
+	let mut mutations = vec![Mutation { amount: bank_transaction.amount, account: Account::from_raw(bank_account) }];
+	mutations.extend(contra.iter().map(|(account, amount)| Mutation { amount: *amount, account: Account::from_raw(account) }));
+
+	Transaction::new(bank_transaction.date, description, tags, mutations)
+}
+
+/// Read the bank references of the transactions in a grootboek file.
+///
+/// A missing file has no bank references.
+fn read_bank_references(path: &Path) -> Result<BTreeSet<String>, Error> {
+	if !path.exists() {
+		return Ok(BTreeSet::new());
+	}
+	let data = std::fs::read_to_string(path)
+		.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+	let transactions = Transaction::parse_from_str(&data)
+		.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+	Ok(transactions.iter()
+		.flat_map(|x| &x.tags)
+		.filter(|x| x.label == BANK_REFERENCE_TAG)
+		.map(|x| x.value.to_string())
+		.collect())
+}
+
+fn expand(format: &str, args: &BTreeMap<&str, String>, what: &str) -> Result<String, Error> {
+	let expanded = SimpleCurlyFormat.format(format, args)
+		.map_err(|e| format!("failed to expand {}: {}", what, e))?;
+	Ok(expanded.into_owned())
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp_tools::migrate::{CONFIG_VERSION, ConfigKind};
+use zzp_tools::schema::SchemaFile;
+use zzp_tools::{CustomerConfig, ZzpConfig};
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub enum ConfigCommand {
+	/// Upgrade zzp.toml and all customer.toml files to the current configuration version.
+	///
+	/// The original files are kept with a .bak extension.
+	/// Comments in upgraded files are not preserved.
+	Migrate(MigrateOptions),
+
+	/// List the administrations of the workspace in zzp-workspace.toml.
+	Administrations,
+
+	/// Print the JSON schema of a configuration file, for validation and autocompletion in editors.
+	Schema(SchemaOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct MigrateOptions {
+	/// Only show the changes, without writing any files.
+	#[structopt(long)]
+	dry_run: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct SchemaOptions {
+	/// The configuration file to print the schema of.
+	#[structopt(possible_values = &["zzp", "customer", "invoice"])]
+	file: SchemaFile,
+
+	/// Write the schema to this file instead of standard output.
+	#[structopt(long, short)]
+	#[structopt(value_name = "FILE")]
+	output: Option<PathBuf>,
+}
+
+pub fn run(command: ConfigCommand) -> Result<(), Error> {
+	match command {
+		ConfigCommand::Migrate(options) => migrate(options),
+		ConfigCommand::Administrations => administrations(),
+		ConfigCommand::Schema(options) => schema(options),
+	}
+}
+
+fn migrate(options: MigrateOptions) -> Result<(), Error> {
+	// Do not parse zzp.toml, since that may fail before it is migrated.
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)?;
+	let root_dir = zzp_config_path.parent().unwrap();
+	let customer_paths = CustomerConfig::find_all(root_dir)?;
+
+	let mut migrated = 0;
+	migrated += migrate_file(&zzp_config_path, ConfigKind::Zzp, &options)?;
+	for path in &customer_paths {
+		migrated += migrate_file(path, ConfigKind::Customer, &options)?;
+	}
+
+	if options.dry_run {
+		log::info!("{} file(s) need to be migrated to version {}", migrated, CONFIG_VERSION);
+	} else {
+		log::info!("migrated {} file(s) to version {}", migrated, CONFIG_VERSION);
+	}
+	Ok(())
+}
+
+fn administrations() -> Result<(), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let (workspace_dir, workspace) = zzp_tools::workspace::read_workspace(&current_dir)?
+		.ok_or_else(|| format!("could not find {}", zzp_tools::workspace::WORKSPACE_FILE))?;
+
+	// Mark the administration that commands would use from the working directory.
+	let active = zzp_tools::workspace::find_zzp_config(&current_dir).ok();
+	for administration in &workspace.administration {
+		let config_path = administration.config_path(&workspace_dir);
+		let company = match ZzpConfig::read_file(&config_path) {
+			Ok(config) => config.company.name,
+			Err(e) => format!("{}", Paint::red(e)),
+		};
+		let marker = if active.as_ref() == Some(&config_path) { "*" } else { " " };
+		let default = if workspace.default.as_ref() == Some(&administration.name) { " (default)" } else { "" };
+		println!("{} {}{}  {}  {}",
+			marker,
+			Paint::cyan(&administration.name),
+			default,
+			Paint::fixed(241, administration.path.display()),
+			company,
+		);
+	}
+	Ok(())
+}
+
+fn schema(options: SchemaOptions) -> Result<(), Error> {
+	let schema = zzp_tools::schema::to_json(&zzp_tools::schema::schema(options.file));
+	match &options.output {
+		None => println!("{}", schema),
+		Some(path) => {
+			std::fs::write(path, format!("{}\n", schema))
+				.map_err(|e| format!("failed to write to {}: {}", path.display(), e))?;
+			log::info!("wrote the JSON schema of the {} configuration to {}", options.file, path.display());
+		},
+	}
+	Ok(())
+}
+
+/// Migrate a single file and print the changes.
+///
+/// Returns 1 if the file needed to be migrated, 0 otherwise.
+fn migrate_file(path: &Path, kind: ConfigKind, options: &MigrateOptions) -> Result<usize, Error> {
+	let changes = zzp_tools::migrate::migrate_file(path, kind, !options.dry_run)?;
+	if changes.is_empty() {
+		println!("{}: {}", path.display(), Paint::fixed(241, "up to date"));
+		return Ok(0);
+	}
+	println!("{}:", Paint::cyan(path.display()));
+	for change in changes {
+		println!("  {}", change);
+	}
+	Ok(1)
+}
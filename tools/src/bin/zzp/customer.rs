@@ -0,0 +1,334 @@
+use ordered_float::NotNan;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::register::{InvoiceRegister, InvoiceStatus};
+use zzp_tools::{CustomerConfig, DeliveryMethod, HourlyRate, ZzpConfig};
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[allow(clippy::large_enum_variant)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub enum CustomerCommand {
+	/// Create a customer directory with a customer.toml and an empty uurlog.
+	Add(AddOptions),
+
+	/// List all customers with their hour rate and open balance.
+	List,
+
+	/// Show the details of a customer.
+	Show(ShowOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct AddOptions {
+	/// The name of the customer, as shown on invoices.
+	name: String,
+
+	/// The price per hour.
+	#[structopt(long)]
+	#[structopt(value_name = "AMOUNT")]
+	rate: NotNan<f64>,
+
+	/// The name of the customer in grootboek accounts, derived from the name by default.
+	#[structopt(long)]
+	#[structopt(value_name = "NAME")]
+	grootboek_name: Option<String>,
+
+	/// The directory to create, relative to the directory of zzp.toml (defaults to the grootboek name).
+	#[structopt(long)]
+	#[structopt(value_name = "DIR")]
+	dir: Option<PathBuf>,
+
+	/// The email address to send invoices to.
+	#[structopt(long)]
+	#[structopt(value_name = "ADDRESS")]
+	email: Option<String>,
+
+	/// The Peppol participant identifier of the customer, like 0106:12345678.
+	#[structopt(long)]
+	#[structopt(value_name = "SCHEME:ID")]
+	peppol_id: Option<String>,
+
+	/// The Dutch government organization identification number (OIN) of the customer.
+	#[structopt(long)]
+	#[structopt(value_name = "OIN")]
+	oin: Option<String>,
+
+	/// How to deliver invoices to the customer: pdf-email, ubl or peppol.
+	#[structopt(long)]
+	#[structopt(value_name = "METHOD")]
+	delivery: Option<DeliveryMethod>,
+
+	/// A line of the address of the customer (can be repeated).
+	#[structopt(long)]
+	#[structopt(value_name = "LINE")]
+	#[structopt(number_of_values = 1)]
+	address: Vec<String>,
+
+	/// The number of days the customer has to pay an invoice, instead of the default from zzp.toml.
+	#[structopt(long)]
+	#[structopt(value_name = "DAYS")]
+	payment_days: Option<u32>,
+
+	/// A short code for the customer, used for {customer_code} in invoice numbers.
+	#[structopt(long)]
+	#[structopt(value_name = "CODE")]
+	code: Option<String>,
+
+	/// The format for invoice numbers of the customer, instead of the default from zzp.toml.
+	#[structopt(long)]
+	#[structopt(value_name = "FORMAT")]
+	number_format: Option<String>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ShowOptions {
+	/// The name or grootboek name of the customer.
+	name: String,
+}
+
+pub fn run(command: CustomerCommand, format: OutputFormat) -> Result<(), Error> {
+	let (root_dir, zzp_config) = super::read_zzp_config()?;
+	match command {
+		CustomerCommand::Add(options) => add_customer(&root_dir, &zzp_config, options),
+		CustomerCommand::List => list_customers(&root_dir, &zzp_config, format),
+		CustomerCommand::Show(options) => show_customer(&root_dir, &zzp_config, options, format),
+	}
+}
+
+fn add_customer(root_dir: &Path, config: &ZzpConfig, options: AddOptions) -> Result<(), Error> {
+	let grootboek_name = options.grootboek_name.unwrap_or_else(|| slug(&options.name));
+	if grootboek_name.is_empty() {
+		return Err(format!("can not derive a grootboek name from {:?}: use --grootboek-name", options.name).into());
+	}
+
+	for (path, customer_config) in read_customers(root_dir)? {
+		let customer = &customer_config.customer;
+		if customer.name == options.name || customer.grootboek_name == grootboek_name {
+			return Err(format!("customer {} ({}) already exists in {}", customer.name, customer.grootboek_name, path.display()).into());
+		}
+	}
+
+	if let Some(format) = &options.number_format {
+		zzp_tools::numbering::check_format(format)?;
+	}
+
+	let dir = root_dir.join(options.dir.as_deref().unwrap_or_else(|| Path::new(&grootboek_name)));
+	let config_path = dir.join("customer.toml");
+	let hours_path = dir.join(zzp_tools::DEFAULT_HOURS_PATH);
+	if config_path.exists() || hours_path.exists() {
+		return Err(format!("{} already contains a customer.toml or uurlog", dir.display()).into());
+	}
+
+	let mut customer_config = CustomerConfig::new(options.name, grootboek_name, options.rate);
+	customer_config.customer.email = options.email;
+	customer_config.customer.peppol_id = options.peppol_id;
+	customer_config.customer.oin = options.oin;
+	customer_config.customer.delivery = options.delivery;
+	customer_config.customer.address = options.address;
+	customer_config.invoice.payment_days = options.payment_days;
+	customer_config.customer.code = options.code;
+	customer_config.invoice.number_format = options.number_format;
+
+	std::fs::create_dir_all(&dir)
+		.map_err(|e| format!("failed to create directory {}: {}", dir.display(), e))?;
+	customer_config.write_file(&config_path)?;
+	std::fs::write(&hours_path, "")
+		.map_err(|e| format!("failed to write to {}: {}", hours_path.display(), e))?;
+
+	let mut zzp_config = config.clone();
+	zzp_config.apply_customer_overrides(&customer_config);
+	log::info!("created customer {} with a rate of {} per hour in {}",
+		customer_config.customer.name,
+		zzp_config.currency().format(rate_cents(options.rate)),
+		dir.display(),
+	);
+	Ok(())
+}
+
+fn list_customers(root_dir: &Path, config: &ZzpConfig, format: OutputFormat) -> Result<(), Error> {
+	let register = InvoiceRegister::open(root_dir, config)?;
+	let customers = read_customers(root_dir)?;
+
+	if format == OutputFormat::Json {
+		Json::array(customers.iter().map(|(path, customer_config)| customer_json(root_dir, path, customer_config, &register))).print();
+		return Ok(());
+	}
+
+	for (path, customer_config) in customers {
+		let mut zzp_config = config.clone();
+		zzp_config.apply_customer_overrides(&customer_config);
+		let customer = &customer_config.customer;
+		let open = open_balance(&register, &customer.name);
+		let dir = path.parent().unwrap();
+		println!("{name:<24}  {rate:>12}/h  {open:>14}  {dir}",
+			name = Paint::default(&customer.name).bold(),
+			rate = format_rate(&zzp_config, current_rate(&customer_config)),
+			open = paint_open(&zzp_config, open),
+			dir = dir.strip_prefix(root_dir).unwrap_or(dir).display(),
+		);
+	}
+
+	Ok(())
+}
+
+fn show_customer(root_dir: &Path, config: &ZzpConfig, options: ShowOptions, format: OutputFormat) -> Result<(), Error> {
+	let (path, customer_config) = read_customers(root_dir)?
+		.into_iter()
+		.find(|(_, x)| x.customer.name == options.name || x.customer.grootboek_name == options.name)
+		.ok_or_else(|| format!("customer {} not found below {}", options.name, root_dir.display()))?;
+	let register = InvoiceRegister::open(root_dir, config)?;
+	if format == OutputFormat::Json {
+		customer_json(root_dir, &path, &customer_config, &register).print();
+		return Ok(());
+	}
+	let mut zzp_config = config.clone();
+	zzp_config.apply_customer_overrides(&customer_config);
+	let currency = zzp_config.currency();
+	let customer = &customer_config.customer;
+	let invoice = &customer_config.invoice;
+
+	println!("{} {}", Paint::default("Customer:").bold(), customer.name);
+	for line in &customer.address {
+		println!("{} {}", Paint::default("Address:").bold(), line);
+	}
+	if let Some(email) = &customer.email {
+		println!("{} {}", Paint::default("Email:").bold(), email);
+	}
+	if let Some(peppol_id) = &customer.peppol_id {
+		println!("{} {}", Paint::default("Peppol ID:").bold(), peppol_id);
+	}
+	if let Some(oin) = &customer.oin {
+		println!("{} {}", Paint::default("OIN:").bold(), oin);
+	}
+	if let Some(delivery) = customer.delivery {
+		println!("{} {}", Paint::default("Delivery:").bold(), delivery);
+	}
+	if let Some(vat_number) = &customer.vat_number {
+		println!("{} {}", Paint::default("VAT number:").bold(), vat_number);
+	}
+	println!("{} {}", Paint::default("Grootboek name:").bold(), customer.grootboek_name);
+	if let Some(code) = &customer.code {
+		println!("{} {}", Paint::default("Code:").bold(), code);
+	}
+	match &invoice.price_per_hour {
+		HourlyRate::Fixed(rate) => println!("{} {}", Paint::default("Rate:").bold(), currency.format(rate_cents(*rate))),
+		HourlyRate::Periods(periods) => {
+			for period in periods {
+				println!("{} {} (from {})", Paint::default("Rate:").bold(), currency.format(rate_cents(period.rate)), period.start_date);
+			}
+		},
+	}
+	for tag in &zzp_config.tag {
+		if let Some(price_per_hour) = tag.price_per_hour {
+			println!("{} {} ({})", Paint::default("Rate:").bold(), currency.format(rate_cents(price_per_hour)), tag.name);
+		}
+	}
+	println!("{} {}", Paint::default("Payment days:").bold(), invoice.payment_days.or(config.invoice.payment_days).unwrap_or(zzp_tools::register::DEFAULT_PAYMENT_DAYS));
+	println!("{} {}", Paint::default("Number format:").bold(), zzp_config.invoice.number_format.as_deref().unwrap_or(zzp_tools::numbering::DEFAULT_NUMBER_FORMAT));
+	for recurring in &customer_config.recurring {
+		println!("{} {}", Paint::default("Recurring:").bold(), recurring.name);
+	}
+
+	let invoices: Vec<_> = register.invoices().iter()
+		.filter(|x| x.customer == customer.name)
+		.collect();
+	println!("{} {}", Paint::default("Invoices:").bold(), invoices.len());
+	println!("{} {}", Paint::default("Open balance:").bold(), paint_open(&zzp_config, open_balance(&register, &customer.name)));
+	println!("{} {}", Paint::default("Configuration:").bold(), path.display());
+
+	Ok(())
+}
+
+/// Get the details of a customer as JSON.
+fn customer_json(root_dir: &Path, path: &Path, customer_config: &CustomerConfig, register: &InvoiceRegister) -> Json {
+	let customer = &customer_config.customer;
+	let dir = path.parent().unwrap();
+	Json::object([
+		("name", customer.name.as_str().into()),
+		("grootboek_name", customer.grootboek_name.as_str().into()),
+		("code", customer.code.as_deref().into()),
+		("email", customer.email.as_deref().into()),
+		("peppol_id", customer.peppol_id.as_deref().into()),
+		("oin", customer.oin.as_deref().into()),
+		("delivery", customer.delivery.map(|x| x.to_string()).into()),
+		("address", Json::array(&customer.address)),
+		("price_per_hour", current_rate(customer_config).map(|x| x.into_inner()).into()),
+		("invoices", register.invoices().iter().filter(|x| x.customer == customer.name).count().into()),
+		("open_balance", open_balance(register, &customer.name).into()),
+		("directory", dir.strip_prefix(root_dir).unwrap_or(dir).display().to_string().into()),
+	])
+}
+
+/// Read the configuration of all customers below the root directory.
+pub(crate) fn read_customers(root_dir: &Path) -> Result<Vec<(PathBuf, CustomerConfig)>, Error> {
+	let paths = CustomerConfig::find_all(root_dir)?;
+	paths.into_iter()
+		.map(|path| {
+			let config = CustomerConfig::read_file(&path)?;
+			Ok((path, config))
+		})
+		.collect()
+}
+
+/// Get the total of the unpaid invoices of a customer.
+fn open_balance(register: &InvoiceRegister, customer: &str) -> Cents {
+	register.invoices().iter()
+		.filter(|x| x.customer == customer && x.status != InvoiceStatus::Paid)
+		.map(|x| x.total())
+		.sum()
+}
+
+fn paint_open(config: &ZzpConfig, amount: Cents) -> Paint<String> {
+	if amount == Cents(0) {
+		Paint::fixed(241, config.currency().format(amount))
+	} else {
+		Paint::yellow(config.currency().format(amount))
+	}
+}
+
+/// Get the price per hour of a customer for today.
+fn current_rate(customer_config: &CustomerConfig) -> Option<NotNan<f64>> {
+	customer_config.invoice.price_per_hour.at(Date::today())
+}
+
+fn format_rate(config: &ZzpConfig, rate: Option<NotNan<f64>>) -> String {
+	match rate {
+		Some(rate) => config.currency().format(rate_cents(rate)),
+		None => "-".into(),
+	}
+}
+
+fn rate_cents(rate: NotNan<f64>) -> Cents {
+	Cents::from_f64(rate.into_inner(), zzp::grootboek::RoundingMode::HalfAwayFromZero)
+}
+
+/// Derive a name for grootboek accounts and directories from a customer name.
+///
+/// The name is converted to lowercase, and everything except letters and digits is replaced by a single dash.
+fn slug(name: &str) -> String {
+	let mut slug = String::new();
+	for c in name.chars() {
+		if c.is_alphanumeric() {
+			slug.extend(c.to_lowercase());
+		} else if !slug.is_empty() && !slug.ends_with('-') {
+			slug.push('-');
+		}
+	}
+	slug.trim_end_matches('-').to_string()
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp_tools::ZzpConfig;
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct InitOptions {
+	/// The directory to create zzp.toml in [default: the working directory].
+	#[structopt(long, short)]
+	#[structopt(value_name = "DIR")]
+	directory: Option<PathBuf>,
+
+	/// Overwrite zzp.toml if it already exists.
+	#[structopt(long)]
+	force: bool,
+}
+
+pub fn run(options: InitOptions) -> Result<(), Error> {
+	let dir = match options.directory {
+		Some(dir) => dir,
+		None => zzp_tools::workspace::current_dir()?,
+	};
+	let path = dir.join("zzp.toml");
+	if path.exists() && !options.force {
+		return Err(format!("{} already exists, use --force to overwrite it", path.display()).into());
+	}
+
+	let data = ZzpConfig::example().to_commented_toml()?;
+	std::fs::create_dir_all(&dir)
+		.map_err(|e| format!("failed to create directory {}: {}", dir.display(), e))?;
+	std::fs::write(&path, data)
+		.map_err(|e| format!("failed to write to {}: {}", path.display(), e))?;
+
+	log::info!("created {}, edit it to fill in your company details", path.display());
+	log::info!("add customers with `zzp customer add`");
+	Ok(())
+}
@@ -0,0 +1,784 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::gregorian::Date;
+use zzp::grootboek::{Cents, Transaction};
+use zzp::partial_date::PartialDate;
+use zzp::uurlog::Hours;
+use zzp_tools::generate::{GenerateError, GenerateOptions, GeneratedInvoice, InvoiceError};
+use zzp_tools::hours::HourRates;
+use zzp_tools::invoice::InvoiceRenderer;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::aging::AGING_COLUMNS;
+use zzp_tools::register::{InvoiceRegister, InvoiceStatus, RegisteredInvoice, DEFAULT_REMINDER_INTERVAL};
+use zzp_tools::{Currency, CustomerConfig, ZzpConfig};
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub enum InvoiceCommand {
+	/// List the invoices in the invoice register.
+	List(ListOptions),
+
+	/// Show the details of an invoice from the invoice register.
+	Show(ShowOptions),
+
+	/// Show the unpaid invoices and compare them with the debitor balances in the grootboek.
+	Open(OpenOptions),
+
+	/// Show the outstanding amount per customer by the number of days past the due date.
+	Aging(AgingOptions),
+
+	/// Send payment reminders for overdue invoices by email.
+	Remind(RemindOptions),
+
+	/// Book the payment of an invoice in the grootboek and mark it as paid.
+	Paid(PaidOptions),
+
+	/// Submit an invoice to the Peppol access point, for example again after a failed delivery.
+	Peppol(PeppolOptions),
+
+	/// Generate the recurring invoices of all customers for a period.
+	Recurring(RecurringOptions),
+
+	/// Generate an invoice for each customer with uninvoiced hours in a period.
+	All(AllOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ListOptions {
+	/// Only list invoices for customers with this name.
+	#[structopt(long)]
+	#[structopt(value_name = "NAME")]
+	customer: Option<String>,
+
+	/// Only list invoices with an invoice date in this year.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY")]
+	year: Option<i16>,
+
+	/// Only list invoices with this status.
+	#[structopt(long)]
+	#[structopt(value_name = "created|sent|paid")]
+	status: Option<InvoiceStatus>,
+
+	/// Only list unpaid invoices that are past their due date.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "status")]
+	overdue: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct ShowOptions {
+	/// The number of the invoice to show.
+	number: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct OpenOptions {
+	/// Only show invoices for customers with this name.
+	#[structopt(long)]
+	#[structopt(value_name = "NAME")]
+	customer: Option<String>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct AgingOptions {
+	/// Compute the report for this date instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct RemindOptions {
+	/// The numbers of the invoices to send a reminder for [default: all overdue invoices that need a reminder].
+	numbers: Vec<String>,
+
+	/// Use this date instead of today to determine which invoices are overdue.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+
+	/// The minimum number of days between reminders for the same invoice [default: 14].
+	#[structopt(long)]
+	#[structopt(value_name = "DAYS")]
+	interval: Option<u32>,
+
+	/// Only show the reminders that would be sent.
+	#[structopt(long)]
+	dry_run: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct PaidOptions {
+	/// The number of the paid invoice.
+	number: String,
+
+	/// The date of the payment instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+
+	/// The grootboek account of the bank account that received the payment.
+	#[structopt(long)]
+	#[structopt(value_name = "ACCOUNT")]
+	bank_account: Option<String>,
+
+	/// Only mark the invoice as paid, without adding the payment to the grootboek.
+	#[structopt(long)]
+	skip_grootboek: bool,
+
+	/// Add the payment to the grootboek even if it contains transactions with a later date.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "skip-grootboek")]
+	force_grootboek: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct PeppolOptions {
+	/// The number of the invoice to submit.
+	number: String,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct RecurringOptions {
+	/// The period to generate recurring invoices for, every month in the period is invoiced.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY[-MM]")]
+	period: PartialDate,
+
+	/// The date to use for the invoices instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+
+	/// Send the invoices to the customers by email after creating them, even without an email delivery method.
+	#[structopt(long)]
+	email: bool,
+
+	/// Do not automatically add the invoices to the grootboek.
+	#[structopt(long)]
+	skip_grootboek: bool,
+
+	/// Add the invoices to the grootboek even if it contains transactions with a later date.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "skip-grootboek")]
+	force_grootboek: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct AllOptions {
+	/// The period to create invoices for, like 2024-05 or last-month.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY[-MM[-DD]]")]
+	period: PartialDate,
+
+	/// The date to use for the invoices instead of today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+
+	/// Create draft invoices with a watermark, without adding them to the grootboek or using invoice numbers.
+	#[structopt(long)]
+	draft: bool,
+
+	/// Send the invoices to the customers by email after creating them, even without an email delivery method.
+	#[structopt(long)]
+	email: bool,
+
+	/// Do not automatically add the invoices to the grootboek.
+	#[structopt(long)]
+	skip_grootboek: bool,
+
+	/// Add the invoices to the grootboek even if it contains transactions with a later date.
+	#[structopt(long)]
+	#[structopt(conflicts_with = "skip-grootboek")]
+	force_grootboek: bool,
+}
+
+pub fn run(command: InvoiceCommand, format: OutputFormat) -> Result<(), Error> {
+	let (root_dir, zzp_config) = super::read_zzp_config()?;
+	let mut register = InvoiceRegister::open(&root_dir, &zzp_config)?;
+
+	match command {
+		InvoiceCommand::List(options) => list_invoices(&zzp_config, &register, options, format),
+		InvoiceCommand::Show(options) => show_invoice(&zzp_config, &register, options, format),
+		InvoiceCommand::Open(options) => open_invoices(&root_dir, &zzp_config, &register, options, format),
+		InvoiceCommand::Aging(options) => show_aging(&zzp_config, &register, options, format),
+		InvoiceCommand::Remind(options) => send_reminders(&root_dir, &zzp_config, &mut register, options),
+		InvoiceCommand::Paid(options) => mark_paid(&root_dir, &zzp_config, &mut register, options),
+		InvoiceCommand::Peppol(options) => submit_peppol(&root_dir, &zzp_config, &mut register, options),
+		InvoiceCommand::Recurring(options) => generate_recurring(&root_dir, &zzp_config, options),
+		InvoiceCommand::All(options) => generate_all(&root_dir, &zzp_config, options),
+	}
+}
+
+fn list_invoices(config: &ZzpConfig, register: &InvoiceRegister, options: ListOptions, format: OutputFormat) -> Result<(), Error> {
+	let today = Date::today();
+	let invoices = register.invoices().iter()
+		.filter(|x| options.customer.as_ref().map(|name| &x.customer == name).unwrap_or(true))
+		.filter(|x| options.year.map(|year| x.date.year() == year).unwrap_or(true))
+		.filter(|x| options.status.map(|status| x.status == status).unwrap_or(true))
+		.filter(|x| !options.overdue || x.is_overdue(today));
+
+	if format == OutputFormat::Json {
+		Json::array(invoices.map(|x| invoice_json(x, today))).print();
+		return Ok(());
+	}
+
+	for invoice in invoices {
+		println!("{number}  {date}  {due_date}  {status:<7}  {total:>14}  {customer:<24}  {file}",
+			number = Paint::magenta(&invoice.number),
+			date = Paint::cyan(invoice.date),
+			due_date = Paint::cyan(invoice.due_date),
+			status = paint_status(invoice, today),
+			total = format_total(config, invoice),
+			customer = invoice.customer,
+			file = Paint::fixed(241, invoice.file.display()),
+		);
+	}
+
+	Ok(())
+}
+
+fn show_invoice(config: &ZzpConfig, register: &InvoiceRegister, options: ShowOptions, format: OutputFormat) -> Result<(), Error> {
+	let invoice = register.find(&options.number)
+		.ok_or_else(|| format!("invoice {} not found in {}", options.number, register.path().display()))?;
+	let today = Date::today();
+	if format == OutputFormat::Json {
+		invoice_json(invoice, today).print();
+		return Ok(());
+	}
+
+	println!("{} {}", Paint::default("Invoice:").bold(), Paint::magenta(&invoice.number));
+	println!("{} {}", Paint::default("Customer:").bold(), invoice.customer);
+	println!("{} {}", Paint::default("Debitor account:").bold(), invoice.debitor_account);
+	println!("{} {}", Paint::default("Date:").bold(), Paint::cyan(invoice.date));
+	println!("{} {}", Paint::default("Due date:").bold(), Paint::cyan(invoice.due_date));
+	println!("{} {}", Paint::default("Total:").bold(), format_total(config, invoice));
+	println!("{} {}", Paint::default("Status:").bold(), paint_status(invoice, today));
+	if let Some(date) = invoice.sent_date {
+		println!("{} {}", Paint::default("Sent:").bold(), Paint::cyan(date));
+	}
+	if let Some(date) = invoice.reminder_date {
+		println!("{} {}", Paint::default("Last reminder:").bold(), Paint::cyan(date));
+	}
+	if let Some(date) = invoice.paid_date {
+		println!("{} {}", Paint::default("Paid:").bold(), Paint::cyan(date));
+	}
+	if let Some(status) = invoice.peppol_status {
+		match &invoice.peppol_reference {
+			Some(reference) => println!("{} {} ({})", Paint::default("Peppol:").bold(), status, reference),
+			None => println!("{} {}", Paint::default("Peppol:").bold(), status),
+		}
+	}
+	println!("{} {}", Paint::default("File:").bold(), invoice.file.display());
+
+	Ok(())
+}
+
+/// Show the unpaid invoices with the number of days they are outstanding.
+///
+/// The outstanding amount per debitor account is compared with the balance of the account in the grootboek,
+/// to find payments that were booked without marking the invoice as paid (or the other way around).
+/// The balance includes all grootboek files from the date of the first invoice of the debitor account.
+fn open_invoices(root_dir: &Path, config: &ZzpConfig, register: &InvoiceRegister, options: OpenOptions, format: OutputFormat) -> Result<(), Error> {
+	let today = Date::today();
+	let mut invoices: Vec<_> = register.invoices().iter()
+		.filter(|x| x.status != InvoiceStatus::Paid)
+		.filter(|x| options.customer.as_ref().map(|name| &x.customer == name).unwrap_or(true))
+		.collect();
+	invoices.sort_by_key(|x| (x.due_date, x.date));
+	if invoices.is_empty() && format == OutputFormat::Text {
+		log::info!("no open invoices");
+		return Ok(());
+	}
+
+	// The outstanding amount per debitor account according to the invoice register.
+	let mut outstanding: BTreeMap<&str, Cents> = BTreeMap::new();
+	for invoice in &invoices {
+		*outstanding.entry(&invoice.debitor_account).or_insert(Cents(0)) += invoice.total();
+	}
+	let first_date = register.invoices().iter()
+		.filter(|x| outstanding.contains_key(x.debitor_account.as_str()))
+		.map(|x| x.date)
+		.min()
+		.unwrap_or(today);
+
+	let mut balances: BTreeMap<&str, Cents> = outstanding.keys().map(|&x| (x, Cents(0))).collect();
+	let paths = super::grootboek_paths(root_dir, config, first_date, today)?;
+	for path in paths {
+		let data = std::fs::read_to_string(&path)
+			.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+		let transactions = Transaction::parse_from_str(&data)
+			.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+		for mutation in transactions.iter().filter(|x| x.date >= first_date).flat_map(|x| &x.mutations) {
+			if let Some(balance) = balances.get_mut(mutation.account.as_str()) {
+				*balance += mutation.amount;
+			}
+		}
+	}
+
+	let total: Cents = outstanding.values().sum();
+	let overdue: Cents = invoices.iter().filter(|x| x.is_overdue(today)).map(|x| x.total()).sum();
+
+	if format == OutputFormat::Json {
+		Json::object([
+			("invoices", Json::array(invoices.iter().map(|x| invoice_json(x, today)))),
+			("debitors", Json::array(outstanding.iter().map(|(account, amount)| Json::object([
+				("account", (*account).into()),
+				("outstanding", (*amount).into()),
+				("grootboek_balance", balances[account].into()),
+			])))),
+			("total", total.into()),
+			("overdue", overdue.into()),
+		]).print();
+		return Ok(());
+	}
+
+	for invoice in &invoices {
+		let days = format!("{} days", invoice.days_outstanding(today));
+		let overdue = invoice.is_overdue(today);
+		println!("{number}  {date}  {due_date}  {days:>9}  {total:>14}  {customer}",
+			number = Paint::magenta(&invoice.number),
+			date = Paint::cyan(invoice.date),
+			due_date = if overdue { Paint::red(invoice.due_date).bold() } else { Paint::cyan(invoice.due_date) },
+			days = if overdue { Paint::red(days).bold() } else { Paint::default(days) },
+			total = if overdue { Paint::red(format_total(config, invoice)).bold() } else { Paint::default(format_total(config, invoice)) },
+			customer = invoice.customer,
+		);
+	}
+
+	println!();
+	for (account, amount) in &outstanding {
+		let balance = balances[account];
+		let status = if balance == *amount {
+			Paint::green(String::from("ok"))
+		} else {
+			Paint::red(format!("grootboek balance {}", config.currency().format(balance))).bold()
+		};
+		println!("{account:<24}  {amount:>14}  {status}",
+			account = account,
+			amount = config.currency().format(*amount),
+			status = status,
+		);
+	}
+
+	println!("{} {}", Paint::default("Total open:").bold(), config.currency().format(total));
+	println!("{} {}", Paint::default("Total overdue:").bold(), Paint::red(config.currency().format(overdue)).bold());
+
+	Ok(())
+}
+
+/// Show the outstanding amount per customer by the number of days past the due date.
+fn show_aging(config: &ZzpConfig, register: &InvoiceRegister, options: AgingOptions, format: OutputFormat) -> Result<(), Error> {
+	let today = options.date.unwrap_or_else(Date::today);
+	let lines = zzp_tools::aging::aging_report(register, today);
+
+	if format == OutputFormat::Json {
+		Json::array(lines.iter().map(|line| Json::object([
+			("customer", line.customer.as_str().into()),
+			("currency", line.currency.as_str().into()),
+			("amounts", Json::object(AGING_COLUMNS.iter().zip(line.amounts).map(|(&column, amount)| (column, amount.into())))),
+			("total", line.total().into()),
+		]))).print();
+		return Ok(());
+	}
+
+	if lines.is_empty() {
+		log::info!("no open invoices");
+		return Ok(());
+	}
+
+	print!("{:<24}", Paint::default("Customer").bold());
+	for column in AGING_COLUMNS {
+		print!("  {:>14}", Paint::default(column).bold());
+	}
+	println!("  {:>14}", Paint::default("total").bold());
+	for line in &lines {
+		print!("{:<24}", line.customer);
+		for (i, amount) in line.amounts.iter().enumerate() {
+			let amount = format_amount(config, &line.currency, *amount);
+			if i > 0 && line.amounts[i] != Cents(0) {
+				print!("  {:>14}", Paint::red(amount).bold());
+			} else {
+				print!("  {:>14}", amount);
+			}
+		}
+		println!("  {:>14}", format_amount(config, &line.currency, line.total()));
+	}
+
+	Ok(())
+}
+
+/// Send payment reminders for overdue invoices by email and record them in the invoice register.
+///
+/// Without invoice numbers, a reminder is sent for each overdue invoice that did not get a reminder recently.
+/// Errors for one invoice are reported, but do not prevent sending the other reminders.
+fn send_reminders(root_dir: &Path, config: &ZzpConfig, register: &mut InvoiceRegister, options: RemindOptions) -> Result<(), Error> {
+	let today = options.date.unwrap_or_else(Date::today);
+	let interval = options.interval.unwrap_or(DEFAULT_REMINDER_INTERVAL);
+	let numbers: Vec<String> = if options.numbers.is_empty() {
+		register.invoices().iter()
+			.filter(|x| x.needs_reminder(today, interval))
+			.map(|x| x.number.clone())
+			.collect()
+	} else {
+		for number in &options.numbers {
+			let invoice = register.find(number)
+				.ok_or_else(|| format!("invoice {} not found in {}", number, register.path().display()))?;
+			if !invoice.is_overdue(today) {
+				return Err(format!("invoice {} is not overdue", number).into());
+			}
+		}
+		options.numbers
+	};
+	if numbers.is_empty() {
+		log::info!("no invoices need a reminder");
+		return Ok(());
+	}
+
+	let mut customers = BTreeMap::new();
+	for path in CustomerConfig::find_all(root_dir)? {
+		let customer_config = CustomerConfig::read_file(&path)?;
+		customers.insert(customer_config.customer.name.clone(), customer_config);
+	}
+
+	let mut failed = 0;
+	for number in &numbers {
+		let invoice = register.find(number).unwrap().clone();
+		let customer_config = match customers.get(&invoice.customer) {
+			Some(customer_config) => customer_config,
+			None => {
+				log::error!("invoice {}: customer {} not found", number, invoice.customer);
+				failed += 1;
+				continue;
+			},
+		};
+		let mut zzp_config = config.clone();
+		zzp_config.apply_customer_overrides(customer_config);
+
+		let total = format_total(&zzp_config, &invoice);
+		if options.dry_run {
+			println!("{number}  {due_date}  {days:>9}  {total:>14}  {customer}",
+				number = Paint::magenta(&invoice.number),
+				due_date = Paint::cyan(invoice.due_date),
+				days = Paint::red(format!("{} days", invoice.days_overdue(today))).bold(),
+				total = total,
+				customer = invoice.customer,
+			);
+			continue;
+		}
+
+		let attachments = [root_dir.join(&invoice.file)];
+		let reminder = zzp_tools::email::ReminderEmail {
+			invoice_number: number,
+			total: &total,
+			invoice_date: invoice.date,
+			due_date: invoice.due_date,
+			days_overdue: invoice.days_overdue(today),
+			attachments: &attachments,
+		};
+		if let Err(e) = zzp_tools::email::send_reminder(root_dir, &zzp_config, &customer_config.customer, &reminder) {
+			log::error!("invoice {}: {}", number, e);
+			failed += 1;
+			continue;
+		}
+
+		// Save after each reminder, so a later failure does not cause reminders to be sent twice.
+		register.mark_reminded(number, today)?;
+		register.save()?;
+		log::info!("sent a payment reminder for invoice {} to {}", number, invoice.customer);
+	}
+
+	if failed != 0 {
+		Err(format!("failed to send {} of {} payment reminder(s)", failed, numbers.len()).into())
+	} else {
+		Ok(())
+	}
+}
+
+fn mark_paid(root_dir: &Path, config: &ZzpConfig, register: &mut InvoiceRegister, options: PaidOptions) -> Result<(), Error> {
+	let date = options.date.unwrap_or_else(Date::today);
+	register.mark_paid(&options.number, date)?;
+	let invoice = register.find(&options.number).unwrap();
+
+	if !options.skip_grootboek {
+		zzp_tools::grootboek::append_payment(root_dir, config, invoice, date, options.bank_account.as_deref(), options.force_grootboek)?;
+	}
+
+	register.save()?;
+	log::info!("marked invoice {} as paid on {}", options.number, date);
+
+	let invoice = register.find(&options.number).unwrap();
+	zzp_tools::hooks::run_paid_hook(root_dir, config, invoice, date)?;
+
+	Ok(())
+}
+
+/// Submit an invoice to the Peppol access point and record the result in the register.
+fn submit_peppol(root_dir: &Path, config: &ZzpConfig, register: &mut InvoiceRegister, options: PeppolOptions) -> Result<(), Error> {
+	let reference = zzp_tools::peppol::deliver_invoice(root_dir, config, register, &options.number)?;
+	match reference {
+		Some(reference) => log::info!("submitted invoice {} to the Peppol access point with reference {}", options.number, reference),
+		None => log::info!("submitted invoice {} to the Peppol access point", options.number),
+	}
+	Ok(())
+}
+
+/// Generate the recurring invoices of all customers for each month in a period.
+///
+/// Months that already have an invoice in the invoice register are skipped,
+/// so running the command again for the same period does not create duplicate invoices.
+fn generate_recurring(root_dir: &Path, config: &ZzpConfig, options: RecurringOptions) -> Result<(), Error> {
+	let period = options.period.as_range();
+	let date = options.date.unwrap_or_else(Date::today);
+
+	let customer_config_paths = CustomerConfig::find_all(root_dir)?;
+	let renderer = InvoiceRenderer::new(config);
+	for path in customer_config_paths {
+		let customer_config = CustomerConfig::read_file(&path)?;
+		let mut zzp_config = config.clone();
+		zzp_config.apply_customer_overrides(&customer_config);
+
+		let mut month = period.start.year_month();
+		while month.first_day() < period.end {
+			for recurring in customer_config.recurring.iter().filter(|x| x.is_active(month)) {
+				let key = recurring.key(month);
+				let register = InvoiceRegister::open(root_dir, &zzp_config)?;
+				let existing = register.invoices().iter()
+					.find(|x| x.customer == customer_config.customer.name && x.recurring.as_deref() == Some(key.as_str()));
+				if let Some(existing) = existing {
+					log::info!("skipping {} for {}: already invoiced with invoice {}", key, customer_config.customer.name, existing.number);
+					continue;
+				}
+
+				let entry = recurring.make_entry(&zzp_config, month)?;
+				let generate_options = GenerateOptions {
+					email: options.email,
+					skip_grootboek: options.skip_grootboek,
+					force_grootboek: options.force_grootboek,
+					reference: customer_config.invoice.reference.as_deref(),
+					recurring: Some(&key),
+					renderer: Some(&renderer),
+					.. GenerateOptions::new(date)
+				};
+				let generated = zzp_tools::generate::generate_invoice(root_dir, &zzp_config, &customer_config, vec![entry], &generate_options)
+					.map_err(|e| format!("{}: {}", path.display(), e))?;
+				log::info!("generated invoice {} for {} ({}): {}", generated.number, customer_config.customer.name, key, generated.path.display());
+			}
+			month = month.next();
+		}
+	}
+
+	Ok(())
+}
+
+/// Generate an invoice for each customer with hours in a period.
+///
+/// Hours on dates that are already invoiced are left out, so customers are only invoiced for new hours.
+/// Errors for one customer are reported, but do not prevent invoicing the other customers.
+fn generate_all(root_dir: &Path, config: &ZzpConfig, options: AllOptions) -> Result<(), Error> {
+	let period = options.period.as_range();
+	let date = options.date.unwrap_or_else(Date::today);
+
+	let customer_config_paths = CustomerConfig::find_all(root_dir)?;
+
+	// The fonts and styles are the same for all customers, so one renderer is used for all invoices.
+	let renderer = InvoiceRenderer::new(config);
+	let generate_options = GenerateOptions {
+		draft: options.draft,
+		email: options.email,
+		skip_grootboek: options.skip_grootboek,
+		force_grootboek: options.force_grootboek,
+		renderer: Some(&renderer),
+		.. GenerateOptions::new(date)
+	};
+
+	let mut summary = Vec::new();
+	let mut failed = 0;
+	for path in customer_config_paths {
+		match generate_for_customer(root_dir, config, &path, &period, &generate_options) {
+			Ok(Some(row)) => summary.push(row),
+			Ok(None) => (),
+			Err(e) => {
+				log::error!("{}: {}", path.display(), e);
+				failed += 1;
+			},
+		}
+	}
+
+	if !summary.is_empty() {
+		println!();
+	}
+	for (invoice, customer, hours) in &summary {
+		println!("{number}  {hours:>8}  {total:>14}  {customer}",
+			number = Paint::magenta(&invoice.number),
+			hours = Paint::yellow(hours),
+			total = config.currency().format(invoice.total),
+			customer = customer,
+		);
+	}
+	log::info!("generated {} invoice(s) for {} to {}", summary.len(), period.start, period.end.prev());
+
+	if failed != 0 {
+		Err(format!("failed to generate the invoices of {} customer(s)", failed).into())
+	} else {
+		Ok(())
+	}
+}
+
+/// Generate an invoice for the hours of a single customer in a period.
+///
+/// Hours on dates that are covered by an invoice in the register are not invoiced again.
+/// The invoice period starts at the first uninvoiced hours and ends at the end of `period`.
+///
+/// The reference and period of the invoice are filled in, the other options are taken from `options`.
+/// Returns `None` if the customer has no uninvoiced hours in the period.
+pub(crate) fn generate_for_customer(
+	root_dir: &Path,
+	config: &ZzpConfig,
+	customer_config_path: &Path,
+	period: &Range<Date>,
+	options: &GenerateOptions,
+) -> Result<Option<(GeneratedInvoice, String, Hours)>, InvoiceError> {
+	let date = options.date;
+	let customer_config = CustomerConfig::read_file(customer_config_path)
+		.map_err(InvoiceError::ReadConfig)?;
+	let mut zzp_config = config.clone();
+	zzp_config.apply_customer_overrides(&customer_config);
+	let customer = &customer_config.customer;
+
+	let mut hour_entries = customer_config.read_hours(customer_config_path.parent().unwrap())
+		.map_err(InvoiceError::ReadHours)?;
+	hour_entries.retain(|x| x.date >= period.start && x.date < period.end);
+	if hour_entries.is_empty() {
+		log::debug!("skipping {}: no hours in the period", customer.name);
+		return Ok(None);
+	}
+
+	let register = InvoiceRegister::open(root_dir, &zzp_config)
+		.map_err(GenerateError::ReadRegister)?;
+	hour_entries.retain(|x| register.find_period(&customer.name, &(x.date..x.date.next())).is_none());
+	let start = match hour_entries.iter().map(|x| x.date).min() {
+		Some(start) => start,
+		None => {
+			log::info!("skipping {}: all hours in the period are already invoiced", customer.name);
+			return Ok(None);
+		},
+	};
+
+	let mut total_hours = Hours::from_minutes(0);
+	for entry in &hour_entries {
+		total_hours += entry.hours;
+	}
+
+	let rates = HourRates::from_config(&zzp_config, &customer_config);
+	let mut entries = zzp_tools::hours::make_invoice_entries(&customer_config, hour_entries, Vec::new(), &rates, date)
+		.map_err(InvoiceError::Entries)?;
+	if let Some(discount) = &customer_config.invoice.discount {
+		let value = discount.value().map_err(InvoiceError::Entries)?;
+		let discount_entries = value.make_entries(&discount.description, date, &entries, rates.vat_percentage);
+		entries.extend(discount_entries);
+	}
+
+	let generate_options = GenerateOptions {
+		reference: customer_config.invoice.reference.as_deref(),
+		period: Some(start..period.end),
+		.. options.clone()
+	};
+	let generated = zzp_tools::generate::generate_invoice(root_dir, &zzp_config, &customer_config, entries, &generate_options)?;
+	Ok(Some((generated, customer.name.clone(), total_hours)))
+}
+
+/// Get an invoice from the register as JSON.
+pub(crate) fn invoice_json(invoice: &RegisteredInvoice, today: Date) -> Json {
+	Json::object([
+		("number", invoice.number.as_str().into()),
+		("customer", invoice.customer.as_str().into()),
+		("debitor_account", invoice.debitor_account.as_str().into()),
+		("date", invoice.date.into()),
+		("due_date", invoice.due_date.into()),
+		("currency", invoice.currency.as_str().into()),
+		("total", invoice.total().into()),
+		("status", invoice.status.to_string().into()),
+		("overdue", invoice.is_overdue(today).into()),
+		("days_outstanding", (invoice.status != InvoiceStatus::Paid).then(|| invoice.days_outstanding(today)).into()),
+		("sent_date", invoice.sent_date.into()),
+		("reminder_date", invoice.reminder_date.into()),
+		("paid_date", invoice.paid_date.into()),
+		("peppol_status", invoice.peppol_status.map(|x| x.to_string()).into()),
+		("peppol_reference", invoice.peppol_reference.as_deref().into()),
+		("file", invoice.file.display().to_string().into()),
+	])
+}
+
+/// Color the status of an invoice, marking overdue invoices in red.
+fn paint_status(invoice: &RegisteredInvoice, today: Date) -> Paint<String> {
+	if invoice.is_overdue(today) {
+		Paint::red(format!("{} (overdue)", invoice.status)).bold()
+	} else if invoice.status == InvoiceStatus::Paid {
+		Paint::green(invoice.status.to_string())
+	} else {
+		Paint::yellow(invoice.status.to_string())
+	}
+}
+
+/// Format the total of an invoice in the currency of the invoice.
+fn format_total(config: &ZzpConfig, invoice: &RegisteredInvoice) -> String {
+	format_amount(config, &invoice.currency, invoice.total())
+}
+
+/// Format an amount in the currency with the given ISO 4217 code.
+fn format_amount(config: &ZzpConfig, currency_code: &str, amount: Cents) -> String {
+	let currency = config.currency();
+	if currency.code == currency_code {
+		currency.format(amount)
+	} else {
+		// The amount is for a customer with a different currency.
+		let currency = Currency {
+			symbol: currency_code.to_string(),
+			code: currency_code.to_string(),
+			symbol_space: None,
+			decimal_separator: None,
+			thousands_separator: None,
+			locale: None,
+		};
+		currency.format(amount)
+	}
+}
@@ -0,0 +1,181 @@
+use dynfmt::{Format, SimpleCurlyFormat};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp::gregorian::Date;
+use zzp::grootboek::Transaction;
+use zzp_tools::ZzpConfig;
+use zzp_tools::json::OutputFormat;
+
+mod archive;
+mod bank;
+mod config;
+mod customer;
+mod init;
+mod invoice;
+mod report;
+#[cfg(feature = "serve")]
+mod serve;
+mod tui;
+
+/// An error of a subcommand, reported once by `main`.
+type Error = Box<dyn std::error::Error>;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+#[structopt(setting = clap::AppSettings::VersionlessSubcommands)]
+struct Options {
+	#[structopt(long, short)]
+	#[structopt(parse(from_occurrences))]
+	#[structopt(global = true)]
+	verbose: i8,
+
+	/// The administration to use from zzp-workspace.toml [env: ZZP_ADMINISTRATION].
+	#[structopt(long)]
+	#[structopt(global = true)]
+	#[structopt(value_name = "NAME")]
+	administration: Option<String>,
+
+	/// The output format of reports and lists.
+	#[structopt(long)]
+	#[structopt(global = true)]
+	#[structopt(value_name = "FORMAT")]
+	#[structopt(possible_values = &["text", "json"])]
+	#[structopt(default_value = "text")]
+	format: OutputFormat,
+
+	#[structopt(subcommand)]
+	command: Command,
+}
+
+#[derive(StructOpt)]
+enum Command {
+	/// Create a zzp.toml with all configuration options documented.
+	Init(init::InitOptions),
+
+	/// Show and manage generated invoices.
+	Invoice(invoice::InvoiceCommand),
+
+	/// Import bank statements into the grootboek.
+	Bank(bank::BankCommand),
+
+	/// Add and show customers.
+	Customer(customer::CustomerCommand),
+
+	/// Upgrade the configuration files.
+	Config(config::ConfigCommand),
+
+	/// Collect the grootboek, invoices, hour logs and reports of a year in a ZIP archive.
+	Archive(archive::ArchiveOptions),
+
+	/// Show reports for the tax return.
+	Report(report::ReportCommand),
+
+	/// Browse hours, grootboek accounts and open invoices in an interactive terminal UI.
+	Tui(tui::TuiOptions),
+
+	/// Serve the hours, grootboek accounts and invoices over HTTP for a web or mobile frontend.
+	#[cfg(feature = "serve")]
+	Serve(serve::ServeOptions),
+}
+
+fn main() {
+	let options = Options::from_args();
+	init_logging(options.verbose);
+	zzp_tools::workspace::select_administration(options.administration.clone());
+
+	if let Err(e) = do_main(options) {
+		log::error!("{}", e);
+		std::process::exit(1);
+	}
+}
+
+fn init_logging(verbosity: i8) {
+	let level = if verbosity <= -2 {
+		log::LevelFilter::Error
+	} else if verbosity == -1 {
+		log::LevelFilter::Warn
+	} else if verbosity == 0 {
+		log::LevelFilter::Info
+	} else if verbosity == 1 {
+		log::LevelFilter::Debug
+	} else {
+		log::LevelFilter::Trace
+	};
+
+	env_logger::from_env("RUST_LOG").filter_module(module_path!(), level).init();
+}
+
+fn do_main(options: Options) -> Result<(), Error> {
+	match options.command {
+		Command::Init(x) => init::run(x),
+		Command::Invoice(x) => invoice::run(x, options.format),
+		Command::Bank(x) => bank::run(x),
+		Command::Customer(x) => customer::run(x, options.format),
+		Command::Config(x) => config::run(x),
+		Command::Archive(x) => archive::run(x),
+		Command::Report(x) => report::run(x, options.format),
+		Command::Tui(x) => tui::run(x),
+		#[cfg(feature = "serve")]
+		Command::Serve(x) => serve::run(x),
+	}
+}
+
+/// Find and read the ZZP configuration.
+///
+/// Returns the directory containing `zzp.toml` and the parsed configuration.
+fn read_zzp_config() -> Result<(PathBuf, ZzpConfig), Error> {
+	let current_dir = zzp_tools::workspace::current_dir()?;
+	let zzp_config_path = zzp_tools::workspace::find_zzp_config(&current_dir)?;
+	let zzp_config = ZzpConfig::read_file(&zzp_config_path)?;
+	let root_dir = zzp_config_path.parent().unwrap().to_path_buf();
+	Ok((root_dir, zzp_config))
+}
+
+/// Get the grootboek files with transactions from the given dates.
+///
+/// The grootboek path can contain date placeholders, so the dates may be spread over multiple files.
+/// Files that do not exist are not included.
+fn grootboek_paths(root_dir: &Path, config: &ZzpConfig, start: Date, end: Date) -> Result<BTreeSet<PathBuf>, String> {
+	let mut paths = BTreeSet::new();
+	let mut date = start;
+	while date <= end {
+		let path = SimpleCurlyFormat.format(&config.grootboek.path, zzp_tools::grootboek::date_format_args(date))
+			.map_err(|e| format!("failed to expand grootboek path: {}", e))?;
+		let path = root_dir.join(&*path);
+		if path.is_file() {
+			paths.insert(path);
+		}
+		date = date.next();
+	}
+	Ok(paths)
+}
+
+/// Read the grootboek files with transactions from the given dates.
+///
+/// Returns the path and contents of each file.
+fn read_grootboek_files(root_dir: &Path, config: &ZzpConfig, start: Date, end: Date) -> Result<Vec<(PathBuf, String)>, Error> {
+	let paths = grootboek_paths(root_dir, config, start, end)?;
+	paths.into_iter()
+		.map(|path| {
+			let data = std::fs::read_to_string(&path)
+				.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+			Ok((path, data))
+		})
+		.collect()
+}
+
+/// Parse the transactions from the given dates from grootboek files.
+fn parse_grootboek_files(files: &[(PathBuf, String)], start: Date, end: Date) -> Result<Vec<Transaction<'_>>, Error> {
+	let mut transactions = Vec::new();
+	for (path, data) in files {
+		let parsed = Transaction::parse_from_str(data)
+			.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+		transactions.extend(parsed.into_iter().filter(|x| x.date >= start && x.date <= end));
+	}
+	Ok(transactions)
+}
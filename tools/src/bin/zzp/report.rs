@@ -0,0 +1,373 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use structopt::clap;
+use yansi::Paint;
+
+use zzp::gregorian::{Date, Year, YearMonth};
+use zzp::grootboek::Cents;
+use zzp::partial_date::PartialDate;
+use zzp::uurlog::Hours;
+use zzp_tools::income_statement::IncomeStatement;
+use zzp_tools::json::{Json, OutputFormat};
+use zzp_tools::quarterly_report::QuarterlyReport;
+use zzp_tools::revenue::RevenueReport;
+use zzp_tools::vat_return::VatReturn;
+use zzp_tools::ZzpConfig;
+
+use super::Error;
+
+/// The number of hours per year needed for the hours criterion (urencriterium) of the Dutch income tax.
+const HOURS_CRITERION: u32 = 1225;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub enum ReportCommand {
+	/// Show the revenue of a year per customer and quarter.
+	Revenue(RevenueOptions),
+
+	/// Show the logged hours of a year against the hours criterion (urencriterium) of 1225 hours.
+	Hours(HoursOptions),
+
+	/// Show the results, VAT position, hours and top customers of a quarter.
+	Quarter(QuarterOptions),
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct RevenueOptions {
+	/// The fiscal year to report on, defaults to the current fiscal year.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY")]
+	year: Option<i16>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct HoursOptions {
+	/// The fiscal year to report on, defaults to the current fiscal year.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY")]
+	year: Option<i16>,
+
+	/// Also count hours with one of the non-billable tags from zzp.toml.
+	#[structopt(long)]
+	non_billable: bool,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct QuarterOptions {
+	/// The quarter to report on, like 2024-Q2.
+	#[structopt(long)]
+	#[structopt(value_name = "YEAR-QUARTER")]
+	period: PartialDate,
+
+	/// Also write the report as PDF to this file.
+	#[structopt(long)]
+	#[structopt(value_name = "FILE")]
+	pdf: Option<PathBuf>,
+}
+
+pub fn run(command: ReportCommand, format: OutputFormat) -> Result<(), Error> {
+	let (root_dir, zzp_config) = super::read_zzp_config()?;
+	match command {
+		ReportCommand::Revenue(options) => revenue(&root_dir, &zzp_config, options, format),
+		ReportCommand::Hours(options) => hours(&root_dir, &zzp_config, options, format),
+		ReportCommand::Quarter(options) => quarter(&root_dir, &zzp_config, options, format),
+	}
+}
+
+fn revenue(root_dir: &Path, config: &ZzpConfig, options: RevenueOptions, format: OutputFormat) -> Result<(), Error> {
+	let year = options.year.map(Year::new).unwrap_or_else(|| config.fiscal_year_of(Date::today()));
+	let range = config.fiscal_year(year);
+
+	let customers: Vec<_> = super::customer::read_customers(root_dir)?
+		.into_iter()
+		.map(|(_, customer_config)| customer_config)
+		.collect();
+
+	let files = super::read_grootboek_files(root_dir, config, range.start, range.end.prev())?;
+	let transactions = super::parse_grootboek_files(&files, range.start, range.end.prev())?;
+
+	let report = RevenueReport::compute(config, &customers, year.to_number(), &transactions)?;
+
+	if format == OutputFormat::Json {
+		report.to_json().print();
+		return Ok(());
+	}
+
+	let currency = config.currency();
+	let amount = |x: Cents| format!("{:>14}", currency.format(x));
+	let name_width = report.customers.iter()
+		.map(|x| x.name.chars().count())
+		.chain([5])
+		.max()
+		.unwrap_or(0);
+
+	println!("{}", Paint::default(format!("Revenue {}", config.fiscal_year_name(year))).bold());
+	println!();
+	println!("{:<width$}  {:>14}  {:>14}  {:>14}  {:>14}  {:>14}", "", "Q1", "Q2", "Q3", "Q4", "Total", width = name_width);
+	for customer in &report.customers {
+		println!("{:<width$}  {}  {}  {}  {}  {}",
+			customer.name,
+			amount(customer.quarters[0]),
+			amount(customer.quarters[1]),
+			amount(customer.quarters[2]),
+			amount(customer.quarters[3]),
+			Paint::default(amount(customer.total())).bold(),
+			width = name_width,
+		);
+	}
+	if report.other != [Cents(0); 4] {
+		println!("{}  {}  {}  {}  {}  {}",
+			Paint::fixed(241, format!("{:<width$}", "Other", width = name_width)),
+			amount(report.other[0]),
+			amount(report.other[1]),
+			amount(report.other[2]),
+			amount(report.other[3]),
+			Paint::default(amount(report.other.iter().sum())).bold(),
+		);
+	}
+	let totals = report.quarter_totals();
+	println!("{}  {}  {}  {}  {}  {}",
+		Paint::default(format!("{:<width$}", "Total", width = name_width)).bold(),
+		Paint::default(amount(totals[0])).bold(),
+		Paint::default(amount(totals[1])).bold(),
+		Paint::default(amount(totals[2])).bold(),
+		Paint::default(amount(totals[3])).bold(),
+		Paint::green(amount(report.total())).bold(),
+	);
+
+	Ok(())
+}
+
+fn hours(root_dir: &Path, config: &ZzpConfig, options: HoursOptions, format: OutputFormat) -> Result<(), Error> {
+	let today = Date::today();
+	let year = options.year.map(Year::new).unwrap_or_else(|| config.fiscal_year_of(today));
+	let range = config.fiscal_year(year);
+	let non_billable_tags = config.uurlog.as_ref().map(|x| x.non_billable_tags.as_slice()).unwrap_or(&[]);
+
+	let mut customers: BTreeMap<String, Hours> = BTreeMap::new();
+	let mut months: BTreeMap<YearMonth, Hours> = BTreeMap::new();
+	let mut total = Hours::from_minutes(0);
+	for (path, customer_config) in super::customer::read_customers(root_dir)? {
+		let entries = customer_config.read_hours(path.parent().unwrap())?;
+		for entry in entries {
+			if !range.contains(&entry.date) {
+				continue;
+			}
+			if !options.non_billable && entry.tags.iter().any(|tag| non_billable_tags.contains(tag)) {
+				continue;
+			}
+			*customers.entry(customer_config.customer.name.clone()).or_insert(Hours::from_minutes(0)) += entry.hours;
+			*months.entry(entry.date.year_month()).or_insert(Hours::from_minutes(0)) += entry.hours;
+			total += entry.hours;
+		}
+	}
+
+	// Extrapolate the hours of a year in progress to the whole year.
+	let last_day = range.end.prev();
+	let days_in_year = days_between(range.start, last_day);
+	let elapsed_days = if today > last_day {
+		days_in_year
+	} else {
+		days_between(range.start, today)
+	};
+	let in_progress = elapsed_days > 0 && elapsed_days < days_in_year;
+	let projected = if in_progress {
+		Some(Hours::from_minutes((u64::from(total.total_minutes()) * days_in_year as u64 / elapsed_days as u64) as u32))
+	} else {
+		None
+	};
+	let criterion = Hours::from_hours_minutes(HOURS_CRITERION, 0);
+	let remaining = Hours::from_minutes(criterion.total_minutes().saturating_sub(total.total_minutes()));
+	let remaining_weeks = (days_in_year - elapsed_days) as f64 / 7.0;
+	let remaining_business_days = if in_progress {
+		zzp::calendar::business_days_between(today, range.end)
+	} else {
+		0
+	};
+	let percentage = f64::from(total.total_minutes()) / f64::from(criterion.total_minutes()) * 100.0;
+
+	if format == OutputFormat::Json {
+		Json::object([
+			("year", i32::from(year.to_number()).into()),
+			("hours", total.into()),
+			("criterion", HOURS_CRITERION.into()),
+			("percentage", percentage.into()),
+			("projected", projected.into()),
+			("remaining_business_days", remaining_business_days.into()),
+			("customers", Json::array(customers.iter().map(|(name, hours)| Json::object([
+				("customer", name.into()),
+				("hours", (*hours).into()),
+			])))),
+			("months", Json::array(months.iter().map(|(month, hours)| Json::object([
+				("month", month.to_string().into()),
+				("hours", (*hours).into()),
+			])))),
+		]).print();
+	} else {
+		let name_width = customers.keys().map(|x| x.chars().count()).max().unwrap_or(0);
+		println!("{}", Paint::default(format!("Hours criterion {}", config.fiscal_year_name(year))).bold());
+		println!();
+		for (name, hours) in &customers {
+			println!("{:<width$}  {:>10}", name, hours.to_string(), width = name_width);
+		}
+		if !customers.is_empty() {
+			println!();
+		}
+		for (month, hours) in &months {
+			println!("{}  {:>10}", Paint::cyan(month), hours.to_string());
+		}
+		if !months.is_empty() {
+			println!();
+		}
+
+		let done = ((percentage / 100.0).min(1.0) * 40.0).round() as usize;
+		let bar = format!("{}{}", "█".repeat(done), "░".repeat(40 - done));
+		let bar = if total >= criterion { Paint::green(bar) } else { Paint::yellow(bar) };
+		println!("{} {} of {}h ({:.1}%)", Paint::default("Total:").bold(), total, HOURS_CRITERION, percentage);
+		println!("{}", bar);
+		if let Some(projected) = projected {
+			println!("{} {}", Paint::default("Projected:").bold(), projected);
+			if remaining.total_minutes() > 0 {
+				let per_week = Hours::from_minutes((f64::from(remaining.total_minutes()) / remaining_weeks).ceil() as u32);
+				println!("{} {} per week for the rest of the year", Paint::default("Needed:").bold(), per_week);
+				if remaining_business_days > 0 {
+					let per_day = Hours::from_minutes((f64::from(remaining.total_minutes()) / f64::from(remaining_business_days)).ceil() as u32);
+					println!("{} {} per business day for the {} remaining business days", Paint::default("Needed:").bold(), per_day, remaining_business_days);
+				}
+			}
+		}
+	}
+
+	match projected {
+		Some(projected) if projected < criterion => {
+			log::warn!("at the current pace you will log about {} this year, which is not enough for the hours criterion of {} hours", projected, HOURS_CRITERION);
+		},
+		None if elapsed_days == days_in_year && total < criterion => {
+			log::warn!("the {} logged hours do not meet the hours criterion of {} hours", total, HOURS_CRITERION);
+		},
+		_ => (),
+	}
+
+	Ok(())
+}
+
+fn quarter(root_dir: &Path, config: &ZzpConfig, options: QuarterOptions, format: OutputFormat) -> Result<(), Error> {
+	let (year, quarter) = match options.period {
+		PartialDate::YearQuarter(year, quarter) => (year, quarter),
+		period => {
+			return Err(format!("invalid period {}, expected a quarter like 2024-Q2", period).into());
+		},
+	};
+	let period = options.period.to_string();
+	let start = options.period.as_start_date();
+	let end = options.period.as_end_date();
+
+	let customers = super::customer::read_customers(root_dir)?;
+	let files = super::read_grootboek_files(root_dir, config, start, end)?;
+	let transactions = super::parse_grootboek_files(&files, start, end)?;
+
+	let statement = IncomeStatement::compute(config, &transactions)?;
+	let vat_return = match &config.vat_return {
+		Some(_) => Some(VatReturn::compute(config, &transactions)?),
+		None => None,
+	};
+	let customer_configs: Vec<_> = customers.iter().map(|(_, x)| x.clone()).collect();
+	let revenue = RevenueReport::compute(config, &customer_configs, year.to_number(), &transactions)?;
+	let customer_revenue = revenue.customers.iter()
+		.map(|x| (x.name.clone(), x.quarters[usize::from(quarter) - 1]))
+		.collect();
+
+	let mut hours = Vec::new();
+	for (path, customer_config) in &customers {
+		let entries = customer_config.read_hours(path.parent().unwrap())?;
+		let total = entries.iter()
+			.filter(|x| x.date >= start && x.date <= end)
+			.fold(Hours::from_minutes(0), |total, x| total + x.hours);
+		if total.total_minutes() > 0 {
+			hours.push((customer_config.customer.name.clone(), total));
+		}
+	}
+	hours.sort();
+
+	let report = QuarterlyReport::new(period.clone(), &statement, vat_return.as_ref(), hours, customer_revenue);
+
+	match format {
+		OutputFormat::Json => report.to_json().print(),
+		OutputFormat::Text => print_quarterly_report(config, &report),
+	}
+
+	if let Some(path) = &options.pdf {
+		let file = std::fs::File::create(path)
+			.map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+		report.make_pdf(std::io::BufWriter::new(file), config)
+			.map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+	}
+
+	Ok(())
+}
+
+fn print_quarterly_report(config: &ZzpConfig, report: &QuarterlyReport) {
+	let currency = config.currency();
+	let amount = |x: Cents| format!("{:>14}", currency.format(x));
+	let name_width = report.hours.iter()
+		.map(|(name, _)| name.chars().count())
+		.chain(report.top_customers.iter().map(|(name, _)| name.chars().count()))
+		.chain([13])
+		.max()
+		.unwrap_or(0);
+	let heading = |title: &str| println!("{}", Paint::cyan(title).bold());
+	let line = |name: &str, value: String| println!("  {:<width$}  {:>14}", name, value, width = name_width);
+	let total = |name: &str, value: String| println!("  {}  {}",
+		Paint::default(format!("{:<width$}", name, width = name_width)).bold(),
+		Paint::default(format!("{:>14}", value)).bold(),
+	);
+
+	println!("{}", Paint::default(format!("Quarterly report {}", report.period)).bold());
+	println!();
+	heading("Results");
+	line("Revenue", amount(report.revenue));
+	line("Costs", amount(report.costs));
+	total("Result", amount(report.result()));
+
+	if let Some(vat) = &report.vat {
+		println!();
+		heading("VAT");
+		line("VAT to pay", amount(vat.vat));
+		line("VAT input tax", amount(vat.input_vat));
+		total("VAT due", amount(vat.vat_due));
+	}
+
+	println!();
+	heading("Hours");
+	for (name, hours) in &report.hours {
+		line(name, hours.to_string());
+	}
+	total("Total hours", report.total_hours().to_string());
+
+	if !report.top_customers.is_empty() {
+		println!();
+		heading("Top customers");
+		for (name, revenue) in &report.top_customers {
+			line(name, amount(*revenue));
+		}
+	}
+}
+
+/// Count the days from `start` until and including `end`.
+fn days_between(start: Date, end: Date) -> usize {
+	std::iter::successors(Some(start), |x| Some(x.next()))
+		.take_while(|x| *x <= end)
+		.count()
+}
@@ -0,0 +1,580 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use structopt::StructOpt;
+use structopt::clap;
+
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+use zzp::uurlog::{Entry, Hours};
+use zzp_tools::generate::{GenerateOptions, InvoiceError};
+use zzp_tools::json::Json;
+use zzp_tools::period::Period;
+use zzp_tools::register::{InvoiceRegister, InvoiceStatus};
+use zzp_tools::{CustomerConfig, ZzpConfig};
+
+use super::Error;
+
+/// The environment variable with the API token.
+const TOKEN_VARIABLE: &str = "ZZP_API_TOKEN";
+
+/// The maximum size of a request, including the headers.
+const MAX_REQUEST_SIZE: u64 = 64 * 1024;
+
+/// The number of threads that handle connections.
+const WORKER_THREADS: usize = 4;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+#[structopt(after_help = ENDPOINTS)]
+pub struct ServeOptions {
+	/// The address and port to listen on.
+	#[structopt(long)]
+	#[structopt(value_name = "ADDRESS:PORT")]
+	#[structopt(default_value = "127.0.0.1:8080")]
+	listen: String,
+
+	/// Read the API token from this file instead of the ZZP_API_TOKEN environment variable.
+	#[structopt(long)]
+	#[structopt(value_name = "FILE")]
+	token_file: Option<PathBuf>,
+
+	/// Allow a web frontend on this origin to use the API, like https://zzp.example.com.
+	#[structopt(long)]
+	#[structopt(value_name = "ORIGIN")]
+	allow_origin: Option<String>,
+}
+
+/// The endpoints, shown at the end of the help.
+const ENDPOINTS: &str = "\
+ENDPOINTS:
+    GET  /hours?period=&customer=           Hour entries, of the current month by default
+    POST /hours                             Add an hour entry: customer, date, hours, description
+    GET  /accounts?period=                  Grootboek balances, of the current fiscal year by default
+    GET  /invoices/open                     Unpaid invoices
+    POST /invoices                          Generate an invoice: customer, period, date, draft
+
+Requests must have an `Authorization: Bearer <token>` header.
+Parameters of POST requests are sent as application/x-www-form-urlencoded body.
+Responses are JSON documents.";
+
+pub fn run(options: ServeOptions) -> Result<(), Error> {
+	let (root_dir, _config) = super::read_zzp_config()?;
+	let token = match &options.token_file {
+		Some(path) => std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read {}: {}", path.display(), e))?,
+		None => std::env::var(TOKEN_VARIABLE)
+			.map_err(|_| format!("no API token: set {} or use --token-file", TOKEN_VARIABLE))?,
+	};
+	let token = token.trim();
+	if token.is_empty() {
+		return Err("the API token can not be empty".into());
+	}
+
+	let server = Server {
+		root_dir,
+		token_digest: Sha256::digest(token.as_bytes()).to_vec(),
+		allow_origin: options.allow_origin,
+		write_lock: Mutex::new(()),
+	};
+
+	let listener = TcpListener::bind(&options.listen)
+		.map_err(|e| format!("failed to listen on {}: {}", options.listen, e))?;
+	log::info!("listening on http://{}", options.listen);
+
+	// Each worker accepts and handles connections, so a slow request does not block the others.
+	std::thread::scope(|scope| {
+		for _ in 0..WORKER_THREADS {
+			scope.spawn(|| loop {
+				match listener.accept() {
+					Ok((stream, _address)) => server.handle_connection(stream),
+					Err(e) => log::warn!("failed to accept connection: {}", e),
+				}
+			});
+		}
+	});
+	Ok(())
+}
+
+struct Server {
+	/// The directory with `zzp.toml`.
+	root_dir: PathBuf,
+
+	/// The SHA-256 digest of the API token.
+	token_digest: Vec<u8>,
+
+	/// The origin that is allowed to make cross-origin requests.
+	allow_origin: Option<String>,
+
+	/// Held by requests that write to the administration, so writes never overlap.
+	write_lock: Mutex<()>,
+}
+
+/// A parsed HTTP request.
+struct Request {
+	method: String,
+	path: String,
+	headers: Vec<(String, String)>,
+
+	/// The parameters from the query string and form body.
+	params: Vec<(String, String)>,
+}
+
+/// A failed request, sent as JSON object with an error message.
+#[derive(Debug)]
+struct HttpError {
+	status: u16,
+	message: String,
+}
+
+impl Server {
+	fn handle_connection(&self, mut stream: TcpStream) {
+		if let Err(e) = stream.set_read_timeout(Some(Duration::from_secs(10))) {
+			log::warn!("failed to set read timeout: {}", e);
+		}
+
+		let (status, body) = match read_request(&mut stream) {
+			Ok(request) => {
+				let (status, body) = self.handle_request(&request)
+					.unwrap_or_else(|e| (e.status, Json::object([("error", e.message.into())])));
+				log::info!("{} {} {}", request.method, request.path, status);
+				(status, body)
+			},
+			Err(e) => {
+				log::warn!("invalid request: {}", e.message);
+				(e.status, Json::object([("error", e.message.into())]))
+			},
+		};
+
+		if let Err(e) = self.write_response(&mut stream, status, &body) {
+			log::warn!("failed to send response: {}", e);
+		}
+	}
+
+	fn handle_request(&self, request: &Request) -> Result<(u16, Json), HttpError> {
+		// Preflight requests of browsers do not have the token.
+		if request.method == "OPTIONS" && self.allow_origin.is_some() {
+			return Ok((204, Json::Null));
+		}
+		self.authorize(request)?;
+
+		let config_path = self.root_dir.join("zzp.toml");
+		let config = ZzpConfig::read_file(&config_path)
+			.map_err(|e| HttpError::internal(e.to_string()))?;
+
+		match (request.method.as_str(), request.path.as_str()) {
+			("GET", "/hours") => self.get_hours(&config, request),
+			("POST", "/hours") => self.add_hours(request),
+			("GET", "/accounts") => self.get_accounts(&config, request),
+			("GET", "/invoices/open") => self.get_open_invoices(&config),
+			("POST", "/invoices") => self.generate_invoice(&config, request),
+			(_, "/hours" | "/accounts" | "/invoices/open" | "/invoices") => {
+				Err(HttpError::new(405, format!("method {} not allowed for {}", request.method, request.path)))
+			},
+			_ => Err(HttpError::new(404, format!("no such endpoint: {}", request.path))),
+		}
+	}
+
+	/// Check the bearer token of a request.
+	fn authorize(&self, request: &Request) -> Result<(), HttpError> {
+		let token = request.header("authorization")
+			.and_then(|x| x.strip_prefix("Bearer "))
+			.ok_or_else(|| HttpError::new(401, "missing bearer token"))?;
+
+		// Compare digests, so the comparison time does not depend on the token.
+		if Sha256::digest(token.trim().as_bytes()).as_slice() != self.token_digest {
+			return Err(HttpError::new(401, "invalid token"));
+		}
+		Ok(())
+	}
+
+	/// Get the hour entries in a period, optionally of a single customer.
+	fn get_hours(&self, config: &ZzpConfig, request: &Request) -> Result<(u16, Json), HttpError> {
+		let customer = request.param("customer");
+		let month = Date::today().year_month();
+		let period = match request.param("period") {
+			Some(period) => parse_period(period, &config.period)?,
+			None => month.first_day()..month.next().first_day(),
+		};
+
+		let mut entries = Vec::new();
+		let mut total = Hours::from_minutes(0);
+		for path in CustomerConfig::find_all(&self.root_dir)? {
+			let customer_config = CustomerConfig::read_file(&path)
+				.map_err(|e| HttpError::internal(e.to_string()))?;
+			let name = &customer_config.customer.name;
+			if customer.map(|x| x != name).unwrap_or(false) {
+				continue;
+			}
+			for entry in customer_config.read_hours(path.parent().unwrap())? {
+				if entry.date >= period.start && entry.date < period.end {
+					total += entry.hours;
+					entries.push((name.clone(), entry));
+				}
+			}
+		}
+		entries.sort_by(|(a_name, a), (b_name, b)| (a.date, a_name).cmp(&(b.date, b_name)));
+
+		Ok((200, Json::object([
+			("start_date", period.start.into()),
+			("end_date", period.end.prev().into()),
+			("entries", Json::array(entries.iter().map(|(customer, entry)| entry_json(customer, entry)))),
+			("total", total.into()),
+		])))
+	}
+
+	/// Add an hour entry to the hour log of a customer.
+	fn add_hours(&self, request: &Request) -> Result<(u16, Json), HttpError> {
+		let customer = request.required_param("customer")?;
+		let date = match request.param("date") {
+			Some(date) => date.parse()
+				.map_err(|e| HttpError::bad_request(format!("invalid date {:?}: {}", date, e)))?,
+			None => Date::today(),
+		};
+		let hours = request.required_param("hours")?;
+		let hours = Hours::from_str(hours)
+			.map_err(|e| HttpError::bad_request(format!("invalid hours {:?}: {}", hours, e)))?;
+		let description = request.required_param("description")?;
+		// The entry is a single line in the hour log, so a newline would add a second entry.
+		if description.chars().any(char::is_control) {
+			return Err(HttpError::bad_request("the description of the entry can not contain newlines or other control characters"));
+		}
+		let (tags, description) = zzp::uurlog::split_tags(description)
+			.map_err(|e| HttpError::bad_request(e.to_string()))?;
+		if description.is_empty() {
+			return Err(HttpError::bad_request("the description of the entry can not be empty"));
+		}
+
+		let (customer_config_path, customer_config) = find_customer(&self.root_dir, customer)?;
+		let file = customer_config.hours_file(customer_config_path.parent().unwrap(), date)?;
+		let entry = Entry {
+			date,
+			hours,
+			tags,
+			description: description.to_string(),
+		};
+		let _write_guard = self.lock_writes();
+		zzp::uurlog::append_entry(&file, &entry)
+			.map_err(|e| HttpError::internal(format!("failed to add entry to {}: {}", file.display(), e)))?;
+
+		Ok((201, Json::object([
+			("entry", entry_json(&customer_config.customer.name, &entry)),
+			("file", file.display().to_string().into()),
+		])))
+	}
+
+	/// Get the balance of each grootboek account in a period.
+	fn get_accounts(&self, config: &ZzpConfig, request: &Request) -> Result<(u16, Json), HttpError> {
+		let period = match request.param("period") {
+			Some(period) => parse_period(period, &config.period)?,
+			None => config.fiscal_year(config.fiscal_year_of(Date::today())),
+		};
+		let end_date = period.end.prev();
+
+		let files = super::read_grootboek_files(&self.root_dir, config, period.start, end_date)
+			.map_err(|e| HttpError::internal(format!("failed to read the grootboek: {}", e)))?;
+		let transactions = super::parse_grootboek_files(&files, period.start, end_date)
+			.map_err(|e| HttpError::internal(format!("failed to parse the grootboek: {}", e)))?;
+
+		let mut balances: BTreeMap<&str, Cents> = BTreeMap::new();
+		for mutation in transactions.iter().flat_map(|x| &x.mutations) {
+			*balances.entry(mutation.account.as_str()).or_insert(Cents(0)) += mutation.amount;
+		}
+
+		Ok((200, Json::object([
+			("start_date", period.start.into()),
+			("end_date", end_date.into()),
+			("accounts", Json::array(balances.iter().map(|(account, balance)| Json::object([
+				("account", (*account).into()),
+				("balance", (*balance).into()),
+			])))),
+		])))
+	}
+
+	/// Get the unpaid invoices from the invoice register.
+	fn get_open_invoices(&self, config: &ZzpConfig) -> Result<(u16, Json), HttpError> {
+		let today = Date::today();
+		let register = InvoiceRegister::open(&self.root_dir, config)
+			.map_err(|e| HttpError::internal(e.to_string()))?;
+		let mut invoices: Vec<_> = register.invoices().iter()
+			.filter(|x| x.status != InvoiceStatus::Paid)
+			.collect();
+		invoices.sort_by_key(|x| (x.due_date, x.date));
+
+		let total: Cents = invoices.iter().map(|x| x.total()).sum();
+		let overdue: Cents = invoices.iter().filter(|x| x.is_overdue(today)).map(|x| x.total()).sum();
+		Ok((200, Json::object([
+			("invoices", Json::array(invoices.iter().map(|x| super::invoice::invoice_json(x, today)))),
+			("total", total.into()),
+			("overdue", overdue.into()),
+		])))
+	}
+
+	/// Generate an invoice for the hours of a customer in a period.
+	fn generate_invoice(&self, config: &ZzpConfig, request: &Request) -> Result<(u16, Json), HttpError> {
+		let customer = request.required_param("customer")?;
+		let date = match request.param("date") {
+			Some(date) => date.parse()
+				.map_err(|e| HttpError::bad_request(format!("invalid date {:?}: {}", date, e)))?,
+			None => Date::today(),
+		};
+		let draft = match request.param("draft") {
+			None | Some("false") => false,
+			Some("true") => true,
+			Some(value) => return Err(HttpError::bad_request(format!("invalid value for draft: {:?}, expected true or false", value))),
+		};
+
+		let (customer_config_path, customer_config) = find_customer(&self.root_dir, customer)?;
+		let mut customer_periods = config.clone();
+		customer_periods.apply_customer_overrides(&customer_config);
+		let period = parse_period(request.required_param("period")?, &customer_periods.period)?;
+
+		let options = GenerateOptions {
+			draft,
+			.. GenerateOptions::new(date)
+		};
+		let _write_guard = self.lock_writes();
+		let generated = super::invoice::generate_for_customer(&self.root_dir, config, &customer_config_path, &period, &options)?;
+		let (invoice, _customer, hours) = generated
+			.ok_or_else(|| HttpError::new(422, format!("{} has no uninvoiced hours in the period", customer)))?;
+
+		Ok((201, Json::object([
+			("number", invoice.number.as_str().into()),
+			("customer", customer.into()),
+			("hours", hours.into()),
+			("total", invoice.total.into()),
+			("file", invoice.path.display().to_string().into()),
+			("emailed", invoice.emailed.into()),
+			("submitted", invoice.submitted.into()),
+		])))
+	}
+
+	/// Wait until no other request is writing to the administration.
+	fn lock_writes(&self) -> std::sync::MutexGuard<'_, ()> {
+		// The lock protects no data, so a panic of another request while holding it does no harm.
+		self.write_lock.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	fn write_response(&self, stream: &mut TcpStream, status: u16, body: &Json) -> std::io::Result<()> {
+		let body = match body {
+			Json::Null => String::new(),
+			body => format!("{}\n", body),
+		};
+		let mut response = format!("HTTP/1.1 {} {}\r\n", status, reason_phrase(status));
+		response += "Connection: close\r\n";
+		if status == 401 {
+			response += "WWW-Authenticate: Bearer\r\n";
+		}
+		if let Some(origin) = &self.allow_origin {
+			response += &format!("Access-Control-Allow-Origin: {}\r\n", origin);
+			response += "Access-Control-Allow-Methods: GET, POST\r\n";
+			response += "Access-Control-Allow-Headers: Authorization, Content-Type\r\n";
+		}
+		if !body.is_empty() {
+			response += "Content-Type: application/json\r\n";
+		}
+		response += &format!("Content-Length: {}\r\n\r\n", body.len());
+		response += &body;
+		stream.write_all(response.as_bytes())?;
+		stream.flush()
+	}
+}
+
+impl Request {
+	/// Get the value of a header by its lowercase name.
+	fn header(&self, name: &str) -> Option<&str> {
+		self.headers.iter()
+			.find(|(key, _)| key == name)
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// Get the value of a parameter.
+	fn param(&self, name: &str) -> Option<&str> {
+		self.params.iter()
+			.find(|(key, _)| key == name)
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// Get the value of a parameter that must be present.
+	fn required_param(&self, name: &str) -> Result<&str, HttpError> {
+		self.param(name)
+			.ok_or_else(|| HttpError::bad_request(format!("missing parameter: {}", name)))
+	}
+}
+
+impl HttpError {
+	fn new(status: u16, message: impl Into<String>) -> Self {
+		Self { status, message: message.into() }
+	}
+
+	fn bad_request(message: impl Into<String>) -> Self {
+		Self::new(400, message)
+	}
+
+	fn internal(message: impl Into<String>) -> Self {
+		Self::new(500, message)
+	}
+}
+
+/// Errors from reading the administration are errors of the server.
+impl From<String> for HttpError {
+	fn from(message: String) -> Self {
+		Self::internal(message)
+	}
+}
+
+impl From<InvoiceError> for HttpError {
+	fn from(error: InvoiceError) -> Self {
+		Self::internal(error.to_string())
+	}
+}
+
+/// Read a request with the parameters from the query string and form body.
+fn read_request(stream: &mut TcpStream) -> Result<Request, HttpError> {
+	let mut reader = BufReader::new(stream.take(MAX_REQUEST_SIZE));
+
+	let request_line = read_line(&mut reader)?;
+	let mut parts = request_line.split(' ');
+	let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+		(Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => (method, target),
+		_ => return Err(HttpError::bad_request("invalid request line")),
+	};
+
+	let mut headers = Vec::new();
+	loop {
+		let line = read_line(&mut reader)?;
+		if line.is_empty() {
+			break;
+		}
+		let (name, value) = line.split_once(':')
+			.ok_or_else(|| HttpError::bad_request("invalid header"))?;
+		headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+	}
+
+	let (path, query) = target.split_once('?').unwrap_or((target, ""));
+	let mut request = Request {
+		method: method.to_string(),
+		path: path.to_string(),
+		headers,
+		params: parse_form(query)?,
+	};
+
+	let content_length = match request.header("content-length") {
+		Some(length) => length.parse::<u64>().map_err(|_| HttpError::bad_request("invalid content length"))?,
+		None => 0,
+	};
+	if content_length > 0 {
+		let mut body = Vec::new();
+		reader.take(content_length).read_to_end(&mut body)
+			.map_err(|e| HttpError::bad_request(format!("failed to read request body: {}", e)))?;
+		if body.len() as u64 != content_length {
+			return Err(HttpError::new(413, "request too large"));
+		}
+		if !request.header("content-type").unwrap_or_default().starts_with("application/x-www-form-urlencoded") {
+			return Err(HttpError::new(415, "the request body must be application/x-www-form-urlencoded"));
+		}
+		let body = std::str::from_utf8(&body)
+			.map_err(|_| HttpError::bad_request("invalid UTF-8 in request body"))?;
+		request.params.extend(parse_form(body)?);
+	}
+
+	Ok(request)
+}
+
+/// Read a line of the request without the line ending.
+fn read_line(reader: &mut impl BufRead) -> Result<String, HttpError> {
+	let mut line = String::new();
+	reader.read_line(&mut line)
+		.map_err(|e| HttpError::bad_request(format!("failed to read request: {}", e)))?;
+	if !line.ends_with('\n') {
+		return Err(HttpError::bad_request("incomplete request"));
+	}
+	Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/// Parse `application/x-www-form-urlencoded` parameters, as used in query strings.
+fn parse_form(data: &str) -> Result<Vec<(String, String)>, HttpError> {
+	data.split('&')
+		.filter(|x| !x.is_empty())
+		.map(|pair| {
+			let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+			Ok((decode_component(key)?, decode_component(value)?))
+		})
+		.collect()
+}
+
+/// Decode a percent-encoded form component, where `+` is a space.
+fn decode_component(data: &str) -> Result<String, HttpError> {
+	let mut bytes = Vec::with_capacity(data.len());
+	let mut input = data.bytes();
+	while let Some(byte) = input.next() {
+		match byte {
+			b'+' => bytes.push(b' '),
+			b'%' => {
+				let high = input.next().and_then(|x| char::from(x).to_digit(16));
+				let low = input.next().and_then(|x| char::from(x).to_digit(16));
+				match (high, low) {
+					(Some(high), Some(low)) => bytes.push((high * 16 + low) as u8),
+					_ => return Err(HttpError::bad_request(format!("invalid percent encoding in {:?}", data))),
+				}
+			},
+			byte => bytes.push(byte),
+		}
+	}
+	String::from_utf8(bytes)
+		.map_err(|_| HttpError::bad_request(format!("invalid UTF-8 in {:?}", data)))
+}
+
+/// Parse a period parameter, which can be the name of a custom period.
+fn parse_period(input: &str, periods: &[zzp_tools::PeriodConfig]) -> Result<Range<Date>, HttpError> {
+	let period: Period = input.parse()
+		.map_err(|e| HttpError::bad_request(format!("invalid period {:?}: {}", input, e)))?;
+	period.as_range(periods)
+		.map_err(HttpError::bad_request)
+}
+
+/// Find the configuration of a customer by name.
+fn find_customer(root_dir: &Path, name: &str) -> Result<(PathBuf, CustomerConfig), HttpError> {
+	for path in CustomerConfig::find_all(root_dir)? {
+		let customer_config = CustomerConfig::read_file(&path)
+			.map_err(|e| HttpError::internal(e.to_string()))?;
+		if customer_config.customer.name == name {
+			return Ok((path, customer_config));
+		}
+	}
+	Err(HttpError::new(404, format!("unknown customer {:?}", name)))
+}
+
+/// Get an hour entry with the customer as JSON.
+fn entry_json(customer: &str, entry: &Entry) -> Json {
+	Json::object([
+		("customer", customer.into()),
+		("date", entry.date.into()),
+		("hours", entry.hours.into()),
+		("tags", Json::array(&entry.tags)),
+		("description", entry.description.as_str().into()),
+	])
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+	match status {
+		200 => "OK",
+		201 => "Created",
+		204 => "No Content",
+		400 => "Bad Request",
+		401 => "Unauthorized",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		413 => "Payload Too Large",
+		415 => "Unsupported Media Type",
+		422 => "Unprocessable Entity",
+		_ => "Internal Server Error",
+	}
+}
@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use structopt::clap;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use zzp::gregorian::{Date, YearMonth};
+use zzp::grootboek::{Cents, Transaction};
+use zzp::uurlog::{Entry, Hours};
+use zzp_tools::register::{InvoiceRegister, InvoiceStatus, RegisteredInvoice};
+use zzp_tools::{Currency, CustomerConfig, ZzpConfig};
+
+use super::Error;
+
+#[derive(StructOpt)]
+#[structopt(setting = clap::AppSettings::DeriveDisplayOrder)]
+#[structopt(setting = clap::AppSettings::UnifiedHelpMessage)]
+#[structopt(setting = clap::AppSettings::ColoredHelp)]
+pub struct TuiOptions {
+	/// The date to start at, defaults to today.
+	#[structopt(long)]
+	#[structopt(value_name = "YYYY-MM-DD")]
+	date: Option<Date>,
+}
+
+/// The keys shown at the bottom of the screen.
+const HELP: &str = "q quit  tab pane  ←/→ day  pgup/pgdn month  t today  / filter  enter details  esc back  r reload";
+
+pub fn run(options: TuiOptions) -> Result<(), Error> {
+	let (root_dir, config) = super::read_zzp_config()?;
+	let mut app = App::new(root_dir, config, options.date.unwrap_or_else(Date::today));
+	app.reload();
+	if let Some(error) = app.error.take() {
+		return Err(error.into());
+	}
+
+	let mut terminal = ratatui::init();
+	let result = app.run(&mut terminal);
+	ratatui::restore();
+	result.map_err(|e| format!("terminal error: {}", e).into())
+}
+
+/// A pane of the terminal UI.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Pane {
+	Hours,
+	Accounts,
+	Invoices,
+}
+
+/// An hour entry with the customer it was logged for.
+struct HourEntry {
+	customer: String,
+	entry: Entry,
+}
+
+/// A grootboek transaction, copied out of the file it was parsed from.
+struct Booking {
+	date: Date,
+	description: String,
+	mutations: Vec<(String, Cents)>,
+}
+
+struct App {
+	root_dir: PathBuf,
+	config: ZzpConfig,
+	currency: Currency,
+
+	/// The selected date.
+	date: Date,
+
+	/// The year of the loaded bookings.
+	loaded_year: Option<i16>,
+
+	pane: Pane,
+
+	/// The filter and selected line of each pane.
+	filters: [String; 3],
+	editing_filter: bool,
+	selection: [ListState; 3],
+
+	/// The account shown in the transactions view of the accounts pane.
+	drill_down: Option<String>,
+	drill_down_selection: ListState,
+
+	hours: Vec<HourEntry>,
+	bookings: Vec<Booking>,
+	invoices: Vec<RegisteredInvoice>,
+
+	/// The last error, shown at the bottom of the screen.
+	error: Option<String>,
+	quit: bool,
+}
+
+impl Pane {
+	fn index(self) -> usize {
+		self as usize
+	}
+
+	fn next(self) -> Self {
+		match self {
+			Self::Hours => Self::Accounts,
+			Self::Accounts => Self::Invoices,
+			Self::Invoices => Self::Hours,
+		}
+	}
+
+	fn prev(self) -> Self {
+		match self {
+			Self::Hours => Self::Invoices,
+			Self::Accounts => Self::Hours,
+			Self::Invoices => Self::Accounts,
+		}
+	}
+}
+
+impl App {
+	fn new(root_dir: PathBuf, config: ZzpConfig, date: Date) -> Self {
+		Self {
+			root_dir,
+			currency: config.currency(),
+			config,
+			date,
+			loaded_year: None,
+			pane: Pane::Hours,
+			filters: Default::default(),
+			editing_filter: false,
+			selection: Default::default(),
+			drill_down: None,
+			drill_down_selection: ListState::default(),
+			hours: Vec::new(),
+			bookings: Vec::new(),
+			invoices: Vec::new(),
+			error: None,
+			quit: false,
+		}
+	}
+
+	fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+		while !self.quit {
+			terminal.draw(|frame| self.draw(frame))?;
+			if let Event::Key(key) = event::read()? {
+				if key.kind == KeyEventKind::Press {
+					self.handle_key(key);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Read the hours, grootboek and invoice register again.
+	fn reload(&mut self) {
+		self.error = None;
+		if let Err(e) = self.load_hours() {
+			self.error = Some(e);
+		}
+		if let Err(e) = self.load_invoices() {
+			self.error = Some(e);
+		}
+		self.loaded_year = None;
+		self.load_bookings();
+	}
+
+	fn load_hours(&mut self) -> Result<(), String> {
+		self.hours.clear();
+		for path in CustomerConfig::find_all(&self.root_dir)? {
+			let customer_config = CustomerConfig::read_file(&path)
+				.map_err(|e| e.to_string())?;
+			let entries = customer_config.read_hours(path.parent().unwrap())?;
+			self.hours.extend(entries.into_iter().map(|entry| HourEntry {
+				customer: customer_config.customer.name.clone(),
+				entry,
+			}));
+		}
+		self.hours.sort_by(|a, b| (a.entry.date, &a.customer).cmp(&(b.entry.date, &b.customer)));
+		Ok(())
+	}
+
+	fn load_invoices(&mut self) -> Result<(), String> {
+		let register = InvoiceRegister::open(&self.root_dir, &self.config)
+			.map_err(|e| e.to_string())?;
+		self.invoices = register.invoices().to_vec();
+		self.invoices.sort_by_key(|x| (x.due_date, x.date));
+		Ok(())
+	}
+
+	/// Load the bookings of the year of the selected date, if they are not loaded yet.
+	fn load_bookings(&mut self) {
+		let year = self.date.year().to_number();
+		if self.loaded_year == Some(year) {
+			return;
+		}
+		self.bookings.clear();
+		self.loaded_year = Some(year);
+		if let Err(e) = self.read_bookings(self.date.year().first_day(), self.date.year().last_day()) {
+			self.error = Some(e);
+		}
+	}
+
+	fn read_bookings(&mut self, start: Date, end: Date) -> Result<(), String> {
+		for path in super::grootboek_paths(&self.root_dir, &self.config, start, end)? {
+			let data = std::fs::read_to_string(&path)
+				.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+			let transactions = Transaction::parse_from_str(&data)
+				.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+			self.bookings.extend(transactions.iter()
+				.filter(|x| x.date >= start && x.date <= end)
+				.map(|x| Booking {
+					date: x.date,
+					description: x.description.to_string(),
+					mutations: x.mutations.iter().map(|x| (x.account.as_str().to_string(), x.amount)).collect(),
+				})
+			);
+		}
+		self.bookings.sort_by_key(|x| x.date);
+		Ok(())
+	}
+
+	fn set_date(&mut self, date: Date) {
+		self.date = date;
+		self.load_bookings();
+	}
+
+	fn filter(&self, pane: Pane) -> &str {
+		&self.filters[pane.index()]
+	}
+
+	fn handle_key(&mut self, key: KeyEvent) {
+		if self.editing_filter {
+			let filter = &mut self.filters[self.pane.index()];
+			match key.code {
+				KeyCode::Enter => self.editing_filter = false,
+				KeyCode::Esc => {
+					filter.clear();
+					self.editing_filter = false;
+				},
+				KeyCode::Backspace => {
+					filter.pop();
+				},
+				KeyCode::Char(c) => filter.push(c),
+				_ => (),
+			}
+			self.selection[self.pane.index()].select(Some(0));
+			return;
+		}
+
+		match key.code {
+			KeyCode::Char('q') => self.quit = true,
+			KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+			KeyCode::Tab => self.pane = self.pane.next(),
+			KeyCode::BackTab => self.pane = self.pane.prev(),
+			KeyCode::Left => self.set_date(self.date.prev()),
+			KeyCode::Right => self.set_date(self.date.next()),
+			KeyCode::PageUp => self.set_date(same_day(self.date.year_month().prev(), self.date.day())),
+			KeyCode::PageDown => self.set_date(same_day(self.date.year_month().next(), self.date.day())),
+			KeyCode::Char('t') => self.set_date(Date::today()),
+			KeyCode::Char('r') => self.reload(),
+			KeyCode::Char('/') => self.editing_filter = true,
+			KeyCode::Up => self.selected_list().select_previous(),
+			KeyCode::Down => self.selected_list().select_next(),
+			KeyCode::Home => self.selected_list().select_first(),
+			KeyCode::End => self.selected_list().select_last(),
+			KeyCode::Enter if self.pane == Pane::Accounts && self.drill_down.is_none() => {
+				let selected = self.selection[self.pane.index()].selected().unwrap_or(0);
+				self.drill_down = self.account_totals().into_iter().nth(selected).map(|(account, _)| account);
+				self.drill_down_selection = ListState::default().with_selected(Some(0));
+			},
+			KeyCode::Esc if self.pane == Pane::Accounts && self.drill_down.is_some() => self.drill_down = None,
+			KeyCode::Esc => self.filters[self.pane.index()].clear(),
+			_ => (),
+		}
+	}
+
+	fn selected_list(&mut self) -> &mut ListState {
+		if self.pane == Pane::Accounts && self.drill_down.is_some() {
+			&mut self.drill_down_selection
+		} else {
+			&mut self.selection[self.pane.index()]
+		}
+	}
+
+	/// Get the hour entries in the month of the selected date that match the filter.
+	fn month_hours(&self) -> Vec<&HourEntry> {
+		let month = self.date.year_month();
+		let filter = self.filter(Pane::Hours).to_lowercase();
+		self.hours.iter()
+			.filter(|x| x.entry.date.year_month() == month)
+			.filter(|x| {
+				matches(&x.customer, &filter)
+					|| matches(&x.entry.description, &filter)
+					|| x.entry.tags.iter().any(|tag| matches(tag, &filter))
+			})
+			.collect()
+	}
+
+	/// Get the bookings from the start of the year until the selected date.
+	fn bookings_until_date(&self) -> impl Iterator<Item = &Booking> {
+		let date = self.date;
+		self.bookings.iter().filter(move |x| x.date <= date)
+	}
+
+	/// Get the balance of each account that matches the filter, from the start of the year until the selected date.
+	fn account_totals(&self) -> Vec<(String, Cents)> {
+		let filter = self.filter(Pane::Accounts).to_lowercase();
+		let mut totals: BTreeMap<&str, Cents> = BTreeMap::new();
+		for (account, amount) in self.bookings_until_date().flat_map(|x| &x.mutations) {
+			if matches(account, &filter) {
+				*totals.entry(account).or_insert(Cents(0)) += *amount;
+			}
+		}
+		totals.into_iter().map(|(account, total)| (account.to_string(), total)).collect()
+	}
+
+	/// Get the invoices that are unpaid on the selected date and match the filter.
+	fn open_invoices(&self) -> Vec<&RegisteredInvoice> {
+		let filter = self.filter(Pane::Invoices).to_lowercase();
+		self.invoices.iter()
+			.filter(|x| x.date <= self.date)
+			.filter(|x| x.status != InvoiceStatus::Paid || x.paid_date.map(|paid| paid > self.date).unwrap_or(false))
+			.filter(|x| matches(&x.number, &filter) || matches(&x.customer, &filter))
+			.collect()
+	}
+
+	fn draw(&mut self, frame: &mut Frame) {
+		let [header, body, footer] = Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+		let [left, right] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(body);
+		let [top_right, bottom_right] = Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(right);
+
+		let title = Line::from(vec![
+			Span::styled(self.config.company.name.as_str(), Style::new().bold()),
+			Span::raw("  "),
+			Span::styled(self.date.to_string(), Style::new().cyan().bold()),
+		]);
+		frame.render_widget(Paragraph::new(title), header);
+
+		self.draw_hours(frame, left);
+		self.draw_accounts(frame, top_right);
+		self.draw_invoices(frame, bottom_right);
+
+		let footer_line = if let Some(error) = &self.error {
+			Line::styled(error.as_str(), Style::new().red().bold())
+		} else if self.editing_filter {
+			Line::from(vec![Span::styled("Filter: ", Style::new().bold()), Span::raw(self.filter(self.pane)), Span::raw("█")])
+		} else {
+			Line::styled(HELP, Style::new().fg(Color::DarkGray))
+		};
+		frame.render_widget(Paragraph::new(footer_line), footer);
+	}
+
+	fn pane_block(&self, pane: Pane, title: String) -> Block<'static> {
+		let filter = self.filter(pane);
+		let title = if filter.is_empty() {
+			title
+		} else {
+			format!("{} [/{}]", title, filter)
+		};
+		let block = Block::bordered().title(Span::styled(title, Style::new().bold()));
+		if self.pane == pane {
+			block.border_style(Style::new().yellow())
+		} else {
+			block.border_style(Style::new().fg(Color::DarkGray))
+		}
+	}
+
+	fn draw_hours(&mut self, frame: &mut Frame, area: Rect) {
+		let entries = self.month_hours();
+		let day_total = entries.iter().filter(|x| x.entry.date == self.date).fold(Hours::from_minutes(0), |total, x| total + x.entry.hours);
+		let month_total = entries.iter().fold(Hours::from_minutes(0), |total, x| total + x.entry.hours);
+		let items: Vec<_> = entries.iter()
+			.map(|x| {
+				let style = if x.entry.date == self.date { Style::new().bold() } else { Style::new() };
+				ListItem::new(Line::from(vec![
+					Span::styled(x.entry.date.to_string(), Style::new().cyan()),
+					Span::raw("  "),
+					Span::styled(format!("{:>7}", x.entry.hours.to_string()), style),
+					Span::raw("  "),
+					Span::styled(x.customer.clone(), Style::new().magenta()),
+					Span::raw("  "),
+					Span::styled(x.entry.tags.iter().map(|tag| format!("[{}] ", tag)).collect::<String>(), Style::new().green()),
+					Span::styled(x.entry.description.clone(), style),
+				]))
+			})
+			.collect();
+		let title = format!("Hours {}: {} today, {} this month", self.date.year_month(), day_total, month_total);
+		let block = self.pane_block(Pane::Hours, title);
+		let list = List::new(items).block(block).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, area, &mut self.selection[Pane::Hours.index()]);
+	}
+
+	fn draw_accounts(&mut self, frame: &mut Frame, area: Rect) {
+		if let Some(account) = self.drill_down.clone() {
+			return self.draw_transactions(frame, area, &account);
+		}
+		let totals = self.account_totals();
+		let width = totals.iter().map(|(account, _)| account.chars().count()).max().unwrap_or(0);
+		let items: Vec<_> = totals.iter()
+			.map(|(account, total)| ListItem::new(Line::from(vec![
+				Span::raw(format!("{:<width$}", account, width = width)),
+				Span::raw("  "),
+				Span::styled(format!("{:>14}", self.currency.format(*total)), amount_style(*total)),
+			])))
+			.collect();
+		let title = format!("Accounts {} - {}", self.date.year().first_day(), self.date);
+		let block = self.pane_block(Pane::Accounts, title);
+		let list = List::new(items).block(block).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, area, &mut self.selection[Pane::Accounts.index()]);
+	}
+
+	fn draw_transactions(&mut self, frame: &mut Frame, area: Rect, account: &str) {
+		let mut balance = Cents(0);
+		let items: Vec<_> = self.bookings_until_date()
+			.filter_map(|booking| {
+				let amount: Cents = booking.mutations.iter()
+					.filter(|(x, _)| x == account)
+					.map(|(_, amount)| *amount)
+					.sum();
+				if !booking.mutations.iter().any(|(x, _)| x == account) {
+					return None;
+				}
+				balance += amount;
+				Some(ListItem::new(Line::from(vec![
+					Span::styled(booking.date.to_string(), Style::new().cyan()),
+					Span::raw("  "),
+					Span::styled(format!("{:>14}", self.currency.format(amount)), amount_style(amount)),
+					Span::raw("  "),
+					Span::styled(format!("{:>14}", self.currency.format(balance)), amount_style(balance)),
+					Span::raw("  "),
+					Span::raw(booking.description.clone()),
+				])))
+			})
+			.collect();
+		let block = self.pane_block(Pane::Accounts, format!("Transactions {}", account));
+		let list = List::new(items).block(block).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, area, &mut self.drill_down_selection);
+	}
+
+	fn draw_invoices(&mut self, frame: &mut Frame, area: Rect) {
+		let invoices = self.open_invoices();
+		let total: Cents = invoices.iter().map(|x| x.total()).sum();
+		let items: Vec<_> = invoices.iter()
+			.map(|invoice| {
+				let overdue = invoice.due_date < self.date;
+				let style = if overdue { Style::new().red().bold() } else { Style::new() };
+				ListItem::new(Line::from(vec![
+					Span::styled(invoice.number.clone(), Style::new().magenta()),
+					Span::raw("  "),
+					Span::styled(invoice.due_date.to_string(), if overdue { style } else { Style::new().cyan() }),
+					Span::raw("  "),
+					Span::styled(format!("{:>14}", self.currency.format(invoice.total())), style),
+					Span::raw("  "),
+					Span::raw(invoice.customer.clone()),
+				]))
+			})
+			.collect();
+		let title = format!("Open invoices: {}", self.currency.format(total));
+		let block = self.pane_block(Pane::Invoices, title);
+		let list = List::new(items).block(block).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, area, &mut self.selection[Pane::Invoices.index()]);
+	}
+}
+
+/// Check if a value contains a lowercase filter, ignoring case.
+fn matches(value: &str, filter: &str) -> bool {
+	filter.is_empty() || value.to_lowercase().contains(filter)
+}
+
+fn amount_style(amount: Cents) -> Style {
+	if amount.is_negative() {
+		Style::new().red()
+	} else {
+		Style::new().green()
+	}
+}
+
+/// Get the given day in a month, or the last day of the month if it is shorter.
+fn same_day(month: YearMonth, day: u8) -> Date {
+	let day = day.min(month.last_day().day());
+	Date::new(month.year(), month.month(), day).unwrap()
+}
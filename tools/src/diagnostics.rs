@@ -0,0 +1,163 @@
+//! Helpful diagnostics for errors in configuration files.
+//!
+//! The errors from serde only name the unknown or missing field.
+//! This adds a suggestion for misspelled fields and an example of how to write the field.
+
+use toml::value::Table;
+
+/// An error in a configuration file, with hints on how to fix it.
+#[derive(Debug)]
+pub struct ConfigError {
+	/// The error from the TOML deserializer.
+	pub error: toml::de::Error,
+
+	/// Hints on how to fix the error, like a suggestion for a misspelled field and an example.
+	pub hints: Vec<String>,
+}
+
+impl std::error::Error for ConfigError {}
+impl std::fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.error)?;
+		for hint in &self.hints {
+			write!(f, "\n{}", hint)?;
+		}
+		Ok(())
+	}
+}
+
+/// Add hints to an error from deserializing a configuration file.
+///
+/// The `table` is the parsed configuration file that failed to deserialize.
+pub fn diagnose(error: toml::de::Error, table: &Table) -> ConfigError {
+	let hints = hints(&error.to_string(), table);
+	ConfigError { error, hints }
+}
+
+/// Get the hints for an error message.
+fn hints(message: &str, table: &Table) -> Vec<String> {
+	// The message ends with the path of the table with the error, like "for key `Invoice`".
+	let (message, path) = match message.split_once(" for key `") {
+		Some((message, key)) => (message, key.split('`').next().unwrap_or_default().split('.').collect()),
+		None => (message, Vec::new()),
+	};
+
+	if let Some(rest) = message.strip_prefix("unknown field `") {
+		let (field, expected) = match rest.split_once('`') {
+			Some(x) => x,
+			None => return Vec::new(),
+		};
+		// The expected fields are quoted with backticks: "expected one of `a`, `b`".
+		let expected: Vec<&str> = expected.split('`').skip(1).step_by(2).collect();
+		let suggestion = match suggest(field, &expected) {
+			Some(x) => x,
+			None => return Vec::new(),
+		};
+		let value = find_value(table, &path, field);
+		vec![
+			format!("help: did you mean `{}`?", suggestion),
+			example(table, &path, suggestion, value),
+		]
+	} else if let Some(rest) = message.strip_prefix("missing field `") {
+		let field = rest.split('`').next().unwrap_or_default();
+		let location = match section_header(table, &path) {
+			Some(header) => format!("the {} section", header),
+			None => "the file".into(),
+		};
+		vec![
+			format!("help: add `{}` to {}, for example:", field, location),
+			example(table, &path, field, None),
+		]
+	} else {
+		Vec::new()
+	}
+}
+
+/// Find the expected field that is most similar to a misspelled field.
+///
+/// Only fields that are close enough to be a likely typo are suggested.
+fn suggest<'a>(field: &str, expected: &[&'a str]) -> Option<&'a str> {
+	let field = field.to_lowercase();
+	let max_distance = (field.chars().count() / 3).clamp(1, 3);
+	expected.iter()
+		.map(|candidate| (edit_distance(&field, &candidate.to_lowercase()), *candidate))
+		.filter(|(distance, _)| *distance <= max_distance)
+		.min_by_key(|(distance, _)| *distance)
+		.map(|(_, candidate)| candidate)
+}
+
+/// Compute the Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut previous: Vec<usize> = (0..=b.len()).collect();
+	for (i, a) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, b) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(a != *b);
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
+/// Get the value of a field in the table at the given path.
+///
+/// For arrays of tables, the value is taken from the first table with the field.
+fn find_value<'a>(table: &'a Table, path: &[&str], field: &str) -> Option<&'a toml::Value> {
+	let (first, rest) = match path.split_first() {
+		Some(x) => x,
+		None => return table.get(field),
+	};
+	match table.get(*first)? {
+		toml::Value::Table(table) => find_value(table, rest, field),
+		toml::Value::Array(array) => array.iter()
+			.filter_map(|x| x.as_table())
+			.find_map(|table| find_value(table, rest, field)),
+		_ => None,
+	}
+}
+
+/// Get the header of the section at the given path, like `[Invoice]` or `[[Tag]]`.
+fn section_header(table: &Table, path: &[&str]) -> Option<String> {
+	let (last, parent) = path.split_last()?;
+	let value = match parent.split_last() {
+		None => table.get(*last),
+		Some((field, parent)) => find_value(table, parent, field)
+			.and_then(|x| x.as_table())
+			.and_then(|x| x.get(*last)),
+	};
+	if let Some(toml::Value::Array(_)) = value {
+		Some(format!("[[{}]]", path.join(".")))
+	} else {
+		Some(format!("[{}]", path.join(".")))
+	}
+}
+
+/// Make an example of a field in the section at the given path.
+///
+/// The value is shown if known, otherwise a placeholder is used.
+/// Fields with a table as value and fields starting with an uppercase letter are shown as section.
+fn example(table: &Table, path: &[&str], field: &str, value: Option<&toml::Value>) -> String {
+	let mut lines = Vec::new();
+	let mut field_path = path.to_vec();
+	field_path.push(field);
+	match value {
+		Some(toml::Value::Table(_)) => lines.push(format!("[{}]", field_path.join("."))),
+		Some(toml::Value::Array(array)) if array.iter().all(|x| x.is_table()) && !array.is_empty() => {
+			lines.push(format!("[[{}]]", field_path.join(".")));
+		},
+		None if field.starts_with(|c: char| c.is_ascii_uppercase()) => lines.push(format!("[{}]", field_path.join("."))),
+		value => {
+			lines.extend(section_header(table, path));
+			let value = value.map(|x| x.to_string()).unwrap_or_else(|| "...".into());
+			lines.push(format!("{} = {}", field, value));
+		},
+	}
+	let mut example = String::new();
+	for line in lines {
+		example.push_str("\n    ");
+		example.push_str(&line);
+	}
+	example
+}
@@ -0,0 +1,259 @@
+//! Delivery of invoices by email.
+
+use dynfmt::{Format, SimpleCurlyFormat};
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use zzp::gregorian::Date;
+
+use crate::{Customer, EmailConfig, SmtpEncryption, ZzpConfig};
+
+/// The default path of the delivery log, relative to the directory of `zzp.toml`.
+pub const DEFAULT_DELIVERY_LOG: &str = "email-log.txt";
+
+/// The default subject of payment reminders.
+pub const DEFAULT_REMINDER_SUBJECT: &str = "Reminder: invoice {invoice_number}";
+
+/// The default body of payment reminders.
+pub const DEFAULT_REMINDER_BODY: &str = "Dear {customer},
+
+According to our records, invoice {invoice_number} of {invoice_date} for {total} has not been paid yet.
+The invoice was due on {due_date}, {days_overdue} days ago.
+Could you please pay the invoice as soon as possible? The invoice is attached for your convenience.
+
+If you have already paid the invoice, please disregard this reminder.
+
+Kind regards,
+{company}
+";
+
+/// An invoice to send by email.
+pub struct InvoiceEmail<'a> {
+	/// The invoice number.
+	pub invoice_number: &'a str,
+
+	/// The formatted total amount due.
+	pub total: &'a str,
+
+	/// The files to attach to the email.
+	pub attachments: &'a [PathBuf],
+}
+
+/// A payment reminder for an overdue invoice to send by email.
+pub struct ReminderEmail<'a> {
+	/// The invoice number.
+	pub invoice_number: &'a str,
+
+	/// The formatted total amount due.
+	pub total: &'a str,
+
+	/// The invoice date.
+	pub invoice_date: Date,
+
+	/// The date the invoice should have been paid.
+	pub due_date: Date,
+
+	/// The number of days the invoice is past the due date.
+	pub days_overdue: usize,
+
+	/// The files to attach to the email, normally the invoice itself.
+	pub attachments: &'a [PathBuf],
+}
+
+/// Send an invoice to a customer by email and record it in the delivery log.
+///
+/// The `root_dir` is the directory containing `zzp.toml`.
+pub fn send_invoice(root_dir: impl AsRef<Path>, config: &ZzpConfig, customer: &Customer, invoice: &InvoiceEmail) -> Result<(), EmailError> {
+	let email_config = config.email.as_ref()
+		.ok_or(EmailError::NoEmailConfig)?;
+	let args: BTreeMap<_, _> = [
+		("invoice_number", invoice.invoice_number),
+		("company", config.company.name.as_str()),
+		("customer", customer.name.as_str()),
+		("total", invoice.total),
+	].into_iter().collect();
+	let subject = SimpleCurlyFormat.format(&email_config.subject, &args)
+		.map_err(|e| EmailError::Template { template: "email subject", error: e.to_string() })?;
+	let body = SimpleCurlyFormat.format(&email_config.body, &args)
+		.map_err(|e| EmailError::Template { template: "email body", error: e.to_string() })?;
+	send(root_dir.as_ref(), email_config, customer, &subject, body.into_owned(), invoice.invoice_number, invoice.attachments)
+}
+
+/// Send a payment reminder for an overdue invoice by email and record it in the delivery log.
+///
+/// The `root_dir` is the directory containing `zzp.toml`.
+pub fn send_reminder(root_dir: impl AsRef<Path>, config: &ZzpConfig, customer: &Customer, reminder: &ReminderEmail) -> Result<(), EmailError> {
+	let email_config = config.email.as_ref()
+		.ok_or(EmailError::NoEmailConfig)?;
+	let invoice_date = reminder.invoice_date.to_string();
+	let due_date = reminder.due_date.to_string();
+	let days_overdue = reminder.days_overdue.to_string();
+	let args: BTreeMap<_, _> = [
+		("invoice_number", reminder.invoice_number),
+		("company", config.company.name.as_str()),
+		("customer", customer.name.as_str()),
+		("total", reminder.total),
+		("invoice_date", &invoice_date),
+		("due_date", &due_date),
+		("days_overdue", &days_overdue),
+	].into_iter().collect();
+	let subject = email_config.reminder_subject.as_deref().unwrap_or(DEFAULT_REMINDER_SUBJECT);
+	let subject = SimpleCurlyFormat.format(subject, &args)
+		.map_err(|e| EmailError::Template { template: "reminder subject", error: e.to_string() })?;
+	let body = email_config.reminder_body.as_deref().unwrap_or(DEFAULT_REMINDER_BODY);
+	let body = SimpleCurlyFormat.format(body, &args)
+		.map_err(|e| EmailError::Template { template: "reminder body", error: e.to_string() })?;
+	send(root_dir.as_ref(), email_config, customer, &subject, body.into_owned(), reminder.invoice_number, reminder.attachments)
+}
+
+/// Send an email with attachments to a customer and record it in the delivery log.
+fn send(root_dir: &Path, email_config: &EmailConfig, customer: &Customer, subject: &str, body: String, invoice_number: &str, attachments: &[PathBuf]) -> Result<(), EmailError> {
+	let to = customer.email.as_deref()
+		.ok_or_else(|| EmailError::NoAddress(customer.name.clone()))?;
+
+	let mut builder = Message::builder()
+		.from(parse_mailbox(&email_config.from)?)
+		.to(parse_mailbox(to)?)
+		.subject(subject);
+	if let Some(bcc) = &email_config.bcc {
+		builder = builder.bcc(parse_mailbox(bcc)?);
+	}
+
+	let mut parts = MultiPart::mixed().singlepart(SinglePart::plain(body));
+	for path in attachments {
+		let data = std::fs::read(path)
+			.map_err(|e| EmailError::ReadAttachment(path.clone(), e))?;
+		let file_name = path.file_name()
+			.ok_or_else(|| EmailError::NoFileName(path.clone()))?
+			.to_string_lossy()
+			.into_owned();
+		parts = parts.singlepart(Attachment::new(file_name).body(data, content_type(path)));
+	}
+	let message = builder.multipart(parts)
+		.map_err(EmailError::Build)?;
+
+	connect(email_config)?
+		.send(&message)
+		.map_err(|error| EmailError::Send { to: to.to_string(), error })?;
+
+	let log_path = root_dir.join(email_config.delivery_log.as_deref().unwrap_or(DEFAULT_DELIVERY_LOG));
+	record_delivery(&log_path, invoice_number, to, attachments)
+}
+
+/// Connect to the configured SMTP server.
+fn connect(config: &EmailConfig) -> Result<SmtpTransport, EmailError> {
+	let encryption = config.encryption.unwrap_or(SmtpEncryption::Starttls);
+	let builder = match encryption {
+		SmtpEncryption::Starttls => SmtpTransport::starttls_relay(&config.server),
+		SmtpEncryption::Tls => SmtpTransport::relay(&config.server),
+	};
+	let mut builder = builder
+		.map_err(|error| EmailError::Connect { server: config.server.clone(), error })?;
+	if let Some(port) = config.port {
+		builder = builder.port(port);
+	}
+	if let Some(username) = &config.username {
+		let password = match &config.password_env {
+			Some(name) => std::env::var(name)
+				.map_err(|error| EmailError::Password { name: name.clone(), error })?,
+			None => String::new(),
+		};
+		builder = builder.credentials(Credentials::new(username.clone(), password));
+	}
+	Ok(builder.build())
+}
+
+/// Append a line for a sent invoice to the delivery log.
+fn record_delivery(path: &Path, invoice_number: &str, to: &str, attachments: &[PathBuf]) -> Result<(), EmailError> {
+	use std::io::Write;
+
+	let files: Vec<_> = attachments.iter()
+		.map(|path| path.display().to_string())
+		.collect();
+	let mut file = std::fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)
+		.map_err(|e| EmailError::OpenLog(path.to_path_buf(), e))?;
+	writeln!(file, "{}\t{}\t{}\t{}", Date::today(), invoice_number, to, files.join("\t"))
+		.map_err(|e| EmailError::WriteLog(path.to_path_buf(), e))
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, EmailError> {
+	address.parse()
+		.map_err(|error| EmailError::InvalidAddress { address: address.to_string(), error })
+}
+
+fn content_type(path: &Path) -> ContentType {
+	let extension = path.extension()
+		.and_then(|x| x.to_str())
+		.map(|x| x.to_ascii_lowercase());
+	match extension.as_deref() {
+		Some("pdf") => ContentType::parse("application/pdf").unwrap(),
+		Some("xml") => ContentType::parse("application/xml").unwrap(),
+		_ => ContentType::parse("application/octet-stream").unwrap(),
+	}
+}
+
+/// An error while sending an email.
+#[derive(Debug)]
+pub enum EmailError {
+	/// The configuration has no `[Email]` section.
+	NoEmailConfig,
+
+	/// The subject or body template could not be expanded.
+	Template { template: &'static str, error: String },
+
+	/// The customer has no email address.
+	NoAddress(String),
+
+	/// An email address could not be parsed.
+	InvalidAddress { address: String, error: lettre::address::AddressError },
+
+	/// An attachment could not be read.
+	ReadAttachment(PathBuf, std::io::Error),
+
+	/// The path of an attachment has no file name.
+	NoFileName(PathBuf),
+
+	/// The email could not be created.
+	Build(lettre::error::Error),
+
+	/// The connection to the SMTP server could not be configured.
+	Connect { server: String, error: lettre::transport::smtp::Error },
+
+	/// The SMTP password could not be read from the environment.
+	Password { name: String, error: std::env::VarError },
+
+	/// The email could not be sent.
+	Send { to: String, error: lettre::transport::smtp::Error },
+
+	/// The delivery log could not be opened.
+	OpenLog(PathBuf, std::io::Error),
+
+	/// The delivery could not be written to the delivery log.
+	WriteLog(PathBuf, std::io::Error),
+}
+
+impl std::error::Error for EmailError {}
+impl std::fmt::Display for EmailError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::NoEmailConfig => write!(f, "missing [Email] section in zzp.toml"),
+			Self::Template { template, error } => write!(f, "failed to expand {}: {}", template, error),
+			Self::NoAddress(customer) => write!(f, "no email address configured for customer {}", customer),
+			Self::InvalidAddress { address, error } => write!(f, "invalid email address {:?}: {}", address, error),
+			Self::ReadAttachment(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+			Self::NoFileName(path) => write!(f, "attachment has no file name: {}", path.display()),
+			Self::Build(e) => write!(f, "failed to create email: {}", e),
+			Self::Connect { server, error } => write!(f, "failed to configure SMTP connection to {}: {}", server, error),
+			Self::Password { name, error } => write!(f, "failed to read SMTP password from environment variable {}: {}", name, error),
+			Self::Send { to, error } => write!(f, "failed to send email to {}: {}", to, error),
+			Self::OpenLog(path, e) => write!(f, "failed to open {}: {}", path.display(), e),
+			Self::WriteLog(path, e) => write!(f, "failed to write to {}: {}", path.display(), e),
+		}
+	}
+}
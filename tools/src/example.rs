@@ -0,0 +1,168 @@
+//! Fully commented example configuration files.
+//!
+//! The documentation of each field is taken from the JSON schema of the configuration structs, see the `schema` module.
+//! Fields that are not set are written as comment, so the example shows every available option.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use toml::value::Table;
+
+/// Serialize a configuration as TOML with the documentation of each field as comment.
+pub fn to_commented_toml<T: Serialize + JsonSchema>(config: &T) -> Result<String, String> {
+	let value = toml::Value::try_from(config)
+		.map_err(|e| format!("failed to serialize configuration: {}", e))?;
+	let table = value.as_table()
+		.ok_or("the configuration is not a table")?;
+	let schema = crate::schema::schema_for::<T>();
+	let root = schema.as_object()
+		.ok_or("the schema of the configuration is not an object")?;
+	let empty = Map::new();
+	let definitions = match root.get("definitions") {
+		Some(Value::Object(definitions)) => definitions,
+		_ => &empty,
+	};
+
+	let mut output = String::new();
+	write_table(&mut output, &[], root, definitions, Some(table), false);
+	Ok(output.trim_start().to_string())
+}
+
+/// Write the fields of a table, followed by the tables of nested structs.
+///
+/// If `table` is `None` or `commented` is true, all fields are written as comment.
+fn write_table(output: &mut String, path: &[&str], schema: &Map<String, Value>, definitions: &Map<String, Value>, table: Option<&Table>, commented: bool) {
+	let prefix = if commented { "# " } else { "" };
+	let mut nested = Vec::new();
+	let mut first = true;
+
+	let empty = Map::new();
+	let properties = match schema.get("properties") {
+		Some(Value::Object(properties)) => properties,
+		_ => &empty,
+	};
+	for (key, field) in properties {
+		let doc = field.get("description").and_then(Value::as_str).unwrap_or("");
+		let value = table.and_then(|x| x.get(key));
+		if let Some((nested_schema, array)) = table_schema(field, definitions) {
+			match value {
+				None | Some(toml::Value::Table(_)) => {
+					nested.push((key, doc, nested_schema, array, value));
+					continue;
+				},
+				Some(toml::Value::Array(items)) if !items.is_empty() && items.iter().all(|x| x.is_table()) => {
+					nested.push((key, doc, nested_schema, array, value));
+					continue;
+				},
+				// Fields like the localization can also be written as plain value.
+				Some(_) => (),
+			}
+		}
+
+		// Separate documented fields with an empty line, but put the first field directly below the header.
+		if !doc.is_empty() {
+			if !first {
+				output.push('\n');
+			}
+			write_doc(output, doc);
+		}
+		first = false;
+		match value {
+			Some(value) => output.push_str(&format!("{}{} = {}\n", prefix, key, inline(value))),
+			None => output.push_str(&format!("# {} = ...\n", key)),
+		}
+	}
+
+	for (key, doc, nested_schema, array, value) in nested {
+		let mut field_path = path.to_vec();
+		field_path.push(key);
+		let header = field_path.join(".");
+		let tables: Vec<Option<&Table>> = match value {
+			Some(toml::Value::Table(table)) => vec![Some(table)],
+			Some(toml::Value::Array(items)) if !items.is_empty() => items.iter().map(|x| x.as_table()).collect(),
+			_ => vec![None],
+		};
+		for (i, table) in tables.into_iter().enumerate() {
+			let commented = commented || table.is_none();
+			let prefix = if commented { "# " } else { "" };
+			output.push('\n');
+			if i == 0 {
+				write_doc(output, doc);
+			}
+			if array {
+				output.push_str(&format!("{}[[{}]]\n", prefix, header));
+			} else {
+				output.push_str(&format!("{}[{}]\n", prefix, header));
+			}
+			write_table(output, &field_path, nested_schema, definitions, table, commented);
+		}
+	}
+}
+
+/// Get the schema of the struct of a field that is written as table, and if it is a list of tables.
+fn table_schema<'a>(field: &'a Value, definitions: &'a Map<String, Value>) -> Option<(&'a Map<String, Value>, bool)> {
+	let field = resolve(field, definitions);
+	if let Some(items) = field.get("items") {
+		return struct_schema(resolve(items, definitions)).map(|x| (x, true));
+	}
+	if let Some(schema) = struct_schema(field) {
+		return Some((schema, false));
+	}
+
+	// Fields like the localization can also be a plain value, so look for a reference to a struct in the alternatives.
+	field.get("anyOf")?
+		.as_array()?
+		.iter()
+		.filter(|x| x.get("$ref").is_some())
+		.find_map(|x| struct_schema(resolve(x, definitions)))
+		.map(|x| (x, false))
+}
+
+/// Get the schema of a struct as map, or `None` if the schema is not for a struct.
+fn struct_schema(schema: &Value) -> Option<&Map<String, Value>> {
+	schema.get("properties")?;
+	schema.as_object()
+}
+
+/// Follow references to definitions, including references wrapped in `allOf` to allow a description next to them.
+fn resolve<'a>(mut schema: &'a Value, definitions: &'a Map<String, Value>) -> &'a Value {
+	loop {
+		if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+			match reference.strip_prefix("#/definitions/").and_then(|x| definitions.get(x)) {
+				Some(definition) => schema = definition,
+				None => return schema,
+			}
+		} else if let Some([inner]) = schema.get("allOf").and_then(Value::as_array).map(Vec::as_slice) {
+			schema = inner;
+		} else {
+			return schema;
+		}
+	}
+}
+
+/// Write a doc comment as TOML comment.
+fn write_doc(output: &mut String, doc: &str) {
+	for line in doc.lines() {
+		if line.is_empty() {
+			output.push_str("#\n");
+		} else {
+			output.push_str(&format!("# {}\n", line));
+		}
+	}
+}
+
+/// Format a value on a single line, with inline tables.
+fn inline(value: &toml::Value) -> String {
+	match value {
+		toml::Value::Array(items) => {
+			let items: Vec<_> = items.iter().map(inline).collect();
+			format!("[{}]", items.join(", "))
+		},
+		toml::Value::Table(table) if table.is_empty() => "{}".into(),
+		toml::Value::Table(table) => {
+			let fields: Vec<_> = table.iter().map(|(key, value)| format!("{} = {}", key, inline(value))).collect();
+			format!("{{ {} }}", fields.join(", "))
+		},
+		value => value.to_string(),
+	}
+}
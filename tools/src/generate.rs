@@ -0,0 +1,806 @@
+//! Generation of invoices with their e-invoice, grootboek transaction and register entry.
+
+use dynfmt::{Format, SimpleCurlyFormat};
+use ordered_float::NotNan;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use yansi::Paint;
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+
+use crate::email::EmailError;
+use crate::grootboek::UpdateError;
+use crate::hooks::{Hook, HookError};
+use crate::hours::HourRates;
+use crate::invoice::{DiscountValue, InvoiceEntry, InvoiceExtras, InvoiceFile, InvoiceRenderer, InvoiceTotals};
+use crate::numbering::{InvoiceNumbering, NumberFormatError};
+use crate::peppol::PeppolError;
+use crate::quote::QuoteFile;
+use crate::register::{InvoiceRegister, InvoiceStatus, RegisterError, RegisteredInvoice};
+use crate::ubl::UblError;
+use crate::workspace::FindConfigError;
+use crate::{CustomerConfig, DeliveryMethod, HourlyRate, ReadFileError, WriteFileError, ZzpConfig};
+
+/// Options for generating an invoice.
+#[derive(Debug, Clone)]
+pub struct GenerateOptions<'a> {
+	/// The invoice number to use instead of the next number in the sequence.
+	pub number: Option<&'a str>,
+
+	/// The invoice date.
+	pub date: Date,
+
+	/// Write the invoice to this path instead of the default.
+	pub output: Option<PathBuf>,
+
+	/// Overwrite the output files if they exist.
+	pub overwrite: bool,
+
+	/// Create a draft invoice with a watermark, without adding it to the grootboek or using an invoice number.
+	pub draft: bool,
+
+	/// Only show the invoice lines, totals, file name and grootboek transaction, without writing any files.
+	pub dry_run: bool,
+
+	/// Send the invoice to the customer by email after creating it.
+	///
+	/// Invoices for customers with an email delivery method are always sent, unless it is a draft.
+	pub email: bool,
+
+	/// Do not add the invoice to the grootboek.
+	pub skip_grootboek: bool,
+
+	/// Add the invoice to the grootboek even if it contains transactions with a later date.
+	pub force_grootboek: bool,
+
+	/// The purchase order number or other reference of the customer.
+	pub reference: Option<&'a str>,
+
+	/// The key of the recurring invoice that this invoice is generated for.
+	pub recurring: Option<&'a str>,
+
+	/// The period with invoiced hours, as half-open range of dates.
+	///
+	/// If this is `None`, the period from the first to the last date of the invoice entries is used.
+	pub period: Option<Range<Date>>,
+
+	/// The renderer for the PDF invoice, to share the fonts and styles between multiple invoices.
+	///
+	/// If this is `None`, a new renderer is created for the invoice.
+	pub renderer: Option<&'a InvoiceRenderer>,
+}
+
+/// A generated invoice.
+#[derive(Debug, Clone)]
+pub struct GeneratedInvoice {
+	/// The invoice number.
+	pub number: String,
+
+	/// The path of the PDF invoice.
+	pub path: PathBuf,
+
+	/// The total amount of the invoice including VAT.
+	pub total: Cents,
+
+	/// The invoice was sent to the customer by email.
+	pub emailed: bool,
+
+	/// The invoice was submitted to the Peppol access point.
+	pub submitted: bool,
+}
+
+impl<'a> GenerateOptions<'a> {
+	/// Create options to generate a regular invoice on the given date.
+	pub fn new(date: Date) -> Self {
+		Self {
+			number: None,
+			date,
+			output: None,
+			overwrite: false,
+			draft: false,
+			dry_run: false,
+			email: false,
+			skip_grootboek: false,
+			force_grootboek: false,
+			reference: None,
+			recurring: None,
+			period: None,
+			renderer: None,
+		}
+	}
+}
+
+/// Generate an invoice for a customer.
+///
+/// This writes the PDF invoice and the UBL e-invoice (if the customer wants one),
+/// appends the transaction to the grootboek, records the invoice in the invoice register
+/// and sends the invoice by email if requested or if that is the delivery method of the customer.
+/// Invoices for customers with the Peppol delivery method are submitted to the access point, if one is configured.
+/// Finally, the `post_invoice` hook is run.
+///
+/// The `root_dir` is the directory containing `zzp.toml`.
+/// The customer overrides must already be applied to the ZZP configuration.
+pub fn generate_invoice(
+	root_dir: &Path,
+	config: &ZzpConfig,
+	customer_config: &CustomerConfig,
+	mut entries: Vec<InvoiceEntry>,
+	options: &GenerateOptions,
+) -> Result<GeneratedInvoice, GenerateError> {
+	let date = options.date;
+	let delivery = customer_config.customer.delivery;
+	if delivery.is_some_and(|x| x.needs_ubl()) && customer_config.e_invoice.is_none() {
+		return Err(GenerateError::MissingEInvoice {
+			customer: customer_config.customer.name.clone(),
+			delivery: delivery.unwrap(),
+		});
+	}
+	// Every invoice gets a period, so the invoiced hours are not invoiced again.
+	let period = options.period.clone().or_else(|| entry_period(&entries));
+	let send_email = options.email || (!options.draft && delivery.is_some_and(|x| x.is_email()));
+	let submit_peppol = !options.draft && delivery == Some(DeliveryMethod::Peppol) && config.peppol.is_some();
+
+	let mut numbering = InvoiceNumbering::open(root_dir, config, &customer_config.customer)
+		.map_err(GenerateError::ReadNumbering)?;
+	let mut invoice_register = InvoiceRegister::open(root_dir, config)
+		.map_err(GenerateError::ReadRegister)?;
+	let (number, sequence_number) = match options.number {
+		Some(number) => {
+			// A used number may only be given again to generate the same invoice again.
+			let used = invoice_register.find(number).is_some() || numbering.is_used(date, number).map_err(GenerateError::Numbering)?;
+			if used && !options.overwrite && !options.draft {
+				return Err(GenerateError::DuplicateNumber(number.to_string()));
+			}
+			(number.to_string(), None)
+		},
+		None => {
+			let next = numbering.peek(date, &invoice_register).map_err(GenerateError::Numbering)?;
+			(next.number.clone(), Some(next))
+		},
+	};
+
+	let args: BTreeMap<_, _> = [
+		("year", date.year().to_string()),
+		("month", format!("{:02}", date.month().to_number())),
+		("day", format!("{:02}", date.day())),
+	].into_iter().collect();
+
+	let grootboek_path = crate::grootboek::expand_template(&config.grootboek.path, &args, "grootboek path")
+		.map_err(GenerateError::Grootboek)?;
+	let grootboek_path = root_dir.join(grootboek_path);
+	let grootboek_dir = grootboek_path.parent()
+		.ok_or_else(|| GenerateError::NoParentDirectory(grootboek_path.clone()))?;
+
+	let invoice_directory = expand(&config.invoice.directory, &args, "invoice directory")?;
+	let output = options.output.clone().unwrap_or_else(|| {
+		invoice_file_name(root_dir.join(&invoice_directory), &number, config, options.draft)
+	});
+
+	let invoice_tag_value = output.strip_prefix(grootboek_dir)
+		.map_err(|_| GenerateError::OutsideGrootboekDirectory {
+			invoice: output.clone(),
+			grootboek_dir: grootboek_dir.to_path_buf(),
+		})?
+		.display()
+		.to_string();
+
+	let quarter;
+	if date.month() >= zzp::gregorian::October {
+		quarter = 4;
+	} else if date.month() >= zzp::gregorian::July {
+		quarter = 3;
+	} else if date.month() >= zzp::gregorian::April {
+		quarter = 2;
+	} else {
+		quarter = 1;
+	}
+
+	let format_args: BTreeMap<_, _> = [
+		("year", date.year().to_string()),
+		("month", format!("{:02}", date.month().to_number())),
+		("day", format!("{:02}", date.day())),
+		("quarter", quarter.to_string()),
+		("debitor", customer_config.customer.grootboek_name.clone()),
+		("invoice_number", number.clone()),
+	].into_iter().collect();
+
+	crate::invoice::apply_small_business_scheme(config, &mut entries);
+	crate::invoice::apply_intra_eu_rate(config, &customer_config.customer, &mut entries);
+	let totals = InvoiceTotals::compute(&entries, &config.tax);
+	let total_vat: BTreeMap<_, _> = totals.vat.iter().filter(|_| !config.tax.kor).map(|(key, &value)| {
+		let mut format_args = format_args.clone();
+		format_args.insert("percentage", key.to_string());
+
+		let key = expand(&config.grootboek.vat_account, &format_args, "VAT account")?;
+		Ok((key, value))
+	}).collect::<Result<_, GenerateError>>()?;
+
+	let description = expand(&config.invoice.grootboek_description, &format_args, "grootboek description")?;
+	let debitor_account = expand(&config.grootboek.debitor_account, &format_args, "debitor account")?;
+	let revenue_account = expand(&config.grootboek.revenue_account, &format_args, "revenue account")?;
+
+	let mut tags = vec![
+		zzp::grootboek::Tag {
+			label: &config.invoice.grootboek_tag,
+			value: &invoice_tag_value,
+		},
+	];
+	if let Some(reference) = options.reference {
+		tags.push(zzp::grootboek::Tag {
+			label: config.invoice.reference_tag.as_deref().unwrap_or("reference"),
+			value: reference,
+		});
+	}
+	// Tag intra-EU revenue with the VAT number of the customer for the ICP declaration.
+	if let (true, Some(vat_number)) = (crate::invoice::is_intra_eu_supply(config, &customer_config.customer), &customer_config.customer.vat_number) {
+		tags.push(zzp::grootboek::Tag {
+			label: config.grootboek.icp_tag.as_deref().unwrap_or("icp"),
+			value: vat_number,
+		});
+	}
+	// Tag invoices to customers with their own currency, since the amounts are in that currency.
+	if let Some(currency) = customer_config.currency() {
+		tags.push(zzp::grootboek::Tag {
+			label: config.grootboek.currency_tag.as_deref().unwrap_or("currency"),
+			value: &currency.code,
+		});
+	}
+
+	let mutations = zzp::grootboek::invoice_mutations(
+		zzp::grootboek::Account::from_raw(&debitor_account),
+		zzp::grootboek::Account::from_raw(&revenue_account),
+		totals.net,
+		total_vat.iter().map(|(account, &amount)| (zzp::grootboek::Account::from_raw(account), amount)),
+	);
+	let grootboek_entry = zzp::grootboek::Transaction::new(date, &description, tags, mutations);
+
+	if options.dry_run {
+		print_preview(config, &number, &output, &entries, &totals);
+		crate::grootboek::print_full_colored(&grootboek_entry);
+		return Ok(GeneratedInvoice {
+			number,
+			path: output,
+			total: totals.total(),
+			emailed: false,
+			submitted: false,
+		});
+	}
+
+	if let Some(parent) = output.parent() {
+		std::fs::create_dir_all(parent)
+			.map_err(|e| GenerateError::CreateDirectory(parent.into(), e))?;
+	}
+
+	let file = create_file(&output, options.overwrite)?;
+	let renderer;
+	let renderer = match options.renderer {
+		Some(renderer) => renderer,
+		None => {
+			renderer = InvoiceRenderer::new(config);
+			&renderer
+		},
+	};
+	renderer.render_invoice(
+		std::io::BufWriter::new(file),
+		config,
+		&customer_config.customer,
+		&number,
+		date,
+		&entries,
+		&InvoiceExtras {
+			notes: &customer_config.invoice.notes,
+			draft: options.draft,
+			reference: options.reference,
+		},
+	).map_err(GenerateError::Render)?;
+
+	let mut attachments = vec![output.clone()];
+
+	// Write the UBL e-invoice next to the PDF invoice if the customer wants one.
+	// Drafts only get a PDF, since an e-invoice can not be marked as draft.
+	if let (Some(e_invoice), false) = (&customer_config.e_invoice, options.draft) {
+		let ubl_output = output.with_extension("xml");
+		let file = create_file(&ubl_output, options.overwrite)?;
+		crate::ubl::write_ubl_invoice(
+			std::io::BufWriter::new(file),
+			config,
+			&customer_config.customer,
+			e_invoice,
+			&number,
+			date,
+			&entries,
+		).map_err(GenerateError::Ubl)?;
+		attachments.push(ubl_output);
+	}
+
+	crate::grootboek::print_full_colored(&grootboek_entry);
+	if !options.skip_grootboek && !options.draft {
+		zzp::grootboek::append_transaction(&grootboek_path, &grootboek_entry, options.force_grootboek)
+			.map_err(|e| GenerateError::Grootboek(UpdateError::Append(e)))?;
+	}
+
+	// Record the invoice in the register to keep track of its payment status.
+	let mut register = None;
+	if !options.draft {
+		invoice_register.add(RegisteredInvoice {
+			number: number.clone(),
+			customer: customer_config.customer.name.clone(),
+			debitor_account: debitor_account.to_string(),
+			date,
+			due_date: crate::register::due_date(config, date),
+			currency: config.currency().code,
+			total_cents: totals.total().total_cents(),
+			file: output.strip_prefix(root_dir).unwrap_or(&output).to_path_buf(),
+			status: InvoiceStatus::Created,
+			sent_date: None,
+			paid_date: None,
+			reminder_date: None,
+			recurring: options.recurring.map(String::from),
+			period_start: period.as_ref().map(|x| x.start),
+			period_end: period.as_ref().map(|x| x.end),
+			dates: invoiced_dates(&entries, period.as_ref()),
+			peppol_status: None,
+			peppol_reference: None,
+		});
+		invoice_register.save().map_err(GenerateError::WriteRegister)?;
+
+		// All files of the invoice are written, so the number from the sequence is now used.
+		if let Some(sequence_number) = &sequence_number {
+			numbering.consume(sequence_number).map_err(GenerateError::WriteNumbering)?;
+		}
+		register = Some(invoice_register);
+	}
+
+	if send_email {
+		let total = config.currency().format(totals.total());
+		let email = crate::email::InvoiceEmail {
+			invoice_number: &number,
+			total: &total,
+			attachments: &attachments,
+		};
+		crate::email::send_invoice(root_dir, config, &customer_config.customer, &email)
+			.map_err(GenerateError::Email)?;
+		if let Some(register) = &mut register {
+			register.mark_sent(&number, Date::today()).map_err(GenerateError::Register)?;
+			register.save().map_err(GenerateError::WriteRegister)?;
+		}
+	}
+
+	if let (true, Some(register)) = (submit_peppol, &mut register) {
+		crate::peppol::deliver_invoice(root_dir, config, register, &number)
+			.map_err(|error| GenerateError::Peppol { number: number.clone(), error: Box::new(error) })?;
+	}
+
+	if !options.draft {
+		let ubl = attachments.get(1).map(|x| x.display().to_string()).unwrap_or_default();
+		let args = [
+			("number", number.clone()),
+			("customer", customer_config.customer.name.clone()),
+			("date", date.to_string()),
+			("total", config.currency().format(totals.total())),
+			("pdf", output.display().to_string()),
+			("ubl", ubl),
+		].into_iter().collect();
+		crate::hooks::run_hook(root_dir, config, Hook::PostInvoice, &args)
+			.map_err(|error| GenerateError::Hook { number: number.clone(), error })?;
+	}
+
+	Ok(GeneratedInvoice {
+		number,
+		path: output,
+		total: totals.total(),
+		emailed: send_email,
+		submitted: submit_peppol,
+	})
+}
+
+/// The configuration of an administration and one of its customers.
+#[derive(Debug)]
+pub struct InvoiceConfig {
+	/// The directory containing `zzp.toml`.
+	pub root_dir: PathBuf,
+
+	/// The directory containing `customer.toml`.
+	pub customer_dir: PathBuf,
+
+	/// The ZZP configuration, with the customer overrides applied.
+	pub zzp_config: ZzpConfig,
+
+	/// The customer configuration.
+	pub customer_config: CustomerConfig,
+}
+
+impl InvoiceConfig {
+	/// Find and read the configuration of the customer that a directory belongs to.
+	///
+	/// The administration is found like the `zzp` tools do from the working directory,
+	/// so the administration selected with [`crate::workspace::select_administration`] is used.
+	pub fn find(dir: impl AsRef<Path>) -> Result<Self, InvoiceError> {
+		let dir = dir.as_ref();
+		let zzp_config_path = crate::workspace::find_zzp_config(dir)
+			.map_err(InvoiceError::FindConfig)?;
+		let root_dir = zzp_config_path.parent().unwrap().to_path_buf();
+		let customer_config_path = CustomerConfig::find(&root_dir, dir)
+			.ok_or(InvoiceError::FindConfig(FindConfigError::NoCustomerConfig))?;
+		let customer_dir = customer_config_path.parent().unwrap().to_path_buf();
+
+		let mut zzp_config = ZzpConfig::read_file(&zzp_config_path)
+			.map_err(InvoiceError::ReadConfig)?;
+		let customer_config = CustomerConfig::read_file(&customer_config_path)
+			.map_err(InvoiceError::ReadConfig)?;
+		zzp_config.apply_customer_overrides(&customer_config);
+
+		Ok(Self {
+			root_dir,
+			customer_dir,
+			zzp_config,
+			customer_config,
+		})
+	}
+
+	/// Get the next invoice number in the sequence of the customer, without consuming it.
+	pub fn peek_number(&self, date: Date) -> Result<String, InvoiceError> {
+		let numbering = InvoiceNumbering::open(&self.root_dir, &self.zzp_config, &self.customer_config.customer)
+			.map_err(GenerateError::ReadNumbering)?;
+		let register = InvoiceRegister::open(&self.root_dir, &self.zzp_config)
+			.map_err(GenerateError::ReadRegister)?;
+		Ok(numbering.peek(date, &register).map_err(GenerateError::Numbering)?.number)
+	}
+}
+
+/// Options for reading the entries of an invoice and generating it.
+#[derive(Debug, Clone)]
+pub struct InvoiceOptions<'a> {
+	/// Where the invoice entries come from.
+	pub entries: EntrySource<'a>,
+
+	/// Give a discount on the invoice instead of the discount of the customer, if any.
+	pub discount: Option<DiscountValue>,
+
+	/// The description of the discount [default: Discount].
+	pub discount_description: Option<&'a str>,
+
+	/// The options for generating the invoice.
+	///
+	/// If no reference is given, the reference from the customer configuration is used.
+	pub generate: GenerateOptions<'a>,
+}
+
+/// The source of the entries of an invoice.
+#[derive(Debug, Clone)]
+pub enum EntrySource<'a> {
+	/// A TOML file with invoice entries.
+	File(&'a Path),
+
+	/// The entries of an accepted quote.
+	Quote(&'a Path),
+
+	/// Hour log entries, converted to invoice entries with the rates of the customer.
+	Hours(HoursSource<'a>),
+}
+
+/// Options for creating invoice entries from hour log entries.
+///
+/// Options that are `None` default to the configuration of the customer.
+#[derive(Debug, Clone, Default)]
+pub struct HoursSource<'a> {
+	/// The hour log to read instead of the hour logs of the customer.
+	pub file: Option<&'a Path>,
+
+	/// Only consider hour entries from this date or later.
+	pub start_date: Option<Date>,
+
+	/// Only consider hour entries before this date.
+	pub end_date: Option<Date>,
+
+	/// A TOML file with extra invoice entries to add.
+	pub extra_entries: Option<&'a Path>,
+
+	/// The unit to display for hour entries on the invoice.
+	pub unit: Option<&'a str>,
+
+	/// The price per hour.
+	pub price_per_hour: Option<NotNan<f64>>,
+
+	/// The VAT percentage.
+	pub vat: Option<NotNan<f64>>,
+
+	/// Add a single invoice entry per day with the given summary.
+	pub summarize_days: Option<&'a str>,
+}
+
+/// Read the entries of an invoice and generate it.
+///
+/// This applies the discount and the reference of the customer,
+/// and then generates the invoice with [`generate_invoice`].
+pub fn generate(options: &InvoiceOptions, config: &InvoiceConfig) -> Result<GeneratedInvoice, InvoiceError> {
+	let zzp_config = &config.zzp_config;
+	let customer_config = &config.customer_config;
+	let date = options.generate.date;
+
+	let (mut entries, vat_percentage) = match &options.entries {
+		EntrySource::File(path) => {
+			let invoice: InvoiceFile = crate::read_toml(path).map_err(InvoiceError::ReadEntries)?;
+			(invoice.entries, zzp_config.tax.vat)
+		},
+		EntrySource::Quote(path) => {
+			let quote: QuoteFile = crate::read_toml(path).map_err(InvoiceError::ReadEntries)?;
+			(quote.entries, zzp_config.tax.vat)
+		},
+		EntrySource::Hours(hours) => {
+			let defaults = HourRates::from_config(zzp_config, customer_config);
+			let rates = HourRates {
+				unit: hours.unit.unwrap_or(defaults.unit),
+				unit_price: hours.price_per_hour.map(HourlyRate::from).unwrap_or(defaults.unit_price),
+				vat_percentage: hours.vat.unwrap_or(defaults.vat_percentage),
+				summarize_days: hours.summarize_days.or(defaults.summarize_days),
+				tags: defaults.tags,
+				surcharges: defaults.surcharges,
+			};
+
+			let hour_entries = match hours.file {
+				Some(file) => crate::hours::read_hours_file(file, hours.start_date, hours.end_date),
+				None => crate::hours::read_customer_hours(customer_config, &config.customer_dir, hours.start_date, hours.end_date),
+			};
+			let hour_entries = hour_entries.map_err(InvoiceError::ReadHours)?;
+
+			let mut extra_entries = Vec::new();
+			if let Some(path) = hours.extra_entries {
+				let invoice: InvoiceFile = crate::read_toml(path).map_err(InvoiceError::ReadEntries)?;
+				extra_entries = invoice.entries;
+			}
+			let entries = crate::hours::make_invoice_entries(customer_config, hour_entries, extra_entries, &rates, date)
+				.map_err(InvoiceError::Entries)?;
+			(entries, rates.vat_percentage)
+		},
+	};
+	if let EntrySource::File(_) | EntrySource::Quote(_) = options.entries {
+		entries.sort_by_key(|x| x.date);
+	}
+
+	// Determine the period before adding the discount, since the discount entries have the invoice date.
+	let period = options.generate.period.clone().or_else(|| entry_period(&entries));
+
+	let reference = options.generate.reference.or(customer_config.invoice.reference.as_deref());
+
+	// Add the invoice wide discount at the end of the invoice.
+	let discount = match (options.discount, &customer_config.invoice.discount) {
+		(Some(value), _) => Some((value, options.discount_description.unwrap_or("Discount"))),
+		(None, Some(discount)) => Some((discount.value().map_err(InvoiceError::Entries)?, discount.description.as_str())),
+		(None, None) => None,
+	};
+	if let Some((value, description)) = discount {
+		let discount_entries = value.make_entries(description, date, &entries, vat_percentage);
+		entries.extend(discount_entries);
+	}
+
+	let generate_options = GenerateOptions {
+		reference,
+		period,
+		..options.generate.clone()
+	};
+	generate_invoice(&config.root_dir, zzp_config, customer_config, entries, &generate_options)
+		.map_err(InvoiceError::Generate)
+}
+
+/// Print the lines and totals of an invoice that would be generated.
+fn print_preview(config: &ZzpConfig, number: &str, output: &Path, entries: &[InvoiceEntry], totals: &InvoiceTotals) {
+	let currency = config.currency();
+	println!("{} {}", Paint::default("Invoice:").bold(), Paint::magenta(number));
+	println!("{} {}", Paint::default("File:").bold(), output.display());
+	println!();
+	for entry in entries {
+		println!("{date}  {quantity:>8} {unit:<6}  {unit_price:>12}  {total:>14}  {vat:>3}%  {description}",
+			date = Paint::cyan(entry.date),
+			quantity = entry.quantity,
+			unit = entry.unit,
+			unit_price = currency.format(Cents::from_f64(entry.unit_price.into_inner(), config.tax.rounding_mode)),
+			total = currency.format(entry.total_ex_vat_cents(config.tax.rounding_mode)),
+			vat = entry.vat_percentage,
+			description = entry.description,
+		);
+	}
+	println!();
+	println!("{} {}", Paint::default("Total excluding VAT:").bold(), currency.format(totals.net));
+	for (percentage, vat) in &totals.vat {
+		let taxable = totals.taxable.get(percentage).copied().unwrap_or(Cents(0));
+		println!("{} {} over {}", Paint::default(format!("VAT {}%:", percentage)).bold(), currency.format(*vat), currency.format(taxable));
+	}
+	println!("{} {}", Paint::default("Total:").bold(), currency.format(totals.total()));
+	println!();
+}
+
+/// Get the default file name for an invoice.
+///
+/// Slashes in the invoice number are replaced by dashes.
+pub fn invoice_file_name(invoice_dir: impl AsRef<Path>, number: &str, config: &ZzpConfig, draft: bool) -> PathBuf {
+	let mut invoice = config.invoice_localization.invoice.clone();
+	unsafe {
+		invoice.as_bytes_mut()[0].make_ascii_uppercase();
+	}
+	let suffix = if draft {
+		format!(" ({})", config.invoice_localization.draft())
+	} else {
+		String::new()
+	};
+	invoice_dir.as_ref().join(format!("{company} - {invoice} {number}{suffix}.pdf",
+		company = config.company.name,
+		number = number.replace(['/', '\\'], "-"),
+		invoice = invoice,
+		suffix = suffix,
+	))
+}
+
+/// Get the period from the first to the last date of the invoice entries, as half-open range.
+///
+/// Returns `None` if there are no entries.
+fn entry_period(entries: &[InvoiceEntry]) -> Option<Range<Date>> {
+	let start = entries.iter().map(|x| x.date).min()?;
+	let end = entries.iter().map(|x| x.date).max()?;
+	Some(start..end.next())
+}
+
+/// Get the sorted and deduplicated dates of the invoice entries within the invoice period, if any.
+fn invoiced_dates(entries: &[InvoiceEntry], period: Option<&Range<Date>>) -> Vec<Date> {
+	let dates: std::collections::BTreeSet<Date> = entries.iter()
+		.map(|x| x.date)
+		.filter(|x| period.map(|period| period.contains(x)).unwrap_or(true))
+		.collect();
+	dates.into_iter().collect()
+}
+
+fn create_file(path: &Path, overwrite: bool) -> Result<std::fs::File, GenerateError> {
+	std::fs::OpenOptions::new()
+		.create(true)
+		.truncate(true)
+		.create_new(!overwrite)
+		.write(true)
+		.open(path)
+		.map_err(|e| GenerateError::CreateFile(path.into(), e))
+}
+
+/// Expand the placeholders in a template from the configuration.
+fn expand(template: &str, args: &BTreeMap<&str, String>, name: &'static str) -> Result<String, GenerateError> {
+	SimpleCurlyFormat.format(template, args)
+		.map(|x| x.into_owned())
+		.map_err(|e| GenerateError::Template { name, error: e.to_string() })
+}
+
+#[derive(Debug)]
+pub enum InvoiceError {
+	/// The configuration files could not be found.
+	FindConfig(FindConfigError),
+
+	/// A configuration file could not be read.
+	ReadConfig(ReadFileError),
+
+	/// A file with invoice entries or a quote could not be read.
+	ReadEntries(ReadFileError),
+
+	/// The period of the invoice could not be determined.
+	Period(String),
+
+	/// The hour log entries could not be read.
+	ReadHours(String),
+
+	/// The invoice entries could not be created.
+	Entries(String),
+
+	/// The invoice could not be generated.
+	Generate(GenerateError),
+}
+
+impl std::error::Error for InvoiceError {}
+impl std::fmt::Display for InvoiceError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::FindConfig(error) => write!(f, "{}", error),
+			Self::ReadConfig(error) => write!(f, "{}", error),
+			Self::ReadEntries(error) => write!(f, "{}", error),
+			Self::Period(error) => write!(f, "{}", error),
+			Self::ReadHours(error) => write!(f, "{}", error),
+			Self::Entries(error) => write!(f, "{}", error),
+			Self::Generate(error) => write!(f, "{}", error),
+		}
+	}
+}
+
+impl From<FindConfigError> for InvoiceError {
+	fn from(other: FindConfigError) -> Self {
+		Self::FindConfig(other)
+	}
+}
+
+impl From<GenerateError> for InvoiceError {
+	fn from(other: GenerateError) -> Self {
+		Self::Generate(other)
+	}
+}
+
+#[derive(Debug)]
+pub enum GenerateError {
+	/// The customer wants an e-invoice, but has no e-invoice configuration.
+	MissingEInvoice { customer: String, delivery: DeliveryMethod },
+
+	/// The invoice numbering file could not be read.
+	ReadNumbering(ReadFileError),
+
+	/// The invoice number could not be determined from the number format.
+	Numbering(NumberFormatError),
+
+	/// The used invoice number could not be recorded in the numbering file.
+	WriteNumbering(WriteFileError),
+
+	/// The invoice number given for the invoice is already used by another invoice.
+	DuplicateNumber(String),
+
+	/// A template from the configuration could not be expanded.
+	Template { name: &'static str, error: String },
+
+	/// The grootboek path has no parent directory.
+	NoParentDirectory(PathBuf),
+
+	/// The invoice would not be below the grootboek directory, so it can not be referenced from the grootboek.
+	OutsideGrootboekDirectory { invoice: PathBuf, grootboek_dir: PathBuf },
+
+	/// The directory for the invoice could not be created.
+	CreateDirectory(PathBuf, std::io::Error),
+
+	/// An output file could not be created.
+	CreateFile(PathBuf, std::io::Error),
+
+	/// The PDF invoice could not be rendered.
+	Render(String),
+
+	/// The UBL e-invoice could not be written.
+	Ubl(UblError),
+
+	/// The invoice could not be added to the grootboek.
+	Grootboek(UpdateError),
+
+	/// The invoice register could not be read.
+	ReadRegister(ReadFileError),
+
+	/// The invoice could not be updated in the invoice register.
+	Register(RegisterError),
+
+	/// The invoice register could not be saved.
+	WriteRegister(WriteFileError),
+
+	/// The invoice could not be sent by email.
+	Email(EmailError),
+
+	/// The invoice was created, but could not be submitted to the Peppol access point.
+	Peppol { number: String, error: Box<PeppolError> },
+
+	/// The invoice was created, but the `post_invoice` hook failed.
+	Hook { number: String, error: HookError },
+}
+
+impl std::error::Error for GenerateError {}
+impl std::fmt::Display for GenerateError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::MissingEInvoice { customer, delivery } => write!(f, "customer {} has delivery method {}, but no [EInvoice] section in customer.toml", customer, delivery),
+			Self::ReadNumbering(error) => write!(f, "{}", error),
+			Self::Numbering(error) => write!(f, "{}", error),
+			Self::WriteNumbering(error) => write!(f, "{}", error),
+			Self::DuplicateNumber(number) => write!(f, "invoice number {} is already used, use --overwrite to generate the invoice again", number),
+			Self::Template { name, error } => write!(f, "failed to expand {}: {}", name, error),
+			Self::NoParentDirectory(path) => write!(f, "failed to determine parent directory of {}", path.display()),
+			Self::OutsideGrootboekDirectory { invoice, grootboek_dir } => write!(f, "invoice path ({}) is not below the grootboek directory ({})", invoice.display(), grootboek_dir.display()),
+			Self::CreateDirectory(path, error) => write!(f, "failed to create directory {}: {}", path.display(), error),
+			Self::CreateFile(path, error) => write!(f, "failed to create {}: {}", path.display(), error),
+			Self::Render(error) => write!(f, "{}", error),
+			Self::Ubl(error) => write!(f, "{}", error),
+			Self::Grootboek(error) => write!(f, "{}", error),
+			Self::ReadRegister(error) => write!(f, "{}", error),
+			Self::Register(error) => write!(f, "{}", error),
+			Self::WriteRegister(error) => write!(f, "{}", error),
+			Self::Email(error) => write!(f, "{}", error),
+			Self::Peppol { number, error } => write!(f, "{}, submit it again with `zzp invoice peppol {}`", error, number),
+			Self::Hook { number, error } => write!(f, "invoice {} was created, but {}", number, error),
+		}
+	}
+}
@@ -1,5 +1,16 @@
+use dynfmt::{Format, SimpleCurlyFormat};
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 use yansi::Paint;
-use zzp::grootboek::{Cents, Transaction};
+use zzp::gregorian::Date;
+use zzp::grootboek::{Account, AppendError, Cents, MappedFile, NoteLine, Tag, Transaction};
+use zzp::index::{FileKind, IndexError};
+
+use crate::register::RegisteredInvoice;
+use crate::workspace::FindConfigError;
+use crate::{ReadFileError, ZzpConfig};
 
 #[allow(clippy::comparison_chain)]
 pub fn color_cents(cents: Cents) -> yansi::Paint<Cents> {
@@ -13,22 +24,30 @@ pub fn color_cents(cents: Cents) -> yansi::Paint<Cents> {
 }
 
 pub fn print_full_colored(transaction: &Transaction) {
-	eprintln!("{date}: {desc}",
+	write_full_colored(&mut std::io::stderr().lock(), transaction).unwrap();
+}
+
+pub fn write_full_colored(out: &mut impl std::io::Write, transaction: &Transaction) -> std::io::Result<()> {
+	writeln!(out, "{date}: {desc}",
 		date = Paint::cyan(transaction.date),
 		desc = Paint::magenta(transaction.description),
-	);
+	)?;
+	for note in &transaction.notes {
+		writeln!(out, "\t; {note}", note = Paint::fixed(241, note))?;
+	}
 	for tag in &transaction.tags {
-		eprintln!("{label}: {value}",
+		writeln!(out, "{label}: {value}",
 			label = Paint::cyan(tag.label),
 			value = Paint::cyan(tag.value),
-		);
+		)?;
 	}
 	for mutation in &transaction.mutations {
-		eprintln!("{amount} {account}",
+		writeln!(out, "{amount} {account}",
 			amount  = color_cents(mutation.amount),
 			account = mutation.account,
-		);
+		)?;
 	}
+	Ok(())
 }
 
 pub fn write_full(out: &mut impl std::io::Write, transaction: &Transaction) -> std::io::Result<()> {
@@ -36,6 +55,9 @@ pub fn write_full(out: &mut impl std::io::Write, transaction: &Transaction) -> s
 		date = transaction.date,
 		desc = transaction.description,
 	)?;
+	for note in &transaction.notes {
+		writeln!(out, "{}", NoteLine(note))?;
+	}
 	for tag in &transaction.tags {
 		writeln!(out, "{label}: {value}",
 			label = tag.label,
@@ -50,3 +72,641 @@ pub fn write_full(out: &mut impl std::io::Write, transaction: &Transaction) -> s
 	}
 	Ok(())
 }
+
+/// The result of checking the documents attached to transactions.
+#[derive(Debug, Default)]
+pub struct AttachmentCheck<'a> {
+	/// Attachments referring to documents that do not exist.
+	pub dangling: Vec<DanglingAttachment<'a>>,
+
+	/// Documents that are not referenced by any transaction.
+	///
+	/// Only the attachments directory is searched, if one was given.
+	pub orphaned: Vec<PathBuf>,
+}
+
+/// A tag referring to a document that does not exist.
+#[derive(Debug, Clone)]
+pub struct DanglingAttachment<'a> {
+	/// The date of the transaction with the tag.
+	pub date: Date,
+
+	/// The description of the transaction with the tag.
+	pub description: &'a str,
+
+	/// The tag referring to the missing document.
+	pub tag: Tag<'a>,
+}
+
+impl AttachmentCheck<'_> {
+	/// Check if no dangling attachments or orphaned documents were found.
+	pub fn is_ok(&self) -> bool {
+		self.dangling.is_empty() && self.orphaned.is_empty()
+	}
+}
+
+/// Check that the documents referenced by tags with one of the given labels exist.
+///
+/// Tag values are interpreted as paths relative to the grootboek directory.
+/// Missing documents are only reported for the transactions for which `selected` returns true.
+///
+/// If `attachments_dir` is given, files below it that are not referenced by any transaction are reported as orphaned.
+/// All transactions of the grootboek must be given for this, not only the selected ones,
+/// or documents referenced by the other transactions are reported as orphaned too.
+/// Hidden files and directories are skipped, and symbolic links are not followed.
+///
+/// All paths are canonicalized before they are compared, so `./a.pdf` and `a.pdf` refer to the same document.
+/// The orphaned documents are reported with their canonical path.
+pub fn check_attachments<'a>(
+	transactions: &[Transaction<'a>],
+	selected: impl Fn(&Transaction<'a>) -> bool,
+	labels: &[&str],
+	grootboek_dir: &Path,
+	attachments_dir: Option<&Path>,
+) -> std::io::Result<AttachmentCheck<'a>> {
+	let mut result = AttachmentCheck::default();
+	let mut referenced = BTreeSet::new();
+
+	for transaction in transactions {
+		for tag in &transaction.tags {
+			if !labels.contains(&tag.label) {
+				continue;
+			}
+			match grootboek_dir.join(tag.value).canonicalize() {
+				Ok(path) if path.is_file() => {
+					referenced.insert(path);
+				},
+				_ if selected(transaction) => result.dangling.push(DanglingAttachment {
+					date: transaction.date,
+					description: transaction.description,
+					tag: *tag,
+				}),
+				_ => (),
+			}
+		}
+	}
+
+	if let Some(attachments_dir) = attachments_dir {
+		let mut directories = vec![attachments_dir.canonicalize()?];
+		while let Some(directory) = directories.pop() {
+			for entry in std::fs::read_dir(&directory)? {
+				let entry = entry?;
+				if entry.file_name().to_string_lossy().starts_with('.') {
+					continue;
+				}
+				// The file type of the entry itself, so symbolic links (and loops of them) are not followed.
+				let file_type = entry.file_type()?;
+				if file_type.is_dir() {
+					directories.push(entry.path());
+				} else if file_type.is_file() {
+					let path = entry.path().canonicalize()?;
+					if !referenced.contains(&path) {
+						result.orphaned.push(path);
+					}
+				}
+			}
+		}
+	}
+	result.orphaned.sort();
+
+	Ok(result)
+}
+
+#[cfg(test)]
+#[test]
+fn test_check_attachments() {
+	use assert2::assert;
+
+	let dir = std::env::temp_dir().join(format!("zzp-test-attachments-{}", std::process::id()));
+	let attachments = dir.join("documents");
+	std::fs::create_dir_all(attachments.join("2023")).unwrap();
+	std::fs::create_dir_all(attachments.join("2024/.hidden")).unwrap();
+	std::fs::write(attachments.join("2023/old.pdf"), "").unwrap();
+	std::fs::write(attachments.join("2024/new.pdf"), "").unwrap();
+	std::fs::write(attachments.join("2024/orphan.pdf"), "").unwrap();
+	std::fs::write(attachments.join("2024/.hidden/secret.pdf"), "").unwrap();
+	#[cfg(unix)]
+	std::os::unix::fs::symlink("..", attachments.join("2024/loop")).unwrap();
+
+	let data = concat!(
+		"2023-06-01: Old invoice\n",
+		"invoice: documents/2023/old.pdf\n",
+		"+1.00 debitors/acme\n",
+		"-1.00 revenue\n",
+		"\n",
+		"2024-06-01: New invoice\n",
+		"invoice: documents/2024/new.pdf\n",
+		"+1.00 debitors/acme\n",
+		"-1.00 revenue\n",
+		"\n",
+		"2024-07-01: Lost invoice\n",
+		"invoice: documents/2024/lost.pdf\n",
+		"+1.00 debitors/acme\n",
+		"-1.00 revenue\n",
+	);
+	let transactions = Transaction::parse_from_str(data).unwrap();
+	let in_2024 = |x: &Transaction| x.date.year().to_number() == 2024;
+
+	// Documents of the transactions that are not selected are not orphaned.
+	let check = check_attachments(&transactions, in_2024, &["invoice"], &dir, Some(&attachments)).unwrap();
+	assert!(check.dangling.len() == 1);
+	assert!(check.dangling[0].tag.value == "documents/2024/lost.pdf");
+	assert!(check.orphaned == vec![attachments.join("2024/orphan.pdf").canonicalize().unwrap()]);
+	assert!(!check.is_ok());
+
+	// Missing documents are only reported for the selected transactions.
+	let check = check_attachments(&transactions, |x| x.date.year().to_number() == 2023, &["invoice"], &dir, None).unwrap();
+	assert!(check.is_ok());
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Format an amount as a plain decimal number for CSV files, like `-12.34`.
+pub fn format_decimal(amount: Cents) -> String {
+	let cents = amount.total_cents();
+	let sign = if cents < 0 { "-" } else { "" };
+	let cents = cents.unsigned_abs();
+	format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
+
+/// Quote a field for a CSV file if it contains a comma, a quote or a line break.
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+	if field.contains(&[',', '"', '\n', '\r'][..]) {
+		Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+	} else {
+		Cow::Borrowed(field)
+	}
+}
+
+/// Quote a string for a JSON document.
+pub fn json_string(value: &str) -> String {
+	let mut output = String::with_capacity(value.len() + 2);
+	output.push('"');
+	for c in value.chars() {
+		match c {
+			'"' => output.push_str("\\\""),
+			'\\' => output.push_str("\\\\"),
+			'\n' => output.push_str("\\n"),
+			'\r' => output.push_str("\\r"),
+			'\t' => output.push_str("\\t"),
+			c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+			c => output.push(c),
+		}
+	}
+	output.push('"');
+	output
+}
+
+/// Write the mutations of transactions as CSV, with one row per mutation.
+///
+/// The tags of a transaction are joined into a single column, like `invoice: 2024-001; icp: NL123`.
+pub fn write_mutations_csv<'a>(out: &mut impl std::io::Write, transactions: impl IntoIterator<Item = &'a Transaction<'a>>) -> std::io::Result<()> {
+	writeln!(out, "date,description,account,amount,tags")?;
+	for transaction in transactions {
+		let tags = transaction.tags.iter()
+			.map(|tag| format!("{}: {}", tag.label, tag.value))
+			.collect::<Vec<_>>()
+			.join("; ");
+		for mutation in &transaction.mutations {
+			writeln!(out, "{},{},{},{},{}",
+				transaction.date,
+				csv_field(transaction.description),
+				csv_field(mutation.account.as_str()),
+				format_decimal(mutation.amount),
+				csv_field(&tags),
+			)?;
+		}
+	}
+	Ok(())
+}
+
+/// Write the mutations of transactions as a JSON array, with one object per mutation.
+pub fn write_mutations_json<'a>(out: &mut impl std::io::Write, transactions: impl IntoIterator<Item = &'a Transaction<'a>>) -> std::io::Result<()> {
+	write!(out, "[")?;
+	let mut first = true;
+	for transaction in transactions {
+		let tags = transaction.tags.iter()
+			.map(|tag| format!("{{\"label\": {}, \"value\": {}}}", json_string(tag.label), json_string(tag.value)))
+			.collect::<Vec<_>>()
+			.join(", ");
+		for mutation in &transaction.mutations {
+			if !first {
+				write!(out, ",")?;
+			}
+			first = false;
+			write!(out, "\n\t{{\"date\": \"{}\", \"description\": {}, \"account\": {}, \"amount\": {}, \"tags\": [{}]}}",
+				transaction.date,
+				json_string(transaction.description),
+				json_string(mutation.account.as_str()),
+				format_decimal(mutation.amount),
+				tags,
+			)?;
+		}
+	}
+	writeln!(out, "\n]")?;
+	Ok(())
+}
+
+/// The contents of a grootboek file, or the part of it with the transactions of a period.
+pub enum Contents {
+	/// The whole file, or the part of it that was selected with the index.
+	Read(String),
+
+	/// The whole file, mapped into memory.
+	Mapped(MappedFile),
+}
+
+impl std::ops::Deref for Contents {
+	type Target = str;
+
+	fn deref(&self) -> &str {
+		match self {
+			Self::Read(x) => x,
+			Self::Mapped(x) => x,
+		}
+	}
+}
+
+/// Read the transactions from `start_date` until but not including `end_date` from a grootboek file.
+///
+/// If the file has an index, only the part of the file with the transactions of the period is read.
+/// Otherwise, the whole file is read and the transactions must still be filtered by date.
+pub fn read_period(path: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Contents, ReadPeriodError> {
+	if let Some(indexed) = read_indexed_period(path, start_date, end_date)? {
+		return Ok(indexed);
+	}
+	std::fs::read_to_string(path)
+		.map(Contents::Read)
+		.map_err(|e| ReadPeriodError::Read(path.to_path_buf(), e))
+}
+
+/// Read only the transactions from `start_date` until but not including `end_date` from a grootboek file with an index.
+///
+/// Returns `None` if no dates are given or the file has no index.
+/// The caller must then read the whole file itself, for example by mapping it into memory with [`MappedFile::open`].
+pub fn read_indexed_period(path: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Option<Contents>, ReadPeriodError> {
+	if start_date.is_none() && end_date.is_none() {
+		return Ok(None);
+	}
+	let indexed = zzp::index::read_period(path, FileKind::Grootboek, start_date, end_date)
+		.map_err(ReadPeriodError::Index)?;
+	Ok(indexed.map(|indexed| Contents::Read(indexed.data)))
+}
+
+/// Read the transactions of a period from a grootboek file without blocking the async executor.
+///
+/// The file is read on the blocking thread pool of tokio, see [`read_period`].
+#[cfg(feature = "tokio")]
+pub async fn read_period_async(path: PathBuf, start_date: Option<Date>, end_date: Option<Date>) -> Result<Contents, ReadPeriodError> {
+	tokio::task::spawn_blocking(move || read_period(&path, start_date, end_date)).await
+		.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Find the zzp.toml for a grootboek file.
+pub fn find_config(grootboek_file: &Path) -> Result<PathBuf, FindConfigError> {
+	let file = grootboek_file.canonicalize()
+		.map_err(|e| FindConfigError::ResolvePath(grootboek_file.to_path_buf(), e))?;
+	file.parent()
+		.and_then(|grootboek_dir| ZzpConfig::find("/", grootboek_dir))
+		.ok_or(FindConfigError::NoZzpConfig)
+}
+
+/// Read the zzp.toml that applies to a grootboek file.
+pub fn read_config(grootboek_file: &Path) -> Result<ZzpConfig, ConfigError> {
+	let config_path = find_config(grootboek_file)
+		.map_err(ConfigError::Find)?;
+	ZzpConfig::read_file(&config_path)
+		.map_err(ConfigError::Read)
+}
+
+/// A filter to select transactions.
+///
+/// Every condition that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+	/// Only match transactions on or after this date.
+	pub start_date: Option<Date>,
+
+	/// Only match transactions before this date.
+	pub end_date: Option<Date>,
+
+	/// Only match transactions that mutate this account or a sub-account.
+	pub account: Option<String>,
+
+	/// Only match transactions with a description that matches this regular expression.
+	pub description: Option<Regex>,
+
+	/// Only match transactions with all of these tags.
+	pub tags: Vec<TagFilter>,
+
+	/// Only match transactions with a mutation of at least this absolute amount (on the account, if set).
+	pub min_amount: Option<Cents>,
+
+	/// Only match transactions with a mutation of at most this absolute amount (on the account, if set).
+	pub max_amount: Option<Cents>,
+}
+
+/// A filter on the tags of a transaction.
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+	/// The label of the tag.
+	pub label: String,
+
+	/// The value of the tag, or `None` to match any value.
+	pub value: Option<String>,
+}
+
+impl TransactionFilter {
+	/// Check if a transaction matches the filter.
+	pub fn matches(&self, transaction: &Transaction) -> bool {
+		if let Some(start_date) = &self.start_date {
+			if transaction.date < *start_date {
+				return false;
+			}
+		}
+		if let Some(end_date) = &self.end_date {
+			if transaction.date >= *end_date {
+				return false;
+			}
+		}
+		if let Some(account) = &self.account {
+			if !transaction.mutates_account(account) {
+				return false;
+			}
+		}
+		if let Some(description) = &self.description {
+			if !description.is_match(transaction.description) {
+				return false;
+			}
+		}
+		if !self.tags.iter().all(|filter| filter.matches(transaction)) {
+			return false;
+		}
+		if self.min_amount.is_some() || self.max_amount.is_some() {
+			let found = transaction.mutations.iter()
+				.filter(|x| self.account.as_ref().map(|account| x.account.matches_prefix(account)).unwrap_or(true))
+				.map(|x| Cents(x.amount.total_cents().abs()))
+				.any(|x| self.min_amount.map(|min| x >= min).unwrap_or(true) && self.max_amount.map(|max| x <= max).unwrap_or(true));
+			if !found {
+				return false;
+			}
+		}
+		true
+	}
+}
+
+impl TagFilter {
+	/// Check if a transaction has a matching tag.
+	pub fn matches(&self, transaction: &Transaction) -> bool {
+		transaction.tags.iter()
+			.any(|x| x.label == self.label && self.value.as_ref().map(|value| x.value == value).unwrap_or(true))
+	}
+}
+
+impl std::str::FromStr for TagFilter {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let (label, value) = match input.split_once('=') {
+			Some((label, value)) => (label, Some(value.trim().to_string())),
+			None => (input, None),
+		};
+		let label = label.trim();
+		if label.is_empty() {
+			return Err(format!("invalid tag filter {:?}, expected LABEL or LABEL=VALUE", input));
+		}
+		Ok(Self { label: label.to_string(), value })
+	}
+}
+
+/// A tree of accounts with data for each account, like the total of all mutations.
+#[derive(Debug, Clone)]
+pub struct AccountTree<'a, T> {
+	/// The root of the tree, with an empty account name.
+	pub root: AccountNode<'a, T>,
+}
+
+/// An account in an [`AccountTree`].
+#[derive(Debug, Clone)]
+pub struct AccountNode<'a, T> {
+	/// The account.
+	pub account: Account<'a>,
+
+	/// The data for the account, including all sub-accounts.
+	pub data: T,
+
+	/// The sub-accounts, in the order they were first inserted.
+	pub children: Vec<AccountNode<'a, T>>,
+}
+
+impl<'a, T> AccountTree<'a, T> {
+	/// Create a tree with only a root node.
+	pub fn new(root_data: T) -> Self {
+		Self {
+			root: AccountNode::new(Account::from_raw(""), root_data),
+		}
+	}
+
+	/// Insert an account and update the data of the account and all of its parents.
+	///
+	/// Accounts that are not in the tree yet are added with `initial_data` before they are updated.
+	pub fn insert(&mut self, account: Account<'a>, update: impl Fn(&mut T), initial_data: T)
+	where
+		T: Clone
+	{
+		update(&mut self.root.data);
+		let mut current = &mut self.root;
+		for account in account.walk_nodes() {
+			if let Some(x) = current.children.iter().position(|x| x.account == account) {
+				current = &mut current.children[x];
+			} else {
+				current.children.push(AccountNode::new(account, initial_data.clone()));
+				current = current.children.last_mut().unwrap();
+			}
+			update(&mut current.data);
+		}
+	}
+}
+
+impl<'a, T> AccountNode<'a, T> {
+	/// Create a node without sub-accounts.
+	pub fn new(account: Account<'a>, data: T) -> Self {
+		Self { account, data, children: Vec::new() }
+	}
+}
+
+/// Compute the total of the mutations of each account, including all sub-accounts.
+pub fn compute_totals<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>) -> AccountTree<'a, Cents> {
+	let mut root = AccountTree::new(Cents(0));
+
+	for transaction in transactions {
+		for mutation in &transaction.mutations {
+			root.insert(mutation.account, |x| *x += mutation.amount, Cents(0));
+		}
+	}
+
+	root
+}
+
+/// Find the transactions with mutations that do not add up to zero, with the unbalanced amount.
+pub fn find_unbalanced<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>) -> impl Iterator<Item = (Transaction<'a>, Cents)> {
+	transactions.into_iter().filter_map(|transaction| {
+		let balance = transaction.mutations.iter().fold(Cents(0), |sum, mutation| sum + mutation.amount);
+		if balance != Cents(0) {
+			Some((transaction, balance))
+		} else {
+			None
+		}
+	})
+}
+
+/// A mutation in the register of an account, with the running balance.
+#[derive(Debug, Clone)]
+pub struct RegisterRow<'a> {
+	/// The date of the transaction.
+	pub date: Date,
+
+	/// The description of the transaction.
+	pub description: &'a str,
+
+	/// The mutated account, which is the registered account or a sub-account.
+	pub account: Account<'a>,
+
+	/// The amount of the mutation.
+	pub amount: Cents,
+
+	/// The balance of the registered account after the mutation.
+	pub balance: Cents,
+}
+
+/// Get the register of an account: all mutations of the account and its sub-accounts with the running balance.
+pub fn register<'a>(transactions: impl IntoIterator<Item = Transaction<'a>>, account: &str) -> Vec<RegisterRow<'a>> {
+	let mut balance = Cents(0);
+	let mut rows = Vec::new();
+	for transaction in transactions {
+		for mutation in transaction.mutations.iter().filter(|x| x.account.matches_prefix(account)) {
+			balance += mutation.amount;
+			rows.push(RegisterRow {
+				date: transaction.date,
+				description: transaction.description,
+				account: mutation.account,
+				amount: mutation.amount,
+				balance,
+			});
+		}
+	}
+	rows
+}
+
+/// Get the date placeholders for grootboek paths, accounts and descriptions.
+pub fn date_format_args(date: Date) -> BTreeMap<&'static str, String> {
+	[
+		("year", date.year().to_string()),
+		("month", format!("{:02}", date.month().to_number())),
+		("day", format!("{:02}", date.day())),
+		("quarter", ((date.month().to_number() - 1) / 3 + 1).to_string()),
+	].into_iter().collect()
+}
+
+/// Expand the placeholders in a template from the configuration.
+///
+/// The `name` describes the template in the error message, like `grootboek path`.
+pub fn expand_template(template: &str, args: &BTreeMap<&str, String>, name: &'static str) -> Result<String, UpdateError> {
+	SimpleCurlyFormat.format(template, args)
+		.map(|x| x.into_owned())
+		.map_err(|e| UpdateError::Template { name, error: e.to_string() })
+}
+
+/// Book the payment of an invoice in the grootboek.
+///
+/// The payment is booked from the bank account to the debitor account of the invoice.
+/// If no bank account is given, the bank account from the configuration is used.
+/// The transaction is printed to standard error before it is appended to the grootboek.
+pub fn append_payment(root_dir: &Path, config: &ZzpConfig, invoice: &RegisteredInvoice, date: Date, bank_account: Option<&str>, force: bool) -> Result<(), UpdateError> {
+	let mut format_args = date_format_args(date);
+	format_args.insert("customer", invoice.customer.clone());
+	format_args.insert("invoice_number", invoice.number.clone());
+
+	let bank_account = bank_account
+		.or(config.grootboek.bank_account.as_deref())
+		.ok_or(UpdateError::NoBankAccount)?;
+	let bank_account = expand_template(bank_account, &format_args, "bank account")?;
+	let grootboek_path = root_dir.join(expand_template(&config.grootboek.path, &format_args, "grootboek path")?);
+	let description = config.invoice.payment_description.as_deref()
+		.unwrap_or(crate::register::DEFAULT_PAYMENT_DESCRIPTION);
+	let description = expand_template(description, &format_args, "payment description")?;
+
+	let mutations = zzp::grootboek::payment_mutations(
+		Account::from_raw(&bank_account),
+		Account::from_raw(&invoice.debitor_account),
+		invoice.total(),
+	);
+	let transaction = Transaction::new(date, &description, Vec::new(), mutations);
+
+	print_full_colored(&transaction);
+	zzp::grootboek::append_transaction(&grootboek_path, &transaction, force)
+		.map_err(UpdateError::Append)
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
+	/// A template from the configuration could not be expanded.
+	Template { name: &'static str, error: String },
+
+	/// No bank account was given, and none is configured.
+	NoBankAccount,
+
+	/// The transaction could not be appended to the grootboek.
+	Append(AppendError),
+}
+
+impl std::error::Error for UpdateError {}
+impl std::fmt::Display for UpdateError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Template { name, error } => write!(f, "failed to expand {}: {}", name, error),
+			Self::NoBankAccount => write!(f, "no bank account given: use --bank-account or set bank_account in the [Grootboek] section of zzp.toml"),
+			Self::Append(error) => write!(f, "{}", error),
+		}
+	}
+}
+
+/// An error while reading the transactions of a period from a grootboek file.
+#[derive(Debug)]
+pub enum ReadPeriodError {
+	/// The index of the file could not be used.
+	Index(IndexError),
+
+	/// The file could not be read.
+	Read(PathBuf, std::io::Error),
+}
+
+impl std::error::Error for ReadPeriodError {}
+impl std::fmt::Display for ReadPeriodError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Index(error) => write!(f, "{}", error),
+			Self::Read(path, error) => write!(f, "failed to read {}: {}", path.display(), error),
+		}
+	}
+}
+
+/// An error while reading the zzp.toml that applies to a grootboek file.
+#[derive(Debug)]
+pub enum ConfigError {
+	/// The zzp.toml could not be found.
+	Find(FindConfigError),
+
+	/// The zzp.toml could not be read.
+	Read(ReadFileError),
+}
+
+impl std::error::Error for ConfigError {}
+impl std::fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Find(error) => write!(f, "{}", error),
+			Self::Read(error) => write!(f, "{}", error),
+		}
+	}
+}
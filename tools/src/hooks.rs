@@ -0,0 +1,189 @@
+//! Hook commands that run after events, like generating an invoice.
+//!
+//! The commands are configured in the `[Hooks]` section of `zzp.toml`, see [`HooksConfig`].
+
+use dynfmt::{Format, SimpleCurlyFormat};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use zzp::gregorian::Date;
+
+use crate::register::RegisteredInvoice;
+use crate::{HooksConfig, ZzpConfig};
+
+/// An event that can run a hook command.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Hook {
+	/// An invoice was generated.
+	PostInvoice,
+
+	/// A bank statement was imported.
+	PostBankImport,
+
+	/// An invoice was marked as paid.
+	PostPaid,
+}
+
+impl Hook {
+	/// Get the configured command of the hook, if any.
+	fn command(self, config: &HooksConfig) -> Option<&str> {
+		match self {
+			Self::PostInvoice => config.post_invoice.as_deref(),
+			Self::PostBankImport => config.post_bank_import.as_deref(),
+			Self::PostPaid => config.post_paid.as_deref(),
+		}
+	}
+}
+
+/// Run the command of a hook with the given placeholders, if the hook is configured.
+///
+/// The command runs in `root_dir`, the directory containing `zzp.toml`.
+/// Returns an error if the command can not be started or does not exit successfully.
+pub fn run_hook(root_dir: &Path, config: &ZzpConfig, hook: Hook, args: &BTreeMap<&str, String>) -> Result<(), HookError> {
+	let command = match config.hooks.as_ref().and_then(|x| hook.command(x)) {
+		Some(command) => command,
+		None => return Ok(()),
+	};
+
+	let words = expand_command(hook, command, args)?;
+	let (program, arguments) = words.split_first()
+		.ok_or(HookError::Empty(hook))?;
+
+	let status = Command::new(program)
+		.args(arguments)
+		.current_dir(root_dir)
+		.status()
+		.map_err(|error| HookError::Start { hook, program: program.clone(), error })?;
+	if !status.success() {
+		return Err(HookError::Failed { hook, program: program.clone(), status });
+	}
+	Ok(())
+}
+
+/// Run the `post_paid` hook for an invoice that was paid on `date`.
+pub fn run_paid_hook(root_dir: &Path, config: &ZzpConfig, invoice: &RegisteredInvoice, date: Date) -> Result<(), HookError> {
+	let args = [
+		("number", invoice.number.clone()),
+		("customer", invoice.customer.clone()),
+		("date", date.to_string()),
+		("total", config.currency().format(invoice.total())),
+	].into_iter().collect();
+	run_hook(root_dir, config, Hook::PostPaid, &args)
+}
+
+/// Split the command of a hook in words and fill in the placeholders.
+///
+/// Placeholders are filled in after splitting, so a value with spaces stays a single word.
+/// Every `{` and `}` is read as part of a placeholder,
+/// so a literal `{` or `}` in the command makes the expansion fail.
+fn expand_command(hook: Hook, command: &str, args: &BTreeMap<&str, String>) -> Result<Vec<String>, HookError> {
+	let words = split_command(command)
+		.map_err(|quote| HookError::UnterminatedQuote { hook, quote })?;
+	words.iter()
+		.map(|word| SimpleCurlyFormat.format(word, args).map(|x| x.into_owned()))
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| HookError::Expand { hook, error: e.to_string() })
+}
+
+/// Split a command in words on whitespace.
+///
+/// Words can be quoted with single or double quotes to include whitespace.
+/// Quotes can appear anywhere in a word, like `a"b c"` for the word `ab c`, and `''` is an empty word.
+/// Returns the quote character if a quoted word is not terminated.
+fn split_command(command: &str) -> Result<Vec<String>, char> {
+	let mut words = Vec::new();
+	let mut word = None;
+	let mut quote = None;
+	for c in command.chars() {
+		match (quote, c) {
+			(Some(q), c) if c == q => quote = None,
+			(Some(_), c) => word.get_or_insert_with(String::new).push(c),
+			(None, '\'' | '"') => {
+				quote = Some(c);
+				word.get_or_insert_with(String::new);
+			},
+			(None, c) if c.is_whitespace() => words.extend(word.take()),
+			(None, c) => word.get_or_insert_with(String::new).push(c),
+		}
+	}
+	if let Some(quote) = quote {
+		return Err(quote);
+	}
+	words.extend(word);
+	Ok(words)
+}
+
+impl std::fmt::Display for Hook {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::PostInvoice => write!(f, "post_invoice"),
+			Self::PostBankImport => write!(f, "post_bank_import"),
+			Self::PostPaid => write!(f, "post_paid"),
+		}
+	}
+}
+
+/// An error while running a hook command.
+#[derive(Debug)]
+pub enum HookError {
+	/// The command has a quoted word without closing quote.
+	UnterminatedQuote { hook: Hook, quote: char },
+
+	/// A placeholder in the command could not be expanded.
+	Expand { hook: Hook, error: String },
+
+	/// The command is empty.
+	Empty(Hook),
+
+	/// The command could not be started.
+	Start { hook: Hook, program: String, error: std::io::Error },
+
+	/// The command did not exit successfully.
+	Failed { hook: Hook, program: String, status: ExitStatus },
+}
+
+impl std::error::Error for HookError {}
+impl std::fmt::Display for HookError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::UnterminatedQuote { hook, quote } => write!(f, "invalid {} hook: missing closing {}", hook, quote),
+			Self::Expand { hook, error } => write!(f, "failed to expand {} hook: {}", hook, error),
+			Self::Empty(hook) => write!(f, "invalid {} hook: empty command", hook),
+			Self::Start { hook, program, error } => write!(f, "failed to run {} hook {:?}: {}", hook, program, error),
+			Self::Failed { hook, program, status } => write!(f, "{} hook {:?} failed: {}", hook, program, status),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_split_command() {
+	use assert2::assert;
+
+	assert!(split_command("./notify.sh  invoice\t42") == Ok(vec!["./notify.sh".into(), "invoice".into(), "42".into()]));
+	assert!(split_command("notify 'generated invoice' \"for customer\"") == Ok(vec!["notify".into(), "generated invoice".into(), "for customer".into()]));
+	assert!(split_command("notify '' \"\"") == Ok(vec!["notify".into(), "".into(), "".into()]));
+	assert!(split_command("notify a\"b c\"d 'e'f") == Ok(vec!["notify".into(), "ab cd".into(), "ef".into()]));
+	assert!(split_command("notify \"it's\" 'say \"hi\"'") == Ok(vec!["notify".into(), "it's".into(), "say \"hi\"".into()]));
+	assert!(split_command("") == Ok(vec![]));
+	assert!(split_command("notify 'unterminated") == Err('\''));
+	assert!(split_command("notify \"unterminated") == Err('"'));
+	assert!(split_command("notify a\"b") == Err('"'));
+}
+
+#[cfg(test)]
+#[test]
+fn test_expand_command() {
+	use assert2::{assert, let_assert};
+
+	let args = [
+		("customer", "Acme Corp".to_string()),
+		("pdf", "invoices/2024 01.pdf".to_string()),
+	].into_iter().collect();
+	let_assert!(Ok(words) = expand_command(Hook::PostInvoice, "notify {pdf} '{customer} paid'", &args));
+	assert!(words == ["notify", "invoices/2024 01.pdf", "Acme Corp paid"]);
+
+	let_assert!(Err(HookError::UnterminatedQuote { quote, .. }) = expand_command(Hook::PostInvoice, "notify '{pdf}", &args));
+	assert!(quote == '\'');
+	assert!(let Err(HookError::Expand { .. }) = expand_command(Hook::PostInvoice, "notify {missing}", &args));
+}
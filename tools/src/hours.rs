@@ -0,0 +1,349 @@
+//! Conversion of hour log entries to invoice entries.
+
+use ordered_float::NotNan;
+use std::collections::{btree_map, BTreeMap};
+use std::path::Path;
+use zzp::gregorian::Date;
+use zzp::index::FileKind;
+use zzp::uurlog::Entry;
+
+use crate::invoice::InvoiceEntry;
+use crate::{CustomerConfig, ExpenseConfig, HourlyRate, SurchargeRule, TagConfig, ZzpConfig};
+
+/// The rates and presentation of hour entries.
+#[derive(Debug, Clone)]
+pub struct HourRates<'a> {
+	/// The unit to display for hour entries on the invoice.
+	pub unit: &'a str,
+
+	/// The price per hour, which can depend on the date of the entry.
+	pub unit_price: HourlyRate,
+
+	/// The VAT percentage.
+	pub vat_percentage: NotNan<f64>,
+
+	/// Add a single invoice entry per day with the given summary.
+	pub summarize_days: Option<&'a str>,
+
+	/// The tags with their own rates, overriding the defaults above for tagged entries.
+	pub tags: &'a [TagConfig],
+
+	/// The surcharges for work on specific days.
+	pub surcharges: &'a [SurchargeRule],
+}
+
+impl<'a> HourRates<'a> {
+	/// Get the default rates for a customer.
+	///
+	/// The customer overrides must already be applied to the ZZP configuration.
+	pub fn from_config(config: &'a ZzpConfig, customer_config: &'a CustomerConfig) -> Self {
+		Self {
+			unit: &config.invoice_localization.hours,
+			unit_price: customer_config.invoice.price_per_hour.clone(),
+			vat_percentage: config.tax.vat,
+			summarize_days: customer_config.invoice.summarize_per_day.as_deref(),
+			tags: &config.tag,
+			surcharges: &customer_config.surcharge,
+		}
+	}
+}
+
+/// Read the entries of an hour log from `start_date` until but not including `end_date`.
+///
+/// If the hour log has an index, only the entries of the period are parsed.
+pub fn read_hours_file(path: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, String> {
+	let indexed = if start_date.is_some() || end_date.is_some() {
+		zzp::index::read_period(path, FileKind::Uurlog, start_date, end_date)
+			.map_err(|e| e.to_string())?
+	} else {
+		None
+	};
+
+	let mut entries = match indexed {
+		Some(indexed) => zzp::uurlog::parse_bytes(indexed.data.as_bytes())
+			.map_err(|mut e| {
+				e.line += indexed.line - 1;
+				format!("failed to read hour entries from {}: {}", path.display(), e)
+			})?,
+		None => zzp::uurlog::parse_file(path)
+			.map_err(|e| format!("failed to read hour entries from {}: {}", path.display(), e))?,
+	};
+	retain_period(&mut entries, start_date, end_date);
+	Ok(entries)
+}
+
+/// Read the entries of an hour log without blocking the async executor.
+///
+/// The file is read and parsed on the blocking thread pool of tokio, see [`read_hours_file`].
+#[cfg(feature = "tokio")]
+pub async fn read_hours_file_async(path: std::path::PathBuf, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, String> {
+	tokio::task::spawn_blocking(move || read_hours_file(&path, start_date, end_date)).await
+		.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Read the entries from all hour logs of a customer from `start_date` until but not including `end_date`.
+///
+/// The `customer_dir` is the directory containing `customer.toml`.
+pub fn read_customer_hours(customer_config: &CustomerConfig, customer_dir: &Path, start_date: Option<Date>, end_date: Option<Date>) -> Result<Vec<Entry>, String> {
+	let mut entries = customer_config.read_hours(customer_dir)?;
+	retain_period(&mut entries, start_date, end_date);
+	Ok(entries)
+}
+
+/// Keep only the entries from `start_date` until but not including `end_date`.
+pub fn retain_period(entries: &mut Vec<Entry>, start_date: Option<Date>, end_date: Option<Date>) {
+	if let Some(start_date) = start_date {
+		entries.retain(|x| x.date >= start_date);
+	}
+	if let Some(end_date) = end_date {
+		entries.retain(|x| x.date < end_date);
+	}
+}
+
+/// Get the price per hour of an hour log entry.
+///
+/// The first tag of the entry that is configured for the customer determines the price,
+/// just like when creating an invoice.
+/// Surcharges are included, but discounts of the tag are not taken into account.
+pub fn price_per_hour(rates: &HourRates, entry: &Entry) -> Result<NotNan<f64>, String> {
+	let tag = entry.tags.iter()
+		.find_map(|tag| rates.tags.iter().find(|x| &x.name == tag));
+	let price = match tag.and_then(|tag| tag.price_per_hour) {
+		Some(price) => price,
+		None => rates.unit_price.at_or_err(entry.date)?,
+	};
+	match surcharge(rates, tag, entry.date) {
+		Some(percentage) => Ok(price * percentage / 100.0),
+		None => Ok(price),
+	}
+}
+
+/// Get the surcharge percentage for hours on a date, optionally with a tag.
+///
+/// If both the tag and one or more surcharge rules for the date have a surcharge, the highest one is used.
+pub fn surcharge(rates: &HourRates, tag: Option<&TagConfig>, date: Date) -> Option<NotNan<f64>> {
+	let rules = rates.surcharges.iter()
+		.filter(|rule| rule.applies_to(date))
+		.map(|rule| rule.percentage);
+	tag.and_then(|tag| tag.surcharge).into_iter().chain(rules).max()
+}
+
+/// Apply a surcharge to the price and description of an hour entry.
+///
+/// The percentage is added to the description, like `work on the weekend (150%)`.
+fn apply_surcharge(unit_price: NotNan<f64>, description: String, surcharge: Option<NotNan<f64>>) -> (NotNan<f64>, String) {
+	match surcharge {
+		Some(percentage) => (unit_price * percentage / 100.0, format!("{} ({}%)", description, percentage)),
+		None => (unit_price, description),
+	}
+}
+
+/// Create the invoice entries for the hour log entries of a customer.
+///
+/// Hour entries with a configured tag use the price, VAT percentage, surcharge, discount and section of the tag.
+/// Surcharges for the date of an entry are applied to both tagged and untagged hour entries.
+/// Entries with the tag of an expense of the customer are invoiced as expense instead of as hours.
+/// The extra entries are added before the hour entries and the expenses and fixed-price entries of the customer after them,
+/// followed by the discounts of the tags.
+/// The invoice wide discount is not added.
+pub fn make_invoice_entries(
+	customer_config: &CustomerConfig,
+	hour_entries: Vec<Entry>,
+	extra_entries: Vec<InvoiceEntry>,
+	rates: &HourRates,
+	date: Date,
+) -> Result<Vec<InvoiceEntry>, String> {
+	// Split hour entries on tags that we care about.
+	let mut tagged_hour_entries = BTreeMap::new();
+	let mut untagged_hour_entries = Vec::new();
+	for tag in rates.tags {
+		tagged_hour_entries.insert(tag.name.as_str(), Vec::new());
+	}
+
+	let mut expense_entries = Vec::new();
+
+	'entries:
+	for entry in hour_entries {
+		if let Some(expense) = find_expense(&customer_config.expense, &entry) {
+			expense_entries.push(make_expense_entry(expense, entry, rates.vat_percentage)?);
+			continue;
+		}
+		for tag in &entry.tags {
+			if let Some(tagged_entries) = tagged_hour_entries.get_mut(tag.as_str()) {
+				tagged_entries.push(entry);
+				continue 'entries;
+			}
+		}
+		untagged_hour_entries.push(entry);
+	}
+
+	let mut invoice_entries = extra_entries;
+	let mut discount_entries = Vec::new();
+
+	// Summarize entries per day, if requested.
+	let untagged_hour_entries = if let Some(description) = rates.summarize_days {
+		summarize_hours_per_day(untagged_hour_entries, description)
+	} else {
+		untagged_hour_entries
+	};
+
+	for entry in untagged_hour_entries {
+		let (unit_price, description) = apply_surcharge(
+			rates.unit_price.at_or_err(entry.date)?,
+			entry.description,
+			surcharge(rates, None, entry.date),
+		);
+		invoice_entries.push(InvoiceEntry {
+			unit_price,
+			description,
+			quantity: NotNan::new(f64::from(entry.hours.total_minutes()) / 60.0).unwrap(),
+			unit: rates.unit.to_string(),
+			date: entry.date,
+			vat_percentage: rates.vat_percentage,
+			section: None,
+		});
+	}
+
+	for tag in rates.tags {
+		let hour_entries = if let Some(description) = &tag.summarize_per_day {
+			summarize_hours_per_day(tagged_hour_entries.get(tag.name.as_str()).unwrap(), description)
+		} else {
+			tagged_hour_entries.get(tag.name.as_str()).unwrap().clone()
+		};
+		let tag_entries = hour_entries.into_iter().map(|entry| {
+			let unit_price = match tag.price_per_hour {
+				Some(price) => price,
+				None => rates.unit_price.at_or_err(entry.date)?,
+			};
+			let (unit_price, description) = apply_surcharge(unit_price, entry.description, surcharge(rates, Some(tag), entry.date));
+			Ok(InvoiceEntry {
+				description,
+				quantity: NotNan::new(f64::from(entry.hours.total_minutes()) / 60.0).unwrap(),
+				unit: rates.unit.to_string(),
+				date: entry.date,
+				unit_price,
+				vat_percentage: tag.vat.unwrap_or(rates.vat_percentage),
+				section: tag.section.clone(),
+			})
+		}).collect::<Result<Vec<_>, String>>()?;
+		if let Some(discount) = &tag.discount {
+			let value = discount.value().map_err(|e| format!("tag {}: {}", tag.name, e))?;
+			let entries = value.make_entries(&discount.description, date, &tag_entries, tag.vat.unwrap_or(rates.vat_percentage));
+			discount_entries.extend(entries.into_iter().map(|entry| InvoiceEntry {
+				section: tag.section.clone(),
+				..entry
+			}));
+		}
+		invoice_entries.extend(tag_entries);
+	}
+
+	invoice_entries.sort_by_key(|x| x.date);
+
+	// Add the expenses after the hour entries.
+	expense_entries.sort_by_key(|x| x.date);
+	invoice_entries.append(&mut expense_entries);
+
+	// Add the fixed-price entries of the customer after the hour entries.
+	invoice_entries.extend(customer_config.fixed_entry.iter().map(|entry| {
+		InvoiceEntry {
+			date,
+			description: entry.description.clone(),
+			quantity: entry.quantity.unwrap_or_else(|| NotNan::new(1.0).unwrap()),
+			unit: entry.unit.clone().unwrap_or_default(),
+			unit_price: entry.price,
+			vat_percentage: entry.vat.unwrap_or(rates.vat_percentage),
+			section: None,
+		}
+	}));
+
+	// Add discounts at the end of the invoice, with the invoice wide discount over all other entries.
+	invoice_entries.append(&mut discount_entries);
+
+	// Group entries per section, in the order of the configured tags followed by the expenses.
+	if invoice_entries.iter().any(|entry| entry.section.is_some()) {
+		invoice_entries.sort_by_key(|entry| match &entry.section {
+			None => 0,
+			Some(section) => rates.tags.iter()
+				.filter_map(|tag| tag.section.as_ref())
+				.chain(customer_config.expense.iter().filter_map(|expense| expense.section.as_ref()))
+				.position(|x| x == section)
+				.map_or(usize::MAX, |i| i + 1),
+		});
+	}
+
+	Ok(invoice_entries)
+}
+
+/// Find the expense for an hour log entry.
+///
+/// The first tag of the entry that is configured as expense determines the expense.
+pub fn find_expense<'a>(expenses: &'a [ExpenseConfig], entry: &Entry) -> Option<&'a ExpenseConfig> {
+	entry.tags.iter().find_map(|tag| expenses.iter().find(|x| &x.tag == tag))
+}
+
+/// Create the invoice entry for an expense logged in the hour log.
+///
+/// The description of the hour log entry can start with the quantity, otherwise the quantity is 1.
+pub fn make_expense_entry(expense: &ExpenseConfig, entry: Entry, vat_percentage: NotNan<f64>) -> Result<InvoiceEntry, String> {
+	if entry.hours.total_minutes() != 0 {
+		return Err(format!("{}: entries for expense [{}] must have zero hours, got {}", entry.date, expense.tag, entry.hours));
+	}
+	let (quantity, description) = split_quantity(&entry.description);
+	let description = match description.is_empty() {
+		true => expense.description.clone(),
+		false => format!("{}: {}", expense.description, description),
+	};
+	Ok(InvoiceEntry {
+		date: entry.date,
+		description,
+		quantity: quantity.unwrap_or_else(|| NotNan::new(1.0).unwrap()),
+		unit: expense.unit.clone().unwrap_or_default(),
+		unit_price: expense.price,
+		vat_percentage: expense.vat.unwrap_or(vat_percentage),
+		section: expense.section.clone(),
+	})
+}
+
+/// Split the leading quantity from the description of an expense entry.
+///
+/// Both a dot and a comma are accepted as decimal separator.
+fn split_quantity(description: &str) -> (Option<NotNan<f64>>, &str) {
+	let (first, rest) = description.split_once(' ').unwrap_or((description, ""));
+	let quantity = first.replace(',', ".").parse::<f64>().ok()
+		.filter(|x| x.is_finite())
+		.and_then(|x| NotNan::new(x).ok());
+	match quantity {
+		Some(quantity) => (Some(quantity), rest.trim()),
+		None => (None, description),
+	}
+}
+
+/// Summarize hour entries with a single entry per day.
+pub fn summarize_hours_per_day<I>(entries: I, description: &str) -> Vec<zzp::uurlog::Entry>
+where
+	I: IntoIterator,
+	I::Item: std::borrow::Borrow<zzp::uurlog::Entry>,
+{
+	use std::borrow::Borrow;
+	let mut entries_per_day = BTreeMap::new();
+	for entry in entries {
+		let entry = entry.borrow();
+		match entries_per_day.entry(entry.date) {
+			btree_map::Entry::Vacant(x) => {
+				x.insert(entry.hours);
+			},
+			btree_map::Entry::Occupied(mut x) => {
+				*x.get_mut() += entry.hours;
+			}
+		}
+	}
+
+	entries_per_day.into_iter().map(|(date, hours)| {
+		zzp::uurlog::Entry {
+			date,
+			hours,
+			tags: Vec::new(),
+			description: description.to_owned(),
+		}
+	}).collect()
+}
@@ -0,0 +1,623 @@
+//! Calendars in the iCalendar format of RFC 5545.
+
+use jiff::tz::TimeZone;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use zzp::gregorian::{Date, Month};
+use zzp::uurlog::{Entry, Hours};
+
+/// The maximum length of a content line in octets, without the line ending.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// A calendar with all-day events.
+#[derive(Debug, Clone, Default)]
+pub struct Calendar {
+	/// The name of the calendar, shown by calendar applications.
+	pub name: Option<String>,
+
+	/// The events in the calendar.
+	pub events: Vec<Event>,
+}
+
+/// An all-day event.
+#[derive(Debug, Clone)]
+pub struct Event {
+	/// The unique identifier of the event.
+	pub uid: String,
+
+	/// The date of the event.
+	pub date: Date,
+
+	/// The title of the event.
+	pub summary: String,
+
+	/// The categories of the event.
+	pub categories: Vec<String>,
+}
+
+/// A calendar read from an iCalendar file.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedCalendar {
+	/// The name of the calendar, if the file has one.
+	pub name: Option<String>,
+
+	/// The events in the calendar, without cancelled events.
+	pub events: Vec<ParsedEvent>,
+}
+
+/// An event read from an iCalendar file.
+#[derive(Debug, Clone)]
+pub struct ParsedEvent {
+	/// The unique identifier of the event, if it has one.
+	pub uid: Option<String>,
+
+	/// The date on which the event starts.
+	pub date: Date,
+
+	/// The duration of the event, or `None` for all-day events.
+	pub duration: Option<Hours>,
+
+	/// The title of the event.
+	pub summary: String,
+
+	/// The categories of the event.
+	pub categories: Vec<String>,
+
+	/// The event repeats, only the first occurrence is described by the other fields.
+	pub recurring: bool,
+}
+
+/// The start or end of an event.
+#[derive(Debug, Copy, Clone)]
+struct DateTime {
+	/// The date in local time.
+	date: Date,
+
+	/// The minutes since midnight in local time, or `None` for a date without time.
+	minutes: Option<u32>,
+
+	/// The seconds since the Unix epoch, for times in UTC or with a known time zone.
+	timestamp: Option<i64>,
+}
+
+/// The time zones used to convert times to local time.
+struct TimeZones {
+	/// The local time zone.
+	local: TimeZone,
+
+	/// The `TZID` parameters that are not in the time zone database.
+	unknown: BTreeSet<String>,
+}
+
+impl Calendar {
+	/// Create a calendar with an all-day event for each hour log entry.
+	///
+	/// The duration of the entry is shown in the summary and the tags are added as categories.
+	pub fn from_entries(name: Option<String>, entries: &[Entry]) -> Self {
+		// Count identical entries, so each of them gets a different identifier.
+		let mut seen = BTreeMap::new();
+		let events = entries.iter()
+			.map(|entry| {
+				let occurrence = seen.entry(entry).or_insert(0);
+				*occurrence += 1;
+				Event {
+					uid: entry_uid(entry, *occurrence),
+					date: entry.date,
+					summary: format!("{} {}", entry.hours, entry.description),
+					categories: entry.tags.clone(),
+				}
+			})
+			.collect();
+		Self { name, events }
+	}
+}
+
+/// Get a stable identifier for an hour log entry.
+///
+/// Exporting the same entries again gives the same identifiers,
+/// so calendar applications update the events instead of adding them twice.
+fn entry_uid(entry: &Entry, occurrence: usize) -> String {
+	let data = format!("{}\n{}\n{}\n{}\n{}", entry.date, entry.hours, entry.tags.join(","), entry.description, occurrence);
+	let digest: String = Sha256::digest(data.as_bytes()).iter().take(16).map(|x| format!("{:02x}", x)).collect();
+	format!("{}@uurlog.zzp", digest)
+}
+
+/// Format a date as iCalendar date value, like `20240131`.
+fn format_date(date: Date) -> String {
+	format!("{:04}{:02}{:02}", date.year().to_number(), date.month().to_number(), date.day())
+}
+
+/// Escape a text value.
+fn escape_text(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'\\' => escaped.push_str("\\\\"),
+			';' => escaped.push_str("\\;"),
+			',' => escaped.push_str("\\,"),
+			'\n' => escaped.push_str("\\n"),
+			'\r' => (),
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+/// Write a content line, folded at 75 octets without splitting characters.
+fn write_line(f: &mut std::fmt::Formatter, line: &str) -> std::fmt::Result {
+	let mut remaining = line;
+	// Continuation lines start with a space, which counts towards the line length.
+	let mut max_length = MAX_LINE_LENGTH;
+	while remaining.len() > max_length {
+		let mut split = max_length;
+		while !remaining.is_char_boundary(split) {
+			split -= 1;
+		}
+		write!(f, "{}\r\n ", &remaining[..split])?;
+		remaining = &remaining[split..];
+		max_length = MAX_LINE_LENGTH - 1;
+	}
+	write!(f, "{}\r\n", remaining)
+}
+
+impl std::fmt::Display for Calendar {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write_line(f, "BEGIN:VCALENDAR")?;
+		write_line(f, "VERSION:2.0")?;
+		write_line(f, "PRODID:-//zzp-rs//uurlog//EN")?;
+		write_line(f, "CALSCALE:GREGORIAN")?;
+		if let Some(name) = &self.name {
+			write_line(f, &format!("X-WR-CALNAME:{}", escape_text(name)))?;
+		}
+		for event in &self.events {
+			write!(f, "{}", event)?;
+		}
+		write_line(f, "END:VCALENDAR")
+	}
+}
+
+impl std::fmt::Display for Event {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write_line(f, "BEGIN:VEVENT")?;
+		write_line(f, &format!("UID:{}", self.uid))?;
+		// Use the date of the event as timestamp, so exporting the same entries twice gives the same file.
+		write_line(f, &format!("DTSTAMP:{}T000000Z", format_date(self.date)))?;
+		write_line(f, &format!("DTSTART;VALUE=DATE:{}", format_date(self.date)))?;
+		write_line(f, &format!("DTEND;VALUE=DATE:{}", format_date(self.date.next())))?;
+		write_line(f, &format!("SUMMARY:{}", escape_text(&self.summary)))?;
+		if !self.categories.is_empty() {
+			let categories: Vec<_> = self.categories.iter().map(|x| escape_text(x)).collect();
+			write_line(f, &format!("CATEGORIES:{}", categories.join(",")))?;
+		}
+		write_line(f, "TRANSP:TRANSPARENT")?;
+		write_line(f, "END:VEVENT")
+	}
+}
+
+impl ParsedCalendar {
+	/// Parse an iCalendar file.
+	///
+	/// Times in UTC and times with a `TZID` parameter are converted to the local time zone of the system.
+	/// Times without time zone and times with a `TZID` that is not in the time zone database are used as written,
+	/// so the duration of such an event that crosses a daylight saving time change can be off by an hour.
+	pub fn parse(data: &str) -> Result<Self, String> {
+		Self::parse_in_time_zone(data, TimeZone::system())
+	}
+
+	/// Parse an iCalendar file, converting times to the given local time zone.
+	fn parse_in_time_zone(data: &str, local: TimeZone) -> Result<Self, String> {
+		let mut calendar = Self::default();
+		let mut time_zones = TimeZones { local, unknown: BTreeSet::new() };
+		let mut components = Vec::new();
+		let mut event = EventBuilder::default();
+
+		for (line_number, line) in unfold_lines(data) {
+			let ContentLine { name, params, value } = split_line(&line)
+				.ok_or_else(|| format!("line {}: expected a property like NAME:VALUE", line_number))?;
+			let result = match (name.as_str(), components.last().map(String::as_str)) {
+				("BEGIN", _) => {
+					if value.eq_ignore_ascii_case("VEVENT") {
+						event = EventBuilder::default();
+					}
+					components.push(value.to_ascii_uppercase());
+					Ok(())
+				},
+				("END", _) => match components.pop() {
+					Some(component) if component.eq_ignore_ascii_case(value) => {
+						if component == "VEVENT" && components.len() == 1 {
+							std::mem::take(&mut event).build()
+								.map(|event| calendar.events.extend(event))
+						} else {
+							Ok(())
+						}
+					},
+					_ => Err(format!("unexpected END:{}", value)),
+				},
+				("X-WR-CALNAME", Some("VCALENDAR")) => {
+					calendar.name = Some(unescape_text(value));
+					Ok(())
+				},
+				(name, Some("VEVENT")) if components.len() == 2 => event.add_property(name, &params, value, &mut time_zones),
+				_ => Ok(()),
+			};
+			result.map_err(|e| format!("line {}: {}", line_number, e))?;
+		}
+
+		if let Some(component) = components.last() {
+			return Err(format!("missing END:{}", component));
+		}
+		for time_zone in &time_zones.unknown {
+			log::warn!("unknown time zone {:?}: times in this time zone are used as written", time_zone);
+		}
+		Ok(calendar)
+	}
+}
+
+/// A content line split in its parts.
+struct ContentLine<'a> {
+	/// The uppercase name of the property.
+	name: String,
+
+	/// The parameters with their uppercase names.
+	params: Vec<(String, String)>,
+
+	value: &'a str,
+}
+
+/// The properties of an event that is being parsed.
+#[derive(Debug, Default)]
+struct EventBuilder {
+	uid: Option<String>,
+	start: Option<DateTime>,
+	end: Option<DateTime>,
+	duration: Option<u32>,
+	summary: Option<String>,
+	categories: Vec<String>,
+	recurring: bool,
+	cancelled: bool,
+}
+
+impl EventBuilder {
+	fn add_property(&mut self, name: &str, params: &[(String, String)], value: &str, time_zones: &mut TimeZones) -> Result<(), String> {
+		match name {
+			"UID" => self.uid = Some(value.to_string()),
+			"DTSTART" => self.start = Some(parse_date_time(value, params, time_zones)?),
+			"DTEND" => self.end = Some(parse_date_time(value, params, time_zones)?),
+			"DURATION" => self.duration = Some(parse_duration(value)?),
+			"SUMMARY" => self.summary = Some(unescape_text(value)),
+			"CATEGORIES" => self.categories.extend(split_list(value).map(|x| unescape_text(&x))),
+			"RRULE" | "RDATE" => self.recurring = true,
+			"STATUS" => self.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+			_ => (),
+		}
+		Ok(())
+	}
+
+	/// Get the parsed event, or `None` if the event was cancelled.
+	fn build(self) -> Result<Option<ParsedEvent>, String> {
+		if self.cancelled {
+			return Ok(None);
+		}
+		let start = self.start.ok_or("event without DTSTART")?;
+		let duration = match (start.minutes, self.end, self.duration) {
+			(None, _, _) => None,
+			(Some(_), _, Some(minutes)) => Some(minutes),
+			(Some(start_minutes), Some(end), None) => {
+				let minutes = match (start.timestamp, end.timestamp) {
+					// Use the real time between start and end, so a daylight saving time change in between is taken into account.
+					(Some(start), Some(end)) => (end - start).div_euclid(60),
+					_ => days_between(start.date, end.date) * 24 * 60 + i64::from(end.minutes.unwrap_or(0)) - i64::from(start_minutes),
+				};
+				let minutes = u32::try_from(minutes)
+					.map_err(|_| "event ends before it starts")?;
+				Some(minutes)
+			},
+			// An event with a start time but without end or duration takes no time at all.
+			(Some(_), None, None) => Some(0),
+		};
+		Ok(Some(ParsedEvent {
+			uid: self.uid,
+			date: start.date,
+			duration: duration.map(Hours::from_minutes),
+			summary: self.summary.unwrap_or_default(),
+			categories: self.categories,
+			recurring: self.recurring,
+		}))
+	}
+}
+
+/// Join folded lines, giving each line with the number of its first line.
+fn unfold_lines(data: &str) -> Vec<(usize, String)> {
+	let mut lines: Vec<(usize, String)> = Vec::new();
+	for (i, line) in data.split('\n').enumerate() {
+		let line = line.strip_suffix('\r').unwrap_or(line);
+		match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+			(Some(continuation), Some((_, last))) => last.push_str(continuation),
+			_ if line.is_empty() => (),
+			_ => lines.push((i + 1, line.to_string())),
+		}
+	}
+	lines
+}
+
+/// Split a content line in the name, the parameters and the value.
+fn split_line(line: &str) -> Option<ContentLine<'_>> {
+	// Parameter values can contain colons inside quotes.
+	let mut quoted = false;
+	let colon = line.char_indices().find(|&(_, c)| {
+		if c == '"' {
+			quoted = !quoted;
+		}
+		c == ':' && !quoted
+	})?.0;
+
+	let mut parts = line[..colon].split(';');
+	let name = parts.next()?.trim().to_ascii_uppercase();
+	if name.is_empty() {
+		return None;
+	}
+	let params = parts
+		.map(|param| {
+			let (key, value) = param.split_once('=').unwrap_or((param, ""));
+			(key.trim().to_ascii_uppercase(), value.trim_matches('"').to_string())
+		})
+		.collect();
+	Some(ContentLine { name, params, value: &line[colon + 1..] })
+}
+
+/// Split a list value on commas that are not escaped.
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+	let mut items = Vec::new();
+	let mut current = String::new();
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => {
+				current.push(c);
+				current.extend(chars.next());
+			},
+			',' => items.push(std::mem::take(&mut current)),
+			c => current.push(c),
+		}
+	}
+	items.push(current);
+	items.into_iter().filter(|x| !x.is_empty())
+}
+
+/// Undo the escaping of a text value.
+fn unescape_text(value: &str) -> String {
+	let mut text = String::with_capacity(value.len());
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		match (c, c == '\\') {
+			(_, true) => match chars.next() {
+				Some('n' | 'N') => text.push('\n'),
+				Some(c) => text.push(c),
+				None => text.push('\\'),
+			},
+			(c, false) => text.push(c),
+		}
+	}
+	text
+}
+
+/// Parse a date like `20240131` or a date with time like `20240131T093000Z`.
+///
+/// Times in UTC (with a `Z` suffix) and times with a known `TZID` parameter are converted to local time.
+fn parse_date_time(value: &str, params: &[(String, String)], time_zones: &mut TimeZones) -> Result<DateTime, String> {
+	let invalid = || format!("invalid date or time: {:?}", value);
+	let is_date = params.iter().any(|(key, value)| key == "VALUE" && value.eq_ignore_ascii_case("DATE"));
+	let (date, time) = match value.split_once('T') {
+		Some((date, time)) if !is_date => (date, Some(time)),
+		_ => (value, None),
+	};
+	if date.len() != 8 || !date.bytes().all(|x| x.is_ascii_digit()) {
+		return Err(invalid());
+	}
+	let year: i16 = date[0..4].parse().map_err(|_| invalid())?;
+	let month = Month::new(date[4..6].parse().map_err(|_| invalid())?).map_err(|_| invalid())?;
+	let day: u8 = date[6..8].parse().map_err(|_| invalid())?;
+	let date = Date::new(year, month, day).map_err(|_| invalid())?;
+
+	let (time, utc) = match time {
+		None => return Ok(DateTime { date, minutes: None, timestamp: None }),
+		Some(time) => match time.strip_suffix('Z') {
+			Some(time) => (time, true),
+			None => (time, false),
+		},
+	};
+	if time.len() != 6 || !time.bytes().all(|x| x.is_ascii_digit()) {
+		return Err(invalid());
+	}
+	let hours: u32 = time[0..2].parse().map_err(|_| invalid())?;
+	let minutes: u32 = time[2..4].parse().map_err(|_| invalid())?;
+	if hours > 23 || minutes > 59 {
+		return Err(invalid());
+	}
+
+	// Times without time zone are floating: they are the same in every time zone.
+	let floating = DateTime { date, minutes: Some(hours * 60 + minutes), timestamp: None };
+	let time_zone = if utc {
+		TimeZone::UTC
+	} else {
+		match params.iter().find(|(key, _)| key == "TZID") {
+			None => return Ok(floating),
+			Some((_, name)) => match TimeZone::get(name) {
+				Ok(time_zone) => time_zone,
+				Err(_) => {
+					time_zones.unknown.insert(name.clone());
+					return Ok(floating);
+				},
+			},
+		}
+	};
+	let civil = jiff::civil::DateTime::new(year, month.to_number() as i8, day as i8, hours as i8, minutes as i8, 0, 0)
+		.map_err(|_| invalid())?;
+	let timestamp = time_zone.to_timestamp(civil)
+		.map_err(|e| format!("{}: {}", invalid(), e))?;
+	let local = timestamp.to_zoned(time_zones.local.clone()).datetime();
+	let month = Month::new(local.month() as u8).map_err(|_| invalid())?;
+	let date = Date::new(local.year(), month, local.day() as u8).map_err(|_| invalid())?;
+	let minutes = local.hour() as u32 * 60 + local.minute() as u32;
+	Ok(DateTime { date, minutes: Some(minutes), timestamp: Some(timestamp.as_second()) })
+}
+
+/// Parse a duration like `PT1H30M` or `P1D` as number of minutes.
+///
+/// Seconds are ignored.
+fn parse_duration(value: &str) -> Result<u32, String> {
+	let invalid = || format!("invalid duration: {:?}", value);
+	let value = value.strip_prefix('+').unwrap_or(value);
+	let value = value.strip_prefix('P').ok_or_else(invalid)?;
+
+	let mut minutes = 0;
+	let mut number = String::new();
+	for c in value.chars() {
+		if c.is_ascii_digit() {
+			number.push(c);
+			continue;
+		}
+		if c == 'T' && number.is_empty() {
+			continue;
+		}
+		let amount: u32 = number.parse().map_err(|_| invalid())?;
+		number.clear();
+		minutes += match c {
+			'W' => amount * 7 * 24 * 60,
+			'D' => amount * 24 * 60,
+			'H' => amount * 60,
+			'M' => amount,
+			'S' => 0,
+			_ => return Err(invalid()),
+		};
+	}
+	if !number.is_empty() {
+		return Err(invalid());
+	}
+	Ok(minutes)
+}
+
+/// Get the number of days from one date to another.
+fn days_between(start: Date, end: Date) -> i64 {
+	day_number(end) - day_number(start)
+}
+
+/// Get the number of days since 1 March of the year 0 in the proleptic Gregorian calendar.
+fn day_number(date: Date) -> i64 {
+	let month = i64::from(date.month().to_number());
+	// Count years from March, so the leap day is the last day of the year.
+	let year = i64::from(date.year().to_number()) - i64::from(month <= 2);
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(date.day()) - 1;
+	year * 365 + year.div_euclid(4) - year.div_euclid(100) + year.div_euclid(400) + day_of_year
+}
+
+#[cfg(test)]
+fn parse_test_events(events: &str, local: TimeZone) -> Vec<ParsedEvent> {
+	let data = format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n", events);
+	ParsedCalendar::parse_in_time_zone(&data, local).unwrap().events
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_utc() {
+	use assert2::assert;
+
+	let events = parse_test_events(concat!(
+		"BEGIN:VEVENT\r\n",
+		"DTSTART:20240131T233000Z\r\n",
+		"DTEND:20240201T010000Z\r\n",
+		"SUMMARY:Late meeting\r\n",
+		"END:VEVENT\r\n",
+	), TimeZone::fixed(jiff::tz::offset(2)));
+	assert!(events.len() == 1);
+	assert!(events[0].date == Date::new(2024, zzp::gregorian::February, 1).unwrap());
+	assert!(events[0].duration == Some(Hours::from_minutes(90)));
+
+	let events = parse_test_events(concat!(
+		"BEGIN:VEVENT\r\n",
+		"DTSTART:20240131T233000Z\r\n",
+		"DURATION:PT1H\r\n",
+		"END:VEVENT\r\n",
+	), TimeZone::UTC);
+	assert!(events[0].date == Date::new(2024, zzp::gregorian::January, 31).unwrap());
+	assert!(events[0].duration == Some(Hours::from_minutes(60)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_tzid() {
+	use assert2::assert;
+
+	let events = parse_test_events(concat!(
+		"BEGIN:VEVENT\r\n",
+		"DTSTART;TZID=Europe/Amsterdam:20240131T003000\r\n",
+		"DTEND;TZID=Europe/Amsterdam:20240131T020000\r\n",
+		"END:VEVENT\r\n",
+		// Daylight saving time starts at 02:00 on 31 March 2024 in Amsterdam.
+		"BEGIN:VEVENT\r\n",
+		"DTSTART;TZID=\"Europe/Amsterdam\":20240331T010000\r\n",
+		"DTEND;TZID=\"Europe/Amsterdam\":20240331T040000\r\n",
+		"END:VEVENT\r\n",
+		"BEGIN:VEVENT\r\n",
+		"DTSTART;TZID=Custom Zone:20240131T003000\r\n",
+		"DTEND;TZID=Custom Zone:20240131T020000\r\n",
+		"END:VEVENT\r\n",
+	), TimeZone::UTC);
+	assert!(events.len() == 3);
+	assert!(events[0].date == Date::new(2024, zzp::gregorian::January, 30).unwrap());
+	assert!(events[0].duration == Some(Hours::from_minutes(90)));
+	assert!(events[1].date == Date::new(2024, zzp::gregorian::March, 31).unwrap());
+	assert!(events[1].duration == Some(Hours::from_minutes(120)));
+
+	// Unknown time zones are used as written.
+	assert!(events[2].date == Date::new(2024, zzp::gregorian::January, 31).unwrap());
+	assert!(events[2].duration == Some(Hours::from_minutes(90)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_date() {
+	use assert2::assert;
+
+	let events = parse_test_events(concat!(
+		"BEGIN:VEVENT\r\n",
+		"DTSTART;VALUE=DATE:20240131\r\n",
+		"DTEND;VALUE=DATE:20240201\r\n",
+		"SUMMARY:Holiday\r\n",
+		"RRULE:FREQ=YEARLY\r\n",
+		"END:VEVENT\r\n",
+		"BEGIN:VEVENT\r\n",
+		"DTSTART:20240131T100000\r\n",
+		"DTEND:20240131T113000\r\n",
+		"END:VEVENT\r\n",
+	), TimeZone::fixed(jiff::tz::offset(-8)));
+	assert!(events.len() == 2);
+	assert!(events[0].date == Date::new(2024, zzp::gregorian::January, 31).unwrap());
+	assert!(events[0].duration.is_none());
+	assert!(events[0].recurring);
+
+	// Floating times are the same in every time zone.
+	assert!(events[1].date == Date::new(2024, zzp::gregorian::January, 31).unwrap());
+	assert!(events[1].duration == Some(Hours::from_minutes(90)));
+	assert!(!events[1].recurring);
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_folded_lines() {
+	use assert2::assert;
+
+	let events = parse_test_events(concat!(
+		"BEGIN:VEVENT\r\n",
+		"DTSTART;VALUE=DATE:20240131\r\n",
+		"SUMMARY:A long\r\n",
+		"  title, with\\, escapes\n",
+		"\tand a tab\r\n",
+		"CATEGORIES:work,cust\r\n",
+		" omer\r\n",
+		"END:VEVENT\r\n",
+	), TimeZone::UTC);
+	assert!(events.len() == 1);
+	assert!(events[0].summary == "A long title, with, escapesand a tab");
+	assert!(events[0].categories == ["work", "customer"]);
+}
@@ -0,0 +1,119 @@
+//! The Dutch declaration of intra-community supplies (opgaaf ICP).
+
+use std::collections::BTreeMap;
+use zzp::grootboek::{Cents, Transaction};
+
+use crate::json::Json;
+use crate::revenue::is_revenue_account;
+use crate::{CustomerConfig, ZzpConfig};
+
+/// The declaration of intra-community supplies for a period, computed from the grootboek.
+#[derive(Debug, Clone)]
+pub struct IcpDeclaration {
+	/// The supplies per VAT number, sorted by country code and VAT number.
+	pub lines: Vec<IcpLine>,
+}
+
+/// The supplies to a single VAT number.
+#[derive(Debug, Clone)]
+pub struct IcpLine {
+	/// The country code of the VAT number, like `DE`.
+	pub country_code: String,
+
+	/// The VAT number without the country code.
+	pub vat_number: String,
+
+	/// The name of the customer with this VAT number, if known.
+	pub customer: Option<String>,
+
+	/// The exact revenue of the supplies.
+	pub amount: Cents,
+}
+
+impl IcpDeclaration {
+	/// Compute the declaration from the transactions of a period.
+	///
+	/// The supplies are the revenue mutations of transactions with the ICP tag,
+	/// which is added when generating invoices for intra-EU customers.
+	/// The tag value is the VAT number of the customer.
+	pub fn compute<'a>(config: &ZzpConfig, customers: &[CustomerConfig], transactions: impl IntoIterator<Item = &'a Transaction<'a>>) -> Self {
+		let icp_tag = config.grootboek.icp_tag.as_deref().unwrap_or("icp");
+
+		let mut totals: BTreeMap<(String, String), Cents> = BTreeMap::new();
+		let mut names = BTreeMap::new();
+		for transaction in transactions {
+			let vat_number = match transaction.tags.iter().find(|x| x.label == icp_tag) {
+				Some(tag) => tag.value,
+				None => continue,
+			};
+			let customer = customers.iter()
+				.map(|x| &x.customer)
+				.find(|x| x.vat_number.as_deref().map(normalize) == Some(normalize(vat_number)));
+			let key = split_vat_number(vat_number, customer.and_then(|x| x.country_code.as_deref()));
+			if let Some(customer) = customer {
+				names.insert(key.clone(), customer.name.clone());
+			}
+
+			let revenue: Cents = transaction.mutations.iter()
+				.filter(|x| is_revenue_account(config, x.account))
+				.map(|x| x.amount)
+				.sum();
+			*totals.entry(key).or_insert(Cents(0)) -= revenue;
+		}
+
+		let lines = totals.into_iter()
+			.map(|(key, amount)| IcpLine {
+				customer: names.get(&key).cloned(),
+				country_code: key.0,
+				vat_number: key.1,
+				amount,
+			})
+			.collect();
+		Self { lines }
+	}
+
+	/// The total of all supplies.
+	pub fn total(&self) -> Cents {
+		self.lines.iter().map(|x| x.amount).sum()
+	}
+
+	/// Get the declaration as JSON with exact amounts.
+	pub fn to_json(&self, period: &str) -> Json {
+		Json::object([
+			("period", period.into()),
+			("lines", Json::array(self.lines.iter().map(|line| Json::object([
+				("country_code", line.country_code.as_str().into()),
+				("vat_number", line.vat_number.as_str().into()),
+				("customer", line.customer.as_deref().into()),
+				("amount", line.amount.into()),
+			])))),
+			("total", self.total().into()),
+		])
+	}
+}
+
+/// Remove spaces and dots from a VAT number and convert it to uppercase.
+fn normalize(vat_number: &str) -> String {
+	vat_number.chars()
+		.filter(|x| !x.is_whitespace() && *x != '.')
+		.map(|x| x.to_ascii_uppercase())
+		.collect()
+}
+
+/// Split a VAT number in the country code and the rest of the number.
+///
+/// If the VAT number does not start with a country code, the country code of the customer is used.
+/// Greece uses `EL` as country code for VAT numbers instead of `GR`.
+fn split_vat_number(vat_number: &str, country_code: Option<&str>) -> (String, String) {
+	let vat_number = normalize(vat_number);
+	if vat_number.len() > 2 && vat_number.chars().take(2).all(|x| x.is_ascii_alphabetic()) {
+		let (country_code, number) = vat_number.split_at(2);
+		return (country_code.to_string(), number.to_string());
+	}
+	let country_code = match country_code.map(|x| x.to_ascii_uppercase()) {
+		Some(x) if x == "GR" => "EL".to_string(),
+		Some(x) => x,
+		None => String::new(),
+	};
+	(country_code, vat_number)
+}
@@ -0,0 +1,195 @@
+//! Income statement (winst- en verliesrekening) from the grootboek.
+
+use pdf_writer::{A4, BoxPosition, PdfWriter, Margins, mm, pt, MM_PER_PT};
+use zzp::grootboek::{Cents, Transaction};
+
+use crate::grootboek::{csv_field, format_decimal};
+use crate::json::Json;
+use crate::{AccountKind, ZzpConfig};
+
+/// The income statement of a period.
+#[derive(Debug, Clone)]
+pub struct IncomeStatement<'a> {
+	/// The revenue per category of the chart of accounts.
+	pub revenue: Vec<IncomeStatementLine<'a>>,
+
+	/// The expenses per category of the chart of accounts.
+	pub expenses: Vec<IncomeStatementLine<'a>>,
+}
+
+/// A category of revenue or expenses.
+#[derive(Debug, Clone)]
+pub struct IncomeStatementLine<'a> {
+	/// The name of the category.
+	pub name: &'a str,
+
+	/// The total amount, positive for revenue and expenses alike.
+	pub amount: Cents,
+}
+
+impl<'a> IncomeStatement<'a> {
+	/// Compute the income statement from the transactions of a period.
+	///
+	/// Each mutation is counted for the most specific entry of the chart of accounts that matches its account.
+	/// Mutations on accounts that are not revenue or expense accounts are ignored.
+	pub fn compute<'b>(config: &'a ZzpConfig, transactions: impl IntoIterator<Item = &'b Transaction<'b>>) -> Result<Self, String> {
+		// Accounts with the same name are combined into one category.
+		let categories = |kind| {
+			let mut lines: Vec<IncomeStatementLine> = Vec::new();
+			for account in config.account.iter().filter(|x| x.kind == kind) {
+				let name = account.name.as_deref().unwrap_or(&account.account);
+				if !lines.iter().any(|x| x.name == name) {
+					lines.push(IncomeStatementLine { name, amount: Cents(0) });
+				}
+			}
+			lines
+		};
+		let mut revenue = categories(AccountKind::Revenue);
+		let mut expenses = categories(AccountKind::Expense);
+		if revenue.is_empty() && expenses.is_empty() {
+			return Err("no revenue or expense accounts in the chart of accounts, add them as [[Account]] to zzp.toml".into());
+		}
+
+		for transaction in transactions {
+			for mutation in &transaction.mutations {
+				let account = match config.find_account(mutation.account) {
+					Some(x) => x,
+					None => continue,
+				};
+				let name = account.name.as_deref().unwrap_or(&account.account);
+				match account.kind {
+					AccountKind::Revenue => revenue.iter_mut().find(|x| x.name == name).unwrap().amount -= mutation.amount,
+					AccountKind::Expense => expenses.iter_mut().find(|x| x.name == name).unwrap().amount += mutation.amount,
+					_ => (),
+				}
+			}
+		}
+
+		Ok(Self { revenue, expenses })
+	}
+
+	/// The total revenue.
+	pub fn total_revenue(&self) -> Cents {
+		self.revenue.iter().map(|x| x.amount).sum()
+	}
+
+	/// The total expenses.
+	pub fn total_expenses(&self) -> Cents {
+		self.expenses.iter().map(|x| x.amount).sum()
+	}
+
+	/// The net result: the profit, or the loss if negative.
+	pub fn net_result(&self) -> Cents {
+		self.total_revenue() - self.total_expenses()
+	}
+
+	/// Get the income statement as JSON.
+	pub fn to_json(&self, period: &str) -> Json {
+		let lines = |lines: &[IncomeStatementLine]| Json::array(lines.iter().map(|line| Json::object([
+			("name", line.name.into()),
+			("amount", line.amount.into()),
+		])));
+		Json::object([
+			("period", period.into()),
+			("revenue", lines(&self.revenue)),
+			("expenses", lines(&self.expenses)),
+			("total_revenue", self.total_revenue().into()),
+			("total_expenses", self.total_expenses().into()),
+			("net_result", self.net_result().into()),
+		])
+	}
+
+	/// Write the income statement as CSV with a section, category and amount column.
+	pub fn write_csv(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+		writeln!(out, "section,category,amount")?;
+		for line in &self.revenue {
+			writeln!(out, "revenue,{},{}", csv_field(line.name), format_decimal(line.amount))?;
+		}
+		for line in &self.expenses {
+			writeln!(out, "expenses,{},{}", csv_field(line.name), format_decimal(line.amount))?;
+		}
+		writeln!(out, "total,revenue,{}", format_decimal(self.total_revenue()))?;
+		writeln!(out, "total,expenses,{}", format_decimal(self.total_expenses()))?;
+		writeln!(out, "total,net result,{}", format_decimal(self.net_result()))?;
+		Ok(())
+	}
+
+	/// Render the income statement as PDF document.
+	///
+	/// The `period` is shown under the title, like `2024` or `2024-01-01 - 2024-06-30`.
+	pub fn make_pdf<W>(&self, stream: W, config: &ZzpConfig, period: &str) -> Result<(), String>
+	where
+		W: std::io::Write + 'static,
+	{
+		let mut writer = PdfWriter::new(stream)?;
+		let currency = config.currency();
+		let money = |amount: Cents| currency.format(amount).replacen('-', "\u{2212}", 1);
+
+		let font_size = pt(*config.invoice.font_size);
+		let font_family = config.invoice.font_family();
+		let basic = pdf_writer::TextStyle {
+			font: pdf_writer::FontSpec::plain(&font_family, font_size),
+			align: pdf_writer::TextAlign::Left,
+			justify: false,
+			line_height: 1.0,
+		};
+		let basic_right = pdf_writer::TextStyle {
+			align: pdf_writer::TextAlign::Right,
+			.. basic.clone()
+		};
+		let bold = |font_size| pdf_writer::TextStyle {
+			font: pdf_writer::FontSpec::bold(&font_family, font_size),
+			align: pdf_writer::TextAlign::Left,
+			justify: false,
+			line_height: 0.8,
+		};
+		let bold_right = pdf_writer::TextStyle {
+			align: pdf_writer::TextAlign::Right,
+			.. bold(font_size)
+		};
+
+		let page = writer.page(A4, Margins::vh(mm(30.0), mm(20.0)))?;
+		let vskip = font_size * 1.5 * MM_PER_PT;
+
+		let title = page.draw_text_box("Income statement", &bold(font_size * 2.8), BoxPosition::at_xy(mm(20.0), mm(30.0)), None)?;
+		let mut y = mm(title.logical.max.y) + vskip;
+		let subtitle = page.draw_text_box(&format!("{}\n{}", config.company.name, period), &basic, BoxPosition::at_xy(mm(20.0), y), None)?;
+		y = mm(subtitle.logical.max.y) + vskip * 2.0;
+
+		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width());
+		table.position(BoxPosition::at_xy(mm(20.0), y));
+		table.cell_padding(Margins::vh(font_size * 0.25 * MM_PER_PT, font_size * 0.5 * MM_PER_PT));
+		table.add_column(true, None);
+		table.add_column(false, None);
+
+		let mut borders = Vec::new();
+		let mut rows = 0;
+		for (title, lines, total) in [("Revenue", &self.revenue, self.total_revenue()), ("Expenses", &self.expenses, self.total_expenses())] {
+			table.add_cell(title, &bold(font_size))?;
+			table.add_cell("", &basic)?;
+			for line in lines {
+				table.add_cell(line.name, &basic)?;
+				table.add_cell(&money(line.amount), &basic_right)?;
+			}
+			table.add_cell(&format!("Total {}", title.to_lowercase()), &bold(font_size))?;
+			table.add_cell(&money(total), &bold_right)?;
+			rows += lines.len() + 2;
+			borders.push(rows - 1);
+			table.add_cell("", &basic)?;
+			table.add_cell("", &basic)?;
+			rows += 1;
+		}
+		table.add_cell("Net result", &bold(font_size))?;
+		table.add_cell(&money(self.net_result()), &bold_right)?;
+		borders.push(rows);
+
+		let table = table.build();
+		table.draw(&page);
+		for row in borders {
+			table.draw_horizontal_border(&page, row, .., pt(0.5));
+		}
+		page.emit(&writer)?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,121 @@
+//! The figures for the Dutch income tax return (aangifte inkomstenbelasting).
+
+use zzp::grootboek::{Cents, Transaction};
+
+use crate::json::Json;
+use crate::ZzpConfig;
+
+/// The figures for the income tax return of a period, computed from the grootboek.
+#[derive(Debug, Clone)]
+pub struct IncomeTax<'a> {
+	/// The fields of the tax form with their amounts.
+	pub fields: Vec<IncomeTaxLine<'a>>,
+}
+
+/// A field of the income tax return with the exact amount.
+#[derive(Debug, Clone)]
+pub struct IncomeTaxLine<'a> {
+	/// The name of the field, like `omzet`.
+	pub name: &'a str,
+
+	/// The description of the field on the tax form.
+	pub description: &'a str,
+
+	/// If the field is revenue or a correction that adds to the profit, instead of a cost.
+	pub credit: bool,
+
+	/// The amount, positive for revenue and costs alike.
+	pub amount: Cents,
+}
+
+impl<'a> IncomeTax<'a> {
+	/// Compute the figures for the income tax return from the transactions of a period.
+	///
+	/// Each mutation is counted for the field with the most specific account that matches it.
+	pub fn compute<'b>(config: &'a ZzpConfig, transactions: impl IntoIterator<Item = &'b Transaction<'b>>) -> Result<Self, String> {
+		let tax_config = config.income_tax.as_ref()
+			.ok_or("missing IncomeTax section in zzp.toml")?;
+		if tax_config.fields.is_empty() {
+			return Err("no fields in the IncomeTax section of zzp.toml, add them as [[IncomeTax.Field]]".into());
+		}
+
+		let mut fields: Vec<_> = tax_config.fields.iter()
+			.map(|field| {
+				let known = known_field(&field.name);
+				IncomeTaxLine {
+					name: &field.name,
+					description: field.description.as_deref()
+						.or(known.map(|(description, _)| description))
+						.unwrap_or(""),
+					credit: field.credit
+						.or(known.map(|(_, credit)| credit))
+						.unwrap_or(false),
+					amount: Cents(0),
+				}
+			})
+			.collect();
+
+		for mutation in transactions.into_iter().flat_map(|x| &x.mutations) {
+			// Count the mutation for the field with the most specific matching account.
+			let field = tax_config.fields.iter()
+				.enumerate()
+				.flat_map(|(i, field)| field.accounts.iter().map(move |account| (i, account)))
+				.filter(|(_, account)| mutation.account.matches_prefix(account))
+				.max_by_key(|(_, account)| account.trim_end_matches('/').len());
+			if let Some((i, _)) = field {
+				let line = &mut fields[i];
+				if line.credit {
+					line.amount -= mutation.amount;
+				} else {
+					line.amount += mutation.amount;
+				}
+			}
+		}
+
+		Ok(Self { fields })
+	}
+
+	/// The profit: the revenue and corrections minus the costs.
+	pub fn profit(&self) -> Cents {
+		self.fields.iter()
+			.map(|x| if x.credit { x.amount } else { -x.amount })
+			.sum()
+	}
+
+	/// Get the figures as JSON with exact amounts.
+	pub fn to_json(&self, period: &str) -> Json {
+		Json::object([
+			("period", period.into()),
+			("fields", Json::array(self.fields.iter().map(|line| Json::object([
+				("name", line.name.into()),
+				("description", line.description.into()),
+				("credit", line.credit.into()),
+				("amount", line.amount.into()),
+			])))),
+			("profit", self.profit().into()),
+		])
+	}
+}
+
+/// Get the description and kind of a known field of the income tax return.
+///
+/// Returns the description on the tax form, and `true` if the field is revenue or a correction that adds to the profit.
+pub fn known_field(name: &str) -> Option<(&'static str, bool)> {
+	match name {
+		"omzet" => Some(("Netto-omzet", true)),
+		"overige-opbrengsten" => Some(("Overige opbrengsten", true)),
+		"inkoop" => Some(("Inkoopwaarde van de omzet", false)),
+		"uitbesteed-werk" => Some(("Kosten van uitbesteed werk en andere externe kosten", false)),
+		"personeelskosten" => Some(("Personeelskosten", false)),
+		"afschrijvingen" => Some(("Afschrijvingen op immateriële en materiële vaste activa", false)),
+		"autokosten" => Some(("Auto- en transportkosten", false)),
+		"huisvestingskosten" => Some(("Huisvestingskosten", false)),
+		"onderhoud" => Some(("Onderhoud en energiekosten van overige materiële vaste activa", false)),
+		"verkoopkosten" => Some(("Verkoopkosten", false)),
+		"andere-kosten" => Some(("Andere kosten", false)),
+		"financiele-lasten" => Some(("Financiële baten en lasten", false)),
+		"privegebruik-auto" => Some(("Correctie privégebruik auto", true)),
+		"prive-correcties" => Some(("Overige privé-correcties", true)),
+		_ => None,
+	}
+}
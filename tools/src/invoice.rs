@@ -1,28 +1,47 @@
+use dynfmt::{Format, SimpleCurlyFormat};
 use ordered_float::NotNan;
 use std::collections::BTreeMap;
-use zzp::gregorian::{Date, Month};
+use zzp::gregorian::{Date, Month, YearMonth};
+use zzp::grootboek::{Cents, RoundingMode};
 
-use pdf_writer::{A4, BoxPosition, PdfWriter, Margins, mm, pt, MM_PER_PT};
+use pdf_writer::{A4, BoxPosition, PdfWriter, Margins, TextStyle, mm, pt, MM_PER_PT};
 
-use crate::{ZzpConfig, Customer, DateLocalization};
+use crate::{ZzpConfig, Customer, DateLocalization, FontRole, QuantityFormat, Tax, VatRounding};
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct InvoiceFile {
 	#[serde(rename = "Entry")]
 	pub entries: Vec<InvoiceEntry>,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct InvoiceEntry {
+	#[schemars(schema_with = "crate::schema::date")]
 	#[serde(deserialize_with = "deserialize_date")]
 	pub date: Date,
 	pub description: String,
+	/// The quantity of the entry.
+	///
+	/// May be negative for correction lines, like crediting hours that were invoiced too much on a previous invoice.
+	#[serde(default = "default_quantity")]
+	#[schemars(with = "f64")]
 	pub quantity: NotNan<f64>,
+	#[serde(default)]
 	pub unit: String,
+	/// The price per unit without VAT, which may also be negative.
+	#[schemars(with = "f64")]
 	pub unit_price: NotNan<f64>,
+	#[schemars(with = "f64")]
 	pub vat_percentage: NotNan<f64>,
+	/// The section of the invoice to show the entry in.
+	#[serde(default)]
+	pub section: Option<String>,
+}
+
+fn default_quantity() -> NotNan<f64> {
+	NotNan::new(1.0).unwrap()
 }
 
 fn deserialize_date<'de, D: serde::de::Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
@@ -56,8 +75,240 @@ impl InvoiceEntry {
 	pub fn total_inc_vat(&self) -> NotNan<f64> {
 		self.quantity * self.unit_price * (self.vat_percentage * 0.01 + 1.0)
 	}
+
+	/// Create a fixed-price entry with a quantity of 1 and no unit.
+	pub fn fixed(date: Date, description: impl Into<String>, price: NotNan<f64>, vat_percentage: NotNan<f64>) -> Self {
+		Self {
+			date,
+			description: description.into(),
+			quantity: default_quantity(),
+			unit: String::new(),
+			unit_price: price,
+			vat_percentage,
+			section: None,
+		}
+	}
+
+	/// Check if the entry is measured in hours.
+	pub fn is_hours(&self, config: &ZzpConfig) -> bool {
+		self.unit == config.invoice_localization.hours
+	}
+
+	/// Format the quantity and unit for display.
+	///
+	/// Entries without a unit are shown without decimals if the quantity is a whole number.
+	/// Hours are shown according to the configured quantity format.
+	pub fn format_quantity(&self, config: &ZzpConfig) -> String {
+		if self.is_hours(config) && config.invoice.quantity_format == Some(QuantityFormat::HoursMinutes) {
+			format_hours_minutes(self.quantity)
+		} else if self.unit.is_empty() && self.quantity.fract() == 0.0 {
+			format!("{}", self.quantity)
+		} else {
+			format!("{:.02} {}", self.quantity, self.unit)
+		}
+	}
+
+	/// Get the total price of the entry without VAT, rounded to whole cents.
+	///
+	/// The total is computed from the quantity and the unit price rounded to [`DECIMALS`] decimals,
+	/// so it is exactly the product of the values written in e-invoices.
+	pub fn total_ex_vat_cents(&self, rounding: RoundingMode) -> Cents {
+		let total = fixed_point(self.quantity) * fixed_point(self.unit_price);
+		Cents(rounding.div(total, 10_i64.pow(2 * DECIMALS - 2)) as i32)
+	}
+}
+
+/// The number of decimals of quantities and unit prices used to compute the total of an entry.
+pub const DECIMALS: u32 = 4;
+
+/// Convert a quantity or unit price to a fixed point number with [`DECIMALS`] decimals.
+fn fixed_point(value: NotNan<f64>) -> i64 {
+	(value.into_inner() * 10_f64.powi(DECIMALS as i32)).round() as i64
+}
+
+/// Format a quantity or unit price with at most [`DECIMALS`] decimals.
+///
+/// Trailing zeros are removed, but at least `min_decimals` decimals are kept.
+pub fn format_fixed_point(value: NotNan<f64>, min_decimals: usize) -> String {
+	let value = fixed_point(value);
+	let sign = if value < 0 { "-" } else { "" };
+	let value = value.unsigned_abs();
+	let scale = 10_u64.pow(DECIMALS);
+	let fraction = format!("{:0width$}", value % scale, width = DECIMALS as usize);
+	let fraction = fraction.trim_end_matches('0');
+	let fraction = format!("{:0<width$}", fraction, width = min_decimals);
+	if fraction.is_empty() {
+		format!("{}{}", sign, value / scale)
+	} else {
+		format!("{}{}.{}", sign, value / scale, fraction)
+	}
+}
+
+/// The default notice on invoices of companies that use the small business scheme (KOR).
+pub const DEFAULT_KOR_NOTICE: &str = "Vrijgesteld van btw op grond van de kleineondernemersregeling.";
+
+/// Remove the VAT from invoice entries if the company uses the small business scheme (KOR).
+pub fn apply_small_business_scheme(config: &ZzpConfig, entries: &mut [InvoiceEntry]) {
+	if config.tax.kor {
+		for entry in entries {
+			entry.vat_percentage = NotNan::new(0.0).unwrap();
+		}
+	}
+}
+
+/// The default notice on invoices for intra-EU customers.
+pub const DEFAULT_REVERSE_CHARGE_NOTICE: &str = "Btw verlegd naar de afnemer (intracommunautaire prestatie).";
+
+/// Check if an invoice to a customer is an intra-EU supply with the VAT reverse-charged to the customer.
+///
+/// Companies using the small business scheme (KOR) never reverse-charge VAT.
+pub fn is_intra_eu_supply(config: &ZzpConfig, recipient: &Customer) -> bool {
+	!config.tax.kor && recipient.is_intra_eu(config.company_country_code())
+}
+
+/// Apply the intra-EU 0% VAT rate to invoice entries if the customer is a business in another EU country.
+pub fn apply_intra_eu_rate(config: &ZzpConfig, recipient: &Customer, entries: &mut [InvoiceEntry]) {
+	if is_intra_eu_supply(config, recipient) {
+		for entry in entries {
+			entry.vat_percentage = NotNan::new(0.0).unwrap();
+		}
+	}
 }
 
+impl crate::RecurringInvoice {
+	/// Check if the recurring invoice should be invoiced for a month.
+	pub fn is_active(&self, month: YearMonth) -> bool {
+		if let Some(start_date) = self.start_date {
+			if start_date > month.last_day() {
+				return false;
+			}
+		}
+		if let Some(end_date) = self.end_date {
+			if end_date < month.first_day() {
+				return false;
+			}
+		}
+		true
+	}
+
+	/// Get the key to recognize the invoice for a month in the invoice register.
+	pub fn key(&self, month: YearMonth) -> String {
+		format!("{}/{}", self.name, month)
+	}
+
+	/// Create the invoice entry for a month.
+	pub fn make_entry(&self, config: &ZzpConfig, month: YearMonth) -> Result<InvoiceEntry, String> {
+		let args: BTreeMap<_, _> = [
+			("year", month.year().to_string()),
+			("month", format!("{:02}", month.month().to_number())),
+			("month_name", format_month(month.month(), &config.date_localization).to_string()),
+		].into_iter().collect();
+		let description = SimpleCurlyFormat.format(&self.description, &args)
+			.map_err(|e| format!("failed to expand description of recurring invoice {}: {}", self.name, e))?;
+		Ok(InvoiceEntry::fixed(month.first_day(), description, self.amount, self.vat.unwrap_or(config.tax.vat)))
+	}
+}
+
+/// The value of a discount.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DiscountValue {
+	/// A percentage of the discounted price.
+	Percentage(NotNan<f64>),
+
+	/// A fixed amount in money units (euro, yen, dollar, ...).
+	Amount(NotNan<f64>),
+}
+
+impl DiscountValue {
+	/// Create the invoice entries for a discount on the given entries.
+	///
+	/// The created entries have a negative price.
+	/// A percentage discount creates one entry per VAT percentage of the discounted entries,
+	/// so that the VAT is reduced accordingly.
+	/// VAT percentages with a negative total (because of correction lines) do not get a discount.
+	/// A fixed amount discount creates a single entry with the given VAT percentage.
+	pub fn make_entries(self, description: &str, date: Date, entries: &[InvoiceEntry], vat_percentage: NotNan<f64>) -> Vec<InvoiceEntry> {
+		match self {
+			Self::Percentage(percentage) => {
+				let mut totals: BTreeMap<NotNan<f64>, NotNan<f64>> = BTreeMap::new();
+				for entry in entries {
+					*totals.entry(entry.vat_percentage).or_default() += entry.total_ex_vat();
+				}
+				totals.into_iter()
+					.filter(|(_, total)| total.into_inner() > 0.0)
+					.map(|(vat_percentage, total)| {
+						InvoiceEntry::fixed(date, format!("{} ({}%)", description, percentage), -(total * percentage / 100.0), vat_percentage)
+					})
+					.collect()
+			},
+			Self::Amount(amount) => vec![InvoiceEntry::fixed(date, description, -amount, vat_percentage)],
+		}
+	}
+}
+
+impl std::str::FromStr for DiscountValue {
+	type Err = String;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		let parse = |data: &str| -> Result<NotNan<f64>, String> {
+			let value: f64 = data.trim().parse().map_err(|_| format!("invalid discount: {:?}", data))?;
+			NotNan::new(value).map_err(|_| format!("invalid discount: {:?}", data))
+		};
+		if let Some(percentage) = data.strip_suffix('%') {
+			Ok(Self::Percentage(parse(percentage)?))
+		} else {
+			Ok(Self::Amount(parse(data)?))
+		}
+	}
+}
+
+/// The totals of an invoice in whole cents.
+#[derive(Debug, Clone)]
+pub struct InvoiceTotals {
+	/// The total amount without VAT.
+	pub net: Cents,
+
+	/// The total amount without VAT per VAT percentage.
+	pub taxable: BTreeMap<NotNan<f64>, Cents>,
+
+	/// The VAT per VAT percentage.
+	pub vat: BTreeMap<NotNan<f64>, Cents>,
+}
+
+impl InvoiceTotals {
+	/// Compute the totals for a list of invoice entries.
+	///
+	/// Each entry is rounded to whole cents with the rounding mode from the tax configuration.
+	/// The VAT is computed over the total per VAT percentage, or for each entry if configured.
+	pub fn compute(entries: &[InvoiceEntry], tax: &Tax) -> Self {
+		let rounding = tax.rounding_mode;
+		let lines = entries.iter().map(|entry| (entry.total_ex_vat_cents(rounding), entry.vat_percentage.into_inner()));
+		let per_line = tax.vat_rounding.unwrap_or_default() == VatRounding::PerLine;
+		let totals = zzp::grootboek::vat_totals(lines, per_line, rounding);
+
+		// The percentages come from the entries, so they are not NaN.
+		let percentage = |x: &zzp::grootboek::VatTotal| NotNan::new(x.percentage).unwrap();
+		Self {
+			net: totals.iter().map(|x| x.net).sum(),
+			taxable: totals.iter().map(|x| (percentage(x), x.net)).collect(),
+			vat: totals.iter().map(|x| (percentage(x), x.vat)).collect(),
+		}
+	}
+
+	/// Get the total VAT over all VAT percentages.
+	pub fn total_vat(&self) -> Cents {
+		self.vat.values().sum()
+	}
+
+	/// Get the total amount including VAT.
+	pub fn total(&self) -> Cents {
+		self.net + self.total_vat()
+	}
+}
+
+/// Render a single invoice.
+///
+/// Use an [`InvoiceRenderer`] to render multiple invoices.
 pub fn make_invoice<W>(
 	stream: W,
 	config: &ZzpConfig,
@@ -65,192 +316,595 @@ pub fn make_invoice<W>(
 	invoice_number: &str,
 	invoice_date: Date,
 	entries: &[InvoiceEntry],
+	extras: &InvoiceExtras,
 ) -> Result<(), String>
 where
 	W: std::io::Write + 'static,
 {
-	let mut writer = PdfWriter::new(stream)?;
-	let lang = &config.invoice_localization;
-
-	let font_size = pt(*config.invoice.font_size);
-
-	let plain = |font_size| pdf_writer::TextStyle {
-		font: pdf_writer::FontSpec::plain(&config.invoice.font, font_size),
-		align: pdf_writer::TextAlign::Left,
-		justify: false,
-		line_height: 1.0,
-	};
-	let basic = plain(font_size);
-	let basic_right = pdf_writer::TextStyle {
-		align: pdf_writer::TextAlign::Right,
-		.. basic.clone()
-	};
-
-	let bold = |font_size| pdf_writer::TextStyle {
-		font: pdf_writer::FontSpec::bold(&config.invoice.font, font_size),
-		align: pdf_writer::TextAlign::Left,
-		justify: false,
-		line_height: 0.8,
-	};
-
-	let page = writer.page(A4, Margins::vh(mm(30.0), mm(20.0)))?;
-
-	// Add reciepient name and address.
+	InvoiceRenderer::new(config).render_invoice(stream, config, recipient, invoice_number, invoice_date, entries, extras)
+}
+
+/// Optional extras for an invoice.
+#[derive(Debug, Default)]
+pub struct InvoiceExtras<'a> {
+	/// Extra lines to show above the payment footer.
+	pub notes: &'a [String],
+
+	/// Mark the invoice as draft with a watermark.
+	pub draft: bool,
+
+	/// The purchase order number or other reference of the customer.
+	pub reference: Option<&'a str>,
+}
+
+/// The document specific parts of the invoice layout.
+pub(crate) struct DocumentDetails<'a> {
+	/// The title of the document.
+	pub title: &'a str,
+
+	/// The number of the document.
+	pub number: &'a str,
+
+	/// The date of the document.
+	pub date: Date,
+
+	/// Labelled details to show below the title, like the document number and date.
+	pub details: Vec<(&'a str, String)>,
+
+	/// Extra lines to show above the footer.
+	pub notes: &'a [String],
+
+	/// The footer of the document.
+	pub footer: &'a str,
+
+	/// Text to show in large letters behind the content, like "CONCEPT" for drafts.
+	pub watermark: Option<&'a str>,
+}
+
+/// Renders invoices and quotes with the invoice layout.
+///
+/// The font families and text styles are computed once from the configuration,
+/// so a renderer can be reused to render many documents in one run, like when invoicing all customers.
+///
+/// Customer overrides do not change the fonts,
+/// so a renderer created from the main configuration can render documents for any customer.
+pub struct InvoiceRenderer {
+	/// The font size in points.
+	font_size: f64,
+	styles: Styles,
+}
+
+impl std::fmt::Debug for InvoiceRenderer {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("InvoiceRenderer")
+			.field("font_size", &self.font_size)
+			.finish_non_exhaustive()
+	}
+}
+
+/// The text styles of the invoice layout.
+struct Styles {
+	basic: TextStyle,
+	basic_right: TextStyle,
+	bold_right: TextStyle,
+	section: TextStyle,
+	section_right: TextStyle,
+	title: TextStyle,
+	footer: TextStyle,
+	watermark: TextStyle,
+}
+
+impl InvoiceRenderer {
+	/// Create a renderer with the fonts from the configuration.
+	pub fn new(config: &ZzpConfig) -> Self {
+		let font_size = pt(*config.invoice.font_size);
+		// Characters missing from the main font are looked up in the fallback fonts.
+		let font_family = config.font_family(FontRole::Body);
+		let heading_font_family = config.font_family(FontRole::Heading);
+
+		let basic = TextStyle {
+			font: pdf_writer::FontSpec::plain(&font_family, font_size),
+			align: pdf_writer::TextAlign::Left,
+			justify: false,
+			line_height: 1.0,
+		};
+		let bold = |font_size| TextStyle {
+			font: pdf_writer::FontSpec::bold(&heading_font_family, font_size),
+			align: pdf_writer::TextAlign::Left,
+			justify: false,
+			line_height: 0.8,
+		};
+		let section = TextStyle {
+			font: pdf_writer::FontSpec::bold(&heading_font_family, font_size),
+			.. basic.clone()
+		};
+
+		let styles = Styles {
+			basic_right: TextStyle {
+				align: pdf_writer::TextAlign::Right,
+				.. basic.clone()
+			},
+			bold_right: TextStyle {
+				align: pdf_writer::TextAlign::Right,
+				.. bold(font_size)
+			},
+			section_right: TextStyle {
+				align: pdf_writer::TextAlign::Right,
+				.. section.clone()
+			},
+			title: TextStyle {
+				font: pdf_writer::FontSpec::bold(&config.font_family(FontRole::Title), font_size * 2.8),
+				.. bold(font_size * 2.8)
+			},
+			footer: TextStyle {
+				font: pdf_writer::FontSpec::plain(&config.font_family(FontRole::Footer), font_size),
+				.. basic.clone()
+			},
+			watermark: bold(font_size * 8.0),
+			section,
+			basic,
+		};
+
+		Self { font_size: *config.invoice.font_size, styles }
+	}
+
+	/// Render an invoice.
+	///
+	/// The `config` must have the customer overrides applied, for the localization and currency of the customer.
+	#[allow(clippy::too_many_arguments)]
+	pub fn render_invoice<W>(
+		&self,
+		stream: W,
+		config: &ZzpConfig,
+		recipient: &Customer,
+		invoice_number: &str,
+		invoice_date: Date,
+		entries: &[InvoiceEntry],
+		extras: &InvoiceExtras,
+	) -> Result<(), String>
+	where
+		W: std::io::Write + 'static,
 	{
-		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width() * 0.5);
-		table.position(BoxPosition::at_xy(mm(20.0), mm(42.0)));
-		table.cell_padding(Margins::tblr(mm(0.0), -font_size * 0.2 * MM_PER_PT, mm(0.0), mm(0.0)));
-
-		table.add_column(false, None);
-		table.add_column(false, None);
-
-		table.add_cell(&format!("{}:    ", &lang.to), &basic_right)?;
-		table.add_cell(&recipient.name, &basic)?;
-		for line in &recipient.address {
-			table.add_cell("", &basic_right)?;
-			table.add_cell(line, &basic)?;
+		let lang = &config.invoice_localization;
+		let mut details = vec![
+			(lang.invoice_number.as_str(), invoice_number.to_string()),
+			(lang.invoice_date.as_str(), format_date(invoice_date, &config.date_localization)),
+		];
+		if let Some(due_date) = &lang.due_date {
+			details.push((due_date.as_str(), format_date(crate::register::due_date(config, invoice_date), &config.date_localization)));
+		}
+		if let Some(reference) = extras.reference {
+			details.push((lang.reference(), reference.to_string()));
+		}
+		let document = DocumentDetails {
+			title: &lang.invoice,
+			number: invoice_number,
+			date: invoice_date,
+			details,
+			notes: extras.notes,
+			footer: &lang.footer,
+			watermark: extras.draft.then(|| lang.draft()),
+		};
+		self.render_document(stream, config, recipient, &document, entries)
+	}
+
+	/// Render a document with the invoice layout.
+	///
+	/// The document is converted to PDF/A if the configuration asks for it.
+	pub(crate) fn render_document<W>(
+		&self,
+		mut stream: W,
+		config: &ZzpConfig,
+		recipient: &Customer,
+		document: &DocumentDetails,
+		entries: &[InvoiceEntry],
+	) -> Result<(), String>
+	where
+		W: std::io::Write + 'static,
+	{
+		if !config.invoice.pdf_a {
+			return self.write_pdf(stream, config, recipient, document, entries);
 		}
 
-		let table = table.build();
-		table.draw(&page);
+		// Cairo can not write PDF/A, so the finished PDF is converted in memory.
+		let buffer = SharedBuffer::default();
+		self.write_pdf(buffer.clone(), config, recipient, document, entries)?;
+		let title = format!("{} {}", document.title, document.number);
+		let metadata = crate::pdfa::Metadata {
+			title: &title,
+			author: &config.company.name,
+			date: document.date,
+		};
+		let pdf = crate::pdfa::convert(&buffer.0.borrow(), &metadata)
+			.map_err(|e| format!("failed to convert {} to PDF/A: {}", title, e))?;
+		stream.write_all(&pdf)
+			.and_then(|()| stream.flush())
+			.map_err(|e| format!("failed to write PDF: {}", e))
 	}
 
-	let mut y;
-	let vskip = font_size * 1.5 * MM_PER_PT;
-	// Add sender name and address.
+	/// Write a document with the invoice layout as PDF.
+	fn write_pdf<W>(
+		&self,
+		stream: W,
+		config: &ZzpConfig,
+		recipient: &Customer,
+		document: &DocumentDetails,
+		entries: &[InvoiceEntry],
+	) -> Result<(), String>
+	where
+		W: std::io::Write + 'static,
 	{
-		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width() * 0.5);
-		table.position(BoxPosition::at(page.line_right()).anchor_right());
-		table.cell_padding(Margins::tblr(mm(0.0), -font_size * 0.2 * MM_PER_PT, mm(0.0), mm(0.0)));
-
-		table.add_column(false, None);
-		table.add_column(false, None);
-
-		table.add_cell(&format!("{}:    ", &lang.from), &basic_right)?;
-		table.add_cell(&config.company.name, &basic)?;
-		for line in &config.company.address {
-			table.add_cell("", &basic_right)?;
-			table.add_cell(line, &basic)?;
+		let mut writer = PdfWriter::new(stream)?;
+		let lang = &config.invoice_localization;
+		// Companies using the small business scheme (KOR) do not charge VAT at all.
+		let show_vat = !config.tax.kor;
+		let currency = config.currency();
+		let rounding = config.tax.rounding_mode;
+		// Negative amounts from discounts and correction lines use a real minus sign, since a hyphen is easily overlooked.
+		let money = |amount: Cents| {
+			currency.format(amount).replacen('-', "\u{2212}", 1)
+		};
+
+		let font_size = pt(self.font_size);
+		let styles = &self.styles;
+
+		// Draw the watermark first, so the rest of the content is drawn over it.
+		let new_page = |writer: &mut PdfWriter| -> Result<pdf_writer::Page, String> {
+			let page = writer.page(A4, Margins::vh(mm(30.0), mm(20.0)))?;
+			if let Some(watermark) = document.watermark {
+				let position = BoxPosition::at_xy(mm(20.0), mm(A4.height * 0.5)).anchor_middle_y();
+				page.draw_text_box(watermark, &styles.watermark, position, Some(page.text_width()))?;
+			}
+			Ok(page)
+		};
+
+		let page = new_page(&mut writer)?;
+		let text_width = page.text_width();
+
+		// Add reciepient name and address.
+		{
+			let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width() * 0.5);
+			table.position(BoxPosition::at_xy(mm(20.0), mm(42.0)));
+			table.cell_padding(Margins::tblr(mm(0.0), -font_size * 0.2 * MM_PER_PT, mm(0.0), mm(0.0)));
+
+			table.add_column(false, None);
+			table.add_column(false, None);
+
+			table.add_cell(&format!("{}:    ", &lang.to), &styles.basic_right)?;
+			table.add_cell(&recipient.name, &styles.basic)?;
+			for line in &recipient.address {
+				table.add_cell("", &styles.basic_right)?;
+				table.add_cell(line, &styles.basic)?;
+			}
+			if let Some(vat_number) = &recipient.vat_number {
+				table.add_cell("", &styles.basic_right)?;
+				table.add_cell(&format!("{}: {}", lang.vat_number.as_deref().unwrap_or("Btw-nummer"), vat_number), &styles.basic)?;
+			}
+
+			let table = table.build();
+			table.draw(&page);
 		}
 
-		table.add_cell("", &basic)?;
-		table.add_cell("", &basic)?;
-		for line in &config.company.contact {
-			table.add_cell(&format!("{}:    ", line.name), &basic_right)?;
-			table.add_cell(&line.value, &basic)?;
+		let mut y;
+		let vskip = font_size * 1.5 * MM_PER_PT;
+		// Add sender name and address.
+		{
+			let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width() * 0.5);
+			table.position(BoxPosition::at(page.line_right()).anchor_right());
+			table.cell_padding(Margins::tblr(mm(0.0), -font_size * 0.2 * MM_PER_PT, mm(0.0), mm(0.0)));
+
+			table.add_column(false, None);
+			table.add_column(false, None);
+
+			table.add_cell(&format!("{}:    ", &lang.from), &styles.basic_right)?;
+			table.add_cell(&config.company.name, &styles.basic)?;
+			for line in &config.company.address {
+				table.add_cell("", &styles.basic_right)?;
+				table.add_cell(line, &styles.basic)?;
+			}
+
+			table.add_cell("", &styles.basic)?;
+			table.add_cell("", &styles.basic)?;
+			for line in &config.company.contact {
+				table.add_cell(&format!("{}:    ", line.name), &styles.basic_right)?;
+				table.add_cell(&line.value, &styles.basic)?;
+			}
+
+			table.add_cell("", &styles.basic)?;
+			table.add_cell("", &styles.basic)?;
+			for line in &config.company.legal {
+				table.add_cell(&format!("{}:    ", line.name), &styles.basic_right)?;
+				table.add_cell(&line.value, &styles.basic)?;
+			}
+
+			table.add_cell("", &styles.basic)?;
+			table.add_cell("", &styles.basic)?;
+			for line in &config.company.payment {
+				table.add_cell(&format!("{}:    ", line.name), &styles.basic_right)?;
+				table.add_cell(&line.value, &styles.basic)?;
+			}
+
+			let table = table.build();
+			y = table.baseline(table.rows() - 1);
+			table.draw(&page);
 		}
 
-		table.add_cell("", &basic)?;
-		table.add_cell("", &basic)?;
-		for line in &config.company.legal {
-			table.add_cell(&format!("{}:    ", line.name), &basic_right)?;
-			table.add_cell(&line.value, &basic)?;
+		{
+			let title = page.draw_text_box(document.title, &styles.title, BoxPosition::at_xy(mm(20.0), y).anchor_baseline(), None)?;
+			y = mm(title.logical.max.y) + vskip;
+
+			let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width());
+			table.position(BoxPosition::at_xy(mm(20.0), y));
+			table.cell_padding(Margins::tblr(mm(0.0), -font_size * 0.2 * MM_PER_PT, mm(0.0), mm(0.0)));
+			table.add_column(false, None);
+			table.add_column(false, None);
+			for (label, value) in &document.details {
+				table.add_cell(&format!("{}:    ", label), &styles.basic_right)?;
+				table.add_cell(value, &styles.basic)?;
+			}
+			let table = table.build();
+			y += mm(table.size().height) + vskip;
+			table.draw(&page);
 		}
 
-		table.add_cell("", &basic)?;
-		table.add_cell("", &basic)?;
-		for line in &config.company.payment {
-			table.add_cell(&format!("{}:    ", line.name), &basic_right)?;
-			table.add_cell(&line.value, &basic)?;
+		// Consecutive entries with the same section are grouped under a header with a subtotal.
+		// Each row also tracks the running total, for the carry-over subtotals of multi-page invoices.
+		// The amounts are rounded the same way as the invoice totals, so the lines add up to the totals.
+		let mut rows = Vec::new();
+		let mut total_ex_vat = Cents(0);
+		for section in entries.chunk_by(|a, b| a.section == b.section) {
+			if let Some(title) = &section[0].section {
+				rows.push((EntryRow::Section(title), total_ex_vat));
+			}
+
+			let mut subtotal = Cents(0);
+			let mut subtotal_hours = NotNan::new(0.0).unwrap();
+			for entry in section {
+				let price = entry.total_ex_vat_cents(rounding);
+				subtotal += price;
+				if entry.is_hours(config) {
+					subtotal_hours += entry.quantity;
+				}
+				total_ex_vat += price;
+				rows.push((EntryRow::Entry(entry), total_ex_vat));
+			}
+
+			if section[0].section.is_some() {
+				rows.push((EntryRow::Subtotal { amount: subtotal, hours: subtotal_hours }, total_ex_vat));
+			}
 		}
 
-		let table = table.build();
-		y = table.baseline(table.rows() - 1);
-		table.draw(&page);
-	}
+		let empty_columns = if show_vat { 4 } else { 3 };
+
+		// Build the table with the given rows, optionally with the carry-over subtotals of the previous and current page.
+		let make_entries_table = |writer: &PdfWriter, rows: &[(EntryRow, Cents)], y, brought_forward: Option<Cents>, carried_forward: Option<Cents>| -> Result<pdf_writer::Table, String> {
+			let mut table = pdf_writer::TableBuilder::new(writer, text_width);
+			table.position(BoxPosition::at_xy(mm(20.0), y));
+			table.cell_padding(Margins::vh(font_size * 0.25 * MM_PER_PT, font_size * 0.5 * MM_PER_PT));
+			table.add_column(false, None);
+			table.add_column(true, None);
+			table.add_column(false, None);
+			table.add_column(false, None);
+			table.add_column(false, None);
+			if show_vat {
+				table.add_column(false, None);
+			}
+			table.add_cell(&lang.date, &styles.basic)?;
+			table.add_cell(&lang.description, &styles.basic)?;
+			table.add_cell(&lang.quantity, &styles.basic)?;
+			table.add_cell(&lang.entry_unit_price, &styles.basic)?;
+			table.add_cell(&lang.entry_total_price, &styles.basic)?;
+			if show_vat {
+				table.add_cell(&lang.vat, &styles.basic)?;
+			}
+
+			let add_carry_over = |table: &mut pdf_writer::TableBuilder, label: &str, amount: Cents| -> Result<(), String> {
+				table.add_cell("", &styles.basic)?;
+				table.add_cell(label, &styles.section)?;
+				table.add_cell("", &styles.basic)?;
+				table.add_cell("", &styles.basic)?;
+				table.add_cell(&money(amount), &styles.section_right)?;
+				if show_vat {
+					table.add_cell("", &styles.basic)?;
+				}
+				Ok(())
+			};
+
+			if let Some(amount) = brought_forward {
+				add_carry_over(&mut table, lang.brought_forward(), amount)?;
+			}
+
+			for (row, _) in rows {
+				match row {
+					EntryRow::Section(title) => {
+						table.add_cell("", &styles.basic)?;
+						table.add_cell(title, &styles.section)?;
+						for _ in 0..empty_columns {
+							table.add_cell("", &styles.basic)?;
+						}
+					},
+					EntryRow::Entry(entry) => {
+						table.add_cell(&format_date(entry.date, &config.date_localization), &styles.basic_right)?;
+						table.add_cell(&entry.description, &styles.basic)?;
+						let quantity = entry.format_quantity(config);
+						let quantity = match quantity.strip_prefix('-') {
+							Some(quantity) => format!("\u{2212}{}", quantity),
+							None => quantity,
+						};
+						table.add_cell(&quantity, &styles.basic_right)?;
+						table.add_cell(&money(Cents::from_f64(entry.unit_price.into_inner(), rounding)), &styles.basic_right)?;
+						table.add_cell(&money(entry.total_ex_vat_cents(rounding)), &styles.basic_right)?;
+						if show_vat {
+							table.add_cell(&format!("{}%", entry.vat_percentage), &styles.basic_right)?;
+						}
+					},
+					EntryRow::Subtotal { amount, hours } => {
+						table.add_cell("", &styles.basic)?;
+						table.add_cell(lang.subtotal.as_deref().unwrap_or("Subtotal"), &styles.section)?;
+						if *hours == 0.0 {
+							table.add_cell("", &styles.basic)?;
+						} else if config.invoice.quantity_format == Some(QuantityFormat::HoursMinutes) {
+							table.add_cell(&format_hours_minutes(*hours), &styles.section_right)?;
+						} else {
+							table.add_cell(&format!("{:.02} {}", hours, lang.hours), &styles.section_right)?;
+						}
+						table.add_cell("", &styles.basic)?;
+						table.add_cell(&money(*amount), &styles.section_right)?;
+						if show_vat {
+							table.add_cell("", &styles.basic)?;
+						}
+					},
+				}
+			}
+
+			if let Some(amount) = carried_forward {
+				add_carry_over(&mut table, lang.carried_forward(), amount)?;
+			}
+
+			Ok(table.build())
+		};
 
-	{
-		let title = page.draw_text_box(&lang.invoice, &bold(font_size * 2.8), BoxPosition::at_xy(mm(20.0), y).anchor_baseline(), None)?;
-		y = mm(title.logical.max.y) + vskip;
-
-		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width());
-		table.position(BoxPosition::at_xy(mm(20.0), y));
-		table.cell_padding(Margins::tblr(mm(0.0), -font_size * 0.2 * MM_PER_PT, mm(0.0), mm(0.0)));
-		table.add_column(false, None);
-		table.add_column(false, None);
-		table.add_cell(&format!("{}:    ", lang.invoice_number), &basic_right)?;
-		table.add_cell(invoice_number, &basic)?;
-		table.add_cell(&format!("{}:    ", lang.invoice_date), &basic_right)?;
-		table.add_cell(&format_date(invoice_date, &config.date_localization), &basic)?;
-		let table = table.build();
-		y += mm(table.size().height) + vskip;
-		table.draw(&page);
-	}
+		let totals = InvoiceTotals::compute(entries, &config.tax);
+		let make_totals_table = |writer: &PdfWriter, y| -> Result<pdf_writer::Table, String> {
+			let mut table = pdf_writer::TableBuilder::new(writer, text_width);
+			table.position(BoxPosition::at_xy(text_width + mm(20.0), y).anchor_right().anchor_middle_y());
+			table.cell_padding(Margins::vh(font_size * 0.25 * MM_PER_PT, font_size * 0.5 * MM_PER_PT));
+			table.add_column(false, None);
+			table.add_column(false, None);
+			if show_vat {
+				table.add_cell(&format!("{}:", lang.total_ex_vat), &styles.basic_right)?;
+				table.add_cell(&money(totals.net), &styles.basic_right)?;
+				for (percentage, total) in &totals.vat {
+					table.add_cell(&format!("{} {}%:", lang.total_vat, percentage), &styles.basic_right)?;
+					table.add_cell(&money(*total), &styles.basic_right)?;
+				}
+			}
+			table.add_cell(&format!("{}:", lang.total_due), &styles.bold_right)?;
+			table.add_cell(&money(totals.total()), &styles.bold_right)?;
+			Ok(table.build())
+		};
 
-	let mut total_ex_vat = NotNan::new(0.0).unwrap();
-	let mut totals_vat: BTreeMap<NotNan<f64>, NotNan<f64>> = BTreeMap::new();
-	{
-		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width());
-		table.position(BoxPosition::at_xy(mm(20.0), y));
-		table.cell_padding(Margins::vh(font_size * 0.25 * MM_PER_PT, font_size * 0.5 * MM_PER_PT));
-		table.add_column(false, None);
-		table.add_column(true, None);
-		table.add_column(false, None);
-		table.add_column(false, None);
-		table.add_column(false, None);
-		table.add_column(false, None);
-		table.add_cell(&lang.date, &basic)?;
-		table.add_cell(&lang.description, &basic)?;
-		table.add_cell(&lang.quantity, &basic)?;
-		table.add_cell(&lang.entry_unit_price, &basic)?;
-		table.add_cell(&lang.entry_total_price, &basic)?;
-		table.add_cell(&lang.vat, &basic)?;
+		let mut notes = document.notes.to_vec();
+		if config.tax.kor {
+			notes.insert(0, lang.kor_notice.as_deref().unwrap_or(DEFAULT_KOR_NOTICE).to_string());
+		} else if is_intra_eu_supply(config, recipient) {
+			notes.insert(0, lang.reverse_charge_notice.as_deref().unwrap_or(DEFAULT_REVERSE_CHARGE_NOTICE).to_string());
+		}
 
-		for entry in entries {
-			let price = entry.quantity * entry.unit_price;
-			total_ex_vat += price;
-			*totals_vat.entry(entry.vat_percentage).or_default() += price * entry.vat_percentage / 100.0;
-
-			table.add_cell(&format_date(entry.date, &config.date_localization), &basic_right)?;
-			table.add_cell(&entry.description, &basic)?;
-			table.add_cell(&format!("{:.02} {}", entry.quantity, entry.unit), &basic_right)?;
-			table.add_cell(&format!("{} {:.02}", lang.currency_symbol, entry.unit_price), &basic_right)?;
-			table.add_cell(&format!("{} {:.02}", lang.currency_symbol, price), &basic_right)?;
-			table.add_cell(&format!("{}%", entry.vat_percentage), &basic_right)?;
+		// Divide the rows over the pages.
+		// The last page also needs room for the totals and the notes above the footer.
+		// The other pages end with a carried forward subtotal above the bottom margin.
+		let footer_y = mm(A4.height - 40.0);
+		let line_height = font_size * 1.2 * MM_PER_PT;
+		let row_descent = font_size * 0.6 * MM_PER_PT;
+		let notes_height = line_height * notes.iter().map(|x| x.lines().count()).sum::<usize>() as f64;
+		let totals_height = mm(make_totals_table(&writer, mm(0.0))?.size().height);
+		let last_page_limit = footer_y - vskip * 3.0 - totals_height - notes_height;
+		let page_limit = mm(A4.height - 30.0) - vskip * 2.0;
+
+		let mut pages = Vec::new();
+		let mut start = 0;
+		let mut page_y = y;
+		loop {
+			let table = make_entries_table(&writer, &rows[start..], page_y, (start > 0).then(|| rows[start - 1].1), None)?;
+			if start == rows.len() || (page_y + mm(table.size().height)).get() <= last_page_limit.get() {
+				pages.push((start..rows.len(), page_y));
+				break;
+			}
+			let header_rows = if start > 0 { 2 } else { 1 };
+			let fits = (0..rows.len() - start)
+				.take_while(|i| (table.baseline(header_rows + i) + row_descent).get() <= page_limit.get())
+				.count()
+				.max(1);
+			pages.push((start..start + fits, page_y));
+			start += fits;
+			page_y = mm(30.0);
+		}
+
+		let page_count = pages.len();
+		let mut first_page = Some(page);
+		for (index, (range, page_y)) in pages.into_iter().enumerate() {
+			let page = match first_page.take() {
+				Some(page) => page,
+				None => new_page(&mut writer)?,
+			};
+			let is_last = index + 1 == page_count;
+
+			let brought_forward = (range.start > 0).then(|| rows[range.start - 1].1);
+			let carried_forward = (!is_last).then(|| rows[range.end - 1].1);
+			let table = make_entries_table(&writer, &rows[range], page_y, brought_forward, carried_forward)?;
+			let mut y = page_y + mm(table.size().height) + vskip;
+			table.draw(&page);
+			table.draw_horizontal_border(&page, 1, .., pt(0.5));
+			if brought_forward.is_some() {
+				table.draw_horizontal_border(&page, 2, .., pt(0.5));
+			}
+			if carried_forward.is_some() {
+				table.draw_horizontal_border(&page, table.rows() - 1, .., pt(0.5));
+			}
+
+			if is_last {
+				y = (y + footer_y - vskip) * 0.5;
+				let table = make_totals_table(&writer, y)?;
+				y += mm(table.size().height) + vskip;
+				table.draw(&page);
+				table.draw_horizontal_border(&page, table.rows() - 1, .., pt(0.5));
+
+				if !notes.is_empty() {
+					page.draw_text_box(&notes.join("\n"), &styles.basic, BoxPosition::at_xy(mm(20.0), y), Some(text_width))?;
+				}
+
+				page.draw_text_box(document.footer, &styles.footer, BoxPosition::at_xy(mm(20.0), footer_y), Some(text_width))?;
+			}
+
+			let page_number = format!("{} / {}", index + 1, page_count);
+			page.draw_text_box(&page_number, &styles.footer, BoxPosition::at_xy(mm(20.0) + text_width * 0.5, mm(A4.height - 20.0)), Some(text_width))?;
+			page.emit(&writer)?;
 		}
 
-		let table = table.build();
-		y += mm(table.size().height) + vskip;
-		table.draw(&page);
-		table.draw_horizontal_border(&page, 1, .., pt(0.5));
+		Ok(())
 	}
+}
 
-	{
-		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width());
-		y = (y + mm(A4.height - 40.0) - vskip) * 0.5;
-		table.position(BoxPosition::at_xy(page.text_width() + mm(20.0), y).anchor_right().anchor_middle_y());
-		table.cell_padding(Margins::vh(font_size * 0.25 * MM_PER_PT, font_size * 0.5 * MM_PER_PT));
-		table.add_column(false, None);
-		table.add_column(false, None);
-		table.add_cell(&format!("{}:", lang.total_ex_vat), &basic_right)?;
-		table.add_cell(&format!("{} {:.02}", lang.currency_symbol, total_ex_vat), &basic_right)?;
-		let mut total_inc_vat = total_ex_vat;
-		for (percentage, total) in &totals_vat {
-			total_inc_vat += *total;
-			table.add_cell(&format!("{} {}%:", lang.total_vat, percentage), &basic_right)?;
-			table.add_cell(&format!("{}{:.02}", lang.currency_symbol, total), &basic_right)?;
-		}
+/// An in-memory PDF file that can be read back after the PDF writer is done with it.
+#[derive(Clone, Default)]
+struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
 
-		let bold_right = pdf_writer::TextStyle {
-			align: pdf_writer::TextAlign::Right,
-			.. bold(font_size)
-		};
-		table.add_cell(&format!("{}:", lang.total_due), &bold_right)?;
-		table.add_cell(&format!("{}{:.02}", lang.currency_symbol, total_inc_vat), &bold_right)?;
-		let table = table.build();
-		y += mm(table.size().height) + vskip;
-		table.draw(&page);
-		table.draw_horizontal_border(&page, table.rows() - 1, .., pt(0.5));
+impl std::io::Write for SharedBuffer {
+	fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+		self.0.borrow_mut().extend_from_slice(data);
+		Ok(data.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
 	}
+}
 
-	page.draw_text_box(&lang.footer, &basic, BoxPosition::at_xy(mm(20.0), mm(A4.height - 40.0)), Some(page.text_width()))?;
-	page.draw_text_box("1 / 1", &basic, BoxPosition::at_xy(mm(20.0) + page.text_width() * 0.5, mm(A4.height - 20.0)), Some(page.text_width()))?;
+/// A row in the table with invoice entries.
+enum EntryRow<'a> {
+	/// The title of a section.
+	Section(&'a str),
+
+	/// An invoice entry.
+	Entry(&'a InvoiceEntry),
+
+	/// The subtotal of a section.
+	Subtotal {
+		amount: Cents,
+		hours: NotNan<f64>,
+	},
+}
 
-	page.emit(&writer)?;
-	Ok(())
+/// Format a number of hours as hours and minutes, like `7:45`.
+fn format_hours_minutes(hours: NotNan<f64>) -> String {
+	let minutes = (hours.into_inner() * 60.0).round() as i64;
+	let sign = if minutes < 0 { "-" } else { "" };
+	let minutes = minutes.unsigned_abs();
+	format!("{}{}:{:02}", sign, minutes / 60, minutes % 60)
 }
 
-fn format_date(date: Date, localization: &DateLocalization) -> String {
+pub(crate) fn format_date(date: Date, localization: &DateLocalization) -> String {
 	let month = format_month(date.month(), localization);
 	format!("{} {} {}", date.day(), month, date.year())
 }
@@ -0,0 +1,200 @@
+//! Machine-readable output of reports and lists.
+
+use zzp::gregorian::Date;
+use zzp::grootboek::{Cents, Transaction};
+use zzp::uurlog::Hours;
+
+use crate::grootboek::{format_decimal, json_string};
+
+/// The output format of reports and lists.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OutputFormat {
+	/// Colored text for the terminal.
+	Text,
+
+	/// A JSON document for scripts and dashboards.
+	Json,
+}
+
+/// A JSON value.
+///
+/// Objects keep the order of their fields.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+	Null,
+	Bool(bool),
+	Number(String),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>),
+}
+
+impl Json {
+	/// Create a JSON object from (key, value) pairs.
+	pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Json)>) -> Self {
+		Self::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+	}
+
+	/// Create a JSON array from values.
+	pub fn array<T: Into<Json>>(values: impl IntoIterator<Item = T>) -> Self {
+		Self::Array(values.into_iter().map(Into::into).collect())
+	}
+
+	/// Print the value to standard output, followed by a newline.
+	pub fn print(&self) {
+		println!("{}", self);
+	}
+
+	fn write_indented(&self, f: &mut std::fmt::Formatter, indent: usize) -> std::fmt::Result {
+		match self {
+			Self::Null => write!(f, "null"),
+			Self::Bool(value) => write!(f, "{}", value),
+			Self::Number(value) => write!(f, "{}", value),
+			Self::String(value) => write!(f, "{}", json_string(value)),
+			Self::Array(values) if values.is_empty() => write!(f, "[]"),
+			Self::Object(fields) if fields.is_empty() => write!(f, "{{}}"),
+			Self::Array(values) => {
+				writeln!(f, "[")?;
+				for (i, value) in values.iter().enumerate() {
+					write!(f, "{}", "\t".repeat(indent + 1))?;
+					value.write_indented(f, indent + 1)?;
+					writeln!(f, "{}", if i + 1 < values.len() { "," } else { "" })?;
+				}
+				write!(f, "{}]", "\t".repeat(indent))
+			},
+			Self::Object(fields) => {
+				writeln!(f, "{{")?;
+				for (i, (key, value)) in fields.iter().enumerate() {
+					write!(f, "{}{}: ", "\t".repeat(indent + 1), json_string(key))?;
+					value.write_indented(f, indent + 1)?;
+					writeln!(f, "{}", if i + 1 < fields.len() { "," } else { "" })?;
+				}
+				write!(f, "{}}}", "\t".repeat(indent))
+			},
+		}
+	}
+}
+
+impl std::fmt::Display for Json {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		self.write_indented(f, 0)
+	}
+}
+
+impl From<bool> for Json {
+	fn from(value: bool) -> Self {
+		Self::Bool(value)
+	}
+}
+
+impl From<i32> for Json {
+	fn from(value: i32) -> Self {
+		Self::Number(value.to_string())
+	}
+}
+
+impl From<u32> for Json {
+	fn from(value: u32) -> Self {
+		Self::Number(value.to_string())
+	}
+}
+
+impl From<usize> for Json {
+	fn from(value: usize) -> Self {
+		Self::Number(value.to_string())
+	}
+}
+
+impl From<f64> for Json {
+	fn from(value: f64) -> Self {
+		if value.is_finite() {
+			Self::Number(value.to_string())
+		} else {
+			Self::Null
+		}
+	}
+}
+
+impl From<&str> for Json {
+	fn from(value: &str) -> Self {
+		Self::String(value.to_string())
+	}
+}
+
+impl From<String> for Json {
+	fn from(value: String) -> Self {
+		Self::String(value)
+	}
+}
+
+impl From<&String> for Json {
+	fn from(value: &String) -> Self {
+		Self::String(value.clone())
+	}
+}
+
+/// Amounts are written as decimal numbers, like `-12.34`.
+impl From<Cents> for Json {
+	fn from(value: Cents) -> Self {
+		Self::Number(format_decimal(value))
+	}
+}
+
+/// Hours are written as decimal numbers of hours, like `2.5`.
+impl From<Hours> for Json {
+	fn from(value: Hours) -> Self {
+		Self::from(f64::from(value.total_minutes()) / 60.0)
+	}
+}
+
+/// Dates are written as `YYYY-MM-DD` strings.
+impl From<Date> for Json {
+	fn from(value: Date) -> Self {
+		Self::String(value.to_string())
+	}
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+	fn from(value: Option<T>) -> Self {
+		match value {
+			Some(value) => value.into(),
+			None => Self::Null,
+		}
+	}
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+	fn from(value: Vec<T>) -> Self {
+		Self::array(value)
+	}
+}
+
+impl From<&Transaction<'_>> for Json {
+	fn from(transaction: &Transaction) -> Self {
+		Self::object([
+			("date", transaction.date.into()),
+			("description", transaction.description.into()),
+			("notes", Self::array(transaction.notes.iter().copied())),
+			("tags", Self::array(transaction.tags.iter().map(|tag| Self::object([
+				("label", tag.label.into()),
+				("value", tag.value.into()),
+			])))),
+			("mutations", Self::array(transaction.mutations.iter().map(|mutation| Self::object([
+				("account", mutation.account.as_str().into()),
+				("amount", mutation.amount.into()),
+			])))),
+		])
+	}
+}
+
+impl std::str::FromStr for OutputFormat {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		match input {
+			"text" => Ok(Self::Text),
+			"json" => Ok(Self::Json),
+			_ => Err(format!("invalid output format {:?}, expected text or json", input)),
+		}
+	}
+}
@@ -1,14 +1,52 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use ordered_float::NotNan;
 
+pub mod aging;
+pub mod bank;
+pub mod diagnostics;
+pub mod email;
+pub mod example;
+pub mod generate;
 pub mod invoice;
+pub mod json;
+pub mod localization;
+pub mod migrate;
 pub mod grootboek;
+pub mod hooks;
+pub mod hours;
+pub mod icp;
+pub mod ical;
+pub mod income_statement;
+pub mod income_tax;
+pub mod numbering;
+pub mod pdfa;
+pub mod peppol;
+pub mod period;
+pub mod quarterly_report;
+pub mod quote;
+pub mod register;
+pub mod revenue;
+pub mod schema;
+pub mod timer;
+pub mod toggl;
+pub mod ubl;
+pub mod vat_return;
+pub mod workspace;
+pub mod xaf;
+
+/// The default path of the hour log of a customer, relative to the directory of `customer.toml`.
+pub const DEFAULT_HOURS_PATH: &str = "uurlog";
 
 /// Main configuration file for the ZZP tools.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields, rename_all = "PascalCase")]
 pub struct ZzpConfig {
+	/// The version of the configuration format, see [`migrate::CONFIG_VERSION`].
+	#[serde(rename = "version")]
+	pub version: u32,
+
 	/// The company details.
 	pub company: Company,
 
@@ -21,17 +59,70 @@ pub struct ZzpConfig {
 	/// Cosmetic invoice options.
 	pub invoice: Invoice,
 
-	/// Invoice localization details.
+	/// Invoice localization details, or the name of a built-in preset like `nl`.
+	#[schemars(schema_with = "schema::invoice_localization")]
+	#[serde(deserialize_with = "localization::deserialize_invoice")]
 	pub invoice_localization: InvoiceLocalization,
 
-	/// Date localization details.
+	/// Date localization details, or the name of a built-in preset like `nl`.
+	#[schemars(schema_with = "schema::date_localization")]
+	#[serde(deserialize_with = "localization::deserialize_date")]
 	pub date_localization: DateLocalization,
+
+	/// Structured company details for electronic invoices.
+	pub e_invoice: Option<CompanyEInvoice>,
+
+	/// The currency and number formatting for invoices.
+	pub currency: Option<Currency>,
+
+	/// Settings for sending invoices by email.
+	pub email: Option<EmailConfig>,
+
+	/// Settings for delivering e-invoices through a Peppol access point.
+	pub peppol: Option<PeppolConfig>,
+
+	/// Commands to run after events, like generating an invoice.
+	pub hooks: Option<HooksConfig>,
+
+	/// Settings for quotes.
+	pub quote: Option<QuoteConfig>,
+
+	/// Quote localization details.
+	pub quote_localization: Option<QuoteLocalization>,
+
+	/// Settings for the hour log.
+	pub uurlog: Option<UurlogConfig>,
+
+	/// Settings for the VAT return (btw-aangifte).
+	pub vat_return: Option<VatReturnConfig>,
+
+	/// Settings for the income tax return (aangifte inkomstenbelasting).
+	pub income_tax: Option<IncomeTaxConfig>,
+
+	/// The chart of accounts, with the type of grootboek accounts for financial statements.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub account: Vec<AccountConfig>,
+
+	/// Custom periods that can be selected by name, like a fiscal year that does not match the calendar year.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub period: Vec<PeriodConfig>,
+
+	/// Default details on tags for hour entries of all customers.
+	///
+	/// Tags with the same name in a customer configuration override the details of the default tag.
+	/// After applying the customer overrides, this holds all tags for the customer.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tag: Vec<TagConfig>,
 }
 
 /// Configuration file for specific customers.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields, rename_all = "PascalCase")]
 pub struct CustomerConfig {
+	/// The version of the configuration format, see [`migrate::CONFIG_VERSION`].
+	#[serde(rename = "version")]
+	pub version: u32,
+
 	/// Details about the customer itself.
 	pub customer: Customer,
 
@@ -39,11 +130,55 @@ pub struct CustomerConfig {
 	pub invoice: CustomerInvoice,
 
 	/// Details on tags for hour entries related to invoicing.
-	#[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub tag: Vec<TagConfig>,
+
+	/// Fixed-price entries to add to every invoice.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub fixed_entry: Vec<FixedEntry>,
+
+	/// Expenses that are logged as tagged entries in the hour log, like a kilometer allowance.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub expense: Vec<ExpenseConfig>,
+
+	/// Surcharges on the price per hour for work on specific days, like weekends or public holidays.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub surcharge: Vec<SurchargeRule>,
+
+	/// Invoices to generate every month, like a retainer.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub recurring: Vec<RecurringInvoice>,
+
+	/// Custom periods of the customer, like their fiscal quarters.
+	///
+	/// Periods with the same name as a period in the main configuration override that period.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub period: Vec<PeriodConfig>,
+
+	/// Rules to import calendar events as hour log entries with `uurlog import ical`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub calendar_rule: Vec<CalendarRule>,
+
+	/// Structured customer details for electronic invoices.
+	///
+	/// If present, a UBL e-invoice is generated alongside the PDF invoice.
+	pub e_invoice: Option<CustomerEInvoice>,
+
+	/// Invoice localization details that override the ones from the main configuration.
+	#[schemars(schema_with = "schema::invoice_localization")]
+	#[serde(default, deserialize_with = "localization::deserialize_invoice_opt")]
+	pub invoice_localization: Option<InvoiceLocalization>,
+
+	/// Date localization details that override the ones from the main configuration.
+	#[schemars(schema_with = "schema::date_localization")]
+	#[serde(default, deserialize_with = "localization::deserialize_date_opt")]
+	pub date_localization: Option<DateLocalization>,
+
+	/// The currency and number formatting that override the ones from the main configuration.
+	pub currency: Option<Currency>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Company {
 	/// The name of the company.
@@ -60,9 +195,222 @@ pub struct Company {
 
 	/// Payment details such as IBAN and BIC.
 	pub payment: Vec<KeyValue>,
+
+	/// The visual identity of the company on generated invoices and quotes.
+	pub branding: Option<Branding>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// The visual identity of the company on generated invoices and quotes.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Branding {
+	/// The path of the company logo, relative to the directory of `zzp.toml`.
+	///
+	/// The logo is not drawn yet, since the PDF writer can not draw images.
+	pub logo: Option<String>,
+
+	/// The accent color as hexadecimal RGB value, like `#1a5fb4`.
+	///
+	/// The accent color is not used yet, since the PDF writer can not draw colored text.
+	pub accent_color: Option<String>,
+
+	/// Fonts to use for specific text roles instead of the invoice font.
+	#[serde(default)]
+	pub fonts: BrandingFonts,
+}
+
+/// Fonts for specific text roles on generated invoices and quotes.
+///
+/// The fallback fonts of the invoice configuration are used for all roles.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BrandingFonts {
+	/// The font for the title of the document.
+	pub title: Option<String>,
+
+	/// The font for bold text, like section headers and totals.
+	pub heading: Option<String>,
+
+	/// The font for regular text.
+	pub body: Option<String>,
+
+	/// The font for the payment footer and page numbers.
+	pub footer: Option<String>,
+}
+
+/// A text role on generated invoices and quotes, which can have its own font.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FontRole {
+	/// The title of the document.
+	Title,
+
+	/// Bold text, like section headers and totals.
+	Heading,
+
+	/// Regular text.
+	Body,
+
+	/// The payment footer and page numbers.
+	Footer,
+}
+
+/// Structured company details for electronic invoices.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CompanyEInvoice {
+	/// The street name and house number.
+	pub street: String,
+
+	/// The postal code.
+	pub postal_code: String,
+
+	/// The city.
+	pub city: String,
+
+	/// The ISO 3166-1 alpha-2 country code, like `NL`.
+	pub country_code: String,
+
+	/// The VAT identification number, like `NL123456789B01`.
+	pub vat_number: String,
+
+	/// The Chamber of Commerce (KvK) number.
+	pub coc_number: Option<String>,
+
+	/// The IBAN to receive payments on.
+	pub iban: String,
+}
+
+/// Structured customer details for electronic invoices.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CustomerEInvoice {
+	/// The street name and house number.
+	pub street: String,
+
+	/// The postal code.
+	pub postal_code: String,
+
+	/// The city.
+	pub city: String,
+
+	/// The ISO 3166-1 alpha-2 country code, like `NL`.
+	pub country_code: String,
+
+	/// The VAT identification number of the customer.
+	pub vat_number: Option<String>,
+
+	/// The Chamber of Commerce (KvK) number of the customer.
+	pub coc_number: Option<String>,
+
+	/// The reference of the buyer, required by many government customers.
+	pub buyer_reference: Option<String>,
+}
+
+/// Settings for sending invoices by email.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+	/// The SMTP server to send email with.
+	pub server: String,
+
+	/// The port of the SMTP server (default 587 for `starttls` and 465 for `tls`).
+	pub port: Option<u16>,
+
+	/// The encryption to use for the SMTP connection (default `starttls`).
+	pub encryption: Option<SmtpEncryption>,
+
+	/// The username to log in to the SMTP server.
+	pub username: Option<String>,
+
+	/// The environment variable that holds the password for the SMTP server.
+	pub password_env: Option<String>,
+
+	/// The sender of the email, like `Company <invoices@example.com>`.
+	pub from: String,
+
+	/// Send a blind copy of each email to this address.
+	pub bcc: Option<String>,
+
+	/// The subject of the email.
+	///
+	/// Supports `{invoice_number}`, `{company}`, `{customer}` and `{total}`.
+	pub subject: String,
+
+	/// The body of the email.
+	///
+	/// Supports the same placeholders as the subject.
+	pub body: String,
+
+	/// The subject of payment reminders for overdue invoices (default `Reminder: invoice {invoice_number}`).
+	///
+	/// Supports the placeholders of the subject, and `{invoice_date}`, `{due_date}` and `{days_overdue}`.
+	pub reminder_subject: Option<String>,
+
+	/// The body of payment reminders for overdue invoices.
+	///
+	/// Supports the same placeholders as the reminder subject.
+	pub reminder_body: Option<String>,
+
+	/// The file to record sent emails in (default `email-log.txt`).
+	pub delivery_log: Option<String>,
+}
+
+/// Settings for delivering e-invoices through a Peppol access point.
+///
+/// The UBL e-invoice is posted to the API of the access point, which sends it to the customer over the Peppol network.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PeppolConfig {
+	/// The URL of the API endpoint of the access point to post UBL e-invoices to.
+	pub endpoint: String,
+
+	/// The environment variable that holds the API token of the access point.
+	pub token_env: Option<String>,
+
+	/// The HTTP header to send the API token in (default `Authorization` with a `Bearer` token).
+	pub token_header: Option<String>,
+
+	/// The field of the JSON response with the reference of the submitted invoice at the access point (default `id`).
+	pub reference_field: Option<String>,
+}
+
+/// Commands to run after events, to add notifications, uploads or other steps.
+///
+/// Commands are run in the directory of `zzp.toml` and not through a shell.
+/// Placeholders are filled in after the command is split into arguments, so a value with spaces stays a single argument.
+/// Arguments can be quoted with single or double quotes.
+/// A literal `{` or `}` can not be used in a command: it is read as part of a placeholder and the hook fails.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+	/// The command to run after an invoice is generated, like `./notify.sh {pdf} {number}`.
+	///
+	/// Supports `{number}`, `{customer}`, `{date}`, `{total}`, `{pdf}` and `{ubl}` (empty without an e-invoice).
+	pub post_invoice: Option<String>,
+
+	/// The command to run after a bank statement is imported.
+	///
+	/// Supports `{statement}` and `{count}` (the number of imported transactions).
+	pub post_bank_import: Option<String>,
+
+	/// The command to run after an invoice is marked as paid.
+	///
+	/// Supports `{number}`, `{customer}`, `{date}` (of the payment) and `{total}`.
+	pub post_paid: Option<String>,
+}
+
+/// The encryption to use for an SMTP connection.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpEncryption {
+	/// Connect without encryption and upgrade the connection with STARTTLS.
+	Starttls,
+
+	/// Connect with TLS directly.
+	Tls,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct GrootboekConfig {
 	/// The path to the grootboek file.
@@ -74,6 +422,23 @@ pub struct GrootboekConfig {
 	/// The grootboek account to put debts from debitors on.
 	pub debitor_account: String,
 
+	/// The grootboek account of the bank account that receives invoice payments.
+	pub bank_account: Option<String>,
+
+	/// The file with rules to categorize imported bank transactions, relative to `zzp.toml` (default `bank-rules.toml`).
+	pub bank_rules: Option<String>,
+
+	/// The tag label for the VAT number of intra-EU customers, used for the ICP declaration (default `icp`).
+	pub icp_tag: Option<String>,
+
+	/// The tag label for the currency of invoices to customers with their own currency (default `currency`).
+	pub currency_tag: Option<String>,
+
+	/// The directory with the documents attached to transactions, relative to the grootboek file.
+	///
+	/// `grootboek check --attachments` reports files in this directory that are not referenced by any transaction.
+	pub attachments_directory: Option<String>,
+
 	/// The grootboek account to put debts to creditors on.
 	pub creditor_account: String,
 
@@ -87,71 +452,675 @@ pub struct GrootboekConfig {
 	pub vat_input_account: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Tax {
 	/// Default VAT percentage for delivered goods/services.
+	#[schemars(with = "f64")]
 	pub vat: NotNan<f64>,
+
+	/// The company uses the small business scheme (kleineondernemersregeling, KOR).
+	///
+	/// Invoices will not have VAT and no VAT is booked in the grootboek.
+	#[serde(default)]
+	pub kor: bool,
+
+	/// How to compute the VAT of invoices (default `per_rate`).
+	pub vat_rounding: Option<VatRounding>,
+
+	/// How to round the amounts of invoices to whole cents (default `half_away_from_zero`).
+	///
+	/// Supports `half_away_from_zero`, `half_even`, `toward_zero`, `away_from_zero`, `down` and `up`.
+	/// This is used for the line totals and the VAT, both on the invoice and in the grootboek.
+	#[schemars(schema_with = "schema::rounding_mode")]
+	#[serde(default, with = "serde_rounding_mode")]
+	pub rounding_mode: zzp::grootboek::RoundingMode,
+
+	/// The number of the first month of the fiscal year, from 1 to 12 (default 1).
+	///
+	/// Year reports like the revenue overview, the hours criterion and the year archive cover the fiscal year.
+	/// A fiscal year is named after the calendar year it starts in.
+	#[schemars(schema_with = "schema::month")]
+	#[serde(default, with = "serde_option_month", skip_serializing_if = "Option::is_none")]
+	pub fiscal_year_start: Option<zzp::gregorian::Month>,
+}
+
+/// How to compute the VAT of invoices.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VatRounding {
+	/// Compute the VAT over the total of all lines with the same VAT percentage.
+	#[default]
+	PerRate,
+
+	/// Compute the VAT of each line and add them up per VAT percentage.
+	PerLine,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Invoice {
+	/// The font to use for generated invoices.
+	pub font: String,
+
+	/// Fonts to use for characters that are missing from the main font, like Cyrillic, CJK or emoji.
+	///
+	/// The fonts are tried in order for each character that the main font does not have.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub fallback_fonts: Vec<String>,
+
+	/// The base font size to use for generated invoices.
+	#[schemars(with = "f64")]
+	pub font_size: NotNan<f64>,
+
+	/// The directory to save invoices.
+	pub directory: String,
+
+	/// The description to use for the generated grootboek transaction.
+	pub grootboek_description: String,
+
+	/// The tag to use to link the invoice file to a transaction.
+	pub grootboek_tag: String,
+
+	/// The format for generated invoice numbers (default `{year}-{seq:03}`).
+	///
+	/// Supports `{year}`, `{month}`, `{customer_code}` and `{seq}`, where `{seq:0N}` pads the sequence number to `N` digits.
+	/// The sequence restarts every year if the format contains `{year}`, and every month if it also contains `{month}`.
+	/// Formats with `{customer_code}` have a separate sequence for each customer.
+	/// Each format has its own sequence, so customers with a different format do not share a sequence.
+	#[serde(default, deserialize_with = "numbering::deserialize_format_opt")]
+	pub number_format: Option<String>,
+
+	/// The file to store the last used invoice number sequence (default `invoice-numbers.toml`).
+	pub number_counter: Option<String>,
+
+	/// How to show quantities of hours on invoices (default `decimal`).
+	pub quantity_format: Option<QuantityFormat>,
+
+	/// The number of days customers have to pay an invoice (default 30).
+	///
+	/// Can also be written as `payment_term_days`.
+	#[serde(alias = "payment_term_days")]
+	pub payment_days: Option<u32>,
+
+	/// The file to keep the register of generated invoices (default `invoices.toml`).
+	pub register: Option<String>,
+
+	/// The tag to use for the customer reference in the generated grootboek transaction (default `reference`).
+	pub reference_tag: Option<String>,
+
+	/// The description to use for the grootboek transaction of a payment (default `Payment of invoice {invoice_number}`).
+	pub payment_description: Option<String>,
+
+	/// Create invoices and quotes as PDF/A-3b files, for long-term archiving.
+	///
+	/// All fonts must be embeddable, which is the case for normal TrueType and OpenType fonts.
+	#[serde(default)]
+	pub pdf_a: bool,
+}
+
+/// How to show quantities of hours on invoices.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuantityFormat {
+	/// Show hours as a decimal number with the unit, like `7.75 hours`.
+	#[default]
+	Decimal,
+
+	/// Show hours as hours and minutes, like `7:45`.
+	HoursMinutes,
+}
+
+/// How invoices are delivered to a customer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeliveryMethod {
+	/// Send the PDF invoice by email.
+	PdfEmail,
+
+	/// Send the PDF invoice and the UBL e-invoice by email.
+	Ubl,
+
+	/// Submit the UBL e-invoice to the Peppol access point from the `[Peppol]` section of `zzp.toml`.
+	///
+	/// Without a `[Peppol]` section, the UBL e-invoice is only created to upload it by hand.
+	Peppol,
+}
+
+impl DeliveryMethod {
+	/// Check if invoices with this delivery method are sent by email.
+	pub fn is_email(self) -> bool {
+		match self {
+			Self::PdfEmail | Self::Ubl => true,
+			Self::Peppol => false,
+		}
+	}
+
+	/// Check if invoices with this delivery method need a UBL e-invoice.
+	pub fn needs_ubl(self) -> bool {
+		match self {
+			Self::PdfEmail => false,
+			Self::Ubl | Self::Peppol => true,
+		}
+	}
+}
+
+impl std::fmt::Display for DeliveryMethod {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::PdfEmail => write!(f, "pdf-email"),
+			Self::Ubl => write!(f, "ubl"),
+			Self::Peppol => write!(f, "peppol"),
+		}
+	}
+}
+
+impl std::str::FromStr for DeliveryMethod {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		match input {
+			"pdf-email" => Ok(Self::PdfEmail),
+			"ubl" => Ok(Self::Ubl),
+			"peppol" => Ok(Self::Peppol),
+			_ => Err(format!("invalid delivery method {:?}, expected pdf-email, ubl or peppol", input)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QuoteConfig {
+	/// The directory to save quotes.
+	pub directory: String,
+
+	/// The number of days a quote stays valid (default 30).
+	pub valid_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UurlogConfig {
+	/// The number of hours to work per day, like `8h`.
+	#[schemars(schema_with = "schema::hours")]
+	#[serde(default, with = "serde_option_hours", skip_serializing_if = "Option::is_none")]
+	pub daily_target: Option<zzp::uurlog::Hours>,
+
+	/// The number of hours to work per week, like `40h`.
+	#[schemars(schema_with = "schema::hours")]
+	#[serde(default, with = "serde_option_hours", skip_serializing_if = "Option::is_none")]
+	pub weekly_target: Option<zzp::uurlog::Hours>,
+
+	/// Tags of hour entries for work that is not billed, like `admin` or `acquisition`.
+	///
+	/// These hours only count for the hours criterion report when asked for.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub non_billable_tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct VatReturnConfig {
+	/// The boxes (rubrieken) of the VAT return with the accounts that make up their amounts.
+	#[serde(rename = "Box")]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub boxes: Vec<VatReturnBox>,
+
+	/// The accounts with VAT input tax for box 5b (defaults to the VAT input account of the grootboek).
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub input_vat: Vec<String>,
+
+	/// The grootboek account to put the VAT to pay or to receive on when booking the VAT return.
+	pub payment_account: Option<String>,
+
+	/// The grootboek account to put the difference caused by rounding to whole euros on.
+	///
+	/// If not set, the exact amount is booked on the payment account.
+	pub rounding_account: Option<String>,
+
+	/// The file with the filed VAT returns, relative to `zzp.toml` (default `vat-declarations.toml`).
+	pub declarations: Option<String>,
+}
+
+/// A box (rubriek) of the VAT return, like `1a`.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct VatReturnBox {
+	/// The name of the box, like `1a`.
+	pub name: String,
+
+	/// The description of the box (defaults to the description on the VAT return for known boxes).
+	pub description: Option<String>,
+
+	/// The accounts with the turnover for this box.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub turnover: Vec<String>,
+
+	/// The accounts with the VAT for this box.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub vat: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IncomeTaxConfig {
+	/// The fields of the income tax return with the accounts that make up their amounts.
+	#[serde(rename = "Field")]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub fields: Vec<IncomeTaxField>,
+}
+
+/// A field of the income tax return, like `omzet` or `afschrijvingen`.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IncomeTaxField {
+	/// The name of the field.
+	pub name: String,
+
+	/// The description of the field (defaults to the description on the tax form for known fields).
+	pub description: Option<String>,
+
+	/// The accounts that make up the amount of the field, including their sub-accounts.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub accounts: Vec<String>,
+
+	/// The field is revenue or a correction that adds to the profit (defaults to true for known revenue and correction fields).
+	pub credit: Option<bool>,
+}
+
+/// An entry in the chart of accounts.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+	/// The grootboek account, including its sub-accounts.
+	pub account: String,
+
+	/// The type of the account.
+	#[serde(rename = "type")]
+	pub kind: AccountKind,
+
+	/// The name to show in financial statements (defaults to the account).
+	pub name: Option<String>,
+}
+
+/// The type of a grootboek account.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountKind {
+	/// Things the company owns, like the bank account or debts of customers.
+	Asset,
+
+	/// Debts of the company, like VAT to pay.
+	Liability,
+
+	/// The capital of the owner.
+	Equity,
+
+	/// Income of the company.
+	Revenue,
+
+	/// Costs of the company.
+	Expense,
+}
+
+/// Customer details.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Customer {
+	pub name: String,
+	pub address: Vec<String>,
+	pub grootboek_name: String,
+
+	/// The email address to send invoices to.
+	pub email: Option<String>,
+
+	/// The Peppol participant identifier of the customer, as `scheme:identifier`, like `0106:12345678`.
+	pub peppol_id: Option<String>,
+
+	/// The Dutch government organization identification number (OIN) of the customer.
+	///
+	/// Government customers are identified by their OIN on e-invoices if they have no Peppol identifier.
+	pub oin: Option<String>,
+
+	/// How invoices are delivered to the customer (default: not delivered automatically).
+	pub delivery: Option<DeliveryMethod>,
+
+	/// The VAT identification number of the customer, shown on invoices.
+	pub vat_number: Option<String>,
+
+	/// The ISO 3166-1 alpha-2 country code of the customer, like `DE`.
+	///
+	/// Customers with a VAT number in another EU country are invoiced with the intra-EU 0% rate.
+	pub country_code: Option<String>,
+
+	/// A short code for the customer, used for `{customer_code}` in invoice numbers.
+	pub code: Option<String>,
+
+	/// The path of the hour log, relative to the directory of `customer.toml` (default `uurlog`).
+	///
+	/// Supports `{year}` and `{month}` to keep a separate hour log per year or month, like `uurlog-{year}`.
+	pub hours_path: Option<String>,
+}
+
+/// Details on how to invoice a customer.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CustomerInvoice {
+	/// The price per hour in money units (euro, yen, dollar, ...).
+	///
+	/// This can also be a list of rates with the date from which they apply,
+	/// like `[{ start_date = "2024-01-01", rate = 90.0 }, { start_date = "2024-07-01", rate = 95.0 }]`.
+	pub price_per_hour: HourlyRate,
+
+	/// Summarize all hours per day with a single entry.
+	pub summarize_per_day: Option<String>,
+
+	/// A discount on the total price of the invoice.
+	pub discount: Option<Discount>,
+
+	/// How to show quantities of hours on invoices for this customer.
+	pub quantity_format: Option<QuantityFormat>,
+
+	/// The number of days this customer has to pay an invoice, instead of the payment term from the main configuration.
+	///
+	/// Can also be written as `payment_term_days`.
+	#[serde(alias = "payment_term_days")]
+	pub payment_days: Option<u32>,
+
+	/// Extra lines to show above the payment footer, like purchase conditions or a project reference.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub notes: Vec<String>,
+
+	/// The purchase order number or other reference to show on invoices for this customer.
+	pub reference: Option<String>,
+
+	/// The format for invoice numbers of this customer, instead of the format from the main configuration.
+	#[serde(default, deserialize_with = "numbering::deserialize_format_opt")]
+	pub number_format: Option<String>,
+
+	/// The currency of invoices for this customer, instead of the currency from the main configuration.
+	///
+	/// This takes precedence over the `[Currency]` section of the customer configuration.
+	pub currency: Option<Currency>,
+}
+
+/// A price per hour that can change over time.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum HourlyRate {
+	/// The same price for all dates.
+	#[schemars(with = "f64")]
+	Fixed(NotNan<f64>),
+
+	/// Prices that apply from a start date, sorted by start date.
+	Periods(Vec<RatePeriod>),
+}
+
+/// A price per hour that applies from a start date until the start of the next period.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RatePeriod {
+	/// The first day on which the rate applies.
+	#[schemars(schema_with = "schema::date")]
+	#[serde(with = "serde_date")]
+	pub start_date: zzp::gregorian::Date,
+
+	/// The price per hour.
+	#[schemars(with = "f64")]
+	pub rate: NotNan<f64>,
+}
+
+impl HourlyRate {
+	/// Get the price per hour on a date.
+	///
+	/// Returns `None` if the date is before the start of the first period.
+	pub fn at(&self, date: zzp::gregorian::Date) -> Option<NotNan<f64>> {
+		match self {
+			Self::Fixed(rate) => Some(*rate),
+			Self::Periods(periods) => periods.iter()
+				.take_while(|x| x.start_date <= date)
+				.last()
+				.map(|x| x.rate),
+		}
+	}
+
+	/// Get the price per hour on a date, or an error if there is no rate for the date.
+	pub fn at_or_err(&self, date: zzp::gregorian::Date) -> Result<NotNan<f64>, String> {
+		self.at(date)
+			.ok_or_else(|| format!("no price per hour for {}: the first rate period starts later", date))
+	}
+}
+
+impl From<NotNan<f64>> for HourlyRate {
+	fn from(rate: NotNan<f64>) -> Self {
+		Self::Fixed(rate)
+	}
+}
+
+impl<'de> Deserialize<'de> for HourlyRate {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		use serde::de::Error;
+		match toml::Value::deserialize(deserializer)? {
+			toml::Value::Integer(rate) => Ok(Self::Fixed(NotNan::new(rate as f64).unwrap())),
+			toml::Value::Float(rate) => NotNan::new(rate)
+				.map(Self::Fixed)
+				.map_err(|_| D::Error::custom("the price per hour can not be NaN")),
+			periods @ toml::Value::Array(_) => {
+				let mut periods: Vec<RatePeriod> = periods.try_into().map_err(D::Error::custom)?;
+				if periods.is_empty() {
+					return Err(D::Error::custom("the list of rate periods can not be empty"));
+				}
+				periods.sort_by_key(|x| x.start_date);
+				if let Some(x) = periods.windows(2).find(|x| x[0].start_date == x[1].start_date) {
+					return Err(D::Error::custom(format!("multiple rate periods start on {}", x[0].start_date)));
+				}
+				Ok(Self::Periods(periods))
+			},
+			_ => Err(D::Error::custom("expected a price per hour or a list of rate periods")),
+		}
+	}
+}
+
+/// A custom period that can be selected by name on the command line.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PeriodConfig {
+	/// The name to select the period with, like `fy2024-q1`.
+	pub name: String,
+
+	/// The first day of the period.
+	#[schemars(schema_with = "schema::date")]
+	#[serde(with = "serde_date")]
+	pub start_date: zzp::gregorian::Date,
+
+	/// The last day of the period.
+	#[schemars(schema_with = "schema::date")]
+	#[serde(with = "serde_date")]
+	pub end_date: zzp::gregorian::Date,
+}
+
+/// A rule to import calendar events as hour log entries.
+///
+/// Each event is imported with the first rule that matches it.
+/// Events that match no rule are not imported.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CalendarRule {
+	/// Only match events from the calendar with this name.
+	pub calendar: Option<String>,
+
+	/// Only match events with a title that matches this regular expression, like `(?i)^acme`.
+	pub title: Option<String>,
+
+	/// The tags to add to the imported entries.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tags: Vec<String>,
+}
+
+/// Details on tags for hour entries related to invoicing.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TagConfig {
+	/// The name of the tag.
+	pub name: String,
+
+	/// The price per hour in money units (euro, yen, dollar, ...).
+	#[schemars(with = "Option<f64>")]
+	pub price_per_hour: Option<NotNan<f64>>,
+
+	/// Summarize all hours per day with a single entry.
+	pub summarize_per_day: Option<String>,
+
+	/// VAT percentage for tagged entries.
+	#[schemars(with = "Option<f64>")]
+	pub vat: Option<NotNan<f64>>,
+
+	/// The price of tagged entries as percentage of the normal price per hour, like 150 for evening work.
+	#[schemars(with = "Option<f64>")]
+	pub surcharge: Option<NotNan<f64>>,
+
+	/// A discount on the total price of the tagged entries.
+	pub discount: Option<Discount>,
+
+	/// Show the tagged entries in a separate section with a subtotal, using this title.
+	pub section: Option<String>,
+}
+
+/// A fixed-price entry that is added to every invoice of a customer.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FixedEntry {
+	/// The description of the entry on the invoice.
+	pub description: String,
+
+	/// The price per unit in money units (euro, yen, dollar, ...).
+	#[schemars(with = "f64")]
+	pub price: NotNan<f64>,
+
+	/// The quantity of the entry (default 1).
+	#[schemars(with = "Option<f64>")]
+	pub quantity: Option<NotNan<f64>>,
+
+	/// The unit to display for the quantity.
+	pub unit: Option<String>,
+
+	/// VAT percentage for the entry.
+	#[schemars(with = "Option<f64>")]
+	pub vat: Option<NotNan<f64>>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// An expense that is logged in the hour log with a tag, like a kilometer allowance or a parking fee.
+///
+/// Hour log entries with the tag are invoiced as expense instead of as hours, and must have zero hours.
+/// The description of the entry can start with the quantity, like `2024-03-01, 0m, [km] 120 visit to the office`.
+/// Entries without a quantity are invoiced once, which can be used for fixed surcharges.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
-pub struct Invoice {
-	/// The font to use for generated invoices.
-	pub font: String,
+pub struct ExpenseConfig {
+	/// The tag of hour log entries for the expense, without brackets.
+	pub tag: String,
 
-	/// The base font size to use for generated invoices.
-	pub font_size: NotNan<f64>,
+	/// The description of the expense on the invoice.
+	///
+	/// The remaining description of the hour log entry is added after it.
+	pub description: String,
 
-	/// The directory to save invoices.
-	pub directory: String,
+	/// The price per unit in money units (euro, yen, dollar, ...).
+	#[schemars(with = "f64")]
+	pub price: NotNan<f64>,
 
-	/// The description to use for the generated grootboek transaction.
-	pub grootboek_description: String,
+	/// The unit to display for the quantity, like `km`.
+	pub unit: Option<String>,
 
-	/// The tag to use to link the invoice file to a transaction.
-	pub grootboek_tag: String,
+	/// VAT percentage for the expense.
+	#[schemars(with = "Option<f64>")]
+	pub vat: Option<NotNan<f64>>,
+
+	/// Show the expenses in a separate section with a subtotal, using this title.
+	pub section: Option<String>,
 }
 
-/// Customer details.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// A surcharge on the price per hour for work on specific days.
+///
+/// If multiple surcharges apply to an hour entry, only the highest one is used.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
-pub struct Customer {
-	pub name: String,
-	pub address: Vec<String>,
-	pub grootboek_name: String,
+pub struct SurchargeRule {
+	/// The days on which the surcharge applies.
+	pub days: Vec<SurchargeDay>,
+
+	/// The price as percentage of the normal price per hour, like 150.
+	#[schemars(with = "f64")]
+	pub percentage: NotNan<f64>,
 }
 
-/// Details on how to invoice a customer.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
-pub struct CustomerInvoice {
-	/// The price per hour in money units (euro, yen, dollar, ...).
-	pub price_per_hour: NotNan<f64>,
+/// A day on which a surcharge applies.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SurchargeDay {
+	Monday,
+	Tuesday,
+	Wednesday,
+	Thursday,
+	Friday,
+	Saturday,
+	Sunday,
 
-	/// Summarize all hours per day with a single entry.
-	pub summarize_per_day: Option<String>,
+	/// A Dutch public holiday, see [`zzp::calendar::holidays`].
+	PublicHoliday,
 }
 
-	/// Details on tags for hour entries related to invoicing.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// An invoice that is generated every month, like a retainer.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
-pub struct TagConfig {
-	/// Thename of the tag.
+pub struct RecurringInvoice {
+	/// A unique name for the recurring invoice, used to avoid invoicing the same month twice.
 	pub name: String,
 
-	/// The price per hour in money units (euro, yen, dollar, ...).
-	pub price_per_hour: Option<NotNan<f64>>,
+	/// The description of the entry on the invoice.
+	///
+	/// Supports `{year}`, `{month}` and `{month_name}` for the invoiced month.
+	pub description: String,
 
-	/// Summarize all hours per day with a single entry.
-	pub summarize_per_day: Option<String>,
+	/// The monthly amount in money units (euro, yen, dollar, ...).
+	#[schemars(with = "f64")]
+	pub amount: NotNan<f64>,
 
-	/// VAT percentage for tagged entries.
+	/// VAT percentage for the entry.
+	#[schemars(with = "Option<f64>")]
 	pub vat: Option<NotNan<f64>>,
+
+	/// The first day of the recurring invoice, the month of this date is the first month to invoice.
+	#[schemars(schema_with = "schema::date")]
+	#[serde(default, with = "serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub start_date: Option<zzp::gregorian::Date>,
+
+	/// The last day of the recurring invoice, the month of this date is the last month to invoice.
+	#[schemars(schema_with = "schema::date")]
+	#[serde(default, with = "serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub end_date: Option<zzp::gregorian::Date>,
+}
+
+/// A discount on an invoice.
+///
+/// Exactly one of `percentage` or `amount` must be set.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Discount {
+	/// The description of the discount on the invoice.
+	pub description: String,
+
+	/// The discount as a percentage of the price.
+	#[schemars(with = "Option<f64>")]
+	pub percentage: Option<NotNan<f64>>,
+
+	/// The discount as a fixed amount in money units (euro, yen, dollar, ...).
+	#[schemars(with = "Option<f64>")]
+	pub amount: Option<NotNan<f64>>,
 }
 
 /// Localizaton details for invoices.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct InvoiceLocalization {
 	/// Translation for "Invoice".
@@ -182,6 +1151,24 @@ pub struct InvoiceLocalization {
 	pub total_vat: String,
 	/// Translation for "Total due".
 	pub total_due: String,
+	/// Translation for "Subtotal" (default "Subtotal").
+	pub subtotal: Option<String>,
+	/// The notice that the invoice is exempt from VAT because of the small business scheme (KOR).
+	pub kor_notice: Option<String>,
+	/// Translation for "VAT number" for the VAT number of the recipient (default "Btw-nummer").
+	pub vat_number: Option<String>,
+	/// The notice that the VAT is reverse-charged to the customer for intra-EU invoices.
+	pub reverse_charge_notice: Option<String>,
+	/// The watermark for draft invoices (default "CONCEPT").
+	pub draft: Option<String>,
+	/// Translation for "Reference" for the purchase order number of the customer (default "Reference").
+	pub reference: Option<String>,
+	/// Translation for "Carried forward" for the subtotal at the bottom of a page (default "Carried forward").
+	pub carried_forward: Option<String>,
+	/// Translation for "Brought forward" for the subtotal at the top of the next page (default "Brought forward").
+	pub brought_forward: Option<String>,
+	/// Translation for "Due date" to show the due date on invoices (the due date is not shown if not set).
+	pub due_date: Option<String>,
 	/// Translation for "hours".
 	pub hours: String,
 	/// The currency symbol.
@@ -190,8 +1177,64 @@ pub struct InvoiceLocalization {
 	pub footer: String,
 }
 
+/// Localization details for quotes.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QuoteLocalization {
+	/// Translation for "Quote".
+	pub quote: String,
+	/// Translation for "Quote number".
+	pub quote_number: String,
+	/// Translation for "Quote date".
+	pub quote_date: String,
+	/// Translation for "Valid until".
+	pub valid_until: String,
+	/// The footer with the conditions of the quote.
+	pub footer: String,
+}
+
+/// Currency and number formatting details.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Currency {
+	/// The ISO 4217 currency code, like `EUR` or `USD`.
+	pub code: String,
+
+	/// The currency symbol, like `€` or `$`.
+	pub symbol: String,
+
+	/// Put a space between the currency symbol and the amount (default true).
+	pub symbol_space: Option<bool>,
+
+	/// The decimal separator (default `.`).
+	pub decimal_separator: Option<String>,
+
+	/// The thousands separator (default none).
+	pub thousands_separator: Option<String>,
+
+	/// The locale for formatting amounts, which sets the default separators and the position of the symbol.
+	pub locale: Option<CurrencyLocale>,
+}
+
+/// A locale for formatting amounts of money.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrencyLocale {
+	/// English formatting, like `€ 1,234.56`.
+	En,
+
+	/// Dutch formatting, like `€ 1.234,56`.
+	Nl,
+
+	/// German formatting, like `1.234,56 €`.
+	De,
+
+	/// French formatting, like `1 234,56 €`.
+	Fr,
+}
+
 /// Localizaton details for dates.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct DateLocalization {
 	pub january: String,
@@ -209,7 +1252,7 @@ pub struct DateLocalization {
 }
 
 /// A generic key/value pair.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct KeyValue {
 	pub name: String,
@@ -236,18 +1279,368 @@ impl ZzpConfig {
 		}
 	}
 
+	/// Create an example configuration with placeholder company details.
+	///
+	/// Use [`Self::to_commented_toml`] to write it as configuration file with all available options.
+	pub fn example() -> Self {
+		let key_value = |name: &str, value: &str| KeyValue { name: name.into(), value: value.into() };
+		Self {
+			version: migrate::CONFIG_VERSION,
+			company: Company {
+				name: "Example".into(),
+				address: vec!["Street 1".into(), "1234 AB City".into()],
+				contact: vec![key_value("Email", "info@example.com")],
+				legal: vec![key_value("KvK", "12345678"), key_value("BTW", "NL000000000B01")],
+				payment: vec![key_value("IBAN", "NL00BANK0123456789")],
+				branding: None,
+			},
+			grootboek: GrootboekConfig {
+				path: "grootboek.txt".into(),
+				revenue_account: "revenue".into(),
+				debitor_account: "debitors/{debitor}".into(),
+				bank_account: Some("bank".into()),
+				bank_rules: None,
+				icp_tag: None,
+				currency_tag: None,
+				attachments_directory: None,
+				creditor_account: "creditors".into(),
+				vat_account: "vat/{percentage}".into(),
+				vat_input_account: "vat-input".into(),
+			},
+			tax: Tax {
+				vat: NotNan::new(21.0).unwrap(),
+				kor: false,
+				vat_rounding: None,
+				rounding_mode: Default::default(),
+				fiscal_year_start: None,
+			},
+			invoice: Invoice {
+				font: "DejaVu Sans".into(),
+				fallback_fonts: Vec::new(),
+				font_size: NotNan::new(10.0).unwrap(),
+				directory: "invoices".into(),
+				grootboek_description: "Invoice {invoice_number}".into(),
+				grootboek_tag: "invoice".into(),
+				number_format: None,
+				number_counter: None,
+				quantity_format: None,
+				payment_days: Some(30),
+				register: None,
+				reference_tag: None,
+				payment_description: None,
+				pdf_a: false,
+			},
+			invoice_localization: InvoiceLocalization::preset("en").unwrap(),
+			date_localization: DateLocalization::preset("en").unwrap(),
+			e_invoice: None,
+			currency: None,
+			email: None,
+			peppol: None,
+			hooks: None,
+			quote: None,
+			quote_localization: None,
+			uurlog: None,
+			vat_return: None,
+			income_tax: None,
+			account: Vec::new(),
+			period: Vec::new(),
+			tag: Vec::new(),
+		}
+	}
+
+	/// Serialize the configuration as TOML, with the documentation of every field as comment.
+	///
+	/// Options that are not set are included as comment too.
+	pub fn to_commented_toml(&self) -> Result<String, String> {
+		example::to_commented_toml(self)
+	}
+
 	/// Parse a ZZP configuration from a byte slice.
 	pub fn parse(bytes: &[u8]) -> Result<Self, toml::de::Error> {
 		toml::from_slice(bytes)
 	}
 
 	/// Parse a file as ZZP configuration.
+	///
+	/// The file can include other files, see [`read_toml_with_includes`].
 	pub fn read_file(path: impl AsRef<Path>) -> Result<Self, ReadFileError> {
-		read_toml(path)
+		read_toml_with_includes(path, migrate::ConfigKind::Zzp)
+	}
+
+	/// Find the entry in the chart of accounts for a grootboek account.
+	///
+	/// If multiple entries match, the most specific one is used.
+	pub fn find_account(&self, account: zzp::grootboek::Account) -> Option<&AccountConfig> {
+		self.account.iter()
+			.filter(|x| account.matches_prefix(&x.account))
+			.max_by_key(|x| x.account.len())
+	}
+
+	/// Apply the customer specific overrides from a customer configuration.
+	///
+	/// This replaces the invoice and date localization, the currency, the quantity format,
+	/// the payment term and the invoice number format if the customer configuration has them.
+	///
+	/// The tags of the customer are merged over the default tags.
+	/// The tags of the customer come first, followed by the default tags that the customer does not override.
+	pub fn apply_customer_overrides(&mut self, customer: &CustomerConfig) {
+		let mut tags: Vec<TagConfig> = customer.tag.iter()
+			.map(|tag| match self.tag.iter().find(|x| x.name == tag.name) {
+				Some(default) => default.overridden_by(tag),
+				None => tag.clone(),
+			})
+			.collect();
+		tags.extend(self.tag.iter().filter(|x| !customer.tag.iter().any(|tag| tag.name == x.name)).cloned());
+		self.tag = tags;
+
+		let mut periods = customer.period.clone();
+		periods.extend(self.period.iter().filter(|x| !customer.period.iter().any(|period| period.name == x.name)).cloned());
+		self.period = periods;
+
+		if let Some(format) = customer.invoice.quantity_format {
+			self.invoice.quantity_format = Some(format);
+		}
+		if let Some(format) = &customer.invoice.number_format {
+			self.invoice.number_format = Some(format.clone());
+		}
+		if let Some(days) = customer.invoice.payment_days {
+			self.invoice.payment_days = Some(days);
+		}
+		if let Some(localization) = &customer.invoice_localization {
+			self.invoice_localization = localization.clone();
+		}
+		if let Some(localization) = &customer.date_localization {
+			self.date_localization = localization.clone();
+		}
+		if let Some(currency) = customer.currency() {
+			self.currency = Some(currency.clone());
+		}
+	}
+
+	/// Get the first month of the fiscal year.
+	pub fn fiscal_year_start(&self) -> zzp::gregorian::Month {
+		self.tax.fiscal_year_start.unwrap_or(zzp::gregorian::Month::January)
+	}
+
+	/// Get the dates of a fiscal year as half-open range.
+	pub fn fiscal_year(&self, year: zzp::gregorian::Year) -> std::ops::Range<zzp::gregorian::Date> {
+		zzp::calendar::fiscal_year(year, self.fiscal_year_start())
+	}
+
+	/// Get the fiscal year of a date.
+	pub fn fiscal_year_of(&self, date: zzp::gregorian::Date) -> zzp::gregorian::Year {
+		zzp::calendar::fiscal_year_of(date, self.fiscal_year_start())
+	}
+
+	/// Get the name of a fiscal year, like `2024`, or `2024/2025` if the fiscal year is not a calendar year.
+	pub fn fiscal_year_name(&self, year: zzp::gregorian::Year) -> String {
+		if self.fiscal_year_start() == zzp::gregorian::Month::January {
+			year.to_string()
+		} else {
+			format!("{}/{}", year, year.next())
+		}
+	}
+
+	/// Get the font family list for a text role of generated invoices and quotes.
+	///
+	/// This uses the font from the branding for the role if set, or the invoice font otherwise.
+	pub fn font_family(&self, role: FontRole) -> String {
+		let fonts = self.company.branding.as_ref().map(|x| &x.fonts);
+		let font = fonts.and_then(|fonts| match role {
+			FontRole::Title => fonts.title.as_deref(),
+			FontRole::Heading => fonts.heading.as_deref(),
+			FontRole::Body => fonts.body.as_deref(),
+			FontRole::Footer => fonts.footer.as_deref(),
+		});
+		self.invoice.font_family_with(font.unwrap_or(&self.invoice.font))
+	}
+
+	/// Get the country code of the company.
+	///
+	/// This is the country code from the `[EInvoice]` section, or `NL` if that section is missing.
+	pub fn company_country_code(&self) -> &str {
+		self.e_invoice.as_ref().map(|x| x.country_code.as_str()).unwrap_or("NL")
+	}
+
+	/// Get the currency for invoices.
+	///
+	/// If no currency is configured, this uses euros with the currency symbol from the invoice localization.
+	pub fn currency(&self) -> Currency {
+		self.currency.clone().unwrap_or_else(|| Currency {
+			code: "EUR".into(),
+			symbol: self.invoice_localization.currency_symbol.clone(),
+			symbol_space: None,
+			decimal_separator: None,
+			thousands_separator: None,
+			locale: None,
+		})
+	}
+}
+
+/// The ISO 3166-1 alpha-2 country codes of the EU member states.
+pub const EU_COUNTRY_CODES: &[&str] = &[
+	"AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU",
+	"IE", "IT", "LT", "LU", "LV", "MT", "NL", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+impl Customer {
+	/// Check if the customer is a business in another EU country than the company.
+	///
+	/// This requires both a VAT number and a country code for the customer.
+	pub fn is_intra_eu(&self, company_country_code: &str) -> bool {
+		let country_code = match (&self.vat_number, &self.country_code) {
+			(Some(_), Some(country_code)) => country_code.to_ascii_uppercase(),
+			_ => return false,
+		};
+		!country_code.eq_ignore_ascii_case(company_country_code) && EU_COUNTRY_CODES.contains(&country_code.as_str())
+	}
+}
+
+impl Invoice {
+	/// Get the font family list for generated invoices, with the fallback fonts after the main font.
+	pub fn font_family(&self) -> String {
+		self.font_family_with(&self.font)
+	}
+
+	/// Get the font family list with the given main font, followed by the fallback fonts.
+	fn font_family_with(&self, font: &str) -> String {
+		std::iter::once(font)
+			.chain(self.fallback_fonts.iter().map(|x| x.as_str()))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+}
+
+impl InvoiceLocalization {
+	/// Get the watermark for draft invoices.
+	pub fn draft(&self) -> &str {
+		self.draft.as_deref().unwrap_or("CONCEPT")
+	}
+
+	/// Get the label for the customer reference.
+	pub fn reference(&self) -> &str {
+		self.reference.as_deref().unwrap_or("Reference")
+	}
+
+	/// Get the label for the subtotal at the bottom of a page that continues on the next page.
+	pub fn carried_forward(&self) -> &str {
+		self.carried_forward.as_deref().unwrap_or("Carried forward")
+	}
+
+	/// Get the label for the subtotal of the previous pages at the top of a page.
+	pub fn brought_forward(&self) -> &str {
+		self.brought_forward.as_deref().unwrap_or("Brought forward")
+	}
+}
+
+impl PeriodConfig {
+	/// Get the period as half-open date range.
+	pub fn as_range(&self) -> std::ops::Range<zzp::gregorian::Date> {
+		self.start_date..self.end_date.next()
+	}
+}
+
+impl TagConfig {
+	/// Get the tag details with the details that are set in `other` replacing those of `self`.
+	pub fn overridden_by(&self, other: &TagConfig) -> TagConfig {
+		TagConfig {
+			name: other.name.clone(),
+			price_per_hour: other.price_per_hour.or(self.price_per_hour),
+			summarize_per_day: other.summarize_per_day.clone().or_else(|| self.summarize_per_day.clone()),
+			vat: other.vat.or(self.vat),
+			surcharge: other.surcharge.or(self.surcharge),
+			discount: other.discount.clone().or_else(|| self.discount.clone()),
+			section: other.section.clone().or_else(|| self.section.clone()),
+		}
+	}
+}
+
+impl SurchargeRule {
+	/// Check if the surcharge applies to a date.
+	pub fn applies_to(&self, date: zzp::gregorian::Date) -> bool {
+		self.days.iter().any(|day| day.matches(date))
+	}
+}
+
+impl SurchargeDay {
+	/// Check if a date is on this day.
+	pub fn matches(self, date: zzp::gregorian::Date) -> bool {
+		let weekday = match self {
+			Self::Monday => 1,
+			Self::Tuesday => 2,
+			Self::Wednesday => 3,
+			Self::Thursday => 4,
+			Self::Friday => 5,
+			Self::Saturday => 6,
+			Self::Sunday => 7,
+			Self::PublicHoliday => return zzp::calendar::is_holiday(date),
+		};
+		zzp::calendar::weekday(date) == weekday
 	}
 }
 
 impl CustomerConfig {
+	/// Create a customer configuration with only the required details.
+	///
+	/// The other details can be added by editing the configuration file.
+	pub fn new(name: String, grootboek_name: String, price_per_hour: NotNan<f64>) -> Self {
+		Self {
+			version: migrate::CONFIG_VERSION,
+			customer: Customer {
+				name,
+				address: Vec::new(),
+				grootboek_name,
+				email: None,
+				peppol_id: None,
+				oin: None,
+				delivery: None,
+				vat_number: None,
+				country_code: None,
+				code: None,
+				hours_path: None,
+			},
+			invoice: CustomerInvoice {
+				price_per_hour: price_per_hour.into(),
+				summarize_per_day: None,
+				discount: None,
+				quantity_format: None,
+				payment_days: None,
+				notes: Vec::new(),
+				reference: None,
+				number_format: None,
+				currency: None,
+			},
+			tag: Vec::new(),
+			fixed_entry: Vec::new(),
+			expense: Vec::new(),
+			surcharge: Vec::new(),
+			recurring: Vec::new(),
+			period: Vec::new(),
+			calendar_rule: Vec::new(),
+			e_invoice: None,
+			invoice_localization: None,
+			date_localization: None,
+			currency: None,
+		}
+	}
+
+	/// Create an example customer configuration with placeholder details.
+	///
+	/// Use [`Self::to_commented_toml`] to write it as configuration file with all available options.
+	pub fn example() -> Self {
+		let mut config = Self::new("Example Customer".into(), "example-customer".into(), NotNan::new(100.0).unwrap());
+		config.customer.address = vec!["Street 2".into(), "5678 CD Town".into()];
+		config.customer.email = Some("invoices@example.com".into());
+		config
+	}
+
+	/// Serialize the configuration as TOML, with the documentation of every field as comment.
+	///
+	/// Options that are not set are included as comment too.
+	pub fn to_commented_toml(&self) -> Result<String, String> {
+		example::to_commented_toml(self)
+	}
+
 	/// Find the customer configuration file by searching the filesystem.
 	///
 	/// This looks for `customer.toml` in the start dir and each parent dir until it is found,
@@ -273,8 +1666,169 @@ impl CustomerConfig {
 	}
 
 	/// Parse a file as customer configuration.
+	///
+	/// The file can include other files, see [`read_toml_with_includes`].
 	pub fn read_file(path: impl AsRef<Path>) -> Result<Self, ReadFileError> {
-		read_toml(path)
+		read_toml_with_includes(path, migrate::ConfigKind::Customer)
+	}
+
+	/// Get the currency of the customer, if it has its own currency.
+	pub fn currency(&self) -> Option<&Currency> {
+		self.invoice.currency.as_ref().or(self.currency.as_ref())
+	}
+
+	/// Write the customer configuration to a file.
+	pub fn write_file(&self, path: impl AsRef<Path>) -> Result<(), WriteFileError> {
+		write_toml_atomic(path.as_ref(), self)
+	}
+
+	/// Get the hour log for entries on a date.
+	///
+	/// The `customer_dir` is the directory containing `customer.toml`.
+	/// The file does not need to exist.
+	pub fn hours_file(&self, customer_dir: impl AsRef<Path>, date: zzp::gregorian::Date) -> Result<PathBuf, String> {
+		let path = expand_hours_path(self.hours_path(), |x| x.to_string(), |placeholder| match placeholder {
+			"year" => Some(date.year().to_string()),
+			"month" => Some(format!("{:02}", date.month().to_number())),
+			_ => None,
+		})?;
+		Ok(customer_dir.as_ref().join(path))
+	}
+
+	/// Find all existing hour logs of the customer, sorted by path.
+	///
+	/// The `customer_dir` is the directory containing `customer.toml`.
+	/// If the hours path has placeholders, this finds the hour log of every year or month.
+	pub fn hours_files(&self, customer_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, String> {
+		let path = customer_dir.as_ref().join(self.hours_path());
+
+		// Walk the components of the path, matching the components with placeholders against the directory entries.
+		let mut found = vec![PathBuf::new()];
+		for component in path.components() {
+			let component = component.as_os_str().to_string_lossy();
+			if !component.contains('{') {
+				found.iter_mut().for_each(|x| x.push(&*component));
+				continue;
+			}
+			let regex = expand_hours_path(&component, regex::escape, |placeholder| match placeholder {
+				"year" => Some(r"\d{4}".into()),
+				"month" => Some(r"\d{2}".into()),
+				_ => None,
+			})?;
+			let regex = regex::Regex::new(&format!("^{}$", regex))
+				.map_err(|e| format!("invalid hours path {:?}: {}", self.hours_path(), e))?;
+			let mut matches = Vec::new();
+			for dir in found.iter().filter(|x| x.is_dir()) {
+				let entries = std::fs::read_dir(dir)
+					.map_err(|e| format!("failed to read directory {}: {}", dir.display(), e))?;
+				for entry in entries {
+					let entry = entry
+						.map_err(|e| format!("failed to read directory {}: {}", dir.display(), e))?;
+					if regex.is_match(&entry.file_name().to_string_lossy()) {
+						matches.push(entry.path());
+					}
+				}
+			}
+			found = matches;
+		}
+
+		found.retain(|x| x.is_file());
+		found.sort();
+		Ok(found)
+	}
+
+	/// Read the entries of all hour logs of the customer, sorted by date.
+	///
+	/// The `customer_dir` is the directory containing `customer.toml`.
+	pub fn read_hours(&self, customer_dir: impl AsRef<Path>) -> Result<Vec<zzp::uurlog::Entry>, String> {
+		let mut entries = Vec::new();
+		for path in self.hours_files(customer_dir)? {
+			let parsed = zzp::uurlog::parse_file(&path)
+				.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+			entries.extend(parsed);
+		}
+		entries.sort_by_key(|x| x.date);
+		Ok(entries)
+	}
+
+	/// Get the path of the hour log, possibly with placeholders.
+	fn hours_path(&self) -> &str {
+		self.customer.hours_path.as_deref().unwrap_or(DEFAULT_HOURS_PATH)
+	}
+
+	/// Find all customer configuration files below a directory.
+	///
+	/// Hidden directories are skipped.
+	/// The returned paths are sorted.
+	pub fn find_all(root_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, String> {
+		let mut found = Vec::new();
+		let mut dirs = vec![root_dir.as_ref().to_path_buf()];
+		while let Some(dir) = dirs.pop() {
+			let entries = std::fs::read_dir(&dir)
+				.map_err(|e| format!("failed to read directory {}: {}", dir.display(), e))?;
+			for entry in entries {
+				let entry = entry
+					.map_err(|e| format!("failed to read directory {}: {}", dir.display(), e))?;
+				let file_name = entry.file_name();
+				let file_type = entry.file_type()
+					.map_err(|e| format!("failed to determine file type of {}: {}", entry.path().display(), e))?;
+				if file_type.is_dir() && !file_name.to_string_lossy().starts_with('.') {
+					dirs.push(entry.path());
+				} else if file_type.is_file() && file_name == "customer.toml" {
+					found.push(entry.path());
+				}
+			}
+		}
+		found.sort();
+		Ok(found)
+	}
+}
+
+impl Currency {
+	/// Format an amount of money with the currency symbol and separators.
+	///
+	/// Explicitly configured separators take precedence over the ones from the locale.
+	pub fn format(&self, amount: zzp::grootboek::Cents) -> String {
+		let cents = amount.total_cents();
+		let sign = if cents < 0 { "-" } else { "" };
+		let cents = cents.unsigned_abs();
+
+		let (decimal_separator, thousands_separator, symbol_after) = match self.locale {
+			None => (".", "", false),
+			Some(CurrencyLocale::En) => (".", ",", false),
+			Some(CurrencyLocale::Nl) => (",", ".", false),
+			Some(CurrencyLocale::De) => (",", ".", true),
+			Some(CurrencyLocale::Fr) => (",", "\u{202F}", true),
+		};
+		let decimal_separator = self.decimal_separator.as_deref().unwrap_or(decimal_separator);
+		let thousands_separator = self.thousands_separator.as_deref().unwrap_or(thousands_separator);
+
+		let whole = (cents / 100).to_string();
+		let mut grouped = String::with_capacity(whole.len() * 2);
+		for (i, digit) in whole.chars().enumerate() {
+			if i > 0 && i % 3 == whole.len() % 3 {
+				grouped.push_str(thousands_separator);
+			}
+			grouped.push(digit);
+		}
+
+		let space = if self.symbol_space.unwrap_or(true) { " " } else { "" };
+		if symbol_after {
+			format!("{}{}{}{:02}{}{}", sign, grouped, decimal_separator, cents % 100, space, self.symbol)
+		} else {
+			format!("{}{}{}{}{}{:02}", self.symbol, space, sign, grouped, decimal_separator, cents % 100)
+		}
+	}
+}
+
+impl Discount {
+	/// Get the value of the discount.
+	pub fn value(&self) -> Result<invoice::DiscountValue, String> {
+		match (self.percentage, self.amount) {
+			(Some(percentage), None) => Ok(invoice::DiscountValue::Percentage(percentage)),
+			(None, Some(amount)) => Ok(invoice::DiscountValue::Amount(amount)),
+			_ => Err(format!("discount {:?} must have exactly one of `percentage` or `amount`", self.description)),
+		}
 	}
 }
 
@@ -283,6 +1837,9 @@ pub enum ReadFileError {
 	Open(PathBuf, std::io::Error),
 	Read(PathBuf, std::io::Error),
 	Toml(PathBuf, toml::de::Error),
+	Include(PathBuf, String),
+	Migrate(PathBuf, String),
+	Config(PathBuf, diagnostics::ConfigError),
 }
 
 impl std::error::Error for ReadFileError {}
@@ -292,6 +1849,9 @@ impl std::fmt::Display for ReadFileError {
 			Self::Open(path, error) => write!(f, "failed to open {} for reading: {}", path.display(), error),
 			Self::Read(path, error) => write!(f, "failed to read from {}: {}", path.display(), error),
 			Self::Toml(path, error) => write!(f, "failed to parse {}: {}", path.display(), error),
+			Self::Include(path, error) => write!(f, "failed to include files in {}: {}", path.display(), error),
+			Self::Migrate(path, error) => write!(f, "failed to read {}: {}", path.display(), error),
+			Self::Config(path, error) => write!(f, "failed to parse {}: {}", path.display(), error),
 		}
 	}
 }
@@ -308,3 +1868,232 @@ pub fn read_toml<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> Resu
 	toml::from_slice(&bytes)
 		.map_err(|e| ReadFileError::Toml(path.into(), e))
 }
+
+/// Read a TOML file merged over the files it includes.
+///
+/// A file can include other files with a top-level `include` key,
+/// which is a path or a list of paths relative to the directory of the file.
+/// Included files can include other files themselves.
+///
+/// The included files are merged in order, and the file itself is merged over them.
+/// Tables are merged key by key, all other values (including arrays) replace the included value.
+/// So an included `[Invoice]` table can be extended or partially overridden,
+/// but `[[Account]]` entries from the file replace the included entries.
+///
+/// Each file is migrated to the current configuration version before it is merged, see [`migrate`].
+pub fn read_toml_with_includes<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>, kind: migrate::ConfigKind) -> Result<T, ReadFileError> {
+	let path = path.as_ref();
+	let table = read_toml_table(path, kind, &mut Vec::new())?;
+	toml::Value::Table(table.clone()).try_into()
+		.map_err(|e| ReadFileError::Config(path.into(), diagnostics::diagnose(e, &table)))
+}
+
+/// Read a TOML file as table and merge it over the files it includes.
+///
+/// The `stack` holds the files that are being read, to detect include cycles.
+fn read_toml_table(path: &Path, kind: migrate::ConfigKind, stack: &mut Vec<PathBuf>) -> Result<toml::value::Table, ReadFileError> {
+	let canonical = path.canonicalize()
+		.map_err(|e| ReadFileError::Open(path.into(), e))?;
+	if stack.contains(&canonical) {
+		return Err(ReadFileError::Include(path.into(), "the file includes itself".into()));
+	}
+
+	let mut table: toml::value::Table = read_toml(path)?;
+	migrate::migrate(kind, &mut table)
+		.map_err(|e| ReadFileError::Migrate(path.into(), e))?;
+	let includes = match table.remove("include") {
+		None => Vec::new(),
+		Some(toml::Value::String(include)) => vec![include],
+		Some(toml::Value::Array(includes)) => includes.into_iter()
+			.map(|x| match x {
+				toml::Value::String(include) => Ok(include),
+				_ => Err(ReadFileError::Include(path.into(), "`include` must be a path or a list of paths".into())),
+			})
+			.collect::<Result<_, _>>()?,
+		Some(_) => return Err(ReadFileError::Include(path.into(), "`include` must be a path or a list of paths".into())),
+	};
+	if includes.is_empty() {
+		return Ok(table);
+	}
+
+	stack.push(canonical);
+	let dir = path.parent().unwrap_or_else(|| Path::new(""));
+	let mut merged = toml::value::Table::new();
+	for include in includes {
+		merge_toml_table(&mut merged, read_toml_table(&dir.join(include), kind, stack)?);
+	}
+	stack.pop();
+
+	merge_toml_table(&mut merged, table);
+	Ok(merged)
+}
+
+/// Merge a TOML table over another one.
+///
+/// Tables are merged recursively, all other values replace the value in the base table.
+fn merge_toml_table(base: &mut toml::value::Table, table: toml::value::Table) {
+	for (key, value) in table {
+		match (base.get_mut(&key), value) {
+			(Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge_toml_table(base, value),
+			(_, value) => {
+				base.insert(key, value);
+			},
+		}
+	}
+}
+
+/// Expand the placeholders in an hours path.
+///
+/// The literal text is passed through `literal` and each placeholder is replaced with the result of `replace`.
+fn expand_hours_path(path: &str, literal: impl Fn(&str) -> String, replace: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+	let mut output = String::with_capacity(path.len());
+	let mut rest = path;
+	while let Some(start) = rest.find('{') {
+		output.push_str(&literal(&rest[..start]));
+		let end = rest[start..].find('}')
+			.ok_or_else(|| format!("unterminated placeholder in hours path: {:?}", path))?;
+		let placeholder = &rest[start + 1..start + end];
+		let value = replace(placeholder)
+			.ok_or_else(|| format!("unknown placeholder in hours path: {{{}}}", placeholder))?;
+		output.push_str(&value);
+		rest = &rest[start + end + 1..];
+	}
+	output.push_str(&literal(rest));
+	Ok(output)
+}
+
+/// Write a value as TOML to a temporary file and move it over the destination.
+///
+/// This ensures the destination is never left with partially written data.
+pub(crate) fn write_toml_atomic<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), WriteFileError> {
+	let data = toml::to_string(value)
+		.map_err(|e| WriteFileError::Serialize(path.into(), e))?;
+	write_atomic(path, &data)
+}
+
+/// Write serialized TOML to a temporary file and move it over the destination.
+pub(crate) fn write_atomic(path: &Path, data: &str) -> Result<(), WriteFileError> {
+	let tmp_path = path.with_extension("toml.tmp");
+	std::fs::write(&tmp_path, data)
+		.map_err(|e| WriteFileError::Write(tmp_path.clone(), e))?;
+	std::fs::rename(&tmp_path, path)
+		.map_err(|e| WriteFileError::Rename(tmp_path, path.into(), e))
+}
+
+#[derive(Debug)]
+pub enum WriteFileError {
+	Serialize(PathBuf, toml::ser::Error),
+	Write(PathBuf, std::io::Error),
+	Rename(PathBuf, PathBuf, std::io::Error),
+}
+
+impl std::error::Error for WriteFileError {}
+impl std::fmt::Display for WriteFileError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Serialize(path, error) => write!(f, "failed to serialize data for {}: {}", path.display(), error),
+			Self::Write(path, error) => write!(f, "failed to write to {}: {}", path.display(), error),
+			Self::Rename(from, to, error) => write!(f, "failed to rename {} to {}: {}", from.display(), to.display(), error),
+		}
+	}
+}
+
+/// Serialize and deserialize rounding modes as strings like `half_even`.
+pub(crate) mod serde_rounding_mode {
+	use zzp::grootboek::RoundingMode;
+
+	pub fn serialize<S: serde::Serializer>(mode: &RoundingMode, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(mode)
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<RoundingMode, D::Error> {
+		let value: String = serde::Deserialize::deserialize(deserializer)?;
+		value.parse()
+			.map_err(|e| serde::de::Error::custom(format!("{}: {:?}", e, value)))
+	}
+}
+
+/// Serialize and deserialize dates as `yyyy-mm-dd` strings.
+pub(crate) mod serde_date {
+	use zzp::gregorian::Date;
+
+	pub fn serialize<S: serde::Serializer>(date: &Date, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(date)
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
+		let value: String = serde::Deserialize::deserialize(deserializer)?;
+		value.parse()
+			.map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Str(&value), &"yyyy-mm-dd"))
+	}
+}
+
+/// Serialize and deserialize optional months as month numbers.
+pub(crate) mod serde_option_month {
+	use zzp::gregorian::Month;
+
+	pub fn serialize<S: serde::Serializer>(month: &Option<Month>, serializer: S) -> Result<S::Ok, S::Error> {
+		match month {
+			Some(month) => serializer.serialize_u8(month.to_number()),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Month>, D::Error> {
+		let number: u8 = serde::Deserialize::deserialize(deserializer)?;
+		Month::new(number)
+			.map(Some)
+			.map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Unsigned(number.into()), &"a month number from 1 to 12"))
+	}
+}
+
+/// Serialize and deserialize optional dates as `yyyy-mm-dd` strings.
+pub(crate) mod serde_option_date {
+	use zzp::gregorian::Date;
+
+	pub fn serialize<S: serde::Serializer>(date: &Option<Date>, serializer: S) -> Result<S::Ok, S::Error> {
+		match date {
+			Some(date) => serializer.collect_str(date),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Date>, D::Error> {
+		super::serde_date::deserialize(deserializer).map(Some)
+	}
+}
+
+/// Serialize and deserialize lists of dates as `yyyy-mm-dd` strings.
+pub(crate) mod serde_dates {
+	use zzp::gregorian::Date;
+
+	pub fn serialize<S: serde::Serializer>(dates: &[Date], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(dates.iter().map(|x| x.to_string()))
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Date>, D::Error> {
+		let values: Vec<String> = serde::Deserialize::deserialize(deserializer)?;
+		values.iter()
+			.map(|value| value.parse().map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Str(value), &"yyyy-mm-dd")))
+			.collect()
+	}
+}
+
+/// Serialize and deserialize optional hours like `7h30m`.
+pub(crate) mod serde_option_hours {
+	use zzp::uurlog::Hours;
+
+	pub fn serialize<S: serde::Serializer>(hours: &Option<Hours>, serializer: S) -> Result<S::Ok, S::Error> {
+		match hours {
+			Some(hours) => serializer.collect_str(hours),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Hours>, D::Error> {
+		let value: String = serde::Deserialize::deserialize(deserializer)?;
+		Hours::from_str(&value)
+			.map(Some)
+			.map_err(serde::de::Error::custom)
+	}
+}
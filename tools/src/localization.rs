@@ -0,0 +1,176 @@
+//! Built-in localization presets for invoices and dates.
+//!
+//! A localization section in `zzp.toml` or `customer.toml` can be the name of a preset:
+//!
+//! ```toml
+//! InvoiceLocalization = "nl"
+//! DateLocalization = "nl"
+//! ```
+//!
+//! Or a table with a preset and the fields to override:
+//!
+//! ```toml
+//! [InvoiceLocalization]
+//! preset = "en"
+//! footer = "Please pay within 14 days."
+//! ```
+//!
+//! A table without a preset must contain all required fields.
+
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, Error};
+use serde::Serialize;
+
+use crate::{DateLocalization, InvoiceLocalization};
+
+/// The languages with a built-in preset.
+pub const PRESETS: &[&str] = &["nl", "en", "de", "fr"];
+
+impl InvoiceLocalization {
+	/// Get the built-in invoice localization for a language, like `nl`.
+	pub fn preset(language: &str) -> Option<Self> {
+		let (invoice, to, from, invoice_number, invoice_date, date, description, quantity, entry_unit_price, entry_total_price) = match language {
+			"nl" => ("Factuur", "Aan", "Van", "Factuurnummer", "Factuurdatum", "Datum", "Omschrijving", "Aantal", "Prijs per eenheid", "Totaalprijs"),
+			"en" => ("Invoice", "To", "From", "Invoice number", "Invoice date", "Date", "Description", "Quantity", "Price per unit", "Total price"),
+			"de" => ("Rechnung", "An", "Von", "Rechnungsnummer", "Rechnungsdatum", "Datum", "Beschreibung", "Menge", "Einzelpreis", "Gesamtpreis"),
+			"fr" => ("Facture", "À", "De", "Numéro de facture", "Date de facture", "Date", "Description", "Quantité", "Prix unitaire", "Prix total"),
+			_ => return None,
+		};
+		let (vat, total_ex_vat, total_vat, total_due, subtotal, vat_number, draft, reference, carried_forward, brought_forward, due_date, hours) = match language {
+			"nl" => ("Btw", "Totaal exclusief btw", "Totaal btw", "Totaal te voldoen", "Subtotaal", "Btw-nummer", "CONCEPT", "Referentie", "Transport", "Transport", "Vervaldatum", "uur"),
+			"en" => ("VAT", "Total excluding VAT", "Total VAT", "Total due", "Subtotal", "VAT number", "DRAFT", "Reference", "Carried forward", "Brought forward", "Due date", "hours"),
+			"de" => ("MwSt.", "Summe netto", "Summe MwSt.", "Rechnungsbetrag", "Zwischensumme", "USt-IdNr.", "ENTWURF", "Referenz", "Übertrag", "Übertrag", "Fälligkeitsdatum", "Stunden"),
+			"fr" => ("TVA", "Total HT", "Total TVA", "Total TTC", "Sous-total", "Numéro de TVA", "BROUILLON", "Référence", "À reporter", "Report", "Date d'échéance", "heures"),
+			_ => return None,
+		};
+		let (kor_notice, reverse_charge_notice, footer) = match language {
+			"nl" => (
+				"Vrijgesteld van btw op grond van de kleineondernemersregeling.",
+				"Btw verlegd naar de afnemer (intracommunautaire prestatie).",
+				"Gelieve het totaalbedrag binnen de betalingstermijn over te maken onder vermelding van het factuurnummer.",
+			),
+			"en" => (
+				"Exempt from VAT under the small business scheme.",
+				"VAT reverse charged to the customer (intra-community supply).",
+				"Please pay the total amount within the payment term, stating the invoice number.",
+			),
+			"de" => (
+				"Umsatzsteuerbefreit nach der Kleinunternehmerregelung.",
+				"Steuerschuldnerschaft des Leistungsempfängers (innergemeinschaftliche Leistung).",
+				"Bitte überweisen Sie den Rechnungsbetrag innerhalb der Zahlungsfrist unter Angabe der Rechnungsnummer.",
+			),
+			"fr" => (
+				"Exonéré de TVA au titre du régime des petites entreprises.",
+				"Autoliquidation de la TVA par le preneur (prestation intracommunautaire).",
+				"Merci de régler le montant total dans le délai de paiement en indiquant le numéro de facture.",
+			),
+			_ => return None,
+		};
+		Some(Self {
+			invoice: invoice.into(),
+			to: to.into(),
+			from: from.into(),
+			invoice_number: invoice_number.into(),
+			invoice_date: invoice_date.into(),
+			date: date.into(),
+			description: description.into(),
+			quantity: quantity.into(),
+			entry_unit_price: entry_unit_price.into(),
+			entry_total_price: entry_total_price.into(),
+			vat: vat.into(),
+			total_ex_vat: total_ex_vat.into(),
+			total_vat: total_vat.into(),
+			total_due: total_due.into(),
+			subtotal: Some(subtotal.into()),
+			kor_notice: Some(kor_notice.into()),
+			vat_number: Some(vat_number.into()),
+			reverse_charge_notice: Some(reverse_charge_notice.into()),
+			draft: Some(draft.into()),
+			reference: Some(reference.into()),
+			carried_forward: Some(carried_forward.into()),
+			brought_forward: Some(brought_forward.into()),
+			due_date: Some(due_date.into()),
+			hours: hours.into(),
+			currency_symbol: "€".into(),
+			footer: footer.into(),
+		})
+	}
+}
+
+impl DateLocalization {
+	/// Get the built-in date localization for a language, like `nl`.
+	pub fn preset(language: &str) -> Option<Self> {
+		let months = match language {
+			"nl" => ["januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september", "oktober", "november", "december"],
+			"en" => ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"],
+			"de" => ["Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November", "Dezember"],
+			"fr" => ["janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre", "décembre"],
+			_ => return None,
+		};
+		let [january, february, march, april, may, june, july, august, september, october, november, december] = months.map(String::from);
+		Some(Self {
+			january,
+			february,
+			march,
+			april,
+			may,
+			june,
+			july,
+			august,
+			september,
+			october,
+			november,
+			december,
+		})
+	}
+}
+
+/// Deserialize an invoice localization that may use a preset.
+pub(crate) fn deserialize_invoice<'de, D: Deserializer<'de>>(deserializer: D) -> Result<InvoiceLocalization, D::Error> {
+	with_preset(toml::Value::deserialize(deserializer)?, InvoiceLocalization::preset)
+}
+
+/// Deserialize an optional invoice localization that may use a preset.
+pub(crate) fn deserialize_invoice_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<InvoiceLocalization>, D::Error> {
+	deserialize_invoice(deserializer).map(Some)
+}
+
+/// Deserialize a date localization that may use a preset.
+pub(crate) fn deserialize_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateLocalization, D::Error> {
+	with_preset(toml::Value::deserialize(deserializer)?, DateLocalization::preset)
+}
+
+/// Deserialize an optional date localization that may use a preset.
+pub(crate) fn deserialize_date_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateLocalization>, D::Error> {
+	deserialize_date(deserializer).map(Some)
+}
+
+/// Resolve a localization from the name of a preset, or a table with an optional preset and overrides.
+fn with_preset<T, E>(value: toml::Value, preset: fn(&str) -> Option<T>) -> Result<T, E>
+where
+	T: Serialize + DeserializeOwned,
+	E: Error,
+{
+	let load_preset = |name: &str| -> Result<toml::value::Table, E> {
+		let preset = preset(name)
+			.ok_or_else(|| E::custom(format!("unknown localization preset {:?}, expected one of: {}", name, PRESETS.join(", "))))?;
+		match toml::Value::try_from(preset).map_err(E::custom)? {
+			toml::Value::Table(table) => Ok(table),
+			_ => unreachable!("localization presets serialize to a table"),
+		}
+	};
+
+	let table = match value {
+		toml::Value::String(name) => load_preset(&name)?,
+		toml::Value::Table(mut overrides) => match overrides.remove("preset") {
+			None => overrides,
+			Some(toml::Value::String(name)) => {
+				let mut table = load_preset(&name)?;
+				table.extend(overrides);
+				table
+			},
+			Some(_) => return Err(E::custom("the localization preset must be a string")),
+		},
+		_ => return Err(E::custom("expected the name of a localization preset or a table")),
+	};
+	toml::Value::Table(table).try_into().map_err(E::custom)
+}
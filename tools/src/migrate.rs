@@ -0,0 +1,304 @@
+//! Versioning and migration of configuration files.
+//!
+//! Both `zzp.toml` and `customer.toml` have a top-level `version` with the version of the configuration format.
+//! Files without a version are treated as version 0.
+//!
+//! When a field is renamed or restructured, the version is increased and a migration is added.
+//! Old files are migrated in memory when they are read, and `zzp config migrate` rewrites them on disk.
+
+use std::path::Path;
+use toml::value::Table;
+use toml_edit::DocumentMut;
+
+/// The current version of the configuration format.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// The kind of a configuration file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConfigKind {
+	/// The main configuration file, `zzp.toml`.
+	Zzp,
+
+	/// A customer configuration file, `customer.toml`.
+	Customer,
+}
+
+/// A migration from one version of the configuration format to the next.
+struct Migration {
+	/// The version that the migration upgrades from.
+	from: u32,
+
+	/// A description of the changes.
+	description: &'static str,
+
+	/// The kinds of configuration files that the migration applies to.
+	kinds: &'static [ConfigKind],
+
+	/// The changes made by the migration.
+	changes: &'static [Change],
+}
+
+/// A single change made by a migration.
+///
+/// Changes are described as data so they can be applied both to a parsed table when a file is read,
+/// and to the file itself by [`migrate_file`] without losing comments and formatting.
+enum Change {
+	/// Rename a key in a section.
+	RenameKey {
+		section: &'static str,
+		old: &'static str,
+		new: &'static str,
+	},
+}
+
+/// All migrations, sorted by version.
+const MIGRATIONS: &[Migration] = &[
+	Migration {
+		from: 0,
+		description: "rename `payment_term_days` to `payment_days` in the [Invoice] section",
+		kinds: &[ConfigKind::Zzp, ConfigKind::Customer],
+		changes: &[
+			Change::RenameKey { section: "Invoice", old: "payment_term_days", new: "payment_days" },
+		],
+	},
+];
+
+/// Get the version of a configuration file.
+pub fn config_version(table: &Table) -> Result<u32, String> {
+	match table.get("version") {
+		None => Ok(0),
+		Some(toml::Value::Integer(version)) => parse_version(*version),
+		Some(_) => Err("the configuration version must be a number".into()),
+	}
+}
+
+/// Get the version of a configuration file that is being edited.
+fn document_version(document: &DocumentMut) -> Result<u32, String> {
+	match document.get("version") {
+		None => Ok(0),
+		Some(item) => match item.as_integer() {
+			Some(version) => parse_version(version),
+			None => Err("the configuration version must be a number".into()),
+		},
+	}
+}
+
+/// Check that a configuration version is valid and supported.
+fn parse_version(version: i64) -> Result<u32, String> {
+	let version = u32::try_from(version)
+		.map_err(|_| format!("invalid configuration version: {}", version))?;
+	if version > CONFIG_VERSION {
+		return Err(format!(
+			"the configuration has version {}, but this version of zzp only supports up to version {}: please upgrade zzp",
+			version,
+			CONFIG_VERSION,
+		));
+	}
+	Ok(version)
+}
+
+/// Apply the migrations for a configuration file of the given kind and version.
+///
+/// The `apply` function applies a single change, returning true if anything changed.
+fn apply_migrations(kind: ConfigKind, version: u32, mut apply: impl FnMut(&Change) -> Result<bool, String>) -> Result<Vec<String>, String> {
+	let mut changes = Vec::new();
+	for migration in MIGRATIONS.iter().filter(|x| x.from >= version && x.kinds.contains(&kind)) {
+		let mut changed = false;
+		for change in migration.changes {
+			changed |= apply(change)
+				.map_err(|e| format!("failed to migrate from version {}: {}", migration.from, e))?;
+		}
+		if changed {
+			changes.push(migration.description.to_string());
+		}
+	}
+	if version != CONFIG_VERSION {
+		changes.push(format!("set the configuration version to {}", CONFIG_VERSION));
+	}
+	Ok(changes)
+}
+
+/// Migrate a configuration file to the current version.
+///
+/// Returns the descriptions of the changes, which is empty if the file is already up to date.
+/// Files written for a newer version of the configuration format are rejected.
+pub fn migrate(kind: ConfigKind, table: &mut Table) -> Result<Vec<String>, String> {
+	let version = config_version(table)?;
+	let changes = apply_migrations(kind, version, |change| match *change {
+		Change::RenameKey { section, old, new } => rename_key(table, section, old, new),
+	})?;
+	if version != CONFIG_VERSION {
+		table.insert("version".into(), toml::Value::Integer(CONFIG_VERSION.into()));
+	}
+	Ok(changes)
+}
+
+/// Migrate a configuration file that is being edited to the current version.
+///
+/// Only the affected keys are touched: comments, formatting and the order of keys are preserved.
+fn migrate_document(kind: ConfigKind, document: &mut DocumentMut) -> Result<Vec<String>, String> {
+	let version = document_version(document)?;
+	let changes = apply_migrations(kind, version, |change| match *change {
+		Change::RenameKey { section, old, new } => rename_document_key(document, section, old, new),
+	})?;
+	if version != CONFIG_VERSION {
+		document.insert("version", toml_edit::value(i64::from(CONFIG_VERSION)));
+	}
+	Ok(changes)
+}
+
+/// Rename a key in a section of a configuration file.
+///
+/// It is an error if the section has both the old and the new key.
+fn rename_key(table: &mut Table, section: &str, old: &str, new: &str) -> Result<bool, String> {
+	let section_table = match table.get_mut(section) {
+		Some(toml::Value::Table(x)) => x,
+		_ => return Ok(false),
+	};
+	let value = match section_table.remove(old) {
+		Some(x) => x,
+		None => return Ok(false),
+	};
+	if section_table.contains_key(new) {
+		return Err(format!("the [{}] section has both `{}` and `{}`", section, old, new));
+	}
+	section_table.insert(new.into(), value);
+	Ok(true)
+}
+
+/// Rename a key in a section of a configuration file that is being edited.
+///
+/// The renamed key keeps its position, comments and formatting.
+/// It is an error if the section has both the old and the new key.
+fn rename_document_key(document: &mut DocumentMut, section: &str, old: &str, new: &str) -> Result<bool, String> {
+	let section_table = match document.get_mut(section) {
+		Some(toml_edit::Item::Table(x)) => x,
+		Some(toml_edit::Item::Value(toml_edit::Value::InlineTable(x))) if x.contains_key(old) => {
+			return Err(format!("the [{}] section is an inline table: rename `{}` to `{}` by hand", section, old, new));
+		},
+		_ => return Ok(false),
+	};
+	if !section_table.contains_key(old) {
+		return Ok(false);
+	}
+	if section_table.contains_key(new) {
+		return Err(format!("the [{}] section has both `{}` and `{}`", section, old, new));
+	}
+
+	// Removing and inserting moves a key to the end, so move all following keys after the renamed key too.
+	let following: Vec<String> = section_table.iter()
+		.map(|(key, _)| key.to_owned())
+		.skip_while(|key| key != old)
+		.skip(1)
+		.collect();
+	if let Some((key, item)) = section_table.remove_entry(old) {
+		let renamed = toml_edit::Key::new(new)
+			.with_leaf_decor(key.leaf_decor().clone())
+			.with_dotted_decor(key.dotted_decor().clone());
+		section_table.insert_formatted(&renamed, item);
+	}
+	for key in following {
+		if let Some((key, item)) = section_table.remove_entry(&key) {
+			section_table.insert_formatted(&key, item);
+		}
+	}
+	Ok(true)
+}
+
+/// Migrate a configuration file on disk to the current version.
+///
+/// Included files are not migrated, they need to be migrated separately.
+/// If `write` is false, this only reports the migrations that would be applied.
+/// Otherwise, the original file is kept with a `.bak` extension added to its name.
+/// An existing backup is never overwritten: the migration fails instead.
+///
+/// Only the migrated keys are changed: comments, formatting and the order of keys are preserved.
+///
+/// Returns the descriptions of the changes, which is empty if the file is already up to date.
+pub fn migrate_file(path: impl AsRef<Path>, kind: ConfigKind, write: bool) -> Result<Vec<String>, String> {
+	let path = path.as_ref();
+	let data = std::fs::read_to_string(path)
+		.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+	let mut document: DocumentMut = data.parse()
+		.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+	let changes = migrate_document(kind, &mut document)
+		.map_err(|e| format!("{}: {}", path.display(), e))?;
+	if changes.is_empty() || !write {
+		return Ok(changes);
+	}
+
+	let mut backup = path.as_os_str().to_owned();
+	backup.push(".bak");
+	let backup = Path::new(&backup);
+	let mut backup_file = std::fs::OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(backup)
+		.map_err(|e| match e.kind() {
+			std::io::ErrorKind::AlreadyExists => format!("backup file {} already exists: remove it first", backup.display()),
+			_ => format!("failed to create {}: {}", backup.display(), e),
+		})?;
+	if let Err(e) = std::io::Write::write_all(&mut backup_file, data.as_bytes()) {
+		let _ = std::fs::remove_file(backup);
+		return Err(format!("failed to write {}: {}", backup.display(), e));
+	}
+	crate::write_atomic(path, &document.to_string())
+		.map_err(|e| e.to_string())?;
+	Ok(changes)
+}
+
+#[cfg(test)]
+#[test]
+fn test_migrate() {
+	use assert2::assert;
+
+	let mut table: Table = toml::from_str("[Invoice]\npayment_term_days = 14\n").unwrap();
+	let changes = migrate(ConfigKind::Customer, &mut table).unwrap();
+	assert!(changes.len() == 2);
+	assert!(config_version(&table) == Ok(CONFIG_VERSION));
+	assert!(table["Invoice"].get("payment_days") == Some(&toml::Value::Integer(14)));
+	assert!(table["Invoice"].get("payment_term_days").is_none());
+	assert!(migrate(ConfigKind::Customer, &mut table) == Ok(Vec::new()));
+
+	let mut table: Table = toml::from_str("[Invoice]\npayment_term_days = 14\npayment_days = 30\n").unwrap();
+	assert!(let Err(_) = migrate(ConfigKind::Zzp, &mut table));
+
+	let mut table: Table = toml::from_str("version = 1000\n").unwrap();
+	assert!(let Err(_) = migrate(ConfigKind::Zzp, &mut table));
+}
+
+#[cfg(test)]
+#[test]
+fn test_migrate_document() {
+	use assert2::assert;
+
+	let mut document: DocumentMut = concat!(
+		"# My configuration.\n",
+		"[Invoice]\n",
+		"# Pay within two weeks.\n",
+		"payment_term_days = 14 # days\n",
+		"vat_percentage = 21\n",
+		"\n",
+		"# The customer.\n",
+		"[Customer]\n",
+		"name = \"Customer\"\n",
+	).parse().unwrap();
+	let changes = migrate_document(ConfigKind::Zzp, &mut document).unwrap();
+	assert!(changes.len() == 2);
+	assert!(document.to_string() == concat!(
+		"version = 1\n",
+		"# My configuration.\n",
+		"[Invoice]\n",
+		"# Pay within two weeks.\n",
+		"payment_days = 14 # days\n",
+		"vat_percentage = 21\n",
+		"\n",
+		"# The customer.\n",
+		"[Customer]\n",
+		"name = \"Customer\"\n",
+	));
+	assert!(migrate_document(ConfigKind::Zzp, &mut document) == Ok(Vec::new()));
+
+	let mut document: DocumentMut = "Invoice = { payment_term_days = 14 }\n".parse().unwrap();
+	assert!(let Err(_) = migrate_document(ConfigKind::Zzp, &mut document));
+}
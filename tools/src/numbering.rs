@@ -0,0 +1,323 @@
+//! Automatic invoice number sequencing.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use zzp::gregorian::Date;
+
+use crate::register::InvoiceRegister;
+use crate::{Customer, ReadFileError, WriteFileError, ZzpConfig};
+
+/// The default format for generated invoice numbers.
+pub const DEFAULT_NUMBER_FORMAT: &str = "{year}-{seq:03}";
+
+/// The default path of the invoice number counter file, relative to the directory of `zzp.toml`.
+pub const DEFAULT_COUNTER_PATH: &str = "invoice-numbers.toml";
+
+/// Generator for sequential invoice numbers with a persisted counter.
+///
+/// The counter file stores the last used sequence number for each counter.
+/// Each number format has its own counters, so customers with different number formats do not share a sequence.
+/// Within a format, there is a counter for each year and/or month, depending on the format.
+/// If the number format contains the customer code, there is also a counter for each customer.
+#[derive(Debug, Clone)]
+pub struct InvoiceNumbering {
+	path: PathBuf,
+	format: String,
+	customer_code: Option<String>,
+	counters: BTreeMap<String, u32>,
+}
+
+/// An invoice number from the sequence that has not been consumed yet.
+#[derive(Debug, Clone)]
+pub struct SequenceNumber {
+	/// The key of the counter the sequence number is counted in.
+	counter: String,
+
+	/// The sequence number within the counter.
+	seq: u32,
+
+	/// The formatted invoice number.
+	pub number: String,
+}
+
+impl InvoiceNumbering {
+	/// Open the invoice numbering for a ZZP configuration.
+	///
+	/// The `root_dir` is the directory containing `zzp.toml`.
+	/// The customer overrides must already be applied to the ZZP configuration.
+	/// A missing counter file is treated as if no invoice numbers were used yet.
+	pub fn open(root_dir: impl AsRef<Path>, config: &ZzpConfig, customer: &Customer) -> Result<Self, ReadFileError> {
+		let path = root_dir.as_ref().join(config.invoice.number_counter.as_deref().unwrap_or(DEFAULT_COUNTER_PATH));
+		let format = config.invoice.number_format.clone().unwrap_or_else(|| DEFAULT_NUMBER_FORMAT.into());
+		let counters = if path.exists() {
+			crate::read_toml(&path)?
+		} else {
+			BTreeMap::new()
+		};
+		Ok(Self {
+			path,
+			format,
+			customer_code: customer.code.clone(),
+			counters,
+		})
+	}
+
+	/// Get the path of the counter file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Get the next invoice number for an invoice date without consuming it.
+	///
+	/// Numbers that are already in the invoice register are skipped,
+	/// so the sequence never repeats a number that was given to an invoice manually.
+	pub fn peek(&self, date: Date, register: &InvoiceRegister) -> Result<SequenceNumber, NumberFormatError> {
+		// Without a sequence number, the loop below would never find an unused number.
+		check_format(&self.format)?;
+		let counter = self.counter_key(date);
+		let mut seq = self.counters.get(&counter).copied().unwrap_or(0);
+		loop {
+			seq += 1;
+			let number = format_number(&self.format, date, self.customer_code.as_deref(), seq)?;
+			if register.find(&number).is_none() {
+				return Ok(SequenceNumber { counter, seq, number });
+			}
+		}
+	}
+
+	/// Consume an invoice number from [`Self::peek`] and save the counter file.
+	///
+	/// Call this only when the invoice with the number has been created,
+	/// so that failing to create an invoice does not leave a gap in the sequence.
+	pub fn consume(&mut self, number: &SequenceNumber) -> Result<(), WriteFileError> {
+		let counter = self.counters.entry(number.counter.clone()).or_insert(0);
+		*counter = (*counter).max(number.seq);
+		self.save()
+	}
+
+	/// Check if the sequence already produced an invoice number in the counter of an invoice date.
+	pub fn is_used(&self, date: Date, number: &str) -> Result<bool, NumberFormatError> {
+		let last = self.counters.get(&self.counter_key(date)).copied().unwrap_or(0);
+		for seq in 1..=last {
+			if format_number(&self.format, date, self.customer_code.as_deref(), seq)? == number {
+				return Ok(true);
+			}
+		}
+		Ok(false)
+	}
+
+	/// Get the key of the counter for the sequence number of an invoice date.
+	///
+	/// The key is the number format, followed by the customer code and the period if the format contains them.
+	fn counter_key(&self, date: Date) -> String {
+		let mut key = self.format.clone();
+		if let (Some(code), true) = (&self.customer_code, self.format.contains("{customer_code}")) {
+			key.push('/');
+			key.push_str(code);
+		}
+		match (self.format.contains("{year}"), self.format.contains("{month}")) {
+			(true, true) => key.push_str(&format!("/{}-{:02}", date.year(), date.month().to_number())),
+			(true, false) => key.push_str(&format!("/{}", date.year())),
+			(false, _) => (),
+		}
+		key
+	}
+
+	/// Write the counters to a temporary file and move it over the counter file.
+	fn save(&self) -> Result<(), WriteFileError> {
+		crate::write_toml_atomic(&self.path, &self.counters)
+	}
+}
+
+/// Check that an invoice number format is valid and contains a sequence number.
+///
+/// A format without `{seq}` would give every invoice the same number.
+pub fn check_format(format: &str) -> Result<(), NumberFormatError> {
+	let date = Date::new(2000, zzp::gregorian::January, 1).unwrap();
+	let first = format_number(format, date, Some("CODE"), 1)?;
+	let second = format_number(format, date, Some("CODE"), 2)?;
+	if first == second {
+		return Err(NumberFormatError::MissingSequence(format.into()));
+	}
+	Ok(())
+}
+
+/// Deserialize an optional invoice number format, rejecting invalid formats.
+pub(crate) fn deserialize_format_opt<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+	let format: String = serde::Deserialize::deserialize(deserializer)?;
+	check_format(&format).map_err(serde::de::Error::custom)?;
+	Ok(Some(format))
+}
+
+/// Expand an invoice number format.
+fn format_number(format: &str, date: Date, customer_code: Option<&str>, seq: u32) -> Result<String, NumberFormatError> {
+	let mut output = String::with_capacity(format.len());
+	let mut rest = format;
+	while let Some(start) = rest.find('{') {
+		output.push_str(&rest[..start]);
+		let end = rest[start..].find('}')
+			.ok_or_else(|| NumberFormatError::UnterminatedPlaceholder(format.into()))?;
+		let placeholder = &rest[start + 1..start + end];
+		match placeholder {
+			"year" => output.push_str(&date.year().to_string()),
+			"month" => output.push_str(&format!("{:02}", date.month().to_number())),
+			"customer_code" => {
+				let code = customer_code
+					.ok_or(NumberFormatError::MissingCustomerCode)?;
+				output.push_str(code);
+			},
+			"seq" => output.push_str(&seq.to_string()),
+			_ => {
+				let width = placeholder.strip_prefix("seq:0")
+					.and_then(|width| width.parse::<usize>().ok())
+					.ok_or_else(|| NumberFormatError::UnknownPlaceholder(placeholder.into()))?;
+				output.push_str(&format!("{:0width$}", seq, width = width));
+			},
+		}
+		rest = &rest[start + end + 1..];
+	}
+	output.push_str(rest);
+	Ok(output)
+}
+
+/// An invalid invoice number format.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NumberFormatError {
+	/// A placeholder in the format is not closed.
+	UnterminatedPlaceholder(String),
+
+	/// The format contains an unknown placeholder.
+	UnknownPlaceholder(String),
+
+	/// The format uses `{customer_code}`, but the customer has no code.
+	MissingCustomerCode,
+
+	/// The format does not contain `{seq}`.
+	MissingSequence(String),
+}
+
+impl std::error::Error for NumberFormatError {}
+impl std::fmt::Display for NumberFormatError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::UnterminatedPlaceholder(format) => write!(f, "unterminated placeholder in invoice number format: {:?}", format),
+			Self::UnknownPlaceholder(placeholder) => write!(f, "unknown placeholder in invoice number format: {{{}}}", placeholder),
+			Self::MissingCustomerCode => write!(f, "the invoice number format uses {{customer_code}}, but the customer has no code in customer.toml"),
+			Self::MissingSequence(format) => write!(f, "invoice number format {:?} does not contain {{seq}}, so every invoice would get the same number", format),
+		}
+	}
+}
+
+#[cfg(test)]
+fn test_numbering(name: &str, format: &str, customer_code: Option<&str>) -> InvoiceNumbering {
+	let dir = std::env::temp_dir().join(format!("zzp-test-numbering-{}-{}", name, std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	InvoiceNumbering {
+		path: dir.join(DEFAULT_COUNTER_PATH),
+		format: format.into(),
+		customer_code: customer_code.map(String::from),
+		counters: BTreeMap::new(),
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_format_number() {
+	use assert2::assert;
+
+	let date = Date::new(2024, zzp::gregorian::March, 5).unwrap();
+	assert!(format_number("{year}-{seq:03}", date, None, 7).as_deref() == Ok("2024-007"));
+	assert!(format_number("{year}{month}-{seq}", date, None, 12).as_deref() == Ok("202403-12"));
+	assert!(format_number("{customer_code}/{seq:02}", date, Some("ACME"), 3).as_deref() == Ok("ACME/03"));
+	assert!(format_number("INV{seq}", date, None, 1234).as_deref() == Ok("INV1234"));
+	assert!(format_number("{customer_code}-{seq}", date, None, 1).is_err());
+	assert!(format_number("{year}-{seq", date, None, 1).is_err());
+	assert!(format_number("{day}-{seq}", date, None, 1).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_check_format() {
+	use assert2::assert;
+
+	assert!(check_format(DEFAULT_NUMBER_FORMAT).is_ok());
+	assert!(check_format("{customer_code}-{seq:04}").is_ok());
+	assert!(check_format("{year}-{month}").is_err());
+	assert!(check_format("INVOICE").is_err());
+	assert!(check_format("{year}-{sequence}").is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_peek() {
+	use assert2::assert;
+
+	let date = Date::new(2024, zzp::gregorian::March, 5).unwrap();
+	let mut numbering = test_numbering("peek", "{year}-{seq:03}", None);
+	numbering.counters.insert("{year}-{seq:03}/2024".into(), 4);
+	numbering.counters.insert("{year}-{seq:03}/2023".into(), 9);
+	numbering.counters.insert("{customer_code}-{seq}/ACME".into(), 20);
+
+	let register = InvoiceRegister::from_invoices(Vec::new());
+	let next = numbering.peek(date, &register).unwrap();
+	assert!(next.number == "2024-005");
+	assert!(next.seq == 5);
+
+	// Peeking does not consume the number.
+	assert!(numbering.peek(date, &register).unwrap().number == "2024-005");
+
+	// Numbers that are already in the register are skipped.
+	let mut invoice = crate::register::test_invoice("Acme", None, Vec::new());
+	invoice.number = "2024-005".into();
+	let register = InvoiceRegister::from_invoices(vec![invoice]);
+	assert!(numbering.peek(date, &register).unwrap().number == "2024-006");
+
+	// A format without a sequence number is rejected instead of looping forever.
+	let numbering = test_numbering("peek", "INVOICE", None);
+	assert!(numbering.peek(date, &register).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_counter_per_format() {
+	use assert2::assert;
+
+	let date = Date::new(2024, zzp::gregorian::March, 5).unwrap();
+	let register = InvoiceRegister::from_invoices(Vec::new());
+
+	let mut acme = test_numbering("format", "A-{seq}", Some("ACME"));
+	acme.counters.insert("B-{seq}".into(), 7);
+	assert!(acme.peek(date, &register).unwrap().number == "A-1");
+
+	let mut acme = test_numbering("format", "{customer_code}-{year}-{seq}", Some("ACME"));
+	acme.counters.insert("{customer_code}-{year}-{seq}/GLOBEX/2024".into(), 7);
+	assert!(acme.peek(date, &register).unwrap().number == "ACME-2024-1");
+}
+
+#[cfg(test)]
+#[test]
+fn test_consume() {
+	use assert2::assert;
+
+	let date = Date::new(2024, zzp::gregorian::March, 5).unwrap();
+	let register = InvoiceRegister::from_invoices(Vec::new());
+	let mut numbering = test_numbering("consume", "{year}-{month}-{seq}", None);
+
+	let first = numbering.peek(date, &register).unwrap();
+	assert!(first.number == "2024-03-1");
+	assert!(numbering.is_used(date, "2024-03-1") == Ok(false));
+	numbering.consume(&first).unwrap();
+	assert!(numbering.is_used(date, "2024-03-1") == Ok(true));
+	assert!(numbering.peek(date, &register).unwrap().number == "2024-03-2");
+
+	// Consuming an older number does not move the counter back.
+	let second = numbering.peek(date, &register).unwrap();
+	numbering.consume(&second).unwrap();
+	numbering.consume(&first).unwrap();
+	assert!(numbering.peek(date, &register).unwrap().number == "2024-03-3");
+
+	// The counters are saved in the counter file.
+	let saved: BTreeMap<String, u32> = crate::read_toml(&numbering.path).unwrap();
+	assert!(saved.get("{year}-{month}-{seq}/2024-03") == Some(&2));
+	std::fs::remove_dir_all(numbering.path.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,1409 @@
+//! Conversion of generated PDF files to PDF/A-3b.
+//!
+//! Cairo can not write PDF/A files by itself, but the files it writes are close.
+//! The conversion appends an incremental update to the PDF file with the parts that PDF/A requires:
+//! XMP metadata that identifies the file as PDF/A-3b, an sRGB output intent and a file identifier.
+//! Image interpolation is disabled, since PDF/A does not allow it.
+//!
+//! Before anything is added, the file is checked for things that can not be fixed afterwards:
+//! all fonts must be embedded and the file may not be encrypted.
+//! These checks cover what cairo can produce; they are not a replacement for a full PDF/A validator.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use zzp::gregorian::Date;
+
+/// The details of a document to record in the PDF/A metadata.
+#[derive(Debug, Clone)]
+pub struct Metadata<'a> {
+	/// The title of the document, like `Invoice 2024-001`.
+	pub title: &'a str,
+
+	/// The author of the document.
+	pub author: &'a str,
+
+	/// The date of the document.
+	pub date: Date,
+}
+
+/// An error that prevents converting a PDF file to PDF/A.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdfAError {
+	/// The PDF file could not be parsed.
+	Parse {
+		offset: usize,
+		message: String,
+	},
+
+	/// The PDF file is encrypted, which PDF/A does not allow.
+	Encrypted,
+
+	/// A font is not embedded in the PDF file.
+	FontNotEmbedded(String),
+}
+
+impl std::fmt::Display for PdfAError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Parse { offset, message } => write!(f, "invalid PDF file at byte {}: {}", offset, message),
+			Self::Encrypted => write!(f, "PDF/A files can not be encrypted"),
+			Self::FontNotEmbedded(name) => write!(f, "font {} is not embedded, which PDF/A requires", name),
+		}
+	}
+}
+
+impl std::error::Error for PdfAError {}
+
+/// The program that is recorded as creator and producer of the PDF/A files.
+const PRODUCER: &str = concat!("zzp-tools ", env!("CARGO_PKG_VERSION"));
+
+/// The name of the output condition of the sRGB output intent.
+const SRGB: &str = "sRGB IEC61966-2.1";
+
+/// Convert a PDF file to PDF/A-3b.
+pub fn convert(pdf: &[u8], metadata: &Metadata) -> Result<Vec<u8>, PdfAError> {
+	let document = Document::parse(pdf)?;
+	if document.trailer.contains_key(b"Encrypt".as_slice()) {
+		return Err(PdfAError::Encrypted);
+	}
+
+	let (root_number, root_generation) = match document.trailer.get(b"Root".as_slice()) {
+		Some(&Object::Reference(number, generation)) => (number, generation),
+		_ => return Err(document.error(document.startxref, "trailer has no /Root reference")),
+	};
+
+	let mut update = Update::new(&document);
+
+	// Check the fonts and disable image interpolation.
+	let numbers: Vec<u32> = document.xref.keys().copied().collect();
+	for number in numbers {
+		let (object, stream) = document.get(number)?;
+		let dict = match &object {
+			Object::Dictionary(dict) => dict,
+			_ => continue,
+		};
+		if dict.get(b"Type".as_slice()) == Some(&Object::name("Font")) {
+			check_font(&document, dict)?;
+		} else if let Some(data) = stream {
+			if dict.get(b"Interpolate".as_slice()) == Some(&Object::Boolean(true)) {
+				let mut dict = dict.clone();
+				dict.insert(b"Interpolate".to_vec(), Object::Boolean(false));
+				update.replace_stream(number, dict, data.to_vec());
+			}
+		}
+	}
+
+	// Add the metadata and the output intent to the document catalog.
+	let mut catalog = match document.resolve(&Object::Reference(root_number, root_generation))? {
+		Object::Dictionary(dict) => dict,
+		_ => return Err(document.error(document.startxref, "document catalog is not a dictionary")),
+	};
+
+	let profile = update.add_stream(dictionary([("N", Object::Integer(3))]), srgb_profile());
+	let output_intent = update.add(Object::Dictionary(dictionary([
+		("Type", Object::name("OutputIntent")),
+		("S", Object::name("GTS_PDFA1")),
+		("OutputConditionIdentifier", Object::String(SRGB.into())),
+		("Info", Object::String(SRGB.into())),
+		("DestOutputProfile", profile),
+	])));
+	let xmp = update.add_stream(
+		dictionary([("Type", Object::name("Metadata")), ("Subtype", Object::name("XML"))]),
+		xmp_metadata(metadata).into_bytes(),
+	);
+
+	// PDF/A does not allow actions that run when the document is opened or closed.
+	catalog.remove(b"AA".as_slice());
+	catalog.insert(b"Metadata".to_vec(), xmp);
+	catalog.insert(b"OutputIntents".to_vec(), Object::Array(vec![output_intent]));
+	update.replace(root_number, root_generation, Object::Dictionary(catalog));
+
+	// The document information must match the XMP metadata.
+	let date = Object::String(format!("D:{:04}{:02}{:02}000000Z", metadata.date.year().to_number(), metadata.date.month().to_number(), metadata.date.day()).into_bytes());
+	let info = update.add(Object::Dictionary(dictionary([
+		("Title", text_string(metadata.title)),
+		("Author", text_string(metadata.author)),
+		("Creator", text_string(PRODUCER)),
+		("Producer", text_string(PRODUCER)),
+		("CreationDate", date.clone()),
+		("ModDate", date),
+	])));
+
+	// Keep the permanent part of the file identifier if there is one.
+	let hash = Sha256::digest(pdf)[..16].to_vec();
+	let permanent_id = match document.trailer.get(b"ID".as_slice()) {
+		Some(Object::Array(ids)) if !ids.is_empty() => ids[0].clone(),
+		_ => Object::String(hash.clone()),
+	};
+
+	let trailer = dictionary([
+		("Root", Object::Reference(root_number, root_generation)),
+		("Info", info),
+		("ID", Object::Array(vec![permanent_id, Object::String(hash)])),
+		("Prev", Object::Integer(document.startxref as i64)),
+	]);
+	Ok(update.finish(trailer))
+}
+
+/// Check that a font is embedded.
+///
+/// Type 3 fonts are defined by content streams in the file itself, and composite fonts are checked through their descendant fonts.
+fn check_font(document: &Document, font: &Dictionary) -> Result<(), PdfAError> {
+	let subtype = font.get(b"Subtype".as_slice());
+	if subtype == Some(&Object::name("Type3")) || subtype == Some(&Object::name("Type0")) {
+		return Ok(());
+	}
+
+	let name = match font.get(b"BaseFont".as_slice()) {
+		Some(Object::Name(name)) => String::from_utf8_lossy(name).into_owned(),
+		_ => String::from("without name"),
+	};
+
+	let descriptor = match font.get(b"FontDescriptor".as_slice()) {
+		Some(descriptor) => document.resolve(descriptor)?,
+		None => return Err(PdfAError::FontNotEmbedded(name)),
+	};
+	let embedded = match &descriptor {
+		Object::Dictionary(descriptor) => ["FontFile", "FontFile2", "FontFile3"].iter().any(|key| descriptor.contains_key(key.as_bytes())),
+		_ => false,
+	};
+	if embedded {
+		Ok(())
+	} else {
+		Err(PdfAError::FontNotEmbedded(name))
+	}
+}
+
+/// Encode a text string for the document information dictionary.
+///
+/// Text strings are always written as UTF-16BE, so they can contain any character.
+fn text_string(text: &str) -> Object {
+	let mut data = vec![0xFE, 0xFF];
+	for unit in text.encode_utf16() {
+		data.extend_from_slice(&unit.to_be_bytes());
+	}
+	Object::String(data)
+}
+
+/// Create the XMP metadata packet for a document.
+fn xmp_metadata(metadata: &Metadata) -> String {
+	let date = format!("{:04}-{:02}-{:02}T00:00:00Z", metadata.date.year().to_number(), metadata.date.month().to_number(), metadata.date.day());
+	let mut xmp = String::new();
+	xmp.push_str("<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
+	xmp.push_str("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n");
+	xmp.push_str("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n");
+	xmp.push_str("<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n");
+	xmp.push_str("<pdfaid:part>3</pdfaid:part>\n");
+	xmp.push_str("<pdfaid:conformance>B</pdfaid:conformance>\n");
+	xmp.push_str("</rdf:Description>\n");
+	xmp.push_str("<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+	xmp.push_str("<dc:format>application/pdf</dc:format>\n");
+	xmp.push_str(&format!("<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n", crate::ubl::escape(metadata.title)));
+	xmp.push_str(&format!("<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n", crate::ubl::escape(metadata.author)));
+	xmp.push_str("</rdf:Description>\n");
+	xmp.push_str("<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n");
+	xmp.push_str(&format!("<xmp:CreatorTool>{}</xmp:CreatorTool>\n", PRODUCER));
+	xmp.push_str(&format!("<xmp:CreateDate>{}</xmp:CreateDate>\n", date));
+	xmp.push_str(&format!("<xmp:ModifyDate>{}</xmp:ModifyDate>\n", date));
+	xmp.push_str("</rdf:Description>\n");
+	xmp.push_str("<rdf:Description rdf:about=\"\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\n");
+	xmp.push_str(&format!("<pdf:Producer>{}</pdf:Producer>\n", PRODUCER));
+	xmp.push_str("</rdf:Description>\n");
+	xmp.push_str("</rdf:RDF>\n");
+	xmp.push_str("</x:xmpmeta>\n");
+	xmp.push_str("<?xpacket end=\"w\"?>");
+	xmp
+}
+
+/// Create an ICC profile for the sRGB color space.
+///
+/// The profile is a version 2 display profile with the sRGB primaries adapted to D50 and the sRGB tone curve.
+fn srgb_profile() -> Vec<u8> {
+	fn s15_fixed16(value: f64) -> [u8; 4] {
+		((value * 65536.0).round() as i32).to_be_bytes()
+	}
+
+	fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+		let mut data = b"XYZ \0\0\0\0".to_vec();
+		data.extend_from_slice(&s15_fixed16(x));
+		data.extend_from_slice(&s15_fixed16(y));
+		data.extend_from_slice(&s15_fixed16(z));
+		data
+	}
+
+	let description = b"sRGB IEC61966-2.1\0";
+	let mut desc = b"desc\0\0\0\0".to_vec();
+	desc.extend_from_slice(&(description.len() as u32).to_be_bytes());
+	desc.extend_from_slice(description);
+	// No Unicode and ScriptCode descriptions.
+	desc.extend_from_slice(&[0; 4 + 4 + 2 + 1 + 67]);
+
+	let mut cprt = b"text\0\0\0\0".to_vec();
+	cprt.extend_from_slice(b"No copyright, use freely\0");
+
+	let mut curve = b"curv\0\0\0\0".to_vec();
+	let points = 1024;
+	curve.extend_from_slice(&(points as u32).to_be_bytes());
+	for i in 0..points {
+		let value = f64::from(i) / f64::from(points - 1);
+		let linear = if value <= 0.04045 {
+			value / 12.92
+		} else {
+			((value + 0.055) / 1.055).powf(2.4)
+		};
+		curve.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+	}
+
+	let tags: [(&[u8; 4], Vec<u8>); 9] = [
+		(b"desc", desc),
+		(b"cprt", cprt),
+		(b"wtpt", xyz(0.9642, 1.0, 0.8249)),
+		(b"rXYZ", xyz(0.4361, 0.2225, 0.0139)),
+		(b"gXYZ", xyz(0.3851, 0.7169, 0.0971)),
+		(b"bXYZ", xyz(0.1431, 0.0606, 0.7141)),
+		(b"rTRC", curve.clone()),
+		(b"gTRC", curve.clone()),
+		(b"bTRC", curve),
+	];
+
+	let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+	let mut data = Vec::new();
+	let data_start = 128 + 4 + 12 * tags.len();
+	for (signature, tag) in &tags {
+		table.extend_from_slice(*signature);
+		table.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+		table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+		data.extend_from_slice(tag);
+		// Tag data must start on a 4 byte boundary.
+		while data.len() % 4 != 0 {
+			data.push(0);
+		}
+	}
+
+	let mut header = Vec::with_capacity(128);
+	header.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+	header.extend_from_slice(&[0; 4]);
+	header.extend_from_slice(&[2, 0x10, 0, 0]);
+	header.extend_from_slice(b"mntrRGB XYZ ");
+	for field in [1998u16, 2, 9, 6, 49, 0] {
+		header.extend_from_slice(&field.to_be_bytes());
+	}
+	header.extend_from_slice(b"acsp");
+	header.extend_from_slice(&[0; 4 + 4 + 4 + 4 + 8 + 4]);
+	header.extend_from_slice(&s15_fixed16(0.9642));
+	header.extend_from_slice(&s15_fixed16(1.0));
+	header.extend_from_slice(&s15_fixed16(0.8249));
+	header.resize(128, 0);
+
+	let mut profile = header;
+	profile.extend_from_slice(&table);
+	profile.extend_from_slice(&data);
+	profile
+}
+
+/// A PDF object.
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+	Null,
+	Boolean(bool),
+	Integer(i64),
+	Real(f64),
+	String(Vec<u8>),
+	Name(Vec<u8>),
+	Array(Vec<Object>),
+	Dictionary(Dictionary),
+	Reference(u32, u16),
+}
+
+/// A PDF dictionary.
+type Dictionary = BTreeMap<Vec<u8>, Object>;
+
+impl Object {
+	fn name(name: &str) -> Self {
+		Self::Name(name.as_bytes().to_vec())
+	}
+
+	fn as_integer(&self) -> Option<i64> {
+		match self {
+			Self::Integer(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	/// Write the object in PDF syntax.
+	fn write(&self, output: &mut Vec<u8>) {
+		match self {
+			Self::Null => output.extend_from_slice(b"null"),
+			Self::Boolean(value) => output.extend_from_slice(value.to_string().as_bytes()),
+			Self::Integer(value) => output.extend_from_slice(value.to_string().as_bytes()),
+			Self::Real(value) => output.extend_from_slice(value.to_string().as_bytes()),
+			Self::String(data) => {
+				output.push(b'<');
+				for byte in data {
+					output.extend_from_slice(format!("{:02X}", byte).as_bytes());
+				}
+				output.push(b'>');
+			},
+			Self::Name(name) => {
+				output.push(b'/');
+				for &byte in name {
+					if (b'!'..=b'~').contains(&byte) && !is_delimiter(byte) && byte != b'#' {
+						output.push(byte);
+					} else {
+						output.extend_from_slice(format!("#{:02X}", byte).as_bytes());
+					}
+				}
+			},
+			Self::Array(items) => {
+				output.push(b'[');
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						output.push(b' ');
+					}
+					item.write(output);
+				}
+				output.push(b']');
+			},
+			Self::Dictionary(dict) => {
+				output.extend_from_slice(b"<<");
+				for (key, value) in dict {
+					Self::Name(key.clone()).write(output);
+					output.push(b' ');
+					value.write(output);
+				}
+				output.extend_from_slice(b">>");
+			},
+			Self::Reference(number, generation) => {
+				output.extend_from_slice(format!("{} {} R", number, generation).as_bytes());
+			},
+		}
+	}
+}
+
+/// Create a dictionary from key-value pairs.
+fn dictionary<const N: usize>(entries: [(&str, Object); N]) -> Dictionary {
+	entries.into_iter()
+		.map(|(key, value)| (key.as_bytes().to_vec(), value))
+		.collect()
+}
+
+fn is_whitespace(byte: u8) -> bool {
+	matches!(byte, b'\0' | b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+	matches!(byte, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+/// Where an object is stored in the file.
+#[derive(Debug, Copy, Clone)]
+enum Location {
+	/// The object is stored at a byte offset in the file.
+	Offset(usize, u16),
+
+	/// The object is stored in an object stream.
+	Compressed {
+		stream: u32,
+		index: usize,
+	},
+}
+
+/// A parsed PDF file.
+struct Document<'a> {
+	data: &'a [u8],
+
+	/// The location of the current version of each object.
+	xref: BTreeMap<u32, Location>,
+
+	/// The trailer of the last update of the file.
+	trailer: Dictionary,
+
+	/// The offset of the last cross-reference section.
+	startxref: usize,
+
+	/// If the last cross-reference section is a cross-reference stream.
+	xref_stream: bool,
+}
+
+impl<'a> Document<'a> {
+	/// Parse the cross-reference sections of a PDF file.
+	fn parse(data: &'a [u8]) -> Result<Self, PdfAError> {
+		let mut document = Self {
+			data,
+			xref: BTreeMap::new(),
+			trailer: Dictionary::new(),
+			startxref: 0,
+			xref_stream: false,
+		};
+
+		let tail_start = data.len().saturating_sub(1024);
+		let keyword = data[tail_start..].windows(9)
+			.rposition(|window| window == b"startxref")
+			.ok_or_else(|| document.error(data.len(), "no startxref found"))?;
+		let mut parser = Parser::new(data, tail_start + keyword + 9);
+		document.startxref = parser.parse_integer()? as usize;
+
+		let mut offset = Some(document.startxref);
+		let mut seen = Vec::new();
+		while let Some(section) = offset {
+			// Guard against loops in the /Prev chain.
+			if seen.contains(&section) {
+				return Err(document.error(section, "loop in cross-reference sections"));
+			}
+			seen.push(section);
+
+			let (trailer, is_stream) = document.parse_xref_section(section)?;
+			if seen.len() == 1 {
+				document.trailer = trailer.clone();
+				document.xref_stream = is_stream;
+			}
+
+			// Hybrid files have an extra cross-reference stream for the same update.
+			if let Some(stream) = trailer.get(b"XRefStm".as_slice()).and_then(Object::as_integer) {
+				document.parse_xref_section(stream as usize)?;
+			}
+			offset = trailer.get(b"Prev".as_slice()).and_then(Object::as_integer).map(|x| x as usize);
+		}
+
+		Ok(document)
+	}
+
+	fn error(&self, offset: usize, message: impl Into<String>) -> PdfAError {
+		PdfAError::Parse { offset, message: message.into() }
+	}
+
+	/// Parse a cross-reference section and the trailer.
+	///
+	/// Entries that are already known come from a newer update, so they are not overwritten.
+	fn parse_xref_section(&mut self, offset: usize) -> Result<(Dictionary, bool), PdfAError> {
+		let mut parser = Parser::new(self.data, offset);
+		parser.skip_whitespace();
+		if !parser.eat_keyword(b"xref") {
+			let (dict, data) = self.parse_indirect_object(offset, None)?.1.into_stream(offset)?;
+			self.parse_xref_stream(offset, &dict, data)?;
+			return Ok((dict, true));
+		}
+
+		loop {
+			parser.skip_whitespace();
+			if parser.eat_keyword(b"trailer") {
+				break;
+			}
+			let first = parser.parse_integer()?;
+			let count = parser.parse_integer()?;
+			for number in first..first + count {
+				let entry_offset = parser.parse_integer()?;
+				let generation = parser.parse_integer()?;
+				parser.skip_whitespace();
+				let kind = parser.next_byte()?;
+				if kind == b'n' {
+					self.xref.entry(number as u32).or_insert(Location::Offset(entry_offset as usize, generation as u16));
+				}
+			}
+		}
+
+		match parser.parse_object()? {
+			Object::Dictionary(trailer) => Ok((trailer, false)),
+			_ => Err(self.error(parser.position, "trailer is not a dictionary")),
+		}
+	}
+
+	/// Parse the entries of a cross-reference stream.
+	fn parse_xref_stream(&mut self, offset: usize, dict: &Dictionary, data: &[u8]) -> Result<(), PdfAError> {
+		let data = self.decode_stream(offset, dict, data)?;
+		let widths: Vec<usize> = match dict.get(b"W".as_slice()) {
+			Some(Object::Array(widths)) if widths.len() == 3 => widths.iter()
+				.map(|x| x.as_integer().map(|x| x as usize))
+				.collect::<Option<_>>()
+				.ok_or_else(|| self.error(offset, "invalid /W in cross-reference stream"))?,
+			_ => return Err(self.error(offset, "missing /W in cross-reference stream")),
+		};
+		let size = dict.get(b"Size".as_slice()).and_then(Object::as_integer).unwrap_or(0);
+		let index = match dict.get(b"Index".as_slice()) {
+			Some(Object::Array(index)) => index.iter()
+				.map(Object::as_integer)
+				.collect::<Option<Vec<_>>>()
+				.ok_or_else(|| self.error(offset, "invalid /Index in cross-reference stream"))?,
+			_ => vec![0, size],
+		};
+
+		let entry_size: usize = widths.iter().sum();
+		let mut entries = data.chunks_exact(entry_size.max(1));
+		for range in index.chunks_exact(2) {
+			for number in range[0]..range[0] + range[1] {
+				let entry = entries.next().ok_or_else(|| self.error(offset, "cross-reference stream is too short"))?;
+				let (kind, rest) = entry.split_at(widths[0]);
+				let (field1, field2) = rest.split_at(widths[1]);
+				let kind = if widths[0] == 0 { 1 } else { read_be(kind) };
+				let location = match kind {
+					1 => Location::Offset(read_be(field1) as usize, read_be(field2) as u16),
+					2 => Location::Compressed { stream: read_be(field1) as u32, index: read_be(field2) as usize },
+					_ => continue,
+				};
+				self.xref.entry(number as u32).or_insert(location);
+			}
+		}
+		Ok(())
+	}
+
+	/// Parse an indirect object at an offset.
+	///
+	/// Returns the object number with the object, or with the dictionary and the raw data of a stream.
+	fn parse_indirect_object(&self, offset: usize, expected: Option<u32>) -> Result<(u32, Indirect<'a>), PdfAError> {
+		let mut parser = Parser::new(self.data, offset);
+		let number = parser.parse_integer()? as u32;
+		parser.parse_integer()?;
+		parser.skip_whitespace();
+		if !parser.eat_keyword(b"obj") {
+			return Err(self.error(parser.position, "expected obj keyword"));
+		}
+		if expected.is_some_and(|expected| expected != number) {
+			return Err(self.error(offset, format!("expected object {}, found object {}", expected.unwrap_or(0), number)));
+		}
+
+		let object = parser.parse_object()?;
+		parser.skip_whitespace();
+		if !parser.eat_keyword(b"stream") {
+			return Ok((number, Indirect::Object(object)));
+		}
+		let dict = match object {
+			Object::Dictionary(dict) => dict,
+			_ => return Err(self.error(parser.position, "stream without dictionary")),
+		};
+
+		// The stream data starts after CRLF or LF.
+		if self.data[parser.position..].starts_with(b"\r\n") {
+			parser.position += 2;
+		} else if self.data[parser.position..].starts_with(b"\n") {
+			parser.position += 1;
+		}
+		let length = match dict.get(b"Length".as_slice()) {
+			Some(Object::Integer(length)) => *length as usize,
+			Some(Object::Reference(number, _)) if Some(*number) != expected => {
+				self.get(*number)?.0.as_integer().ok_or_else(|| self.error(offset, "invalid stream length"))? as usize
+			},
+			_ => return Err(self.error(offset, "invalid stream length")),
+		};
+		let data = self.data.get(parser.position..parser.position + length)
+			.ok_or_else(|| self.error(parser.position, "stream data extends beyond the end of the file"))?;
+		Ok((number, Indirect::Stream(dict, data)))
+	}
+
+	/// Get an object with the raw data if it is a stream.
+	fn get(&self, number: u32) -> Result<(Object, Option<&'a [u8]>), PdfAError> {
+		match self.xref.get(&number) {
+			None => Ok((Object::Null, None)),
+			Some(&Location::Offset(offset, _)) => match self.parse_indirect_object(offset, Some(number))?.1 {
+				Indirect::Object(object) => Ok((object, None)),
+				Indirect::Stream(dict, data) => Ok((Object::Dictionary(dict), Some(data))),
+			},
+			Some(&Location::Compressed { stream, index }) => {
+				let offset = match self.xref.get(&stream) {
+					Some(&Location::Offset(offset, _)) => offset,
+					_ => return Err(self.error(0, format!("object stream {} not found", stream))),
+				};
+				let (dict, data) = self.parse_indirect_object(offset, Some(stream))?.1.into_stream(offset)?;
+				let data = self.decode_stream(offset, &dict, data)?;
+				let count = dict.get(b"N".as_slice()).and_then(Object::as_integer).unwrap_or(0) as usize;
+				let first = dict.get(b"First".as_slice()).and_then(Object::as_integer).unwrap_or(0) as usize;
+				if index >= count {
+					return Err(self.error(offset, format!("object {} not found in object stream", number)));
+				}
+
+				let mut parser = Parser::new(&data, 0);
+				let mut object_offset = 0;
+				for _ in 0..=index {
+					parser.parse_integer()?;
+					object_offset = parser.parse_integer()? as usize;
+				}
+				let mut parser = Parser::new(&data, first + object_offset);
+				let object = parser.parse_object().map_err(|_| self.error(offset, format!("invalid object {} in object stream", number)))?;
+				Ok((object, None))
+			},
+		}
+	}
+
+	/// Follow references until a direct object is found.
+	fn resolve(&self, object: &Object) -> Result<Object, PdfAError> {
+		let mut object = object.clone();
+		for _ in 0..32 {
+			match object {
+				Object::Reference(number, _) => object = self.get(number)?.0,
+				object => return Ok(object),
+			}
+		}
+		Err(self.error(0, "too many nested references"))
+	}
+
+	/// Decode the data of a stream.
+	///
+	/// Only the filters used for cross-reference streams and object streams are supported.
+	fn decode_stream(&self, offset: usize, dict: &Dictionary, data: &[u8]) -> Result<Vec<u8>, PdfAError> {
+		let filters = match dict.get(b"Filter".as_slice()) {
+			None => Vec::new(),
+			Some(Object::Array(filters)) => filters.clone(),
+			Some(filter) => vec![filter.clone()],
+		};
+		let params = match dict.get(b"DecodeParms".as_slice()) {
+			None | Some(Object::Null) => Vec::new(),
+			Some(Object::Array(params)) => params.clone(),
+			Some(params) => vec![params.clone()],
+		};
+
+		let mut data = data.to_vec();
+		for (i, filter) in filters.iter().enumerate() {
+			if filter != &Object::name("FlateDecode") {
+				return Err(self.error(offset, "unsupported stream filter"));
+			}
+			let mut decoded = Vec::new();
+			flate2::read::ZlibDecoder::new(data.as_slice())
+				.read_to_end(&mut decoded)
+				.map_err(|e| self.error(offset, format!("failed to decompress stream: {}", e)))?;
+			data = match params.get(i).map(|x| self.resolve(x)).transpose()? {
+				Some(Object::Dictionary(params)) => self.unpredict(offset, &params, decoded)?,
+				_ => decoded,
+			};
+		}
+		Ok(data)
+	}
+
+	/// Undo the PNG predictors of a compressed stream.
+	fn unpredict(&self, offset: usize, params: &Dictionary, data: Vec<u8>) -> Result<Vec<u8>, PdfAError> {
+		let predictor = params.get(b"Predictor".as_slice()).and_then(Object::as_integer).unwrap_or(1);
+		if predictor == 1 {
+			return Ok(data);
+		} else if predictor < 10 {
+			return Err(self.error(offset, "unsupported TIFF predictor"));
+		}
+		let get = |key: &[u8], default| params.get(key).and_then(Object::as_integer).unwrap_or(default) as usize;
+		let pixel_bytes = (get(b"Colors", 1) * get(b"BitsPerComponent", 8)).div_ceil(8);
+		let row_bytes = (get(b"Columns", 1) * get(b"Colors", 1) * get(b"BitsPerComponent", 8)).div_ceil(8);
+
+		let mut output = Vec::with_capacity(data.len());
+		let mut previous = vec![0u8; row_bytes];
+		for row in data.chunks(row_bytes + 1) {
+			let (&kind, row) = row.split_first().ok_or_else(|| self.error(offset, "empty predictor row"))?;
+			let mut current = row.to_vec();
+			current.resize(row_bytes, 0);
+			for i in 0..row_bytes {
+				let left = if i >= pixel_bytes { current[i - pixel_bytes] } else { 0 };
+				let up = previous[i];
+				let up_left = if i >= pixel_bytes { previous[i - pixel_bytes] } else { 0 };
+				let prediction = match kind {
+					0 => 0,
+					1 => left,
+					2 => up,
+					3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+					4 => paeth(left, up, up_left),
+					_ => return Err(self.error(offset, "invalid PNG predictor")),
+				};
+				current[i] = current[i].wrapping_add(prediction);
+			}
+			output.extend_from_slice(&current);
+			previous = current;
+		}
+		Ok(output)
+	}
+}
+
+/// The Paeth predictor from the PNG specification.
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+	let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+	let distance_left = (estimate - i16::from(left)).abs();
+	let distance_up = (estimate - i16::from(up)).abs();
+	let distance_up_left = (estimate - i16::from(up_left)).abs();
+	if distance_left <= distance_up && distance_left <= distance_up_left {
+		left
+	} else if distance_up <= distance_up_left {
+		up
+	} else {
+		up_left
+	}
+}
+
+/// Read a big endian number of any size.
+fn read_be(data: &[u8]) -> u64 {
+	data.iter().fold(0, |value, &byte| value << 8 | u64::from(byte))
+}
+
+/// An indirect object read from the file.
+enum Indirect<'a> {
+	Object(Object),
+	Stream(Dictionary, &'a [u8]),
+}
+
+impl<'a> Indirect<'a> {
+	fn into_stream(self, offset: usize) -> Result<(Dictionary, &'a [u8]), PdfAError> {
+		match self {
+			Self::Stream(dict, data) => Ok((dict, data)),
+			Self::Object(_) => Err(PdfAError::Parse { offset, message: "expected a stream".into() }),
+		}
+	}
+}
+
+/// A parser for PDF objects.
+struct Parser<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn new(data: &'a [u8], position: usize) -> Self {
+		Self { data, position }
+	}
+
+	fn error(&self, message: impl Into<String>) -> PdfAError {
+		PdfAError::Parse { offset: self.position, message: message.into() }
+	}
+
+	fn peek(&self) -> Option<u8> {
+		self.data.get(self.position).copied()
+	}
+
+	fn next_byte(&mut self) -> Result<u8, PdfAError> {
+		let byte = self.peek().ok_or_else(|| self.error("unexpected end of file"))?;
+		self.position += 1;
+		Ok(byte)
+	}
+
+	/// Skip whitespace and comments.
+	fn skip_whitespace(&mut self) {
+		while let Some(byte) = self.peek() {
+			if is_whitespace(byte) {
+				self.position += 1;
+			} else if byte == b'%' {
+				while !matches!(self.peek(), None | Some(b'\r' | b'\n')) {
+					self.position += 1;
+				}
+			} else {
+				break;
+			}
+		}
+	}
+
+	/// Consume a keyword if it is next in the input.
+	fn eat_keyword(&mut self, keyword: &[u8]) -> bool {
+		let end = self.position + keyword.len();
+		let matches = self.data.get(self.position..end) == Some(keyword)
+			&& self.data.get(end).is_none_or(|&byte| is_whitespace(byte) || is_delimiter(byte));
+		if matches {
+			self.position = end;
+		}
+		matches
+	}
+
+	/// Read a token of regular characters.
+	fn regular_token(&mut self) -> &'a [u8] {
+		let start = self.position;
+		while self.peek().is_some_and(|byte| !is_whitespace(byte) && !is_delimiter(byte)) {
+			self.position += 1;
+		}
+		&self.data[start..self.position]
+	}
+
+	fn parse_integer(&mut self) -> Result<i64, PdfAError> {
+		self.skip_whitespace();
+		let start = self.position;
+		let token = self.regular_token();
+		std::str::from_utf8(token).ok()
+			.and_then(|token| token.parse().ok())
+			.ok_or(PdfAError::Parse { offset: start, message: "expected an integer".into() })
+	}
+
+	fn parse_object(&mut self) -> Result<Object, PdfAError> {
+		self.skip_whitespace();
+		match self.peek() {
+			None => Err(self.error("unexpected end of file")),
+			Some(b'/') => {
+				self.position += 1;
+				let token = self.regular_token();
+				Ok(Object::Name(decode_name(token)))
+			},
+			Some(b'<') if self.data.get(self.position + 1) == Some(&b'<') => {
+				self.position += 2;
+				let mut dict = Dictionary::new();
+				loop {
+					self.skip_whitespace();
+					if self.data[self.position..].starts_with(b">>") {
+						self.position += 2;
+						return Ok(Object::Dictionary(dict));
+					}
+					let key = match self.parse_object()? {
+						Object::Name(key) => key,
+						_ => return Err(self.error("dictionary key is not a name")),
+					};
+					let value = self.parse_object()?;
+					dict.insert(key, value);
+				}
+			},
+			Some(b'<') => {
+				self.position += 1;
+				let mut digits = Vec::new();
+				loop {
+					match self.next_byte()? {
+						b'>' => break,
+						byte if byte.is_ascii_hexdigit() => digits.push(byte),
+						byte if is_whitespace(byte) => (),
+						_ => return Err(self.error("invalid hexadecimal string")),
+					}
+				}
+				if digits.len() % 2 == 1 {
+					digits.push(b'0');
+				}
+				let data = digits.chunks(2)
+					.map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+					.collect();
+				Ok(Object::String(data))
+			},
+			Some(b'(') => {
+				self.position += 1;
+				self.parse_literal_string().map(Object::String)
+			},
+			Some(b'[') => {
+				self.position += 1;
+				let mut items = Vec::new();
+				loop {
+					self.skip_whitespace();
+					if self.peek() == Some(b']') {
+						self.position += 1;
+						return Ok(Object::Array(items));
+					}
+					items.push(self.parse_object()?);
+				}
+			},
+			Some(byte) if byte.is_ascii_digit() || matches!(byte, b'+' | b'-' | b'.') => self.parse_number(),
+			Some(_) => {
+				let start = self.position;
+				match self.regular_token() {
+					b"true" => Ok(Object::Boolean(true)),
+					b"false" => Ok(Object::Boolean(false)),
+					b"null" => Ok(Object::Null),
+					_ => Err(PdfAError::Parse { offset: start, message: "unexpected token".into() }),
+				}
+			},
+		}
+	}
+
+	/// Parse a number, or a reference if the number is followed by a generation and `R`.
+	fn parse_number(&mut self) -> Result<Object, PdfAError> {
+		let start = self.position;
+		let token = std::str::from_utf8(self.regular_token()).unwrap_or_default();
+		if let Ok(value) = token.parse::<i64>() {
+			let after_number = self.position;
+			if let Ok(generation) = self.parse_integer() {
+				self.skip_whitespace();
+				if self.eat_keyword(b"R") {
+					return Ok(Object::Reference(value as u32, generation as u16));
+				}
+			}
+			self.position = after_number;
+			Ok(Object::Integer(value))
+		} else {
+			token.parse::<f64>()
+				.map(Object::Real)
+				.map_err(|_| PdfAError::Parse { offset: start, message: "invalid number".into() })
+		}
+	}
+
+	/// Parse a literal string after the opening parenthesis.
+	fn parse_literal_string(&mut self) -> Result<Vec<u8>, PdfAError> {
+		let mut data = Vec::new();
+		let mut depth = 0;
+		loop {
+			match self.next_byte()? {
+				b'(' => {
+					depth += 1;
+					data.push(b'(');
+				},
+				b')' if depth == 0 => return Ok(data),
+				b')' => {
+					depth -= 1;
+					data.push(b')');
+				},
+				b'\\' => match self.next_byte()? {
+					b'n' => data.push(b'\n'),
+					b'r' => data.push(b'\r'),
+					b't' => data.push(b'\t'),
+					b'b' => data.push(b'\x08'),
+					b'f' => data.push(b'\x0C'),
+					b'\r' => {
+						if self.peek() == Some(b'\n') {
+							self.position += 1;
+						}
+					},
+					b'\n' => (),
+					digit @ b'0'..=b'7' => {
+						let mut value = u32::from(digit - b'0');
+						for _ in 0..2 {
+							match self.peek() {
+								Some(digit @ b'0'..=b'7') => {
+									value = value * 8 + u32::from(digit - b'0');
+									self.position += 1;
+								},
+								_ => break,
+							}
+						}
+						data.push(value as u8);
+					},
+					byte => data.push(byte),
+				},
+				byte => data.push(byte),
+			}
+		}
+	}
+}
+
+/// Decode the `#xx` escapes in a name.
+fn decode_name(token: &[u8]) -> Vec<u8> {
+	let mut name = Vec::with_capacity(token.len());
+	let mut i = 0;
+	while i < token.len() {
+		let escaped = token.get(i + 1..i + 3)
+			.filter(|_| token[i] == b'#')
+			.and_then(|hex| std::str::from_utf8(hex).ok())
+			.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+		match escaped {
+			Some(byte) => {
+				name.push(byte);
+				i += 3;
+			},
+			None => {
+				name.push(token[i]);
+				i += 1;
+			},
+		}
+	}
+	name
+}
+
+/// An incremental update to a PDF file.
+struct Update<'a> {
+	document: &'a Document<'a>,
+
+	/// The next free object number.
+	next_number: u32,
+
+	/// The new and replaced objects, with their generation and the serialized object.
+	objects: BTreeMap<u32, (u16, Vec<u8>)>,
+}
+
+impl<'a> Update<'a> {
+	fn new(document: &'a Document<'a>) -> Self {
+		let size = document.trailer.get(b"Size".as_slice()).and_then(Object::as_integer).unwrap_or(0) as u32;
+		let next_number = document.xref.keys().next_back().map_or(size, |last| size.max(last + 1));
+		Self {
+			document,
+			next_number,
+			objects: BTreeMap::new(),
+		}
+	}
+
+	/// Add a new object and return a reference to it.
+	fn add(&mut self, object: Object) -> Object {
+		let mut data = Vec::new();
+		object.write(&mut data);
+		self.add_raw(data)
+	}
+
+	/// Add a new stream and return a reference to it.
+	fn add_stream(&mut self, dict: Dictionary, data: Vec<u8>) -> Object {
+		self.add_raw(serialize_stream(dict, &data))
+	}
+
+	fn add_raw(&mut self, data: Vec<u8>) -> Object {
+		let number = self.next_number;
+		self.next_number += 1;
+		self.objects.insert(number, (0, data));
+		Object::Reference(number, 0)
+	}
+
+	/// Replace an existing object.
+	fn replace(&mut self, number: u32, generation: u16, object: Object) {
+		let mut data = Vec::new();
+		object.write(&mut data);
+		self.objects.insert(number, (generation, data));
+	}
+
+	/// Replace an existing stream, keeping the data as it is.
+	fn replace_stream(&mut self, number: u32, dict: Dictionary, data: Vec<u8>) {
+		let generation = match self.document.xref.get(&number) {
+			Some(&Location::Offset(_, generation)) => generation,
+			_ => 0,
+		};
+		self.objects.insert(number, (generation, serialize_stream(dict, &data)));
+	}
+
+	/// Append the update to the original file.
+	fn finish(self, mut trailer: Dictionary) -> Vec<u8> {
+		let mut output = self.document.data.to_vec();
+		if !output.ends_with(b"\n") {
+			output.push(b'\n');
+		}
+
+		let mut offsets = Vec::new();
+		for (number, (generation, data)) in &self.objects {
+			offsets.push((*number, *generation, output.len()));
+			output.extend_from_slice(format!("{} {} obj\n", number, generation).as_bytes());
+			output.extend_from_slice(data);
+			output.extend_from_slice(b"\nendobj\n");
+		}
+
+		// Use the same kind of cross-reference section as the original file,
+		// since readers that only know cross-reference tables would not understand a file that mixes both.
+		let xref_offset = output.len();
+		if self.document.xref_stream {
+			let number = self.next_number;
+			offsets.push((number, 0, xref_offset));
+			let mut entries = Vec::new();
+			for &(_, generation, offset) in &offsets {
+				entries.push(1);
+				entries.extend_from_slice(&(offset as u64).to_be_bytes());
+				entries.extend_from_slice(&generation.to_be_bytes());
+			}
+			trailer.insert(b"Type".to_vec(), Object::name("XRef"));
+			trailer.insert(b"Size".to_vec(), Object::Integer(i64::from(number) + 1));
+			trailer.insert(b"W".to_vec(), Object::Array(vec![Object::Integer(1), Object::Integer(8), Object::Integer(2)]));
+			trailer.insert(b"Index".to_vec(), Object::Array(subsections(&offsets).into_iter().flat_map(|(first, count)| {
+				[Object::Integer(i64::from(first)), Object::Integer(count as i64)]
+			}).collect()));
+			output.extend_from_slice(format!("{} 0 obj\n", number).as_bytes());
+			output.extend_from_slice(&serialize_stream(trailer, &entries));
+			output.extend_from_slice(b"\nendobj\n");
+		} else {
+			output.extend_from_slice(b"xref\n");
+			let mut entries = offsets.iter();
+			for (first, count) in subsections(&offsets) {
+				output.extend_from_slice(format!("{} {}\n", first, count).as_bytes());
+				for (_, generation, offset) in entries.by_ref().take(count) {
+					output.extend_from_slice(format!("{:010} {:05} n\r\n", offset, generation).as_bytes());
+				}
+			}
+			trailer.insert(b"Size".to_vec(), Object::Integer(i64::from(self.next_number)));
+			output.extend_from_slice(b"trailer\n");
+			Object::Dictionary(trailer).write(&mut output);
+			output.push(b'\n');
+		}
+		output.extend_from_slice(format!("startxref\n{}\n%%EOF\n", xref_offset).as_bytes());
+		output
+	}
+}
+
+/// Split sorted object numbers into runs of consecutive numbers.
+///
+/// Returns the first object number and the number of objects of each run.
+fn subsections(offsets: &[(u32, u16, usize)]) -> Vec<(u32, usize)> {
+	let mut subsections: Vec<(u32, usize)> = Vec::new();
+	for &(number, _, _) in offsets {
+		match subsections.last_mut() {
+			Some((first, count)) if *first + *count as u32 == number => *count += 1,
+			_ => subsections.push((number, 1)),
+		}
+	}
+	subsections
+}
+
+/// Serialize a stream with the correct length.
+fn serialize_stream(mut dict: Dictionary, data: &[u8]) -> Vec<u8> {
+	dict.insert(b"Length".to_vec(), Object::Integer(data.len() as i64));
+	let mut output = Vec::new();
+	Object::Dictionary(dict).write(&mut output);
+	output.extend_from_slice(b"\nstream\n");
+	output.extend_from_slice(data);
+	output.extend_from_slice(b"\nendstream");
+	output
+}
+
+#[cfg(test)]
+fn test_metadata() -> Metadata<'static> {
+	Metadata {
+		title: "Invoice 2024-001",
+		author: "Zelfstandige",
+		date: Date::new(2024, zzp::gregorian::March, 5).unwrap(),
+	}
+}
+
+/// Create a stream object with the correct length.
+#[cfg(test)]
+fn test_stream(dict: &str, data: &[u8]) -> Vec<u8> {
+	let mut output = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+	output.extend_from_slice(data);
+	output.extend_from_slice(b"\nendstream");
+	output
+}
+
+#[cfg(test)]
+fn test_deflate(data: &[u8]) -> Vec<u8> {
+	use std::io::Write;
+	let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+	encoder.write_all(data).unwrap();
+	encoder.finish().unwrap()
+}
+
+/// Create the objects of a single page document like cairo writes them, numbered from 1.
+#[cfg(test)]
+fn test_objects(embedded: bool) -> Vec<Vec<u8>> {
+	let descriptor: &[u8] = if embedded {
+		b"<< /Type /FontDescriptor /FontName /ABCDEF+Test#20Sans /Flags 32 /FontFile2 7 0 R >>"
+	} else {
+		b"<< /Type /FontDescriptor /FontName /ABCDEF+Test#20Sans /Flags 32 >>"
+	};
+	vec![
+		b"<< /Type /Catalog /Pages 2 0 R /AA << /O 9 0 R >> >>".to_vec(),
+		b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+		b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595.28 841.89] /Resources << /Font << /F1 4 0 R >> /XObject << /I1 8 0 R >> >> /Contents 6 0 R >>".to_vec(),
+		b"<< /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+Test#20Sans /FontDescriptor 5 0 R >>".to_vec(),
+		descriptor.to_vec(),
+		test_stream("", b"BT /F1 12 Tf (Invoice \\(draft\\)) Tj ET q 10 0 0 10 0 0 cm /I1 Do Q"),
+		test_stream("/Length1 4", b"\0\x01\0\0"),
+		test_stream("/Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Interpolate true", b"\xFF\0\0"),
+	]
+}
+
+/// Create a PDF file with a classic cross-reference table.
+#[cfg(test)]
+fn test_pdf_xref_table(objects: &[Vec<u8>], trailer: &str) -> Vec<u8> {
+	let mut output = b"%PDF-1.5\n%\xE2\xE3\xCF\xD3\n".to_vec();
+	let mut offsets = Vec::new();
+	for (i, object) in objects.iter().enumerate() {
+		offsets.push(output.len());
+		output.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+		output.extend_from_slice(object);
+		output.extend_from_slice(b"\nendobj\n");
+	}
+	let xref = output.len();
+	output.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f\r\n", objects.len() + 1).as_bytes());
+	for offset in offsets {
+		output.extend_from_slice(format!("{:010} 00000 n\r\n", offset).as_bytes());
+	}
+	output.extend_from_slice(format!("trailer\n<< /Size {} {} >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, trailer, xref).as_bytes());
+	output
+}
+
+/// Create a PDF file with a cross-reference stream that uses the PNG up predictor.
+///
+/// The objects with the given numbers are stored in a compressed object stream.
+#[cfg(test)]
+fn test_pdf_xref_stream(objects: &[Vec<u8>], compressed: &[usize]) -> Vec<u8> {
+	let object_stream = objects.len() + 1;
+	let xref_stream = objects.len() + 2;
+
+	// The type, offset or object stream, and generation or index of each object.
+	let mut entries = vec![(0u8, 0u16, 0u8); xref_stream + 1];
+	let mut output = b"%PDF-1.5\n%\xE2\xE3\xCF\xD3\n".to_vec();
+	let mut header = String::new();
+	let mut body = Vec::new();
+	for (i, object) in objects.iter().enumerate() {
+		let number = i + 1;
+		if let Some(index) = compressed.iter().position(|&x| x == number) {
+			header.push_str(&format!("{} {} ", number, body.len()));
+			body.extend_from_slice(object);
+			body.push(b'\n');
+			entries[number] = (2, object_stream as u16, index as u8);
+		} else {
+			entries[number] = (1, output.len() as u16, 0);
+			output.extend_from_slice(format!("{} 0 obj\n", number).as_bytes());
+			output.extend_from_slice(object);
+			output.extend_from_slice(b"\nendobj\n");
+		}
+	}
+
+	let mut data = header.into_bytes();
+	let first = data.len();
+	data.extend_from_slice(&body);
+	entries[object_stream] = (1, output.len() as u16, 0);
+	output.extend_from_slice(format!("{} 0 obj\n", object_stream).as_bytes());
+	output.extend_from_slice(&test_stream(&format!("/Type /ObjStm /N {} /First {} /Filter /FlateDecode", compressed.len(), first), &test_deflate(&data)));
+	output.extend_from_slice(b"\nendobj\n");
+
+	let xref = output.len();
+	entries[xref_stream] = (1, xref as u16, 0);
+	let mut rows = Vec::new();
+	let mut previous = [0u8; 4];
+	for (kind, field1, field2) in entries {
+		let row = [kind, (field1 >> 8) as u8, field1 as u8, field2];
+		rows.push(2);
+		rows.extend(row.iter().zip(&previous).map(|(current, previous)| current.wrapping_sub(*previous)));
+		previous = row;
+	}
+	let dict = format!("/Type /XRef /Size {} /Root 1 0 R /W [1 2 1] /Filter /FlateDecode /DecodeParms << /Predictor 12 /Columns 4 >>", xref_stream + 1);
+	output.extend_from_slice(format!("{} 0 obj\n", xref_stream).as_bytes());
+	output.extend_from_slice(&test_stream(&dict, &test_deflate(&rows)));
+	output.extend_from_slice(format!("\nendobj\nstartxref\n{}\n%%EOF\n", xref).as_bytes());
+	output
+}
+
+/// Check that a converted file still has the original objects and the additions for PDF/A.
+#[cfg(test)]
+fn check_converted(original: &[u8], converted: &[u8], xref_stream: bool) {
+	use assert2::{assert, let_assert};
+
+	assert!(converted.starts_with(original));
+	let_assert!(Ok(document) = Document::parse(converted));
+	assert!(document.xref_stream == xref_stream);
+	assert!(document.trailer.get(b"Prev".as_slice()).and_then(Object::as_integer).is_some());
+
+	let_assert!(Some(root) = document.trailer.get(b"Root".as_slice()));
+	let_assert!(Ok(Object::Dictionary(catalog)) = document.resolve(root));
+	assert!(catalog.get(b"Pages".as_slice()) == Some(&Object::Reference(2, 0)));
+	assert!(!catalog.contains_key(b"AA".as_slice()));
+
+	let_assert!(Some(Object::Reference(metadata, 0)) = catalog.get(b"Metadata".as_slice()));
+	let_assert!(Ok((Object::Dictionary(_), Some(xmp))) = document.get(*metadata));
+	let xmp = String::from_utf8_lossy(xmp);
+	assert!(xmp.contains("<pdfaid:part>3</pdfaid:part>"));
+	assert!(xmp.contains("<rdf:li xml:lang=\"x-default\">Invoice 2024-001</rdf:li>"));
+	assert!(xmp.contains("<xmp:CreateDate>2024-03-05T00:00:00Z</xmp:CreateDate>"));
+
+	let_assert!(Some(Object::Array(intents)) = catalog.get(b"OutputIntents".as_slice()));
+	assert!(intents.len() == 1);
+	let_assert!(Ok(Object::Dictionary(intent)) = document.resolve(&intents[0]));
+	assert!(intent.get(b"S".as_slice()) == Some(&Object::name("GTS_PDFA1")));
+	let_assert!(Some(Object::Reference(profile, 0)) = intent.get(b"DestOutputProfile".as_slice()));
+	let_assert!(Ok((Object::Dictionary(profile_dict), Some(profile))) = document.get(*profile));
+	assert!(profile_dict.get(b"N".as_slice()) == Some(&Object::Integer(3)));
+	assert!(profile == srgb_profile().as_slice());
+
+	let_assert!(Some(info) = document.trailer.get(b"Info".as_slice()));
+	let_assert!(Ok(Object::Dictionary(info)) = document.resolve(info));
+	assert!(info.get(b"Title".as_slice()) == Some(&text_string("Invoice 2024-001")));
+	assert!(info.get(b"CreationDate".as_slice()) == Some(&Object::String(b"D:20240305000000Z".to_vec())));
+	let_assert!(Some(Object::Array(id)) = document.trailer.get(b"ID".as_slice()));
+	assert!(id.len() == 2);
+
+	// The objects of the original file can still be read, and image interpolation is disabled.
+	let_assert!(Ok((Object::Dictionary(font), None)) = document.get(4));
+	assert!(font.get(b"BaseFont".as_slice()) == Some(&Object::Name(b"ABCDEF+Test Sans".to_vec())));
+	let_assert!(Ok((Object::Dictionary(image), Some(pixels))) = document.get(8));
+	assert!(image.get(b"Interpolate".as_slice()) == Some(&Object::Boolean(false)));
+	assert!(pixels == b"\xFF\0\0");
+}
+
+#[cfg(test)]
+#[test]
+fn test_convert_xref_table() {
+	use assert2::{assert, let_assert};
+
+	let original = test_pdf_xref_table(&test_objects(true), "/Root 1 0 R");
+	let_assert!(Ok(converted) = convert(&original, &test_metadata()));
+	check_converted(&original, &converted, false);
+
+	// The update itself can be parsed as an update to the converted file.
+	let_assert!(Ok(again) = convert(&converted, &test_metadata()));
+	check_converted(&converted, &again, false);
+
+	// The permanent part of the file identifier is kept.
+	let original = test_pdf_xref_table(&test_objects(true), "/Root 1 0 R /ID [<0102> <0102>]");
+	let_assert!(Ok(converted) = convert(&original, &test_metadata()));
+	let_assert!(Ok(document) = Document::parse(&converted));
+	let_assert!(Some(Object::Array(id)) = document.trailer.get(b"ID".as_slice()));
+	assert!(id[0] == Object::String(vec![1, 2]));
+	assert!(id[1] != Object::String(vec![1, 2]));
+}
+
+#[cfg(test)]
+#[test]
+fn test_convert_xref_stream() {
+	use assert2::let_assert;
+
+	// The catalog, the pages and the font are stored in a compressed object stream.
+	let original = test_pdf_xref_stream(&test_objects(true), &[1, 2, 4, 5]);
+	let_assert!(Ok(converted) = convert(&original, &test_metadata()));
+	check_converted(&original, &converted, true);
+
+	let_assert!(Ok(again) = convert(&converted, &test_metadata()));
+	check_converted(&converted, &again, true);
+}
+
+#[cfg(test)]
+#[test]
+fn test_convert_errors() {
+	use assert2::assert;
+
+	let original = test_pdf_xref_table(&test_objects(false), "/Root 1 0 R");
+	assert!(convert(&original, &test_metadata()) == Err(PdfAError::FontNotEmbedded("ABCDEF+Test Sans".into())));
+
+	let original = test_pdf_xref_stream(&test_objects(false), &[1, 2, 4, 5]);
+	assert!(convert(&original, &test_metadata()) == Err(PdfAError::FontNotEmbedded("ABCDEF+Test Sans".into())));
+
+	let original = test_pdf_xref_table(&test_objects(true), "/Root 1 0 R /Encrypt << /Filter /Standard >>");
+	assert!(convert(&original, &test_metadata()) == Err(PdfAError::Encrypted));
+
+	assert!(let Err(PdfAError::Parse { .. }) = convert(b"%PDF-1.5\nnot a pdf file\n", &test_metadata()));
+
+	let original = test_pdf_xref_table(&test_objects(true), "/Root 1 0 R");
+	assert!(let Err(PdfAError::Parse { .. }) = convert(&original[..original.len() / 2], &test_metadata()));
+}
+
+#[cfg(test)]
+#[test]
+fn test_unpredict() {
+	use assert2::assert;
+
+	let document = Document {
+		data: b"",
+		xref: BTreeMap::new(),
+		trailer: Dictionary::new(),
+		startxref: 0,
+		xref_stream: false,
+	};
+	let params = dictionary([("Predictor", Object::Integer(15)), ("Columns", Object::Integer(3))]);
+
+	// Rows with the none, sub, up, average and paeth predictors.
+	let data = vec![
+		0, 10, 20, 30,
+		1, 5, 1, 1,
+		2, 1, 1, 1,
+		3, 3, 3, 3,
+		4, 1, 2, 3,
+	];
+	assert!(document.unpredict(0, &params, data) == Ok(vec![
+		10, 20, 30,
+		5, 6, 7,
+		6, 7, 8,
+		6, 9, 11,
+		7, 11, 14,
+	]));
+
+	assert!(let Err(PdfAError::Parse { .. }) = document.unpredict(0, &params, vec![5, 1, 2, 3]));
+	let tiff = dictionary([("Predictor", Object::Integer(2))]);
+	assert!(let Err(PdfAError::Parse { .. }) = document.unpredict(0, &tiff, vec![1, 2, 3]));
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_objects() {
+	use assert2::assert;
+
+	let parse = |data: &[u8]| Parser::new(data, 0).parse_object();
+	assert!(parse(b"<< /A 1 /B [2 0 R -3.5 true null] /C (a\\(b\\)\\101) /D <48 69> /E#20F /G >>") == Ok(Object::Dictionary(dictionary([
+		("A", Object::Integer(1)),
+		("B", Object::Array(vec![Object::Reference(2, 0), Object::Real(-3.5), Object::Boolean(true), Object::Null])),
+		("C", Object::String(b"a(b)A".to_vec())),
+		("D", Object::String(b"Hi".to_vec())),
+		("E F", Object::name("G")),
+	]))));
+	assert!(parse(b"% comment\n[1 2]") == Ok(Object::Array(vec![Object::Integer(1), Object::Integer(2)])));
+	assert!(let Err(PdfAError::Parse { .. }) = parse(b"<< /A 1"));
+	assert!(let Err(PdfAError::Parse { .. }) = parse(b"<zz>"));
+	assert!(let Err(PdfAError::Parse { .. }) = parse(b"nope"));
+
+	// Written objects parse to the same object.
+	let object = Object::Dictionary(dictionary([
+		("Name With Space", Object::name("A/B#C")),
+		("String", Object::String(b"(\xFF)".to_vec())),
+		("Array", Object::Array(vec![Object::Reference(12, 1), Object::Integer(-4), Object::Boolean(false)])),
+	]));
+	let mut data = Vec::new();
+	object.write(&mut data);
+	assert!(parse(&data) == Ok(object));
+}
+
+#[cfg(test)]
+#[test]
+fn test_subsections() {
+	use assert2::assert;
+
+	assert!(subsections(&[]) == Vec::new());
+	assert!(subsections(&[(1, 0, 0), (2, 0, 0), (5, 0, 0), (7, 0, 0), (8, 0, 0)]) == vec![(1, 2), (5, 1), (7, 2)]);
+}
@@ -0,0 +1,195 @@
+//! Delivery of UBL e-invoices through a Peppol access point.
+//!
+//! The e-invoice is posted to the API of the access point over HTTPS.
+//! The access point reads the receiver from the e-invoice and sends it to the customer over the Peppol network.
+
+use std::path::{Path, PathBuf};
+use zzp::gregorian::Date;
+
+use crate::register::{InvoiceRegister, PeppolStatus, RegisterError};
+use crate::{PeppolConfig, WriteFileError, ZzpConfig};
+
+/// The default field of the JSON response with the reference of the submitted invoice.
+pub const DEFAULT_REFERENCE_FIELD: &str = "id";
+
+/// Submit an invoice from the register to the access point and record the result in the register.
+///
+/// The UBL e-invoice is the file of the invoice with the `xml` extension.
+/// Returns the reference of the invoice at the access point, if the access point responded with one.
+///
+/// The `root_dir` is the directory containing `zzp.toml`.
+pub fn deliver_invoice(root_dir: &Path, config: &ZzpConfig, register: &mut InvoiceRegister, number: &str) -> Result<Option<String>, PeppolError> {
+	let peppol = config.peppol.as_ref()
+		.ok_or(PeppolError::NoPeppolConfig)?;
+	let invoice = register.find(number)
+		.ok_or_else(|| RegisterError::NotFound(number.into()))?;
+	let ubl_path = root_dir.join(&invoice.file).with_extension("xml");
+	if !ubl_path.exists() {
+		return Err(PeppolError::NoUbl { number: number.into(), path: ubl_path });
+	}
+
+	let result = submit(peppol, &ubl_path);
+	let (status, reference) = match &result {
+		Ok(reference) => (PeppolStatus::Submitted, reference.clone()),
+		Err(_) => (PeppolStatus::Failed, None),
+	};
+	register.mark_peppol(number, Date::today(), status, reference)?;
+	register.save()
+		.map_err(PeppolError::SaveRegister)?;
+	result.map_err(|error| PeppolError::Submit { number: number.into(), error })
+}
+
+/// Post a UBL e-invoice to the access point.
+///
+/// Returns the reference of the invoice from the response, if any.
+fn submit(config: &PeppolConfig, ubl_path: &Path) -> Result<Option<String>, SubmitError> {
+	let ubl = std::fs::read(ubl_path)
+		.map_err(|e| SubmitError::ReadUbl(ubl_path.to_path_buf(), e))?;
+
+	// Read the body of error responses too, since it usually explains what is wrong with the invoice.
+	let agent: ureq::Agent = ureq::Agent::config_builder()
+		.http_status_as_error(false)
+		.build()
+		.into();
+	let mut request = agent.post(&config.endpoint)
+		.header("Content-Type", "application/xml");
+	if let Some(name) = &config.token_env {
+		let token = std::env::var(name)
+			.map_err(|error| SubmitError::Token { name: name.clone(), error })?;
+		request = match &config.token_header {
+			Some(header) => request.header(header, token),
+			None => request.header("Authorization", format!("Bearer {}", token)),
+		};
+	}
+
+	let mut response = request.send(&ubl)
+		.map_err(|error| SubmitError::Post { endpoint: config.endpoint.clone(), error })?;
+	let status = response.status();
+	let body = response.body_mut().read_to_string()
+		.map_err(SubmitError::ReadResponse)?;
+	let field = config.reference_field.as_deref().unwrap_or(DEFAULT_REFERENCE_FIELD);
+	parse_response(status.as_u16(), &body, field)
+}
+
+/// Get the result of a submission from the HTTP status and the body of the response of the access point.
+///
+/// Returns an error if the status is not a success status, or the reference of the invoice from `field` otherwise.
+fn parse_response(status: u16, body: &str, field: &str) -> Result<Option<String>, SubmitError> {
+	if !(200..300).contains(&status) {
+		return Err(SubmitError::Rejected { status, body: body.trim().to_string() });
+	}
+	Ok(find_reference(body, field))
+}
+
+/// Get the reference of a submitted invoice from a field of the JSON response.
+///
+/// The invoice is submitted even if the response has no reference, so this only logs a warning in that case.
+fn find_reference(body: &str, field: &str) -> Option<String> {
+	let response: serde_json::Value = match serde_json::from_str(body) {
+		Ok(x) => x,
+		Err(e) => {
+			log::warn!("the response of the access point is not valid JSON: {}", e);
+			return None;
+		},
+	};
+	match response.get(field) {
+		Some(serde_json::Value::String(reference)) => Some(reference.clone()),
+		Some(serde_json::Value::Number(reference)) => Some(reference.to_string()),
+		_ => {
+			log::warn!("the response of the access point has no {:?} field with the reference of the invoice", field);
+			None
+		},
+	}
+}
+
+/// An error while delivering an invoice through the Peppol access point.
+#[derive(Debug)]
+pub enum PeppolError {
+	/// The configuration has no `[Peppol]` section.
+	NoPeppolConfig,
+
+	/// The invoice could not be found or updated in the register.
+	Register(RegisterError),
+
+	/// The invoice has no UBL e-invoice.
+	NoUbl { number: String, path: PathBuf },
+
+	/// The invoice register could not be saved.
+	SaveRegister(WriteFileError),
+
+	/// The invoice could not be submitted to the access point.
+	Submit { number: String, error: SubmitError },
+}
+
+/// An error while submitting an e-invoice to the access point.
+#[derive(Debug)]
+pub enum SubmitError {
+	/// The UBL e-invoice could not be read.
+	ReadUbl(PathBuf, std::io::Error),
+
+	/// The API token could not be read from the environment.
+	Token { name: String, error: std::env::VarError },
+
+	/// The e-invoice could not be posted to the access point.
+	Post { endpoint: String, error: ureq::Error },
+
+	/// The response of the access point could not be read.
+	ReadResponse(ureq::Error),
+
+	/// The access point responded with an HTTP error status.
+	Rejected { status: u16, body: String },
+}
+
+impl From<RegisterError> for PeppolError {
+	fn from(other: RegisterError) -> Self {
+		Self::Register(other)
+	}
+}
+
+impl std::error::Error for PeppolError {}
+impl std::fmt::Display for PeppolError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::NoPeppolConfig => write!(f, "missing [Peppol] section in zzp.toml"),
+			Self::Register(e) => write!(f, "{}", e),
+			Self::NoUbl { number, path } => write!(f, "invoice {} has no UBL e-invoice: {} does not exist", number, path.display()),
+			Self::SaveRegister(e) => write!(f, "{}", e),
+			Self::Submit { number, error } => write!(f, "failed to submit invoice {} to the Peppol access point: {}", number, error),
+		}
+	}
+}
+
+impl std::error::Error for SubmitError {}
+impl std::fmt::Display for SubmitError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::ReadUbl(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+			Self::Token { name, error } => write!(f, "failed to read API token from environment variable {}: {}", name, error),
+			Self::Post { endpoint, error } => write!(f, "failed to post to {}: {}", endpoint, error),
+			Self::ReadResponse(e) => write!(f, "failed to read the response of the access point: {}", e),
+			Self::Rejected { status, body } => write!(f, "the access point responded with HTTP status {}: {}", status, body),
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_response() {
+	use assert2::{assert, let_assert};
+
+	assert!(parse_response(201, r#"{"id": "abc-123", "status": "queued"}"#, "id").unwrap() == Some("abc-123".into()));
+	assert!(parse_response(202, r#"{"transmission": 42}"#, "transmission").unwrap() == Some("42".into()));
+
+	// The invoice was accepted, even if the response has no usable reference.
+	assert!(parse_response(200, r#"{"id": null}"#, "id").unwrap() == None);
+	assert!(parse_response(200, r#"{"reference": "abc-123"}"#, "id").unwrap() == None);
+	assert!(parse_response(200, r#"["abc-123"]"#, "id").unwrap() == None);
+	assert!(parse_response(200, "", "id").unwrap() == None);
+	assert!(parse_response(204, "accepted", "id").unwrap() == None);
+
+	let_assert!(Err(SubmitError::Rejected { status, body }) = parse_response(400, "  invalid invoice: missing BuyerReference\n", "id"));
+	assert!(status == 400);
+	assert!(body == "invalid invoice: missing BuyerReference");
+	assert!(let Err(SubmitError::Rejected { status: 500, .. }) = parse_response(500, r#"{"id": "abc-123"}"#, "id"));
+	assert!(let Err(SubmitError::Rejected { status: 301, .. }) = parse_response(301, "", "id"));
+}
@@ -0,0 +1,83 @@
+//! Periods given on the command line.
+//!
+//! A period is a partial date like `2024-Q2` or `last-month`,
+//! or the name of a custom period from the `[[Period]]` sections of `zzp.toml` or `customer.toml`.
+
+use std::ops::Range;
+use zzp::gregorian::Date;
+use zzp::partial_date::{PartialDate, ParsePartialDateError};
+
+use crate::PeriodConfig;
+
+/// A period given on the command line.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Period {
+	/// A year, half year, quarter, month, week or day.
+	Date(PartialDate),
+
+	/// The name of a custom period from the configuration.
+	Named(String),
+}
+
+impl Period {
+	/// Get the period as half-open date range.
+	///
+	/// Named periods are looked up in `periods`.
+	pub fn as_range(&self, periods: &[PeriodConfig]) -> Result<Range<Date>, String> {
+		match self {
+			Self::Date(date) => Ok(date.as_range()),
+			Self::Named(name) => {
+				let period = periods.iter()
+					.find(|x| x.name == *name)
+					.ok_or_else(|| unknown_period(name, periods))?;
+				if period.end_date < period.start_date {
+					return Err(format!("the end date of period {:?} is before the start date", name));
+				}
+				Ok(period.as_range())
+			},
+		}
+	}
+
+	/// Check if the period needs the custom periods from the configuration.
+	pub fn is_named(&self) -> bool {
+		matches!(self, Self::Named(_))
+	}
+}
+
+fn unknown_period(name: &str, periods: &[PeriodConfig]) -> String {
+	if periods.is_empty() {
+		format!("unknown period {:?}, no custom periods are configured", name)
+	} else {
+		let names: Vec<_> = periods.iter().map(|x| x.name.as_str()).collect();
+		format!("unknown period {:?}, expected a date or one of: {}", name, names.join(", "))
+	}
+}
+
+impl std::str::FromStr for Period {
+	type Err = ParsePartialDateError;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		match input.parse() {
+			Ok(date) => Ok(Self::Date(date)),
+			Err(e) => {
+				// Anything that does not start with a digit can be the name of a custom period.
+				let is_name = input.starts_with(|c: char| c.is_ascii_alphabetic())
+					&& input.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+				if is_name {
+					Ok(Self::Named(input.to_string()))
+				} else {
+					Err(e)
+				}
+			},
+		}
+	}
+}
+
+impl std::fmt::Display for Period {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Date(date) => write!(f, "{}", date),
+			Self::Named(name) => write!(f, "{}", name),
+		}
+	}
+}
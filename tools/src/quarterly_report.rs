@@ -0,0 +1,231 @@
+//! Quarterly management report with the results, VAT position and hours of a quarter.
+
+use pdf_writer::{A4, BoxPosition, PdfWriter, Margins, mm, pt, MM_PER_PT};
+use zzp::grootboek::Cents;
+use zzp::uurlog::Hours;
+
+use crate::income_statement::IncomeStatement;
+use crate::json::Json;
+use crate::vat_return::VatReturn;
+use crate::ZzpConfig;
+
+/// The number of customers listed in the top customers of a quarterly report.
+pub const TOP_CUSTOMERS: usize = 5;
+
+/// The figures of a quarter for the quarterly report.
+#[derive(Debug, Clone)]
+pub struct QuarterlyReport {
+	/// The quarter, like `2024-Q2`.
+	pub period: String,
+
+	/// The total revenue.
+	pub revenue: Cents,
+
+	/// The total costs.
+	pub costs: Cents,
+
+	/// The VAT position, if the VAT return is configured.
+	pub vat: Option<VatPosition>,
+
+	/// The logged hours per customer, sorted by name.
+	pub hours: Vec<(String, Hours)>,
+
+	/// The customers with the most revenue in the quarter, with their revenue.
+	pub top_customers: Vec<(String, Cents)>,
+}
+
+/// The VAT position of a quarter in whole euros.
+#[derive(Debug, Copy, Clone)]
+pub struct VatPosition {
+	/// The VAT to pay (box 5a).
+	pub vat: Cents,
+
+	/// The VAT input tax (box 5b).
+	pub input_vat: Cents,
+
+	/// The VAT to pay, or to receive if negative (box 5c).
+	pub vat_due: Cents,
+}
+
+impl QuarterlyReport {
+	/// Create the report from the income statement and VAT return of the quarter.
+	///
+	/// The `customers` are the revenue of each customer in the quarter.
+	/// Only the customers with the most revenue are kept.
+	pub fn new(period: String, statement: &IncomeStatement, vat_return: Option<&VatReturn>, hours: Vec<(String, Hours)>, mut customers: Vec<(String, Cents)>) -> Self {
+		customers.retain(|(_, revenue)| *revenue != Cents(0));
+		customers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		customers.truncate(TOP_CUSTOMERS);
+		Self {
+			period,
+			revenue: statement.total_revenue(),
+			costs: statement.total_expenses(),
+			vat: vat_return.map(|x| VatPosition {
+				vat: x.total_vat(),
+				input_vat: x.total_input_vat(),
+				vat_due: x.vat_due(),
+			}),
+			hours,
+			top_customers: customers,
+		}
+	}
+
+	/// The result: the profit, or the loss if negative.
+	pub fn result(&self) -> Cents {
+		self.revenue - self.costs
+	}
+
+	/// The total of the logged hours.
+	pub fn total_hours(&self) -> Hours {
+		self.hours.iter().fold(Hours::from_minutes(0), |total, (_, hours)| total + *hours)
+	}
+
+	/// Get the report as JSON.
+	pub fn to_json(&self) -> Json {
+		Json::object([
+			("period", self.period.as_str().into()),
+			("revenue", self.revenue.into()),
+			("costs", self.costs.into()),
+			("result", self.result().into()),
+			("vat", self.vat.map(|vat| Json::object([
+				("vat", vat.vat.into()),
+				("input_vat", vat.input_vat.into()),
+				("vat_due", vat.vat_due.into()),
+			])).into()),
+			("hours", Json::array(self.hours.iter().map(|(customer, hours)| Json::object([
+				("customer", customer.into()),
+				("hours", (*hours).into()),
+			])))),
+			("total_hours", self.total_hours().into()),
+			("top_customers", Json::array(self.top_customers.iter().map(|(customer, revenue)| Json::object([
+				("customer", customer.into()),
+				("revenue", (*revenue).into()),
+			])))),
+		])
+	}
+
+	/// Render the report as PDF document.
+	pub fn make_pdf<W>(&self, stream: W, config: &ZzpConfig) -> Result<(), String>
+	where
+		W: std::io::Write + 'static,
+	{
+		let mut writer = PdfWriter::new(stream)?;
+		let currency = config.currency();
+		let money = |amount: Cents| currency.format(amount).replacen('-', "\u{2212}", 1);
+
+		let font_size = pt(*config.invoice.font_size);
+		let font_family = config.invoice.font_family();
+		let basic = pdf_writer::TextStyle {
+			font: pdf_writer::FontSpec::plain(&font_family, font_size),
+			align: pdf_writer::TextAlign::Left,
+			justify: false,
+			line_height: 1.0,
+		};
+		let basic_right = pdf_writer::TextStyle {
+			align: pdf_writer::TextAlign::Right,
+			.. basic.clone()
+		};
+		let bold = |font_size| pdf_writer::TextStyle {
+			font: pdf_writer::FontSpec::bold(&font_family, font_size),
+			align: pdf_writer::TextAlign::Left,
+			justify: false,
+			line_height: 0.8,
+		};
+		let bold_right = pdf_writer::TextStyle {
+			align: pdf_writer::TextAlign::Right,
+			.. bold(font_size)
+		};
+
+		let page = writer.page(A4, Margins::vh(mm(30.0), mm(20.0)))?;
+		let vskip = font_size * 1.5 * MM_PER_PT;
+
+		let title = page.draw_text_box("Quarterly report", &bold(font_size * 2.8), BoxPosition::at_xy(mm(20.0), mm(30.0)), None)?;
+		let mut y = mm(title.logical.max.y) + vskip;
+		let subtitle = page.draw_text_box(&format!("{}\n{}", config.company.name, self.period), &basic, BoxPosition::at_xy(mm(20.0), y), None)?;
+		y = mm(subtitle.logical.max.y) + vskip * 2.0;
+
+		let mut table = pdf_writer::TableBuilder::new(&writer, page.text_width());
+		table.position(BoxPosition::at_xy(mm(20.0), y));
+		table.cell_padding(Margins::vh(font_size * 0.25 * MM_PER_PT, font_size * 0.5 * MM_PER_PT));
+		table.add_column(true, None);
+		table.add_column(false, None);
+
+		let mut sections = Vec::new();
+		sections.push(Section {
+			title: "Results",
+			lines: vec![
+				("Revenue".into(), money(self.revenue)),
+				("Costs".into(), money(self.costs)),
+			],
+			total: Some(("Result".into(), money(self.result()))),
+		});
+		if let Some(vat) = &self.vat {
+			sections.push(Section {
+				title: "VAT",
+				lines: vec![
+					("VAT to pay".into(), money(vat.vat)),
+					("VAT input tax".into(), money(vat.input_vat)),
+				],
+				total: Some(("VAT due".into(), money(vat.vat_due))),
+			});
+		}
+		sections.push(Section {
+			title: "Hours",
+			lines: self.hours.iter()
+				.map(|(customer, hours)| (customer.clone(), hours.to_string()))
+				.collect(),
+			total: Some(("Total hours".into(), self.total_hours().to_string())),
+		});
+		if !self.top_customers.is_empty() {
+			sections.push(Section {
+				title: "Top customers",
+				lines: self.top_customers.iter()
+					.map(|(customer, revenue)| (customer.clone(), money(*revenue)))
+					.collect(),
+				total: None,
+			});
+		}
+
+		let mut borders = Vec::new();
+		let mut rows = 0;
+		for section in &sections {
+			table.add_cell(section.title, &bold(font_size))?;
+			table.add_cell("", &basic)?;
+			for (name, value) in &section.lines {
+				table.add_cell(name, &basic)?;
+				table.add_cell(value, &basic_right)?;
+			}
+			rows += section.lines.len() + 1;
+			if let Some((name, value)) = &section.total {
+				table.add_cell(name, &bold(font_size))?;
+				table.add_cell(value, &bold_right)?;
+				rows += 1;
+				borders.push(rows - 1);
+			}
+			table.add_cell("", &basic)?;
+			table.add_cell("", &basic)?;
+			rows += 1;
+		}
+
+		let table = table.build();
+		table.draw(&page);
+		for row in borders {
+			table.draw_horizontal_border(&page, row, .., pt(0.5));
+		}
+		page.emit(&writer)?;
+
+		Ok(())
+	}
+}
+
+/// A section of the PDF report.
+struct Section {
+	/// The title of the section.
+	title: &'static str,
+
+	/// The lines of the section with their value.
+	lines: Vec<(String, String)>,
+
+	/// The total with its value, drawn with a border above it.
+	total: Option<(String, String)>,
+}
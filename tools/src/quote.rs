@@ -0,0 +1,63 @@
+//! Generation of quotes with the invoice layout.
+
+use zzp::gregorian::Date;
+
+use crate::invoice::{format_date, DocumentDetails, InvoiceEntry, InvoiceRenderer};
+use crate::{Customer, ZzpConfig};
+
+/// The default number of days a quote stays valid.
+pub const DEFAULT_VALID_DAYS: u32 = 30;
+
+/// A quote with the entries to offer.
+///
+/// The entries use the same format as an `InvoiceFile`,
+/// so an accepted quote can be turned into an invoice with the same entries.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuoteFile {
+	/// The number of days the quote stays valid, overriding the configured default.
+	pub valid_days: Option<u32>,
+
+	#[serde(rename = "Entry")]
+	pub entries: Vec<InvoiceEntry>,
+}
+
+impl QuoteFile {
+	/// Get the last day the quote is valid when created on the given date.
+	pub fn valid_until(&self, config: &ZzpConfig, quote_date: Date) -> Date {
+		let valid_days = self.valid_days
+			.or_else(|| config.quote.as_ref().and_then(|x| x.valid_days))
+			.unwrap_or(DEFAULT_VALID_DAYS);
+		(0..valid_days).fold(quote_date, |date, _| date.next())
+	}
+}
+
+pub fn make_quote<W>(
+	stream: W,
+	config: &ZzpConfig,
+	recipient: &Customer,
+	quote_number: &str,
+	quote_date: Date,
+	valid_until: Date,
+	entries: &[InvoiceEntry],
+) -> Result<(), String>
+where
+	W: std::io::Write + 'static,
+{
+	let lang = config.quote_localization.as_ref()
+		.ok_or("missing [QuoteLocalization] section in zzp.toml")?;
+	let document = DocumentDetails {
+		title: &lang.quote,
+		number: quote_number,
+		date: quote_date,
+		details: vec![
+			(&lang.quote_number, quote_number.to_string()),
+			(&lang.quote_date, format_date(quote_date, &config.date_localization)),
+			(&lang.valid_until, format_date(valid_until, &config.date_localization)),
+		],
+		notes: &[],
+		footer: &lang.footer,
+		watermark: None,
+	};
+	InvoiceRenderer::new(config).render_document(stream, config, recipient, &document, entries)
+}
@@ -0,0 +1,447 @@
+//! Register of generated invoices and their payment status.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+
+use crate::{ReadFileError, WriteFileError, ZzpConfig};
+
+/// The default path of the invoice register, relative to the directory of `zzp.toml`.
+pub const DEFAULT_REGISTER_PATH: &str = "invoices.toml";
+
+/// The default number of days a customer has to pay an invoice.
+pub const DEFAULT_PAYMENT_DAYS: u32 = 30;
+
+/// The default number of days between payment reminders for the same invoice.
+pub const DEFAULT_REMINDER_INTERVAL: u32 = 14;
+
+/// The default description for the grootboek transaction of a payment.
+pub const DEFAULT_PAYMENT_DESCRIPTION: &str = "Payment of invoice {invoice_number}";
+
+/// The register of generated invoices.
+///
+/// The register is stored as a TOML file with an `[[Invoice]]` table for each invoice.
+#[derive(Debug, Clone)]
+pub struct InvoiceRegister {
+	path: PathBuf,
+	invoices: Vec<RegisteredInvoice>,
+}
+
+/// An invoice in the register.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegisteredInvoice {
+	/// The invoice number.
+	pub number: String,
+
+	/// The name of the customer.
+	pub customer: String,
+
+	/// The grootboek account with the outstanding amount of the invoice.
+	pub debitor_account: String,
+
+	/// The invoice date.
+	#[serde(with = "crate::serde_date")]
+	pub date: Date,
+
+	/// The date the invoice should be paid.
+	#[serde(with = "crate::serde_date")]
+	pub due_date: Date,
+
+	/// The ISO 4217 currency code of the invoice.
+	pub currency: String,
+
+	/// The total amount of the invoice including VAT, in cents.
+	pub total_cents: i32,
+
+	/// The invoice file, relative to the directory of `zzp.toml`.
+	pub file: PathBuf,
+
+	/// The status of the invoice.
+	pub status: InvoiceStatus,
+
+	/// The date the invoice was sent to the customer.
+	#[serde(default, with = "crate::serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub sent_date: Option<Date>,
+
+	/// The date the invoice was paid.
+	#[serde(default, with = "crate::serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub paid_date: Option<Date>,
+
+	/// The date the last payment reminder was sent to the customer.
+	#[serde(default, with = "crate::serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub reminder_date: Option<Date>,
+
+	/// The recurring invoice and month this invoice was generated for, like `retainer/2024-05`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub recurring: Option<String>,
+
+	/// The first day of the period with invoiced hours.
+	#[serde(default, with = "crate::serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub period_start: Option<Date>,
+
+	/// The day after the last day of the period with invoiced hours.
+	#[serde(default, with = "crate::serde_option_date", skip_serializing_if = "Option::is_none")]
+	pub period_end: Option<Date>,
+
+	/// The dates of the invoiced entries.
+	///
+	/// Hours on these dates are not invoiced again.
+	#[serde(default, with = "crate::serde_dates", skip_serializing_if = "Vec::is_empty")]
+	pub dates: Vec<Date>,
+
+	/// The status of the delivery through a Peppol access point, if the invoice was submitted to one.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub peppol_status: Option<PeppolStatus>,
+
+	/// The reference of the invoice at the Peppol access point.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub peppol_reference: Option<String>,
+}
+
+/// The status of an invoice.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+	/// The invoice has been created, but not sent.
+	Created,
+
+	/// The invoice has been sent to the customer.
+	Sent,
+
+	/// The invoice has been paid.
+	Paid,
+}
+
+/// The status of the delivery of an invoice through a Peppol access point.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeppolStatus {
+	/// The access point accepted the invoice.
+	Submitted,
+
+	/// Submitting the invoice to the access point failed.
+	Failed,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RegisterFile {
+	#[serde(rename = "Invoice")]
+	#[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+	invoices: Vec<RegisteredInvoice>,
+}
+
+impl InvoiceRegister {
+	/// Open the invoice register for a ZZP configuration.
+	///
+	/// The `root_dir` is the directory containing `zzp.toml`.
+	/// A missing register file is treated as an empty register.
+	pub fn open(root_dir: impl AsRef<Path>, config: &ZzpConfig) -> Result<Self, ReadFileError> {
+		let path = root_dir.as_ref().join(config.invoice.register.as_deref().unwrap_or(DEFAULT_REGISTER_PATH));
+		let file = if path.exists() {
+			crate::read_toml(&path)?
+		} else {
+			RegisterFile::default()
+		};
+		Ok(Self { path, invoices: file.invoices })
+	}
+
+	/// Get the path of the register file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Get all invoices in the register, in the order they were added.
+	pub fn invoices(&self) -> &[RegisteredInvoice] {
+		&self.invoices
+	}
+
+	/// Find an invoice by number.
+	pub fn find(&self, number: &str) -> Option<&RegisteredInvoice> {
+		self.invoices.iter().find(|x| x.number == number)
+	}
+
+	/// Find an invoice for a customer with invoiced hours in a period.
+	///
+	/// The period is a half-open range of dates.
+	pub fn find_period(&self, customer: &str, period: &Range<Date>) -> Option<&RegisteredInvoice> {
+		self.invoices.iter().find(|x| x.customer == customer && x.covers(period))
+	}
+
+	/// Find an invoice by number for modification.
+	pub fn find_mut(&mut self, number: &str) -> Option<&mut RegisteredInvoice> {
+		self.invoices.iter_mut().find(|x| x.number == number)
+	}
+
+	/// Add an invoice to the register.
+	///
+	/// If the register already has an invoice with the same number, it is replaced.
+	/// This happens when an invoice is generated again with an explicit number.
+	pub fn add(&mut self, invoice: RegisteredInvoice) {
+		match self.find_mut(&invoice.number) {
+			Some(existing) => *existing = invoice,
+			None => self.invoices.push(invoice),
+		}
+	}
+
+	/// Mark an invoice as sent.
+	///
+	/// A paid invoice keeps the paid status.
+	pub fn mark_sent(&mut self, number: &str, date: Date) -> Result<(), RegisterError> {
+		let invoice = self.find_mut(number)
+			.ok_or_else(|| RegisterError::NotFound(number.into()))?;
+		invoice.sent_date = Some(date);
+		if invoice.status == InvoiceStatus::Created {
+			invoice.status = InvoiceStatus::Sent;
+		}
+		Ok(())
+	}
+
+	/// Record the result of submitting an invoice to a Peppol access point.
+	///
+	/// A submitted invoice is also marked as sent.
+	pub fn mark_peppol(&mut self, number: &str, date: Date, status: PeppolStatus, reference: Option<String>) -> Result<(), RegisterError> {
+		let invoice = self.find_mut(number)
+			.ok_or_else(|| RegisterError::NotFound(number.into()))?;
+		invoice.peppol_status = Some(status);
+		invoice.peppol_reference = reference;
+		if status == PeppolStatus::Submitted {
+			self.mark_sent(number, date)?;
+		}
+		Ok(())
+	}
+
+	/// Record that a payment reminder was sent for an invoice.
+	pub fn mark_reminded(&mut self, number: &str, date: Date) -> Result<(), RegisterError> {
+		let invoice = self.find_mut(number)
+			.ok_or_else(|| RegisterError::NotFound(number.into()))?;
+		invoice.reminder_date = Some(date);
+		Ok(())
+	}
+
+	/// Mark an invoice as paid.
+	///
+	/// Returns an error if the invoice is not in the register or if it is already paid.
+	pub fn mark_paid(&mut self, number: &str, date: Date) -> Result<(), RegisterError> {
+		let invoice = self.find_mut(number)
+			.ok_or_else(|| RegisterError::NotFound(number.into()))?;
+		if invoice.status == InvoiceStatus::Paid {
+			return Err(RegisterError::AlreadyPaid(number.into()));
+		}
+		invoice.status = InvoiceStatus::Paid;
+		invoice.paid_date = Some(date);
+		Ok(())
+	}
+
+	/// Write the register to a temporary file and move it over the register file.
+	pub fn save(&self) -> Result<(), WriteFileError> {
+		let file = RegisterFile { invoices: self.invoices.clone() };
+		crate::write_toml_atomic(&self.path, &file)
+	}
+}
+
+impl RegisteredInvoice {
+	/// Get the total amount of the invoice including VAT.
+	pub fn total(&self) -> Cents {
+		Cents(self.total_cents)
+	}
+
+	/// Check if the invoice has invoiced hours in a period.
+	///
+	/// The period is a half-open range of dates.
+	/// The recorded dates of the invoice are used if there are any, otherwise the invoice period.
+	pub fn covers(&self, period: &Range<Date>) -> bool {
+		if !self.dates.is_empty() {
+			return self.dates.iter().any(|x| period.contains(x));
+		}
+		match (self.period_start, self.period_end) {
+			(Some(start), Some(end)) => start < period.end && end > period.start,
+			_ => false,
+		}
+	}
+
+	/// Check if the invoice is past the due date and not paid on the given date.
+	pub fn is_overdue(&self, today: Date) -> bool {
+		self.status != InvoiceStatus::Paid && self.due_date < today
+	}
+
+	/// Get the number of days from the invoice date until the given date.
+	pub fn days_outstanding(&self, today: Date) -> usize {
+		days_between(self.date, today)
+	}
+
+	/// Get the number of days an unpaid invoice is past the due date, or 0 if it is not overdue.
+	pub fn days_overdue(&self, today: Date) -> usize {
+		if self.is_overdue(today) {
+			days_between(self.due_date, today)
+		} else {
+			0
+		}
+	}
+
+	/// Check if a payment reminder should be sent for the invoice.
+	///
+	/// A reminder is due for overdue invoices, unless the last reminder was sent less than `interval_days` ago.
+	pub fn needs_reminder(&self, today: Date, interval_days: u32) -> bool {
+		let last_reminder_expired = match self.reminder_date {
+			Some(date) => days_between(date, today) >= interval_days as usize,
+			None => true,
+		};
+		self.is_overdue(today) && last_reminder_expired
+	}
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RegisterError {
+	/// The invoice is not in the register.
+	NotFound(String),
+
+	/// The invoice is already marked as paid.
+	AlreadyPaid(String),
+}
+
+impl std::error::Error for RegisterError {}
+impl std::fmt::Display for RegisterError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::NotFound(number) => write!(f, "invoice {} not found in the invoice register", number),
+			Self::AlreadyPaid(number) => write!(f, "invoice {} is already marked as paid", number),
+		}
+	}
+}
+
+/// Count the days from `start` until `end`, or 0 if `end` is not after `start`.
+fn days_between(start: Date, end: Date) -> usize {
+	std::iter::successors(Some(start), |x| Some(x.next()))
+		.take_while(|x| *x < end)
+		.count()
+}
+
+impl std::fmt::Display for InvoiceStatus {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Created => write!(f, "created"),
+			Self::Sent => write!(f, "sent"),
+			Self::Paid => write!(f, "paid"),
+		}
+	}
+}
+
+impl std::fmt::Display for PeppolStatus {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Submitted => write!(f, "submitted"),
+			Self::Failed => write!(f, "failed"),
+		}
+	}
+}
+
+impl std::str::FromStr for InvoiceStatus {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		match input {
+			"created" => Ok(Self::Created),
+			"sent" => Ok(Self::Sent),
+			"paid" => Ok(Self::Paid),
+			_ => Err(format!("invalid invoice status {:?}, expected created, sent or paid", input)),
+		}
+	}
+}
+
+/// Get the due date of an invoice created on the given date.
+pub fn due_date(config: &ZzpConfig, invoice_date: Date) -> Date {
+	let payment_days = config.invoice.payment_days.unwrap_or(DEFAULT_PAYMENT_DAYS);
+	(0..payment_days).fold(invoice_date, |date, _| date.next())
+}
+
+#[cfg(test)]
+impl InvoiceRegister {
+	/// Create a register with the given invoices, that is not backed by a file.
+	pub(crate) fn from_invoices(invoices: Vec<RegisteredInvoice>) -> Self {
+		Self { path: PathBuf::new(), invoices }
+	}
+}
+
+#[cfg(test)]
+pub(crate) fn test_invoice(customer: &str, period: Option<Range<Date>>, dates: Vec<Date>) -> RegisteredInvoice {
+	let date: Date = "2024-02-01".parse().unwrap();
+	RegisteredInvoice {
+		number: "2024-001".into(),
+		customer: customer.into(),
+		debitor_account: "debitors/acme".into(),
+		date,
+		due_date: date,
+		currency: "EUR".into(),
+		total_cents: 12100,
+		file: "invoice.pdf".into(),
+		status: InvoiceStatus::Created,
+		sent_date: None,
+		paid_date: None,
+		reminder_date: None,
+		recurring: None,
+		period_start: period.as_ref().map(|x| x.start),
+		period_end: period.as_ref().map(|x| x.end),
+		dates,
+		peppol_status: None,
+		peppol_reference: None,
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn test_covers_dates() {
+	use assert2::assert;
+
+	let date = |x: &str| x.parse::<Date>().unwrap();
+
+	// An invoice without a period still covers the dates of its entries.
+	let invoice = test_invoice("Acme", None, vec![date("2024-01-03"), date("2024-01-05")]);
+	assert!(invoice.covers(&(date("2024-01-03")..date("2024-01-04"))));
+	assert!(invoice.covers(&(date("2024-01-01")..date("2024-02-01"))));
+	assert!(!invoice.covers(&(date("2024-01-04")..date("2024-01-05"))));
+	assert!(!invoice.covers(&(date("2024-01-06")..date("2024-02-01"))));
+
+	// The recorded dates take precedence over the period.
+	let invoice = test_invoice("Acme", Some(date("2024-01-01")..date("2024-02-01")), vec![date("2024-01-03")]);
+	assert!(!invoice.covers(&(date("2024-01-04")..date("2024-01-05"))));
+
+	// Without dates and without a period, nothing is known to be invoiced.
+	let invoice = test_invoice("Acme", None, Vec::new());
+	assert!(!invoice.covers(&(date("2024-01-01")..date("2024-02-01"))));
+}
+
+#[cfg(test)]
+#[test]
+fn test_covers_adjacent_periods() {
+	use assert2::assert;
+
+	let date = |x: &str| x.parse::<Date>().unwrap();
+
+	let invoice = test_invoice("Acme", Some(date("2024-01-01")..date("2024-02-01")), Vec::new());
+	assert!(invoice.covers(&(date("2024-01-31")..date("2024-02-01"))));
+	assert!(invoice.covers(&(date("2024-01-15")..date("2024-02-15"))));
+	assert!(!invoice.covers(&(date("2024-02-01")..date("2024-03-01"))));
+	assert!(!invoice.covers(&(date("2023-12-01")..date("2024-01-01"))));
+}
+
+#[cfg(test)]
+#[test]
+fn test_find_period() {
+	use assert2::assert;
+
+	let date = |x: &str| x.parse::<Date>().unwrap();
+
+	let register = InvoiceRegister::from_invoices(vec![
+		test_invoice("Acme", None, vec![date("2024-01-03")]),
+		test_invoice("Globex", Some(date("2024-01-01")..date("2024-02-01")), Vec::new()),
+	]);
+	assert!(register.find_period("Acme", &(date("2024-01-03")..date("2024-01-04"))).is_some());
+	assert!(register.find_period("Acme", &(date("2024-01-10")..date("2024-01-11"))).is_none());
+	assert!(register.find_period("Globex", &(date("2024-01-10")..date("2024-01-11"))).is_some());
+	assert!(register.find_period("Globex", &(date("2024-02-01")..date("2024-02-02"))).is_none());
+	assert!(register.find_period("Initech", &(date("2024-01-03")..date("2024-01-04"))).is_none());
+}
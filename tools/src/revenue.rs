@@ -0,0 +1,171 @@
+//! Revenue per customer and quarter from the grootboek.
+
+use dynfmt::{Format, SimpleCurlyFormat};
+use std::collections::BTreeMap;
+use zzp::gregorian::{Date, Year};
+use zzp::grootboek::{Account, Cents, Transaction};
+
+use crate::json::Json;
+use crate::{AccountKind, CustomerConfig, ZzpConfig};
+
+/// The revenue of a fiscal year per customer and quarter.
+#[derive(Debug, Clone)]
+pub struct RevenueReport {
+	/// The fiscal year of the report, named after the calendar year it starts in.
+	pub year: i16,
+
+	/// The revenue of each customer with revenue in the year, sorted by name.
+	pub customers: Vec<CustomerRevenue>,
+
+	/// Revenue that could not be attributed to a customer.
+	pub other: [Cents; 4],
+}
+
+/// The revenue of a customer per quarter.
+#[derive(Debug, Clone)]
+pub struct CustomerRevenue {
+	/// The name of the customer.
+	pub name: String,
+
+	/// The revenue excluding VAT in each quarter of the fiscal year.
+	pub quarters: [Cents; 4],
+}
+
+impl RevenueReport {
+	/// Compute the revenue per customer from the transactions of a fiscal year.
+	///
+	/// Revenue mutations are found by expanding the revenue account of `zzp.toml` for each customer.
+	/// If the revenue account does not contain the `{debitor}` placeholder,
+	/// the revenue is attributed to the customer whose debitor account is mutated by the same transaction.
+	/// Revenue that can not be attributed to a single customer is reported as other revenue.
+	pub fn compute<'a>(config: &ZzpConfig, customers: &[CustomerConfig], year: i16, transactions: impl IntoIterator<Item = &'a Transaction<'a>>) -> Result<Self, String> {
+		let mut totals: BTreeMap<&str, [Cents; 4]> = BTreeMap::new();
+		let mut other = [Cents(0); 4];
+		let prefix = revenue_prefix(config);
+		let per_debitor = config.grootboek.revenue_account.contains("{debitor}");
+		let range = config.fiscal_year(Year::new(year));
+
+		for transaction in transactions {
+			if !range.contains(&transaction.date) {
+				continue;
+			}
+			let mut accounts = Vec::with_capacity(customers.len());
+			for customer in customers {
+				let args = format_args(transaction.date, &customer.customer.grootboek_name);
+				let revenue_account = SimpleCurlyFormat.format(&config.grootboek.revenue_account, &args)
+					.map_err(|e| format!("failed to expand revenue account: {}", e))?;
+				let debitor_account = SimpleCurlyFormat.format(&config.grootboek.debitor_account, &args)
+					.map_err(|e| format!("failed to expand debitor account: {}", e))?;
+				accounts.push((customer.customer.name.as_str(), revenue_account.into_owned(), debitor_account.into_owned()));
+			}
+
+			let quarter = zzp::calendar::fiscal_quarter_index(transaction.date, config.fiscal_year_start());
+			for mutation in &transaction.mutations {
+				let candidates: Vec<_> = accounts.iter()
+					.filter(|(_, revenue_account, _)| mutation.account.matches_prefix(revenue_account))
+					.collect();
+				if candidates.is_empty() && (prefix.is_empty() || !mutation.account.matches_prefix(prefix)) {
+					continue;
+				}
+
+				let customer = match candidates.as_slice() {
+					[(name, _, _)] if per_debitor => Some(*name),
+					candidates => {
+						let mut debitors = candidates.iter()
+							.filter(|(_, _, debitor_account)| transaction.mutations.iter().any(|x| x.account.as_str() == debitor_account));
+						match (debitors.next(), debitors.next()) {
+							(Some((name, _, _)), None) => Some(*name),
+							_ => None,
+						}
+					},
+				};
+
+				let revenue = match customer {
+					Some(name) => &mut totals.entry(name).or_insert([Cents(0); 4])[quarter],
+					None => &mut other[quarter],
+				};
+				*revenue -= mutation.amount;
+			}
+		}
+
+		let customers = totals.into_iter()
+			.map(|(name, quarters)| CustomerRevenue { name: name.to_string(), quarters })
+			.collect();
+		Ok(Self { year, customers, other })
+	}
+
+	/// The total revenue of each quarter.
+	pub fn quarter_totals(&self) -> [Cents; 4] {
+		let mut totals = self.other;
+		for customer in &self.customers {
+			for (total, amount) in totals.iter_mut().zip(customer.quarters) {
+				*total += amount;
+			}
+		}
+		totals
+	}
+
+	/// The total revenue of the year.
+	pub fn total(&self) -> Cents {
+		self.quarter_totals().iter().sum()
+	}
+
+	/// Get the report as JSON.
+	pub fn to_json(&self) -> Json {
+		let quarters = |quarters: &[Cents; 4]| Json::array(quarters.iter().copied());
+		Json::object([
+			("year", i32::from(self.year).into()),
+			("customers", Json::array(self.customers.iter().map(|customer| Json::object([
+				("customer", customer.name.as_str().into()),
+				("quarters", quarters(&customer.quarters)),
+				("total", customer.total().into()),
+			])))),
+			("other", quarters(&self.other)),
+			("quarters", quarters(&self.quarter_totals())),
+			("total", self.total().into()),
+		])
+	}
+}
+
+impl CustomerRevenue {
+	/// The total revenue of the customer in the year.
+	pub fn total(&self) -> Cents {
+		self.quarters.iter().sum()
+	}
+}
+
+/// Check if an account is a revenue account.
+///
+/// This is the case if the chart of accounts says so,
+/// or if the account is below the revenue account of the grootboek configuration.
+pub fn is_revenue_account(config: &ZzpConfig, account: Account) -> bool {
+	if let Some(account) = config.find_account(account) {
+		return account.kind == AccountKind::Revenue;
+	}
+	let prefix = revenue_prefix(config);
+	!prefix.is_empty() && account.matches_prefix(prefix)
+}
+
+/// Get the part of the revenue account before the first placeholder.
+///
+/// Mutations on accounts below it are revenue, even if they can not be matched to a customer.
+fn revenue_prefix(config: &ZzpConfig) -> &str {
+	let account = &config.grootboek.revenue_account;
+	account[..account.find('{').unwrap_or(account.len())].trim_end_matches('/')
+}
+
+/// Get the zero-based calendar quarter of a date.
+fn quarter(date: Date) -> usize {
+	usize::from(date.month().to_number() - 1) / 3
+}
+
+/// Get the placeholders for the grootboek accounts of a customer.
+fn format_args(date: Date, debitor: &str) -> BTreeMap<&'static str, String> {
+	[
+		("year", date.year().to_string()),
+		("month", format!("{:02}", date.month().to_number())),
+		("day", format!("{:02}", date.day())),
+		("quarter", (quarter(date) + 1).to_string()),
+		("debitor", debitor.to_string()),
+	].into_iter().collect()
+}
@@ -0,0 +1,248 @@
+//! JSON schemas of the configuration files, so editors can validate and autocomplete them.
+//!
+//! The schemas are derived from the configuration structs with `schemars`, which uses the doc comments as descriptions.
+//! Types from other crates with custom deserialization, like dates and hours, get their schema from the functions in this module.
+//! Configuration files can get fields from included files, so their schemas do not require any fields.
+
+use schemars::generate::SchemaSettings;
+use schemars::transform::transform_subschemas;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde_json::Value;
+
+use crate::{DateLocalization, InvoiceLocalization};
+
+/// A file with a JSON schema.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SchemaFile {
+	/// The main configuration file, `zzp.toml`.
+	Zzp,
+
+	/// A customer configuration file, `customer.toml`.
+	Customer,
+
+	/// A file with invoice entries.
+	Invoice,
+}
+
+impl SchemaFile {
+	/// All files with a JSON schema.
+	pub const ALL: [Self; 3] = [Self::Zzp, Self::Customer, Self::Invoice];
+
+	/// The title of the schema.
+	fn title(self) -> &'static str {
+		match self {
+			Self::Zzp => "zzp.toml",
+			Self::Customer => "customer.toml",
+			Self::Invoice => "invoice entries",
+		}
+	}
+
+	/// The file is a configuration file, which can include other files and is migrated when read.
+	fn is_config(self) -> bool {
+		matches!(self, Self::Zzp | Self::Customer)
+	}
+}
+
+/// Generate the JSON schema of a file.
+pub fn schema(file: SchemaFile) -> Schema {
+	let mut schema = match file {
+		SchemaFile::Zzp => schema_for::<crate::ZzpConfig>(),
+		SchemaFile::Customer => schema_for::<crate::CustomerConfig>(),
+		SchemaFile::Invoice => schema_for::<crate::invoice::InvoiceFile>(),
+	};
+	schema.insert("title".into(), file.title().into());
+	if file.is_config() {
+		remove_required(&mut schema);
+		if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+			properties.insert("include".into(), include_schema().into());
+		}
+	}
+	schema
+}
+
+/// Format a JSON schema as JSON indented with tabs.
+pub fn to_json(schema: &Schema) -> String {
+	use serde::Serialize;
+	let mut output = Vec::new();
+	let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+	schema.serialize(&mut serde_json::Serializer::with_formatter(&mut output, formatter))
+		.expect("failed to serialize JSON schema");
+	String::from_utf8(output).expect("serialized JSON is not valid UTF-8")
+}
+
+/// Generate the JSON schema of a type.
+///
+/// The schema uses JSON schema draft 7, which is supported by most editors.
+pub(crate) fn schema_for<T: JsonSchema>() -> Schema {
+	SchemaSettings::draft07()
+		.with(|settings| {
+			// Remove null first, so the transforms for draft 7 see the references that are left.
+			settings.transforms.insert(0, Box::new(remove_null));
+			settings.transforms.insert(1, Box::new(remove_doc_links));
+		})
+		.into_generator()
+		.into_root_schema_for::<T>()
+}
+
+/// Get the schema of a date, like `2024-03-01`.
+pub(crate) fn date(_generator: &mut SchemaGenerator) -> Schema {
+	json_schema!({
+		"type": "string",
+		"format": "date",
+	})
+}
+
+/// Get the schema of a number of hours, like `7h30m`.
+pub(crate) fn hours(_generator: &mut SchemaGenerator) -> Schema {
+	json_schema!({
+		"type": "string",
+		"pattern": "^([0-9]+h)?([0-9]+m)?$",
+	})
+}
+
+/// Get the schema of the number of a month.
+pub(crate) fn month(_generator: &mut SchemaGenerator) -> Schema {
+	json_schema!({
+		"type": "integer",
+		"minimum": 1,
+		"maximum": 12,
+	})
+}
+
+/// Get the schema of a rounding mode for amounts of money.
+pub(crate) fn rounding_mode(_generator: &mut SchemaGenerator) -> Schema {
+	json_schema!({
+		"type": "string",
+		"enum": ["half_away_from_zero", "half_even", "toward_zero", "away_from_zero", "down", "up"],
+	})
+}
+
+/// Get the schema of the invoice localization, which can also be a built-in preset.
+pub(crate) fn invoice_localization(generator: &mut SchemaGenerator) -> Schema {
+	localization(generator.subschema_for::<InvoiceLocalization>())
+}
+
+/// Get the schema of the date localization, which can also be a built-in preset.
+pub(crate) fn date_localization(generator: &mut SchemaGenerator) -> Schema {
+	localization(generator.subschema_for::<DateLocalization>())
+}
+
+/// Get the schema of a localization that can also be the name of a built-in preset, or a table with a preset and overrides.
+fn localization(table: Schema) -> Schema {
+	let preset = json_schema!({
+		"type": "string",
+		"enum": crate::localization::PRESETS,
+	});
+	json_schema!({
+		"anyOf": [
+			preset,
+			table,
+			{
+				"type": "object",
+				"properties": { "preset": preset },
+				"required": ["preset"],
+			},
+		],
+	})
+}
+
+/// Get the schema of the top-level `include` key.
+fn include_schema() -> Schema {
+	json_schema!({
+		"description": "Other configuration files to merge this file over, relative to the directory of this file.",
+		"oneOf": [
+			{ "type": "string" },
+			{ "type": "array", "items": { "type": "string" } },
+		],
+	})
+}
+
+/// Remove `null` from the allowed values of optional fields, since TOML has no null.
+fn remove_null(schema: &mut Schema) {
+	transform_subschemas(&mut remove_null, schema);
+	let object = match schema.as_object_mut() {
+		Some(x) => x,
+		None => return,
+	};
+
+	if let Some(Value::Array(types)) = object.get_mut("type") {
+		types.retain(|x| x != "null");
+		if types.len() == 1 {
+			let ty = types.remove(0);
+			object.insert("type".into(), ty);
+		}
+	}
+	if let Some(Value::Array(values)) = object.get_mut("enum") {
+		values.retain(|x| !x.is_null());
+	}
+	if let Some(Value::Array(alternatives)) = object.get_mut("anyOf") {
+		alternatives.retain(|x| x.get("type").is_none_or(|x| x != "null"));
+		if alternatives.len() == 1 {
+			if let Some(Value::Object(alternative)) = alternatives.pop() {
+				object.remove("anyOf");
+				for (key, value) in alternative {
+					object.entry(key).or_insert(value);
+				}
+			}
+		}
+	}
+	match object.get_mut("default") {
+		Some(Value::Null) => {
+			object.remove("default");
+		},
+		Some(default) => remove_null_fields(default),
+		None => (),
+	}
+}
+
+/// Remove fields with a null value from the objects in a value, like default values of structs with optional fields.
+fn remove_null_fields(value: &mut Value) {
+	match value {
+		Value::Object(fields) => {
+			fields.retain(|_, x| !x.is_null());
+			fields.values_mut().for_each(remove_null_fields);
+		},
+		Value::Array(items) => items.iter_mut().for_each(remove_null_fields),
+		_ => (),
+	}
+}
+
+/// Keep only the code span of links to other items in descriptions, since the links do not work outside of the documentation.
+fn remove_doc_links(schema: &mut Schema) {
+	transform_subschemas(&mut remove_doc_links, schema);
+	if let Some(Value::String(description)) = schema.get_mut("description") {
+		*description = description.replace("[`", "`").replace("`]", "`");
+	}
+}
+
+/// Remove the required fields of the root object and the definitions of all structs.
+fn remove_required(schema: &mut Schema) {
+	schema.remove("required");
+	if let Some(Value::Object(definitions)) = schema.get_mut("definitions") {
+		for definition in definitions.values_mut() {
+			if let Value::Object(definition) = definition {
+				definition.remove("required");
+			}
+		}
+	}
+}
+
+impl std::fmt::Display for SchemaFile {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Zzp => write!(f, "zzp"),
+			Self::Customer => write!(f, "customer"),
+			Self::Invoice => write!(f, "invoice"),
+		}
+	}
+}
+
+impl std::str::FromStr for SchemaFile {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		Self::ALL.into_iter()
+			.find(|x| x.to_string() == input)
+			.ok_or_else(|| format!("unknown configuration file {:?}, expected zzp, customer or invoice", input))
+	}
+}
@@ -0,0 +1,111 @@
+//! Timer for live tracking of hours.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zzp::gregorian::Date;
+use zzp::uurlog::{Entry, Hours};
+
+use crate::ReadFileError;
+
+/// The path of the running timer, relative to the directory of `zzp.toml`.
+pub const TIMER_PATH: &str = ".uurlog-timer.toml";
+
+/// A running timer.
+///
+/// Only one timer can run at a time.
+/// When the timer is stopped, the elapsed time is added as entry to the hour log.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timer {
+	/// The hour log to add the entry to when the timer is stopped.
+	pub file: PathBuf,
+
+	/// The date the timer was started.
+	#[serde(with = "crate::serde_date")]
+	pub date: Date,
+
+	/// The time the timer was started, in seconds since the Unix epoch.
+	pub start_time: u64,
+
+	/// The tags of the hour log entry.
+	#[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+	pub tags: Vec<String>,
+
+	/// The description of the hour log entry.
+	pub description: String,
+}
+
+impl Timer {
+	/// Create a new timer starting now.
+	pub fn new(file: PathBuf, tags: Vec<String>, description: String) -> Self {
+		Self {
+			file,
+			date: Date::today(),
+			start_time: unix_time(),
+			tags,
+			description,
+		}
+	}
+
+	/// Read the running timer, if any.
+	///
+	/// The `root_dir` is the directory containing `zzp.toml`.
+	pub fn read(root_dir: impl AsRef<Path>) -> Result<Option<Self>, ReadFileError> {
+		let path = root_dir.as_ref().join(TIMER_PATH);
+		if path.exists() {
+			crate::read_toml(&path).map(Some)
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Save the timer as the running timer.
+	///
+	/// Returns an error if another timer is already running.
+	pub fn start(&self, root_dir: impl AsRef<Path>) -> Result<(), String> {
+		let path = root_dir.as_ref().join(TIMER_PATH);
+		let data = toml::to_string(self)
+			.map_err(|e| format!("failed to serialize timer: {}", e))?;
+		let mut file = std::fs::OpenOptions::new()
+			.create_new(true)
+			.write(true)
+			.open(&path)
+			.map_err(|e| match e.kind() {
+				std::io::ErrorKind::AlreadyExists => String::from("a timer is already running, stop it first"),
+				_ => format!("failed to create {}: {}", path.display(), e),
+			})?;
+		std::io::Write::write_all(&mut file, data.as_bytes())
+			.map_err(|e| format!("failed to write to {}: {}", path.display(), e))
+	}
+
+	/// Remove the running timer without adding an entry to the hour log.
+	pub fn discard(root_dir: impl AsRef<Path>) -> Result<(), String> {
+		let path = root_dir.as_ref().join(TIMER_PATH);
+		std::fs::remove_file(&path)
+			.map_err(|e| format!("failed to remove {}: {}", path.display(), e))
+	}
+
+	/// Get the time since the timer was started, rounded to whole minutes.
+	pub fn elapsed(&self) -> Hours {
+		let seconds = unix_time().saturating_sub(self.start_time);
+		Hours::from_minutes(((seconds + 30) / 60) as u32)
+	}
+
+	/// Get the hour log entry for the time since the timer was started.
+	pub fn entry(&self) -> Entry {
+		Entry {
+			date: self.date,
+			hours: self.elapsed(),
+			tags: self.tags.clone(),
+			description: self.description.clone(),
+		}
+	}
+}
+
+fn unix_time() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|x| x.as_secs())
+		.unwrap_or(0)
+}
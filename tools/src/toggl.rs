@@ -0,0 +1,180 @@
+//! Conversion of Toggl Track CSV exports to hour log entries.
+
+use std::collections::BTreeMap;
+use zzp::gregorian::Date;
+use zzp::uurlog::{Entry, Hours};
+
+/// A time entry from a Toggl Track CSV export.
+#[derive(Debug, Clone)]
+pub struct TogglEntry {
+	/// The start date of the entry.
+	pub date: Date,
+
+	/// The duration of the entry in seconds.
+	pub seconds: u32,
+
+	/// The project of the entry, if any.
+	pub project: Option<String>,
+
+	/// The tags of the entry.
+	pub tags: Vec<String>,
+
+	/// The description of the entry.
+	pub description: String,
+}
+
+/// Options for converting Toggl entries to hour log entries.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ConvertOptions {
+	/// Add the project of each entry as first tag.
+	pub project_tag: bool,
+
+	/// Merge entries with the same date, tags and description into a single entry.
+	pub merge_per_day: bool,
+}
+
+/// Parse the detailed CSV export of Toggl Track.
+///
+/// Columns are looked up by name, so the order of the columns does not matter.
+/// The `Start date`, `Duration` and `Description` columns are required,
+/// the `Project` and `Tags` columns are optional.
+pub fn parse_csv(data: &str) -> Result<Vec<TogglEntry>, String> {
+	let data = data.strip_prefix('\u{feff}').unwrap_or(data);
+	let mut reader = csv::ReaderBuilder::new()
+		.flexible(true)
+		.from_reader(data.as_bytes());
+	let header = reader.headers()
+		.map_err(|e| format!("failed to parse CSV header: {}", e))?
+		.clone();
+	if header.is_empty() {
+		return Err(String::from("empty CSV file"));
+	}
+
+	let column = |name: &str| header.iter().position(|x| x.trim().eq_ignore_ascii_case(name));
+	let required = |name: &str| column(name).ok_or_else(|| format!("missing column {:?} in CSV file", name));
+	let date_column = required("Start date")?;
+	let duration_column = required("Duration")?;
+	let description_column = required("Description")?;
+	let project_column = column("Project");
+	let tags_column = column("Tags");
+
+	let mut entries = Vec::new();
+	for record in reader.records() {
+		let record = record.map_err(|e| format!("failed to parse CSV file: {}", e))?;
+		// Skip lines with only empty fields.
+		if record.iter().all(|x| x.is_empty()) {
+			continue;
+		}
+
+		// The position of a record is before the line terminators and empty lines that were skipped to get to the record,
+		// so add the skipped lines to get the line that the record starts on.
+		let line = match record.position() {
+			None => 0,
+			Some(position) => {
+				let skipped = data.as_bytes()[position.byte() as usize..].iter()
+					.take_while(|&&x| x == b'\r' || x == b'\n')
+					.filter(|&&x| x == b'\n')
+					.count();
+				position.line() + skipped as u64
+			},
+		};
+		let field = |index: usize| record.get(index).map(|x| x.trim()).unwrap_or("");
+		let date = field(date_column).parse()
+			.map_err(|_| format!("on line {}: invalid start date: {:?}", line, field(date_column)))?;
+		let seconds = parse_duration(field(duration_column))
+			.ok_or_else(|| format!("on line {}: invalid duration: {:?}", line, field(duration_column)))?;
+		let project = project_column.map(field).filter(|x| !x.is_empty()).map(String::from);
+		let tags = tags_column.map(field).unwrap_or("")
+			.split(',')
+			.map(|x| x.trim())
+			.filter(|x| !x.is_empty())
+			.map(String::from)
+			.collect();
+
+		entries.push(TogglEntry {
+			date,
+			seconds,
+			project,
+			tags,
+			description: field(description_column).to_string(),
+		});
+	}
+
+	Ok(entries)
+}
+
+/// Convert Toggl entries to hour log entries.
+///
+/// The duration is rounded to whole minutes.
+/// When entries are merged, the durations are added before rounding.
+pub fn convert(entries: &[TogglEntry], options: ConvertOptions) -> Vec<Entry> {
+	let tags = |entry: &TogglEntry| -> Vec<String> {
+		let project = entry.project.iter().filter(|_| options.project_tag);
+		project.chain(&entry.tags).map(|x| sanitize_tag(x)).collect()
+	};
+
+	let mut result = Vec::new();
+	if options.merge_per_day {
+		let mut merged: BTreeMap<(Date, Vec<String>, &str), u32> = BTreeMap::new();
+		for entry in entries {
+			*merged.entry((entry.date, tags(entry), &entry.description)).or_default() += entry.seconds;
+		}
+		for ((date, tags, description), seconds) in merged {
+			result.push(make_entry(date, seconds, tags, description));
+		}
+	} else {
+		for entry in entries {
+			result.push(make_entry(entry.date, entry.seconds, tags(entry), &entry.description));
+		}
+	}
+
+	result.sort_by_key(|x| x.date);
+	result
+}
+
+fn make_entry(date: Date, seconds: u32, tags: Vec<String>, description: &str) -> Entry {
+	Entry {
+		date,
+		hours: Hours::from_minutes((seconds + 30) / 60),
+		tags,
+		// The description can not contain newlines in the hour log.
+		description: description.split_whitespace().collect::<Vec<_>>().join(" "),
+	}
+}
+
+/// Remove characters from a tag that can not be used in the hour log.
+fn sanitize_tag(tag: &str) -> String {
+	tag.chars().filter(|&c| c != '[' && c != ']').collect()
+}
+
+/// Parse a duration like `01:30:00` to seconds.
+fn parse_duration(data: &str) -> Option<u32> {
+	let mut fields = data.split(':');
+	let hours: u32 = fields.next()?.parse().ok()?;
+	let minutes: u32 = fields.next()?.parse().ok()?;
+	let seconds: u32 = fields.next().map(|x| x.parse().ok()).unwrap_or(Some(0))?;
+	if fields.next().is_some() || minutes >= 60 || seconds >= 60 {
+		return None;
+	}
+	Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_csv_line_numbers() {
+	use assert2::{assert, let_assert};
+
+	let header = "Description,Start date,Duration\r\n";
+	let data = format!("{}\"two\nlines\",2024-01-02,01:00:00\r\n\r\n\nbad,2024-13-01,01:00:00\r\n", header);
+	let_assert!(Err(error) = parse_csv(&data));
+	assert!(error == "on line 6: invalid start date: \"2024-13-01\"");
+
+	let data = format!("{}work,2024-01-02,01:00:00\nbad,2024-01-02,1 hour\n", header);
+	let_assert!(Err(error) = parse_csv(&data));
+	assert!(error == "on line 3: invalid duration: \"1 hour\"");
+
+	let data = format!("{}work,2024-01-02,01:30:00\n", header);
+	let entries = parse_csv(&data).unwrap();
+	assert!(entries.len() == 1);
+	assert!(entries[0].seconds == 5400);
+}
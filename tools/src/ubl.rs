@@ -0,0 +1,663 @@
+//! Generation of UBL 2.1 electronic invoices.
+//!
+//! The generated invoices follow the NLCIUS specification,
+//! which is the Dutch usage specification of the European EN 16931 standard.
+
+use std::io::Write;
+use ordered_float::NotNan;
+use zzp::gregorian::Date;
+use zzp::grootboek::Cents;
+
+use crate::{ZzpConfig, Customer, CustomerEInvoice};
+use crate::invoice::{format_fixed_point, InvoiceEntry, InvoiceTotals};
+
+const CUSTOMIZATION_ID: &str = "urn:cen.eu:en16931:2017#compliant#urn:fdc:nen.nl:nlcius:v1.0";
+const PROFILE_ID: &str = "urn:fdc:peppol.eu:2017:poacc:billing:01:1.0";
+
+/// Write a UBL 2.1 invoice.
+///
+/// The invoice contains the same entries and totals as the PDF invoice.
+/// Negative entries (like discounts) are written with a negative quantity,
+/// since the standard does not allow negative prices.
+/// Quantities and prices are written with the precision used to compute the line totals,
+/// so that every line total is exactly the quantity times the price.
+pub fn write_ubl_invoice<W: Write>(
+	mut stream: W,
+	config: &ZzpConfig,
+	recipient: &Customer,
+	recipient_details: &CustomerEInvoice,
+	invoice_number: &str,
+	invoice_date: Date,
+	entries: &[InvoiceEntry],
+) -> Result<(), UblError> {
+	let company = config.e_invoice.as_ref()
+		.ok_or(UblError::NoEInvoiceConfig)?;
+	let currency = config.currency();
+	let currency = currency.code.as_str();
+	let totals = InvoiceTotals::compute(entries, &config.tax);
+	let amount = |tag: &str, value: Cents| {
+		format!("<cbc:{tag} currencyID=\"{}\">{}</cbc:{tag}>", escape(currency), format_amount(value), tag = tag)
+	};
+
+	let mut xml = String::new();
+	xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	xml.push_str("<Invoice xmlns=\"urn:oasis:names:specification:ubl:schema:xsd:Invoice-2\"");
+	xml.push_str(" xmlns:cac=\"urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2\"");
+	xml.push_str(" xmlns:cbc=\"urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2\">\n");
+	element(&mut xml, 1, "cbc:CustomizationID", CUSTOMIZATION_ID);
+	element(&mut xml, 1, "cbc:ProfileID", PROFILE_ID);
+	element(&mut xml, 1, "cbc:ID", invoice_number);
+	element(&mut xml, 1, "cbc:IssueDate", &invoice_date.to_string());
+	element(&mut xml, 1, "cbc:InvoiceTypeCode", "380");
+	element(&mut xml, 1, "cbc:DocumentCurrencyCode", currency);
+	if let Some(reference) = &recipient_details.buyer_reference {
+		element(&mut xml, 1, "cbc:BuyerReference", reference);
+	}
+
+	// The seller.
+	open(&mut xml, 1, "cac:AccountingSupplierParty");
+	write_party(&mut xml, Party {
+		name: &config.company.name,
+		street: &company.street,
+		postal_code: &company.postal_code,
+		city: &company.city,
+		country_code: &company.country_code,
+		vat_number: Some(&company.vat_number),
+		coc_number: company.coc_number.as_deref(),
+		endpoint: None,
+	});
+	close(&mut xml, 1, "cac:AccountingSupplierParty");
+
+	// The buyer.
+	open(&mut xml, 1, "cac:AccountingCustomerParty");
+	write_party(&mut xml, Party {
+		name: &recipient.name,
+		street: &recipient_details.street,
+		postal_code: &recipient_details.postal_code,
+		city: &recipient_details.city,
+		country_code: &recipient_details.country_code,
+		vat_number: recipient_details.vat_number.as_deref().or(recipient.vat_number.as_deref()),
+		coc_number: recipient_details.coc_number.as_deref(),
+		endpoint: endpoint(recipient),
+	});
+	close(&mut xml, 1, "cac:AccountingCustomerParty");
+
+	// Payment details: SEPA credit transfer to the company IBAN.
+	open(&mut xml, 1, "cac:PaymentMeans");
+	element(&mut xml, 2, "cbc:PaymentMeansCode", "58");
+	element(&mut xml, 2, "cbc:PaymentID", invoice_number);
+	open(&mut xml, 2, "cac:PayeeFinancialAccount");
+	element(&mut xml, 3, "cbc:ID", &company.iban.replace(' ', ""));
+	close(&mut xml, 2, "cac:PayeeFinancialAccount");
+	close(&mut xml, 1, "cac:PaymentMeans");
+	open(&mut xml, 1, "cac:PaymentTerms");
+	element(&mut xml, 2, "cbc:Note", config.invoice_localization.footer.trim());
+	close(&mut xml, 1, "cac:PaymentTerms");
+
+	// The VAT breakdown.
+	let intra_eu = crate::invoice::is_intra_eu_supply(config, recipient);
+	let exemption_reason = if config.tax.kor {
+		Some(config.invoice_localization.kor_notice.as_deref().unwrap_or(crate::invoice::DEFAULT_KOR_NOTICE))
+	} else if intra_eu {
+		Some(config.invoice_localization.reverse_charge_notice.as_deref().unwrap_or(crate::invoice::DEFAULT_REVERSE_CHARGE_NOTICE))
+	} else {
+		None
+	};
+	open(&mut xml, 1, "cac:TaxTotal");
+	line(&mut xml, 2, &amount("TaxAmount", totals.total_vat()));
+	for (percentage, vat) in &totals.vat {
+		open(&mut xml, 2, "cac:TaxSubtotal");
+		line(&mut xml, 3, &amount("TaxableAmount", totals.taxable[percentage]));
+		line(&mut xml, 3, &amount("TaxAmount", *vat));
+		write_tax_category(&mut xml, 3, "cac:TaxCategory", tax_category(config, intra_eu, *percentage), *percentage, exemption_reason);
+		close(&mut xml, 2, "cac:TaxSubtotal");
+	}
+	close(&mut xml, 1, "cac:TaxTotal");
+
+	open(&mut xml, 1, "cac:LegalMonetaryTotal");
+	line(&mut xml, 2, &amount("LineExtensionAmount", totals.net));
+	line(&mut xml, 2, &amount("TaxExclusiveAmount", totals.net));
+	line(&mut xml, 2, &amount("TaxInclusiveAmount", totals.total()));
+	line(&mut xml, 2, &amount("PayableAmount", totals.total()));
+	close(&mut xml, 1, "cac:LegalMonetaryTotal");
+
+	// The invoice lines.
+	for (i, entry) in entries.iter().enumerate() {
+		let (quantity, price) = if *entry.unit_price < 0.0 {
+			(-entry.quantity, -entry.unit_price)
+		} else {
+			(entry.quantity, entry.unit_price)
+		};
+		let unit_code = if entry.unit == config.invoice_localization.hours { "HUR" } else { "C62" };
+
+		open(&mut xml, 1, "cac:InvoiceLine");
+		element(&mut xml, 2, "cbc:ID", &(i + 1).to_string());
+		line(&mut xml, 2, &format!("<cbc:InvoicedQuantity unitCode=\"{}\">{}</cbc:InvoicedQuantity>", unit_code, format_fixed_point(quantity, 0)));
+		line(&mut xml, 2, &amount("LineExtensionAmount", entry.total_ex_vat_cents(config.tax.rounding_mode)));
+		open(&mut xml, 2, "cac:InvoicePeriod");
+		element(&mut xml, 3, "cbc:StartDate", &entry.date.to_string());
+		element(&mut xml, 3, "cbc:EndDate", &entry.date.to_string());
+		close(&mut xml, 2, "cac:InvoicePeriod");
+		open(&mut xml, 2, "cac:Item");
+		element(&mut xml, 3, "cbc:Name", &entry.description);
+		write_tax_category(&mut xml, 3, "cac:ClassifiedTaxCategory", tax_category(config, intra_eu, entry.vat_percentage), entry.vat_percentage, None);
+		close(&mut xml, 2, "cac:Item");
+		open(&mut xml, 2, "cac:Price");
+		line(&mut xml, 3, &format!("<cbc:PriceAmount currencyID=\"{}\">{}</cbc:PriceAmount>", escape(currency), format_fixed_point(price, 2)));
+		close(&mut xml, 2, "cac:Price");
+		close(&mut xml, 1, "cac:InvoiceLine");
+	}
+
+	xml.push_str("</Invoice>\n");
+
+	stream.write_all(xml.as_bytes())
+		.and_then(|()| stream.flush())
+		.map_err(UblError::Write)
+}
+
+/// An error while writing a UBL invoice.
+#[derive(Debug)]
+pub enum UblError {
+	/// The configuration has no `[EInvoice]` section.
+	NoEInvoiceConfig,
+
+	/// The invoice could not be written.
+	Write(std::io::Error),
+}
+
+impl std::error::Error for UblError {}
+impl std::fmt::Display for UblError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::NoEInvoiceConfig => write!(f, "missing [EInvoice] section in zzp.toml, needed for e-invoices"),
+			Self::Write(e) => write!(f, "failed to write UBL invoice: {}", e),
+		}
+	}
+}
+
+/// The details of a party in a UBL invoice.
+struct Party<'a> {
+	name: &'a str,
+	street: &'a str,
+	postal_code: &'a str,
+	city: &'a str,
+	country_code: &'a str,
+	vat_number: Option<&'a str>,
+	coc_number: Option<&'a str>,
+
+	/// The electronic address as scheme and identifier.
+	endpoint: Option<(&'a str, &'a str)>,
+}
+
+/// Get the electronic address of a customer from the Peppol identifier or the OIN.
+fn endpoint(customer: &Customer) -> Option<(&str, &str)> {
+	if let Some(peppol_id) = &customer.peppol_id {
+		return Some(peppol_id.split_once(':').unwrap_or(("", peppol_id)));
+	}
+	// Scheme 0190 is the Dutch government OIN.
+	customer.oin.as_deref().map(|oin| ("0190", oin))
+}
+
+fn write_party(xml: &mut String, party: Party) {
+	open(xml, 2, "cac:Party");
+	if let Some((scheme, id)) = party.endpoint {
+		if scheme.is_empty() {
+			element(xml, 3, "cbc:EndpointID", id);
+		} else {
+			line(xml, 3, &format!("<cbc:EndpointID schemeID=\"{}\">{}</cbc:EndpointID>", escape(scheme), escape(id)));
+		}
+	}
+	open(xml, 3, "cac:PartyName");
+	element(xml, 4, "cbc:Name", party.name);
+	close(xml, 3, "cac:PartyName");
+	open(xml, 3, "cac:PostalAddress");
+	element(xml, 4, "cbc:StreetName", party.street);
+	element(xml, 4, "cbc:CityName", party.city);
+	element(xml, 4, "cbc:PostalZone", party.postal_code);
+	open(xml, 4, "cac:Country");
+	element(xml, 5, "cbc:IdentificationCode", party.country_code);
+	close(xml, 4, "cac:Country");
+	close(xml, 3, "cac:PostalAddress");
+	if let Some(vat_number) = party.vat_number {
+		open(xml, 3, "cac:PartyTaxScheme");
+		element(xml, 4, "cbc:CompanyID", vat_number);
+		open(xml, 4, "cac:TaxScheme");
+		element(xml, 5, "cbc:ID", "VAT");
+		close(xml, 4, "cac:TaxScheme");
+		close(xml, 3, "cac:PartyTaxScheme");
+	}
+	open(xml, 3, "cac:PartyLegalEntity");
+	element(xml, 4, "cbc:RegistrationName", party.name);
+	if let Some(coc_number) = party.coc_number {
+		// Scheme 0106 is the Dutch Chamber of Commerce.
+		line(xml, 4, &format!("<cbc:CompanyID schemeID=\"0106\">{}</cbc:CompanyID>", escape(coc_number)));
+	}
+	close(xml, 3, "cac:PartyLegalEntity");
+	close(xml, 2, "cac:Party");
+}
+
+/// Get the VAT category code for a VAT percentage.
+fn tax_category(config: &ZzpConfig, intra_eu: bool, percentage: NotNan<f64>) -> &'static str {
+	// Category E is exempt from VAT, K is an intra-EU supply, S is the standard rate and Z is the zero rate.
+	if config.tax.kor {
+		"E"
+	} else if intra_eu {
+		"K"
+	} else if *percentage == 0.0 {
+		"Z"
+	} else {
+		"S"
+	}
+}
+
+fn write_tax_category(xml: &mut String, indent: usize, tag: &str, category: &str, percentage: NotNan<f64>, exemption_reason: Option<&str>) {
+	open(xml, indent, tag);
+	element(xml, indent + 1, "cbc:ID", category);
+	element(xml, indent + 1, "cbc:Percent", &percentage.to_string());
+	if let Some(reason) = exemption_reason {
+		element(xml, indent + 1, "cbc:TaxExemptionReason", reason);
+	}
+	open(xml, indent + 1, "cac:TaxScheme");
+	element(xml, indent + 2, "cbc:ID", "VAT");
+	close(xml, indent + 1, "cac:TaxScheme");
+	close(xml, indent, tag);
+}
+
+fn line(xml: &mut String, indent: usize, data: &str) {
+	for _ in 0..indent {
+		xml.push('\t');
+	}
+	xml.push_str(data);
+	xml.push('\n');
+}
+
+pub(crate) fn open(xml: &mut String, indent: usize, tag: &str) {
+	line(xml, indent, &format!("<{}>", tag));
+}
+
+pub(crate) fn close(xml: &mut String, indent: usize, tag: &str) {
+	line(xml, indent, &format!("</{}>", tag));
+}
+
+pub(crate) fn element(xml: &mut String, indent: usize, tag: &str, value: &str) {
+	line(xml, indent, &format!("<{}>{}</{}>", tag, escape(value), tag));
+}
+
+/// Format an amount with two decimals and without explicit plus sign.
+pub(crate) fn format_amount(amount: Cents) -> String {
+	let cents = amount.total_cents();
+	let sign = if cents < 0 { "-" } else { "" };
+	let cents = cents.unsigned_abs();
+	format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
+
+/// Escape text for use in XML.
+pub(crate) fn escape(data: &str) -> String {
+	let mut output = String::with_capacity(data.len());
+	for c in data.chars() {
+		match c {
+			'&' => output.push_str("&amp;"),
+			'<' => output.push_str("&lt;"),
+			'>' => output.push_str("&gt;"),
+			'"' => output.push_str("&quot;"),
+			'\'' => output.push_str("&apos;"),
+			c => output.push(c),
+		}
+	}
+	output
+}
+
+#[cfg(test)]
+pub(crate) fn test_config(kor: bool) -> ZzpConfig {
+	toml::from_str(&format!(r#"
+		version = 1
+		InvoiceLocalization = "en"
+		DateLocalization = "en"
+
+		[Company]
+		name = "Jansen & Zn <ICT>"
+		address = ["Straat 1", "1234 AB Utrecht"]
+		contact = []
+		legal = []
+		payment = []
+
+		[Grootboek]
+		path = "grootboek"
+		revenue_account = "revenue/{{customer}}"
+		debitor_account = "debitors/{{customer}}"
+		creditor_account = "creditors"
+		vat_account = "vat/payable"
+		vat_input_account = "vat/receivable"
+
+		[Tax]
+		vat = 21
+		kor = {kor}
+
+		[Invoice]
+		font = "Sans"
+		font_size = 10
+		directory = "invoices"
+		grootboek_description = "Invoice {{number}}"
+		grootboek_tag = "invoice"
+
+		[EInvoice]
+		street = "Straat 1"
+		postal_code = "1234 AB"
+		city = "Utrecht"
+		country_code = "NL"
+		vat_number = "NL001234567B01"
+		coc_number = "12345678"
+		iban = "NL00 BANK 0123 4567 89"
+	"#, kor = kor)).unwrap()
+}
+
+#[cfg(test)]
+fn test_customer(name: &str, country_code: &str, vat_number: Option<&str>) -> (Customer, CustomerEInvoice) {
+	let customer = Customer {
+		name: name.into(),
+		address: Vec::new(),
+		grootboek_name: "customer".into(),
+		email: None,
+		peppol_id: Some("0106:87654321".into()),
+		oin: None,
+		delivery: None,
+		vat_number: vat_number.map(Into::into),
+		country_code: Some(country_code.into()),
+		code: None,
+		hours_path: None,
+	};
+	let details = CustomerEInvoice {
+		street: "Weg 2".into(),
+		postal_code: "5678 CD".into(),
+		city: "Amersfoort".into(),
+		country_code: country_code.into(),
+		vat_number: None,
+		coc_number: None,
+		buyer_reference: Some("PO-1".into()),
+	};
+	(customer, details)
+}
+
+#[cfg(test)]
+fn test_entry(day: u8, description: &str, quantity: f64, unit: &str, unit_price: f64, vat_percentage: f64) -> InvoiceEntry {
+	InvoiceEntry {
+		date: Date::new(2024, zzp::gregorian::Month::March, day).unwrap(),
+		description: description.into(),
+		quantity: NotNan::new(quantity).unwrap(),
+		unit: unit.into(),
+		unit_price: NotNan::new(unit_price).unwrap(),
+		vat_percentage: NotNan::new(vat_percentage).unwrap(),
+		section: None,
+	}
+}
+
+#[cfg(test)]
+fn test_write(config: &ZzpConfig, customer: &(Customer, CustomerEInvoice), entries: &[InvoiceEntry]) -> String {
+	let mut output = Vec::new();
+	let date = Date::new(2024, zzp::gregorian::Month::March, 31).unwrap();
+	write_ubl_invoice(&mut output, config, &customer.0, &customer.1, "2024-001", date, entries).unwrap();
+	String::from_utf8(output).unwrap()
+}
+
+#[cfg(test)]
+#[test]
+fn test_write_ubl_invoice() {
+	use assert2::assert;
+
+	let config = test_config(false);
+	let customer = test_customer("Klant \"B\" & 'C'", "NL", None);
+	let entries = [
+		test_entry(1, "Development <backend>", 1.5, "hours", 80.0, 21.0),
+		test_entry(2, "Books & manuals", 2.0, "", 12.5, 0.0),
+		test_entry(3, "Discount", 1.0, "", -20.0, 21.0),
+	];
+	assert!(test_write(&config, &customer, &entries) == TEST_INVOICE);
+}
+
+#[cfg(test)]
+#[test]
+fn test_write_ubl_invoice_kor() {
+	use assert2::assert;
+
+	let config = test_config(true);
+	let customer = test_customer("Klant", "NL", None);
+	let mut entries = [
+		test_entry(1, "Development", 2.0, "hours", 50.0, 21.0),
+		test_entry(3, "Discount", 1.0, "", -20.0, 21.0),
+	];
+	crate::invoice::apply_small_business_scheme(&config, &mut entries);
+
+	let xml = test_write(&config, &customer, &entries);
+	assert!(section(&xml, "cac:TaxTotal") == concat!(
+		"\t<cac:TaxTotal>\n",
+		"\t\t<cbc:TaxAmount currencyID=\"EUR\">0.00</cbc:TaxAmount>\n",
+		"\t\t<cac:TaxSubtotal>\n",
+		"\t\t\t<cbc:TaxableAmount currencyID=\"EUR\">80.00</cbc:TaxableAmount>\n",
+		"\t\t\t<cbc:TaxAmount currencyID=\"EUR\">0.00</cbc:TaxAmount>\n",
+		"\t\t\t<cac:TaxCategory>\n",
+		"\t\t\t\t<cbc:ID>E</cbc:ID>\n",
+		"\t\t\t\t<cbc:Percent>0</cbc:Percent>\n",
+		"\t\t\t\t<cbc:TaxExemptionReason>Exempt from VAT under the small business scheme.</cbc:TaxExemptionReason>\n",
+		"\t\t\t\t<cac:TaxScheme>\n",
+		"\t\t\t\t\t<cbc:ID>VAT</cbc:ID>\n",
+		"\t\t\t\t</cac:TaxScheme>\n",
+		"\t\t\t</cac:TaxCategory>\n",
+		"\t\t</cac:TaxSubtotal>\n",
+		"\t</cac:TaxTotal>\n",
+	));
+	assert!(section(&xml, "cac:ClassifiedTaxCategory").contains("<cbc:ID>E</cbc:ID>"));
+	assert!(xml.contains("<cbc:PayableAmount currencyID=\"EUR\">80.00</cbc:PayableAmount>"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_write_ubl_invoice_reverse_charge() {
+	use assert2::assert;
+
+	let config = test_config(false);
+	let customer = test_customer("Kunde", "DE", Some("DE123456789"));
+	let mut entries = [
+		test_entry(1, "Development", 2.0, "hours", 50.0, 21.0),
+		test_entry(3, "Discount", 1.0, "", -20.0, 21.0),
+	];
+	crate::invoice::apply_intra_eu_rate(&config, &customer.0, &mut entries);
+
+	let xml = test_write(&config, &customer, &entries);
+	assert!(section(&xml, "cac:TaxTotal") == concat!(
+		"\t<cac:TaxTotal>\n",
+		"\t\t<cbc:TaxAmount currencyID=\"EUR\">0.00</cbc:TaxAmount>\n",
+		"\t\t<cac:TaxSubtotal>\n",
+		"\t\t\t<cbc:TaxableAmount currencyID=\"EUR\">80.00</cbc:TaxableAmount>\n",
+		"\t\t\t<cbc:TaxAmount currencyID=\"EUR\">0.00</cbc:TaxAmount>\n",
+		"\t\t\t<cac:TaxCategory>\n",
+		"\t\t\t\t<cbc:ID>K</cbc:ID>\n",
+		"\t\t\t\t<cbc:Percent>0</cbc:Percent>\n",
+		"\t\t\t\t<cbc:TaxExemptionReason>VAT reverse charged to the customer (intra-community supply).</cbc:TaxExemptionReason>\n",
+		"\t\t\t\t<cac:TaxScheme>\n",
+		"\t\t\t\t\t<cbc:ID>VAT</cbc:ID>\n",
+		"\t\t\t\t</cac:TaxScheme>\n",
+		"\t\t\t</cac:TaxCategory>\n",
+		"\t\t</cac:TaxSubtotal>\n",
+		"\t</cac:TaxTotal>\n",
+	));
+	assert!(section(&xml, "cac:ClassifiedTaxCategory").contains("<cbc:ID>K</cbc:ID>"));
+	assert!(xml.contains("<cbc:CompanyID>DE123456789</cbc:CompanyID>"));
+}
+
+/// Get the first element with the given tag from generated XML, including the indentation and the final newline.
+#[cfg(test)]
+fn section<'a>(xml: &'a str, tag: &str) -> &'a str {
+	let open = format!("<{}>", tag);
+	let close = format!("</{}>\n", tag);
+	let start = xml[..xml.find(&open).unwrap()].rfind('\n').unwrap() + 1;
+	let end = start + xml[start..].find(&close).unwrap() + close.len();
+	&xml[start..end]
+}
+
+/// The expected output of [`test_write_ubl_invoice`].
+///
+/// It has lines with the standard and zero rate (categories S and Z), a negative line,
+/// and names with characters that must be escaped.
+#[cfg(test)]
+const TEST_INVOICE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Invoice xmlns="urn:oasis:names:specification:ubl:schema:xsd:Invoice-2" xmlns:cac="urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2" xmlns:cbc="urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2">
+	<cbc:CustomizationID>urn:cen.eu:en16931:2017#compliant#urn:fdc:nen.nl:nlcius:v1.0</cbc:CustomizationID>
+	<cbc:ProfileID>urn:fdc:peppol.eu:2017:poacc:billing:01:1.0</cbc:ProfileID>
+	<cbc:ID>2024-001</cbc:ID>
+	<cbc:IssueDate>2024-03-31</cbc:IssueDate>
+	<cbc:InvoiceTypeCode>380</cbc:InvoiceTypeCode>
+	<cbc:DocumentCurrencyCode>EUR</cbc:DocumentCurrencyCode>
+	<cbc:BuyerReference>PO-1</cbc:BuyerReference>
+	<cac:AccountingSupplierParty>
+		<cac:Party>
+			<cac:PartyName>
+				<cbc:Name>Jansen &amp; Zn &lt;ICT&gt;</cbc:Name>
+			</cac:PartyName>
+			<cac:PostalAddress>
+				<cbc:StreetName>Straat 1</cbc:StreetName>
+				<cbc:CityName>Utrecht</cbc:CityName>
+				<cbc:PostalZone>1234 AB</cbc:PostalZone>
+				<cac:Country>
+					<cbc:IdentificationCode>NL</cbc:IdentificationCode>
+				</cac:Country>
+			</cac:PostalAddress>
+			<cac:PartyTaxScheme>
+				<cbc:CompanyID>NL001234567B01</cbc:CompanyID>
+				<cac:TaxScheme>
+					<cbc:ID>VAT</cbc:ID>
+				</cac:TaxScheme>
+			</cac:PartyTaxScheme>
+			<cac:PartyLegalEntity>
+				<cbc:RegistrationName>Jansen &amp; Zn &lt;ICT&gt;</cbc:RegistrationName>
+				<cbc:CompanyID schemeID="0106">12345678</cbc:CompanyID>
+			</cac:PartyLegalEntity>
+		</cac:Party>
+	</cac:AccountingSupplierParty>
+	<cac:AccountingCustomerParty>
+		<cac:Party>
+			<cbc:EndpointID schemeID="0106">87654321</cbc:EndpointID>
+			<cac:PartyName>
+				<cbc:Name>Klant &quot;B&quot; &amp; &apos;C&apos;</cbc:Name>
+			</cac:PartyName>
+			<cac:PostalAddress>
+				<cbc:StreetName>Weg 2</cbc:StreetName>
+				<cbc:CityName>Amersfoort</cbc:CityName>
+				<cbc:PostalZone>5678 CD</cbc:PostalZone>
+				<cac:Country>
+					<cbc:IdentificationCode>NL</cbc:IdentificationCode>
+				</cac:Country>
+			</cac:PostalAddress>
+			<cac:PartyLegalEntity>
+				<cbc:RegistrationName>Klant &quot;B&quot; &amp; &apos;C&apos;</cbc:RegistrationName>
+			</cac:PartyLegalEntity>
+		</cac:Party>
+	</cac:AccountingCustomerParty>
+	<cac:PaymentMeans>
+		<cbc:PaymentMeansCode>58</cbc:PaymentMeansCode>
+		<cbc:PaymentID>2024-001</cbc:PaymentID>
+		<cac:PayeeFinancialAccount>
+			<cbc:ID>NL00BANK0123456789</cbc:ID>
+		</cac:PayeeFinancialAccount>
+	</cac:PaymentMeans>
+	<cac:PaymentTerms>
+		<cbc:Note>Please pay the total amount within the payment term, stating the invoice number.</cbc:Note>
+	</cac:PaymentTerms>
+	<cac:TaxTotal>
+		<cbc:TaxAmount currencyID="EUR">21.00</cbc:TaxAmount>
+		<cac:TaxSubtotal>
+			<cbc:TaxableAmount currencyID="EUR">25.00</cbc:TaxableAmount>
+			<cbc:TaxAmount currencyID="EUR">0.00</cbc:TaxAmount>
+			<cac:TaxCategory>
+				<cbc:ID>Z</cbc:ID>
+				<cbc:Percent>0</cbc:Percent>
+				<cac:TaxScheme>
+					<cbc:ID>VAT</cbc:ID>
+				</cac:TaxScheme>
+			</cac:TaxCategory>
+		</cac:TaxSubtotal>
+		<cac:TaxSubtotal>
+			<cbc:TaxableAmount currencyID="EUR">100.00</cbc:TaxableAmount>
+			<cbc:TaxAmount currencyID="EUR">21.00</cbc:TaxAmount>
+			<cac:TaxCategory>
+				<cbc:ID>S</cbc:ID>
+				<cbc:Percent>21</cbc:Percent>
+				<cac:TaxScheme>
+					<cbc:ID>VAT</cbc:ID>
+				</cac:TaxScheme>
+			</cac:TaxCategory>
+		</cac:TaxSubtotal>
+	</cac:TaxTotal>
+	<cac:LegalMonetaryTotal>
+		<cbc:LineExtensionAmount currencyID="EUR">125.00</cbc:LineExtensionAmount>
+		<cbc:TaxExclusiveAmount currencyID="EUR">125.00</cbc:TaxExclusiveAmount>
+		<cbc:TaxInclusiveAmount currencyID="EUR">146.00</cbc:TaxInclusiveAmount>
+		<cbc:PayableAmount currencyID="EUR">146.00</cbc:PayableAmount>
+	</cac:LegalMonetaryTotal>
+	<cac:InvoiceLine>
+		<cbc:ID>1</cbc:ID>
+		<cbc:InvoicedQuantity unitCode="HUR">1.5</cbc:InvoicedQuantity>
+		<cbc:LineExtensionAmount currencyID="EUR">120.00</cbc:LineExtensionAmount>
+		<cac:InvoicePeriod>
+			<cbc:StartDate>2024-03-01</cbc:StartDate>
+			<cbc:EndDate>2024-03-01</cbc:EndDate>
+		</cac:InvoicePeriod>
+		<cac:Item>
+			<cbc:Name>Development &lt;backend&gt;</cbc:Name>
+			<cac:ClassifiedTaxCategory>
+				<cbc:ID>S</cbc:ID>
+				<cbc:Percent>21</cbc:Percent>
+				<cac:TaxScheme>
+					<cbc:ID>VAT</cbc:ID>
+				</cac:TaxScheme>
+			</cac:ClassifiedTaxCategory>
+		</cac:Item>
+		<cac:Price>
+			<cbc:PriceAmount currencyID="EUR">80.00</cbc:PriceAmount>
+		</cac:Price>
+	</cac:InvoiceLine>
+	<cac:InvoiceLine>
+		<cbc:ID>2</cbc:ID>
+		<cbc:InvoicedQuantity unitCode="C62">2</cbc:InvoicedQuantity>
+		<cbc:LineExtensionAmount currencyID="EUR">25.00</cbc:LineExtensionAmount>
+		<cac:InvoicePeriod>
+			<cbc:StartDate>2024-03-02</cbc:StartDate>
+			<cbc:EndDate>2024-03-02</cbc:EndDate>
+		</cac:InvoicePeriod>
+		<cac:Item>
+			<cbc:Name>Books &amp; manuals</cbc:Name>
+			<cac:ClassifiedTaxCategory>
+				<cbc:ID>Z</cbc:ID>
+				<cbc:Percent>0</cbc:Percent>
+				<cac:TaxScheme>
+					<cbc:ID>VAT</cbc:ID>
+				</cac:TaxScheme>
+			</cac:ClassifiedTaxCategory>
+		</cac:Item>
+		<cac:Price>
+			<cbc:PriceAmount currencyID="EUR">12.50</cbc:PriceAmount>
+		</cac:Price>
+	</cac:InvoiceLine>
+	<cac:InvoiceLine>
+		<cbc:ID>3</cbc:ID>
+		<cbc:InvoicedQuantity unitCode="C62">-1</cbc:InvoicedQuantity>
+		<cbc:LineExtensionAmount currencyID="EUR">-20.00</cbc:LineExtensionAmount>
+		<cac:InvoicePeriod>
+			<cbc:StartDate>2024-03-03</cbc:StartDate>
+			<cbc:EndDate>2024-03-03</cbc:EndDate>
+		</cac:InvoicePeriod>
+		<cac:Item>
+			<cbc:Name>Discount</cbc:Name>
+			<cac:ClassifiedTaxCategory>
+				<cbc:ID>S</cbc:ID>
+				<cbc:Percent>21</cbc:Percent>
+				<cac:TaxScheme>
+					<cbc:ID>VAT</cbc:ID>
+				</cac:TaxScheme>
+			</cac:ClassifiedTaxCategory>
+		</cac:Item>
+		<cac:Price>
+			<cbc:PriceAmount currencyID="EUR">20.00</cbc:PriceAmount>
+		</cac:Price>
+	</cac:InvoiceLine>
+</Invoice>
+"#;
@@ -0,0 +1,319 @@
+//! The Dutch VAT return (btw-aangifte).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use zzp::gregorian::Date;
+use zzp::grootboek::{Account, Cents, Mutation, RoundingMode, Tag, Transaction};
+
+use crate::json::Json;
+use crate::{ReadFileError, VatReturnConfig, WriteFileError, ZzpConfig};
+
+/// The tag label of the transaction that books a VAT return, with the period as value.
+pub const VAT_RETURN_TAG: &str = "vat-return";
+
+/// The default path of the file with filed VAT returns, relative to the directory of `zzp.toml`.
+pub const DEFAULT_DECLARATIONS_PATH: &str = "vat-declarations.toml";
+
+/// The largest difference in VAT that may be corrected in the next VAT return instead of with a suppletie.
+pub const CORRECTION_THRESHOLD: Cents = Cents(100_000);
+
+/// The VAT return for a period, computed from the grootboek.
+#[derive(Debug, Clone)]
+pub struct VatReturn<'a> {
+	/// The boxes with turnover and VAT.
+	pub boxes: Vec<VatReturnLine<'a>>,
+
+	/// The exact VAT input tax (voorbelasting, box 5b).
+	pub input_vat: Cents,
+
+	/// The balance of each VAT and VAT input account, used to clear the accounts when booking the VAT return.
+	pub balances: BTreeMap<Account<'a>, Cents>,
+}
+
+/// A box of the VAT return with the exact amounts.
+#[derive(Debug, Clone)]
+pub struct VatReturnLine<'a> {
+	/// The name of the box, like `1a`.
+	pub name: &'a str,
+
+	/// The description of the box.
+	pub description: &'a str,
+
+	/// The turnover, or `None` if the box has no turnover accounts.
+	pub turnover: Option<Cents>,
+
+	/// The VAT, or `None` if the box has no VAT accounts.
+	pub vat: Option<Cents>,
+}
+
+impl<'a> VatReturn<'a> {
+	/// Compute the VAT return from the transactions of a period.
+	///
+	/// Transactions with the [`VAT_RETURN_TAG`] are ignored, since they clear the VAT accounts.
+	pub fn compute<'b>(config: &'a ZzpConfig, transactions: impl IntoIterator<Item = &'b Transaction<'a>>) -> Result<Self, String>
+	where
+		'a: 'b,
+	{
+		let vat_config = config.vat_return.as_ref()
+			.ok_or("missing VatReturn section in zzp.toml")?;
+		let input_vat_accounts = if vat_config.input_vat.is_empty() {
+			std::slice::from_ref(&config.grootboek.vat_input_account)
+		} else {
+			vat_config.input_vat.as_slice()
+		};
+
+		let mutations: Vec<&Mutation> = transactions.into_iter()
+			.filter(|x| !x.tags.iter().any(|tag| tag.label == VAT_RETURN_TAG))
+			.flat_map(|x| &x.mutations)
+			.collect();
+
+		let mut balances = BTreeMap::new();
+		let boxes = vat_config.boxes.iter()
+			.map(|vat_box| VatReturnLine {
+				name: &vat_box.name,
+				description: vat_box.description.as_deref()
+					.or_else(|| default_box_description(&vat_box.name))
+					.unwrap_or(""),
+				turnover: (!vat_box.turnover.is_empty()).then(|| -balance(&mutations, &vat_box.turnover, None)),
+				vat: (!vat_box.vat.is_empty()).then(|| -balance(&mutations, &vat_box.vat, Some(&mut balances))),
+			})
+			.collect();
+		let input_vat = balance(&mutations, input_vat_accounts, Some(&mut balances));
+
+		Ok(Self {
+			boxes,
+			input_vat,
+			balances,
+		})
+	}
+
+	/// The VAT to pay (box 5a), as the sum of the VAT of the boxes in whole euros.
+	pub fn total_vat(&self) -> Cents {
+		self.boxes.iter()
+			.filter_map(|x| x.vat)
+			.map(|x| whole_euros(x, RoundingMode::Down))
+			.sum()
+	}
+
+	/// The VAT input tax (box 5b) in whole euros.
+	pub fn total_input_vat(&self) -> Cents {
+		whole_euros(self.input_vat, RoundingMode::Up)
+	}
+
+	/// The VAT to pay, or to receive if negative (box 5c), in whole euros.
+	pub fn vat_due(&self) -> Cents {
+		self.total_vat() - self.total_input_vat()
+	}
+
+	/// Get the VAT return as JSON, with the exact amounts of the boxes and the totals in whole euros.
+	pub fn to_json(&self, period: &str) -> Json {
+		Json::object([
+			("period", period.into()),
+			("boxes", Json::array(self.boxes.iter().map(|line| Json::object([
+				("name", line.name.into()),
+				("description", line.description.into()),
+				("turnover", line.turnover.into()),
+				("vat", line.vat.into()),
+			])))),
+			("total_vat", self.total_vat().into()),
+			("total_input_vat", self.total_input_vat().into()),
+			("vat_due", self.vat_due().into()),
+		])
+	}
+
+	/// Create the transaction that books the VAT return.
+	///
+	/// The transaction clears the VAT and VAT input accounts and puts the VAT to pay on the payment account.
+	/// If a rounding account is configured, the payment account gets the amount in whole euros and the difference goes to the rounding account.
+	pub fn booking(&self, config: &'a VatReturnConfig, date: Date, description: &'a str, period: &'a str) -> Result<Transaction<'a>, String> {
+		let payment_account = config.payment_account.as_deref()
+			.ok_or("missing payment_account in the VatReturn section of zzp.toml")?;
+
+		let mut mutations: Vec<Mutation> = self.balances.iter()
+			.filter(|(_, balance)| balance.total_cents() != 0)
+			.map(|(&account, &balance)| Mutation { amount: -balance, account })
+			.collect();
+		let exact_due: Cents = self.balances.values().map(|&x| -x).sum();
+
+		match &config.rounding_account {
+			Some(rounding_account) => {
+				let due = self.vat_due();
+				mutations.push(Mutation { amount: -due, account: Account::from_raw(payment_account) });
+				if due != exact_due {
+					mutations.push(Mutation { amount: due - exact_due, account: Account::from_raw(rounding_account) });
+				}
+			},
+			None => mutations.push(Mutation { amount: -exact_due, account: Account::from_raw(payment_account) }),
+		}
+
+		Ok(Transaction::new(date, description, vec![Tag { label: VAT_RETURN_TAG, value: period }], mutations))
+	}
+}
+
+/// The filed VAT returns.
+///
+/// The declarations are stored as a TOML file with a `[[Declaration]]` table for each VAT return.
+#[derive(Debug, Clone)]
+pub struct VatDeclarations {
+	path: PathBuf,
+	declarations: Vec<VatDeclaration>,
+}
+
+/// A filed VAT return with the amounts in whole euros as they were declared.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VatDeclaration {
+	/// The period of the VAT return, like `2024-Q2`.
+	pub period: String,
+
+	/// The date the VAT return was filed.
+	#[serde(with = "crate::serde_date")]
+	pub date: Date,
+
+	/// The declared VAT to pay (box 5a), in cents.
+	pub total_vat_cents: i32,
+
+	/// The declared VAT input tax (box 5b), in cents.
+	pub total_input_vat_cents: i32,
+
+	/// The declared boxes.
+	#[serde(rename = "Box")]
+	#[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+	pub boxes: Vec<DeclaredBox>,
+}
+
+/// A box of a filed VAT return.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredBox {
+	/// The name of the box, like `1a`.
+	pub name: String,
+
+	/// The declared turnover in cents.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub turnover_cents: Option<i32>,
+
+	/// The declared VAT in cents.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub vat_cents: Option<i32>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DeclarationsFile {
+	#[serde(rename = "Declaration")]
+	#[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+	declarations: Vec<VatDeclaration>,
+}
+
+impl VatDeclarations {
+	/// Open the file with filed VAT returns for a ZZP configuration.
+	///
+	/// The `root_dir` is the directory containing `zzp.toml`.
+	/// A missing file is treated as a file without declarations.
+	pub fn open(root_dir: impl AsRef<Path>, config: &ZzpConfig) -> Result<Self, ReadFileError> {
+		let path = config.vat_return.as_ref().and_then(|x| x.declarations.as_deref()).unwrap_or(DEFAULT_DECLARATIONS_PATH);
+		let path = root_dir.as_ref().join(path);
+		let file = if path.exists() {
+			crate::read_toml(&path)?
+		} else {
+			DeclarationsFile::default()
+		};
+		Ok(Self { path, declarations: file.declarations })
+	}
+
+	/// Get the path of the declarations file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Find the declaration of a period.
+	pub fn find(&self, period: &str) -> Option<&VatDeclaration> {
+		self.declarations.iter().find(|x| x.period == period)
+	}
+
+	/// Add a declaration, replacing an earlier declaration of the same period.
+	pub fn add(&mut self, declaration: VatDeclaration) {
+		match self.declarations.iter_mut().find(|x| x.period == declaration.period) {
+			Some(existing) => *existing = declaration,
+			None => self.declarations.push(declaration),
+		}
+	}
+
+	/// Write the declarations to a temporary file and move it over the declarations file.
+	pub fn save(&self) -> Result<(), WriteFileError> {
+		let file = DeclarationsFile { declarations: self.declarations.clone() };
+		crate::write_toml_atomic(&self.path, &file)
+	}
+}
+
+impl VatDeclaration {
+	/// Create the declaration of a VAT return with the amounts in whole euros.
+	pub fn new(vat_return: &VatReturn, period: &str, date: Date) -> Self {
+		Self {
+			period: period.to_string(),
+			date,
+			total_vat_cents: vat_return.total_vat().total_cents(),
+			total_input_vat_cents: vat_return.total_input_vat().total_cents(),
+			boxes: vat_return.boxes.iter()
+				.map(|line| DeclaredBox {
+					name: line.name.to_string(),
+					turnover_cents: line.turnover.map(|x| whole_euros(x, RoundingMode::Down).total_cents()),
+					vat_cents: line.vat.map(|x| whole_euros(x, RoundingMode::Down).total_cents()),
+				})
+				.collect(),
+		}
+	}
+
+	/// The declared VAT to pay, or to receive if negative (box 5c).
+	pub fn vat_due(&self) -> Cents {
+		Cents(self.total_vat_cents - self.total_input_vat_cents)
+	}
+}
+
+/// Round an amount to whole euros.
+///
+/// The Belastingdienst allows rounding in your favour:
+/// turnover and VAT to pay are rounded down, VAT input tax is rounded up.
+pub fn whole_euros(amount: Cents, rounding: RoundingMode) -> Cents {
+	Cents(rounding.div(amount.total_cents().into(), 100) as i32 * 100)
+}
+
+/// Get the description of a box of the Dutch VAT return.
+pub fn default_box_description(name: &str) -> Option<&'static str> {
+	match name {
+		"1a" => Some("Leveringen/diensten belast met hoog tarief"),
+		"1b" => Some("Leveringen/diensten belast met laag tarief"),
+		"1c" => Some("Leveringen/diensten belast met overige tarieven, behalve 0%"),
+		"1d" => Some("Privégebruik"),
+		"1e" => Some("Leveringen/diensten belast met 0% of niet bij u belast"),
+		"2a" => Some("Leveringen/diensten waarbij de omzetbelasting naar u is verlegd"),
+		"3a" => Some("Leveringen naar landen buiten de EU"),
+		"3b" => Some("Leveringen naar of diensten in landen binnen de EU"),
+		"3c" => Some("Installatie/afstandsverkopen binnen de EU"),
+		"4a" => Some("Leveringen/diensten uit landen buiten de EU"),
+		"4b" => Some("Leveringen/diensten uit landen binnen de EU"),
+		"5a" => Some("Verschuldigde omzetbelasting"),
+		"5b" => Some("Voorbelasting"),
+		"5c" => Some("Subtotaal"),
+		"5g" => Some("Totaal te betalen of terug te vragen"),
+		_ => None,
+	}
+}
+
+/// Sum the mutations of the given accounts and their sub-accounts.
+///
+/// If `balances` is given, the balance of each individual account is added to it.
+fn balance<'a>(mutations: &[&Mutation<'a>], accounts: &[String], mut balances: Option<&mut BTreeMap<Account<'a>, Cents>>) -> Cents {
+	let mut total = Cents(0);
+	for mutation in mutations {
+		if accounts.iter().any(|x| mutation.account.matches_prefix(x)) {
+			total += mutation.amount;
+			if let Some(balances) = balances.as_deref_mut() {
+				*balances.entry(mutation.account).or_insert(Cents(0)) += mutation.amount;
+			}
+		}
+	}
+	total
+}
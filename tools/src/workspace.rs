@@ -0,0 +1,220 @@
+//! Workspaces with multiple administrations.
+//!
+//! Normally, `zzp.toml` is found by searching the working directory and its parents.
+//! To keep multiple administrations (like two companies, or a new legal entity halfway through the year) side by side,
+//! a `zzp-workspace.toml` in a parent directory can list the directories of the administrations:
+//!
+//! ```toml
+//! default = "bv"
+//!
+//! [[Administration]]
+//! name = "eenmanszaak"
+//! path = "eenmanszaak"
+//!
+//! [[Administration]]
+//! name = "bv"
+//! path = "bv"
+//! ```
+//!
+//! Each administration has its own `zzp.toml`, and the grootboek and invoice directories are resolved relative to it as usual.
+//! An administration can be selected with `--administration` or the `ZZP_ADMINISTRATION` environment variable.
+//! Otherwise, the administration of the working directory is used, or the default administration outside of them.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{ReadFileError, ZzpConfig};
+
+/// The name of the workspace configuration file.
+pub const WORKSPACE_FILE: &str = "zzp-workspace.toml";
+
+/// The environment variable to select an administration.
+pub const ADMINISTRATION_ENV: &str = "ZZP_ADMINISTRATION";
+
+/// The administration selected on the command line.
+static SELECTED_ADMINISTRATION: OnceLock<Option<String>> = OnceLock::new();
+
+/// Configuration file for a workspace with multiple administrations.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "PascalCase")]
+pub struct WorkspaceConfig {
+	/// The name of the administration to use when the working directory is not inside an administration.
+	#[serde(rename = "default")]
+	pub default: Option<String>,
+
+	/// The administrations in the workspace.
+	pub administration: Vec<Administration>,
+}
+
+/// An administration in a workspace.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Administration {
+	/// The name to select the administration with.
+	pub name: String,
+
+	/// The directory with the `zzp.toml` of the administration, relative to the workspace file.
+	pub path: PathBuf,
+}
+
+impl WorkspaceConfig {
+	/// Find the workspace configuration file by searching the filesystem.
+	///
+	/// This looks for `zzp-workspace.toml` in the start dir and each parent dir until it is found.
+	pub fn find(start_dir: impl AsRef<Path>) -> Option<PathBuf> {
+		start_dir.as_ref()
+			.ancestors()
+			.map(|dir| dir.join(WORKSPACE_FILE))
+			.find(|candidate| candidate.is_file())
+	}
+
+	/// Read a workspace configuration from a file.
+	pub fn read_file(path: impl AsRef<Path>) -> Result<Self, crate::ReadFileError> {
+		crate::read_toml(path)
+	}
+
+	/// Get an administration by name.
+	pub fn get(&self, name: &str) -> Result<&Administration, FindConfigError> {
+		self.administration.iter()
+			.find(|x| x.name == name)
+			.ok_or_else(|| FindConfigError::UnknownAdministration {
+				name: name.into(),
+				names: self.names().into_iter().map(String::from).collect(),
+			})
+	}
+
+	/// Get the names of all administrations.
+	pub fn names(&self) -> Vec<&str> {
+		self.administration.iter().map(|x| x.name.as_str()).collect()
+	}
+}
+
+impl Administration {
+	/// Get the path of the `zzp.toml` of the administration.
+	///
+	/// The `workspace_dir` is the directory containing `zzp-workspace.toml`.
+	pub fn config_path(&self, workspace_dir: impl AsRef<Path>) -> PathBuf {
+		workspace_dir.as_ref().join(&self.path).join("zzp.toml")
+	}
+}
+
+/// Select the administration to use for the rest of the program.
+///
+/// This should be called once at startup with the value of the `--administration` option.
+/// If `None` is given, the `ZZP_ADMINISTRATION` environment variable is used if it is set.
+pub fn select_administration(name: Option<String>) {
+	let name = name.or_else(|| std::env::var(ADMINISTRATION_ENV).ok().filter(|x| !x.is_empty()));
+	if SELECTED_ADMINISTRATION.set(name).is_err() {
+		log::warn!("the administration can only be selected once");
+	}
+}
+
+/// Get the selected administration, if any.
+pub fn selected_administration() -> Option<&'static str> {
+	SELECTED_ADMINISTRATION.get().and_then(|x| x.as_deref())
+}
+
+/// Find the `zzp.toml` to use from a directory.
+///
+/// If an administration is selected, this is the `zzp.toml` of that administration in the workspace.
+/// Otherwise, the first `zzp.toml` in the directory and its parents is used,
+/// or the `zzp.toml` of the default administration if the directory is inside a workspace but not inside an administration.
+pub fn find_zzp_config(start_dir: impl AsRef<Path>) -> Result<PathBuf, FindConfigError> {
+	let start_dir = start_dir.as_ref();
+	if let Some(name) = selected_administration() {
+		let (workspace_dir, workspace) = read_workspace(start_dir)?
+			.ok_or_else(|| FindConfigError::NoWorkspace(name.into()))?;
+		return existing_config_path(workspace.get(name)?, &workspace_dir);
+	}
+
+	if let Some(path) = ZzpConfig::find("/", start_dir) {
+		return Ok(path);
+	}
+
+	match read_workspace(start_dir)? {
+		None => Err(FindConfigError::NoZzpConfig),
+		Some((workspace_dir, workspace)) => match &workspace.default {
+			Some(name) => existing_config_path(workspace.get(name)?, &workspace_dir),
+			None => Err(FindConfigError::NoAdministration(workspace.names().into_iter().map(String::from).collect())),
+		},
+	}
+}
+
+/// Get the working directory to find the configuration from.
+pub fn current_dir() -> Result<PathBuf, FindConfigError> {
+	std::env::current_dir().map_err(FindConfigError::WorkingDirectory)
+}
+
+/// Find and read the workspace configuration.
+///
+/// Returns the directory containing the workspace file and the parsed configuration.
+pub fn read_workspace(start_dir: impl AsRef<Path>) -> Result<Option<(PathBuf, WorkspaceConfig)>, FindConfigError> {
+	let path = match WorkspaceConfig::find(start_dir) {
+		Some(x) => x,
+		None => return Ok(None),
+	};
+	let workspace = WorkspaceConfig::read_file(&path)
+		.map_err(FindConfigError::ReadWorkspace)?;
+	Ok(Some((path.parent().unwrap().to_path_buf(), workspace)))
+}
+
+/// Get the path of the `zzp.toml` of an administration, checking that it exists.
+fn existing_config_path(administration: &Administration, workspace_dir: &Path) -> Result<PathBuf, FindConfigError> {
+	let path = administration.config_path(workspace_dir);
+	if path.is_file() {
+		Ok(path)
+	} else {
+		Err(FindConfigError::MissingAdministrationConfig {
+			name: administration.name.clone(),
+			path,
+		})
+	}
+}
+
+#[derive(Debug)]
+pub enum FindConfigError {
+	/// The working directory could not be determined.
+	WorkingDirectory(std::io::Error),
+
+	/// No `zzp.toml` was found in the directory or its parents, and there is no workspace.
+	NoZzpConfig,
+
+	/// No `customer.toml` was found in the directory or its parents.
+	NoCustomerConfig,
+
+	/// An administration was selected, but no workspace was found.
+	NoWorkspace(String),
+
+	/// The directory is inside a workspace without a default administration, but not inside an administration.
+	NoAdministration(Vec<String>),
+
+	/// The selected administration is not in the workspace.
+	UnknownAdministration { name: String, names: Vec<String> },
+
+	/// The administration has no `zzp.toml`.
+	MissingAdministrationConfig { name: String, path: PathBuf },
+
+	/// The workspace configuration could not be read.
+	ReadWorkspace(ReadFileError),
+
+	/// The path of a file to find the configuration for could not be resolved.
+	ResolvePath(PathBuf, std::io::Error),
+}
+
+impl std::error::Error for FindConfigError {}
+impl std::fmt::Display for FindConfigError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::WorkingDirectory(error) => write!(f, "failed to determine working directory: {}", error),
+			Self::NoZzpConfig => write!(f, "could not find zzp.toml"),
+			Self::NoCustomerConfig => write!(f, "could not find customer.toml"),
+			Self::NoWorkspace(name) => write!(f, "could not find {} to select administration {:?}", WORKSPACE_FILE, name),
+			Self::NoAdministration(names) => write!(f, "could not find zzp.toml, select an administration with --administration: {}", names.join(", ")),
+			Self::UnknownAdministration { name, names } => write!(f, "unknown administration {:?}, expected one of: {}", name, names.join(", ")),
+			Self::MissingAdministrationConfig { name, path } => write!(f, "administration {:?} has no configuration file: {}", name, path.display()),
+			Self::ReadWorkspace(error) => write!(f, "{}", error),
+			Self::ResolvePath(path, error) => write!(f, "failed to resolve path {}: {}", path.display(), error),
+		}
+	}
+}
@@ -0,0 +1,347 @@
+//! Generation of XML Auditfile Financieel (XAF) 3.2 files.
+//!
+//! An audit file contains the company details, the chart of accounts and all transactions of a period,
+//! in the format that the Belastingdienst and accounting software can import.
+//! The grootboek has no journals, so all transactions are written to a single memorial journal.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use zzp::gregorian::{Date, YearMonth};
+use zzp::grootboek::{Account, Cents, Transaction};
+
+use crate::{AccountKind, ZzpConfig};
+use crate::ubl::{close, element, format_amount, open};
+
+const NAMESPACE: &str = "http://www.auditfiles.nl/XAF/3.2";
+
+/// The maximum length of an account ID in an audit file.
+const MAX_ACCOUNT_LENGTH: usize = 35;
+
+/// Write an audit file with the transactions from `start` until and including `end`.
+///
+/// Accounts with a revenue or expense type in the chart of accounts are profit and loss accounts.
+/// Like for the income statement, all other accounts are balance sheet accounts.
+pub fn write_xaf<'a, W: Write>(
+	mut stream: W,
+	config: &ZzpConfig,
+	transactions: &[Transaction<'a>],
+	start: Date,
+	end: Date,
+	created: Date,
+) -> Result<(), String> {
+	let accounts: BTreeSet<Account> = transactions.iter()
+		.flat_map(|x| &x.mutations)
+		.map(|x| x.account)
+		.collect();
+	let mut account_types = Vec::new();
+	for &account in &accounts {
+		if account.as_str().len() > MAX_ACCOUNT_LENGTH {
+			return Err(format!("account {:?} is longer than {} characters, which audit files do not support", account.as_str(), MAX_ACCOUNT_LENGTH));
+		}
+		let account_type = match config.find_account(account).map(|x| x.kind) {
+			Some(AccountKind::Revenue | AccountKind::Expense) => "P",
+			_ => "B",
+		};
+		account_types.push((account.as_str(), account_type));
+	}
+
+	// Each month of the export is a period.
+	let mut periods = Vec::new();
+	let mut month = start.year_month();
+	while month <= end.year_month() {
+		periods.push(month);
+		month = month.next();
+	}
+
+	let mut xml = String::new();
+	xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	xml.push_str(&format!("<auditfile xmlns=\"{}\">\n", NAMESPACE));
+
+	open(&mut xml, 1, "header");
+	element(&mut xml, 2, "fiscalYear", &config.fiscal_year_name(config.fiscal_year_of(start)));
+	element(&mut xml, 2, "startDate", &start.to_string());
+	element(&mut xml, 2, "endDate", &end.to_string());
+	element(&mut xml, 2, "curCode", &config.currency().code);
+	element(&mut xml, 2, "dateCreated", &created.to_string());
+	element(&mut xml, 2, "softwareDesc", "zzp-rs");
+	element(&mut xml, 2, "softwareVersion", env!("CARGO_PKG_VERSION"));
+	close(&mut xml, 1, "header");
+
+	open(&mut xml, 1, "company");
+	let details = config.e_invoice.as_ref();
+	if let Some(coc_number) = details.and_then(|x| x.coc_number.as_deref()) {
+		element(&mut xml, 2, "companyIdent", coc_number);
+	}
+	element(&mut xml, 2, "companyName", &config.company.name);
+	element(&mut xml, 2, "taxRegistrationCountry", config.company_country_code());
+	if let Some(details) = details {
+		element(&mut xml, 2, "taxRegIdent", &details.vat_number);
+		open(&mut xml, 2, "streetAddress");
+		element(&mut xml, 3, "streetname", &details.street);
+		element(&mut xml, 3, "city", &details.city);
+		element(&mut xml, 3, "postalCode", &details.postal_code);
+		element(&mut xml, 3, "country", &details.country_code);
+		close(&mut xml, 2, "streetAddress");
+	}
+
+	open(&mut xml, 2, "generalLedger");
+	for (account, account_type) in &account_types {
+		open(&mut xml, 3, "ledgerAccount");
+		element(&mut xml, 4, "accID", account);
+		element(&mut xml, 4, "accDesc", account);
+		element(&mut xml, 4, "accTp", account_type);
+		close(&mut xml, 3, "ledgerAccount");
+	}
+	close(&mut xml, 2, "generalLedger");
+
+	open(&mut xml, 2, "periods");
+	for (i, month) in periods.iter().enumerate() {
+		open(&mut xml, 3, "period");
+		element(&mut xml, 4, "periodNumber", &(i + 1).to_string());
+		element(&mut xml, 4, "periodDesc", &format!("{:04}-{:02}", month.year().to_number(), month.month().to_number()));
+		element(&mut xml, 4, "startDatePeriod", &month.first_day().max(start).to_string());
+		element(&mut xml, 4, "endDatePeriod", &month.last_day().min(end).to_string());
+		close(&mut xml, 3, "period");
+	}
+	close(&mut xml, 2, "periods");
+
+	let mutations = transactions.iter().flat_map(|x| &x.mutations);
+	let total_debit: Cents = mutations.clone().filter(|x| x.amount > Cents(0)).map(|x| x.amount).sum();
+	let total_credit: Cents = mutations.clone().filter(|x| x.amount < Cents(0)).map(|x| -x.amount).sum();
+	open(&mut xml, 2, "transactions");
+	element(&mut xml, 3, "linesCount", &mutations.count().to_string());
+	element(&mut xml, 3, "totalDebit", &format_amount(total_debit));
+	element(&mut xml, 3, "totalCredit", &format_amount(total_credit));
+
+	open(&mut xml, 3, "journal");
+	element(&mut xml, 4, "jrnID", "M");
+	element(&mut xml, 4, "desc", "Memoriaal");
+	element(&mut xml, 4, "jrnTp", "M");
+	for (i, transaction) in transactions.iter().enumerate() {
+		write_transaction(&mut xml, i + 1, transaction, period_number(&periods, transaction.date));
+	}
+	close(&mut xml, 3, "journal");
+	close(&mut xml, 2, "transactions");
+	close(&mut xml, 1, "company");
+	xml.push_str("</auditfile>\n");
+
+	stream.write_all(xml.as_bytes())
+		.map_err(|e| format!("failed to write audit file: {}", e))
+}
+
+fn write_transaction(xml: &mut String, number: usize, transaction: &Transaction, period_number: usize) {
+	// Documents like invoices are referenced with tags, like `invoice: 2024-001`.
+	let doc_ref = transaction.tags.first()
+		.map(|x| x.value.to_string())
+		.unwrap_or_else(|| number.to_string());
+	let total_debit: Cents = transaction.mutations.iter()
+		.filter(|x| x.amount > Cents(0))
+		.map(|x| x.amount)
+		.sum();
+
+	open(xml, 4, "transaction");
+	element(xml, 5, "nr", &number.to_string());
+	element(xml, 5, "desc", transaction.description);
+	element(xml, 5, "periodNumber", &period_number.to_string());
+	element(xml, 5, "trDt", &transaction.date.to_string());
+	element(xml, 5, "amnt", &format_amount(total_debit));
+	element(xml, 5, "amntTp", "D");
+	for (i, mutation) in transaction.mutations.iter().enumerate() {
+		let (amount, amount_type) = if mutation.amount < Cents(0) {
+			(-mutation.amount, "C")
+		} else {
+			(mutation.amount, "D")
+		};
+		open(xml, 5, "trLine");
+		element(xml, 6, "nr", &(i + 1).to_string());
+		element(xml, 6, "accID", mutation.account.as_str());
+		element(xml, 6, "docRef", &doc_ref);
+		element(xml, 6, "effDate", &transaction.date.to_string());
+		element(xml, 6, "desc", transaction.description);
+		element(xml, 6, "amnt", &format_amount(amount));
+		element(xml, 6, "amntTp", amount_type);
+		close(xml, 5, "trLine");
+	}
+	close(xml, 4, "transaction");
+}
+
+/// Get the number of the period with a date, counting from 1.
+fn period_number(periods: &[YearMonth], date: Date) -> usize {
+	periods.iter()
+		.position(|&x| x == date.year_month())
+		.map(|x| x + 1)
+		.unwrap_or(0)
+}
+
+#[cfg(test)]
+#[test]
+fn test_write_xaf() {
+	use assert2::assert;
+	use crate::AccountConfig;
+
+	let mut config = crate::ubl::test_config(false);
+	config.account.push(AccountConfig { account: "revenue".into(), kind: AccountKind::Revenue, name: None });
+	config.account.push(AccountConfig { account: "debitors".into(), kind: AccountKind::Asset, name: None });
+
+	let ledger = concat!(
+		"2024-01-31: Invoice 2024-001\n",
+		"invoice: 2024-001\n",
+		"+121.00 debitors/acme\n",
+		"-100.00 revenue/acme\n",
+		"-21.00 vat/payable\n",
+		"\n",
+		"2024-02-14: Payment of invoice 2024-001 by Acme & Co\n",
+		"+121.00 bank\n",
+		"-121.00 debitors/acme\n",
+	);
+	let transactions = Transaction::parse_from_str(ledger).unwrap();
+	let date = |month, day| Date::new(2024, month, day).unwrap();
+
+	let mut output = Vec::new();
+	let result = write_xaf(
+		&mut output,
+		&config,
+		&transactions,
+		date(zzp::gregorian::Month::January, 15),
+		date(zzp::gregorian::Month::February, 29),
+		date(zzp::gregorian::Month::March, 1),
+	);
+	assert!(let Ok(()) = result);
+	let expected = TEST_AUDIT_FILE.replace("{version}", env!("CARGO_PKG_VERSION"));
+	assert!(String::from_utf8(output).unwrap() == expected);
+}
+
+/// The expected output of [`test_write_xaf`], with `{version}` for the version of zzp.
+#[cfg(test)]
+const TEST_AUDIT_FILE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<auditfile xmlns="http://www.auditfiles.nl/XAF/3.2">
+	<header>
+		<fiscalYear>2024</fiscalYear>
+		<startDate>2024-01-15</startDate>
+		<endDate>2024-02-29</endDate>
+		<curCode>EUR</curCode>
+		<dateCreated>2024-03-01</dateCreated>
+		<softwareDesc>zzp-rs</softwareDesc>
+		<softwareVersion>{version}</softwareVersion>
+	</header>
+	<company>
+		<companyIdent>12345678</companyIdent>
+		<companyName>Jansen &amp; Zn &lt;ICT&gt;</companyName>
+		<taxRegistrationCountry>NL</taxRegistrationCountry>
+		<taxRegIdent>NL001234567B01</taxRegIdent>
+		<streetAddress>
+			<streetname>Straat 1</streetname>
+			<city>Utrecht</city>
+			<postalCode>1234 AB</postalCode>
+			<country>NL</country>
+		</streetAddress>
+		<generalLedger>
+			<ledgerAccount>
+				<accID>bank</accID>
+				<accDesc>bank</accDesc>
+				<accTp>B</accTp>
+			</ledgerAccount>
+			<ledgerAccount>
+				<accID>debitors/acme</accID>
+				<accDesc>debitors/acme</accDesc>
+				<accTp>B</accTp>
+			</ledgerAccount>
+			<ledgerAccount>
+				<accID>revenue/acme</accID>
+				<accDesc>revenue/acme</accDesc>
+				<accTp>P</accTp>
+			</ledgerAccount>
+			<ledgerAccount>
+				<accID>vat/payable</accID>
+				<accDesc>vat/payable</accDesc>
+				<accTp>B</accTp>
+			</ledgerAccount>
+		</generalLedger>
+		<periods>
+			<period>
+				<periodNumber>1</periodNumber>
+				<periodDesc>2024-01</periodDesc>
+				<startDatePeriod>2024-01-15</startDatePeriod>
+				<endDatePeriod>2024-01-31</endDatePeriod>
+			</period>
+			<period>
+				<periodNumber>2</periodNumber>
+				<periodDesc>2024-02</periodDesc>
+				<startDatePeriod>2024-02-01</startDatePeriod>
+				<endDatePeriod>2024-02-29</endDatePeriod>
+			</period>
+		</periods>
+		<transactions>
+			<linesCount>5</linesCount>
+			<totalDebit>242.00</totalDebit>
+			<totalCredit>242.00</totalCredit>
+			<journal>
+				<jrnID>M</jrnID>
+				<desc>Memoriaal</desc>
+				<jrnTp>M</jrnTp>
+				<transaction>
+					<nr>1</nr>
+					<desc>Invoice 2024-001</desc>
+					<periodNumber>1</periodNumber>
+					<trDt>2024-01-31</trDt>
+					<amnt>121.00</amnt>
+					<amntTp>D</amntTp>
+					<trLine>
+						<nr>1</nr>
+						<accID>debitors/acme</accID>
+						<docRef>2024-001</docRef>
+						<effDate>2024-01-31</effDate>
+						<desc>Invoice 2024-001</desc>
+						<amnt>121.00</amnt>
+						<amntTp>D</amntTp>
+					</trLine>
+					<trLine>
+						<nr>2</nr>
+						<accID>revenue/acme</accID>
+						<docRef>2024-001</docRef>
+						<effDate>2024-01-31</effDate>
+						<desc>Invoice 2024-001</desc>
+						<amnt>100.00</amnt>
+						<amntTp>C</amntTp>
+					</trLine>
+					<trLine>
+						<nr>3</nr>
+						<accID>vat/payable</accID>
+						<docRef>2024-001</docRef>
+						<effDate>2024-01-31</effDate>
+						<desc>Invoice 2024-001</desc>
+						<amnt>21.00</amnt>
+						<amntTp>C</amntTp>
+					</trLine>
+				</transaction>
+				<transaction>
+					<nr>2</nr>
+					<desc>Payment of invoice 2024-001 by Acme &amp; Co</desc>
+					<periodNumber>2</periodNumber>
+					<trDt>2024-02-14</trDt>
+					<amnt>121.00</amnt>
+					<amntTp>D</amntTp>
+					<trLine>
+						<nr>1</nr>
+						<accID>bank</accID>
+						<docRef>2</docRef>
+						<effDate>2024-02-14</effDate>
+						<desc>Payment of invoice 2024-001 by Acme &amp; Co</desc>
+						<amnt>121.00</amnt>
+						<amntTp>D</amntTp>
+					</trLine>
+					<trLine>
+						<nr>2</nr>
+						<accID>debitors/acme</accID>
+						<docRef>2</docRef>
+						<effDate>2024-02-14</effDate>
+						<desc>Payment of invoice 2024-001 by Acme &amp; Co</desc>
+						<amnt>121.00</amnt>
+						<amntTp>C</amntTp>
+					</trLine>
+				</transaction>
+			</journal>
+		</transactions>
+	</company>
+</auditfile>
+"#;
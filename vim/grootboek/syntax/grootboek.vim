@@ -2,7 +2,7 @@ if exists("b:current_syntax")
 	finish
 endif
 
-syn match grootboekLineStart "^" nextgroup=grootboekComment,grootboekDate,grootboekAmountPos,grootboekAmountNeg,grootboekLabel skipwhite
+syn match grootboekLineStart "^" nextgroup=grootboekComment,grootboekNote,grootboekDate,grootboekAmountPos,grootboekAmountNeg,grootboekLabel skipwhite
 
 syn match grootboekLabel "\S.\{-}:\@=" contained nextgroup=grootboekLabelSep skipwhite
 syn match grootboekLabelSep ":" contained nextgroup=grootboekLabelValue skipwhite
@@ -16,11 +16,13 @@ syn match grootboekAmountPos "+\d\+.\d\d" contained nextgroup=grootboekAccount s
 syn match grootboekAmountNeg "-\d\+\.\d\d" contained nextgroup=grootboekAccount skipwhite
 syn match grootboekAccount "\S.*" contained
 
+syn match grootboekNote ";.*" contained contains=grootboekTodo
 syn match grootboekComment "#.*" contained contains=grootboekTodo
 syn keyword grootboekTodo TODO contained
 
 hi def link grootboekComment Comment
 hi def link grootboekTodo Todo
+hi def link grootboekNote SpecialComment
 
 hi def link grootboekDate Statement
 hi def link grootboekDateSep Special